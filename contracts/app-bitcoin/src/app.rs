@@ -1,43 +1,108 @@
 use crate::checkpoint::Checkpoint;
-use crate::helper::fetch_staking_validator;
-use crate::interface::{BitcoinConfig, ChangeRates, Dest, Validator};
+use crate::constants::{
+    DEST_COMMITMENT_VERSION, LEGACY_DEST_COMMITMENT_VERSION, MAX_SIGSET_LAG_SURCHARGE_BPS,
+    MAX_SWEEP_INPUTS, VALIDATOR_ADDRESS_PREFIX,
+};
+use crate::helper::bridge_time;
+use crate::helper::is_same_account;
+use crate::helper::{convert_addr_by_prefix, fetch_staking_validator};
+use crate::interface::{
+    BitcoinConfig, ChangeRates, DepositAgeUnit, Dest, ExpiredDeposit, JailedSigner, MintRecord,
+    PendingTransfer, ReserveUtxo, Validator,
+};
+use crate::reward::{accrue_fee_pool_surplus, distribute_rewards};
 use crate::signatory::SignatoryKeys;
 use crate::state::{
-    get_full_btc_denom, get_validators, BITCOIN_CONFIG, CONFIG, CONFIRMED_INDEX, FEE_POOL,
-    FIRST_UNHANDLED_CONFIRMED_INDEX, SIGNERS, SIG_KEYS, VALIDATORS, XPUBS,
+    apply_scheduled_validator_set, get_full_btc_denom, get_validators, record_checkpoint_txid,
+    record_dest_deposit, remove_pending_deposit, BITCOIN_CONFIG, BRIDGE_MIGRATION, BRIDGE_PAUSED,
+    CHECKPOINTS, CHECKPOINT_ATTESTATIONS, CHECKPOINT_CONFIRMATION_HEIGHTS,
+    CHECKPOINT_CONFIRMATION_TIMES, CHECKPOINT_TXIDS, CONFIG, CONFIRMED_INDEX, DEST_SIGSET_INDEXES,
+    DUST_DEPOSITS, EXPIRED_DEPOSITS, EXPIRED_DEPOSITS_BY_DEST, FEE_POOL,
+    FIRST_UNHANDLED_CONFIRMED_INDEX, HEADER_STALL_PAUSED, JAILED_SIGNERS, LEGACY_BTC_DENOMS,
+    MINT_CLAIMS, MINT_RECORDS, PENDING_TRANSFERS, RELAYERS, RESERVE_UTXOS, SIGNED_RESERVE_OUTPUTS,
+    SIGNERS, SIGSET_BTC_HEIGHTS, SIG_KEYS, SMALL_UTXOS_ALLOWED_UNTIL, VALIDATORS,
+    WHITELIST_VALIDATORS, XPUBS,
 };
 use crate::threshold_sig;
+use crate::tx_check::check_relayed_tx;
 
 use super::checkpoint::Input;
-use super::recovery::{RecoveryTxInput, RecoveryTxs};
+use super::recovery::{recovery_lock_time, RecoveryTxInput, RecoveryTxs, SweepInput, SweepTxInput};
 
-use super::checkpoint::BatchType;
 use super::checkpoint::CheckpointQueue;
-use bitcoin::Script;
-use bitcoin::{util::merkleblock::PartialMerkleTree, Transaction};
+use super::checkpoint::{BatchType, WithdrawalFee, WithdrawalPosition};
+use bitcoin::{
+    merkle_tree::PartialMerkleTree, Amount, BlockHash, ScriptBuf, Transaction, TxOut, Txid,
+};
 use common_bitcoin::msg::BondStatus;
 use common_bitcoin::{
-    adapter::Adapter,
+    adapter::{Adapter, WrappedBinary},
+    address::is_dust,
     error::{ContractError, ContractResult},
     xpub::Xpub,
 };
 use cosmwasm_schema::serde::{Deserialize, Serialize};
-use cosmwasm_std::{Addr, Api, Coin, Env, Order, QuerierWrapper, Storage, Uint128};
+use cosmwasm_std::{Addr, Api, Coin, Env, Order, QuerierWrapper, StdResult, Storage, Uint128};
 use ibc_proto::cosmos::staking::v1beta1::QueryValidatorResponse;
 use prost::Message;
 
 use super::outpoint_set::OutpointSet;
 use super::signatory::SignatorySet;
-use light_client_bitcoin::msg::QueryMsg::{HeaderHeight, Network, VerifyTxWithProof};
+use light_client_bitcoin::header::WrappedHeader;
+use light_client_bitcoin::msg::HeaderAtResponse;
+use light_client_bitcoin::msg::QueryMsg as LightClientQueryMsg;
+use light_client_bitcoin::msg::QueryMsg::{
+    BlockHashAtHeight, HeaderAt, HeaderHeight, Network, VerifyTxWithProof,
+    VerifyTxidWithAncestorProof,
+};
 use std::collections::HashMap;
 use std::str::FromStr;
 
 pub const NETWORK: ::bitcoin::Network = ::bitcoin::Network::Bitcoin;
 
-/// Calculates the bridge fee for a deposit of the given amount of BTC, in
-/// satoshis.
-pub fn calc_deposit_fee(_: Uint128) -> u64 {
-    0
+/// The voting power of a validator of the staking module, if it is bonded and
+/// not jailed.
+fn bonded_voting_power(querier: &QuerierWrapper, addr: &str) -> ContractResult<Option<u64>> {
+    let val_addr = convert_addr_by_prefix(addr, VALIDATOR_ADDRESS_PREFIX);
+    let response =
+        QueryValidatorResponse::decode(fetch_staking_validator(querier, val_addr)?.as_slice())
+            .map_err(|err| ContractError::App(err.to_string()))?;
+    let Some(validator) = response.validator else {
+        return Ok(None);
+    };
+    if validator.jailed || validator.status != BondStatus::Bonded as i32 {
+        return Ok(None);
+    }
+    let voting_power = validator
+        .tokens
+        .parse()
+        .map_err(|_| ContractError::App("Cannot parse voting power".to_string()))?;
+    Ok(Some(voting_power))
+}
+
+/// Calculates the bridge fee for a deposit of the given amount, in the same
+/// units as the amount, for a deposit whose sigset lags `sigset_lag`
+/// checkpoints behind the building checkpoint.
+pub fn calc_deposit_fee(amount: Uint128, sigset_lag: u32, config: &BitcoinConfig) -> u64 {
+    let surcharge_bps = config
+        .sigset_lag_surcharge_bps
+        .saturating_mul(sigset_lag as u64)
+        .min(MAX_SIGSET_LAG_SURCHARGE_BPS);
+    amount.multiply_ratio(surcharge_bps, 10_000u64).u128() as u64
+}
+
+/// Applies the discount given to deposits made to the building checkpoint's
+/// sigset to their miner fee.
+pub fn apply_latest_sigset_discount(
+    miner_fee: u64,
+    sigset_lag: u32,
+    config: &BitcoinConfig,
+) -> u64 {
+    if sigset_lag > 0 {
+        return miner_fee;
+    }
+    let discount_bps = config.latest_sigset_discount_bps.min(10_000);
+    miner_fee - (miner_fee as u128 * discount_bps as u128 / 10_000) as u64
 }
 
 /// The main structure where Bitcoin bridge state is held.
@@ -65,9 +130,6 @@ pub struct Bitcoin {
     // TODO: store recovery script data in account struct
     pub signatory_keys: SignatoryKeys, // ?
 
-    /// A pool of BTC where bridge fees are collected.
-    pub(crate) reward_pool: Coin, // ?
-
     // TODO: turn into Coin<Nbtc>
     // pub(crate) fee_pool: i64, // FEE_POOL
     /// The configuration parameters for the Bitcoin module.
@@ -85,7 +147,6 @@ impl Bitcoin {
             checkpoints: CheckpointQueue::default(),
             processed_outpoints: OutpointSet::default(),
             signatory_keys: SignatoryKeys::default(),
-            reward_pool: Coin::default(),
             // fee_pool: 0,
             config: BitcoinConfig::default(),
             recovery_txs: RecoveryTxs::default(),
@@ -96,6 +157,109 @@ impl Bitcoin {
         Ok(FEE_POOL.load(store).unwrap_or_default())
     }
 
+    pub fn ensure_not_paused(&self, store: &dyn Storage) -> ContractResult<()> {
+        if BRIDGE_PAUSED.may_load(store)?.unwrap_or_default() {
+            return Err(ContractError::BridgePaused {
+                reason: "set by the owner or an invariant violation".to_string(),
+            });
+        }
+        if HEADER_STALL_PAUSED.may_load(store)?.unwrap_or_default() {
+            return Err(ContractError::BridgePaused {
+                reason: "no Bitcoin headers relayed recently".to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Checks the accounting invariants of the bridge, returning a description
+    /// of each one which is violated:
+    /// - the nBTC supply plus the mints still pending is backed by the value
+    ///   the building checkpoint carries forward (its inputs less the
+    ///   withdrawals it pays out),
+    /// - the fee pool is covered by what is left of that value,
+    /// - no checkpoint transaction spends more than its inputs.
+    pub fn check_invariants(
+        &self,
+        store: &dyn Storage,
+        querier: &QuerierWrapper,
+    ) -> ContractResult<Vec<String>> {
+        let mut violations = vec![];
+        if self.checkpoints.is_empty(store)? {
+            return Ok(violations);
+        }
+
+        let mut pending_mints = 0u128;
+        for transfer in PENDING_TRANSFERS.iter(store)? {
+            pending_mints += transfer?.coin.amount.u128();
+        }
+        for claim in MINT_CLAIMS.range(store, None, None, Order::Ascending) {
+            let (_, claim) = claim?;
+            pending_mints += claim.coin.amount.u128();
+        }
+        for (i, checkpoint) in CHECKPOINTS.iter(store)?.enumerate() {
+            let checkpoint = checkpoint?;
+            pending_mints += checkpoint
+                .pending
+                .iter()
+                .map(|transfer| transfer.coin.amount.u128())
+                .sum::<u128>();
+            for (batch_index, batch) in checkpoint.batches.iter().enumerate() {
+                for (tx_index, tx) in batch.iter().enumerate() {
+                    let input_value: u64 = tx.input.iter().map(|input| input.amount).sum();
+                    let output_value: u64 =
+                        tx.output.iter().map(|output| output.value.to_sat()).sum();
+                    if output_value > input_value {
+                        violations.push(format!(
+                            "Checkpoint at queue position {} batch {} tx {} has outputs of {} sats exceeding inputs of {} sats",
+                            i, batch_index, tx_index, output_value, input_value
+                        ));
+                    }
+                }
+            }
+        }
+
+        let bitcoin_config = self.config(store)?;
+        let config = CONFIG.load(store)?;
+        let building = self.checkpoints.building(store)?;
+        let building_tx = &building.batches[BatchType::Checkpoint][0];
+        let building_inputs: u64 = building_tx.input.iter().map(|input| input.amount).sum();
+        let pending_burns: u64 = building_tx
+            .output
+            .iter()
+            .map(|output| output.value.to_sat())
+            .sum();
+        let backing = building_inputs.saturating_sub(pending_burns) as u128
+            * bitcoin_config.units_per_sat as u128;
+        let mut supply = 0u128;
+        let mut denoms = LEGACY_BTC_DENOMS.may_load(store)?.unwrap_or_default();
+        denoms.push(get_full_btc_denom(
+            store,
+            config.token_factory_contract.as_str(),
+        )?);
+        for denom in denoms {
+            supply += querier.query_supply(denom)?.amount.u128();
+        }
+
+        let owed = supply + pending_mints;
+        if owed > backing {
+            violations.push(format!(
+                "nBTC supply {} plus pending mints {} exceeds backing reserve {}",
+                supply, pending_mints, backing
+            ));
+        } else {
+            let fee_pool = self.fee_pool(store)?;
+            if fee_pool > 0 && fee_pool as u128 > backing - owed {
+                violations.push(format!(
+                    "Fee pool {} exceeds reserve surplus {}",
+                    fee_pool,
+                    backing - owed
+                ));
+            }
+        }
+
+        Ok(violations)
+    }
+
     pub fn get_checkpoint(
         &self,
         store: &dyn Storage,
@@ -166,6 +330,91 @@ impl Bitcoin {
         )
     }
 
+    /// Whether deposits to the given signatory set confirmed at `btc_height`
+    /// have expired. Sigsets created before their Bitcoin height was recorded
+    /// fall back to bridge time.
+    pub fn is_sigset_expired(
+        &self,
+        env: &Env,
+        store: &dyn Storage,
+        sigset: &SignatorySet,
+        btc_height: u32,
+    ) -> ContractResult<bool> {
+        let config = self.config(store)?;
+        if config.deposit_age_unit == DepositAgeUnit::BtcBlocks {
+            if let Some(created_at) = SIGSET_BTC_HEIGHTS.may_load(store, sigset.index)? {
+                return Ok(btc_height > created_at.saturating_add(config.max_deposit_age_blocks));
+            }
+        }
+        Ok(bridge_time(env) > sigset.create_time() + config.max_deposit_age)
+    }
+
+    /// Like `is_sigset_expired`, for a deposit confirmed at the current light
+    /// client height.
+    pub fn is_sigset_expired_now(
+        &self,
+        env: &Env,
+        querier: &QuerierWrapper,
+        store: &dyn Storage,
+        sigset: &SignatorySet,
+    ) -> ContractResult<bool> {
+        let btc_height = match self.config(store)?.deposit_age_unit {
+            DepositAgeUnit::Seconds => 0,
+            DepositAgeUnit::BtcBlocks => querier
+                .query_wasm_smart(CONFIG.load(store)?.light_client_contract, &HeaderHeight {})?,
+        };
+        self.is_sigset_expired(env, store, sigset, btc_height)
+    }
+
+    /// Rejects deposits below `min_relayable_utxo_sats` or below the miner fee
+    /// of spending them at `max_fee_rate`, unless the owner allows them for
+    /// now.
+    pub fn check_relayable_utxo(
+        &self,
+        env: &Env,
+        store: &dyn Storage,
+        value: u64,
+        input_vsize: u64,
+    ) -> ContractResult<()> {
+        let min_sats = self.config(store)?.min_relayable_utxo_sats;
+        if min_sats == 0 {
+            return Ok(());
+        }
+        let allowed_until = SMALL_UTXOS_ALLOWED_UNTIL.may_load(store)?;
+        if allowed_until.map_or(false, |until| bridge_time(env) <= until) {
+            return Ok(());
+        }
+        let min_sats = min_sats.max(input_vsize * self.checkpoints.config(store).max_fee_rate);
+        if value < min_sats {
+            return Err(ContractError::DepositTooSmall {
+                value,
+                min: min_sats,
+            });
+        }
+        Ok(())
+    }
+
+    /// The miner fee and the bridge fee deducted from a deposit of `amount`
+    /// units to the given sigset, spent by an input of `input_vsize` at the
+    /// fee rate of the sigset's checkpoint.
+    pub fn deposit_fees(
+        &self,
+        store: &dyn Storage,
+        sigset_index: u32,
+        fee_rate: u64,
+        input_vsize: u64,
+        amount: Uint128,
+    ) -> ContractResult<(u64, u64)> {
+        let config = self.config(store)?;
+        let sigset_lag = self.checkpoints.index(store).saturating_sub(sigset_index);
+        let miner_fee = apply_latest_sigset_discount(
+            self.calc_minimum_deposit_fees(store, input_vsize, fee_rate)?,
+            sigset_lag,
+            &config,
+        );
+        Ok((miner_fee, calc_deposit_fee(amount, sigset_lag, &config)))
+    }
+
     pub fn calc_minimum_withdrawal_fees(
         &self,
         store: &dyn Storage,
@@ -200,38 +449,24 @@ impl Bitcoin {
         dest: Dest,
         testing_sandbox: bool,
     ) -> ContractResult<()> {
-        let bitcoin_config = self.config(store)?;
-        let config = CONFIG.load(store)?;
-        let now = env.block.time.seconds();
+        self.ensure_not_paused(store)?;
+        check_relayed_tx(&btc_tx, &self.config(store)?.relay_tx_limits)?;
 
+        let mut block_hash = None;
         if !testing_sandbox {
-            let sidechain_btc_height: u32 =
-                querier.query_wasm_smart(config.light_client_contract.clone(), &HeaderHeight {})?;
-            if sidechain_btc_height < btc_height {
-                return Err(ContractError::App(
-                    format!(
-                        "Block height is in the future, btc_height: {} - sidechain_btc_height: {} queried on contract: {:?}",
-                        btc_height, sidechain_btc_height, config.light_client_contract.clone()
-                    )
-                    .to_string(),
-                ));
-            }
-            if sidechain_btc_height - btc_height < bitcoin_config.min_confirmations {
-                return Err(ContractError::App(
-                    "Block is not sufficiently confirmed".to_string(),
-                ));
-            }
-
-            let _: () = querier
-                .query_wasm_smart(
-                    config.light_client_contract.clone(),
-                    &VerifyTxWithProof {
-                        btc_tx: btc_tx.clone(),
-                        btc_height,
-                        btc_proof,
-                    },
-                )
-                .unwrap();
+            let query = VerifyTxWithProof {
+                btc_tx: btc_tx.clone(),
+                btc_height,
+                btc_proof,
+            };
+            self.verify_deposit_inclusion(querier, store, btc_height, &query)?;
+            // recorded with the mint, to tell later whether the deposit was
+            // reorged out of the chain
+            let hash: Option<WrappedBinary<BlockHash>> = querier.query_wasm_smart(
+                CONFIG.load(store)?.light_client_contract.as_str(),
+                &BlockHashAtHeight { height: btc_height },
+            )?;
+            block_hash = hash.map(|hash| hash.0.to_string());
         }
 
         if btc_vout as usize >= btc_tx.output.len() {
@@ -240,6 +475,71 @@ impl Bitcoin {
             ))?;
         }
         let output = &btc_tx.output[btc_vout as usize];
+        self.process_deposit(
+            env,
+            store,
+            btc_tx.txid(),
+            output,
+            btc_height,
+            btc_vout,
+            sigset_index,
+            dest,
+            block_hash,
+        )
+    }
+
+    /// Checks the deposit's block has enough confirmations and runs the light
+    /// client query verifying the transaction is included in it.
+    fn verify_deposit_inclusion(
+        &self,
+        querier: &QuerierWrapper,
+        store: &dyn Storage,
+        btc_height: u32,
+        verify_query: &LightClientQueryMsg,
+    ) -> ContractResult<()> {
+        let bitcoin_config = self.config(store)?;
+        let config = CONFIG.load(store)?;
+        let sidechain_btc_height: u32 =
+            querier.query_wasm_smart(config.light_client_contract.clone(), &HeaderHeight {})?;
+        if sidechain_btc_height < btc_height {
+            return Err(ContractError::BlockInFuture {
+                height: btc_height,
+                tip: sidechain_btc_height,
+            });
+        }
+        if sidechain_btc_height - btc_height < bitcoin_config.min_confirmations {
+            return Err(ContractError::InsufficientConfirmations {
+                have: sidechain_btc_height - btc_height,
+                need: bitcoin_config.min_confirmations,
+            });
+        }
+
+        let _: () = querier
+            .query_wasm_smart(config.light_client_contract.clone(), verify_query)
+            .unwrap();
+        Ok(())
+    }
+
+    /// Processes a relayed deposit output whose transaction has been verified.
+    #[allow(clippy::too_many_arguments)]
+    fn process_deposit(
+        &mut self,
+        env: &Env,
+        store: &mut dyn Storage,
+        txid: Txid,
+        output: &TxOut,
+        btc_height: u32,
+        btc_vout: u32,
+        sigset_index: u32,
+        dest: Dest,
+        block_hash: Option<String>,
+    ) -> ContractResult<()> {
+        if BRIDGE_MIGRATION.may_load(store)?.is_some() {
+            return Err(ContractError::App(
+                "Deposits are frozen while migrating to a successor bridge".to_string(),
+            ));
+        }
+        let bitcoin_config = self.config(store)?;
 
         // if output.value < self.config.min_deposit_amount {
         //     return Err(ContractError::App(
@@ -253,26 +553,45 @@ impl Bitcoin {
         let dest_bytes = dest.commitment_bytes()?;
         let threshold = self.checkpoints.config(store).sigset_threshold;
 
-        let expected_script = sigset.output_script(&dest_bytes, threshold)?;
-        if output.script_pubkey != expected_script {
+        // addresses derived before destination commitments were versioned
+        // keep working
+        let mut matched = None;
+        for version in [DEST_COMMITMENT_VERSION, LEGACY_DEST_COMMITMENT_VERSION] {
+            let commitment = dest.versioned_commitment_bytes(version)?;
+            if output.script_pubkey == sigset.output_script(&commitment, threshold)? {
+                matched = Some((version, commitment));
+                break;
+            }
+        }
+        let Some((commitment_version, commitment)) = matched else {
+            let expected_script = sigset.output_script(
+                &dest.versioned_commitment_bytes(DEST_COMMITMENT_VERSION)?,
+                threshold,
+            )?;
             return Err(ContractError::App(
                 format!(
                     "Output script does not match signature set {} {}",
-                    output.script_pubkey.to_v0_p2wsh().to_string(),
-                    expected_script.to_v0_p2wsh().to_string()
+                    output.script_pubkey.to_p2wsh().to_string(),
+                    expected_script.to_p2wsh().to_string()
                 )
                 .to_string(),
             ))?;
-        }
-        let outpoint = bitcoin::OutPoint::new(btc_tx.txid(), btc_vout);
+        };
+        let outpoint = bitcoin::OutPoint::new(txid, btc_vout);
         if self.processed_outpoints.contains(store, outpoint) {
-            return Err(ContractError::App(
-                "Output has already been relayed".to_string(),
-            ))?;
+            return Err(ContractError::OutputAlreadyRelayed {
+                txid: outpoint.txid.to_string(),
+                vout: outpoint.vout,
+            });
         }
         let deposit_timeout = sigset.create_time() + bitcoin_config.max_deposit_age;
         self.processed_outpoints
             .insert(store, outpoint, deposit_timeout)?;
+        remove_pending_deposit(store, &txid.to_string(), btc_vout)?;
+        let latest_sigset_index = DEST_SIGSET_INDEXES.may_load(store, &dest_bytes)?;
+        if latest_sigset_index.map_or(true, |index| index < sigset_index) {
+            DEST_SIGSET_INDEXES.save(store, &dest_bytes, &sigset_index)?;
+        }
 
         if !checkpoint.deposits_enabled {
             return Err(ContractError::App(
@@ -280,20 +599,64 @@ impl Bitcoin {
             ))?;
         }
 
-        if now > deposit_timeout {
+        if self.is_sigset_expired(env, store, &sigset, btc_height)? {
+            if output.value.to_sat() < bitcoin_config.dust_deposit_threshold {
+                let txid = txid.to_string();
+                DUST_DEPOSITS.save(
+                    store,
+                    (&txid, btc_vout),
+                    &ExpiredDeposit {
+                        txid: txid.clone(),
+                        vout: btc_vout,
+                        dest,
+                        value: output.value.to_sat(),
+                        sigset_index,
+                        btc_height,
+                        commitment_version,
+                    },
+                )?;
+                return Ok(());
+            }
+            if bitcoin_config.claimable_expired_deposits {
+                let txid = txid.to_string();
+                EXPIRED_DEPOSITS_BY_DEST.save(
+                    store,
+                    (&dest.commitment_bytes()?, &txid, btc_vout),
+                    &(),
+                )?;
+                EXPIRED_DEPOSITS.save(
+                    store,
+                    (&txid, btc_vout),
+                    &ExpiredDeposit {
+                        txid: txid.clone(),
+                        vout: btc_vout,
+                        dest,
+                        value: output.value.to_sat(),
+                        sigset_index,
+                        btc_height,
+                        commitment_version,
+                    },
+                )?;
+                return Ok(());
+            }
+
             let checkpoint = self.checkpoints.building(store)?;
             let checkpoint_config = self.checkpoints.config(store);
             self.recovery_txs.create_recovery_tx(
                 store,
                 RecoveryTxInput {
-                    expired_tx: btc_tx.into_inner(),
-                    vout: btc_vout,
+                    expired_outpoint: outpoint,
+                    value: output.value.to_sat(),
                     old_sigset: &sigset,
                     new_sigset: &checkpoint.sigset,
                     dest,
                     fee_rate: checkpoint.fee_rate,
                     //TODO: Hold checkpoint config on state
                     threshold: checkpoint_config.sigset_threshold,
+                    refund_script: None,
+                    lock_time: recovery_lock_time(btc_height, &bitcoin_config),
+                    commitment_version,
+                    fee_pool_share: 0,
                 },
             )?;
 
@@ -301,27 +664,33 @@ impl Bitcoin {
         }
 
         let prevout = bitcoin::OutPoint {
-            txid: btc_tx.txid(),
+            txid,
             vout: btc_vout,
         };
         let input = Input::new(
             prevout,
             &sigset,
-            &dest_bytes,
-            output.value,
+            &commitment,
+            output.value.to_sat(),
             self.checkpoints.config(store).sigset_threshold,
         )?;
         let input_size = input.est_vsize();
+        self.check_relayable_utxo(env, store, output.value.to_sat(), input_size)?;
 
         // note: we only mint nbtc when it is send to destination
-        let mint_amount = (output.value * bitcoin_config.units_per_sat).into();
-        let denom = get_full_btc_denom(CONFIG.load(store)?.token_factory_contract.as_str());
+        let mint_amount = (output.value.to_sat() * bitcoin_config.units_per_sat).into();
+        let denom = get_full_btc_denom(store, CONFIG.load(store)?.token_factory_contract.as_str())?;
         let mut nbtc = Coin {
             denom,
             amount: mint_amount,
         };
-        let fee_amount = self.calc_minimum_deposit_fees(store, input_size, checkpoint.fee_rate)?;
-        let deposit_fees = calc_deposit_fee(nbtc.amount);
+        let (fee_amount, deposit_fees) = self.deposit_fees(
+            store,
+            sigset_index,
+            checkpoint.fee_rate,
+            input_size,
+            nbtc.amount,
+        )?;
         let fee = (fee_amount + deposit_fees).into();
         nbtc.amount = nbtc.amount.checked_sub(fee).map_err(|_| {
             ContractError::App("Deposit amount is too small to pay its spending fee".to_string())
@@ -329,7 +698,9 @@ impl Bitcoin {
         #[cfg(debug_assertions)]
         println!(
             "Relay deposit with output value: {}, input size: {}, checkpoint fee rate: {}",
-            output.value, input_size, checkpoint.fee_rate
+            output.value.to_sat(),
+            input_size,
+            checkpoint.fee_rate
         );
 
         self.give_miner_fee(store, fee)?;
@@ -344,58 +715,477 @@ impl Bitcoin {
         // let deposit_fee = nbtc.take(calc_deposit_fee(nbtc.amount.into()))?;
         // self.give_rewards(deposit_fee)?;
 
-        building_mut.insert_pending(dest, nbtc)?;
+        record_dest_deposit(store, &dest, output.value.to_sat(), bridge_time(env))?;
 
         let index = self.checkpoints.index(store);
+        MINT_RECORDS.save(
+            store,
+            (&txid.to_string(), btc_vout),
+            &MintRecord {
+                dest: dest.clone(),
+                amount: nbtc.amount,
+                checkpoint_index: index,
+                btc_height,
+                block_hash,
+            },
+        )?;
+
+        building_mut.insert_pending(PendingTransfer::deposit(dest, nbtc, &outpoint))?;
         self.checkpoints.set(store, index, &building_mut)?;
 
         Ok(())
     }
 
+    /// Attaches a reserve output created outside of the checkpoint chain to the
+    /// `Building` checkpoint, which merges it into its reserve output. Returns
+    /// the index of the checkpoint.
+    pub fn register_reserve_utxo(
+        &mut self,
+        store: &mut dyn Storage,
+        outpoint: bitcoin::OutPoint,
+        amount: u64,
+        sigset_index: u32,
+    ) -> ContractResult<u32> {
+        if self.processed_outpoints.contains(store, outpoint) {
+            return Err(ContractError::OutputAlreadyRelayed {
+                txid: outpoint.txid.to_string(),
+                vout: outpoint.vout,
+            });
+        }
+        let txid = outpoint.txid.to_string();
+        if RESERVE_UTXOS.has(store, (&txid, outpoint.vout)) {
+            return Err(ContractError::App(
+                "Reserve output is already registered".to_string(),
+            ));
+        }
+
+        let sigset = self.checkpoints.sigset(store, sigset_index)?;
+        let input = Input::new(
+            outpoint,
+            &sigset,
+            &[0u8],
+            amount,
+            self.checkpoints.config(store).sigset_threshold,
+        )?;
+        // the output can no longer be relayed as a deposit once attached
+        let expiration = sigset.create_time() + self.config(store)?.max_deposit_age;
+        self.processed_outpoints
+            .insert(store, outpoint, expiration)?;
+
+        let index = self.checkpoints.index(store);
+        let mut building = self.checkpoints.building(store)?;
+        building.batches[BatchType::Checkpoint][0].input.push(input);
+        self.checkpoints.set(store, index, &building)?;
+
+        RESERVE_UTXOS.save(
+            store,
+            (&txid, outpoint.vout),
+            &ReserveUtxo {
+                txid: txid.clone(),
+                vout: outpoint.vout,
+                amount,
+                sigset_index,
+                checkpoint_index: index,
+            },
+        )?;
+
+        Ok(index)
+    }
+
+    /// Attaches an output created outside of the checkpoint chain, e.g. by an
+    /// emergency recovery, once its transaction is confirmed. The output must
+    /// pay to the reserve script of the signatory set at `sigset_index`, and
+    /// is attached with its proven value.
+    #[allow(clippy::too_many_arguments)]
+    pub fn relay_reserve_utxo(
+        &mut self,
+        querier: &QuerierWrapper,
+        store: &mut dyn Storage,
+        btc_tx: Adapter<Transaction>,
+        btc_height: u32,
+        btc_proof: Adapter<PartialMerkleTree>,
+        vout: u32,
+        sigset_index: u32,
+    ) -> ContractResult<u32> {
+        let query = VerifyTxWithProof {
+            btc_tx: btc_tx.clone(),
+            btc_height,
+            btc_proof,
+        };
+        self.verify_deposit_inclusion(querier, store, btc_height, &query)?;
+
+        let output = btc_tx
+            .output
+            .get(vout as usize)
+            .ok_or_else(|| ContractError::App("Output index is out of bounds".to_string()))?;
+        let sigset = self.checkpoints.sigset(store, sigset_index)?;
+        let reserve_script =
+            sigset.output_script(&[0u8], self.checkpoints.config(store).sigset_threshold)?;
+        if output.script_pubkey != reserve_script {
+            return Err(ContractError::App(
+                "Output does not pay to the reserve script of the signatory set".to_string(),
+            ));
+        }
+
+        self.register_reserve_utxo(
+            store,
+            bitcoin::OutPoint::new(btc_tx.txid(), vout),
+            output.value.to_sat(),
+            sigset_index,
+        )
+    }
+
+    /// Refunds an expired deposit to the given script, creating a recovery
+    /// transaction for the signatories to sign.
+    ///
+    /// Only the depositor, i.e. the account the deposit's destination
+    /// commitment refers to, may claim it.
+    pub fn claim_expired_deposit(
+        &mut self,
+        store: &mut dyn Storage,
+        claimer: &Addr,
+        txid: String,
+        vout: u32,
+        refund_script: ScriptBuf,
+    ) -> ContractResult<()> {
+        let deposit = EXPIRED_DEPOSITS
+            .may_load(store, (&txid, vout))?
+            .ok_or_else(|| ContractError::App("Expired deposit not found".to_string()))?;
+        if !is_same_account(claimer.as_str(), &deposit.dest.to_source_addr()) {
+            return Err(ContractError::Unauthorized {});
+        }
+        if refund_script.len() as u64 > self.config(store)?.max_withdrawal_script_length {
+            return Err(ContractError::App(
+                "Script exceeds maximum length".to_string(),
+            ));
+        }
+
+        let bitcoin_config = self.config(store)?;
+        let old_sigset = self.checkpoints.get(store, deposit.sigset_index)?.sigset;
+        let building = self.checkpoints.building(store)?;
+        let expired_outpoint = bitcoin::OutPoint::new(
+            bitcoin::Txid::from_str(&deposit.txid)
+                .map_err(|err| ContractError::App(err.to_string()))?,
+            deposit.vout,
+        );
+        let dest_key = deposit.dest.commitment_bytes()?;
+        self.recovery_txs.create_recovery_tx(
+            store,
+            RecoveryTxInput {
+                expired_outpoint,
+                value: deposit.value,
+                old_sigset: &old_sigset,
+                new_sigset: &building.sigset,
+                dest: deposit.dest,
+                fee_rate: building.fee_rate,
+                threshold: self.checkpoints.config(store).sigset_threshold,
+                refund_script: Some(refund_script),
+                lock_time: recovery_lock_time(deposit.btc_height, &bitcoin_config),
+                commitment_version: deposit.commitment_version,
+                fee_pool_share: bitcoin_config.expired_deposit_fee_share(deposit.value),
+            },
+        )?;
+        EXPIRED_DEPOSITS_BY_DEST.remove(store, (&dest_key, &txid, vout));
+        EXPIRED_DEPOSITS.remove(store, (&txid, vout));
+
+        Ok(())
+    }
+
     /// Records proof that a checkpoint produced by the network has been
-    /// confirmed into a Bitcoin block.    
+    /// confirmed into a Bitcoin block.
+    ///
+    /// The checkpoint index is derived from the txid matched by the proof, so
+    /// anyone can relay a confirmation without knowing which checkpoint it
+    /// belongs to. If `cp_index` is given, it must agree with the proof.
+    /// Relaying an already confirmed checkpoint succeeds without effect, so
+    /// relayers can safely retry.
     pub fn relay_checkpoint(
+        &mut self,
+        querier: &QuerierWrapper,
+        store: &mut dyn Storage,
+        btc_height: u32,
+        btc_proof: Adapter<PartialMerkleTree>,
+        cp_index: Option<u32>,
+        ancestor_headers: Vec<WrappedHeader>,
+        relayer: &Addr,
+        testing_sandbox: bool,
+    ) -> ContractResult<bool> {
+        let cp_index = match (self.proven_checkpoint_index(store, &btc_proof)?, cp_index) {
+            (Some(proven), Some(cp_index)) if proven != cp_index => {
+                return Err(ContractError::App(format!(
+                    "Proof is for checkpoint {}, not {}",
+                    proven, cp_index
+                )));
+            }
+            (Some(proven), _) => proven,
+            (None, Some(cp_index)) => cp_index,
+            (None, None) => {
+                return Err(ContractError::App(
+                    "Proof does not match any completed checkpoint".to_string(),
+                ));
+            }
+        };
+        if self.is_checkpoint_confirmed(store, cp_index)? {
+            return Ok(true);
+        }
+
+        self.confirm_checkpoint_tx(
+            querier,
+            store,
+            btc_height,
+            btc_proof,
+            cp_index,
+            None,
+            ancestor_headers,
+            relayer,
+            testing_sandbox,
+        )
+    }
+
+    /// Whether the confirmation of the checkpoint at `index` has been
+    /// recorded, either directly or through a later checkpoint spending its
+    /// reserve output.
+    pub fn is_checkpoint_confirmed(&self, store: &dyn Storage, index: u32) -> ContractResult<bool> {
+        let confirmed = self
+            .checkpoints
+            .confirmed_index(store)
+            .map_or(false, |confirmed_index| index <= confirmed_index);
+        Ok(confirmed || CHECKPOINT_CONFIRMATION_HEIGHTS.has(store, index))
+    }
+
+    /// Records proof that a fee-bumped variant of a checkpoint transaction,
+    /// i.e. the signed transaction with extra inputs attached by a relayer,
+    /// has been confirmed into a Bitcoin block.
+    ///
+    /// Once confirmed, the succeeding `Building` checkpoint is updated to spend
+    /// the reserve output by the txid of the confirmed transaction.
+    pub fn relay_fee_bumped_checkpoint(
         &mut self,
         querier: &QuerierWrapper,
         store: &mut dyn Storage,
         btc_height: u32,
         btc_proof: Adapter<PartialMerkleTree>,
         cp_index: u32,
+        btc_tx: Adapter<Transaction>,
+        relayer: &Addr,
         testing_sandbox: bool,
+    ) -> ContractResult<bool> {
+        if !self.checkpoints.config(store).fee_bump_anyone_can_pay {
+            return Err(ContractError::App(
+                "Checkpoint fee bumping is disabled".to_string(),
+            ));
+        }
+        if self.is_checkpoint_confirmed(store, cp_index)? {
+            return Ok(true);
+        }
+        check_relayed_tx(&btc_tx, &self.config(store)?.relay_tx_limits)?;
+
+        let checkpoint_tx = self.checkpoints.get(store, cp_index)?.checkpoint_tx()?;
+        let spends_checkpoint_inputs = checkpoint_tx.input.iter().all(|txin| {
+            btc_tx
+                .input
+                .iter()
+                .any(|bumped| bumped.previous_output == txin.previous_output)
+        });
+        if btc_tx.output != checkpoint_tx.output
+            || btc_tx.input.len() <= checkpoint_tx.input.len()
+            || !spends_checkpoint_inputs
+        {
+            return Err(ContractError::App(
+                "Transaction is not a fee-bumped checkpoint transaction".to_string(),
+            ));
+        }
+
+        let bumped_txid = btc_tx.txid();
+        let confirmed = self.confirm_checkpoint_tx(
+            querier,
+            store,
+            btc_height,
+            btc_proof,
+            cp_index,
+            Some(btc_tx),
+            vec![],
+            relayer,
+            testing_sandbox,
+        )?;
+
+        if confirmed {
+            record_checkpoint_txid(store, cp_index, &bumped_txid.to_string())?;
+            self.respend_bumped_reserve(store, cp_index, checkpoint_tx.txid(), bumped_txid)?;
+        }
+
+        Ok(confirmed)
+    }
+
+    /// Updates the `Building` checkpoint to spend the reserve output of the
+    /// checkpoint at `cp_index` by the txid of its confirmed fee-bumped
+    /// variant. Does nothing if the building checkpoint does not succeed it.
+    fn respend_bumped_reserve(
+        &mut self,
+        store: &mut dyn Storage,
+        cp_index: u32,
+        original_txid: Txid,
+        bumped_txid: Txid,
     ) -> ContractResult<()> {
-        let bitcoin_config = self.config(store)?;
-        let config = CONFIG.load(store)?;
-        if let Some(conf_index) = self.checkpoints.confirmed_index(store) {
-            if cp_index <= conf_index {
-                return Err(ContractError::App(
-                    "Checkpoint has already been relayed".to_string(),
-                ))?;
+        let building_index = self.checkpoints.index(store);
+        if building_index != cp_index + 1 {
+            return Ok(());
+        }
+        let mut building = self.checkpoints.get(store, building_index)?;
+        for input in building.batches[BatchType::Checkpoint][0].input.iter_mut() {
+            if input.prevout.txid == original_txid {
+                input.prevout =
+                    Adapter::new(bitcoin::OutPoint::new(bumped_txid, input.prevout.vout));
+            }
+        }
+        self.checkpoints.set(store, building_index, &building)
+    }
+
+    /// The index of the completed checkpoint whose transaction is matched by
+    /// the given proof, if any.
+    fn proven_checkpoint_index(
+        &self,
+        store: &dyn Storage,
+        btc_proof: &PartialMerkleTree,
+    ) -> ContractResult<Option<u32>> {
+        let mut matches = vec![];
+        let mut indexes = vec![];
+        btc_proof
+            .extract_matches(&mut matches, &mut indexes)
+            .map_err(|err| ContractError::App(format!("Invalid proof: {:?}", err)))?;
+
+        for txid in matches {
+            if let Some(index) = CHECKPOINT_TXIDS.may_load(store, &txid.to_string())? {
+                return Ok(Some(index));
             }
         }
 
+        Ok(None)
+    }
+
+    /// Verifies the confirmation of the given checkpoint's transaction (or of
+    /// `btc_tx` if given) and marks the checkpoint as confirmed, once enough
+    /// relayers agree. Non-empty `ancestor_headers` prove a block which was
+    /// pruned from the light client.
+    fn confirm_checkpoint_tx(
+        &mut self,
+        querier: &QuerierWrapper,
+        store: &mut dyn Storage,
+        btc_height: u32,
+        btc_proof: Adapter<PartialMerkleTree>,
+        cp_index: u32,
+        btc_tx: Option<Adapter<Transaction>>,
+        ancestor_headers: Vec<WrappedHeader>,
+        relayer: &Addr,
+        testing_sandbox: bool,
+    ) -> ContractResult<bool> {
+        let bitcoin_config = self.config(store)?;
+        let config = CONFIG.load(store)?;
         if !testing_sandbox {
             let sidechain_btc_height: u32 =
                 querier.query_wasm_smart(config.light_client_contract.clone(), &HeaderHeight {})?;
             if sidechain_btc_height - btc_height < bitcoin_config.min_checkpoint_confirmations {
-                return Err(ContractError::App(
-                    "Block is not sufficiently confirmed".to_string(),
-                ));
+                return Err(ContractError::InsufficientConfirmations {
+                    have: sidechain_btc_height - btc_height,
+                    need: bitcoin_config.min_checkpoint_confirmations,
+                });
             }
 
-            let btc_tx = self.checkpoints.get(store, cp_index)?.checkpoint_tx()?;
+            let query = if ancestor_headers.is_empty() {
+                let btc_tx = match btc_tx {
+                    Some(btc_tx) => btc_tx,
+                    None => self.checkpoints.get(store, cp_index)?.checkpoint_tx()?,
+                };
+                VerifyTxWithProof {
+                    btc_tx,
+                    btc_height,
+                    btc_proof,
+                }
+            } else {
+                let txid = match btc_tx {
+                    Some(btc_tx) => btc_tx.txid(),
+                    None => self.checkpoints.txid(store, cp_index)?,
+                };
+                VerifyTxidWithAncestorProof {
+                    txid: Adapter::new(txid),
+                    btc_height,
+                    btc_proof,
+                    headers: ancestor_headers,
+                }
+            };
             let _: () = querier
-                .query_wasm_smart(
-                    config.light_client_contract.clone(),
-                    &VerifyTxWithProof {
-                        btc_tx,
-                        btc_height,
-                        btc_proof,
-                    },
-                )
+                .query_wasm_smart(config.light_client_contract.clone(), &query)
                 .unwrap();
         }
 
-        CONFIRMED_INDEX.save(store, &cp_index)?;
+        let quorum = bitcoin_config.checkpoint_confirmation_quorum;
+        if quorum > 1 {
+            if !RELAYERS.has(store, relayer) {
+                return Err(ContractError::App("Relayer is not registered".to_string()));
+            }
+            CHECKPOINT_ATTESTATIONS.save(store, (cp_index, relayer), &btc_height)?;
+
+            let attestations = CHECKPOINT_ATTESTATIONS
+                .prefix(cp_index)
+                .range(store, None, None, Order::Ascending)
+                .collect::<StdResult<Vec<_>>>()?;
+            let matching: Vec<_> = attestations
+                .iter()
+                .filter(|(_, height)| *height == btc_height)
+                .map(|(attester, _)| attester.clone())
+                .collect();
+            if (matching.len() as u32) < quorum {
+                return Ok(false);
+            }
+
+            for (attester, _) in attestations {
+                CHECKPOINT_ATTESTATIONS.remove(store, (cp_index, &attester));
+            }
+            self.record_confirmation(querier, store, cp_index, btc_height, &matching)?;
+            return Ok(true);
+        }
+
+        self.record_confirmation(querier, store, cp_index, btc_height, &[relayer.clone()])?;
+        Ok(true)
+    }
+
+    /// Records the confirmation height of the checkpoint at `cp_index` and
+    /// advances the confirmed index through it, rewarding the relayers who
+    /// proved the confirmation and the checkpoint's signatories.
+    fn record_confirmation(
+        &mut self,
+        querier: &QuerierWrapper,
+        store: &mut dyn Storage,
+        cp_index: u32,
+        btc_height: u32,
+        relayers: &[Addr],
+    ) -> ContractResult<()> {
+        CHECKPOINT_CONFIRMATION_HEIGHTS.save(store, cp_index, &btc_height)?;
+        if let Some(time) = self.confirmation_time(querier, store, cp_index, btc_height)? {
+            CHECKPOINT_CONFIRMATION_TIMES.save(store, cp_index, &time)?;
+        }
+
+        let config = self.config(store)?;
+        if config.reward_pool_enabled {
+            accrue_fee_pool_surplus(store, &config)?;
+            let sigset = self.checkpoints.sigset(store, cp_index)?;
+            distribute_rewards(store, &config, &sigset, relayers)?;
+        }
+
+        // Each checkpoint spends the reserve output of its predecessor, so a
+        // confirmed checkpoint implies all earlier ones are confirmed too.
+        // Continue through any later checkpoints whose confirmations were
+        // already recorded.
+        let mut confirmed_index = cp_index;
+        while CHECKPOINT_CONFIRMATION_HEIGHTS.has(store, confirmed_index + 1) {
+            confirmed_index += 1;
+        }
+        CONFIRMED_INDEX.save(store, &confirmed_index)?;
+
+        if let Some(delay) = self.checkpoints.config(store).signature_compaction_delay {
+            let delay = delay.max(self.config(store)?.max_offline_checkpoints);
+            self.checkpoints.compact_confirmed(store, delay)?;
+        }
         #[cfg(debug_assertions)]
         println!(
             "Checkpoint {} confirmed at Bitcoin height {}",
@@ -405,16 +1195,113 @@ impl Bitcoin {
         Ok(())
     }
 
+    /// The seconds between the headers of the Bitcoin blocks the checkpoint at
+    /// `cp_index` was signed and confirmed at, if both are still in the light
+    /// client's header queue.
+    fn confirmation_time(
+        &self,
+        querier: &QuerierWrapper,
+        store: &dyn Storage,
+        cp_index: u32,
+        btc_height: u32,
+    ) -> ContractResult<Option<u64>> {
+        let Some(signed_height) = self
+            .checkpoints
+            .get(store, cp_index)
+            .ok()
+            .and_then(|checkpoint| checkpoint.signed_at_btc_height)
+        else {
+            return Ok(None);
+        };
+        let light_client = CONFIG.load(store)?.light_client_contract;
+        let header_time = |height| {
+            querier
+                .query_wasm_smart::<Option<HeaderAtResponse>>(
+                    light_client.clone(),
+                    &HeaderAt { height },
+                )
+                .ok()
+                .flatten()
+                .map(|res| res.header.time() as u64)
+        };
+        Ok(header_time(signed_height)
+            .zip(header_time(btc_height))
+            .map(|(signed_time, confirmed_time)| confirmed_time.saturating_sub(signed_time)))
+    }
+
+    /// Raises the value of the withdrawal added at `position` by `increase`
+    /// satoshis. Fails if the checkpoint of the withdrawal is no longer
+    /// `Building`.
+    ///
+    /// Withdrawals merged into the output of an earlier withdrawal to the same
+    /// script raise the merged output.
+    pub fn increase_withdrawal_value(
+        &mut self,
+        store: &mut dyn Storage,
+        position: WithdrawalPosition,
+        increase: u64,
+    ) -> ContractResult<()> {
+        let index = self.checkpoints.index(store);
+        if position.checkpoint_index != index {
+            return Err(ContractError::App(
+                "Withdrawal is no longer pending".to_string(),
+            ));
+        }
+        let mut checkpoint = self.checkpoints.building(store)?;
+        let not_found = || ContractError::App("Withdrawal not found in checkpoint".to_string());
+        let output = checkpoint.batches[BatchType::Checkpoint][0]
+            .output
+            .get_mut(position.output_index as usize)
+            .ok_or_else(not_found)?;
+        output.value += Amount::from_sat(increase);
+        let withdrawal_fee = checkpoint
+            .withdrawal_fees
+            .get_mut(position.fee_index as usize)
+            .ok_or_else(not_found)?;
+        withdrawal_fee.value += increase;
+
+        self.checkpoints.set(store, index, &checkpoint)?;
+
+        Ok(())
+    }
+
     /// Adds an output to the current `Building` checkpoint to be paid out once
-    /// the checkpoint is fully signed.
+    /// the checkpoint is fully signed, returning the charged fee and where the
+    /// withdrawal was added.
     pub fn add_withdrawal(
         &mut self,
         store: &mut dyn Storage,
-        script_pubkey: Adapter<Script>,
+        script_pubkey: Adapter<ScriptBuf>,
+        amount: Uint128,
+        fee: Option<u64>,
+    ) -> ContractResult<(WithdrawalFee, WithdrawalPosition)> {
+        // Snapshot the fee at the current rate, this is what the user is
+        // charged regardless of which checkpoint pays out the withdrawal.
+        let fee_rate = self.checkpoints.building(store)?.fee_rate;
+        self.add_withdrawal_at_rate(store, script_pubkey, amount, fee, fee_rate)
+    }
+
+    /// Like `add_withdrawal`, charging the miner fee at the given fee rate
+    /// instead of the rate of the building checkpoint, for withdrawals whose
+    /// fee was quoted in advance.
+    pub fn add_withdrawal_at_rate(
+        &mut self,
+        store: &mut dyn Storage,
+        script_pubkey: Adapter<ScriptBuf>,
         mut amount: Uint128,
         fee: Option<u64>,
-    ) -> ContractResult<()> {
+        fee_rate: u64,
+    ) -> ContractResult<(WithdrawalFee, WithdrawalPosition)> {
         let config = self.config(store)?;
+        self.ensure_not_paused(store)?;
+        let migrated = BRIDGE_MIGRATION
+            .may_load(store)?
+            .map_or(false, |migration| migration.checkpoint_index.is_some());
+        if migrated {
+            return Err(ContractError::App(
+                "The reserve was migrated to a successor bridge".to_string(),
+            ));
+        }
         if script_pubkey.len() as u64 > config.max_withdrawal_script_length {
             return Err(ContractError::App(
                 "Script exceeds maximum length".to_string(),
@@ -428,11 +1315,8 @@ impl Bitcoin {
             )));
         }
 
-        let calc_fee_amount = self.calc_minimum_withdrawal_fees(
-            store,
-            script_pubkey.len() as u64,
-            self.checkpoints.building(store)?.fee_rate,
-        )?;
+        let calc_fee_amount =
+            self.calc_minimum_withdrawal_fees(store, script_pubkey.len() as u64, fee_rate)?;
         let fee_amount = std::cmp::max(calc_fee_amount, fee.unwrap_or(0));
         let fee = fee_amount.into();
         amount = amount.checked_sub(fee).map_err(|_| {
@@ -448,35 +1332,68 @@ impl Bitcoin {
         //         "Withdrawal is smaller than than minimum amount".to_string(),
         //     ));
         // }
-        if bitcoin::Amount::from_sat(value) <= script_pubkey.dust_value() {
+        if is_dust(&script_pubkey, value) {
             return Err(ContractError::App(
                 "Withdrawal is too small to pay its dust limit".to_string(),
             ));
         }
 
+        let withdrawal_fee = WithdrawalFee {
+            script_pubkey: script_pubkey.clone(),
+            value,
+            fee_rate,
+            est_vsize: 9 + script_pubkey.len() as u64,
+            fee: fee_amount,
+        };
+
         let output = bitcoin::TxOut {
             script_pubkey: script_pubkey.into_inner(),
-            value,
+            value: Amount::from_sat(value),
         };
 
         let mut checkpoint = self.checkpoints.building(store)?;
+        let fee_index = checkpoint.withdrawal_fees.len() as u32;
+        checkpoint.withdrawal_fees.push(withdrawal_fee.clone());
         let building_checkpoint_batch = &mut checkpoint.batches[BatchType::Checkpoint];
         let checkpoint_tx = building_checkpoint_batch.get_mut(0).unwrap();
-        checkpoint_tx.output.push(Adapter::new(output));
+        // each withdrawal keeps its own fee snapshot and record, only the
+        // output is shared
+        let merge_into = match config.separate_withdrawal_outputs {
+            true => None,
+            false => checkpoint_tx
+                .output
+                .iter()
+                .position(|existing| existing.script_pubkey == output.script_pubkey),
+        };
+        let output_index = match merge_into {
+            Some(pos) => {
+                checkpoint_tx.output[pos].value += output.value;
+                pos
+            }
+            None => {
+                checkpoint_tx.output.push(Adapter::new(output));
+                checkpoint_tx.output.len() - 1
+            }
+        };
         println!("Checkpoint tx output: {:?}", checkpoint_tx.output);
 
         let index = self.checkpoints.index(store);
         self.checkpoints.set(store, index, &checkpoint)?;
         // TODO: push to excess if full
 
-        Ok(())
+        let position = WithdrawalPosition {
+            checkpoint_index: index,
+            output_index: output_index as u32,
+            fee_index,
+        };
+        Ok((withdrawal_fee, position))
     }
 
     /// The amount of BTC in the reserve output of the most recent fully-signed
     /// checkpoint.    
     pub fn value_locked(&self, store: &dyn Storage) -> ContractResult<u64> {
         let last_completed = self.checkpoints.last_completed(store)?;
-        Ok(last_completed.reserve_output()?.unwrap().value)
+        Ok(last_completed.reserve_output()?.unwrap().value.to_sat())
     }
 
     /// The network (e.g. Bitcoin testnet vs mainnet) which is currently
@@ -526,8 +1443,8 @@ impl Bitcoin {
 
         let prev_checkpoint = completed.get(prev_index).unwrap();
 
-        let amount_prev = prev_checkpoint.reserve_output()?.unwrap().value;
-        let amount_now = signing.reserve_output()?.unwrap().value;
+        let amount_prev = prev_checkpoint.reserve_output()?.unwrap().value.to_sat();
+        let amount_now = signing.reserve_output()?.unwrap().value.to_sat();
 
         let reserve_decrease = amount_prev.saturating_sub(amount_now);
 
@@ -577,19 +1494,15 @@ impl Bitcoin {
         querier: &QuerierWrapper,
         store: &mut dyn Storage,
         timestamping_commitment: Vec<u8>,
+        budget: &mut u32,
     ) -> ContractResult<Vec<ConsensusKey>> {
         let bitcoin_config = self.config(store)?;
         let config = CONFIG.load(store)?;
-        let has_completed_cp =
-            if let Err(ContractError::App(err)) = self.checkpoints.last_completed_index(store) {
-                if err == "No completed checkpoints yet" {
-                    false
-                } else {
-                    return Err(ContractError::App(err));
-                }
-            } else {
-                true
-            };
+        let has_completed_cp = match self.checkpoints.last_completed_index(store) {
+            Ok(_) => true,
+            Err(ContractError::NoCompletedCheckpoints {}) => false,
+            Err(err) => return Err(err),
+        };
 
         let reached_capacity_limit = if has_completed_cp {
             self.value_locked(store)? >= bitcoin_config.capacity_limit
@@ -600,6 +1513,12 @@ impl Bitcoin {
         let btc_height =
             querier.query_wasm_smart(config.light_client_contract.clone(), &HeaderHeight {})?;
 
+        // The next signatory set is built from the current registry, so a
+        // scheduled replacement must be in place before stepping.
+        let next_index = self.checkpoints.next_index(store)?;
+        apply_scheduled_validator_set(store, next_index)?;
+        self.reinstate_signers(querier, store)?;
+
         let pushed = self.checkpoints.maybe_step(
             env,
             store,
@@ -612,12 +1531,133 @@ impl Bitcoin {
         // TODO: remove expired outpoints from processed_outpoints
 
         if pushed {
+            self.attach_signed_reserve_outputs(store, btc_height, budget)?;
+            self.sweep_dust_deposits(env, store, budget)?;
             self.offline_signers(store)
         } else {
             Ok(vec![])
         }
     }
 
+    /// Attaches the reserve outputs of signed recovery transactions which can
+    /// be broadcast at `btc_height` to the building checkpoint, crediting the
+    /// fee pool with those kept from expired deposit claims and the depositors
+    /// of swept dust with theirs. Outputs which can't be attached, e.g. since
+    /// they were already relayed, are dropped. At most `budget` outputs are
+    /// attached, the rest wait for the next checkpoint.
+    pub fn attach_signed_reserve_outputs(
+        &mut self,
+        store: &mut dyn Storage,
+        btc_height: u32,
+        budget: &mut u32,
+    ) -> ContractResult<()> {
+        let outputs = SIGNED_RESERVE_OUTPUTS
+            .range(store, None, None, Order::Ascending)
+            .map(|item| item.map(|(_, output)| output))
+            .collect::<StdResult<Vec<_>>>()?;
+        let units_per_sat = self.config(store)?.units_per_sat;
+        for output in outputs {
+            if output.lock_time > btc_height {
+                continue;
+            }
+            if *budget == 0 {
+                break;
+            }
+            *budget -= 1;
+            SIGNED_RESERVE_OUTPUTS.remove(store, (&output.txid, output.vout));
+            let txid =
+                Txid::from_str(&output.txid).map_err(|err| ContractError::App(err.to_string()))?;
+            let attached = self.register_reserve_utxo(
+                store,
+                bitcoin::OutPoint::new(txid, output.vout),
+                output.amount,
+                output.sigset_index,
+            );
+            if attached.is_ok() && !output.credits.is_empty() {
+                let index = self.checkpoints.index(store);
+                let mut building = self.checkpoints.building(store)?;
+                for transfer in output.credits {
+                    building.insert_pending(transfer)?;
+                }
+                self.checkpoints.set(store, index, &building)?;
+            }
+            if attached.is_ok() && output.credit_fee_pool {
+                let amount = output
+                    .amount
+                    .checked_mul(units_per_sat)
+                    .and_then(|amount| i64::try_from(amount).ok())
+                    .ok_or_else(|| ContractError::App("Fee pool overflow".to_string()))?;
+                let fee_pool = self.fee_pool(store)?;
+                FEE_POOL.save(store, &(fee_pool + amount))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Consolidates queued dust deposits into the reserve with a sweep
+    /// transaction, once enough are queued and the building checkpoint's fee
+    /// rate is low enough. Each swept deposit takes one item of `budget`. The
+    /// depositors are credited once the swept output is attached.
+    fn sweep_dust_deposits(
+        &mut self,
+        env: &Env,
+        store: &mut dyn Storage,
+        budget: &mut u32,
+    ) -> ContractResult<()> {
+        let config = self.config(store)?;
+        if config.dust_deposit_threshold == 0 {
+            return Ok(());
+        }
+        let dust = DUST_DEPOSITS
+            .range(store, None, None, Order::Ascending)
+            .take(MAX_SWEEP_INPUTS.min(*budget as usize))
+            .collect::<StdResult<Vec<_>>>()?;
+        if dust.is_empty() || dust.len() < config.dust_sweep_min_inputs as usize {
+            return Ok(());
+        }
+
+        let mut inputs = vec![];
+        let mut lock_time = 0;
+        for (_, deposit) in &dust {
+            lock_time = lock_time.max(recovery_lock_time(deposit.btc_height, &config));
+            let txid = bitcoin::Txid::from_str(&deposit.txid)
+                .map_err(|err| ContractError::App(err.to_string()))?;
+            inputs.push(SweepInput {
+                outpoint: bitcoin::OutPoint::new(txid, deposit.vout),
+                value: deposit.value,
+                sigset: self.checkpoints.sigset(store, deposit.sigset_index)?,
+                dest: deposit.dest.clone(),
+                commitment_version: deposit.commitment_version,
+            });
+        }
+        let building = self.checkpoints.building(store)?;
+        let swept = self.recovery_txs.create_sweep_tx(
+            store,
+            SweepTxInput {
+                inputs,
+                new_sigset: &building.sigset,
+                threshold: self.checkpoints.config(store).sigset_threshold,
+                fee_rate: building.fee_rate,
+                max_fee_bps: config.dust_sweep_max_fee_bps,
+                dest: Dest::Address(env.contract.address.clone()),
+                lock_time,
+                denom: get_full_btc_denom(
+                    store,
+                    CONFIG.load(store)?.token_factory_contract.as_str(),
+                )?,
+                units_per_sat: config.units_per_sat,
+            },
+        )?;
+        if swept {
+            *budget -= dust.len() as u32;
+            for ((txid, vout), _) in dust {
+                DUST_DEPOSITS.remove(store, (&txid, vout));
+            }
+        }
+
+        Ok(())
+    }
+
     /// Returns the consensus keys of signers who have not submitted signatures
     /// for the last `max_offline_checkpoints` checkpoints.
     ///
@@ -672,13 +1712,67 @@ impl Bitcoin {
         store: &mut dyn Storage,
         cons_key: &ConsensusKey,
         addr: String,
+        now: u64,
     ) -> ContractResult<()> {
         VALIDATORS.remove(store, cons_key);
         SIGNERS.remove(store, &addr);
-        if let Some(xpub) = SIG_KEYS.may_load(store, cons_key)? {
+        let xpub = SIG_KEYS.may_load(store, cons_key)?;
+        if let Some(xpub) = &xpub {
             XPUBS.remove(store, &xpub.key.encode());
             SIG_KEYS.remove(store, cons_key);
         }
+        JAILED_SIGNERS.save(
+            store,
+            &addr,
+            &JailedSigner {
+                consensus_key: *cons_key,
+                xpub,
+                jailed_at: now,
+                reinstatement_requested: false,
+            },
+        )?;
+
+        Ok(())
+    }
+
+    /// Re-adds the jailed signers which requested reinstatement to the
+    /// validator registry, if they are still whitelisted and bonded. Signers
+    /// which are not have their request dropped.
+    fn reinstate_signers(
+        &mut self,
+        querier: &QuerierWrapper,
+        store: &mut dyn Storage,
+    ) -> ContractResult<()> {
+        let requested = JAILED_SIGNERS
+            .range(store, None, None, Order::Ascending)
+            .filter(|entry| {
+                entry
+                    .as_ref()
+                    .map_or(true, |(_, signer)| signer.reinstatement_requested)
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+        for (addr, mut signer) in requested {
+            let voting_power = if WHITELIST_VALIDATORS.has(store, Addr::unchecked(&addr)) {
+                bonded_voting_power(querier, &addr)?
+            } else {
+                None
+            };
+            let Some(voting_power) = voting_power else {
+                signer.reinstatement_requested = false;
+                JAILED_SIGNERS.save(store, &addr, &signer)?;
+                continue;
+            };
+
+            JAILED_SIGNERS.remove(store, &addr);
+            SIGNERS.save(store, &addr, &signer.consensus_key)?;
+            VALIDATORS.save(store, &signer.consensus_key, &(voting_power, addr.clone()))?;
+            if let Some(xpub) = signer.xpub {
+                // the key must be set again if another signer has taken it
+                let _ = self
+                    .signatory_keys
+                    .insert(store, signer.consensus_key, xpub);
+            }
+        }
 
         Ok(())
     }
@@ -691,7 +1785,7 @@ impl Bitcoin {
     pub fn take_pending_confirmed(
         &mut self,
         store: &mut dyn Storage,
-    ) -> ContractResult<Vec<Vec<(Dest, Coin)>>> {
+    ) -> ContractResult<Vec<Vec<PendingTransfer>>> {
         let unhandled_confirmed_cps = match self.checkpoints.unhandled_confirmed(store) {
             Err(_) => return Ok(vec![]),
             Ok(val) => val,
@@ -721,18 +1815,12 @@ impl Bitcoin {
     pub fn take_pending_completed(
         &mut self,
         store: &mut dyn Storage,
-    ) -> ContractResult<Vec<Vec<(Dest, Coin)>>> {
+    ) -> ContractResult<Vec<Vec<PendingTransfer>>> {
         let confirmed_dests = self.take_pending_confirmed(store)?;
 
         let last_completed_index = match self.checkpoints.last_completed_index(store) {
-            Err(err) => {
-                if let ContractError::App(err_str) = &err {
-                    if err_str == "No completed checkpoints yet" {
-                        return Ok(vec![]);
-                    }
-                }
-                return Err(err);
-            }
+            Err(ContractError::NoCompletedCheckpoints {}) => return Ok(vec![]),
+            Err(err) => return Err(err),
             Ok(val) => val,
         };
 
@@ -772,31 +1860,6 @@ impl Bitcoin {
         Ok(())
     }
 
-    // TODO: reward pool ...
-    // pub fn give_rewards(&mut self, store: &mut dyn Storage, amount: Uint128) -> ContractResult<()> {
-    //     let config = self.config(store)?;
-    //     let fee_pool = FEE_POOL.load(store)?;
-    //     if fee_pool < (config.fee_pool_target_balance * config.units_per_sat) as i64 {
-    //         let amount: u64 = amount.u128() as u64;
-    //         // TODO:// tokenfactory coin.burn();
-
-    //         let reward_amount = (amount as u128 * config.fee_pool_reward_split.0 as u128
-    //             / config.fee_pool_reward_split.1 as u128) as u64;
-    //         let fee_amount = amount - reward_amount;
-
-    //         // self.reward_pool.give(Coin::mint(reward_amount))?;
-    //         self.reward_pool.amount = self.reward_pool.amount.checked_sub(reward_amount.into())?;
-    //         self.give_miner_fee(store, fee_amount.into())?;
-
-    //         assert_eq!(reward_amount + fee_amount, amount);
-    //     } else {
-    //         // self.reward_pool.give(coin)?;
-    //         self.reward_pool.amount = self.reward_pool.amount.checked_sub(amount)?;
-    //     }
-
-    //     Ok(())
-    // }
-
     // pub fn give_funding_to_fee_pool(
     //     &mut self,
     //     store: &mut dyn Storage,
@@ -0,0 +1,97 @@
+use bitcoin::hashes::{sha256, Hash};
+use common_bitcoin::error::{ContractError, ContractResult};
+use cosmwasm_std::{Binary, Storage};
+
+use crate::state::{APP_DATA, APP_DATA_INTERNAL_COMMITMENTS};
+
+/// Commits the third-party data queued for the checkpoint at `index` along
+/// with the bridge's own timestamping commitment, returning the data to put
+/// in the checkpoint's `OP_RETURN` output.
+///
+/// Without any third-party data the internal commitment is used as is.
+/// Otherwise the output commits to the Merkle root of the SHA-256 of the
+/// internal commitment followed by each data hash, in the order they were
+/// committed.
+pub fn commit_app_data(
+    store: &mut dyn Storage,
+    index: u32,
+    internal_commitment: Vec<u8>,
+) -> ContractResult<Vec<u8>> {
+    let data = APP_DATA.may_load(store, index)?.unwrap_or_default();
+    if data.is_empty() {
+        return Ok(internal_commitment);
+    }
+    APP_DATA_INTERNAL_COMMITMENTS.save(store, index, &Binary::from(internal_commitment.clone()))?;
+    Ok(merkle_root(&app_data_leaves(&internal_commitment, &data)?).to_vec())
+}
+
+/// The Merkle leaves of a checkpoint's `OP_RETURN` commitment.
+pub fn app_data_leaves(
+    internal_commitment: &[u8],
+    data: &[Binary],
+) -> ContractResult<Vec<[u8; 32]>> {
+    let mut leaves = vec![sha256::Hash::hash(internal_commitment).to_byte_array()];
+    for item in data {
+        leaves.push(
+            item.to_vec()
+                .try_into()
+                .map_err(|_| ContractError::App("App data must be 32 bytes".to_string()))?,
+        );
+    }
+    Ok(leaves)
+}
+
+pub fn hash_pair(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut preimage = left.to_vec();
+    preimage.extend_from_slice(right);
+    sha256::Hash::hash(&preimage).to_byte_array()
+}
+
+/// Hashes one level of the tree, pairing the last node with itself if the
+/// level has an odd length.
+fn next_level(level: &[[u8; 32]]) -> Vec<[u8; 32]> {
+    level
+        .chunks(2)
+        .map(|pair| hash_pair(&pair[0], pair.get(1).unwrap_or(&pair[0])))
+        .collect()
+}
+
+pub fn merkle_root(leaves: &[[u8; 32]]) -> [u8; 32] {
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        level = next_level(&level);
+    }
+    level[0]
+}
+
+/// The sibling hashes from the leaf at `index` up to the root.
+pub fn merkle_branch(leaves: &[[u8; 32]], mut index: usize) -> Vec<[u8; 32]> {
+    let mut branch = vec![];
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        let sibling = index ^ 1;
+        branch.push(*level.get(sibling).unwrap_or(&level[index]));
+        level = next_level(&level);
+        index /= 2;
+    }
+    branch
+}
+
+#[cfg(test)]
+pub fn verify_merkle_branch(
+    leaf: [u8; 32],
+    mut index: usize,
+    branch: &[[u8; 32]],
+    root: [u8; 32],
+) -> bool {
+    let mut hash = leaf;
+    for sibling in branch {
+        hash = if index % 2 == 0 {
+            hash_pair(&hash, sibling)
+        } else {
+            hash_pair(sibling, &hash)
+        };
+        index /= 2;
+    }
+    hash == root
+}
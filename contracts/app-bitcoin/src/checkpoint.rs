@@ -1,27 +1,46 @@
 use super::{
     signatory::SignatorySet,
-    threshold_sig::{Signature, ThresholdSig},
+    threshold_sig::{SigScheme, Signature, ThresholdSig},
 };
+use crate::app_data::commit_app_data;
+use crate::helper::bridge_time;
 use crate::state::BUILDING_INDEX;
 use crate::{
-    constants::DEFAULT_FEE_RATE,
-    state::{CHECKPOINT_CONFIG, CONFIRMED_INDEX, FEE_POOL, FIRST_UNHANDLED_CONFIRMED_INDEX},
+    constants::{DEFAULT_FEE_RATE, MAX_COMPACTED_CHECKPOINTS},
+    state::{
+        record_checkpoint_txid, record_fee_rate_change, BRIDGE_MIGRATION, CHECKPOINT_CONFIG,
+        CHECKPOINT_INDEX_TXIDS, COMPACTED_INDEX, CONFIRMED_INDEX, FAST_WITHDRAWAL_CHECKPOINT,
+        FEE_POOL, FEE_RATE_HISTORY, FIRST_UNHANDLED_CONFIRMED_INDEX, FORCED_CHECKPOINT_PUSH,
+        RECLAIMED_SIGNATURE_BYTES, SIGSET_BTC_HEIGHTS,
+    },
 };
 use crate::{
-    interface::{BitcoinConfig, CheckpointConfig, Dest},
-    state::CHECKPOINTS,
+    interface::{
+        BitcoinConfig, CheckpointConfig, CheckpointSchedule, FeeCoverage, FeeRateChange,
+        FeeRateChangeCause, PendingTransfer, SigningDuty, SigningDutySource, SigningProgress,
+    },
+    state::{CHECKPOINTS, SIGSET_ARCHIVE},
 };
+use bitcoin::bip32::{ChildNumber, DerivationPath};
 use bitcoin::hashes::Hash;
-use bitcoin::{blockdata::transaction::EcdsaSighashType, Sequence, Transaction, TxIn, TxOut};
+use bitcoin::psbt::Psbt;
+use bitcoin::script::PushBytes;
+use bitcoin::secp256k1::ecdsa;
+use bitcoin::sighash::{EcdsaSighashType, SighashCache};
+use bitcoin::{
+    absolute, transaction, Amount, ScriptBuf, Sequence, Transaction, TxIn, TxOut, Txid, Witness,
+};
 use common_bitcoin::{
     adapter::Adapter,
+    address::dust_threshold,
     error::{ContractError, ContractResult},
-    xpub::Xpub,
+    xpub::{DerivedPubkeys, Xpub},
 };
 use cosmwasm_schema::serde::{Deserialize, Serialize};
 use cosmwasm_schema::{cw_serde, schemars::JsonSchema};
-use cosmwasm_std::{Api, Coin, Env, Storage};
+use cosmwasm_std::{to_json_vec, Api, Binary, Env, Storage};
 use derive_more::{Deref, DerefMut};
+use light_client_bitcoin::constants::TARGET_SPACING;
 
 /// The status of a checkpoint. Checkpoints start as `Building`, and eventually
 /// advance through the three states.
@@ -44,6 +63,28 @@ pub enum CheckpointStatus {
     Complete,
 }
 
+/// The sighash type an input is signed with.
+#[cw_serde]
+#[derive(Default, Copy)]
+pub enum InputSighashType {
+    /// The signature commits to all inputs and outputs.
+    #[default]
+    All,
+    /// The signature commits to all outputs but only its own input, so other
+    /// inputs can be added to the transaction without invalidating it (e.g.
+    /// to top up the miner fee).
+    AllPlusAnyoneCanPay,
+}
+
+impl From<InputSighashType> for EcdsaSighashType {
+    fn from(sighash_type: InputSighashType) -> Self {
+        match sighash_type {
+            InputSighashType::All => EcdsaSighashType::All,
+            InputSighashType::AllPlusAnyoneCanPay => EcdsaSighashType::AllPlusAnyoneCanPay,
+        }
+    }
+}
+
 /// An input to a Bitcoin transaction - possibly in an unsigned state.
 ///
 /// This structure contains the necessary data for signing an input, and once
@@ -59,12 +100,12 @@ pub struct Input {
     /// The script of the output being spent by this input. In practice, this
     /// will be a pay-to-witness-script-hash (P2WSH) script, containing the hash
     /// of the script in the `redeem_script` field.
-    pub script_pubkey: Adapter<bitcoin::Script>,
+    pub script_pubkey: Adapter<ScriptBuf>,
 
     /// The redeem script which `script_pubkey` contains the hash of, supplied
     /// in the witness of the input when spending. In practice, this will
     /// represent a multisig tied to the associated signatory set.
-    pub redeem_script: Adapter<bitcoin::Script>,
+    pub redeem_script: Adapter<ScriptBuf>,
 
     /// The index of the signatory set which this input is associated with.
     pub sigset_index: u32,
@@ -86,23 +127,38 @@ pub struct Input {
     /// coordinate to submit their signatures, and starts out with no
     /// signatures.
     pub signatures: ThresholdSig,
+
+    /// The sighash type the signatories sign this input with.
+    #[serde(default)]
+    pub sighash_type: InputSighashType,
+
+    /// The final witness of the input, kept instead of the signature shares
+    /// once the input has been compacted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub witness: Option<Vec<Binary>>,
 }
 
 impl Input {
     /// Converts the `Input` to a `bitcoin::TxIn`, useful when constructing an
     /// actual Bitcoin transaction to be broadcast.
     pub fn to_txin(&self) -> ContractResult<TxIn> {
-        let mut witness = self.signatures.to_witness()?;
-        if self.signatures.signed() {
-            witness.push(vec![0]);
-            witness.push(self.redeem_script.to_bytes());
-        }
+        let witness = match &self.witness {
+            Some(witness) => witness.iter().map(|item| item.to_vec()).collect(),
+            None => {
+                let mut witness = self.signatures.to_witness(self.sighash_type.into())?;
+                if self.signatures.signed() {
+                    witness.push(vec![0]);
+                    witness.push(self.redeem_script.to_bytes());
+                }
+                witness
+            }
+        };
 
         Ok(bitcoin::TxIn {
             previous_output: *self.prevout,
-            script_sig: bitcoin::Script::new(),
-            sequence: Sequence(u32::MAX),
-            witness: bitcoin::Witness::from_vec(witness),
+            script_sig: ScriptBuf::new(),
+            sequence: Sequence::MAX,
+            witness: Witness::from_slice(&witness),
         })
     }
 
@@ -127,9 +183,27 @@ impl Input {
             amount,
             est_witness_vsize: sigset.est_witness_vsize(),
             signatures: ThresholdSig::from_sigset(sigset),
+            sighash_type: InputSighashType::default(),
+            witness: None,
         })
     }
 
+    /// Replaces the signature shares of a fully-signed input with its final
+    /// witness. Signers can no longer be told apart afterwards, so this must
+    /// only be done once the input's checkpoint is no longer used to detect
+    /// offline signers.
+    pub fn compact(&mut self) -> ContractResult<()> {
+        if self.witness.is_some() || !self.signatures.signed() {
+            return Ok(());
+        }
+
+        let witness = self.to_txin()?.witness.to_vec();
+        self.witness = Some(witness.into_iter().map(Binary::from).collect());
+        self.signatures.sigs.clear();
+
+        Ok(())
+    }
+
     /// The estimated size of the input, including the worst-case size of the
     /// witness once fully signed, in virtual bytes.
     pub fn est_vsize(&self) -> u64 {
@@ -165,12 +239,19 @@ impl BitcoinTx {
     /// Converts the `BitcoinTx` to a `bitcoin::Transaction`.
     pub fn to_bitcoin_tx(&self) -> ContractResult<Transaction> {
         Ok(bitcoin::Transaction {
-            version: 1,
-            lock_time: bitcoin::PackedLockTime(self.lock_time),
+            version: transaction::Version::ONE,
+            lock_time: absolute::LockTime::from_consensus(self.lock_time),
             input: self
                 .input
                 .iter()
-                .map(|input| input.to_txin())
+                .map(|input| {
+                    let mut txin = input.to_txin()?;
+                    // the lock time is only enforced if an input is not final
+                    if self.lock_time != 0 {
+                        txin.sequence = Sequence::ENABLE_LOCKTIME_NO_RBF;
+                    }
+                    Ok(txin)
+                })
                 .collect::<ContractResult<_>>()?,
             output: self
                 .output
@@ -180,6 +261,42 @@ impl BitcoinTx {
         })
     }
 
+    /// The sighashes of the inputs still needing a signature from `xpub`, or
+    /// `None` if there are none.
+    pub fn signing_duty(
+        &self,
+        pubkeys: &mut DerivedPubkeys,
+        source: SigningDutySource,
+        age: Option<u64>,
+    ) -> ContractResult<Option<SigningDuty>> {
+        let mut sighashes = vec![];
+        let mut remaining_power = 0;
+        for input in &self.input {
+            let pubkey = pubkeys.get(input.sigset_index)?;
+            if input.signatures.needs_sig(pubkey.into()) {
+                sighashes.push((
+                    Binary::from(input.signatures.message().to_vec()),
+                    input.sigset_index,
+                ));
+            }
+            remaining_power = remaining_power.max(
+                input
+                    .signatures
+                    .threshold
+                    .saturating_sub(input.signatures.signed),
+            );
+        }
+        if sighashes.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(SigningDuty {
+            source,
+            age,
+            remaining_power,
+            sighashes,
+        }))
+    }
+
     /// Creates a new `BitcoinTx` with the given locktime, and no inputs or
     /// outputs.
     pub fn with_lock_time(lock_time: u32) -> Self {
@@ -218,7 +335,26 @@ impl BitcoinTx {
     pub fn value(&self) -> ContractResult<u64> {
         self.output
             .iter()
-            .try_fold(0, |sum: u64, out| Ok(sum + out.value))
+            .try_fold(0, |sum: u64, out| Ok(sum + out.value.to_sat()))
+    }
+
+    /// Calculates the sighashes of all inputs and populates their signing
+    /// states with them, sharing the sighash midstate between inputs. Like
+    /// `populate_input_sig_message`, this should only be used once the
+    /// transaction is finalized.
+    pub fn populate_sig_messages(&mut self) -> ContractResult<()> {
+        let bitcoin_tx = self.to_bitcoin_tx()?;
+        let mut sc = SighashCache::new(&bitcoin_tx);
+        for (i, input) in self.input.iter_mut().enumerate() {
+            let sighash = sc.p2wsh_signature_hash(
+                i,
+                &input.redeem_script,
+                Amount::from_sat(input.amount),
+                input.sighash_type.into(),
+            )?;
+            input.signatures.set_message(sighash.to_byte_array());
+        }
+        Ok(())
     }
 
     /// Calculates the sighash to be signed for the given input index, and
@@ -227,20 +363,20 @@ impl BitcoinTx {
     /// coordination of signing will begin.
     pub fn populate_input_sig_message(&mut self, input_index: usize) -> ContractResult<()> {
         let bitcoin_tx = self.to_bitcoin_tx()?;
-        let mut sc = bitcoin::util::sighash::SighashCache::new(&bitcoin_tx);
+        let mut sc = SighashCache::new(&bitcoin_tx);
         let input = self
             .input
             .get_mut(input_index)
             .ok_or(ContractError::InputIndexOutOfBounds(input_index))?;
 
-        let sighash = sc.segwit_signature_hash(
+        let sighash = sc.p2wsh_signature_hash(
             input_index,
             &input.redeem_script,
-            input.amount,
-            EcdsaSighashType::All,
+            Amount::from_sat(input.amount),
+            input.sighash_type.into(),
         )?;
 
-        input.signatures.set_message(sighash.into_inner());
+        input.signatures.set_message(sighash.to_byte_array());
 
         Ok(())
     }
@@ -277,8 +413,8 @@ impl BitcoinTx {
             // Remove any outputs which are too small to pay the threshold.
             let mut min_output = u64::MAX;
             self.output.retain(|output| {
-                let dust_value = output.script_pubkey.dust_value().to_sat();
-                let adjusted_output = output.value.saturating_sub(dust_value);
+                let dust_value = dust_threshold(&output.script_pubkey);
+                let adjusted_output = output.value.to_sat().saturating_sub(dust_value);
                 if adjusted_output < min_output {
                     min_output = adjusted_output;
                 }
@@ -302,7 +438,7 @@ impl BitcoinTx {
 
         // Deduct the final fee share from each remaining output.
         for output in self.output.iter_mut() {
-            output.value -= threshold;
+            output.value -= Amount::from_sat(threshold);
         }
 
         Ok(())
@@ -395,7 +531,7 @@ pub struct Checkpoint {
     /// disbursal.
     ///
     /// These transfers can be initiated by a simple nBTC send or by a deposit.    
-    pub pending: Vec<(Dest, Coin)>,
+    pub pending: Vec<PendingTransfer>,
 
     /// The batches of transactions in the checkpoint, to each be signed
     /// atomically, in order. Currently we have only one batch which is
@@ -425,10 +561,75 @@ pub struct Checkpoint {
 
     pub fees_collected: u64,
 
+    /// The part of the miner fee, in satoshis, paid from the surplus of the
+    /// fee pool because `fees_collected` fell short of it.
+    #[serde(default)]
+    pub fee_pool_top_up: u64,
+
+    /// The part of the fees quoted to the withdrawals paid by this checkpoint
+    /// beyond the final miner cost of their outputs, in satoshis, which stays
+    /// in the fee pool. Set once the checkpoint transaction is built.
+    #[serde(default)]
+    pub withdrawal_fee_surplus: u64,
+
+    /// The fees quoted to the withdrawals queued in this checkpoint, snapshotted
+    /// when each withdrawal was requested.
+    #[serde(default)]
+    pub withdrawal_fees: Vec<WithdrawalFee>,
+
     /// The signatory set associated with the checkpoint. Note that deposits to
     /// slightly older signatory sets can still be processed in this checkpoint,
     /// but the reserve output will be paid to the latest signatory set.
     pub sigset: SignatorySet,
+
+    /// A checkpoint transaction signed off-chain and accepted by a
+    /// supermajority of validators with `ForceCompleteCheckpoint`, used in
+    /// place of the one assembled from the collected signatures.
+    #[serde(default)]
+    pub forced_tx: Option<Adapter<bitcoin::Transaction>>,
+
+    /// The signature scheme of the checkpoint's signatory set, which its
+    /// inputs are signed with.
+    #[serde(default)]
+    pub sig_scheme: SigScheme,
+}
+
+/// The miner fee quoted to a withdrawal at the time it was requested.
+///
+/// The quote is binding: the user pays exactly `fee` (in units) into the fee
+/// pool when withdrawing, even if the withdrawal ends up being paid out in a
+/// later checkpoint with a different fee rate. Once the paying checkpoint is
+/// built, the surplus over the final miner cost of the output is reconciled
+/// into its `withdrawal_fee_surplus`, see `Checkpoint::settle_fees`.
+#[cw_serde]
+pub struct WithdrawalFee {
+    pub script_pubkey: Adapter<ScriptBuf>,
+    /// The withdrawn value, in satoshis.
+    pub value: u64,
+    /// The fee rate of the building checkpoint when the fee was quoted.
+    pub fee_rate: u64,
+    /// The estimated virtual size of the withdrawal output.
+    pub est_vsize: u64,
+    /// The fee charged to the user, in units.
+    pub fee: u64,
+}
+
+/// Where a withdrawal was added to the `Building` checkpoint.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WithdrawalPosition {
+    pub checkpoint_index: u32,
+    /// The index of the withdrawal's output in the checkpoint transaction.
+    /// Withdrawals merged into an earlier output share its index.
+    pub output_index: u32,
+    /// The index of the withdrawal's fee in the checkpoint's
+    /// `withdrawal_fees`.
+    pub fee_index: u32,
+}
+
+impl WithdrawalFee {
+    fn matches(&self, output: &Output) -> bool {
+        self.value == output.value.to_sat() && *self.script_pubkey == output.script_pubkey
+    }
 }
 
 impl Checkpoint {
@@ -444,10 +645,15 @@ impl Checkpoint {
             fee_rate: DEFAULT_FEE_RATE,
             signed_at_btc_height: None,
             deposits_enabled: true,
-            sigset,
             fees_collected: 0,
+            fee_pool_top_up: 0,
+            withdrawal_fee_surplus: 0,
+            withdrawal_fees: vec![],
             pending: vec![],
             batches: vec![],
+            forced_tx: None,
+            sig_scheme: sigset.sig_scheme(),
+            sigset,
         };
 
         let checkpoint_tx = BitcoinTx::default();
@@ -458,11 +664,87 @@ impl Checkpoint {
         Ok(checkpoint)
     }
 
+    /// Compacts the signature shares of every input of the checkpoint's
+    /// transactions, returning the number of bytes of stored state reclaimed.
+    pub fn compact(&mut self) -> ContractResult<u64> {
+        let size_before = to_json_vec(self)?.len() as u64;
+        for batch in self.batches.iter_mut() {
+            for tx in batch.iter_mut() {
+                for input in tx.input.iter_mut() {
+                    input.compact()?;
+                }
+            }
+        }
+        let size_after = to_json_vec(self)?.len() as u64;
+
+        Ok(size_before.saturating_sub(size_after))
+    }
+
     /// Changes the status of the checkpoint to `Complete`.
     pub fn advance(&mut self) {
         self.status = CheckpointStatus::Complete;
     }
 
+    /// Removes and returns the fee snapshots of the given outputs, used when
+    /// withdrawals are moved to the next checkpoint.
+    ///
+    /// An output merging several withdrawals to the same script takes their
+    /// snapshots until their values add up to its value.
+    pub fn take_withdrawal_fees(&mut self, outputs: &[Output]) -> Vec<WithdrawalFee> {
+        let mut taken = vec![];
+        for output in outputs {
+            if let Some(pos) = self
+                .withdrawal_fees
+                .iter()
+                .position(|snapshot| snapshot.matches(output))
+            {
+                taken.push(self.withdrawal_fees.remove(pos));
+                continue;
+            }
+            let mut remaining = output.value.to_sat();
+            while let Some(pos) = self.withdrawal_fees.iter().position(|snapshot| {
+                *snapshot.script_pubkey == output.script_pubkey && snapshot.value <= remaining
+            }) {
+                let snapshot = self.withdrawal_fees.remove(pos);
+                remaining -= snapshot.value;
+                taken.push(snapshot);
+            }
+        }
+        taken
+    }
+
+    /// Settles the fees of the checkpoint once its transaction is built with
+    /// `fees_paid` satoshis of miner fees, returning the fee snapshots of the
+    /// withdrawals in `excess_outputs` and the fees they paid, in satoshis,
+    /// which both move to the next checkpoint.
+    ///
+    /// The fees quoted to the withdrawals paid here are reconciled with the
+    /// miner cost of their outputs at the checkpoint's fee rate, the surplus
+    /// being recorded in `withdrawal_fee_surplus`.
+    pub fn settle_fees(
+        &mut self,
+        fees_paid: u64,
+        excess_outputs: &[Output],
+        units_per_sat: u64,
+    ) -> (Vec<WithdrawalFee>, u64) {
+        let excess_fees = self.take_withdrawal_fees(excess_outputs);
+        let moved = excess_fees
+            .iter()
+            .map(|snapshot| snapshot.fee / units_per_sat)
+            .sum::<u64>()
+            .min(self.fees_collected);
+        self.fees_collected -= moved;
+        self.fee_pool_top_up = fees_paid.saturating_sub(self.fees_collected);
+        self.withdrawal_fee_surplus = self
+            .withdrawal_fees
+            .iter()
+            .map(|snapshot| {
+                (snapshot.fee / units_per_sat).saturating_sub(snapshot.est_vsize * self.fee_rate)
+            })
+            .sum();
+        (excess_fees, moved)
+    }
+
     /// Processes a batch of signatures from a signatory, applying them to the
     /// inputs of transaction batches which are ready to be signed.
     ///
@@ -478,15 +760,26 @@ impl Checkpoint {
     /// present in the signatory set, for all transactions of all batches ready
     /// to be signed. If the signatory provides more or less signatures than
     /// expected, `sign()` will return an error.
+    ///
+    /// Alternatively, signatories with limited signing throughput can submit
+    /// their signatures in chunks. With a `chunk_offset`, the signatures are
+    /// applied to a contiguous range of the inputs the signatory is part of,
+    /// starting at that position and counting inputs already signed. Chunks
+    /// accumulate until the signatory has signed all of its inputs.
     fn sign(
         &mut self,
         api: &dyn Api,
         xpub: &Xpub,
         sigs: Vec<Signature>,
+        chunk_offset: Option<u32>,
         btc_height: u32,
     ) -> ContractResult<()> {
         let cp_was_signed = self.signed();
+        let mut pubkeys = xpub.derived_pubkeys();
         let mut sig_index = 0;
+        let chunk = chunk_offset.map(|offset| offset as usize..offset as usize + sigs.len());
+        // The position of the input among the inputs the signatory is part of
+        let mut position = 0;
 
         // Iterate over all batches in the checkpoint, breaking once iterating
         // to a batch which is not ready to be signed.
@@ -500,12 +793,23 @@ impl Checkpoint {
                 // Iterate over all inputs in the transaction.
                 for k in 0..tx.input.len() {
                     let input = &mut tx.input[k];
-                    let pubkey = xpub.derive_pubkey(input.sigset_index)?;
-
-                    // Skip input if either the signatory is not part of this
-                    // input's signatory set, or the signatory has already
-                    // submitted a signature for this input.
-                    if !input.signatures.needs_sig(pubkey.into()) {
+                    let pubkey = pubkeys.get(input.sigset_index)?;
+
+                    if let Some(chunk) = &chunk {
+                        // Skip input if the signatory is not part of this
+                        // input's signatory set or it is outside the chunk.
+                        // Signing an input twice fails below.
+                        if !input.signatures.contains_key(pubkey.into()) {
+                            continue;
+                        }
+                        position += 1;
+                        if !chunk.contains(&(position - 1)) {
+                            continue;
+                        }
+                    } else if !input.signatures.needs_sig(pubkey.into()) {
+                        // Skip input if either the signatory is not part of
+                        // this input's signatory set, or the signatory has
+                        // already submitted a signature for this input.
                         continue;
                     }
 
@@ -513,9 +817,7 @@ impl Checkpoint {
                     // signatory supplied less signatures than we require from
                     // them.
                     if sig_index >= sigs.len() {
-                        return Err(ContractError::Checkpoint(
-                            "Not enough signatures supplied".into(),
-                        ));
+                        return Err(ContractError::NotEnoughSignatures {});
                     }
                     let sig = &sigs[sig_index];
                     sig_index += 1;
@@ -549,9 +851,7 @@ impl Checkpoint {
         // Error if there are remaining supplied signatures - the signatory
         // supplied more signatures than we require from them.
         if sig_index != sigs.len() {
-            return Err(ContractError::Checkpoint(
-                "Excess signatures supplied".into(),
-            ));
+            return Err(ContractError::ExcessSignatures {});
         }
 
         // If these signatures made the checkpoint fully signed, record the
@@ -565,6 +865,9 @@ impl Checkpoint {
 
     /// Gets the checkpoint transaction as a `bitcoin::Transaction`.    
     pub fn checkpoint_tx(&self) -> ContractResult<Adapter<bitcoin::Transaction>> {
+        if let Some(forced_tx) = &self.forced_tx {
+            return Ok(forced_tx.clone());
+        }
         Ok(Adapter::new(
             self.batches[BatchType::Checkpoint]
                 .last()
@@ -573,6 +876,107 @@ impl Checkpoint {
         ))
     }
 
+    /// Exports the checkpoint transaction as a BIP-174 PSBT, including the
+    /// witness UTXO and redeem script of each input along with all signatures
+    /// collected so far.
+    ///
+    /// Signatory keys which can be derived from one of the given `xpubs` are
+    /// annotated with their BIP-32 derivation (the xpub fingerprint and the
+    /// sigset index as the child number), so external signers can find the
+    /// key they need to sign with.
+    pub fn checkpoint_psbt(&self, xpubs: &[Xpub]) -> ContractResult<Psbt> {
+        let tx = &self.batches[BatchType::Checkpoint][0];
+        let mut derived: Vec<_> = xpubs.iter().map(Xpub::derived_pubkeys).collect();
+        let mut unsigned_tx = tx.to_bitcoin_tx()?;
+        for txin in unsigned_tx.input.iter_mut() {
+            txin.witness = Witness::default();
+        }
+
+        let mut psbt = Psbt::from_unsigned_tx(unsigned_tx)
+            .map_err(|err| ContractError::Checkpoint(format!("Invalid PSBT: {}", err)))?;
+
+        for (input, psbt_input) in tx.input.iter().zip(psbt.inputs.iter_mut()) {
+            psbt_input.witness_utxo = Some(TxOut {
+                value: Amount::from_sat(input.amount),
+                script_pubkey: (*input.script_pubkey).clone(),
+            });
+            psbt_input.witness_script = Some((*input.redeem_script).clone());
+            psbt_input.sighash_type = Some(EcdsaSighashType::from(input.sighash_type).into());
+
+            for (xpub, pubkeys) in xpubs.iter().zip(derived.iter_mut()) {
+                let pubkey = pubkeys.get(input.sigset_index)?;
+                if !input.signatures.contains_key(pubkey.into()) {
+                    continue;
+                }
+                let path =
+                    DerivationPath::from(vec![ChildNumber::from_normal_idx(input.sigset_index)?]);
+                psbt_input
+                    .bip32_derivation
+                    .insert(pubkey, (xpub.fingerprint(), path));
+            }
+
+            for (pubkey, sig) in input.signatures.sigs() {
+                let pubkey = bitcoin::PublicKey::from_slice(pubkey.as_slice())
+                    .map_err(|err| ContractError::Checkpoint(err.to_string()))?;
+                let sig = ecdsa::Signature::from_compact(&sig.0)?;
+                psbt_input.partial_sigs.insert(
+                    pubkey,
+                    bitcoin::ecdsa::Signature {
+                        sig,
+                        hash_ty: input.sighash_type.into(),
+                    },
+                );
+            }
+        }
+
+        Ok(psbt)
+    }
+
+    /// Extracts the signatures the signatory with the given xpub still owes
+    /// for this checkpoint from a PSBT of the checkpoint transaction, in the
+    /// order expected by `sign`.
+    ///
+    /// Only the checkpoint transaction is covered by the PSBT, so this will
+    /// fail if the signatory also needs to sign transactions in other batches.
+    pub fn psbt_sigs(&self, xpub: &Xpub, psbt: &Psbt) -> ContractResult<Vec<Signature>> {
+        let tx = &self.batches[BatchType::Checkpoint][0];
+        if psbt.unsigned_tx.txid() != tx.txid()? {
+            return Err(ContractError::Checkpoint(
+                "PSBT does not match checkpoint transaction".into(),
+            ));
+        }
+
+        let mut pubkeys = xpub.derived_pubkeys();
+        let mut sigs = vec![];
+        for (input, psbt_input) in tx.input.iter().zip(psbt.inputs.iter()) {
+            let pubkey = pubkeys.get(input.sigset_index)?;
+            if !input.signatures.needs_sig(pubkey.into()) {
+                continue;
+            }
+
+            let sig = psbt_input
+                .partial_sigs
+                .get(&bitcoin::PublicKey::new(pubkey))
+                .ok_or_else(|| {
+                    ContractError::Checkpoint("Missing partial signature in PSBT".into())
+                })?;
+            if sig.hash_ty != EcdsaSighashType::from(input.sighash_type) {
+                return Err(ContractError::Checkpoint(
+                    "Partial signature uses the wrong sighash type".into(),
+                ));
+            }
+            sigs.push(Signature(sig.sig.serialize_compact().to_vec()));
+        }
+
+        if sigs.len() != self.to_sign(xpub)?.len() {
+            return Err(ContractError::Checkpoint(
+                "Signatory must sign the other batches before the checkpoint transaction".into(),
+            ));
+        }
+
+        Ok(sigs)
+    }
+
     /// Gets the output containing the reserve funds for the checkpoint, the
     /// "reserve output". This output is owned by the latest signatory set, and
     /// is spent by the suceeding checkpoint transaction.
@@ -597,12 +1001,13 @@ impl Checkpoint {
     /// sigset_index)` - the sighash to be signed and the index of the signatory
     /// set associated with the input.    
     pub fn to_sign(&self, xpub: &Xpub) -> ContractResult<Vec<([u8; 32], u32)>> {
+        let mut pubkeys = xpub.derived_pubkeys();
         let mut msgs = vec![];
 
         for batch in &self.batches {
             for tx in &batch.batch {
                 for input in &tx.input {
-                    let pubkey = xpub.derive_pubkey(input.sigset_index)?;
+                    let pubkey = pubkeys.get(input.sigset_index)?;
                     if input.signatures.needs_sig(pubkey.into()) {
                         msgs.push((input.signatures.message(), input.sigset_index));
                     }
@@ -616,6 +1021,57 @@ impl Checkpoint {
         Ok(msgs)
     }
 
+    /// How many of the inputs ready to be signed which `xpub` is part of it
+    /// has signed.
+    pub fn signing_progress(&self, xpub: &Xpub) -> ContractResult<SigningProgress> {
+        let mut pubkeys = xpub.derived_pubkeys();
+        let mut progress = SigningProgress {
+            signed: 0,
+            total: 0,
+        };
+        for batch in &self.batches {
+            for tx in &batch.batch {
+                for input in &tx.input {
+                    let pubkey = pubkeys.get(input.sigset_index)?;
+                    if !input.signatures.contains_key(pubkey.into()) {
+                        continue;
+                    }
+                    progress.total += 1;
+                    if !input.signatures.needs_sig(pubkey.into()) {
+                        progress.signed += 1;
+                    }
+                }
+            }
+            if !batch.signed() {
+                break;
+            }
+        }
+        Ok(progress)
+    }
+
+    /// The signatures owed by `xpub` on the transactions of the first batch
+    /// which is not fully signed.
+    pub fn duties(&self, index: u32, xpub: &Xpub, age: u64) -> ContractResult<Vec<SigningDuty>> {
+        let mut pubkeys = xpub.derived_pubkeys();
+        let mut duties = vec![];
+        for (batch_index, batch) in self.batches.iter().enumerate() {
+            for (tx_index, tx) in batch.batch.iter().enumerate() {
+                let source = SigningDutySource::Checkpoint {
+                    index,
+                    batch_index: batch_index as u32,
+                    tx_index: tx_index as u32,
+                };
+                if let Some(duty) = tx.signing_duty(&mut pubkeys, source, Some(age))? {
+                    duties.push(duty);
+                }
+            }
+            if !batch.signed() {
+                break;
+            }
+        }
+        Ok(duties)
+    }
+
     pub fn to_single_sign(
         &self,
         xpub: &Xpub,
@@ -684,6 +1140,18 @@ impl Checkpoint {
         self.signed_batches() == self.batches.len()
     }
 
+    /// Whether the checkpoint transaction has filled up to the configured
+    /// `capacity_advance_ratio` of its maximum inputs or outputs.
+    pub fn reached_capacity(&self, config: &CheckpointConfig) -> bool {
+        let (numerator, denominator) = match config.capacity_advance_ratio {
+            Some(ratio) => ratio,
+            None => return false,
+        };
+        let tx = &self.batches[BatchType::Checkpoint][0];
+        tx.input.len() as u64 * denominator >= config.max_inputs * numerator
+            || tx.output.len() as u64 * denominator >= config.max_outputs * numerator
+    }
+
     pub fn checkpoint_tx_miner_fees(&self) -> ContractResult<u64> {
         let mut fees = 0;
 
@@ -695,7 +1163,7 @@ impl Checkpoint {
         }
 
         for output in &tx.output {
-            fees -= output.value;
+            fees -= output.value.to_sat();
         }
 
         Ok(fees)
@@ -765,7 +1233,7 @@ impl Checkpoint {
                 .ok_or(ContractError::Checkpoint(
                     "Cannot get checkpoint tx output".into(),
                 ))?;
-            out_amount += output.value;
+            out_amount += output.value.to_sat();
         }
         Ok((in_amount, out_amount))
     }
@@ -778,7 +1246,7 @@ impl Checkpoint {
         // The reserve output is the first output of the checkpoint tx, and
         // contains all funds held in reserve by the network.
         let reserve_out = bitcoin::TxOut {
-            value: 0, // will be updated after counting ins/outs and fees
+            value: Amount::ZERO, // will be updated after counting ins/outs and fees
             script_pubkey: self.sigset.output_script(&[0u8], config.sigset_threshold)?,
         };
 
@@ -786,9 +1254,11 @@ impl Checkpoint {
         // checkpoint tx, and contains a commitment to some given data, which
         // will be included on the Bitcoin blockchain as `OP_RETURN` data, now
         // timestamped by Bitcoin's proof-of-work security.
+        let timestamping_commitment = <&PushBytes>::try_from(timestamping_commitment)
+            .map_err(|err| ContractError::Checkpoint(err.to_string()))?;
         let timestamping_commitment_out = bitcoin::TxOut {
-            value: 0,
-            script_pubkey: bitcoin::Script::new_op_return(timestamping_commitment),
+            value: Amount::ZERO,
+            script_pubkey: ScriptBuf::new_op_return(timestamping_commitment),
         };
 
         Ok(vec![reserve_out, timestamping_commitment_out])
@@ -840,7 +1310,7 @@ impl SigningCheckpoint {
         sigs: Vec<Signature>,
         btc_height: u32,
     ) -> ContractResult<()> {
-        self.0.sign(api, &xpub, sigs, btc_height)?;
+        self.0.sign(api, &xpub, sigs, None, btc_height)?;
         Ok(())
     }
 }
@@ -870,16 +1340,35 @@ impl BuildingCheckpoint {
     /// it other than adding signatures. This means at this point all
     /// transactions contained within have a known transaction id which will not
     /// change.    
+    ///
+    /// If `reserve_script` is given, the reserve is paid to it rather than to
+    /// the checkpoint's own signatory set, e.g. to migrate it to a successor
+    /// bridge.
     pub fn advance(
         &mut self,
         timestamping_commitment: Vec<u8>,
         cp_fees: u64,
         config: &CheckpointConfig,
+        reserve_script: Option<ScriptBuf>,
     ) -> ContractResult<BuildingAdvanceRes> {
         self.0.status = CheckpointStatus::Signing;
-        let outs = self.additional_outputs(config, &timestamping_commitment)?;
+        let mut outs = self.additional_outputs(config, &timestamping_commitment)?;
+        if let Some(reserve_script) = reserve_script {
+            outs[0].script_pubkey = reserve_script;
+        }
+        #[cfg(feature = "deterministic")]
+        let first_deposit_input = if self.sigset.index > 0 { 1 } else { 0 };
         let checkpoint_batch = &mut self.batches[BatchType::Checkpoint];
         let checkpoint_tx = &mut checkpoint_batch[0];
+
+        // Order deposits and withdrawals canonically rather than by arrival,
+        // keeping the previous reserve input first.
+        #[cfg(feature = "deterministic")]
+        {
+            checkpoint_tx.input[first_deposit_input..].sort_by(|a, b| (*a.prevout).cmp(&b.prevout));
+            checkpoint_tx.output.sort_by(|a, b| (**a).cmp(b));
+        }
+
         for out in outs.iter().rev() {
             checkpoint_tx.output.insert(0, Adapter::new(out.clone()));
         }
@@ -907,7 +1396,7 @@ impl BuildingCheckpoint {
         let mut out_amount = 0;
         for i in 0..checkpoint_tx.output.len() {
             let output = &checkpoint_tx.output[i];
-            out_amount += output.value;
+            out_amount += output.value.to_sat();
         }
 
         // Deduct the outgoing amount and calculated fee amount from the reserve
@@ -916,28 +1405,16 @@ impl BuildingCheckpoint {
             ContractError::Checkpoint("Insufficient reserve value to cover miner fees".into())
         })?;
         let reserve_out = &mut checkpoint_tx.output[0];
-        reserve_out.value = reserve_value;
+        reserve_out.value = Amount::from_sat(reserve_value);
 
         // Prepare the checkpoint tx's inputs to be signed by calculating their
         // sighashes.
-        let bitcoin_tx = checkpoint_tx.to_bitcoin_tx()?;
-        let mut sc = bitcoin::util::sighash::SighashCache::new(&bitcoin_tx);
-        for i in 0..checkpoint_tx.input.len() {
-            let input = &mut checkpoint_tx.input[i];
-            println!(
-                "input_index: {:?}, redeem_script: {:?}, amount: {:?}",
-                i,
-                input.redeem_script.wscript_hash(),
-                input.amount
-            );
-            let sighash = sc.segwit_signature_hash(
-                i,
-                &input.redeem_script,
-                input.amount,
-                EcdsaSighashType::All,
-            )?;
-            input.signatures.set_message(sighash.into_inner());
+        if config.fee_bump_anyone_can_pay {
+            for input in checkpoint_tx.input.iter_mut() {
+                input.sighash_type = InputSighashType::AllPlusAnyoneCanPay;
+            }
         }
+        checkpoint_tx.populate_sig_messages()?;
 
         let reserve_outpoint = bitcoin::OutPoint {
             txid: checkpoint_tx.txid()?,
@@ -958,8 +1435,8 @@ impl BuildingCheckpoint {
     /// Transfers will be processed once the containing checkpoint is finished
     /// being signed, but will be represented in this checkpoint's emergency
     /// disbursal before they are processed.
-    pub fn insert_pending(&mut self, dest: Dest, coin: Coin) -> ContractResult<()> {
-        self.pending.push((dest, coin));
+    pub fn insert_pending(&mut self, transfer: PendingTransfer) -> ContractResult<()> {
+        self.pending.push(transfer);
         Ok(())
     }
 }
@@ -974,6 +1451,15 @@ impl CheckpointQueue {
         BUILDING_INDEX.load(store).unwrap()
     }
 
+    /// The index the next pushed checkpoint will have.
+    pub fn next_index(&self, store: &dyn Storage) -> ContractResult<u32> {
+        if CHECKPOINTS.is_empty(store)? {
+            Ok(self.index(store))
+        } else {
+            Ok(self.index(store) + 1)
+        }
+    }
+
     pub fn first_unhandled_confirmed_index(&self, store: &dyn Storage) -> u32 {
         FIRST_UNHANDLED_CONFIRMED_INDEX.load(store).unwrap()
     }
@@ -982,6 +1468,47 @@ impl CheckpointQueue {
         CONFIRMED_INDEX.may_load(store).unwrap_or_default()
     }
 
+    /// Compacts the signatures of the checkpoints at least `delay` checkpoints
+    /// older than the last confirmed one which have not been compacted yet.
+    /// Returns the number of bytes reclaimed.
+    pub fn compact_confirmed(
+        &mut self,
+        store: &mut dyn Storage,
+        delay: u32,
+    ) -> ContractResult<u64> {
+        let queue_len = self.len(store)?;
+        let end = match self
+            .confirmed_index(store)
+            .and_then(|index| index.checked_sub(delay))
+        {
+            Some(end) if queue_len > 0 => end,
+            _ => return Ok(0),
+        };
+        let first_index = self.index(store) + 1 - queue_len;
+        let start = COMPACTED_INDEX
+            .may_load(store)?
+            .unwrap_or_default()
+            .max(first_index);
+        if start > end {
+            return Ok(0);
+        }
+        let end = end.min(start + MAX_COMPACTED_CHECKPOINTS - 1);
+
+        let mut reclaimed = 0;
+        for index in start..=end {
+            let mut checkpoint = self.get(store, index)?;
+            reclaimed += checkpoint.compact()?;
+            self.set(store, index, &checkpoint)?;
+        }
+        COMPACTED_INDEX.save(store, &(end + 1))?;
+        let total = RECLAIMED_SIGNATURE_BYTES
+            .may_load(store)?
+            .unwrap_or_default();
+        RECLAIMED_SIGNATURE_BYTES.save(store, &(total + reclaimed))?;
+
+        Ok(reclaimed)
+    }
+
     /// Removes all checkpoints from the queue and resets the index to zero.
     pub fn reset(&mut self, store: &mut dyn Storage) -> ContractResult<()> {
         BUILDING_INDEX.save(store, &0)?;
@@ -1104,6 +1631,46 @@ impl CheckpointQueue {
         Ok(out)
     }
 
+    /// Estimates the number of seconds until a withdrawal requested at `now`
+    /// would be included in a Bitcoin block.
+    ///
+    /// The `Building` checkpoint is expected to advance once the recent
+    /// checkpoint cadence has elapsed since it was created. The cadence is
+    /// measured from the creation times of the last checkpoints, so it
+    /// already accounts for how long signatories took to sign them. Once
+    /// signed, the checkpoint transaction is expected to be mined within
+    /// `target_checkpoint_inclusion` blocks.
+    pub fn estimated_withdrawal_eta(&self, store: &dyn Storage, now: u64) -> ContractResult<u64> {
+        let config = self.config(store);
+        let inclusion_time = config.target_checkpoint_inclusion as u64 * TARGET_SPACING as u64;
+        if self.is_empty(store)? {
+            return Ok(config.min_checkpoint_interval + inclusion_time);
+        }
+
+        let mut create_times: Vec<u64> = self
+            .completed(store, 10)?
+            .iter()
+            .map(|cp| cp.create_time())
+            .collect();
+        if let Some(signing) = self.signing(store)? {
+            create_times.push(signing.create_time());
+        }
+        let building = self.building(store)?;
+        create_times.push(building.create_time());
+
+        let cadence = match (create_times.first(), create_times.len()) {
+            (Some(first), len) if len > 1 => (building.create_time() - first) / (len as u64 - 1),
+            _ => config.min_checkpoint_interval,
+        }
+        .clamp(
+            config.min_checkpoint_interval,
+            config.max_checkpoint_interval,
+        );
+
+        let elapsed = now.saturating_sub(building.create_time());
+        Ok(cadence.saturating_sub(elapsed) + inclusion_time)
+    }
+
     /// The index of the last completed checkpoint.
     pub fn last_completed_index(&self, store: &dyn Storage) -> ContractResult<u32> {
         if self.signing(store)?.is_some() {
@@ -1111,7 +1678,7 @@ impl CheckpointQueue {
         } else {
             self.index(store).checked_sub(1)
         }
-        .ok_or_else(|| ContractError::App("No completed checkpoints yet".to_string()))
+        .ok_or(ContractError::NoCompletedCheckpoints {})
     }
 
     pub fn first_index(&self, store: &dyn Storage) -> ContractResult<u32> {
@@ -1229,7 +1796,22 @@ impl CheckpointQueue {
         // fee_pool: &mut i64,
         parent_config: &BitcoinConfig,
     ) -> ContractResult<bool> {
-        let is_should_push = self.should_push(env, store, &timestamping_commitment, btc_height)?;
+        // no checkpoints are built once the reserve was paid to a successor
+        let migration = BRIDGE_MIGRATION.may_load(store)?;
+        if migration
+            .as_ref()
+            .map_or(false, |migration| migration.checkpoint_index.is_some())
+        {
+            return Ok(false);
+        }
+
+        let is_should_push = self.should_push(
+            env,
+            store,
+            &timestamping_commitment,
+            btc_height,
+            parent_config,
+        )?;
         if !is_should_push {
             return Ok(false);
         }
@@ -1240,12 +1822,16 @@ impl CheckpointQueue {
         if is_not_maybe_push {
             return Ok(false);
         }
+        FORCED_CHECKPOINT_PUSH.remove(store);
+        SIGSET_BTC_HEIGHTS.save(store, self.index(store), &btc_height)?;
 
         // Note: remove prune mechanism for not needed
         // self.prune(store).unwrap();
 
         if self.index(store) > 0 {
             let prev_index = self.index(store) - 1;
+            let timestamping_commitment =
+                commit_app_data(store, prev_index, timestamping_commitment)?;
             let cp_fees = self.calc_fee_checkpoint(store, prev_index, &timestamping_commitment)?;
 
             let config = self.config(store);
@@ -1253,8 +1839,21 @@ impl CheckpointQueue {
             let sigset = prev.sigset.clone();
             let prev_fee_rate = prev.fee_rate;
             let mut building_checkpoint = BuildingCheckpoint(prev);
+            let reserve_script = migration
+                .as_ref()
+                .map(|migration| ScriptBuf::from(migration.reserve_script.to_vec()));
             let (reserve_outpoint, reserve_value, fees_paid, excess_inputs, excess_outputs) =
-                building_checkpoint.advance(timestamping_commitment, cp_fees, &config)?;
+                building_checkpoint.advance(
+                    timestamping_commitment,
+                    cp_fees,
+                    &config,
+                    reserve_script,
+                )?;
+            let (excess_withdrawal_fees, excess_fees_collected) = building_checkpoint.settle_fees(
+                fees_paid,
+                &excess_outputs,
+                parent_config.units_per_sat,
+            );
             // update checkpoint
             self.set(store, prev_index, &building_checkpoint)?;
 
@@ -1299,21 +1898,41 @@ impl CheckpointQueue {
                 }
             };
 
+            if fee_rate != prev_fee_rate {
+                record_fee_rate_change(
+                    store,
+                    FeeRateChange {
+                        checkpoint_index: self.index(store),
+                        old_fee_rate: prev_fee_rate,
+                        new_fee_rate: fee_rate,
+                        cause: FeeRateChangeCause::ConfirmationLag,
+                        timestamp: bridge_time(env),
+                    },
+                )?;
+            }
+
             let mut building = self.building(store)?;
             building.fee_rate = fee_rate;
             let building_checkpoint_batch = &mut building.batches[BatchType::Checkpoint];
             let checkpoint_tx = &mut building_checkpoint_batch[0];
 
             // The new checkpoint tx's first input is the reserve output from
-            // the previous checkpoint.
-            let input = Input::new(
-                reserve_outpoint,
-                &sigset,
-                &[0u8], // TODO: double-check safety
-                reserve_value,
-                config.sigset_threshold,
-            )?;
-            checkpoint_tx.input.push(input);
+            // the previous checkpoint, unless it was paid to a successor
+            // bridge. Excess inputs and outputs of the migration checkpoint
+            // stay with this bridge.
+            if let Some(mut migration) = migration {
+                migration.checkpoint_index = Some(prev_index);
+                BRIDGE_MIGRATION.save(store, &migration)?;
+            } else {
+                let input = Input::new(
+                    reserve_outpoint,
+                    &sigset,
+                    &[0u8], // TODO: double-check safety
+                    reserve_value,
+                    config.sigset_threshold,
+                )?;
+                checkpoint_tx.input.push(input);
+            }
 
             // Add any excess inputs and outputs from the previous checkpoint to
             // the new checkpoint.
@@ -1321,11 +1940,16 @@ impl CheckpointQueue {
                 let shares = input.signatures.shares();
                 let mut data = input.clone();
                 data.signatures = ThresholdSig::from_shares(shares);
+                data.signatures.scheme = input.signatures.scheme;
                 checkpoint_tx.input.push(data);
             }
             for output in excess_outputs {
                 checkpoint_tx.output.push(output);
             }
+            // Excess withdrawals keep the fee they were quoted, which now
+            // counts towards this checkpoint.
+            building.withdrawal_fees.extend(excess_withdrawal_fees);
+            building.fees_collected += excess_fees_collected;
 
             let index = self.index(store);
             self.set(store, index, &building)?;
@@ -1373,7 +1997,12 @@ impl CheckpointQueue {
             let prev_fee_rate = prev.fee_rate;
             let mut building_checkpoint = BuildingCheckpoint(prev);
             let (reserve_outpoint, reserve_value, fees_paid, excess_inputs, excess_outputs) =
-                building_checkpoint.advance(timestamping_commitment, cp_fees, &config)?;
+                building_checkpoint.advance(timestamping_commitment, cp_fees, &config, None)?;
+            let (excess_withdrawal_fees, excess_fees_collected) = building_checkpoint.settle_fees(
+                fees_paid,
+                &excess_outputs,
+                parent_config.units_per_sat,
+            );
             // update checkpoint
             self.set(store, prev_index, &building_checkpoint)?;
 
@@ -1440,11 +2069,16 @@ impl CheckpointQueue {
                 let shares = input.signatures.shares();
                 let mut data = input.clone();
                 data.signatures = ThresholdSig::from_shares(shares);
+                data.signatures.scheme = input.signatures.scheme;
                 checkpoint_tx.input.push(data);
             }
             for output in excess_outputs {
                 checkpoint_tx.output.push(output);
             }
+            // Excess withdrawals keep the fee they were quoted, which now
+            // counts towards this checkpoint.
+            building.withdrawal_fees.extend(excess_withdrawal_fees);
+            building.fees_collected += excess_fees_collected;
 
             let index = self.index(store);
             self.set(store, index, &building)?;
@@ -1467,6 +2101,7 @@ impl CheckpointQueue {
                 break;
             }
 
+            SIGSET_ARCHIVE.save(store, oldest.sigset.index, &oldest.sigset)?;
             CHECKPOINTS.pop_front(store)?;
             queue_len -= 1;
         }
@@ -1493,8 +2128,17 @@ impl CheckpointQueue {
             let elapsed = now - self.building(store)?.create_time();
 
             // Do not push if the minimum checkpoint interval has not elapsed
-            // since creating the current `Building` checkpoint.
-            if elapsed < self.config(store).min_checkpoint_interval {
+            // since creating the current `Building` checkpoint, unless the
+            // checkpoint is filling up and the shorter capacity interval has
+            // elapsed.
+            // a tombstoned signatory key moves the reserve as soon as possible
+            let forced = FORCED_CHECKPOINT_PUSH.may_load(store)?.unwrap_or_default();
+            let config = self.config(store);
+            if !forced
+                && elapsed < config.min_checkpoint_interval
+                && (elapsed < config.min_capacity_checkpoint_interval
+                    || !self.building(store)?.reached_capacity(&config))
+            {
                 return Ok(false);
             }
 
@@ -1516,7 +2160,9 @@ impl CheckpointQueue {
             // transfers, or if not enough has been collected to pay for the
             // miner fee, unless the maximum checkpoint interval has elapsed
             // since creating the current `Building` checkpoint.
-            if elapsed < self.config(store).max_checkpoint_interval || self.index(store) == 0 {
+            if !forced
+                && (elapsed < self.config(store).max_checkpoint_interval || self.index(store) == 0)
+            {
                 let checkpoint_tx = building.checkpoint_tx()?;
                 let has_pending_deposit = if self.index(store) == 0 {
                     !checkpoint_tx.input.is_empty()
@@ -1569,8 +2215,7 @@ impl CheckpointQueue {
         // This will also stop the fee rate from being adjusted too high if the
         // issue is simply with relayers failing to report the confirmation of the
         // checkpoint transactions.
-        let unconfs = self.num_unconfirmed(store)?;
-        if unconfs >= self.config(store).max_unconfirmed_checkpoints {
+        if self.reached_unconfirmed_limit(store)? {
             return Ok(false);
         }
 
@@ -1604,6 +2249,7 @@ impl CheckpointQueue {
         store: &dyn Storage,
         timestamping_commitment: &[u8],
         btc_height: u32,
+        parent_config: &BitcoinConfig,
     ) -> ContractResult<bool> {
         // Do not push if there is a checkpoint in the `Signing` state. There
         // should only ever be at most one checkpoint in this state.
@@ -1612,12 +2258,27 @@ impl CheckpointQueue {
         }
 
         if !CHECKPOINTS.is_empty(store)? {
-            let now = env.block.time.seconds();
+            let now = bridge_time(env);
             let elapsed = now - self.building(store)?.create_time();
 
             // Do not push if the minimum checkpoint interval has not elapsed
-            // since creating the current `Building` checkpoint.
-            if elapsed < self.config(store).min_checkpoint_interval {
+            // since creating the current `Building` checkpoint, unless the
+            // checkpoint is filling up and the shorter capacity interval has
+            // elapsed.
+            let config = self.config(store);
+            if elapsed < config.min_checkpoint_interval
+                && (elapsed < config.min_capacity_checkpoint_interval
+                    || !self.building(store)?.reached_capacity(&config))
+            {
+                return Ok(false);
+            }
+
+            // With adaptive scheduling, keep collecting deposits and
+            // withdrawals until enough value is pending or fees are cheap.
+            if config.adaptive_scheduling
+                && elapsed < config.max_checkpoint_interval
+                && !self.schedule(env, store)?.triggered
+            {
                 return Ok(false);
             }
 
@@ -1636,9 +2297,10 @@ impl CheckpointQueue {
             let building = self.building(store)?;
 
             // Don't push if there are no pending deposits, withdrawals, or
-            // transfers, or if not enough has been collected to pay for the
-            // miner fee, unless the maximum checkpoint interval has elapsed
-            // since creating the current `Building` checkpoint.
+            // transfers, or if neither the collected fees nor the surplus of
+            // the fee pool can pay for the miner fee, unless the maximum
+            // checkpoint interval has elapsed since creating the current
+            // `Building` checkpoint.
             if elapsed < self.config(store).max_checkpoint_interval || self.index(store) == 0 {
                 let checkpoint_tx = building.checkpoint_tx()?;
                 let has_pending_deposit = if self.index(store) == 0 {
@@ -1654,11 +2316,12 @@ impl CheckpointQueue {
                     return Ok(false);
                 }
 
-                if building.fees_collected < cp_miner_fees {
+                let coverage = self.fee_coverage(store, parent_config, timestamping_commitment)?;
+                if !coverage.is_covered() {
                     #[cfg(debug_assertions)]
                     println!(
-                        "Not enough collected to pay miner fee: {} < {}",
-                        building.fees_collected, cp_miner_fees,
+                        "Not enough collected to pay miner fee: {} + {} < {}",
+                        coverage.fees_collected, coverage.fee_pool_surplus, cp_miner_fees,
                     );
                     return Ok(false);
                 }
@@ -1692,8 +2355,7 @@ impl CheckpointQueue {
         // This will also stop the fee rate from being adjusted too high if the
         // issue is simply with relayers failing to report the confirmation of the
         // checkpoint transactions.
-        let unconfs = self.num_unconfirmed(store)?;
-        if unconfs >= self.config(store).max_unconfirmed_checkpoints {
+        if self.reached_unconfirmed_limit(store)? {
             return Ok(false);
         }
 
@@ -1706,7 +2368,7 @@ impl CheckpointQueue {
 
         // Build the signatory set for the new checkpoint based on the current
         // validator set.
-        let sigset = SignatorySet::from_validator_ctx(store, env.block.time.seconds(), index)?;
+        let sigset = SignatorySet::from_validator_ctx(store, bridge_time(env), index)?;
         // Do not push if there are no validators in the signatory set.
         if sigset.possible_vp() == 0 {
             return Ok(false);
@@ -1791,7 +2453,12 @@ impl CheckpointQueue {
 
         // Build the signatory set for the new checkpoint based on the current
         // validator set.
-        let sigset = SignatorySet::from_validator_ctx(store, env.block.time.seconds(), index)?;
+        let mut sigset = SignatorySet::from_validator_ctx(store, bridge_time(env), index)?;
+        let config = self.config(store);
+        if config.commit_bridge_instance {
+            sigset.instance_commitment = Some(instance_commitment(&env.contract.address, index));
+        }
+        sigset.script_template = config.script_template;
 
         // Do not push if there are no validators in the signatory set.
         if sigset.possible_vp() == 0 {
@@ -1813,6 +2480,17 @@ impl CheckpointQueue {
         Ok(Some(building))
     }
 
+    /// The signatory set of the checkpoint with the given index, falling back
+    /// to the archived set if the checkpoint was pruned.
+    pub fn sigset(&self, store: &dyn Storage, index: u32) -> ContractResult<SignatorySet> {
+        match self.get(store, index) {
+            Ok(checkpoint) => Ok(checkpoint.sigset),
+            Err(_) => SIGSET_ARCHIVE
+                .may_load(store, index)?
+                .ok_or_else(|| ContractError::App(format!("Signatory set {} not found", index))),
+        }
+    }
+
     /// The active signatory set, which is the signatory set for the `Building`
     /// checkpoint.
     pub fn active_sigset(&self, store: &dyn Storage) -> ContractResult<SignatorySet> {
@@ -1829,7 +2507,8 @@ impl CheckpointQueue {
     /// useful, for example, in being able to check if a signer is offline.
     ///
     /// If the batch of signatures causes the checkpoint to be fully signed, it
-    /// will be advanced to the `Complete` state.
+    /// will be advanced to the `Complete` state and the txid of its checkpoint
+    /// transaction is returned.
     ///
     /// This method is exempt from paying transaction fees since the amount of
     /// signatures that can be submitted is capped and this type of transaction
@@ -1840,9 +2519,10 @@ impl CheckpointQueue {
         store: &mut dyn Storage,
         xpub: &Xpub,
         sigs: Vec<Signature>,
+        chunk_offset: Option<u32>,
         index: u32,
         btc_height: u32,
-    ) -> ContractResult<()> {
+    ) -> ContractResult<Option<Txid>> {
         let mut checkpoint = self.get(store, index)?;
         let status = checkpoint.status.clone();
         if matches!(status, CheckpointStatus::Building) {
@@ -1851,8 +2531,9 @@ impl CheckpointQueue {
             ));
         }
 
-        checkpoint.sign(api, xpub, sigs, btc_height)?;
+        checkpoint.sign(api, xpub, sigs, chunk_offset, btc_height)?;
 
+        let mut signed_txid = None;
         if matches!(status, CheckpointStatus::Signing) && checkpoint.signed() {
             #[cfg(debug_assertions)]
             println!(
@@ -1860,12 +2541,113 @@ impl CheckpointQueue {
                 checkpoint.checkpoint_tx()?
             );
             checkpoint.advance();
-            checkpoint.status = CheckpointStatus::Complete
+            checkpoint.status = CheckpointStatus::Complete;
+            let txid = checkpoint.checkpoint_tx()?.txid();
+            record_checkpoint_txid(store, index, &txid.to_string())?;
+            signed_txid = Some(txid);
         }
 
         self.set(store, index, &checkpoint)?;
 
-        Ok(())
+        Ok(signed_txid)
+    }
+
+    /// Completes a `Signing` checkpoint with a transaction signed off-chain.
+    /// The transaction must have the txid of the checkpoint transaction, so
+    /// it spends the same inputs to the same outputs, and every input must
+    /// carry a witness spending the input's redeem script with signatures of
+    /// more than the threshold of the input's signatory set. Returns the txid.
+    pub fn force_complete(
+        &mut self,
+        api: &dyn Api,
+        store: &mut dyn Storage,
+        index: u32,
+        final_tx: Adapter<bitcoin::Transaction>,
+        btc_height: u32,
+    ) -> ContractResult<Txid> {
+        let mut checkpoint = self.get(store, index)?;
+        if !matches!(checkpoint.status, CheckpointStatus::Signing) {
+            return Err(ContractError::Checkpoint(
+                "Only a signing checkpoint can be force completed".into(),
+            ));
+        }
+        // disbursal and intermediate transactions still need their
+        // signatures collected on chain
+        if checkpoint.signed_batches() < BatchType::Checkpoint as usize {
+            return Err(ContractError::Checkpoint(
+                "Earlier batches of the checkpoint are not signed".into(),
+            ));
+        }
+        let tx = &checkpoint.batches[BatchType::Checkpoint][0];
+        let txid = tx.to_bitcoin_tx()?.txid();
+        if final_tx.txid() != txid {
+            return Err(ContractError::Checkpoint(
+                "Transaction does not match the checkpoint transaction".into(),
+            ));
+        }
+        let mut sc = SighashCache::new(&*final_tx);
+        for (i, (input, txin)) in tx.input.iter().zip(final_tx.input.iter()).enumerate() {
+            // signatures, the selector of the signatories' branch of the
+            // script, and the redeem script
+            let witness = txin.witness.to_vec();
+            let Some((redeem_script, rest)) = witness.split_last() else {
+                return Err(ContractError::Checkpoint(
+                    "Transaction input is not signed".into(),
+                ));
+            };
+            if redeem_script.as_slice() != input.redeem_script.as_bytes()
+                || rest.last().map(Vec::as_slice) != Some(&[0u8][..])
+            {
+                return Err(ContractError::Checkpoint(
+                    "Transaction input is not signed for the checkpoint's redeem script".into(),
+                ));
+            }
+            let sighash = sc.p2wsh_signature_hash(
+                i,
+                &input.redeem_script,
+                Amount::from_sat(input.amount),
+                input.sighash_type.into(),
+            )?;
+            input
+                .signatures
+                .verify_witness(
+                    api,
+                    &sighash.to_byte_array(),
+                    &rest[..rest.len() - 1],
+                    input.sighash_type.into(),
+                )
+                .map_err(|err| {
+                    ContractError::Checkpoint(format!(
+                        "Transaction input {} is not signed by the signatory set: {}",
+                        i, err
+                    ))
+                })?;
+        }
+
+        checkpoint.forced_tx = Some(final_tx);
+        checkpoint.signed_at_btc_height = Some(btc_height);
+        checkpoint.advance();
+        record_checkpoint_txid(store, index, &txid.to_string())?;
+        self.set(store, index, &checkpoint)?;
+        Ok(txid)
+    }
+
+    /// The final txid of the completed checkpoint with the given index.
+    pub fn txid(&self, store: &dyn Storage, index: u32) -> ContractResult<Txid> {
+        if let Some(txid) = CHECKPOINT_INDEX_TXIDS.may_load(store, index)? {
+            return txid
+                .parse::<Txid>()
+                .map_err(|err| ContractError::App(err.to_string()));
+        }
+
+        // checkpoints completed before their txids were recorded
+        let checkpoint = self.get(store, index)?;
+        if !matches!(checkpoint.status, CheckpointStatus::Complete) {
+            return Err(ContractError::Checkpoint(
+                "Checkpoint is not complete".into(),
+            ));
+        }
+        Ok(checkpoint.checkpoint_tx()?.txid())
     }
 
     /// The signatory set for the checkpoint with the given index.
@@ -1873,6 +2655,91 @@ impl CheckpointQueue {
         Ok(self.get(store, index)?.sigset.clone())
     }
 
+    /// How much of the miner fee of the `Building` checkpoint its collected
+    /// fees pay for, with the rest topped up from the fee pool.
+    ///
+    /// The fee pool holds the fees collected by the `Building` checkpoint
+    /// too, so only what exceeds them is surplus.
+    pub fn fee_coverage(
+        &self,
+        store: &dyn Storage,
+        parent_config: &BitcoinConfig,
+        timestamping_commitment: &[u8],
+    ) -> ContractResult<FeeCoverage> {
+        let building = self.building(store)?;
+        let miner_fee =
+            self.calc_fee_checkpoint(store, self.index(store), timestamping_commitment)?;
+        let fee_pool = FEE_POOL.may_load(store)?.unwrap_or_default().max(0) as u64
+            / parent_config.units_per_sat.max(1);
+        let fee_pool_surplus = fee_pool.saturating_sub(building.fees_collected);
+        let covered = building.fees_collected + fee_pool_surplus;
+        let coverage_bps = match miner_fee {
+            0 => 10_000,
+            _ => (covered as u128 * 10_000 / miner_fee as u128) as u64,
+        };
+
+        Ok(FeeCoverage {
+            miner_fee,
+            fees_collected: building.fees_collected,
+            fee_pool_surplus,
+            top_up: miner_fee
+                .saturating_sub(building.fees_collected)
+                .min(fee_pool_surplus),
+            coverage_bps,
+        })
+    }
+
+    /// The state adaptive scheduling decides on whether to advance the
+    /// `Building` checkpoint.
+    pub fn schedule(&self, env: &Env, store: &dyn Storage) -> ContractResult<CheckpointSchedule> {
+        let config = self.config(store);
+        let building = self.building(store)?;
+        let tx = &building.batches[BatchType::Checkpoint][0];
+        // every checkpoint but the first spends the reserve as its first input
+        let reserve_inputs = if self.index(store) == 0 { 0 } else { 1 };
+        let pending_value = tx
+            .input
+            .iter()
+            .skip(reserve_inputs)
+            .map(|input| input.amount)
+            .chain(tx.output.iter().map(|output| output.value.to_sat()))
+            .sum::<u64>();
+
+        let mut fee_rates = FEE_RATE_HISTORY
+            .iter(store)?
+            .map(|change| change.map(|change| change.new_fee_rate))
+            .collect::<Result<Vec<_>, _>>()?;
+        fee_rates.sort_unstable();
+        let percentile = config.adaptive_fee_rate_percentile.min(100) as usize;
+        let favorable_fee_rate = match percentile {
+            0 => None,
+            _ if fee_rates.is_empty() => None,
+            _ => Some(fee_rates[(fee_rates.len() - 1) * percentile / 100]),
+        };
+
+        let reached_capacity = building.reached_capacity(&config);
+        let has_fast_withdrawal =
+            FAST_WITHDRAWAL_CHECKPOINT.may_load(store)? == Some(self.index(store));
+        let triggered = reached_capacity
+            || has_fast_withdrawal
+            || (config.adaptive_pending_value_threshold > 0
+                && pending_value >= config.adaptive_pending_value_threshold)
+            || favorable_fee_rate.map_or(false, |fee_rate| building.fee_rate <= fee_rate);
+        Ok(CheckpointSchedule {
+            adaptive: config.adaptive_scheduling,
+            elapsed: bridge_time(env).saturating_sub(building.create_time()),
+            min_checkpoint_interval: config.min_checkpoint_interval,
+            max_checkpoint_interval: config.max_checkpoint_interval,
+            pending_value,
+            pending_value_threshold: config.adaptive_pending_value_threshold,
+            fee_rate: building.fee_rate,
+            favorable_fee_rate,
+            reached_capacity,
+            has_fast_withdrawal,
+            triggered,
+        })
+    }
+
     /// Query building miner fee for checking with fee_collected
     pub fn query_building_miner_fee(
         &self,
@@ -1883,6 +2750,12 @@ impl CheckpointQueue {
         self.calc_fee_checkpoint(store, cp_index, &timestamping_commitment)
     }
 
+    /// Whether `max_unconfirmed_checkpoints` completed checkpoints are waiting
+    /// for confirmation, so no new checkpoint may be pushed.
+    pub fn reached_unconfirmed_limit(&self, store: &dyn Storage) -> ContractResult<bool> {
+        Ok(self.num_unconfirmed(store)? >= self.config(store).max_unconfirmed_checkpoints)
+    }
+
     /// The number of completed checkpoints which have not yet been confirmed on
     /// the Bitcoin network.
     pub fn num_unconfirmed(&self, store: &dyn Storage) -> ContractResult<u32> {
@@ -1993,7 +2866,7 @@ pub fn adjust_fee_rate(prev_fee_rate: u64, up: bool, config: &CheckpointConfig)
     if up {
         (prev_fee_rate * 5 / 4).max(prev_fee_rate + 1)
     } else {
-        (prev_fee_rate * 3 / 4).min(prev_fee_rate - 1)
+        (prev_fee_rate * 3 / 4).min(prev_fee_rate.saturating_sub(1))
     }
     .clamp(config.min_fee_rate, config.max_fee_rate)
 }
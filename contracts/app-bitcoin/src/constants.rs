@@ -8,12 +8,54 @@ pub const TRANSFER_FEE: u64 = 0;
 
 // checkpoints
 pub const MAX_DEPOSIT_AGE: u64 = 60 * 60 * 24 * 7 * 2; // 2 weeks
+pub const BTC_BLOCK_INTERVAL: u64 = 60 * 10; // target seconds between bitcoin blocks
 pub const MAX_CHECKPOINT_INTERVAL: u64 = 60 * 60 * 24 * 12; // 12 days. This value should be smaller than max_deposit_age & MAX_CHECKPOINT_AGE
 pub const MAX_CHECKPOINT_AGE: u64 = 60 * 60 * 24 * 7 * 3; // 3 weeks
+/// The bridge clock in deterministic mode starts at this time and advances
+/// by `DETERMINISTIC_BLOCK_TIME` seconds per block.
+#[cfg(feature = "deterministic")]
+pub const DETERMINISTIC_GENESIS_TIME: u64 = 1_600_000_000;
+#[cfg(feature = "deterministic")]
+pub const DETERMINISTIC_BLOCK_TIME: u64 = 5;
+/// The most checkpoints compacted by a single confirmation, to bound its gas
+pub const MAX_COMPACTED_CHECKPOINTS: u32 = 10;
+/// The most app data hashes a single checkpoint commits to
+pub const MAX_APP_DATA_PER_CHECKPOINT: usize = 256;
+/// The default number of work items processed per `ClockEndBlock`
+pub const MAX_END_BLOCK_ITEMS: u32 = 200;
+/// The most expired mint claims routed to recovery per `ClockEndBlock`
+pub const MAX_EXPIRED_MINT_CLAIMS: u32 = 10;
+/// The most dust deposits consolidated by a single sweep transaction
+pub const MAX_SWEEP_INPUTS: usize = 50;
+/// The number of change records kept for relayers polling `ChangesSince`
+pub const MAX_CHANGE_RECORDS: u32 = 200;
+/// The number of fee rate changes kept for `FeeRateHistory`
+pub const MAX_FEE_RATE_HISTORY: u32 = 100;
+// max length in bytes of a memo attached to a checkpoint
+pub const MAX_CHECKPOINT_MEMO_LEN: usize = 256;
+/// The length of the largest standard recovery script, a witness v1 program
+pub const MAX_RECOVERY_SCRIPT_LEN: usize = 34;
+/// The largest standard script signature of a relayed transaction input
+pub const MAX_STANDARD_SCRIPT_SIG_SIZE: usize = 1650;
+/// The largest standard null data output script, including the OP_RETURN
+pub const MAX_STANDARD_OP_RETURN_SIZE: usize = 83;
 
 // app constants
 pub const MIN_DEPOSIT_AMOUNT: u64 = 5000; // in satoshis
 pub const MIN_WITHDRAWAL_AMOUNT: u64 = 5000; // in satoshis
+/// The version byte prefixed to bridge instance commitments in sigset scripts
+pub const INSTANCE_COMMITMENT_VERSION: u8 = 1;
+/// The version of the unprefixed destination commitments used by deposit
+/// addresses derived before commitments were versioned
+pub const LEGACY_DEST_COMMITMENT_VERSION: u8 = 0;
+/// The destination commitment version preferred for new deposit addresses
+pub const DEST_COMMITMENT_VERSION: u8 = 1;
+/// The highest surcharge, in basis points, paid by deposits to old sigsets
+pub const MAX_SIGSET_LAG_SURCHARGE_BPS: u64 = 1_000;
+/// Seconds an unconfirmed deposit reported by a relayer stays visible
+pub const PENDING_DEPOSIT_TTL: u64 = 60 * 60 * 24;
+/// Seconds covered by the rolling deposit volume of each destination
+pub const DEST_STATS_WINDOW: u64 = 60 * 60 * 24;
 
 // TODO: move to config
 pub const MAX_SIGNATORIES: u64 = 20;
@@ -21,3 +63,27 @@ pub const SIGSET_THRESHOLD: (u64, u64) = (2, 3);
 
 pub const BTC_NATIVE_TOKEN_DENOM: &str = "obtc";
 pub const VALIDATOR_ADDRESS_PREFIX: &str = "oraivaloper";
+
+// state
+/// The version of the stored state layout, bumped with a new migration for
+/// every breaking change to the serialized checkpoints, inputs or signatures
+pub const CONTRACT_STATE_VERSION: u32 = 2;
+
+// query pagination
+pub const DEFAULT_QUERY_LIMIT: u32 = 10;
+pub const MAX_QUERY_LIMIT: u32 = 30;
+
+// reply ids
+pub const RECEIVE_HOOK_REPLY_ID: u64 = 1;
+pub const MINT_REPLY_ID: u64 = 3;
+pub const NOTIFICATION_REPLY_ID: u64 = 4;
+/// Replies of checkpoint hooks use this offset plus the hook id
+pub const CHECKPOINT_HOOK_REPLY_ID_OFFSET: u64 = 1 << 32;
+/// Replies of ibc memo transfers use this offset plus the refund id
+pub const IBC_MEMO_REPLY_ID_OFFSET: u64 = 1 << 33;
+
+// checkpoint hooks
+pub const MAX_CHECKPOINT_HOOKS: usize = 16;
+/// Failed deliveries after which a hook is deregistered
+pub const MAX_CHECKPOINT_HOOK_FAILURES: u32 = 5;
+pub const CHECKPOINT_HOOK_GAS_LIMIT: u64 = 500_000;
@@ -3,17 +3,27 @@ use cosmwasm_std::entry_point;
 
 use crate::{
     checkpoint::{Checkpoint, CheckpointQueue},
+    constants::{
+        CHECKPOINT_HOOK_REPLY_ID_OFFSET, CONTRACT_STATE_VERSION, IBC_MEMO_REPLY_ID_OFFSET,
+        MINT_REPLY_ID, NOTIFICATION_REPLY_ID, RECEIVE_HOOK_REPLY_ID,
+    },
     entrypoints::*,
     interface::{BitcoinConfig, CheckpointConfig},
+    migration::{migrate_state, state_version},
     msg::{Config, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg, SudoMsg},
     state::{
-        BITCOIN_CONFIG, BUILDING_INDEX, CHECKPOINTS, CHECKPOINT_CONFIG, CONFIG, FEE_POOL,
-        FIRST_UNHANDLED_CONFIRMED_INDEX, FOUNDATION_KEYS, OUTPOINTS,
+        record_admin_action, BITCOIN_CONFIG, BUILDING_INDEX, CHECKPOINTS, CHECKPOINT_CONFIG,
+        CONFIG, FEE_POOL, FIRST_UNHANDLED_CONFIRMED_INDEX, FOUNDATION_KEYS, OUTPOINTS,
+        STATE_VERSION,
     },
 };
-use common_bitcoin::error::ContractError;
-use cosmwasm_std::{to_json_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult};
+use common_bitcoin::{error::ContractError, msg::error_codes};
+use cosmwasm_std::{
+    to_json_binary, to_json_vec, Binary, Deps, DepsMut, Env, MessageInfo, Reply, Response,
+    StdResult,
+};
 use cw2::set_contract_version;
+use sha2::{Digest, Sha256};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:cw_bitcoin";
@@ -40,6 +50,9 @@ pub fn instantiate(
             light_client_contract: msg.light_client_contract,
             swap_router_contract: msg.swap_router_contract,
             osor_entry_point_contract: msg.osor_entry_point_contract,
+            secondary_light_client_contract: None,
+            notification_contract: None,
+            relayer_fee_options: vec![],
         },
     )?;
 
@@ -52,6 +65,7 @@ pub fn instantiate(
     BUILDING_INDEX.save(deps.storage, &0)?;
     FIRST_UNHANDLED_CONFIRMED_INDEX.save(deps.storage, &0)?;
     FOUNDATION_KEYS.save(deps.storage, &Vec::new())?;
+    STATE_VERSION.save(deps.storage, &CONTRACT_STATE_VERSION)?;
 
     Ok(Response::default())
 }
@@ -63,7 +77,26 @@ pub fn execute(
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
-    match msg {
+    #[cfg(feature = "profiling")]
+    let msg_name = crate::profiling::msg_name(&msg);
+    #[cfg(feature = "profiling")]
+    let mut profiled = crate::profiling::ProfiledStorage::new(deps.storage);
+    #[cfg(feature = "profiling")]
+    let deps = DepsMut {
+        storage: &mut profiled,
+        api: deps.api,
+        querier: deps.querier,
+    };
+    let admin_action = match msg.admin_action() {
+        Some(action) => Some((
+            action,
+            info.sender.clone(),
+            Binary::from(Sha256::digest(to_json_vec(&msg)?).to_vec()),
+        )),
+        None => None,
+    };
+    let height = env.block.height;
+    let result = match msg {
         ExecuteMsg::UpdateConfig {
             owner,
             relayer_fee_token,
@@ -75,6 +108,7 @@ pub fn execute(
             light_client_contract,
             swap_router_contract,
             osor_entry_point_contract,
+            relayer_fee_options,
         } => update_config(
             deps.storage,
             info,
@@ -88,6 +122,7 @@ pub fn execute(
             swap_router_contract,
             token_factory_contract,
             osor_entry_point_contract,
+            relayer_fee_options,
         ),
         ExecuteMsg::RelayDeposit {
             btc_tx,
@@ -99,6 +134,7 @@ pub fn execute(
         } => relay_deposit(
             &deps.querier,
             env,
+            info,
             deps.storage,
             btc_tx,
             btc_height,
@@ -107,12 +143,67 @@ pub fn execute(
             sigset_index,
             dest,
         ),
+        ExecuteMsg::ReportPendingDeposit {
+            txid,
+            vout,
+            dest,
+            amount,
+        } => report_pending_deposit(deps.storage, env, info, txid, vout, dest, amount),
         ExecuteMsg::RelayCheckpoint {
             btc_height,
             btc_proof,
             cp_index,
-        } => relay_checkpoint(&deps.querier, deps.storage, btc_height, btc_proof, cp_index),
-        ExecuteMsg::WithdrawToBitcoin { btc_address, fee } => withdraw_to_bitcoin(
+            ancestor_headers,
+        } => relay_checkpoint(
+            &deps.querier,
+            deps.storage,
+            &env,
+            info,
+            btc_height,
+            btc_proof,
+            cp_index,
+            ancestor_headers,
+        ),
+        ExecuteMsg::RelayFeeBumpedCheckpoint {
+            btc_height,
+            btc_proof,
+            cp_index,
+            btc_tx,
+        } => relay_fee_bumped_checkpoint(
+            &deps.querier,
+            deps.storage,
+            &env,
+            info,
+            btc_height,
+            btc_proof,
+            cp_index,
+            btc_tx,
+        ),
+        ExecuteMsg::WithdrawToBitcoin {
+            btc_address,
+            fee,
+            on_behalf_of: Some(granter),
+            amount,
+            priority,
+        } => withdraw_on_behalf(
+            deps.storage,
+            &deps.querier,
+            deps.api,
+            info,
+            env,
+            granter,
+            btc_address,
+            amount,
+            fee,
+            priority,
+        ),
+        ExecuteMsg::WithdrawToBitcoin {
+            btc_address,
+            fee,
+            amount,
+            priority,
+            ..
+        } => withdraw_to_bitcoin(
             deps.storage,
             &deps.querier,
             deps.api,
@@ -120,7 +211,21 @@ pub fn execute(
             env,
             btc_address,
             fee,
+            amount,
+            priority,
         ),
+        ExecuteMsg::PrepareWithdrawal {
+            btc_address,
+            amount,
+        } => prepare_withdrawal(deps.storage, env, info, btc_address, amount),
+        ExecuteMsg::ExecuteWithdrawal { quote_id } => {
+            execute_withdrawal(deps.storage, &deps.querier, deps.api, info, env, quote_id)
+        }
+        ExecuteMsg::SponsorWithdrawalFee { withdrawal_id } => {
+            sponsor_withdrawal_fee(deps.storage, env, info, withdrawal_id)
+        }
+        ExecuteMsg::Burn {} => burn(deps.storage, env, info),
+        ExecuteMsg::CommitData { data, fee } => commit_data(deps.storage, env, info, data, fee),
         ExecuteMsg::UpdateBitcoinConfig { config } => {
             update_bitcoin_config(deps.storage, info, config)
         }
@@ -135,31 +240,112 @@ pub fn execute(
             voting_powers,
             consensus_keys,
         } => add_validators(deps.storage, info, addrs, voting_powers, consensus_keys),
+        ExecuteMsg::RemoveValidators { addrs } => remove_validators(deps.storage, info, addrs),
+        ExecuteMsg::UpdateValidatorPower { addr, voting_power } => {
+            update_validator_power(deps.storage, info, addr, voting_power)
+        }
+        ExecuteMsg::RequestReinstatement {} => request_reinstatement(deps.storage, env, info),
+        ExecuteMsg::SetRecoveryAddress { btc_address } => {
+            set_recovery_address(deps.storage, info, btc_address)
+        }
+        ExecuteMsg::SetValidatorSet { epoch, validators } => {
+            set_validator_set(deps.storage, info, epoch, validators)
+        }
         ExecuteMsg::UpdateFoundationKeys { xpubs } => {
             update_foundation_keys(deps.storage, info, xpubs)
         }
+        ExecuteMsg::UpdateFoundationPolicy { policy } => {
+            update_foundation_policy(deps.storage, info, policy)
+        }
+        ExecuteMsg::WithdrawToLightning {
+            provider,
+            payment_hash,
+            refund_pubkey,
+            fee,
+        } => withdraw_to_lightning(
+            deps.storage,
+            &deps.querier,
+            env,
+            info,
+            provider,
+            payment_hash,
+            refund_pubkey,
+            fee,
+        ),
+        ExecuteMsg::AcceptLightningSwap { swap_id } => {
+            accept_lightning_swap(deps.storage, &deps.querier, deps.api, env, info, swap_id)
+        }
+        ExecuteMsg::RefundLightningSwap { swap_id } => {
+            refund_lightning_swap(deps.storage, env, info, swap_id)
+        }
+        ExecuteMsg::SetSwapProvider {
+            provider,
+            swap_provider,
+        } => set_swap_provider(deps.storage, info, provider, swap_provider),
         ExecuteMsg::SubmitCheckpointSignature {
             xpub,
             sigs,
             checkpoint_index,
             btc_height,
+            offset,
         } => submit_checkpoint_signature(
             deps.api,
             deps.storage,
+            &env,
             xpub,
             sigs,
             checkpoint_index,
             btc_height,
+            offset,
         ),
+        ExecuteMsg::SubmitCheckpointPsbt {
+            xpub,
+            psbt,
+            checkpoint_index,
+            btc_height,
+        } => submit_checkpoint_psbt(
+            deps.api,
+            deps.storage,
+            &env,
+            xpub,
+            psbt,
+            checkpoint_index,
+            btc_height,
+        ),
+        ExecuteMsg::SubmitRecoveryGroupSignature {
+            xpub,
+            sigset_index,
+            sigs,
+        } => {
+            submit_recovery_group_signature(deps.api, deps.storage, &env, xpub, sigset_index, sigs)
+        }
         ExecuteMsg::SubmitRecoverySignature { xpub, sigs } => {
-            submit_recovery_signature(deps.api, deps.storage, xpub, sigs)
+            submit_recovery_signature(deps.api, deps.storage, &env, xpub, sigs)
         }
-        ExecuteMsg::SetSignatoryKey { xpub } => {
-            set_signatory_key(&deps.querier, deps.storage, info, xpub)
+        ExecuteMsg::SetSignatoryKey { xpub, sig } => {
+            set_signatory_key(deps.api, &deps.querier, deps.storage, env, info, xpub, sig)
         }
+        ExecuteMsg::TombstoneSignatory { xpub, evidence } => {
+            tombstone_signatory(deps.storage, &env, info, xpub, evidence)
+        }
+        ExecuteMsg::ForceCompleteCheckpoint { index, final_tx } => force_complete_checkpoint(
+            &deps.querier,
+            deps.api,
+            deps.storage,
+            &env,
+            info,
+            index,
+            final_tx,
+        ),
         ExecuteMsg::RegisterDenom { subdenom, metadata } => {
             register_denom(deps.storage, info, subdenom, metadata)
         }
+        ExecuteMsg::UpdateDenomMetadata { metadata } => {
+            update_denom_metadata(deps.storage, info, metadata)
+        }
+        ExecuteMsg::MigrateDenom { new_subdenom } => {
+            migrate_denom(deps.storage, info, new_subdenom)
+        }
         ExecuteMsg::ChangeBtcDenomOwner { new_owner } => {
             change_btc_denom_owner(deps.storage, info, new_owner)
         }
@@ -170,15 +356,136 @@ pub fn execute(
             val_addr,
             permission,
         } => set_whitelist_validator(deps.storage, info, val_addr, permission),
-    }
+        ExecuteMsg::SetFeeExemption { addr, exempt } => {
+            set_fee_exemption(deps.storage, deps.api, info, addr, exempt)
+        }
+        ExecuteMsg::ImportState { entries, finish } => {
+            import_state(deps.storage, info, entries, finish)
+        }
+        ExecuteMsg::StartBridgeMigration { successor } => {
+            start_bridge_migration(deps.storage, &deps.querier, env, info, successor)
+        }
+        ExecuteMsg::TransferBridgeState { limit } => {
+            transfer_bridge_state(deps.storage, info, limit)
+        }
+        ExecuteMsg::CancelBridgeMigration {} => cancel_bridge_migration(deps.storage, info),
+        ExecuteMsg::ExpectBridgeMigration { predecessor } => {
+            expect_bridge_migration(deps.storage, info, predecessor)
+        }
+        ExecuteMsg::ReceiveMigratedState { entries, handover } => {
+            receive_migrated_state(deps.storage, info, entries, handover)
+        }
+        ExecuteMsg::RegisterReserveUtxo {
+            btc_tx,
+            btc_height,
+            btc_proof,
+            vout,
+            sigset_index,
+        } => register_reserve_utxo(
+            deps.storage,
+            &deps.querier,
+            info,
+            btc_tx,
+            btc_height,
+            btc_proof,
+            vout,
+            sigset_index,
+        ),
+        ExecuteMsg::SetBridgePaused { paused } => set_bridge_paused(deps.storage, info, paused),
+        ExecuteMsg::SetFeeRate { fee_rate } => set_fee_rate(deps.storage, &env, info, fee_rate),
+        ExecuteMsg::AllowSmallUtxos { until } => allow_small_utxos(deps.storage, info, until),
+        ExecuteMsg::SetOwnerMultisig { multisig } => {
+            set_owner_multisig(deps.storage, &deps.querier, info, multisig)
+        }
+        ExecuteMsg::BackfillSigsets { sigsets } => backfill_sigsets(deps.storage, info, sigsets),
+        ExecuteMsg::ClaimExpiredDeposit {
+            txid,
+            vout,
+            refund_script,
+        } => claim_expired_deposit(deps.storage, &env, info, txid, vout, refund_script),
+        ExecuteMsg::ClaimMint {} => claim_mint(&env, deps.storage, info),
+        ExecuteMsg::ClaimIbcRefund {} => claim_ibc_refund(deps.storage, info),
+        ExecuteMsg::ClaimRewards {} => claim_rewards(deps.storage, info),
+        ExecuteMsg::SetWithdrawalAllowance {
+            spender,
+            amount,
+            expiry,
+        } => set_withdrawal_allowance(deps.storage, env, info, spender, amount, expiry),
+        ExecuteMsg::SetRelayer {
+            relayer,
+            permission,
+        } => set_relayer(deps.storage, info, relayer, permission),
+        ExecuteMsg::SetNotificationContract { contract } => {
+            set_notification_contract(deps.storage, info, contract)
+        }
+        ExecuteMsg::SetSecondaryLightClient { contract } => {
+            set_secondary_light_client(deps.storage, info, contract)
+        }
+        ExecuteMsg::SwitchLightClient {} => switch_light_client(deps.storage, &deps.querier, info),
+        ExecuteMsg::SetComplianceTagger { tagger, permission } => {
+            set_compliance_tagger(deps.storage, info, tagger, permission)
+        }
+        ExecuteMsg::TagCheckpoint { index, memo } => {
+            tag_checkpoint(deps.storage, info, index, memo)
+        }
+        ExecuteMsg::SetScreenedScript {
+            btc_address,
+            listed,
+        } => set_screened_script(deps.storage, info, btc_address, listed),
+        ExecuteMsg::ReviewHeldWithdrawal { id, approve } => review_held_withdrawal(
+            deps.storage,
+            &deps.querier,
+            deps.api,
+            env,
+            info,
+            id,
+            approve,
+        ),
+        ExecuteMsg::RegisterHook { addr, events } => {
+            register_hook(deps.storage, deps.api, info, addr, events)
+        }
+        ExecuteMsg::UnregisterHook { addr } => unregister_hook(deps.storage, deps.api, info, addr),
+        ExecuteMsg::ReconcileReorgedDeposits { deposits } => {
+            reconcile_reorged_deposits(deps.storage, &deps.querier, env, info, deposits)
+        }
+    };
+    let result = result.and_then(|response| {
+        if let Some((action, sender, payload_hash)) = admin_action {
+            record_admin_action(deps.storage, height, sender, action, payload_hash)?;
+        }
+        Ok(response)
+    });
+    #[cfg(feature = "profiling")]
+    profiled.record("execute", msg_name);
+    result.map_err(ContractError::coded)
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    #[cfg(feature = "profiling")]
+    let msg_name = crate::profiling::msg_name(&msg);
+    #[cfg(feature = "profiling")]
+    let mut storage = crate::profiling::ReadOnlyStorage(deps.storage);
+    #[cfg(feature = "profiling")]
+    let profiled = crate::profiling::ProfiledStorage::new(&mut storage);
+    #[cfg(feature = "profiling")]
+    let deps = Deps {
+        storage: &profiled,
+        api: deps.api,
+        querier: deps.querier,
+    };
+    let result = query_msg(deps, env, msg);
+    #[cfg(feature = "profiling")]
+    profiled.record("query", msg_name);
+    result
+}
+
+fn query_msg(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::Config {} => to_json_binary(&query_config(deps.storage)?),
         QueryMsg::BitcoinConfig {} => to_json_binary(&query_bitcoin_config(deps.storage)?),
         QueryMsg::CheckpointConfig {} => to_json_binary(&query_checkpoint_config(deps.storage)?),
+        QueryMsg::FullConfig {} => to_json_binary(&query_full_config(deps.storage)?),
         QueryMsg::SignatoryKey { addr } => {
             to_json_binary(&query_signatory_key(deps.storage, addr)?)
         }
@@ -191,24 +498,125 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
         QueryMsg::CheckpointFees { index } => {
             to_json_binary(&query_checkpoint_fees(deps.storage, index)?)
         }
+        QueryMsg::FeeCoverage {} => to_json_binary(&query_fee_coverage(deps.storage)?),
+        QueryMsg::CheckpointSchedule {} => {
+            to_json_binary(&query_checkpoint_schedule(&env, deps.storage)?)
+        }
         QueryMsg::CompletedCheckpointTxs { limit } => {
             to_json_binary(&query_complete_checkpoint_txs(deps.storage, limit)?)
         }
         QueryMsg::CheckpointTx { index } => {
             to_json_binary(&query_checkpoint_tx(deps.storage, index)?)
         }
+        QueryMsg::CheckpointInputs { index } => {
+            to_json_binary(&query_checkpoint_inputs(deps.storage, index)?)
+        }
+        QueryMsg::CheckpointPsbt { index } => {
+            to_json_binary(&query_checkpoint_psbt(deps.storage, index)?)
+        }
+        QueryMsg::RawTxHex { kind, index } => {
+            to_json_binary(&query_raw_tx_hex(deps.storage, kind, index)?)
+        }
+        QueryMsg::CheckpointTxid { index } => {
+            to_json_binary(&query_checkpoint_txid(deps.storage, index)?)
+        }
+        QueryMsg::CheckpointIndexByTxid { txid } => {
+            to_json_binary(&query_checkpoint_index_by_txid(deps.storage, txid)?)
+        }
+        QueryMsg::CheckpointTxidPreimage { index } => {
+            to_json_binary(&query_checkpoint_txid_preimage(deps.storage, index)?)
+        }
+        QueryMsg::MintRecord { txid, vout } => {
+            to_json_binary(&query_mint_record(deps.storage, txid, vout)?)
+        }
+        QueryMsg::ReorgedDeposits { start_after, limit } => {
+            to_json_binary(&query_reorged_deposits(deps.storage, start_after, limit)?)
+        }
+        QueryMsg::BadDebt {} => to_json_binary(&query_bad_debt(deps.storage)?),
+        QueryMsg::Burns { start_after, limit } => {
+            to_json_binary(&query_burns(deps.storage, start_after, limit)?)
+        }
+        QueryMsg::TotalBurned {} => to_json_binary(&query_total_burned(deps.storage)?),
+        QueryMsg::ExpiredDeposits {
+            dest,
+            start_after,
+            limit,
+        } => to_json_binary(&query_expired_deposits(
+            deps.storage,
+            dest,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::DestStats { dest } => to_json_binary(&query_dest_stats(deps.storage, env, dest)?),
+        QueryMsg::TopDestinations { limit } => {
+            to_json_binary(&query_top_destinations(deps.storage, env, limit)?)
+        }
+        QueryMsg::PendingDeposits {
+            dest,
+            start_after,
+            limit,
+        } => to_json_binary(&query_pending_deposits(
+            deps.storage,
+            env,
+            dest,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::MintClaims { recipient } => {
+            to_json_binary(&query_mint_claims(deps.storage, deps.api, recipient)?)
+        }
+        QueryMsg::Pools {} => to_json_binary(&query_pools(deps.storage)?),
+        QueryMsg::RewardBalance { address } => {
+            to_json_binary(&query_reward_balance(deps.storage, deps.api, address)?)
+        }
+        QueryMsg::WithdrawalAllowance { granter, spender } => to_json_binary(
+            &query_withdrawal_allowance(deps.storage, deps.api, granter, spender)?,
+        ),
+        QueryMsg::WithdrawalAllowances {
+            granter,
+            start_after,
+            limit,
+        } => to_json_binary(&query_withdrawal_allowances(
+            deps.storage,
+            deps.api,
+            granter,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::IbcTransfers { start_after, limit } => {
+            to_json_binary(&query_ibc_transfers(deps.storage, start_after, limit)?)
+        }
+        QueryMsg::IbcRefunds { address } => {
+            to_json_binary(&query_ibc_refunds(deps.storage, address)?)
+        }
+        QueryMsg::RelayerFeeOptions {} => to_json_binary(&query_relayer_fee_options(deps.storage)?),
+        QueryMsg::FeeExemptions { start_after, limit } => {
+            to_json_binary(&query_fee_exemptions(deps.storage, start_after, limit)?)
+        }
         QueryMsg::SignedRecoveryTxs {} => to_json_binary(&query_signed_recovery_txs(deps.storage)?),
         QueryMsg::CheckpointByIndex { index } => {
             to_json_binary(&query_checkpoint_by_index(deps.storage, index)?)
         }
+        QueryMsg::CheckpointSummary { index } => {
+            to_json_binary(&query_checkpoint_summary(deps.storage, index)?)
+        }
         QueryMsg::BuildingCheckpoint {} => {
             to_json_binary(&query_building_checkpoint(deps.storage)?)
         }
+        QueryMsg::SigningDuties { xpub } => {
+            to_json_binary(&query_signing_duties(&env, deps.storage, xpub)?)
+        }
         QueryMsg::SigningRecoveryTxs { xpub } => to_json_binary(&query_signing_recovery_txs(
             deps.querier,
             deps.storage,
             xpub,
         )?),
+        QueryMsg::SigningRecoveryGroup { xpub, sigset_index } => to_json_binary(
+            &query_signing_recovery_group(deps.storage, xpub, sigset_index)?,
+        ),
+        QueryMsg::RecoveryGroups { xpub } => {
+            to_json_binary(&query_recovery_groups(deps.storage, xpub)?)
+        }
         QueryMsg::SigningTxsAtCheckpointIndex {
             xpub,
             checkpoint_index,
@@ -217,22 +625,186 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
             xpub,
             checkpoint_index,
         )?),
+        QueryMsg::CheckpointSigningProgress {
+            xpub,
+            checkpoint_index,
+        } => to_json_binary(&query_checkpoint_signing_progress(
+            deps.storage,
+            xpub,
+            checkpoint_index,
+        )?),
         QueryMsg::ProcessedOutpoint { key } => {
             to_json_binary(&query_process_outpoints(deps.storage, key)?)
         }
         QueryMsg::CompletedIndex {} => to_json_binary(&query_completed_index(deps.storage)?),
         QueryMsg::BuildingIndex {} => to_json_binary(&query_building_index(deps.storage)?),
         QueryMsg::ConfirmedIndex {} => to_json_binary(&query_comfirmed_index(deps.storage)?),
+        QueryMsg::IsCheckpointConfirmed { index } => {
+            to_json_binary(&query_is_checkpoint_confirmed(deps.storage, index)?)
+        }
         QueryMsg::UnhandledConfirmedIndex {} => {
             to_json_binary(&query_first_unconfirmed_index(deps.storage)?)
         }
         QueryMsg::ChangeRates { interval } => {
-            to_json_binary(&query_change_rates(deps.storage, _env, interval)?)
+            to_json_binary(&query_change_rates(deps.storage, env, interval)?)
         }
         QueryMsg::ValueLocked {} => to_json_binary(&query_value_locked(deps.storage)?),
+        QueryMsg::EstimatedWithdrawalEta {} => {
+            to_json_binary(&query_estimated_withdrawal_eta(deps.storage, env)?)
+        }
         QueryMsg::CheckEligibleValidator { val_addr } => to_json_binary(
             &query_check_eligible_validator(deps.storage, deps.querier, val_addr)?,
         ),
+        QueryMsg::JailStatus { addr } => to_json_binary(&query_jail_status(deps.storage, addr)?),
+        QueryMsg::Tombstones { start_after, limit } => {
+            to_json_binary(&query_tombstones(deps.storage, start_after, limit)?)
+        }
+        QueryMsg::Metrics {} => to_json_binary(&query_metrics(deps.storage, deps.querier)?),
+        QueryMsg::FoundationPolicy {} => to_json_binary(&query_foundation_policy(deps.storage)?),
+        QueryMsg::SwapProvider { provider } => {
+            to_json_binary(&query_swap_provider(deps.storage, provider)?)
+        }
+        QueryMsg::LightningSwap { swap_id } => {
+            to_json_binary(&query_lightning_swap(deps.storage, swap_id)?)
+        }
+        QueryMsg::Withdrawal { withdrawal_id } => {
+            to_json_binary(&query_withdrawal(deps.storage, withdrawal_id)?)
+        }
+        QueryMsg::WithdrawalQuote { quote_id } => {
+            to_json_binary(&query_withdrawal_quote(deps.storage, quote_id)?)
+        }
+        QueryMsg::ScreenedScripts { start_after, limit } => {
+            to_json_binary(&query_screened_scripts(deps.storage, start_after, limit)?)
+        }
+        QueryMsg::HeldWithdrawals { start_after, limit } => {
+            to_json_binary(&query_held_withdrawals(deps.storage, start_after, limit)?)
+        }
+        QueryMsg::Hooks { start_after, limit } => {
+            to_json_binary(&query_hooks(deps.storage, start_after, limit)?)
+        }
+        QueryMsg::AppDataProof {
+            checkpoint_index,
+            data,
+        } => to_json_binary(&query_app_data_proof(deps.storage, checkpoint_index, data)?),
+        QueryMsg::ReclaimedSignatureBytes {} => {
+            to_json_binary(&query_reclaimed_signature_bytes(deps.storage)?)
+        }
+        QueryMsg::ValidatorSet {} => to_json_binary(&query_validator_set(deps.storage)?),
+        QueryMsg::ScheduledValidatorSet {} => {
+            to_json_binary(&query_scheduled_validator_set(deps.storage)?)
+        }
+        QueryMsg::ExportState {
+            section,
+            start_after,
+            limit,
+        } => to_json_binary(&query_export_state(
+            deps.storage,
+            section,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::BridgeMigration {} => to_json_binary(&query_bridge_migration(deps.storage)?),
+        QueryMsg::MigrationHandshake { predecessor } => {
+            to_json_binary(&query_migration_handshake(deps.storage, predecessor)?)
+        }
+        QueryMsg::DepositAddressStatus { dest, sigset_index } => to_json_binary(
+            &query_deposit_address_status(deps.storage, &deps.querier, env, dest, sigset_index)?,
+        ),
+        QueryMsg::DestCommitment { dest } => to_json_binary(&query_dest_commitment(dest)?),
+        QueryMsg::CheckRecoveryAddress { btc_address } => {
+            to_json_binary(&query_check_recovery_address(deps.storage, btc_address)?)
+        }
+        QueryMsg::SimulateDeposit {
+            value_sats,
+            sigset_index,
+            dest,
+        } => to_json_binary(&query_simulate_deposit(
+            &env,
+            deps.storage,
+            &deps.querier,
+            deps.api,
+            value_sats,
+            sigset_index,
+            dest,
+        )?),
+        QueryMsg::CheckRelayDeposit {
+            txid,
+            vout,
+            sigset_index,
+            dest,
+            btc_height,
+            script_pubkey,
+        } => to_json_binary(&query_check_relay_deposit(
+            &env,
+            deps.storage,
+            &deps.querier,
+            txid,
+            vout,
+            sigset_index,
+            dest,
+            btc_height,
+            script_pubkey,
+        )?),
+        QueryMsg::BridgePaused {} => to_json_binary(&query_bridge_paused(deps.storage)?),
+        QueryMsg::OwnerProposal {
+            title,
+            description,
+            msgs,
+        } => to_json_binary(&query_owner_proposal(
+            &env,
+            deps.storage,
+            deps.querier,
+            title,
+            description,
+            msgs,
+        )?),
+        QueryMsg::ChangesSince { height } => {
+            to_json_binary(&query_changes_since(deps.storage, height)?)
+        }
+        QueryMsg::FeeRateHistory { limit } => {
+            to_json_binary(&query_fee_rate_history(deps.storage, limit)?)
+        }
+        QueryMsg::AdminLog { start_after, limit } => {
+            to_json_binary(&query_admin_log(deps.storage, start_after, limit)?)
+        }
+        QueryMsg::BalanceSnapshot {} => to_json_binary(&query_balance_snapshot(deps.storage)?),
+        QueryMsg::SnapshotBalances { start_after, limit } => {
+            to_json_binary(&query_snapshot_balances(deps.storage, start_after, limit)?)
+        }
+        QueryMsg::LiabilitiesProof { address } => {
+            to_json_binary(&query_liabilities_proof(deps.storage, address)?)
+        }
+        QueryMsg::SnapshotTopBalances { limit } => {
+            to_json_binary(&query_snapshot_top_balances(deps.storage, limit)?)
+        }
+        QueryMsg::SnapshotWithdrawals { start_after, limit } => to_json_binary(
+            &query_snapshot_withdrawals(deps.storage, start_after, limit)?,
+        ),
+        QueryMsg::DisbursalOutputs { start_after, limit } => {
+            to_json_binary(&query_disbursal_outputs(deps.storage, start_after, limit)?)
+        }
+        QueryMsg::NbtcAccount { address } => {
+            to_json_binary(&query_nbtc_account(deps.storage, &deps.querier, address)?)
+        }
+        QueryMsg::NbtcAccounts { start_after, limit } => to_json_binary(&query_nbtc_accounts(
+            deps.storage,
+            &deps.querier,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::SimulateEndBlock {} => to_json_binary(&query_simulate_end_block(
+            &env,
+            deps.storage,
+            deps.querier,
+            deps.api,
+        )?),
+        QueryMsg::ErrorCodes {} => to_json_binary(&error_codes()),
+        QueryMsg::StateVersion {} => to_json_binary(&state_version(deps.storage)?),
+        QueryMsg::SigsetAt { index } => to_json_binary(&query_sigset_at(deps.storage, index)?),
+        QueryMsg::LightClients {} => to_json_binary(&query_light_clients(deps.storage)?),
+        QueryMsg::WalletDescriptor { sigset_index } => {
+            to_json_binary(&query_wallet_descriptor(deps.storage, sigset_index)?)
+        }
     }
 }
 
@@ -240,19 +812,55 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
 pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
     let original_version =
         cw2::ensure_from_older_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
-    let foundation_keys = FOUNDATION_KEYS.may_load(deps.storage)?;
-    if let Some(_) = foundation_keys {
-    } else {
-        FOUNDATION_KEYS.save(deps.storage, &Vec::new())?;
-    }
-    Ok(Response::new().add_attribute("new_version", original_version.to_string()))
+    let original_state_version = migrate_state(deps.storage)?;
+    Ok(Response::new()
+        .add_attribute("new_version", original_version.to_string())
+        .add_attribute("original_state_version", original_state_version.to_string())
+        .add_attribute("state_version", CONTRACT_STATE_VERSION.to_string()))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn sudo(deps: DepsMut, env: Env, msg: SudoMsg) -> Result<Response, ContractError> {
-    match msg {
+    #[cfg(feature = "profiling")]
+    let msg_name = crate::profiling::msg_name(&msg);
+    #[cfg(feature = "profiling")]
+    let mut profiled = crate::profiling::ProfiledStorage::new(deps.storage);
+    #[cfg(feature = "profiling")]
+    let deps = DepsMut {
+        storage: &mut profiled,
+        api: deps.api,
+        querier: deps.querier,
+    };
+    let result = match msg {
         SudoMsg::ClockEndBlock { hash } => {
             clock_end_block(&env, deps.storage, &deps.querier, deps.api, hash)
         }
-    }
+        SudoMsg::VerifyInvariants {} => verify_invariants(deps.storage, &deps.querier),
+        SudoMsg::IbcLifecycleComplete(msg) => ibc_lifecycle_complete(deps.storage, msg),
+    };
+    #[cfg(feature = "profiling")]
+    profiled.record("sudo", msg_name);
+    result.map_err(ContractError::coded)
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> Result<Response, ContractError> {
+    let result = match msg.id {
+        RECEIVE_HOOK_REPLY_ID => receive_hook_reply(msg.result),
+        MINT_REPLY_ID => mint_reply(&env, deps.storage, msg.result),
+        NOTIFICATION_REPLY_ID => notification_reply(msg.result),
+        id if id >= IBC_MEMO_REPLY_ID_OFFSET => ibc_memo_transfer_reply(
+            &env,
+            deps.storage,
+            id - IBC_MEMO_REPLY_ID_OFFSET,
+            msg.result,
+        ),
+        id if id >= CHECKPOINT_HOOK_REPLY_ID_OFFSET => checkpoint_hook_reply(
+            deps.storage,
+            id - CHECKPOINT_HOOK_REPLY_ID_OFFSET,
+            msg.result,
+        ),
+        _ => Err(ContractError::App(format!("Unknown reply id {}", msg.id))),
+    };
+    result.map_err(ContractError::coded)
 }
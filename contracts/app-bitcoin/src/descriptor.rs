@@ -0,0 +1,69 @@
+use bitcoin::Script;
+use common_bitcoin::error::{ContractError, ContractResult};
+
+const INPUT_CHARSET: &str =
+    "0123456789()[],'/*abcdefgh@:$%{}IJKLMNOPQRSTUVWXYZ&+-.;<=>?!^_|~ijklmnopqrstuvwxyzABCDEFGH`#\"\\ ";
+const CHECKSUM_CHARSET: &[u8] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+
+fn polymod(c: u64, val: u64) -> u64 {
+    let c0 = c >> 35;
+    let mut c = ((c & 0x7ffffffff) << 5) ^ val;
+    for (bit, generator) in [
+        0xf5dee51989,
+        0xa9fdca3312,
+        0x1bab10e32d,
+        0x3706b1677a,
+        0x644d626ffd,
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        if c0 & (1 << bit) != 0 {
+            c ^= generator;
+        }
+    }
+    c
+}
+
+/// Computes the checksum of an output script descriptor as specified in
+/// BIP-380.
+pub fn descriptor_checksum(descriptor: &str) -> ContractResult<String> {
+    let mut c = 1;
+    let mut class = 0;
+    let mut class_count = 0;
+    for ch in descriptor.chars() {
+        let pos = INPUT_CHARSET
+            .find(ch)
+            .ok_or_else(|| ContractError::App(format!("Invalid descriptor character: {}", ch)))?
+            as u64;
+        c = polymod(c, pos & 31);
+        class = class * 3 + (pos >> 5);
+        class_count += 1;
+        if class_count == 3 {
+            c = polymod(c, class);
+            class = 0;
+            class_count = 0;
+        }
+    }
+    if class_count > 0 {
+        c = polymod(c, class);
+    }
+    for _ in 0..8 {
+        c = polymod(c, 0);
+    }
+    c ^= 1;
+
+    Ok((0..8)
+        .map(|j| CHECKSUM_CHARSET[((c >> (5 * (7 - j))) & 31) as usize] as char)
+        .collect())
+}
+
+/// A `raw()` descriptor of the given output script, with its checksum.
+///
+/// The weighted multisig template of the bridge can't be expressed with
+/// `multi()` or `sortedmulti()`, so wallets watch the output script itself.
+pub fn raw_descriptor(script_pubkey: &Script) -> ContractResult<String> {
+    let descriptor = format!("raw({})", hex::encode(script_pubkey.as_bytes()));
+    let checksum = descriptor_checksum(&descriptor)?;
+    Ok(format!("{}#{}", descriptor, checksum))
+}
@@ -1,29 +1,65 @@
 use crate::{
-    app::{Bitcoin, ConsensusKey},
-    constants::VALIDATOR_ADDRESS_PREFIX,
-    fee::process_deduct_fee,
-    helper::{convert_addr_by_prefix, fetch_staking_validator},
-    interface::{BitcoinConfig, CheckpointConfig, Dest},
+    app::{Bitcoin, ConsensusKey, NETWORK},
+    checkpoint::{CheckpointQueue, WithdrawalFee, WithdrawalPosition},
+    constants::{
+        DEFAULT_QUERY_LIMIT, MAX_APP_DATA_PER_CHECKPOINT, MAX_CHECKPOINT_HOOKS,
+        MAX_CHECKPOINT_MEMO_LEN, MAX_QUERY_LIMIT, PENDING_DEPOSIT_TTL, VALIDATOR_ADDRESS_PREFIX,
+    },
+    export::{export_migrated, import_entries, import_migrated_entries, migrated_stores_empty},
+    fee::{check_relayer_fee_payment, process_deduct_fee_for},
+    helper::{
+        assert_cw3_multisig, bridge_time, checkpoint_hook_msgs, convert_addr_by_prefix,
+        fetch_staking_validator, notification_msg,
+    },
+    interface::{
+        BitcoinConfig, BridgeMigration, BurnRecord, Change, CheckpointConfig, CheckpointHook, Dest,
+        FeeRateChange, FeeRateChangeCause, HeldWithdrawal, HookEvent, LightningSwap,
+        MigrationHandover, PendingDeposit, QueuedWithdrawal, ReorgedDeposit, ScheduledValidatorSet,
+        ScriptScreening, SignedReserveOutput, SwapProvider, Tombstone, ValidatorInfo,
+        WithdrawalAllowance, WithdrawalPriority, WithdrawalQuote,
+    },
+    lightning::htlc_script,
+    msg::{
+        BackfilledSigset, BridgeNotification, CheckpointHookMsg, Config, ExecuteMsg, FeeData,
+        MigrationHandshakeResponse, QueryMsg, ReorgedDepositId, StateEntry,
+    },
+    signatory::{normalized_xpub_key, signatory_key_challenge, FoundationPolicy},
     state::{
-        get_full_btc_denom, Ratio, BITCOIN_CONFIG, CHECKPOINT_CONFIG, CONFIG, FOUNDATION_KEYS,
-        SIGNERS, TOKEN_FEE_RATIO, VALIDATORS, WHITELIST_VALIDATORS,
+        get_full_btc_denom, is_btc_denom, record_change, record_fee_rate_change,
+        save_pending_deposit, Ratio, ACCOUNT_ACTIVITY, APP_DATA, BAD_DEBT, BITCOIN_CONFIG,
+        BRIDGE_MIGRATION, BRIDGE_PAUSED, BTC_SUBDENOM, BURNS, BURN_COUNT, CHECKPOINT_CONFIG,
+        CHECKPOINT_HOOKS, CHECKPOINT_HOOK_COUNT, CHECKPOINT_MEMOS, COMPLIANCE_TAGGERS, CONFIG,
+        CONFIRMED_INDEX, FAST_WITHDRAWAL_CHECKPOINT, FEE_EXEMPTIONS, FORCED_CHECKPOINT_PUSH,
+        FORCE_COMPLETE_VOTES, FOUNDATION_KEYS, FOUNDATION_POLICY, HELD_WITHDRAWALS,
+        HELD_WITHDRAWAL_COUNT, IBC_REFUNDS, JAILED_SIGNERS, LEGACY_BTC_DENOMS, LIGHTNING_SWAPS,
+        LIGHTNING_SWAP_COUNT, LIGHT_CLIENT_SWITCHED_AT, MIGRATION_PREDECESSOR, MINT_CLAIMS,
+        MINT_CLAIM_COUNT, MINT_RECORDS, RECOVERY_SCRIPTS, RECOVERY_TXS, RELAYERS, REORGED_DEPOSITS,
+        REWARD_BALANCES, SCHEDULED_VALIDATOR_SET, SCREENED_SCRIPTS, SIGNED_RESERVE_OUTPUTS,
+        SIGNERS, SIGSET_ARCHIVE, SMALL_UTXOS_ALLOWED_UNTIL, SWAP_PROVIDERS, TOKEN_FEE_RATIO,
+        TOMBSTONED_XPUBS, TOMBSTONE_VOTES, TOTAL_BURNED, VALIDATORS, WHITELIST_VALIDATORS,
+        WITHDRAWALS, WITHDRAWAL_ALLOWANCES, WITHDRAWAL_COUNT, WITHDRAWAL_QUOTES,
+        WITHDRAWAL_QUOTE_COUNT,
     },
-    threshold_sig::Signature,
+    threshold_sig::{Signature, ThresholdSig},
 };
 
-use bitcoin::{util::merkleblock::PartialMerkleTree, Transaction};
+use bitcoin::{
+    merkle_tree::PartialMerkleTree, psbt::Psbt, BlockHash, Script, ScriptBuf, Transaction,
+};
 use common_bitcoin::{
     adapter::{Adapter, WrappedBinary},
+    address::address_to_script,
     error::{ContractError, ContractResult},
     xpub::Xpub,
 };
 use ibc_proto::cosmos::staking::v1beta1::{BondStatus, QueryValidatorResponse};
+use light_client_bitcoin::header::WrappedHeader;
+use light_client_bitcoin::msg::QueryMsg::{BlockHashAtHeight, HeaderHeight};
 use prost::Message;
-use std::str::FromStr;
 
 use cosmwasm_std::{
-    wasm_execute, Addr, Api, BankMsg, Binary, CosmosMsg, Env, MessageInfo, QuerierWrapper,
-    Response, Storage, Uint128,
+    wasm_execute, Addr, Api, BankMsg, Binary, Coin, CosmosMsg, Env, MessageInfo, Order,
+    QuerierWrapper, Response, StdResult, Storage, SubMsg, Uint128,
 };
 use oraiswap::asset::AssetInfo;
 use std::convert::TryInto;
@@ -42,6 +78,7 @@ pub fn update_config(
     swap_router_contract: Option<Addr>,
     token_factory_contract: Option<Addr>,
     osor_entry_point_contract: Option<Addr>,
+    relayer_fee_options: Option<Vec<Coin>>,
 ) -> ContractResult<Response> {
     let mut config = CONFIG.load(store)?;
     assert_eq!(info.sender, config.owner);
@@ -66,6 +103,27 @@ pub fn update_config(
         config.relayer_fee = relayer_fee;
     }
 
+    if let Some(relayer_fee_options) = relayer_fee_options {
+        for (i, option) in relayer_fee_options.iter().enumerate() {
+            if option.amount.is_zero() {
+                return Err(ContractError::App(format!(
+                    "Relayer fee for {} must not be zero",
+                    option.denom
+                )));
+            }
+            if relayer_fee_options[..i]
+                .iter()
+                .any(|other| other.denom == option.denom)
+            {
+                return Err(ContractError::App(format!(
+                    "Duplicate relayer fee option for {}",
+                    option.denom
+                )));
+            }
+        }
+        config.relayer_fee_options = relayer_fee_options;
+    }
+
     if let Some(token_fee) = token_fee {
         TOKEN_FEE_RATIO.save(store, &token_fee)?;
     }
@@ -96,6 +154,7 @@ pub fn update_checkpoint_config(
     config: CheckpointConfig,
 ) -> ContractResult<Response> {
     assert_eq!(info.sender, CONFIG.load(store)?.owner);
+    config.script_template.validate()?;
     CHECKPOINT_CONFIG.save(store, &config)?;
     Ok(Response::new().add_attribute("action", "update_checkpoint_config"))
 }
@@ -106,6 +165,13 @@ pub fn update_bitcoin_config(
     config: BitcoinConfig,
 ) -> ContractResult<Response> {
     assert_eq!(info.sender, CONFIG.load(store)?.owner);
+    // expired mint claims are minted to the recovery address, without one
+    // they could never be recovered
+    if config.mint_claim_expiry > 0 && config.mint_claim_recovery_address.is_none() {
+        return Err(ContractError::App(
+            "Mint claims can only expire to a recovery address".to_string(),
+        ));
+    }
     BITCOIN_CONFIG.save(store, &config)?;
     Ok(Response::new().add_attribute("action", "update_bitcoin_config"))
 }
@@ -133,9 +199,30 @@ pub fn update_foundation_keys(
         ))
 }
 
+pub fn update_foundation_policy(
+    store: &mut dyn Storage,
+    info: MessageInfo,
+    policy: FoundationPolicy,
+) -> ContractResult<Response> {
+    assert_eq!(info.sender, CONFIG.load(store)?.owner);
+    policy.validate()?;
+    FOUNDATION_POLICY.save(store, &policy)?;
+    Ok(Response::new()
+        .add_attribute("action", "update_foundation_policy")
+        .add_attribute(
+            "threshold",
+            format!("{}/{}", policy.threshold.0, policy.threshold.1),
+        )
+        .add_attribute(
+            "inactivity_timelock",
+            policy.inactivity_timelock.to_string(),
+        ))
+}
+
 pub fn relay_deposit(
     querier: &QuerierWrapper,
     env: Env,
+    info: MessageInfo,
     store: &mut dyn Storage,
     btc_tx: Adapter<Transaction>,
     btc_height: u32,
@@ -146,7 +233,24 @@ pub fn relay_deposit(
 ) -> ContractResult<Response> {
     // dest validation?
     let mut btc = Bitcoin::default();
-    let response = Response::new().add_attribute("action", "relay_deposit");
+    let mut response = Response::new().add_attribute("action", "relay_deposit");
+    if let Some(relayer_fee) = check_relayer_fee_payment(store, &info.funds)? {
+        let config = CONFIG.load(store)?;
+        response = response.add_message(BankMsg::Send {
+            to_address: config.relayer_fee_receiver.to_string(),
+            amount: vec![relayer_fee],
+        });
+    }
+    let recovery_txs = RECOVERY_TXS.len(store)?;
+    let notification = BridgeNotification::DepositProcessed {
+        txid: btc_tx.txid().to_string(),
+        vout: btc_vout,
+        receiver: dest.to_receiver_addr(),
+        amount: btc_tx
+            .output
+            .get(btc_vout as usize)
+            .map_or(0, |output| output.value.to_sat()),
+    };
     btc.relay_deposit(
         querier,
         &env,
@@ -159,10 +263,59 @@ pub fn relay_deposit(
         dest,
         false,
     )?;
+    record_new_recovery_txs(store, &env, recovery_txs)?;
 
-    Ok(response)
+    Ok(response.add_submessages(notification_msg(store, notification)?))
+}
+
+pub fn report_pending_deposit(
+    store: &mut dyn Storage,
+    env: Env,
+    info: MessageInfo,
+    txid: String,
+    vout: u32,
+    dest: Dest,
+    amount: u64,
+) -> ContractResult<Response> {
+    if !RELAYERS.has(store, &info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+    let parsed_txid = txid
+        .parse::<bitcoin::Txid>()
+        .map_err(|err| ContractError::App(err.to_string()))?;
+    let outpoint = bitcoin::OutPoint::new(parsed_txid, vout);
+    if Bitcoin::default()
+        .processed_outpoints
+        .contains(store, outpoint)
+    {
+        return Err(ContractError::OutputAlreadyRelayed {
+            txid: outpoint.txid.to_string(),
+            vout,
+        });
+    }
+
+    // keyed like the relayed outpoint, whatever the case of the reported txid
+    let txid = parsed_txid.to_string();
+    let now = bridge_time(&env);
+    let deposit = PendingDeposit {
+        txid: txid.clone(),
+        vout,
+        dest,
+        amount,
+        relayer: info.sender,
+        reported_at: now,
+        expires_at: now + PENDING_DEPOSIT_TTL,
+    };
+    save_pending_deposit(store, &deposit)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "report_pending_deposit")
+        .add_attribute("txid", txid)
+        .add_attribute("vout", vout.to_string())
+        .add_attribute("amount", amount.to_string()))
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn withdraw_to_bitcoin(
     store: &mut dyn Storage,
     querier: &QuerierWrapper,
@@ -171,254 +324,2655 @@ pub fn withdraw_to_bitcoin(
     env: Env,
     btc_address: String,
     fee: Option<u64>,
+    amount: Option<Uint128>,
+    priority: WithdrawalPriority,
 ) -> ContractResult<Response> {
-    let mut btc = Bitcoin::default();
     let mut cosmos_msgs: Vec<CosmosMsg> = vec![];
 
     let config = CONFIG.load(store)?;
-    let denom = get_full_btc_denom(config.token_factory_contract.as_str());
-    let script_pubkey = bitcoin::Address::from_str(btc_address.as_str())
-        .map_err(|err| ContractError::App(err.to_string()))?
-        .script_pubkey();
-
-    for fund in info.funds {
-        if fund.denom == denom {
-            let fee_data = process_deduct_fee(store, querier, api, fund.clone())?;
-            btc.add_withdrawal(
-                store,
-                Adapter::new(script_pubkey.clone()),
-                fee_data.deducted_amount,
-                fee,
-            )?;
-
-            // burn here
-            cosmos_msgs.push(
-                wasm_execute(
-                    config.token_factory_contract.as_str(),
-                    &tokenfactory::msg::ExecuteMsg::BurnTokens {
-                        amount: fee_data.deducted_amount,
-                        denom: fund.denom,
-                        burn_from_address: env.contract.address.to_string(),
-                    },
-                    vec![],
-                )?
-                .into(),
-            );
-
-            let relayer_fee = fee_data.relayer_fee;
-            if !relayer_fee.amount.is_zero() {
-                cosmos_msgs.push(
-                    (BankMsg::Send {
-                        to_address: config.relayer_fee_receiver.to_string(),
-                        amount: [relayer_fee].to_vec(),
-                    })
-                    .into(),
-                );
-            }
+    let script_pubkey = address_to_script(&btc_address, NETWORK)?;
+    if !is_script_allowed(store, &script_pubkey)? {
+        if amount.is_some() {
+            return Err(ContractError::App(
+                "Withdrawals to reviewed addresses take the whole sent amount".to_string(),
+            ));
+        }
+        return hold_withdrawal(store, env, info, btc_address, fee);
+    }
 
-            let token_fee = fee_data.token_fee;
-            if !token_fee.amount.is_zero() {
-                cosmos_msgs.push(
-                    (BankMsg::Send {
-                        to_address: config.token_fee_receiver.to_string(),
-                        amount: [token_fee].to_vec(),
-                    })
-                    .into(),
-                );
-            }
+    let mut funds = vec![];
+    for fund in info.funds.iter() {
+        if is_btc_denom(store, config.token_factory_contract.as_str(), &fund.denom)? {
+            funds.push(fund.clone());
         }
     }
+    if amount.is_some() && funds.len() != 1 {
+        return Err(ContractError::App(
+            "A withdrawal amount requires sending a single nBTC coin".to_string(),
+        ));
+    }
+
+    let fee_rate = priority_fee_rate(store, priority)?;
+    let mut withdrawal_fees = vec![];
+    for fund in funds {
+        let withdrawal_fee = queue_withdrawal(
+            store,
+            querier,
+            api,
+            &env,
+            info.sender.as_str(),
+            fund,
+            amount,
+            &script_pubkey,
+            fee,
+            Some(fee_rate),
+            &mut cosmos_msgs,
+        )?;
+        withdrawal_fees.push(withdrawal_fee);
+    }
 
-    let response = Response::new().add_attribute("action", "withdraw_to_bitcoin");
+    let response = record_withdrawals(
+        store,
+        &env,
+        &info.sender,
+        &script_pubkey,
+        withdrawal_fees,
+        Response::new()
+            .add_attribute("action", "withdraw_to_bitcoin")
+            .add_attribute("priority", priority.as_str()),
+    )?;
     Ok(response.add_messages(cosmos_msgs))
 }
 
-pub fn relay_checkpoint(
-    querier: &QuerierWrapper,
-    store: &mut dyn Storage,
-    btc_height: u32,
-    btc_proof: Adapter<PartialMerkleTree>,
-    cp_index: u32,
-) -> ContractResult<Response> {
-    let mut btc = Bitcoin::default();
-    let response = Response::new().add_attribute("action", "relay_checkpoint");
-    btc.relay_checkpoint(querier, store, btc_height, btc_proof, cp_index, false)?;
-    Ok(response)
+/// The fee rate to charge a withdrawal of the given priority at. A `Fast`
+/// withdrawal also marks the `Building` checkpoint so adaptive scheduling
+/// advances it without waiting for its other triggers.
+fn priority_fee_rate(store: &mut dyn Storage, priority: WithdrawalPriority) -> ContractResult<u64> {
+    let checkpoints = CheckpointQueue::default();
+    let fee_rate = checkpoints.building(store)?.fee_rate;
+    if priority == WithdrawalPriority::Fast {
+        FAST_WITHDRAWAL_CHECKPOINT.save(store, &checkpoints.index(store))?;
+    }
+    Ok(checkpoints
+        .config(store)
+        .priority_fee_rate(fee_rate, priority))
 }
 
-pub fn submit_checkpoint_signature(
-    api: &dyn Api,
+/// Withdraws `amount` of the granter's nBTC to `btc_address` under the
+/// withdrawal allowance it gave the sender. The withdrawal is recorded as the
+/// granter's.
+#[allow(clippy::too_many_arguments)]
+pub fn withdraw_on_behalf(
     store: &mut dyn Storage,
-    xpub: WrappedBinary<Xpub>,
-    sigs: Vec<Signature>,
-    cp_index: u32,
-    btc_height: u32,
-) -> ContractResult<Response> {
-    let btc = Bitcoin::default();
-    let mut checkpoints = btc.checkpoints;
-    checkpoints.sign(api, store, &xpub.0, sigs, cp_index, btc_height)?;
-    let response = Response::new().add_attribute("action", "submit_checkpoint_signature");
-    Ok(response)
-}
-
-pub fn submit_recovery_signature(
+    querier: &QuerierWrapper,
     api: &dyn Api,
-    store: &mut dyn Storage,
-    xpub: WrappedBinary<Xpub>,
-    sigs: Vec<Signature>,
+    info: MessageInfo,
+    env: Env,
+    granter: Addr,
+    btc_address: String,
+    amount: Option<Uint128>,
+    fee: Option<u64>,
+    priority: WithdrawalPriority,
 ) -> ContractResult<Response> {
-    let btc = Bitcoin::default();
-    let mut recovery_txs = btc.recovery_txs;
-    recovery_txs.sign(api, store, &xpub.0, sigs)?;
-    let response = Response::new().add_attribute("action", "submit_recovery_signature");
-    Ok(response)
+    if !info.funds.is_empty() {
+        return Err(ContractError::App(
+            "Withdrawals on behalf of another account take a fixed amount of its balance"
+                .to_string(),
+        ));
+    }
+    let amount = amount.ok_or_else(|| {
+        ContractError::App("The amount to withdraw on behalf of the granter is missing".to_string())
+    })?;
+    let script_pubkey = address_to_script(&btc_address, NETWORK)?;
+    if !is_script_allowed(store, &script_pubkey)? {
+        return Err(ContractError::App(
+            "Withdrawals to this address are reviewed and cannot be made on behalf of another account"
+                .to_string(),
+        ));
+    }
+
+    let key = (&granter, &info.sender);
+    let mut allowance = WITHDRAWAL_ALLOWANCES
+        .may_load(store, key)?
+        .filter(|allowance| !allowance.is_expired(bridge_time(&env)))
+        .ok_or_else(|| {
+            ContractError::App("No withdrawal allowance from the granter".to_string())
+        })?;
+    allowance.amount = allowance
+        .amount
+        .checked_sub(amount)
+        .map_err(|_| ContractError::App("Withdrawal exceeds the allowance".to_string()))?;
+    if allowance.amount.is_zero() {
+        WITHDRAWAL_ALLOWANCES.remove(store, key);
+    } else {
+        WITHDRAWAL_ALLOWANCES.save(store, key, &allowance)?;
+    }
+
+    let config = CONFIG.load(store)?;
+    let token_factory = config.token_factory_contract.as_str();
+    let denom = get_full_btc_denom(store, token_factory)?;
+    let fee_data = process_deduct_fee_for(
+        store,
+        querier,
+        api,
+        granter.as_str(),
+        Coin::new(amount.u128(), denom.clone()),
+    )?;
+    let fee_rate = priority_fee_rate(store, priority)?;
+    let withdrawal_fee = Bitcoin::default().add_withdrawal_at_rate(
+        store,
+        Adapter::new(script_pubkey.clone()),
+        fee_data.deducted_amount,
+        fee,
+        fee_rate,
+    )?;
+
+    let mut cosmos_msgs: Vec<CosmosMsg> = vec![wasm_execute(
+        token_factory,
+        &tokenfactory::msg::ExecuteMsg::BurnTokens {
+            amount,
+            denom,
+            burn_from_address: granter.to_string(),
+        },
+        vec![],
+    )?
+    .into()];
+    mint_withdrawal_fees(&config, fee_data, &mut cosmos_msgs)?;
+
+    let response = record_withdrawals(
+        store,
+        &env,
+        &granter,
+        &script_pubkey,
+        vec![withdrawal_fee],
+        Response::new()
+            .add_attribute("action", "withdraw_to_bitcoin")
+            .add_attribute("granter", granter.as_str())
+            .add_attribute("spender", info.sender.as_str())
+            .add_attribute("remaining_allowance", allowance.amount.to_string()),
+    )?;
+    Ok(response.add_messages(cosmos_msgs))
 }
 
-pub fn set_signatory_key(
-    querier: &QuerierWrapper,
+pub fn set_withdrawal_allowance(
     store: &mut dyn Storage,
+    env: Env,
     info: MessageInfo,
-    xpub: WrappedBinary<Xpub>,
+    spender: Addr,
+    amount: Uint128,
+    expiry: Option<u64>,
 ) -> ContractResult<Response> {
-    let mut btc = Bitcoin::default();
-    btc.set_signatory_key(querier, store, info.sender, xpub.0)?;
-    let response = Response::new().add_attribute("action", "set_signatory_key");
-    Ok(response)
+    if spender == info.sender {
+        return Err(ContractError::App(
+            "Cannot give a withdrawal allowance to oneself".to_string(),
+        ));
+    }
+    let allowance = WithdrawalAllowance { amount, expiry };
+    if !amount.is_zero() && allowance.is_expired(bridge_time(&env)) {
+        return Err(ContractError::App(
+            "Withdrawal allowance expiry is in the past".to_string(),
+        ));
+    }
+    let key = (&info.sender, &spender);
+    if amount.is_zero() {
+        WITHDRAWAL_ALLOWANCES.remove(store, key);
+    } else {
+        WITHDRAWAL_ALLOWANCES.save(store, key, &allowance)?;
+    }
+    Ok(Response::new()
+        .add_attribute("action", "set_withdrawal_allowance")
+        .add_attribute("granter", info.sender.as_str())
+        .add_attribute("spender", spender.as_str())
+        .add_attribute("amount", amount.to_string())
+        .add_attribute(
+            "expiry",
+            expiry.map_or_else(|| "none".to_string(), |expiry| expiry.to_string()),
+        ))
 }
 
-pub fn add_validators(
+/// Quotes the miner fee of a withdrawal at the fee rate of the building
+/// checkpoint, locking it for `withdrawal_quote_ttl` seconds.
+pub fn prepare_withdrawal(
     store: &mut dyn Storage,
+    env: Env,
     info: MessageInfo,
-    addrs: Vec<String>,
-    voting_powers: Vec<u64>,
-    consensus_keys: Vec<ConsensusKey>,
+    btc_address: String,
+    amount: Uint128,
 ) -> ContractResult<Response> {
-    assert_eq!(info.sender, CONFIG.load(store)?.owner);
-    assert_eq!(addrs.len(), voting_powers.len());
-    assert_eq!(addrs.len(), consensus_keys.len());
-
-    for i in 0..addrs.len() {
-        let addr = &addrs[i];
-        let power = voting_powers[i];
-        let cons_key = &consensus_keys[i];
-
-        SIGNERS.save(store, addr, cons_key)?;
-        VALIDATORS.save(store, cons_key, &(power, addr.clone()))?;
+    let btc = Bitcoin::default();
+    let config = btc.config(store)?;
+    if config.withdrawal_quote_ttl == 0 {
+        return Err(ContractError::App(
+            "Prepared withdrawals are disabled".to_string(),
+        ));
     }
-    let response = Response::new().add_attribute("action", "add_validators");
-    Ok(response)
+    let script_pubkey = address_to_script(&btc_address, NETWORK)?;
+    let fee_rate = btc.checkpoints.building(store)?.fee_rate;
+    let miner_fee =
+        btc.calc_minimum_withdrawal_fees(store, script_pubkey.len() as u64, fee_rate)?;
+    if amount.u128() <= miner_fee as u128 {
+        return Err(ContractError::App(
+            "Withdrawal is too small to pay its miner fee".to_string(),
+        ));
+    }
+
+    let id = WITHDRAWAL_QUOTE_COUNT.may_load(store)?.unwrap_or_default() + 1;
+    WITHDRAWAL_QUOTE_COUNT.save(store, &id)?;
+    let expires_at = bridge_time(&env) + config.withdrawal_quote_ttl;
+    WITHDRAWAL_QUOTES.save(
+        store,
+        id,
+        &WithdrawalQuote {
+            sender: info.sender,
+            btc_address,
+            amount,
+            fee_rate,
+            miner_fee,
+            expires_at,
+            withdrawal_id: None,
+        },
+    )?;
+    Ok(Response::new()
+        .add_attribute("action", "prepare_withdrawal")
+        .add_attribute("quote_id", id.to_string())
+        .add_attribute("miner_fee", miner_fee.to_string())
+        .add_attribute("fee_rate", fee_rate.to_string())
+        .add_attribute("expires_at", expires_at.to_string()))
 }
 
-pub fn register_validator(
+/// Queues the withdrawal quoted by `prepare_withdrawal`, charging the locked
+/// fee rate even if the rate of the building checkpoint changed since.
+pub fn execute_withdrawal(
     store: &mut dyn Storage,
     querier: &QuerierWrapper,
+    api: &dyn Api,
     info: MessageInfo,
+    env: Env,
+    quote_id: u64,
 ) -> ContractResult<Response> {
-    let permission = WHITELIST_VALIDATORS.has(store, info.sender.clone());
-    if !permission {
-        return Err(ContractError::ValidatorUnwhitelisted {});
+    let mut quote = WITHDRAWAL_QUOTES
+        .may_load(store, quote_id)?
+        .ok_or_else(|| ContractError::App("Withdrawal quote not found".to_string()))?;
+    if quote.sender != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    if let Some(withdrawal_id) = quote.withdrawal_id {
+        return Err(ContractError::App(format!(
+            "Withdrawal quote was already executed as withdrawal {}",
+            withdrawal_id
+        )));
+    }
+    if bridge_time(&env) > quote.expires_at {
+        return Err(ContractError::App(
+            "Withdrawal quote has expired".to_string(),
+        ));
     }
 
-    let sender = info.sender;
-    let val_addr = convert_addr_by_prefix(sender.as_str(), VALIDATOR_ADDRESS_PREFIX);
-    let binary_validator_result = fetch_staking_validator(querier, val_addr).unwrap();
-    let validator_response =
-        QueryValidatorResponse::decode(binary_validator_result.as_slice()).unwrap();
-    let validator: ibc_proto::cosmos::staking::v1beta1::Validator =
-        validator_response.validator.unwrap();
-    if validator.jailed {
-        return Err(ContractError::ValidatorJailed {});
+    let token_factory = CONFIG.load(store)?.token_factory_contract;
+    let mut funds = vec![];
+    for fund in &info.funds {
+        if is_btc_denom(store, token_factory.as_str(), &fund.denom)? {
+            funds.push(fund.clone());
+        }
     }
-    if validator.consensus_pubkey.is_none() {
-        return Err(ContractError::ValidatorNoConsensusPubKey {});
+    if funds.len() != 1 || funds[0].amount != quote.amount {
+        return Err(ContractError::App(format!(
+            "Exactly {} nBTC must be sent to execute the withdrawal quote",
+            quote.amount
+        )));
     }
-    if validator.status != BondStatus::Bonded as i32 {
-        return Err(ContractError::ValidatorNotBonded {});
+
+    let script_pubkey = address_to_script(&quote.btc_address, NETWORK)?;
+    if !is_script_allowed(store, &script_pubkey)? {
+        let btc_address = quote.btc_address.clone();
+        return hold_withdrawal(store, env, info, btc_address, Some(quote.miner_fee));
     }
-    let cons_key: [u8; 32] = validator
-        .consensus_pubkey
-        .unwrap()
-        .value
-        .rchunks(32)
-        .next()
-        .unwrap()
-        .try_into()
-        .expect("Failed to convert &[u8] to [u8; 32]");
-    let voting_power: u64 = validator.tokens.parse().expect("Cannot parse voting power");
-    SIGNERS.save(store, sender.as_str(), &cons_key)?;
-    VALIDATORS.save(
+
+    let mut cosmos_msgs = vec![];
+    let withdrawal_fee = queue_withdrawal(
         store,
-        &cons_key,
-        &(voting_power, sender.clone().into_string()),
+        querier,
+        api,
+        &env,
+        info.sender.as_str(),
+        funds.remove(0),
+        Some(quote.amount),
+        &script_pubkey,
+        None,
+        Some(quote.fee_rate),
+        &mut cosmos_msgs,
     )?;
-    let response = Response::new()
-        .add_attribute("action", "register_validator")
-        .add_attribute("sender", sender)
-        .add_attribute("consensus_key", Binary::from(cons_key).to_string())
-        .add_attribute("voting_power", voting_power.to_string());
-    Ok(response)
+    let response = record_withdrawals(
+        store,
+        &env,
+        &info.sender,
+        &script_pubkey,
+        vec![withdrawal_fee],
+        Response::new()
+            .add_attribute("action", "execute_withdrawal")
+            .add_attribute("quote_id", quote_id.to_string()),
+    )?;
+    quote.withdrawal_id = Some(WITHDRAWAL_COUNT.load(store)?);
+    WITHDRAWAL_QUOTES.save(store, quote_id, &quote)?;
+    Ok(response.add_messages(cosmos_msgs))
 }
 
-pub fn register_denom(
+/// Saves the withdrawals queued for `sender`, adding their ids and fees to
+/// the response.
+fn record_withdrawals(
     store: &mut dyn Storage,
-    info: MessageInfo,
-    subdenom: String,
-    metadata: Option<Metadata>,
+    env: &Env,
+    sender: &Addr,
+    script_pubkey: &Script,
+    withdrawal_fees: Vec<(WithdrawalFee, WithdrawalPosition)>,
+    mut response: Response,
 ) -> ContractResult<Response> {
-    assert_eq!(info.sender, CONFIG.load(store)?.owner);
-    let config = CONFIG.load(store)?;
-    let msg = wasm_execute(
-        config.token_factory_contract,
-        &tokenfactory::msg::ExecuteMsg::CreateDenom { subdenom, metadata },
-        info.funds,
-    )?;
+    if !withdrawal_fees.is_empty() {
+        ACCOUNT_ACTIVITY.save(store, sender.as_str(), &bridge_time(env))?;
+    }
+    for (withdrawal_fee, position) in withdrawal_fees {
+        let id = WITHDRAWAL_COUNT.may_load(store)?.unwrap_or_default() + 1;
+        WITHDRAWAL_COUNT.save(store, &id)?;
+        WITHDRAWALS.save(
+            store,
+            id,
+            &QueuedWithdrawal {
+                sender: sender.clone(),
+                script_pubkey: Binary::from(script_pubkey.to_bytes()),
+                value: withdrawal_fee.value,
+                fee: withdrawal_fee.fee,
+                sponsored: 0,
+                checkpoint_index: position.checkpoint_index,
+                output_index: position.output_index,
+                fee_index: position.fee_index,
+            },
+        )?;
+        response = response
+            .add_attribute("withdrawal_id", id.to_string())
+            .add_attribute("miner_fee", withdrawal_fee.fee.to_string())
+            .add_attribute("fee_rate", withdrawal_fee.fee_rate.to_string());
+    }
+    Ok(response)
+}
 
-    Ok(Response::new()
-        .add_message(msg)
-        .add_attribute("action", "register_denom"))
+/// Whether withdrawals may pay to the script under the configured script
+/// screening.
+fn is_script_allowed(store: &dyn Storage, script_pubkey: &Script) -> ContractResult<bool> {
+    let listed = SCREENED_SCRIPTS.has(store, script_pubkey.as_bytes());
+    Ok(
+        match BITCOIN_CONFIG.load(store)?.withdrawal_script_screening {
+            ScriptScreening::Disabled => true,
+            ScriptScreening::Whitelist => listed,
+            ScriptScreening::Blacklist => !listed,
+        },
+    )
 }
 
-// USE THIS WHEN WE HAVE TO CHANGE TO ANOTHER BRIDGE CONTRACT
-pub fn change_btc_denom_owner(
+/// Holds the sent nBTC of a withdrawal to a rejected script for review
+/// instead of burning it. Only the sent nBTC is held, a `max` withdrawal
+/// does not include the rest of the sender's balance.
+fn hold_withdrawal(
     store: &mut dyn Storage,
+    env: Env,
     info: MessageInfo,
-    new_owner: String,
+    btc_address: String,
+    fee: Option<u64>,
 ) -> ContractResult<Response> {
-    let config = CONFIG.load(store)?;
-    assert_eq!(info.sender, config.owner);
+    let token_factory = CONFIG.load(store)?.token_factory_contract;
+    let mut funds = vec![];
+    for fund in info.funds {
+        if is_btc_denom(store, token_factory.as_str(), &fund.denom)? {
+            funds.push(fund);
+        }
+    }
+    if funds.is_empty() {
+        return Err(ContractError::App(
+            "Withdrawals to this address are reviewed, the nBTC to withdraw must be sent"
+                .to_string(),
+        ));
+    }
 
-    let denom = get_full_btc_denom(config.token_factory_contract.as_str());
-    let msg = wasm_execute(
-        config.token_factory_contract,
-        &tokenfactory::msg::ExecuteMsg::ChangeDenomOwner {
-            denom,
-            new_admin_address: new_owner,
+    let id = HELD_WITHDRAWAL_COUNT.may_load(store)?.unwrap_or_default() + 1;
+    HELD_WITHDRAWAL_COUNT.save(store, &id)?;
+    HELD_WITHDRAWALS.save(
+        store,
+        id,
+        &HeldWithdrawal {
+            sender: info.sender,
+            btc_address,
+            funds,
+            fee,
+            held_at: bridge_time(&env),
         },
-        info.funds,
     )?;
-
     Ok(Response::new()
-        .add_message(msg)
-        .add_attribute("action", "change_btc_denom_owner"))
+        .add_attribute("action", "withdraw_to_bitcoin")
+        .add_attribute("held_withdrawal_id", id.to_string()))
 }
 
-pub fn set_whitelist_validator(
-    store: &mut dyn Storage,
-    info: MessageInfo,
-    val_addr: Addr,
-    permission: bool,
-) -> ContractResult<Response> {
-    let config = CONFIG.load(store)?;
-    assert_eq!(info.sender, config.owner);
-    if permission {
-        WHITELIST_VALIDATORS
+/// Burns the sent nBTC without adding a checkpoint output, recording it in
+/// the burn ledger. Burned nBTC no longer backs any BTC, so it first repays
+/// the bad debt.
+pub fn burn(store: &mut dyn Storage, env: Env, info: MessageInfo) -> ContractResult<Response> {
+    let token_factory = CONFIG.load(store)?.token_factory_contract;
+    let mut amount = Uint128::zero();
+    let mut cosmos_msgs: Vec<CosmosMsg> = vec![];
+    for fund in info.funds {
+        if !is_btc_denom(store, token_factory.as_str(), &fund.denom)? {
+            return Err(ContractError::App(format!(
+                "Only nBTC can be burned, got {}",
+                fund.denom
+            )));
+        }
+        amount += fund.amount;
+        cosmos_msgs.push(
+            wasm_execute(
+                token_factory.as_str(),
+                &tokenfactory::msg::ExecuteMsg::BurnTokens {
+                    amount: fund.amount,
+                    denom: fund.denom,
+                    burn_from_address: env.contract.address.to_string(),
+                },
+                vec![],
+            )?
+            .into(),
+        );
+    }
+    if amount.is_zero() {
+        return Err(ContractError::App("No nBTC sent to burn".to_string()));
+    }
+
+    let bad_debt = BAD_DEBT.may_load(store)?.unwrap_or_default();
+    let bad_debt_repaid = bad_debt.min(amount);
+    BAD_DEBT.save(store, &(bad_debt - bad_debt_repaid))?;
+    let total_burned = TOTAL_BURNED.may_load(store)?.unwrap_or_default() + amount;
+    TOTAL_BURNED.save(store, &total_burned)?;
+
+    let id = BURN_COUNT.may_load(store)?.unwrap_or_default() + 1;
+    BURN_COUNT.save(store, &id)?;
+    BURNS.save(
+        store,
+        id,
+        &BurnRecord {
+            burner: info.sender,
+            amount,
+            bad_debt_repaid,
+            burned_at: bridge_time(&env),
+        },
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("action", "burn")
+        .add_attribute("burn_id", id.to_string())
+        .add_attribute("amount", amount.to_string())
+        .add_attribute("bad_debt_repaid", bad_debt_repaid.to_string())
+        .add_messages(cosmos_msgs))
+}
+
+/// Pays back up to the miner fee deducted from a queued withdrawal with the
+/// sent nBTC, raising its output value. Only possible while the withdrawal is
+/// in the building checkpoint; nBTC beyond the outstanding fee is refunded.
+pub fn sponsor_withdrawal_fee(
+    store: &mut dyn Storage,
+    env: Env,
+    info: MessageInfo,
+    withdrawal_id: u64,
+) -> ContractResult<Response> {
+    let config = CONFIG.load(store)?;
+    let units_per_sat = BITCOIN_CONFIG.load(store)?.units_per_sat;
+    let denom = get_full_btc_denom(store, config.token_factory_contract.as_str())?;
+    let mut withdrawal = WITHDRAWALS
+        .may_load(store, withdrawal_id)?
+        .ok_or_else(|| ContractError::App("Withdrawal not found".to_string()))?;
+
+    let mut paid = 0u64;
+    for fund in &info.funds {
+        if fund.denom != denom {
+            return Err(ContractError::App(format!(
+                "Withdrawal fees can only be sponsored with {}",
+                denom
+            )));
+        }
+        paid = u64::try_from(fund.amount.u128())
+            .ok()
+            .and_then(|amount| paid.checked_add(amount))
+            .ok_or_else(|| ContractError::App("Sponsorship amount overflow".to_string()))?;
+    }
+    let outstanding = withdrawal.fee - withdrawal.sponsored;
+    let sponsored_sats = std::cmp::min(paid, outstanding) / units_per_sat;
+    if sponsored_sats == 0 {
+        return Err(ContractError::App(
+            "Sponsorship does not cover any of the outstanding fee".to_string(),
+        ));
+    }
+    let accepted = sponsored_sats * units_per_sat;
+
+    let mut btc = Bitcoin::default();
+    btc.increase_withdrawal_value(
+        store,
+        WithdrawalPosition {
+            checkpoint_index: withdrawal.checkpoint_index,
+            output_index: withdrawal.output_index,
+            fee_index: withdrawal.fee_index,
+        },
+        sponsored_sats,
+    )?;
+    withdrawal.value += sponsored_sats;
+    withdrawal.sponsored += accepted;
+    WITHDRAWALS.save(store, withdrawal_id, &withdrawal)?;
+
+    let mut response = Response::new()
+        .add_attribute("action", "sponsor_withdrawal_fee")
+        .add_attribute("withdrawal_id", withdrawal_id.to_string())
+        .add_attribute("sponsored", accepted.to_string())
+        .add_message(wasm_execute(
+            config.token_factory_contract.as_str(),
+            &tokenfactory::msg::ExecuteMsg::BurnTokens {
+                amount: accepted.into(),
+                denom: denom.clone(),
+                burn_from_address: env.contract.address.to_string(),
+            },
+            vec![],
+        )?);
+    if paid > accepted {
+        response = response.add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: vec![Coin {
+                denom,
+                amount: (paid - accepted).into(),
+            }],
+        });
+    }
+    Ok(response)
+}
+
+pub fn commit_data(
+    store: &mut dyn Storage,
+    env: Env,
+    info: MessageInfo,
+    data: Binary,
+    fee: u64,
+) -> ContractResult<Response> {
+    let min_fee = CHECKPOINT_CONFIG.load(store)?.app_data_fee;
+    if min_fee == 0 {
+        return Err(ContractError::App(
+            "Committing app data is disabled".to_string(),
+        ));
+    }
+    if fee < min_fee {
+        return Err(ContractError::App(format!(
+            "App data fee must be at least {} sats",
+            min_fee
+        )));
+    }
+    if data.len() != 32 {
+        return Err(ContractError::App("App data must be 32 bytes".to_string()));
+    }
+
+    let config = CONFIG.load(store)?;
+    let units_per_sat = BITCOIN_CONFIG.load(store)?.units_per_sat;
+    let denom = get_full_btc_denom(store, config.token_factory_contract.as_str())?;
+    let mut paid = 0u64;
+    for fund in &info.funds {
+        if fund.denom != denom {
+            return Err(ContractError::App(format!(
+                "App data fees can only be paid with {}",
+                denom
+            )));
+        }
+        paid = u64::try_from(fund.amount.u128())
+            .ok()
+            .and_then(|amount| paid.checked_add(amount))
+            .ok_or_else(|| ContractError::App("App data payment overflow".to_string()))?;
+    }
+    let accepted = fee
+        .checked_mul(units_per_sat)
+        .ok_or_else(|| ContractError::App("App data fee overflow".to_string()))?;
+    if paid < accepted {
+        return Err(ContractError::App(
+            "Sent funds do not cover the app data fee".to_string(),
+        ));
+    }
+
+    let mut btc = Bitcoin::default();
+    let index = btc.checkpoints.index(store);
+    let mut committed = APP_DATA.may_load(store, index)?.unwrap_or_default();
+    if committed.contains(&data) {
+        return Err(ContractError::App("App data already committed".to_string()));
+    }
+    if committed.len() >= MAX_APP_DATA_PER_CHECKPOINT {
+        return Err(ContractError::App(
+            "Checkpoint app data slots are full".to_string(),
+        ));
+    }
+    committed.push(data);
+    APP_DATA.save(store, index, &committed)?;
+    btc.give_miner_fee(store, accepted.into())?;
+
+    let mut response = Response::new()
+        .add_attribute("action", "commit_data")
+        .add_attribute("checkpoint_index", index.to_string())
+        .add_attribute("leaf_index", committed.len().to_string())
+        .add_message(wasm_execute(
+            config.token_factory_contract.as_str(),
+            &tokenfactory::msg::ExecuteMsg::BurnTokens {
+                amount: accepted.into(),
+                denom: denom.clone(),
+                burn_from_address: env.contract.address.to_string(),
+            },
+            vec![],
+        )?);
+    if paid > accepted {
+        response = response.add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: vec![Coin {
+                denom,
+                amount: (paid - accepted).into(),
+            }],
+        });
+    }
+    Ok(response)
+}
+
+/// Deducts the bridge fees from `amount` of `fund` and queues the rest to be
+/// withdrawn to `script_pubkey`, pushing the messages burning the withdrawn
+/// nBTC, paying out the fees and refunding the rest of `fund`. Without an
+/// amount the whole fund is withdrawn and the part below one satoshi is added
+/// to the miner fee, so no dust is left.
+#[allow(clippy::too_many_arguments)]
+fn queue_withdrawal(
+    store: &mut dyn Storage,
+    querier: &QuerierWrapper,
+    api: &dyn Api,
+    env: &Env,
+    sender: &str,
+    fund: Coin,
+    amount: Option<Uint128>,
+    script_pubkey: &Script,
+    mut fee: Option<u64>,
+    fee_rate: Option<u64>,
+    cosmos_msgs: &mut Vec<CosmosMsg>,
+) -> ContractResult<(WithdrawalFee, WithdrawalPosition)> {
+    let mut btc = Bitcoin::default();
+    let config = CONFIG.load(store)?;
+    let withdrawn = amount.unwrap_or(fund.amount);
+    let refund = fund
+        .amount
+        .checked_sub(withdrawn)
+        .map_err(|_| ContractError::App("Withdrawal amount exceeds the sent nBTC".to_string()))?;
+    let fee_data = process_deduct_fee_for(
+        store,
+        querier,
+        api,
+        sender,
+        Coin::new(withdrawn.u128(), fund.denom.clone()),
+    )?;
+    let fee_rate = match fee_rate {
+        Some(fee_rate) => fee_rate,
+        None => CheckpointQueue::default().building(store)?.fee_rate,
+    };
+    if amount.is_none() {
+        let units_per_sat = btc.config(store)?.units_per_sat;
+        let miner_fee = btc
+            .calc_minimum_withdrawal_fees(store, script_pubkey.len() as u64, fee_rate)?
+            .max(fee.unwrap_or(0));
+        let remainder = (fee_data.deducted_amount.u128() as u64)
+            .checked_sub(miner_fee)
+            .map_or(0, |value| value % units_per_sat);
+        fee = Some(miner_fee + remainder);
+    }
+    let withdrawal_fee = btc.add_withdrawal_at_rate(
+        store,
+        Adapter::new(script_pubkey.to_owned()),
+        fee_data.deducted_amount,
+        fee,
+        fee_rate,
+    )?;
+
+    // burn here
+    cosmos_msgs.push(
+        wasm_execute(
+            config.token_factory_contract.as_str(),
+            &tokenfactory::msg::ExecuteMsg::BurnTokens {
+                amount: fee_data.deducted_amount,
+                denom: fund.denom.clone(),
+                burn_from_address: env.contract.address.to_string(),
+            },
+            vec![],
+        )?
+        .into(),
+    );
+
+    let relayer_fee = fee_data.relayer_fee;
+    if !relayer_fee.amount.is_zero() {
+        cosmos_msgs.push(
+            (BankMsg::Send {
+                to_address: config.relayer_fee_receiver.to_string(),
+                amount: [relayer_fee].to_vec(),
+            })
+            .into(),
+        );
+    }
+
+    let token_fee = fee_data.token_fee;
+    if !token_fee.amount.is_zero() {
+        cosmos_msgs.push(
+            (BankMsg::Send {
+                to_address: config.token_fee_receiver.to_string(),
+                amount: [token_fee].to_vec(),
+            })
+            .into(),
+        );
+    }
+
+    if !refund.is_zero() {
+        cosmos_msgs.push(
+            (BankMsg::Send {
+                to_address: sender.to_string(),
+                amount: vec![Coin::new(refund.u128(), fund.denom)],
+            })
+            .into(),
+        );
+    }
+
+    Ok(withdrawal_fee)
+}
+
+/// Mints the relayer and token fees of a withdrawal to their receivers, for
+/// withdrawals burning the whole amount from the holder's balance.
+fn mint_withdrawal_fees(
+    config: &Config,
+    fee_data: FeeData,
+    cosmos_msgs: &mut Vec<CosmosMsg>,
+) -> ContractResult<()> {
+    let fees = [
+        (fee_data.relayer_fee, &config.relayer_fee_receiver),
+        (fee_data.token_fee, &config.token_fee_receiver),
+    ];
+    for (coin, receiver) in fees {
+        if coin.amount.is_zero() {
+            continue;
+        }
+        cosmos_msgs.push(
+            wasm_execute(
+                config.token_factory_contract.as_str(),
+                &tokenfactory::msg::ExecuteMsg::MintTokens {
+                    denom: coin.denom,
+                    amount: coin.amount,
+                    mint_to_address: receiver.to_string(),
+                },
+                vec![],
+            )?
+            .into(),
+        );
+    }
+    Ok(())
+}
+
+/// Escrows the sent nBTC for a withdrawal to Lightning through the given swap
+/// provider, which pays the invoice of `payment_hash` once it accepts the swap.
+/// The HTLC timeout is counted from the current Bitcoin height, so a provider
+/// accepting late can't extend the time the user's refund is locked.
+#[allow(clippy::too_many_arguments)]
+pub fn withdraw_to_lightning(
+    store: &mut dyn Storage,
+    querier: &QuerierWrapper,
+    env: Env,
+    info: MessageInfo,
+    provider: Addr,
+    payment_hash: Binary,
+    refund_pubkey: Binary,
+    fee: Option<u64>,
+) -> ContractResult<Response> {
+    let swap_provider = SWAP_PROVIDERS
+        .may_load(store, &provider)?
+        .ok_or_else(|| ContractError::App("Swap provider is not registered".to_string()))?;
+    // reject malformed hashes and keys before escrowing
+    htlc_script(
+        &payment_hash,
+        &swap_provider.claim_pubkey,
+        &refund_pubkey,
+        0,
+    )?;
+
+    let config = CONFIG.load(store)?;
+    let denom = get_full_btc_denom(store, config.token_factory_contract.as_str())?;
+    let amount = match info.funds.as_slice() {
+        [fund] if fund.denom == denom => fund.amount,
+        _ => {
+            return Err(ContractError::App(format!(
+                "Lightning withdrawals must send only {}",
+                denom
+            )))
+        }
+    };
+    let btc_height: u32 =
+        querier.query_wasm_smart(config.light_client_contract.as_str(), &HeaderHeight {})?;
+    let lock_height = btc_height + swap_provider.htlc_timeout_blocks;
+
+    let id = LIGHTNING_SWAP_COUNT.may_load(store)?.unwrap_or_default() + 1;
+    LIGHTNING_SWAP_COUNT.save(store, &id)?;
+    LIGHTNING_SWAPS.save(
+        store,
+        id,
+        &LightningSwap {
+            id,
+            sender: info.sender,
+            provider,
+            payment_hash,
+            refund_pubkey,
+            amount,
+            fee,
+            created_at: env.block.time.seconds(),
+            lock_height,
+        },
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("action", "withdraw_to_lightning")
+        .add_attribute("swap_id", id.to_string())
+        .add_attribute("lock_height", lock_height.to_string()))
+}
+
+/// Accepts an escrowed Lightning withdrawal, committing the provider to pay
+/// its invoice. The nBTC is burned and paid out to the HTLC output in the
+/// building checkpoint.
+pub fn accept_lightning_swap(
+    store: &mut dyn Storage,
+    querier: &QuerierWrapper,
+    api: &dyn Api,
+    env: Env,
+    info: MessageInfo,
+    swap_id: u64,
+) -> ContractResult<Response> {
+    let swap = LIGHTNING_SWAPS.load(store, swap_id)?;
+    if info.sender != swap.provider {
+        return Err(ContractError::Unauthorized {});
+    }
+    let swap_provider = SWAP_PROVIDERS
+        .may_load(store, &swap.provider)?
+        .ok_or_else(|| ContractError::App("Swap provider is not registered".to_string()))?;
+
+    let config = CONFIG.load(store)?;
+    let btc_height: u32 =
+        querier.query_wasm_smart(config.light_client_contract.as_str(), &HeaderHeight {})?;
+    let lock_height = match swap.lock_height {
+        0 => btc_height + swap_provider.htlc_timeout_blocks,
+        lock_height => lock_height,
+    };
+    if btc_height >= lock_height {
+        return Err(ContractError::App(
+            "The HTLC timeout of the swap has passed".to_string(),
+        ));
+    }
+    let witness_script = htlc_script(
+        &swap.payment_hash,
+        &swap_provider.claim_pubkey,
+        &swap.refund_pubkey,
+        lock_height,
+    )?;
+
+    let denom = get_full_btc_denom(store, config.token_factory_contract.as_str())?;
+    let mut cosmos_msgs = vec![];
+    let (withdrawal_fee, _) = queue_withdrawal(
+        store,
+        querier,
+        api,
+        &env,
+        swap.sender.as_str(),
+        Coin {
+            denom,
+            amount: swap.amount,
+        },
+        Some(swap.amount),
+        &witness_script.to_p2wsh(),
+        swap.fee,
+        None,
+        &mut cosmos_msgs,
+    )?;
+    LIGHTNING_SWAPS.remove(store, swap_id);
+
+    Ok(Response::new()
+        .add_attribute("action", "accept_lightning_swap")
+        .add_attribute("swap_id", swap_id.to_string())
+        .add_attribute("lock_height", lock_height.to_string())
+        .add_attribute("htlc_script", hex::encode(witness_script.as_bytes()))
+        .add_attribute("miner_fee", withdrawal_fee.fee.to_string())
+        .add_messages(cosmos_msgs))
+}
+
+/// Returns the escrowed nBTC of a Lightning withdrawal which its provider did
+/// not accept in time, or whose provider was removed.
+pub fn refund_lightning_swap(
+    store: &mut dyn Storage,
+    env: Env,
+    info: MessageInfo,
+    swap_id: u64,
+) -> ContractResult<Response> {
+    let swap = LIGHTNING_SWAPS.load(store, swap_id)?;
+    if info.sender != swap.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    if let Some(swap_provider) = SWAP_PROVIDERS.may_load(store, &swap.provider)? {
+        if env.block.time.seconds() < swap.created_at + swap_provider.accept_timeout {
+            return Err(ContractError::App(
+                "Swap can not be refunded before its accept timeout".to_string(),
+            ));
+        }
+    }
+    LIGHTNING_SWAPS.remove(store, swap_id);
+
+    let config = CONFIG.load(store)?;
+    Ok(Response::new()
+        .add_attribute("action", "refund_lightning_swap")
+        .add_attribute("swap_id", swap_id.to_string())
+        .add_message(BankMsg::Send {
+            to_address: swap.sender.to_string(),
+            amount: vec![Coin {
+                denom: get_full_btc_denom(store, config.token_factory_contract.as_str())?,
+                amount: swap.amount,
+            }],
+        }))
+}
+
+pub fn set_swap_provider(
+    store: &mut dyn Storage,
+    info: MessageInfo,
+    provider: Addr,
+    swap_provider: Option<SwapProvider>,
+) -> ContractResult<Response> {
+    let config = CONFIG.load(store)?;
+    assert_eq!(info.sender, config.owner);
+    match swap_provider {
+        Some(swap_provider) => {
+            bitcoin::PublicKey::from_slice(&swap_provider.claim_pubkey)
+                .map_err(|err| ContractError::App(format!("Invalid claim public key: {}", err)))?;
+            SWAP_PROVIDERS.save(store, &provider, &swap_provider)?;
+        }
+        None => SWAP_PROVIDERS.remove(store, &provider),
+    }
+    Ok(Response::new()
+        .add_attribute("action", "set_swap_provider")
+        .add_attribute("provider", provider))
+}
+
+/// Records the recovery transactions queued after the first `prev_len`.
+pub(super) fn record_new_recovery_txs(
+    store: &mut dyn Storage,
+    env: &Env,
+    prev_len: u32,
+) -> ContractResult<()> {
+    let recovery_txs = Bitcoin::default().recovery_txs;
+    for index in prev_len..RECOVERY_TXS.len(store)? {
+        let txid = recovery_txs.txid(store, index)?.to_string();
+        record_change(store, env.block.height, Change::RecoveryTxCreated { txid })?;
+    }
+    Ok(())
+}
+
+/// Records the checkpoints confirmed after `prev_confirmed`, returning their
+/// notifications and hook messages.
+fn record_confirmed_checkpoints(
+    store: &mut dyn Storage,
+    env: &Env,
+    prev_confirmed: Option<u32>,
+    btc_height: u32,
+) -> ContractResult<Vec<SubMsg>> {
+    let Some(confirmed) = CONFIRMED_INDEX.may_load(store)? else {
+        return Ok(vec![]);
+    };
+    let first = prev_confirmed.map_or(0, |index| index + 1);
+    let mut notifications = vec![];
+    for index in first..=confirmed {
+        record_change(
+            store,
+            env.block.height,
+            Change::CheckpointConfirmed { index },
+        )?;
+        let notification = BridgeNotification::CheckpointConfirmed { index, btc_height };
+        notifications.extend(notification_msg(store, notification)?);
+        let hook_msg = CheckpointHookMsg::CheckpointConfirmed { index, btc_height };
+        notifications.extend(checkpoint_hook_msgs(store, hook_msg)?);
+    }
+    Ok(notifications)
+}
+
+pub fn relay_checkpoint(
+    querier: &QuerierWrapper,
+    store: &mut dyn Storage,
+    env: &Env,
+    info: MessageInfo,
+    btc_height: u32,
+    btc_proof: Adapter<PartialMerkleTree>,
+    cp_index: Option<u32>,
+    ancestor_headers: Vec<WrappedHeader>,
+) -> ContractResult<Response> {
+    let mut btc = Bitcoin::default();
+    let prev_confirmed = CONFIRMED_INDEX.may_load(store)?;
+    let confirmed = btc.relay_checkpoint(
+        querier,
+        store,
+        btc_height,
+        btc_proof,
+        cp_index,
+        ancestor_headers,
+        &info.sender,
+        false,
+    )?;
+    let notifications = record_confirmed_checkpoints(store, env, prev_confirmed, btc_height)?;
+    let response = Response::new()
+        .add_submessages(notifications)
+        .add_attribute("action", "relay_checkpoint")
+        .add_attribute("confirmed", confirmed.to_string());
+    Ok(response)
+}
+
+pub fn relay_fee_bumped_checkpoint(
+    querier: &QuerierWrapper,
+    store: &mut dyn Storage,
+    env: &Env,
+    info: MessageInfo,
+    btc_height: u32,
+    btc_proof: Adapter<PartialMerkleTree>,
+    cp_index: u32,
+    btc_tx: Adapter<Transaction>,
+) -> ContractResult<Response> {
+    let mut btc = Bitcoin::default();
+    let prev_confirmed = CONFIRMED_INDEX.may_load(store)?;
+    let confirmed = btc.relay_fee_bumped_checkpoint(
+        querier,
+        store,
+        btc_height,
+        btc_proof,
+        cp_index,
+        btc_tx,
+        &info.sender,
+        false,
+    )?;
+    let notifications = record_confirmed_checkpoints(store, env, prev_confirmed, btc_height)?;
+    let response = Response::new()
+        .add_submessages(notifications)
+        .add_attribute("action", "relay_fee_bumped_checkpoint")
+        .add_attribute("confirmed", confirmed.to_string());
+    Ok(response)
+}
+
+pub fn submit_checkpoint_signature(
+    api: &dyn Api,
+    store: &mut dyn Storage,
+    env: &Env,
+    xpub: WrappedBinary<Xpub>,
+    sigs: Vec<Signature>,
+    cp_index: u32,
+    btc_height: u32,
+    offset: Option<u32>,
+) -> ContractResult<Response> {
+    let btc = Bitcoin::default();
+    let mut checkpoints = btc.checkpoints;
+    let mut notifications = vec![];
+    if let Some(txid) = checkpoints.sign(api, store, &xpub.0, sigs, offset, cp_index, btc_height)? {
+        let change = Change::CheckpointSigned {
+            index: cp_index,
+            txid: txid.to_string(),
+        };
+        record_change(store, env.block.height, change)?;
+        let notification = BridgeNotification::WithdrawalBroadcast {
+            checkpoint_index: cp_index,
+            txid: txid.to_string(),
+        };
+        notifications.extend(notification_msg(store, notification)?);
+    }
+    let response = Response::new()
+        .add_submessages(notifications)
+        .add_attribute("action", "submit_checkpoint_signature");
+    Ok(response)
+}
+
+pub fn submit_checkpoint_psbt(
+    api: &dyn Api,
+    store: &mut dyn Storage,
+    env: &Env,
+    xpub: WrappedBinary<Xpub>,
+    psbt: Binary,
+    cp_index: u32,
+    btc_height: u32,
+) -> ContractResult<Response> {
+    let btc = Bitcoin::default();
+    let mut checkpoints = btc.checkpoints;
+    let mut notifications = vec![];
+    let psbt = Psbt::deserialize(psbt.as_slice())
+        .map_err(|err| ContractError::Checkpoint(format!("Invalid PSBT: {}", err)))?;
+    let sigs = checkpoints
+        .get(store, cp_index)?
+        .psbt_sigs(&xpub.0, &psbt)?;
+    if let Some(txid) = checkpoints.sign(api, store, &xpub.0, sigs, None, cp_index, btc_height)? {
+        let change = Change::CheckpointSigned {
+            index: cp_index,
+            txid: txid.to_string(),
+        };
+        record_change(store, env.block.height, change)?;
+        let notification = BridgeNotification::WithdrawalBroadcast {
+            checkpoint_index: cp_index,
+            txid: txid.to_string(),
+        };
+        notifications.extend(notification_msg(store, notification)?);
+    }
+    let response = Response::new()
+        .add_submessages(notifications)
+        .add_attribute("action", "submit_checkpoint_psbt");
+    Ok(response)
+}
+
+pub fn submit_recovery_signature(
+    api: &dyn Api,
+    store: &mut dyn Storage,
+    env: &Env,
+    xpub: WrappedBinary<Xpub>,
+    sigs: Vec<Signature>,
+) -> ContractResult<Response> {
+    let btc = Bitcoin::default();
+    let mut recovery_txs = btc.recovery_txs;
+    for txid in recovery_txs.sign(api, store, &xpub.0, sigs)? {
+        let change = Change::RecoveryTxSigned {
+            txid: txid.to_string(),
+        };
+        record_change(store, env.block.height, change)?;
+    }
+    let response = Response::new().add_attribute("action", "submit_recovery_signature");
+    Ok(response)
+}
+
+pub fn submit_recovery_group_signature(
+    api: &dyn Api,
+    store: &mut dyn Storage,
+    env: &Env,
+    xpub: WrappedBinary<Xpub>,
+    sigset_index: u32,
+    sigs: Vec<Signature>,
+) -> ContractResult<Response> {
+    let mut recovery_txs = Bitcoin::default().recovery_txs;
+    for txid in recovery_txs.sign_group(api, store, &xpub.0, sigset_index, sigs)? {
+        let change = Change::RecoveryTxSigned {
+            txid: txid.to_string(),
+        };
+        record_change(store, env.block.height, change)?;
+    }
+    Ok(Response::new()
+        .add_attribute("action", "submit_recovery_group_signature")
+        .add_attribute("sigset_index", sigset_index.to_string()))
+}
+
+pub fn set_signatory_key(
+    api: &dyn Api,
+    querier: &QuerierWrapper,
+    store: &mut dyn Storage,
+    env: Env,
+    info: MessageInfo,
+    xpub: WrappedBinary<Xpub>,
+    sig: Signature,
+) -> ContractResult<Response> {
+    let challenge = signatory_key_challenge(&env.contract.address, &info.sender, &xpub.0);
+    let pubkey = xpub.0.derive_pubkey(0)?;
+    ThresholdSig::secp_verify(api, &challenge, &pubkey.into(), &sig)
+        .map_err(|_| ContractError::App("Invalid signatory key proof of possession".into()))?;
+
+    let mut btc = Bitcoin::default();
+    btc.set_signatory_key(querier, store, info.sender, xpub.0)?;
+    let response = Response::new().add_attribute("action", "set_signatory_key");
+    Ok(response)
+}
+
+pub fn tombstone_signatory(
+    store: &mut dyn Storage,
+    env: &Env,
+    info: MessageInfo,
+    xpub: WrappedBinary<Xpub>,
+    evidence: String,
+) -> ContractResult<Response> {
+    let xpub_key = normalized_xpub_key(&xpub.0);
+    if TOMBSTONED_XPUBS.has(store, &xpub_key) {
+        return Err(ContractError::App(
+            "Signatory key is already tombstoned".to_string(),
+        ));
+    }
+
+    if info.sender != CONFIG.load(store)?.owner {
+        let Some(cons_key) = SIGNERS.may_load(store, info.sender.as_str())? else {
+            return Err(ContractError::App(
+                "Only the owner or validators can tombstone a signatory key".to_string(),
+            ));
+        };
+        let mut voters = TOMBSTONE_VOTES
+            .may_load(store, &xpub_key)?
+            .unwrap_or_default();
+        if !voters.contains(&info.sender.to_string()) {
+            voters.push(info.sender.to_string());
+            TOMBSTONE_VOTES.save(store, &xpub_key, &voters)?;
+        }
+        // make sure the voter is still part of the validator set
+        VALIDATORS.load(store, &cons_key)?;
+
+        let (voted_power, total_power) = tally_validator_votes(store, &voters)?;
+        if voted_power as u128 * 3 <= total_power as u128 * 2 {
+            return Ok(Response::new()
+                .add_attribute("action", "vote_tombstone_signatory")
+                .add_attribute("voted_power", voted_power.to_string())
+                .add_attribute("total_power", total_power.to_string()));
+        }
+    }
+
+    let mut btc = Bitcoin::default();
+    let consensus_key = btc.signatory_keys.tombstone(store, &xpub.0)?;
+    TOMBSTONED_XPUBS.save(
+        store,
+        &xpub_key,
+        &Tombstone {
+            xpub: Binary::from(xpub_key.clone()),
+            consensus_key,
+            evidence,
+            tombstoned_at: bridge_time(env),
+        },
+    )?;
+    TOMBSTONE_VOTES.remove(store, &xpub_key);
+    FORCED_CHECKPOINT_PUSH.save(store, &true)?;
+
+    Ok(Response::new().add_attribute("action", "tombstone_signatory"))
+}
+
+/// The voting power of the given voters which are still validators, and the
+/// total voting power of the validator set.
+fn tally_validator_votes(store: &dyn Storage, voters: &[String]) -> ContractResult<(u64, u64)> {
+    let mut voted_power = 0u64;
+    for voter in voters {
+        let Some(cons_key) = SIGNERS.may_load(store, voter)? else {
+            continue;
+        };
+        if let Some((power, _)) = VALIDATORS.may_load(store, &cons_key)? {
+            voted_power += power;
+        }
+    }
+    let total_power = VALIDATORS
+        .range(store, None, None, Order::Ascending)
+        .map(|entry| entry.map(|(_, (power, _))| power))
+        .sum::<StdResult<u64>>()?;
+    Ok((voted_power, total_power))
+}
+
+pub fn force_complete_checkpoint(
+    querier: &QuerierWrapper,
+    api: &dyn Api,
+    store: &mut dyn Storage,
+    env: &Env,
+    info: MessageInfo,
+    index: u32,
+    final_tx: Adapter<Transaction>,
+) -> ContractResult<Response> {
+    let Some(cons_key) = SIGNERS.may_load(store, info.sender.as_str())? else {
+        return Err(ContractError::App(
+            "Only validators can force complete a checkpoint".to_string(),
+        ));
+    };
+    // make sure the voter is still part of the validator set
+    VALIDATORS.load(store, &cons_key)?;
+
+    let wtxid = final_tx.wtxid().to_string();
+    let mut voters = FORCE_COMPLETE_VOTES
+        .may_load(store, (index, &wtxid))?
+        .unwrap_or_default();
+    if !voters.contains(&info.sender.to_string()) {
+        voters.push(info.sender.to_string());
+        FORCE_COMPLETE_VOTES.save(store, (index, &wtxid), &voters)?;
+    }
+    let (voted_power, total_power) = tally_validator_votes(store, &voters)?;
+    if voted_power as u128 * 3 <= total_power as u128 * 2 {
+        return Ok(Response::new()
+            .add_attribute("action", "vote_force_complete_checkpoint")
+            .add_attribute("index", index.to_string())
+            .add_attribute("voted_power", voted_power.to_string())
+            .add_attribute("total_power", total_power.to_string()));
+    }
+
+    let config = CONFIG.load(store)?;
+    let btc_height: u32 =
+        querier.query_wasm_smart(config.light_client_contract.as_str(), &HeaderHeight {})?;
+    let mut checkpoints = Bitcoin::default().checkpoints;
+    let txid = checkpoints.force_complete(api, store, index, final_tx, btc_height)?;
+    let stale_votes = FORCE_COMPLETE_VOTES
+        .prefix(index)
+        .keys(store, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for wtxid in stale_votes {
+        FORCE_COMPLETE_VOTES.remove(store, (index, &wtxid));
+    }
+
+    let change = Change::CheckpointSigned {
+        index,
+        txid: txid.to_string(),
+    };
+    record_change(store, env.block.height, change)?;
+    let notification = BridgeNotification::WithdrawalBroadcast {
+        checkpoint_index: index,
+        txid: txid.to_string(),
+    };
+    let notifications = notification_msg(store, notification)?;
+    Ok(Response::new()
+        .add_submessages(notifications)
+        .add_attribute("action", "force_complete_checkpoint")
+        .add_attribute("index", index.to_string())
+        .add_attribute("txid", txid.to_string()))
+}
+
+pub fn add_validators(
+    store: &mut dyn Storage,
+    info: MessageInfo,
+    addrs: Vec<String>,
+    voting_powers: Vec<u64>,
+    consensus_keys: Vec<ConsensusKey>,
+) -> ContractResult<Response> {
+    assert_eq!(info.sender, CONFIG.load(store)?.owner);
+    assert_eq!(addrs.len(), voting_powers.len());
+    assert_eq!(addrs.len(), consensus_keys.len());
+
+    for i in 0..addrs.len() {
+        let addr = &addrs[i];
+        let power = voting_powers[i];
+        let cons_key = &consensus_keys[i];
+
+        SIGNERS.save(store, addr, cons_key)?;
+        VALIDATORS.save(store, cons_key, &(power, addr.clone()))?;
+    }
+    let response = Response::new().add_attribute("action", "add_validators");
+    Ok(response)
+}
+
+pub fn remove_validators(
+    store: &mut dyn Storage,
+    info: MessageInfo,
+    addrs: Vec<String>,
+) -> ContractResult<Response> {
+    assert_eq!(info.sender, CONFIG.load(store)?.owner);
+
+    for addr in addrs.iter() {
+        let cons_key = SIGNERS
+            .may_load(store, addr)?
+            .ok_or_else(|| ContractError::App(format!("Validator {} is not registered", addr)))?;
+        SIGNERS.remove(store, addr);
+        VALIDATORS.remove(store, &cons_key);
+    }
+    let response = Response::new().add_attribute("action", "remove_validators");
+    Ok(response)
+}
+
+pub fn update_validator_power(
+    store: &mut dyn Storage,
+    info: MessageInfo,
+    addr: String,
+    voting_power: u64,
+) -> ContractResult<Response> {
+    assert_eq!(info.sender, CONFIG.load(store)?.owner);
+
+    let cons_key = SIGNERS
+        .may_load(store, &addr)?
+        .ok_or_else(|| ContractError::App(format!("Validator {} is not registered", addr)))?;
+    VALIDATORS.save(store, &cons_key, &(voting_power, addr.clone()))?;
+    let response = Response::new()
+        .add_attribute("action", "update_validator_power")
+        .add_attribute("addr", addr)
+        .add_attribute("voting_power", voting_power.to_string());
+    Ok(response)
+}
+
+pub fn set_validator_set(
+    store: &mut dyn Storage,
+    info: MessageInfo,
+    epoch: u32,
+    validators: Vec<ValidatorInfo>,
+) -> ContractResult<Response> {
+    assert_eq!(info.sender, CONFIG.load(store)?.owner);
+
+    // Signatory sets of existing checkpoints are never rewritten.
+    let next_index = CheckpointQueue::default().next_index(store)?;
+    if epoch < next_index {
+        return Err(ContractError::App(format!(
+            "Epoch must be at least the next checkpoint index {}",
+            next_index
+        )));
+    }
+    if validators.is_empty() {
+        return Err(ContractError::App("Validator set is empty".to_string()));
+    }
+
+    SCHEDULED_VALIDATOR_SET.save(store, &ScheduledValidatorSet { epoch, validators })?;
+    let response = Response::new()
+        .add_attribute("action", "set_validator_set")
+        .add_attribute("epoch", epoch.to_string());
+    Ok(response)
+}
+
+pub fn register_validator(
+    store: &mut dyn Storage,
+    querier: &QuerierWrapper,
+    info: MessageInfo,
+) -> ContractResult<Response> {
+    let permission = WHITELIST_VALIDATORS.has(store, info.sender.clone());
+    if !permission {
+        return Err(ContractError::ValidatorUnwhitelisted {});
+    }
+    if JAILED_SIGNERS.has(store, info.sender.as_str()) {
+        return Err(ContractError::App(
+            "Signer was removed for missing checkpoints, request reinstatement instead".to_string(),
+        ));
+    }
+
+    let sender = info.sender;
+    let val_addr = convert_addr_by_prefix(sender.as_str(), VALIDATOR_ADDRESS_PREFIX);
+    let binary_validator_result = fetch_staking_validator(querier, val_addr).unwrap();
+    let validator_response =
+        QueryValidatorResponse::decode(binary_validator_result.as_slice()).unwrap();
+    let validator: ibc_proto::cosmos::staking::v1beta1::Validator =
+        validator_response.validator.unwrap();
+    if validator.jailed {
+        return Err(ContractError::ValidatorJailed {});
+    }
+    if validator.consensus_pubkey.is_none() {
+        return Err(ContractError::ValidatorNoConsensusPubKey {});
+    }
+    if validator.status != BondStatus::Bonded as i32 {
+        return Err(ContractError::ValidatorNotBonded {});
+    }
+    let cons_key: [u8; 32] = validator
+        .consensus_pubkey
+        .unwrap()
+        .value
+        .rchunks(32)
+        .next()
+        .unwrap()
+        .try_into()
+        .expect("Failed to convert &[u8] to [u8; 32]");
+    let voting_power: u64 = validator.tokens.parse().expect("Cannot parse voting power");
+    SIGNERS.save(store, sender.as_str(), &cons_key)?;
+    VALIDATORS.save(
+        store,
+        &cons_key,
+        &(voting_power, sender.clone().into_string()),
+    )?;
+    let response = Response::new()
+        .add_attribute("action", "register_validator")
+        .add_attribute("sender", sender)
+        .add_attribute("consensus_key", Binary::from(cons_key).to_string())
+        .add_attribute("voting_power", voting_power.to_string());
+    Ok(response)
+}
+
+pub fn request_reinstatement(
+    store: &mut dyn Storage,
+    env: Env,
+    info: MessageInfo,
+) -> ContractResult<Response> {
+    let mut signer = JAILED_SIGNERS
+        .may_load(store, info.sender.as_str())?
+        .ok_or_else(|| ContractError::App("Signer is not jailed".to_string()))?;
+    let cooldown = Bitcoin::default()
+        .config(store)?
+        .signer_reinstatement_cooldown;
+    let eligible_at = signer.jailed_at + cooldown;
+    if bridge_time(&env) < eligible_at {
+        return Err(ContractError::App(format!(
+            "Reinstatement can be requested from {}",
+            eligible_at
+        )));
+    }
+    if !WHITELIST_VALIDATORS.has(store, info.sender.clone()) {
+        return Err(ContractError::ValidatorUnwhitelisted {});
+    }
+
+    signer.reinstatement_requested = true;
+    JAILED_SIGNERS.save(store, info.sender.as_str(), &signer)?;
+    Ok(Response::new()
+        .add_attribute("action", "request_reinstatement")
+        .add_attribute("sender", info.sender))
+}
+
+pub fn set_recovery_address(
+    store: &mut dyn Storage,
+    info: MessageInfo,
+    btc_address: String,
+) -> ContractResult<Response> {
+    let script_pubkey = address_to_script(&btc_address, NETWORK)?;
+    Bitcoin::default()
+        .config(store)?
+        .check_recovery_script(&script_pubkey)?;
+    RECOVERY_SCRIPTS.save(
+        store,
+        info.sender.as_str(),
+        &Binary::from(script_pubkey.to_bytes()),
+    )?;
+    Ok(Response::new()
+        .add_attribute("action", "set_recovery_address")
+        .add_attribute("sender", info.sender)
+        .add_attribute("btc_address", btc_address))
+}
+
+pub fn register_denom(
+    store: &mut dyn Storage,
+    info: MessageInfo,
+    subdenom: String,
+    metadata: Option<Metadata>,
+) -> ContractResult<Response> {
+    assert_eq!(info.sender, CONFIG.load(store)?.owner);
+    let config = CONFIG.load(store)?;
+    let msg = wasm_execute(
+        config.token_factory_contract,
+        &tokenfactory::msg::ExecuteMsg::CreateDenom { subdenom, metadata },
+        info.funds,
+    )?;
+
+    Ok(Response::new()
+        .add_message(msg)
+        .add_attribute("action", "register_denom"))
+}
+
+pub fn update_denom_metadata(
+    store: &mut dyn Storage,
+    info: MessageInfo,
+    metadata: Metadata,
+) -> ContractResult<Response> {
+    let config = CONFIG.load(store)?;
+    assert_eq!(info.sender, config.owner);
+
+    let denom = get_full_btc_denom(store, config.token_factory_contract.as_str())?;
+    let msg = wasm_execute(
+        config.token_factory_contract,
+        &tokenfactory::msg::ExecuteMsg::SetMetadata { denom, metadata },
+        info.funds,
+    )?;
+
+    Ok(Response::new()
+        .add_message(msg)
+        .add_attribute("action", "update_denom_metadata"))
+}
+
+pub fn migrate_denom(
+    store: &mut dyn Storage,
+    info: MessageInfo,
+    new_subdenom: String,
+) -> ContractResult<Response> {
+    let config = CONFIG.load(store)?;
+    assert_eq!(info.sender, config.owner);
+    if !BRIDGE_PAUSED.may_load(store)?.unwrap_or_default() {
+        return Err(ContractError::App(
+            "Bridge must be paused to migrate the nBTC denom".to_string(),
+        ));
+    }
+
+    let old_denom = get_full_btc_denom(store, config.token_factory_contract.as_str())?;
+    let new_denom = format!("factory/{}/{}", config.token_factory_contract, new_subdenom);
+    if is_btc_denom(store, config.token_factory_contract.as_str(), &new_denom)? {
+        return Err(ContractError::App(format!(
+            "{} is already an nBTC denom",
+            new_denom
+        )));
+    }
+
+    let mut legacy_denoms = LEGACY_BTC_DENOMS.may_load(store)?.unwrap_or_default();
+    legacy_denoms.push(old_denom.clone());
+    LEGACY_BTC_DENOMS.save(store, &legacy_denoms)?;
+    BTC_SUBDENOM.save(store, &new_subdenom)?;
+
+    let msg = wasm_execute(
+        config.token_factory_contract,
+        &tokenfactory::msg::ExecuteMsg::CreateDenom {
+            subdenom: new_subdenom,
+            metadata: None,
+        },
+        info.funds,
+    )?;
+    Ok(Response::new()
+        .add_message(msg)
+        .add_attribute("action", "migrate_denom")
+        .add_attribute("old_denom", old_denom)
+        .add_attribute("new_denom", new_denom))
+}
+
+// USE THIS WHEN WE HAVE TO CHANGE TO ANOTHER BRIDGE CONTRACT
+pub fn change_btc_denom_owner(
+    store: &mut dyn Storage,
+    info: MessageInfo,
+    new_owner: String,
+) -> ContractResult<Response> {
+    let config = CONFIG.load(store)?;
+    assert_eq!(info.sender, config.owner);
+
+    let denom = get_full_btc_denom(store, config.token_factory_contract.as_str())?;
+    let msg = wasm_execute(
+        config.token_factory_contract,
+        &tokenfactory::msg::ExecuteMsg::ChangeDenomOwner {
+            denom,
+            new_admin_address: new_owner,
+        },
+        info.funds,
+    )?;
+
+    Ok(Response::new()
+        .add_message(msg)
+        .add_attribute("action", "change_btc_denom_owner"))
+}
+
+pub fn set_whitelist_validator(
+    store: &mut dyn Storage,
+    info: MessageInfo,
+    val_addr: Addr,
+    permission: bool,
+) -> ContractResult<Response> {
+    let config = CONFIG.load(store)?;
+    assert_eq!(info.sender, config.owner);
+    if permission {
+        WHITELIST_VALIDATORS
             .save(store, val_addr.clone(), &())
             .unwrap();
     } else {
-        WHITELIST_VALIDATORS.remove(store, val_addr.clone());
+        WHITELIST_VALIDATORS.remove(store, val_addr.clone());
+    }
+    Ok(Response::new()
+        .add_attribute("action", "set_whitelist_validator")
+        .add_attribute("validator_address", val_addr.to_string())
+        .add_attribute("permission", permission.to_string()))
+}
+
+pub fn claim_ibc_refund(store: &mut dyn Storage, info: MessageInfo) -> ContractResult<Response> {
+    let refunds = IBC_REFUNDS
+        .prefix(info.sender.as_str())
+        .range(store, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    if refunds.is_empty() {
+        return Err(ContractError::App("No ibc refunds to claim".to_string()));
+    }
+    let mut amount = vec![];
+    for (denom, refund) in refunds {
+        IBC_REFUNDS.remove(store, (info.sender.as_str(), &denom));
+        amount.push(Coin::new(refund.u128(), denom));
+    }
+    Ok(Response::new()
+        .add_attribute("action", "claim_ibc_refund")
+        .add_attribute("sender", info.sender.to_string())
+        .add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount,
+        }))
+}
+
+pub fn claim_mint(
+    env: &Env,
+    store: &mut dyn Storage,
+    info: MessageInfo,
+) -> ContractResult<Response> {
+    let now = bridge_time(env);
+    let claims = MINT_CLAIMS
+        .prefix(&info.sender)
+        .range(store, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    let token_factory = CONFIG.load(store)?.token_factory_contract;
+    // claims recorded before a `MigrateDenom` are minted as the new denom
+    let denom = get_full_btc_denom(store, token_factory.as_str())?;
+    let mut msgs: Vec<CosmosMsg> = vec![];
+    for (id, claim) in claims {
+        // expired claims are routed to recovery in `ClockEndBlock`
+        if claim
+            .expires_at
+            .map_or(false, |expires_at| expires_at <= now)
+        {
+            continue;
+        }
+        MINT_CLAIMS.remove(store, (&info.sender, id));
+        msgs.push(
+            wasm_execute(
+                token_factory.as_str(),
+                &tokenfactory::msg::ExecuteMsg::MintTokens {
+                    denom: denom.clone(),
+                    amount: claim.coin.amount,
+                    mint_to_address: info.sender.to_string(),
+                },
+                vec![],
+            )?
+            .into(),
+        );
+    }
+    if msgs.is_empty() {
+        return Err(ContractError::App("No claimable mints".to_string()));
+    }
+    Ok(Response::new()
+        .add_attribute("action", "claim_mint")
+        .add_attribute("claims", msgs.len().to_string())
+        .add_messages(msgs))
+}
+
+pub fn claim_rewards(store: &mut dyn Storage, info: MessageInfo) -> ContractResult<Response> {
+    let amount = REWARD_BALANCES
+        .may_load(store, &info.sender)?
+        .unwrap_or_default();
+    if amount == 0 {
+        return Err(ContractError::App("No rewards to claim".to_string()));
+    }
+    REWARD_BALANCES.remove(store, &info.sender);
+
+    let token_factory = CONFIG.load(store)?.token_factory_contract;
+    let denom = get_full_btc_denom(store, token_factory.as_str())?;
+    let msg = wasm_execute(
+        token_factory.as_str(),
+        &tokenfactory::msg::ExecuteMsg::MintTokens {
+            denom,
+            amount: Uint128::from(amount),
+            mint_to_address: info.sender.to_string(),
+        },
+        vec![],
+    )?;
+    Ok(Response::new()
+        .add_attribute("action", "claim_rewards")
+        .add_attribute("amount", amount.to_string())
+        .add_message(msg))
+}
+
+pub fn set_relayer(
+    store: &mut dyn Storage,
+    info: MessageInfo,
+    relayer: Addr,
+    permission: bool,
+) -> ContractResult<Response> {
+    let config = CONFIG.load(store)?;
+    assert_eq!(info.sender, config.owner);
+    if permission {
+        RELAYERS.save(store, &relayer, &())?;
+    } else {
+        RELAYERS.remove(store, &relayer);
+    }
+    Ok(Response::new()
+        .add_attribute("action", "set_relayer")
+        .add_attribute("relayer", relayer.to_string())
+        .add_attribute("permission", permission.to_string()))
+}
+
+pub fn set_notification_contract(
+    store: &mut dyn Storage,
+    info: MessageInfo,
+    contract: Option<Addr>,
+) -> ContractResult<Response> {
+    let mut config = CONFIG.load(store)?;
+    assert_eq!(info.sender, config.owner);
+    config.notification_contract = contract;
+    CONFIG.save(store, &config)?;
+    Ok(Response::new().add_attribute("action", "set_notification_contract"))
+}
+
+pub fn register_hook(
+    store: &mut dyn Storage,
+    api: &dyn Api,
+    info: MessageInfo,
+    addr: String,
+    events: Vec<HookEvent>,
+) -> ContractResult<Response> {
+    let config = CONFIG.load(store)?;
+    assert_eq!(info.sender, config.owner);
+    let addr = api.addr_validate(&addr)?;
+    if events.is_empty() {
+        return Err(ContractError::App("Hook has no events".to_string()));
+    }
+
+    let hooks = CHECKPOINT_HOOKS
+        .range(store, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    let id = match hooks.iter().find(|(_, hook)| hook.addr == addr) {
+        Some((id, _)) => *id,
+        None => {
+            if hooks.len() >= MAX_CHECKPOINT_HOOKS {
+                return Err(ContractError::App("Too many hooks".to_string()));
+            }
+            let id = CHECKPOINT_HOOK_COUNT.may_load(store)?.unwrap_or_default();
+            CHECKPOINT_HOOK_COUNT.save(store, &(id + 1))?;
+            id
+        }
+    };
+    CHECKPOINT_HOOKS.save(
+        store,
+        id,
+        &CheckpointHook {
+            addr: addr.clone(),
+            events,
+            failures: 0,
+        },
+    )?;
+    Ok(Response::new()
+        .add_attribute("action", "register_hook")
+        .add_attribute("hook_id", id.to_string())
+        .add_attribute("addr", addr))
+}
+
+pub fn unregister_hook(
+    store: &mut dyn Storage,
+    api: &dyn Api,
+    info: MessageInfo,
+    addr: String,
+) -> ContractResult<Response> {
+    let config = CONFIG.load(store)?;
+    assert_eq!(info.sender, config.owner);
+    let addr = api.addr_validate(&addr)?;
+    let ids = CHECKPOINT_HOOKS
+        .range(store, None, None, Order::Ascending)
+        .filter_map(|hook| match hook {
+            Ok((id, hook)) if hook.addr == addr => Some(Ok(id)),
+            Ok(_) => None,
+            Err(err) => Some(Err(err)),
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    if ids.is_empty() {
+        return Err(ContractError::App("Hook not found".to_string()));
+    }
+    for id in ids {
+        CHECKPOINT_HOOKS.remove(store, id);
+    }
+    Ok(Response::new()
+        .add_attribute("action", "unregister_hook")
+        .add_attribute("addr", addr))
+}
+
+pub fn set_secondary_light_client(
+    store: &mut dyn Storage,
+    info: MessageInfo,
+    contract: Option<Addr>,
+) -> ContractResult<Response> {
+    let mut config = CONFIG.load(store)?;
+    assert_eq!(info.sender, config.owner);
+    config.secondary_light_client_contract = contract;
+    CONFIG.save(store, &config)?;
+    Ok(Response::new().add_attribute("action", "set_secondary_light_client"))
+}
+
+pub fn switch_light_client(
+    store: &mut dyn Storage,
+    querier: &QuerierWrapper,
+    info: MessageInfo,
+) -> ContractResult<Response> {
+    let mut config = CONFIG.load(store)?;
+    assert_eq!(info.sender, config.owner);
+    let secondary = config
+        .secondary_light_client_contract
+        .clone()
+        .ok_or_else(|| ContractError::App("No secondary light client".to_string()))?;
+
+    let primary_height: u32 =
+        querier.query_wasm_smart(config.light_client_contract.as_str(), &HeaderHeight {})?;
+    let secondary_height: u32 = querier.query_wasm_smart(secondary.as_str(), &HeaderHeight {})?;
+    let height = primary_height.min(secondary_height);
+    let query = BlockHashAtHeight { height };
+    let primary_hash: Option<WrappedBinary<BlockHash>> =
+        querier.query_wasm_smart(config.light_client_contract.as_str(), &query)?;
+    let secondary_hash: Option<WrappedBinary<BlockHash>> =
+        querier.query_wasm_smart(secondary.as_str(), &query)?;
+    match (primary_hash, secondary_hash) {
+        (Some(primary), Some(secondary)) if primary.0 == secondary.0 => {}
+        _ => {
+            return Err(ContractError::App(format!(
+                "Light clients disagree on the header at height {}",
+                height
+            )))
+        }
+    }
+
+    config.secondary_light_client_contract = Some(config.light_client_contract);
+    config.light_client_contract = secondary;
+    CONFIG.save(store, &config)?;
+    LIGHT_CLIENT_SWITCHED_AT.save(store, &height)?;
+    Ok(Response::new()
+        .add_attribute("action", "switch_light_client")
+        .add_attribute("light_client", config.light_client_contract)
+        .add_attribute("btc_height", height.to_string()))
+}
+
+pub fn set_compliance_tagger(
+    store: &mut dyn Storage,
+    info: MessageInfo,
+    tagger: Addr,
+    permission: bool,
+) -> ContractResult<Response> {
+    let config = CONFIG.load(store)?;
+    assert_eq!(info.sender, config.owner);
+    if permission {
+        COMPLIANCE_TAGGERS.save(store, &tagger, &())?;
+    } else {
+        COMPLIANCE_TAGGERS.remove(store, &tagger);
     }
     Ok(Response::new()
-        .add_attribute("action", "set_whitelist_validator")
-        .add_attribute("validator_address", val_addr.to_string())
+        .add_attribute("action", "set_compliance_tagger")
+        .add_attribute("tagger", tagger.to_string())
         .add_attribute("permission", permission.to_string()))
 }
+
+pub fn tag_checkpoint(
+    store: &mut dyn Storage,
+    info: MessageInfo,
+    index: u32,
+    memo: String,
+) -> ContractResult<Response> {
+    let config = CONFIG.load(store)?;
+    if info.sender != config.owner && !COMPLIANCE_TAGGERS.has(store, &info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+    if memo.len() > MAX_CHECKPOINT_MEMO_LEN {
+        return Err(ContractError::App(format!(
+            "Memo exceeds {} bytes",
+            MAX_CHECKPOINT_MEMO_LEN
+        )));
+    }
+    // errors if the checkpoint does not exist or has been pruned
+    CheckpointQueue::default().get(store, index)?;
+    CHECKPOINT_MEMOS.save(store, index, &memo)?;
+    Ok(Response::new()
+        .add_attribute("action", "tag_checkpoint")
+        .add_attribute("index", index.to_string())
+        .add_attribute("memo", memo))
+}
+
+pub fn set_screened_script(
+    store: &mut dyn Storage,
+    info: MessageInfo,
+    btc_address: String,
+    listed: bool,
+) -> ContractResult<Response> {
+    let config = CONFIG.load(store)?;
+    if info.sender != config.owner && !COMPLIANCE_TAGGERS.has(store, &info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+    let script_pubkey = address_to_script(&btc_address, NETWORK)?;
+    if listed {
+        SCREENED_SCRIPTS.save(store, script_pubkey.as_bytes(), &())?;
+    } else {
+        SCREENED_SCRIPTS.remove(store, script_pubkey.as_bytes());
+    }
+    Ok(Response::new()
+        .add_attribute("action", "set_screened_script")
+        .add_attribute("btc_address", btc_address)
+        .add_attribute("listed", listed.to_string()))
+}
+
+pub fn review_held_withdrawal(
+    store: &mut dyn Storage,
+    querier: &QuerierWrapper,
+    api: &dyn Api,
+    env: Env,
+    info: MessageInfo,
+    id: u64,
+    approve: bool,
+) -> ContractResult<Response> {
+    let config = CONFIG.load(store)?;
+    if info.sender != config.owner && !COMPLIANCE_TAGGERS.has(store, &info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+    let held = HELD_WITHDRAWALS
+        .may_load(store, id)?
+        .ok_or_else(|| ContractError::App(format!("Held withdrawal {} not found", id)))?;
+    HELD_WITHDRAWALS.remove(store, id);
+
+    let response = Response::new()
+        .add_attribute("action", "review_held_withdrawal")
+        .add_attribute("held_withdrawal_id", id.to_string())
+        .add_attribute("approve", approve.to_string());
+    if !approve {
+        return Ok(response.add_message(BankMsg::Send {
+            to_address: held.sender.to_string(),
+            amount: held.funds,
+        }));
+    }
+
+    let script_pubkey = address_to_script(&held.btc_address, NETWORK)?;
+    let mut cosmos_msgs = vec![];
+    let mut withdrawal_fees = vec![];
+    for fund in held.funds {
+        withdrawal_fees.push(queue_withdrawal(
+            store,
+            querier,
+            api,
+            &env,
+            held.sender.as_str(),
+            fund.clone(),
+            Some(fund.amount),
+            &script_pubkey,
+            held.fee,
+            None,
+            &mut cosmos_msgs,
+        )?);
+    }
+    let response = record_withdrawals(
+        store,
+        &env,
+        &held.sender,
+        &script_pubkey,
+        withdrawal_fees,
+        response,
+    )?;
+    Ok(response.add_messages(cosmos_msgs))
+}
+
+pub fn set_fee_exemption(
+    store: &mut dyn Storage,
+    api: &dyn Api,
+    info: MessageInfo,
+    addr: String,
+    exempt: bool,
+) -> ContractResult<Response> {
+    let config = CONFIG.load(store)?;
+    assert_eq!(info.sender, config.owner);
+    let addr = api.addr_validate(&addr)?.into_string();
+    if exempt {
+        FEE_EXEMPTIONS.save(store, &addr, &())?;
+    } else {
+        FEE_EXEMPTIONS.remove(store, &addr);
+    }
+    Ok(Response::new()
+        .add_attribute("action", "set_fee_exemption")
+        .add_attribute("addr", addr)
+        .add_attribute("exempt", exempt.to_string()))
+}
+
+pub fn import_state(
+    store: &mut dyn Storage,
+    info: MessageInfo,
+    entries: Vec<StateEntry>,
+    finish: bool,
+) -> ContractResult<Response> {
+    let config = CONFIG.load(store)?;
+    assert_eq!(info.sender, config.owner);
+    let count = entries.len();
+    import_entries(store, entries, finish)?;
+    Ok(Response::new()
+        .add_attribute("action", "import_state")
+        .add_attribute("entries", count.to_string())
+        .add_attribute("finished", finish.to_string()))
+}
+
+pub fn start_bridge_migration(
+    store: &mut dyn Storage,
+    querier: &QuerierWrapper,
+    env: Env,
+    info: MessageInfo,
+    successor: Addr,
+) -> ContractResult<Response> {
+    let config = CONFIG.load(store)?;
+    assert_eq!(info.sender, config.owner);
+    if BRIDGE_MIGRATION.may_load(store)?.is_some() {
+        return Err(ContractError::App(
+            "Bridge migration already started".to_string(),
+        ));
+    }
+
+    let handshake: MigrationHandshakeResponse = querier.query_wasm_smart(
+        successor.clone(),
+        &QueryMsg::MigrationHandshake {
+            predecessor: env.contract.address.clone(),
+        },
+    )?;
+    if !handshake.accepted {
+        return Err(ContractError::App(
+            "Successor does not expect a migration from this bridge".to_string(),
+        ));
+    }
+    if !Script::from_bytes(&handshake.reserve_script).is_p2wsh() {
+        return Err(ContractError::App(
+            "Successor reserve script is not a P2WSH script".to_string(),
+        ));
+    }
+
+    BRIDGE_MIGRATION.save(
+        store,
+        &BridgeMigration {
+            successor: successor.clone(),
+            reserve_script: handshake.reserve_script,
+            successor_sigset_index: handshake.sigset_index,
+            started_at: bridge_time(&env),
+            checkpoint_index: None,
+            transferred_until: None,
+            state_transferred: false,
+        },
+    )?;
+    Ok(Response::new()
+        .add_attribute("action", "start_bridge_migration")
+        .add_attribute("successor", successor)
+        .add_attribute("successor_sigset_index", handshake.sigset_index.to_string()))
+}
+
+pub fn transfer_bridge_state(
+    store: &mut dyn Storage,
+    info: MessageInfo,
+    limit: Option<u32>,
+) -> ContractResult<Response> {
+    let config = CONFIG.load(store)?;
+    assert_eq!(info.sender, config.owner);
+    let mut migration = BRIDGE_MIGRATION
+        .may_load(store)?
+        .ok_or_else(|| ContractError::App("No bridge migration in progress".to_string()))?;
+    let checkpoint_index = migration.checkpoint_index.ok_or_else(|| {
+        ContractError::App("The migration checkpoint has not been built yet".to_string())
+    })?;
+    let checkpoints = CheckpointQueue::default();
+    if checkpoints
+        .confirmed_index(store)
+        .map_or(true, |confirmed| confirmed < checkpoint_index)
+    {
+        return Err(ContractError::App(
+            "The migration checkpoint is not confirmed yet".to_string(),
+        ));
+    }
+    if migration.state_transferred {
+        return Err(ContractError::App(
+            "Bridge state is already transferred".to_string(),
+        ));
+    }
+
+    let limit = limit.unwrap_or(DEFAULT_QUERY_LIMIT).min(MAX_QUERY_LIMIT) as usize;
+    let entries = export_migrated(store, migration.transferred_until.clone(), limit)?;
+    let finish = entries.len() < limit;
+    if let Some(last) = entries.last() {
+        migration.transferred_until = Some(last.key.clone());
+    }
+    migration.state_transferred = finish;
+    BRIDGE_MIGRATION.save(store, &migration)?;
+
+    let mut msgs = vec![];
+    let handover = if finish {
+        let checkpoint = checkpoints.get(store, checkpoint_index)?;
+        let reserve = checkpoint.reserve_output()?.ok_or_else(|| {
+            ContractError::App("The migration checkpoint has no reserve output".to_string())
+        })?;
+        let btc_denom = get_full_btc_denom(store, config.token_factory_contract.as_str())?;
+        let legacy_btc_denoms = LEGACY_BTC_DENOMS.may_load(store)?.unwrap_or_default();
+        // the successor mints and burns all nBTC denoms from now on
+        for denom in legacy_btc_denoms.iter().chain([&btc_denom]) {
+            msgs.push(wasm_execute(
+                config.token_factory_contract.clone(),
+                &tokenfactory::msg::ExecuteMsg::ChangeDenomOwner {
+                    denom: denom.clone(),
+                    new_admin_address: migration.successor.to_string(),
+                },
+                vec![],
+            )?);
+        }
+        Some(MigrationHandover {
+            reserve: SignedReserveOutput {
+                txid: checkpoint.checkpoint_tx()?.txid().to_string(),
+                vout: 0,
+                amount: reserve.value.to_sat(),
+                sigset_index: migration.successor_sigset_index,
+                lock_time: 0,
+                credit_fee_pool: false,
+                credits: vec![],
+            },
+            btc_denom,
+            legacy_btc_denoms,
+        })
+    } else {
+        None
+    };
+
+    let count = entries.len();
+    let msg = wasm_execute(
+        migration.successor,
+        &ExecuteMsg::ReceiveMigratedState { entries, handover },
+        vec![],
+    )?;
+    Ok(Response::new()
+        .add_message(msg)
+        .add_messages(msgs)
+        .add_attribute("action", "transfer_bridge_state")
+        .add_attribute("entries", count.to_string())
+        .add_attribute("finished", finish.to_string()))
+}
+
+pub fn cancel_bridge_migration(
+    store: &mut dyn Storage,
+    info: MessageInfo,
+) -> ContractResult<Response> {
+    let config = CONFIG.load(store)?;
+    assert_eq!(info.sender, config.owner);
+    let migration = BRIDGE_MIGRATION
+        .may_load(store)?
+        .ok_or_else(|| ContractError::App("No bridge migration in progress".to_string()))?;
+    if migration.checkpoint_index.is_some() {
+        return Err(ContractError::App(
+            "The reserve was already paid to the successor".to_string(),
+        ));
+    }
+    BRIDGE_MIGRATION.remove(store);
+    Ok(Response::new()
+        .add_attribute("action", "cancel_bridge_migration")
+        .add_attribute("successor", migration.successor))
+}
+
+pub fn expect_bridge_migration(
+    store: &mut dyn Storage,
+    info: MessageInfo,
+    predecessor: Option<Addr>,
+) -> ContractResult<Response> {
+    let config = CONFIG.load(store)?;
+    assert_eq!(info.sender, config.owner);
+    if predecessor.is_some() && !migrated_stores_empty(store) {
+        return Err(ContractError::App(
+            "Only a bridge without account state can expect a migration".to_string(),
+        ));
+    }
+    match &predecessor {
+        Some(predecessor) => MIGRATION_PREDECESSOR.save(store, predecessor)?,
+        None => MIGRATION_PREDECESSOR.remove(store),
+    }
+    Ok(Response::new()
+        .add_attribute("action", "expect_bridge_migration")
+        .add_attribute(
+            "predecessor",
+            predecessor.map_or("none".to_string(), |addr| addr.to_string()),
+        ))
+}
+
+pub fn receive_migrated_state(
+    store: &mut dyn Storage,
+    info: MessageInfo,
+    entries: Vec<StateEntry>,
+    handover: Option<MigrationHandover>,
+) -> ContractResult<Response> {
+    if MIGRATION_PREDECESSOR.may_load(store)? != Some(info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+    let count = entries.len();
+    import_migrated_entries(store, entries)?;
+    let finish = handover.is_some();
+    if let Some(handover) = handover {
+        take_over_btc_denoms(store, handover.btc_denom, handover.legacy_btc_denoms)?;
+        // attached to the building checkpoint by the next end block
+        let reserve = handover.reserve;
+        SIGNED_RESERVE_OUTPUTS.save(store, (&reserve.txid, reserve.vout), &reserve)?;
+        resume_migrated_counters(store)?;
+        MIGRATION_PREDECESSOR.remove(store);
+    }
+    Ok(Response::new()
+        .add_attribute("action", "receive_migrated_state")
+        .add_attribute("entries", count.to_string())
+        .add_attribute("finished", finish.to_string()))
+}
+
+/// Mints the predecessor's nBTC denom from now on, keeping this bridge's own
+/// denom and the predecessor's former ones withdrawable.
+fn take_over_btc_denoms(
+    store: &mut dyn Storage,
+    btc_denom: String,
+    predecessor_legacy_denoms: Vec<String>,
+) -> ContractResult<()> {
+    let config = CONFIG.load(store)?;
+    let prefix = format!("factory/{}/", config.token_factory_contract);
+    let subdenom = btc_denom.strip_prefix(&prefix).ok_or_else(|| {
+        ContractError::App(format!(
+            "{} is not a denom of this bridge's token factory",
+            btc_denom
+        ))
+    })?;
+
+    let own_denom = get_full_btc_denom(store, config.token_factory_contract.as_str())?;
+    let mut legacy_denoms = LEGACY_BTC_DENOMS.may_load(store)?.unwrap_or_default();
+    for denom in predecessor_legacy_denoms.into_iter().chain([own_denom]) {
+        if denom != btc_denom && !legacy_denoms.contains(&denom) {
+            legacy_denoms.push(denom);
+        }
+    }
+    LEGACY_BTC_DENOMS.save(store, &legacy_denoms)?;
+    BTC_SUBDENOM.save(store, &subdenom.to_string())?;
+    Ok(())
+}
+
+/// Moves the id counters past the ids of the migrated records, which are not
+/// sent by the predecessor.
+fn resume_migrated_counters(store: &mut dyn Storage) -> ContractResult<()> {
+    let last_id = |ids: Option<StdResult<u64>>| ids.transpose().map(Option::unwrap_or_default);
+    let held = last_id(
+        HELD_WITHDRAWALS
+            .keys(store, None, None, Order::Descending)
+            .next(),
+    )?;
+    HELD_WITHDRAWAL_COUNT.save(store, &held)?;
+    let swaps = last_id(
+        LIGHTNING_SWAPS
+            .keys(store, None, None, Order::Descending)
+            .next(),
+    )?;
+    LIGHTNING_SWAP_COUNT.save(store, &swaps)?;
+    let quotes = last_id(
+        WITHDRAWAL_QUOTES
+            .keys(store, None, None, Order::Descending)
+            .next(),
+    )?;
+    WITHDRAWAL_QUOTE_COUNT.save(store, &quotes)?;
+
+    // mint claims are keyed by recipient first, and the counter is the next id
+    let mut next_claim = 0;
+    for key in MINT_CLAIMS.keys(store, None, None, Order::Ascending) {
+        let (_, id) = key?;
+        next_claim = next_claim.max(id + 1);
+    }
+    MINT_CLAIM_COUNT.save(store, &next_claim)?;
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn register_reserve_utxo(
+    store: &mut dyn Storage,
+    querier: &QuerierWrapper,
+    info: MessageInfo,
+    btc_tx: Adapter<Transaction>,
+    btc_height: u32,
+    btc_proof: Adapter<PartialMerkleTree>,
+    vout: u32,
+    sigset_index: u32,
+) -> ContractResult<Response> {
+    let config = CONFIG.load(store)?;
+    if info.sender != config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+    let outpoint = bitcoin::OutPoint::new(btc_tx.txid(), vout);
+    let mut btc = Bitcoin::default();
+    let checkpoint_index = btc.relay_reserve_utxo(
+        querier,
+        store,
+        btc_tx,
+        btc_height,
+        btc_proof,
+        vout,
+        sigset_index,
+    )?;
+    Ok(Response::new()
+        .add_attribute("action", "register_reserve_utxo")
+        .add_attribute("outpoint", outpoint.to_string())
+        .add_attribute("checkpoint_index", checkpoint_index.to_string()))
+}
+
+pub fn set_bridge_paused(
+    store: &mut dyn Storage,
+    info: MessageInfo,
+    paused: bool,
+) -> ContractResult<Response> {
+    let config = CONFIG.load(store)?;
+    if info.sender != config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+    BRIDGE_PAUSED.save(store, &paused)?;
+    Ok(Response::new()
+        .add_attribute("action", "set_bridge_paused")
+        .add_attribute("paused", paused.to_string()))
+}
+
+pub fn set_fee_rate(
+    store: &mut dyn Storage,
+    env: &Env,
+    info: MessageInfo,
+    fee_rate: u64,
+) -> ContractResult<Response> {
+    let config = CONFIG.load(store)?;
+    assert_eq!(info.sender, config.owner);
+    let checkpoint_config = CHECKPOINT_CONFIG.load(store)?;
+    if fee_rate < checkpoint_config.min_fee_rate || fee_rate > checkpoint_config.max_fee_rate {
+        return Err(ContractError::App(format!(
+            "Fee rate must be between {} and {}",
+            checkpoint_config.min_fee_rate, checkpoint_config.max_fee_rate
+        )));
+    }
+
+    let checkpoints = Bitcoin::default().checkpoints;
+    let index = checkpoints.index(store);
+    let mut building = checkpoints.building(store)?;
+    if building.fee_rate != fee_rate {
+        record_fee_rate_change(
+            store,
+            FeeRateChange {
+                checkpoint_index: index,
+                old_fee_rate: building.fee_rate,
+                new_fee_rate: fee_rate,
+                cause: FeeRateChangeCause::Manual,
+                timestamp: bridge_time(env),
+            },
+        )?;
+        building.fee_rate = fee_rate;
+        checkpoints.set(store, index, &building)?;
+    }
+    Ok(Response::new()
+        .add_attribute("action", "set_fee_rate")
+        .add_attribute("fee_rate", fee_rate.to_string()))
+}
+
+pub fn allow_small_utxos(
+    store: &mut dyn Storage,
+    info: MessageInfo,
+    until: Option<u64>,
+) -> ContractResult<Response> {
+    let config = CONFIG.load(store)?;
+    assert_eq!(info.sender, config.owner);
+    match until {
+        Some(until) => SMALL_UTXOS_ALLOWED_UNTIL.save(store, &until)?,
+        None => SMALL_UTXOS_ALLOWED_UNTIL.remove(store),
+    }
+    Ok(Response::new()
+        .add_attribute("action", "allow_small_utxos")
+        .add_attribute(
+            "until",
+            until.map_or("none".to_string(), |until| until.to_string()),
+        ))
+}
+
+pub fn set_owner_multisig(
+    store: &mut dyn Storage,
+    querier: &QuerierWrapper,
+    info: MessageInfo,
+    multisig: Addr,
+) -> ContractResult<Response> {
+    let mut config = CONFIG.load(store)?;
+    if info.sender != config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+    assert_cw3_multisig(querier, &multisig)?;
+    config.owner = multisig.clone();
+    CONFIG.save(store, &config)?;
+    Ok(Response::new()
+        .add_attribute("action", "set_owner_multisig")
+        .add_attribute("multisig", multisig))
+}
+
+pub fn backfill_sigsets(
+    store: &mut dyn Storage,
+    info: MessageInfo,
+    sigsets: Vec<BackfilledSigset>,
+) -> ContractResult<Response> {
+    let config = CONFIG.load(store)?;
+    assert_eq!(info.sender, config.owner);
+    let checkpoints = CheckpointQueue::default();
+    let threshold = checkpoints.config(store).sigset_threshold;
+    for BackfilledSigset {
+        sigset,
+        checkpoint_tx,
+    } in sigsets.iter()
+    {
+        if checkpoints.get(store, sigset.index).is_ok() {
+            return Err(ContractError::App(format!(
+                "Signatory set {} is not pruned",
+                sigset.index
+            )));
+        }
+        if SIGSET_ARCHIVE.has(store, sigset.index) {
+            return Err(ContractError::App(format!(
+                "Signatory set {} is already archived",
+                sigset.index
+            )));
+        }
+        if checkpoints.txid(store, sigset.index)? != checkpoint_tx.txid() {
+            return Err(ContractError::App(format!(
+                "Transaction is not checkpoint {}",
+                sigset.index
+            )));
+        }
+        let reserve_script = sigset.output_script(&[0u8], threshold)?;
+        if checkpoint_tx
+            .output
+            .first()
+            .map(|output| &output.script_pubkey)
+            != Some(&reserve_script)
+        {
+            return Err(ContractError::App(format!(
+                "Signatory set {} does not match the checkpoint's reserve output",
+                sigset.index
+            )));
+        }
+        SIGSET_ARCHIVE.save(store, sigset.index, sigset)?;
+    }
+    Ok(Response::new()
+        .add_attribute("action", "backfill_sigsets")
+        .add_attribute("count", sigsets.len().to_string()))
+}
+
+pub fn claim_expired_deposit(
+    store: &mut dyn Storage,
+    env: &Env,
+    info: MessageInfo,
+    txid: String,
+    vout: u32,
+    refund_script: Adapter<ScriptBuf>,
+) -> ContractResult<Response> {
+    let mut btc = Bitcoin::default();
+    let recovery_txs = RECOVERY_TXS.len(store)?;
+    btc.claim_expired_deposit(
+        store,
+        &info.sender,
+        txid.clone(),
+        vout,
+        refund_script.into_inner(),
+    )?;
+    record_new_recovery_txs(store, env, recovery_txs)?;
+    Ok(Response::new()
+        .add_attribute("action", "claim_expired_deposit")
+        .add_attribute("txid", txid)
+        .add_attribute("vout", vout.to_string()))
+}
+
+/// Reverses the mints of deposits reorged out of the Bitcoin chain. nBTC
+/// still held by an address destination is burned, the rest of the minted
+/// amount is added to the bad debt.
+///
+/// A deposit counts as reorged once the light client has another block than
+/// the one it was minted from at its height, confirmed by `min_confirmations`
+/// blocks.
+pub fn reconcile_reorged_deposits(
+    store: &mut dyn Storage,
+    querier: &QuerierWrapper,
+    env: Env,
+    info: MessageInfo,
+    deposits: Vec<ReorgedDepositId>,
+) -> ContractResult<Response> {
+    let config = CONFIG.load(store)?;
+    if info.sender != config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+    let token_factory = config.token_factory_contract.as_str();
+    let denom = get_full_btc_denom(store, token_factory)?;
+    let last_completed_index = Bitcoin::default().checkpoints.last_completed_index(store)?;
+    let min_confirmations = BITCOIN_CONFIG.load(store)?.min_confirmations;
+    let tip: u32 =
+        querier.query_wasm_smart(config.light_client_contract.as_str(), &HeaderHeight {})?;
+
+    let mut cosmos_msgs: Vec<CosmosMsg> = vec![];
+    let mut total_burned = Uint128::zero();
+    let mut total_deficit = Uint128::zero();
+    for deposit in deposits {
+        let key = (deposit.txid.as_str(), deposit.vout);
+        let record = MINT_RECORDS.may_load(store, key)?.ok_or_else(|| {
+            ContractError::App(format!(
+                "No minted deposit {}:{}",
+                deposit.txid, deposit.vout
+            ))
+        })?;
+        if record.checkpoint_index > last_completed_index {
+            return Err(ContractError::App(format!(
+                "Deposit {}:{} has not been minted yet",
+                deposit.txid, deposit.vout
+            )));
+        }
+
+        let Some(block_hash) = &record.block_hash else {
+            return Err(ContractError::App(format!(
+                "Deposit {}:{} was minted before block hashes were recorded",
+                deposit.txid, deposit.vout
+            )));
+        };
+        // the block replacing the deposit's must be as final as a deposit
+        let confirmations = tip.saturating_sub(record.btc_height);
+        if confirmations < min_confirmations {
+            return Err(ContractError::InsufficientConfirmations {
+                have: confirmations,
+                need: min_confirmations,
+            });
+        }
+        let current_hash: Option<WrappedBinary<BlockHash>> = querier.query_wasm_smart(
+            config.light_client_contract.as_str(),
+            &BlockHashAtHeight {
+                height: record.btc_height,
+            },
+        )?;
+        let current_hash = current_hash.ok_or_else(|| {
+            ContractError::App(format!(
+                "Header at height {} is not in the light client",
+                record.btc_height
+            ))
+        })?;
+        if current_hash.0.to_string() == *block_hash {
+            return Err(ContractError::App(format!(
+                "Deposit {}:{} is still on the canonical chain",
+                deposit.txid, deposit.vout
+            )));
+        }
+
+        let held = match &record.dest {
+            Dest::Address(addr) => querier.query_balance(addr, &denom)?.amount,
+            _ => Uint128::zero(),
+        };
+        let burned = held.min(record.amount);
+        let deficit = record.amount - burned;
+        if !burned.is_zero() {
+            cosmos_msgs.push(
+                wasm_execute(
+                    token_factory,
+                    &tokenfactory::msg::ExecuteMsg::BurnTokens {
+                        amount: burned,
+                        denom: denom.clone(),
+                        burn_from_address: record.dest.to_receiver_addr(),
+                    },
+                    vec![],
+                )?
+                .into(),
+            );
+        }
+        MINT_RECORDS.remove(store, key);
+        REORGED_DEPOSITS.save(
+            store,
+            key,
+            &ReorgedDeposit {
+                txid: deposit.txid.clone(),
+                vout: deposit.vout,
+                dest: record.dest,
+                amount: record.amount,
+                burned,
+                deficit,
+                reconciled_at: bridge_time(&env),
+            },
+        )?;
+        total_burned += burned;
+        total_deficit += deficit;
+    }
+
+    let bad_debt = BAD_DEBT.may_load(store)?.unwrap_or_default() + total_deficit;
+    BAD_DEBT.save(store, &bad_debt)?;
+    Ok(Response::new()
+        .add_attribute("action", "reconcile_reorged_deposits")
+        .add_attribute("burned", total_burned.to_string())
+        .add_attribute("deficit", total_deficit.to_string())
+        .add_attribute("bad_debt", bad_debt.to_string())
+        .add_messages(cosmos_msgs))
+}
@@ -1,7 +1,9 @@
 mod execute;
 mod query;
+mod reply;
 mod sudo;
 
 pub use execute::*;
 pub use query::*;
+pub use reply::*;
 pub use sudo::*;
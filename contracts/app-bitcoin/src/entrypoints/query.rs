@@ -1,25 +1,72 @@
+use super::sudo::clock_end_block;
 use crate::{
-    app::{Bitcoin, ConsensusKey},
-    checkpoint::{Checkpoint, CheckpointQueue, CheckpointStatus},
-    constants::VALIDATOR_ADDRESS_PREFIX,
-    helper::{convert_addr_by_prefix, fetch_staking_validator},
-    interface::{BitcoinConfig, ChangeRates, CheckpointConfig},
-    msg::ConfigResponse,
+    app::{Bitcoin, ConsensusKey, NETWORK},
+    app_data::{app_data_leaves, merkle_branch, merkle_root},
+    checkpoint::{BatchType, Checkpoint, CheckpointQueue, CheckpointStatus, Input},
+    constants::{
+        DEFAULT_QUERY_LIMIT, DEST_COMMITMENT_VERSION, DEST_STATS_WINDOW,
+        LEGACY_DEST_COMMITMENT_VERSION, MAX_QUERY_LIMIT, VALIDATOR_ADDRESS_PREFIX,
+    },
+    descriptor::raw_descriptor,
+    export::export_section,
+    fee::process_deduct_fee_for,
+    helper::{assert_cw3_multisig, bridge_time, convert_addr_by_prefix, fetch_staking_validator},
+    interface::{
+        AdminLogEntry, AppDataProof, BalanceSnapshot, BitcoinConfig, BridgeMigration, BurnRecord,
+        ChangeRates, CheckpointConfig, CheckpointHook, CheckpointInput, CheckpointSchedule,
+        CheckpointSummary, Dest, DestStats, DisbursalOutput, EndBlockReport, ExpiredDeposit,
+        FeeCoverage, FeeRateChange, HeldWithdrawal, IbcTransfer, InputSource, LiabilitiesProof,
+        LightningSwap, MintClaim, MintRecord, PendingDeposit, QueuedWithdrawal,
+        RecoveryGroupStatus, ReorgedDeposit, ScheduledValidatorSet, SigningDuty, SigningProgress,
+        SnapshotWithdrawal, SwapProvider, Tombstone, ValidatorInfo, WithdrawalAllowance,
+        WithdrawalQuote,
+    },
+    migration::state_version,
+    msg::{
+        ChangesResponse, CheckRelayDepositResponse, ConfigResponse, Cw3ExecuteMsg,
+        DepositAddressStatus, DepositAddressStatusResponse, DescriptorKey, DestCommitmentResponse,
+        ExecuteMsg, FoundationPolicyResponse, FullConfigResponse, JailStatusResponse,
+        LightClientsResponse, MetricsResponse, MigrationHandshakeResponse, NbtcAccountResponse,
+        PoolsResponse, RawTxKind, RawTxResponse, RelayDepositRejection, SigsetResponse,
+        SimulateDepositResponse, StateEntry, StateSection, WalletDescriptorResponse,
+    },
     recovery::{RecoveryTxs, SignedRecoveryTx},
     signatory::SignatorySet,
+    snapshot::liabilities_branch,
     state::{
-        BITCOIN_CONFIG, BUILDING_INDEX, CHECKPOINT_CONFIG, CONFIG, OUTPOINTS, SIGNERS, SIG_KEYS,
-        TOKEN_FEE_RATIO, WHITELIST_VALIDATORS,
+        get_full_btc_denom, ACCOUNT_ACTIVITY, ADMIN_LOG, APP_DATA, APP_DATA_INTERNAL_COMMITMENTS,
+        BAD_DEBT, BALANCE_SNAPSHOT, BITCOIN_CONFIG, BRIDGE_MIGRATION, BRIDGE_PAUSED,
+        BUILDING_INDEX, BURNS, CHANGES_PRUNED_HEIGHT, CHANGE_RECORDS, CHECKPOINTS,
+        CHECKPOINT_CONFIG, CHECKPOINT_CONFIRMATION_TIMES, CHECKPOINT_HOOKS, CHECKPOINT_MEMOS,
+        CHECKPOINT_TXIDS, CONFIG, DEST_SIGSET_INDEXES, DEST_STATS, DEST_STATS_BY_TOTAL,
+        EXPIRED_DEPOSITS, EXPIRED_DEPOSITS_BY_DEST, FEE_EXEMPTIONS, FEE_POOL, FEE_RATE_HISTORY,
+        FOUNDATION_KEYS, HEADER_STALL_PAUSED, HELD_WITHDRAWALS, IBC_REFUNDS, IBC_TRANSFERS,
+        JAILED_SIGNERS, LIGHTNING_SWAPS, LIGHT_CLIENT_SWITCHED_AT, MIGRATION_PREDECESSOR,
+        MINT_CLAIMS, MINT_RECORDS, OUTPOINTS, PENDING_DEPOSITS, PENDING_DEPOSITS_BY_DEST,
+        RECLAIMED_SIGNATURE_BYTES, RECOVERY_SCRIPTS, REORGED_DEPOSITS, RESERVE_UTXOS,
+        REWARD_BALANCES, REWARD_POOL, SCHEDULED_VALIDATOR_SET, SCREENED_SCRIPTS, SIGNERS, SIG_KEYS,
+        SNAPSHOT_BALANCES, SNAPSHOT_LEAF_INDEXES, SNAPSHOT_TOP_BALANCES, SNAPSHOT_WITHDRAWALS,
+        SWAP_PROVIDERS, TOKEN_FEE_RATIO, TOMBSTONED_XPUBS, TOTAL_BURNED, VALIDATORS,
+        WHITELIST_VALIDATORS, WITHDRAWALS, WITHDRAWAL_ALLOWANCES, WITHDRAWAL_QUOTES,
     },
+    threshold_sig::Pubkey,
 };
+use bitcoin::hashes::{sha256, Hash};
 use bitcoin::Transaction;
 use common_bitcoin::{
     adapter::{Adapter, WrappedBinary},
+    address::{address_to_script, script_to_address},
+    dry_run::DryRunStorage,
     error::{ContractError, ContractResult},
     xpub::Xpub,
 };
-use cosmwasm_std::{Addr, Env, QuerierWrapper, Storage};
+use cosmwasm_std::{
+    wasm_execute, Addr, Api, Binary, Coin, CosmosMsg, Env, Order, QuerierWrapper, StdResult,
+    Storage, Uint128,
+};
+use cw_storage_plus::Bound;
 use ibc_proto::cosmos::staking::v1beta1::{BondStatus, QueryValidatorResponse};
+use light_client_bitcoin::msg::QueryMsg::{HeaderHeight, HeaderTime};
 use prost::Message;
 use std::str::FromStr;
 
@@ -48,6 +95,26 @@ pub fn query_check_eligible_validator(
     Ok(true)
 }
 
+pub fn query_jail_status(store: &dyn Storage, addr: Addr) -> ContractResult<JailStatusResponse> {
+    let Some(signer) = JAILED_SIGNERS.may_load(store, addr.as_str())? else {
+        return Ok(JailStatusResponse {
+            jailed: false,
+            jailed_at: None,
+            eligible_at: None,
+            reinstatement_requested: false,
+        });
+    };
+    let cooldown = Bitcoin::default()
+        .config(store)?
+        .signer_reinstatement_cooldown;
+    Ok(JailStatusResponse {
+        jailed: true,
+        jailed_at: Some(signer.jailed_at),
+        eligible_at: Some(signer.jailed_at + cooldown),
+        reinstatement_requested: signer.reinstatement_requested,
+    })
+}
+
 pub fn query_config(store: &dyn Storage) -> ContractResult<ConfigResponse> {
     let config = CONFIG.load(store)?;
     let token_fee = TOKEN_FEE_RATIO.load(store)?;
@@ -62,6 +129,18 @@ pub fn query_config(store: &dyn Storage) -> ContractResult<ConfigResponse> {
         light_client_contract: config.light_client_contract,
         swap_router_contract: config.swap_router_contract,
         osor_entry_point_contract: config.osor_entry_point_contract,
+        secondary_light_client_contract: config.secondary_light_client_contract,
+        notification_contract: config.notification_contract,
+        relayer_fee_options: config.relayer_fee_options,
+    })
+}
+
+pub fn query_light_clients(store: &dyn Storage) -> ContractResult<LightClientsResponse> {
+    let config = CONFIG.load(store)?;
+    Ok(LightClientsResponse {
+        active: config.light_client_contract,
+        secondary: config.secondary_light_client_contract,
+        switched_at: LIGHT_CLIENT_SWITCHED_AT.may_load(store)?,
     })
 }
 
@@ -75,6 +154,20 @@ pub fn query_checkpoint_config(store: &dyn Storage) -> ContractResult<Checkpoint
     Ok(checkpoint_config)
 }
 
+pub fn query_full_config(store: &dyn Storage) -> ContractResult<FullConfigResponse> {
+    let config = query_config(store)?;
+    let btc_denom = get_full_btc_denom(store, config.token_factory_contract.as_str())?;
+    Ok(FullConfigResponse {
+        light_client_contract: config.light_client_contract.clone(),
+        token_factory_contract: config.token_factory_contract.clone(),
+        config,
+        bitcoin_config: query_bitcoin_config(store)?,
+        checkpoint_config: query_checkpoint_config(store)?,
+        btc_denom,
+        state_version: state_version(store)?,
+    })
+}
+
 pub fn query_signatory_key(
     store: &dyn Storage,
     addr: Addr,
@@ -103,9 +196,7 @@ pub fn query_withdrawal_fees(
 ) -> ContractResult<u64> {
     let btc = Bitcoin::default();
     let checkpoint = btc.get_checkpoint(store, index)?;
-    let btc_address = bitcoin::Address::from_str(address.as_str())
-        .map_err(|err| ContractError::App(err.to_string()))?;
-    let script = btc_address.script_pubkey();
+    let script = address_to_script(&address, NETWORK)?;
     let withdrawal_fees =
         btc.calc_minimum_withdrawal_fees(store, script.len() as u64, checkpoint.fee_rate)?;
     Ok(withdrawal_fees)
@@ -121,12 +212,47 @@ pub fn query_checkpoint_fees(store: &dyn Storage, index: Option<u32>) -> Contrac
     Ok(checkpoint_fees)
 }
 
+pub fn query_fee_coverage(store: &dyn Storage) -> ContractResult<FeeCoverage> {
+    let parent_config = BITCOIN_CONFIG.load(store)?;
+    // timestamping commitments are 32 byte hashes
+    CheckpointQueue::default().fee_coverage(store, &parent_config, &[0; 32])
+}
+
+pub fn query_checkpoint_schedule(
+    env: &Env,
+    store: &dyn Storage,
+) -> ContractResult<CheckpointSchedule> {
+    CheckpointQueue::default().schedule(env, store)
+}
+
 pub fn query_checkpoint_by_index(store: &dyn Storage, index: u32) -> ContractResult<Checkpoint> {
     let checkpoints = CheckpointQueue::default();
     let checkpoint = checkpoints.get(store, index)?;
     Ok(checkpoint)
 }
 
+pub fn query_checkpoint_summary(
+    store: &dyn Storage,
+    index: u32,
+) -> ContractResult<CheckpointSummary> {
+    let checkpoint = CheckpointQueue::default().get(store, index)?;
+    let txid = match checkpoint.status {
+        CheckpointStatus::Building => None,
+        _ => Some(checkpoint.checkpoint_tx()?.txid().to_string()),
+    };
+    Ok(CheckpointSummary {
+        index,
+        status: checkpoint.status.clone(),
+        fee_rate: checkpoint.fee_rate,
+        fees_collected: checkpoint.fees_collected,
+        fee_pool_top_up: checkpoint.fee_pool_top_up,
+        withdrawal_fee_surplus: checkpoint.withdrawal_fee_surplus,
+        signed_at_btc_height: checkpoint.signed_at_btc_height,
+        txid,
+        memo: CHECKPOINT_MEMOS.may_load(store, index)?,
+    })
+}
+
 pub fn query_building_checkpoint(store: &dyn Storage) -> ContractResult<Checkpoint> {
     let building_index = query_building_index(store)?;
     let checkpoints = CheckpointQueue::default();
@@ -146,6 +272,81 @@ pub fn query_active_sigset(store: &dyn Storage) -> ContractResult<SignatorySet>
     Ok(active_sigset)
 }
 
+pub fn query_foundation_policy(store: &dyn Storage) -> ContractResult<FoundationPolicyResponse> {
+    let checkpoints = CheckpointQueue::default();
+    let active_sigset = checkpoints.active_sigset(store)?;
+    Ok(FoundationPolicyResponse {
+        policy: active_sigset.foundation_policy,
+        foundation_signatories: active_sigset.foundation_signatories,
+        sigset_index: active_sigset.index,
+    })
+}
+
+pub fn query_sigset_at(store: &dyn Storage, index: u32) -> ContractResult<SigsetResponse> {
+    let checkpoints = CheckpointQueue::default();
+    let sigset = checkpoints.sigset(store, index)?;
+    let threshold = checkpoints.config(store).sigset_threshold;
+    let redeem_script_template = sigset.redeem_script(&[], threshold)?;
+    Ok(SigsetResponse {
+        sigset,
+        threshold,
+        redeem_script_template: Binary::from(redeem_script_template.into_bytes()),
+    })
+}
+
+pub fn query_wallet_descriptor(
+    store: &dyn Storage,
+    sigset_index: u32,
+) -> ContractResult<WalletDescriptorResponse> {
+    let checkpoints = CheckpointQueue::default();
+    let sigset = checkpoints.sigset(store, sigset_index)?;
+    let threshold = checkpoints.config(store).sigset_threshold;
+    // reserve outputs commit to a zero destination
+    let redeem_script = sigset.redeem_script(&[0u8], threshold)?;
+    let output_script = redeem_script.to_p2wsh();
+
+    let mut derived = vec![];
+    for item in SIG_KEYS.range(store, None, None, Order::Ascending) {
+        let (_, xpub) = item?;
+        let pubkey: Pubkey = xpub.derive_pubkey(sigset_index)?.into();
+        derived.push((pubkey, format!("{}/{}", xpub.key, sigset_index)));
+    }
+    for xpub in FOUNDATION_KEYS.may_load(store)?.unwrap_or_default() {
+        derived.push((xpub.public_key.into(), xpub.key.to_string()));
+    }
+    let find_key = |pubkey: &Pubkey| {
+        derived
+            .iter()
+            .find(|(derived, _)| derived == pubkey)
+            .map(|(_, key)| key.clone())
+    };
+
+    let signatories = sigset
+        .signatories
+        .iter()
+        .map(|signatory| (signatory, false));
+    let foundation = sigset
+        .foundation_signatories
+        .iter()
+        .map(|signatory| (signatory, true));
+    let keys = signatories
+        .chain(foundation)
+        .map(|(signatory, foundation)| DescriptorKey {
+            pubkey: Binary::from(signatory.pubkey.as_slice()),
+            key: find_key(&signatory.pubkey),
+            voting_power: signatory.voting_power,
+            foundation,
+        })
+        .collect();
+
+    Ok(WalletDescriptorResponse {
+        descriptor: raw_descriptor(&output_script)?,
+        address: script_to_address(&output_script, NETWORK)?.to_string(),
+        redeem_script: Binary::from(redeem_script.into_bytes()),
+        keys,
+    })
+}
+
 pub fn query_checkpoint_tx(
     store: &dyn Storage,
     index: Option<u32>,
@@ -158,6 +359,346 @@ pub fn query_checkpoint_tx(
     checkpoint.checkpoint_tx()
 }
 
+pub fn query_checkpoint_inputs(
+    store: &dyn Storage,
+    index: u32,
+) -> ContractResult<Vec<CheckpointInput>> {
+    let checkpoint = CheckpointQueue::default().get(store, index)?;
+    let checkpoint_tx = &checkpoint.batches[BatchType::Checkpoint][0];
+    let mut inputs = vec![];
+    for input in checkpoint_tx.input.iter() {
+        let txid = input.prevout.txid.to_string();
+        let vout = input.prevout.vout;
+        // reserve outputs commit to a single zero byte
+        let source = if input.dest == [0u8] {
+            match RESERVE_UTXOS.has(store, (&txid, vout)) {
+                true => InputSource::RegisteredReserve,
+                false => InputSource::Reserve,
+            }
+        } else {
+            match MINT_RECORDS.may_load(store, (&txid, vout))? {
+                Some(record) if record.checkpoint_index < index => InputSource::ExcessRollover {
+                    dest: record.dest,
+                    btc_height: record.btc_height,
+                    from_checkpoint: record.checkpoint_index,
+                },
+                Some(record) => InputSource::Deposit {
+                    dest: record.dest,
+                    btc_height: record.btc_height,
+                },
+                None => InputSource::Unknown,
+            }
+        };
+        inputs.push(CheckpointInput {
+            txid,
+            vout,
+            amount: input.amount,
+            sigset_index: input.sigset_index,
+            commitment: Binary::from(input.dest.clone()),
+            source,
+        });
+    }
+    Ok(inputs)
+}
+
+pub fn query_checkpoint_psbt(store: &dyn Storage, index: u32) -> ContractResult<Binary> {
+    let checkpoints = CheckpointQueue::default();
+    let checkpoint = checkpoints.get(store, index)?;
+    let mut xpubs = SIG_KEYS
+        .range(store, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, xpub)| xpub))
+        .collect::<StdResult<Vec<_>>>()?;
+    xpubs.extend(FOUNDATION_KEYS.may_load(store)?.unwrap_or_default());
+    let psbt = checkpoint.checkpoint_psbt(&xpubs)?;
+    Ok(Binary::from(psbt.serialize()))
+}
+
+pub fn query_checkpoint_txid(store: &dyn Storage, index: u32) -> ContractResult<String> {
+    Ok(CheckpointQueue::default().txid(store, index)?.to_string())
+}
+
+pub fn query_checkpoint_index_by_txid(
+    store: &dyn Storage,
+    txid: String,
+) -> ContractResult<Option<u32>> {
+    Ok(CHECKPOINT_TXIDS.may_load(store, &txid)?)
+}
+
+pub fn query_checkpoint_txid_preimage(store: &dyn Storage, index: u32) -> ContractResult<Binary> {
+    let checkpoints = CheckpointQueue::default();
+    let mut tx = checkpoints.get(store, index)?.checkpoint_tx()?.into_inner();
+    for txin in tx.input.iter_mut() {
+        txin.witness = bitcoin::Witness::default();
+    }
+    Ok(Binary::from(bitcoin::consensus::encode::serialize(&tx)))
+}
+
+pub fn query_raw_tx_hex(
+    store: &dyn Storage,
+    kind: RawTxKind,
+    index: u32,
+) -> ContractResult<RawTxResponse> {
+    let tx = match kind {
+        RawTxKind::CheckpointTx => {
+            let checkpoint = CheckpointQueue::default().get(store, index)?;
+            if !checkpoint.signed() {
+                return Err(ContractError::App(
+                    "Checkpoint is not fully signed".to_string(),
+                ));
+            }
+            checkpoint.checkpoint_tx()?.into_inner()
+        }
+        RawTxKind::RecoveryTx => RecoveryTxs::default().signed_tx(store, index)?,
+        RawTxKind::DisbursalTx => {
+            return Err(ContractError::App(
+                "Emergency disbursal transactions are not built".to_string(),
+            ))
+        }
+    };
+    Ok(RawTxResponse {
+        hex: bitcoin::consensus::encode::serialize_hex(&tx),
+        txid: tx.txid().to_string(),
+    })
+}
+
+pub fn query_mint_record(
+    store: &dyn Storage,
+    txid: String,
+    vout: u32,
+) -> ContractResult<Option<MintRecord>> {
+    Ok(MINT_RECORDS.may_load(store, (&txid, vout))?)
+}
+
+pub fn query_reorged_deposits(
+    store: &dyn Storage,
+    start_after: Option<(String, u32)>,
+    limit: Option<u32>,
+) -> ContractResult<Vec<ReorgedDeposit>> {
+    let limit = limit.unwrap_or(DEFAULT_QUERY_LIMIT).min(MAX_QUERY_LIMIT) as usize;
+    let start = start_after
+        .as_ref()
+        .map(|(txid, vout)| Bound::exclusive((txid.as_str(), *vout)));
+    Ok(REORGED_DEPOSITS
+        .range(store, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, deposit)| deposit))
+        .collect::<StdResult<_>>()?)
+}
+
+pub fn query_bad_debt(store: &dyn Storage) -> ContractResult<Uint128> {
+    Ok(BAD_DEBT.may_load(store)?.unwrap_or_default())
+}
+
+pub fn query_burns(
+    store: &dyn Storage,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> ContractResult<Vec<(u64, BurnRecord)>> {
+    let limit = limit.unwrap_or(DEFAULT_QUERY_LIMIT).min(MAX_QUERY_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+    Ok(BURNS
+        .range(store, start, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<_>>()?)
+}
+
+pub fn query_total_burned(store: &dyn Storage) -> ContractResult<Uint128> {
+    Ok(TOTAL_BURNED.may_load(store)?.unwrap_or_default())
+}
+
+pub fn query_expired_deposits(
+    store: &dyn Storage,
+    dest: Dest,
+    start_after: Option<(String, u32)>,
+    limit: Option<u32>,
+) -> ContractResult<Vec<ExpiredDeposit>> {
+    let limit = limit.unwrap_or(DEFAULT_QUERY_LIMIT).min(MAX_QUERY_LIMIT) as usize;
+    let key = dest.commitment_bytes()?;
+    let start = start_after
+        .as_ref()
+        .map(|(txid, vout)| Bound::exclusive((txid.as_str(), *vout)));
+    EXPIRED_DEPOSITS_BY_DEST
+        .sub_prefix(key.as_slice())
+        .keys(store, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (txid, vout) = item?;
+            Ok(EXPIRED_DEPOSITS.load(store, (&txid, vout))?)
+        })
+        .collect()
+}
+
+/// The window volume reads 0 once the window has passed without deposits.
+fn current_dest_stats(env: &Env, mut stats: DestStats) -> DestStats {
+    if bridge_time(env) >= stats.window_started_at + DEST_STATS_WINDOW {
+        stats.window_volume = 0;
+    }
+    stats
+}
+
+pub fn query_dest_stats(
+    store: &dyn Storage,
+    env: Env,
+    dest: Dest,
+) -> ContractResult<Option<DestStats>> {
+    let stats = DEST_STATS.may_load(store, &dest.commitment_bytes()?)?;
+    Ok(stats.map(|stats| current_dest_stats(&env, stats)))
+}
+
+pub fn query_top_destinations(
+    store: &dyn Storage,
+    env: Env,
+    limit: Option<u32>,
+) -> ContractResult<Vec<DestStats>> {
+    let limit = limit.unwrap_or(DEFAULT_QUERY_LIMIT).min(MAX_QUERY_LIMIT) as usize;
+    DEST_STATS_BY_TOTAL
+        .keys(store, None, None, Order::Descending)
+        .take(limit)
+        .map(|item| -> ContractResult<_> {
+            let (_, key) = item?;
+            Ok(current_dest_stats(&env, DEST_STATS.load(store, &key)?))
+        })
+        .collect()
+}
+
+pub fn query_pending_deposits(
+    store: &dyn Storage,
+    env: Env,
+    dest: Dest,
+    start_after: Option<(String, u32)>,
+    limit: Option<u32>,
+) -> ContractResult<Vec<PendingDeposit>> {
+    let now = bridge_time(&env);
+    let limit = limit.unwrap_or(DEFAULT_QUERY_LIMIT).min(MAX_QUERY_LIMIT) as usize;
+    let key = dest.commitment_bytes()?;
+    let start = start_after
+        .as_ref()
+        .map(|(txid, vout)| Bound::exclusive((txid.as_str(), *vout)));
+    PENDING_DEPOSITS_BY_DEST
+        .sub_prefix(key.as_slice())
+        .keys(store, start, None, Order::Ascending)
+        .map(|item| {
+            let (txid, vout) = item?;
+            Ok(PENDING_DEPOSITS.load(store, (&txid, vout))?)
+        })
+        // expired deposits not pruned yet
+        .filter(|deposit: &ContractResult<PendingDeposit>| {
+            deposit
+                .as_ref()
+                .map_or(true, |deposit| deposit.expires_at > now)
+        })
+        .take(limit)
+        .collect()
+}
+
+pub fn query_pools(store: &dyn Storage) -> ContractResult<PoolsResponse> {
+    let config = BITCOIN_CONFIG.load(store)?;
+    Ok(PoolsResponse {
+        fee_pool: FEE_POOL.may_load(store)?.unwrap_or_default(),
+        fee_pool_target: config.fee_pool_target_balance * config.units_per_sat,
+        reward_pool: REWARD_POOL.may_load(store)?.unwrap_or_default(),
+    })
+}
+
+pub fn query_reward_balance(
+    store: &dyn Storage,
+    api: &dyn Api,
+    address: String,
+) -> ContractResult<Uint128> {
+    let address = api.addr_validate(&address)?;
+    let balance = REWARD_BALANCES
+        .may_load(store, &address)?
+        .unwrap_or_default();
+    Ok(Uint128::from(balance))
+}
+
+pub fn query_withdrawal_allowance(
+    store: &dyn Storage,
+    api: &dyn Api,
+    granter: String,
+    spender: String,
+) -> ContractResult<Option<WithdrawalAllowance>> {
+    let granter = api.addr_validate(&granter)?;
+    let spender = api.addr_validate(&spender)?;
+    Ok(WITHDRAWAL_ALLOWANCES.may_load(store, (&granter, &spender))?)
+}
+
+pub fn query_withdrawal_allowances(
+    store: &dyn Storage,
+    api: &dyn Api,
+    granter: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> ContractResult<Vec<(Addr, WithdrawalAllowance)>> {
+    let granter = api.addr_validate(&granter)?;
+    let start_after = start_after
+        .map(|spender| api.addr_validate(&spender))
+        .transpose()?;
+    let limit = limit.unwrap_or(DEFAULT_QUERY_LIMIT).min(MAX_QUERY_LIMIT) as usize;
+    let allowances = WITHDRAWAL_ALLOWANCES
+        .prefix(&granter)
+        .range(
+            store,
+            start_after.as_ref().map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(allowances)
+}
+
+pub fn query_mint_claims(
+    store: &dyn Storage,
+    api: &dyn Api,
+    recipient: String,
+) -> ContractResult<Vec<MintClaim>> {
+    let recipient = api.addr_validate(&recipient)?;
+    Ok(MINT_CLAIMS
+        .prefix(&recipient)
+        .range(store, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, claim)| claim))
+        .collect::<StdResult<_>>()?)
+}
+
+pub fn query_ibc_transfers(
+    store: &dyn Storage,
+    start_after: Option<(String, u64)>,
+    limit: Option<u32>,
+) -> ContractResult<Vec<IbcTransfer>> {
+    let limit = limit.unwrap_or(DEFAULT_QUERY_LIMIT).min(MAX_QUERY_LIMIT) as usize;
+    let start = start_after
+        .as_ref()
+        .map(|(channel, sequence)| Bound::exclusive((channel.as_str(), *sequence)));
+    Ok(IBC_TRANSFERS
+        .range(store, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, transfer)| transfer))
+        .collect::<StdResult<_>>()?)
+}
+
+pub fn query_ibc_refunds(store: &dyn Storage, address: String) -> ContractResult<Vec<Coin>> {
+    Ok(IBC_REFUNDS
+        .prefix(&address)
+        .range(store, None, None, Order::Ascending)
+        .map(|item| item.map(|(denom, amount)| Coin { denom, amount }))
+        .collect::<StdResult<_>>()?)
+}
+
+pub fn query_fee_exemptions(
+    store: &dyn Storage,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> ContractResult<Vec<String>> {
+    let limit = limit.unwrap_or(DEFAULT_QUERY_LIMIT).min(MAX_QUERY_LIMIT) as usize;
+    let start = start_after.as_deref().map(Bound::exclusive);
+    let exemptions = FEE_EXEMPTIONS
+        .keys(store, start, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(exemptions)
+}
+
 pub fn query_last_complete_tx(store: &dyn Storage) -> ContractResult<Adapter<Transaction>> {
     let checkpoints = CheckpointQueue::default();
     let last_complete_tx = checkpoints.last_completed_tx(store)?;
@@ -188,6 +729,44 @@ pub fn query_signing_recovery_txs(
     recovery_txs.to_sign(store, &xpub.0)
 }
 
+pub fn query_signing_recovery_group(
+    store: &dyn Storage,
+    xpub: WrappedBinary<Xpub>,
+    sigset_index: u32,
+) -> ContractResult<Vec<([u8; 32], u32)>> {
+    RecoveryTxs::default().to_sign_group(store, &xpub.0, sigset_index)
+}
+
+pub fn query_recovery_groups(
+    store: &dyn Storage,
+    xpub: Option<WrappedBinary<Xpub>>,
+) -> ContractResult<Vec<RecoveryGroupStatus>> {
+    RecoveryTxs::default().groups(store, xpub.as_ref().map(|xpub| &xpub.0))
+}
+
+pub fn query_signing_duties(
+    env: &Env,
+    store: &dyn Storage,
+    xpub: WrappedBinary<Xpub>,
+) -> ContractResult<Vec<SigningDuty>> {
+    let checkpoints = CheckpointQueue::default();
+    let mut duties = vec![];
+    if let Some(signing) = checkpoints.signing(store)? {
+        let index = checkpoints.index(store) - 1;
+        let age = bridge_time(env).saturating_sub(signing.create_time());
+        duties.extend(signing.duties(index, &xpub.0, age)?);
+    }
+    duties.extend(RecoveryTxs::default().duties(store, &xpub.0)?);
+
+    duties.sort_by(|a, b| {
+        b.age
+            .unwrap_or_default()
+            .cmp(&a.age.unwrap_or_default())
+            .then(a.remaining_power.cmp(&b.remaining_power))
+    });
+    Ok(duties)
+}
+
 pub fn query_single_signing_recovery_txs(
     _querier: QuerierWrapper,
     store: &dyn Storage,
@@ -205,6 +784,10 @@ pub fn query_comfirmed_index(store: &dyn Storage) -> ContractResult<Option<u32>>
     Ok(confirmed_index)
 }
 
+pub fn query_is_checkpoint_confirmed(store: &dyn Storage, index: u32) -> ContractResult<bool> {
+    Bitcoin::default().is_checkpoint_confirmed(store, index)
+}
+
 pub fn query_first_unconfirmed_index(store: &dyn Storage) -> ContractResult<Option<u32>> {
     let checkpoints: CheckpointQueue = CheckpointQueue::default();
     let first_unconfirmed_index = checkpoints.first_unconfirmed_index(store)?;
@@ -256,6 +839,15 @@ pub fn query_signing_txs_at_checkpoint_index(
     checkpoint.to_sign(&xpub.0)
 }
 
+pub fn query_checkpoint_signing_progress(
+    store: &dyn Storage,
+    xpub: WrappedBinary<Xpub>,
+    cp_index: u32,
+) -> ContractResult<SigningProgress> {
+    let checkpoints = CheckpointQueue::default();
+    checkpoints.get(store, cp_index)?.signing_progress(&xpub.0)
+}
+
 pub fn query_single_signing_txs_at_checkpoint_index(
     store: &dyn Storage,
     xpub: WrappedBinary<Xpub>,
@@ -277,14 +869,749 @@ pub fn query_change_rates(
     env: Env,
     interval: u64,
 ) -> ContractResult<ChangeRates> {
-    let now = env.block.time;
+    let now = bridge_time(&env);
     let btc = Bitcoin::default();
-    let change_rates = btc.change_rates(store, interval, now.seconds())?;
+    let change_rates = btc.change_rates(store, interval, now)?;
     Ok(change_rates)
 }
 
+pub fn query_estimated_withdrawal_eta(store: &dyn Storage, env: Env) -> ContractResult<u64> {
+    let checkpoints = CheckpointQueue::default();
+    checkpoints.estimated_withdrawal_eta(store, bridge_time(&env))
+}
+
+pub fn query_metrics(
+    store: &dyn Storage,
+    querier: QuerierWrapper,
+) -> ContractResult<MetricsResponse> {
+    let config = CONFIG.load(store)?;
+    let checkpoints = CheckpointQueue::default();
+    let header_height: u32 =
+        querier.query_wasm_smart(config.light_client_contract.clone(), &HeaderHeight {})?;
+    let header_time: u32 =
+        querier.query_wasm_smart(config.light_client_contract, &HeaderTime {})?;
+    let mut metrics = MetricsResponse {
+        total_checkpoints: checkpoints.len(store)?,
+        fee_pool: FEE_POOL.may_load(store)?.unwrap_or_default(),
+        header_height,
+        header_time,
+        ..Default::default()
+    };
+    let confirmation_times = CHECKPOINT_CONFIRMATION_TIMES
+        .range(store, None, None, Order::Descending)
+        .take(20)
+        .map(|item| item.map(|(_, time)| time))
+        .collect::<StdResult<Vec<_>>>()?;
+    if !confirmation_times.is_empty() {
+        metrics.avg_confirmation_time =
+            confirmation_times.iter().sum::<u64>() / confirmation_times.len() as u64;
+    }
+    if checkpoints.is_empty(store)? {
+        return Ok(metrics);
+    }
+
+    let building = checkpoints.building(store)?;
+    metrics.building_index = checkpoints.index(store);
+    metrics.unconfirmed_checkpoints = checkpoints.num_unconfirmed(store)?;
+    metrics.fee_rate = building.fee_rate;
+    metrics.pending_withdrawals = building.checkpoint_tx()?.output.len() as u32;
+    if let Ok(last_completed) = checkpoints.last_completed(store) {
+        metrics.reserve_value = last_completed
+            .reserve_output()?
+            .map_or(0, |out| out.value.to_sat());
+    }
+
+    Ok(metrics)
+}
+
+pub fn query_withdrawal(
+    store: &dyn Storage,
+    withdrawal_id: u64,
+) -> ContractResult<Option<QueuedWithdrawal>> {
+    Ok(WITHDRAWALS.may_load(store, withdrawal_id)?)
+}
+
+pub fn query_withdrawal_quote(
+    store: &dyn Storage,
+    quote_id: u64,
+) -> ContractResult<Option<WithdrawalQuote>> {
+    Ok(WITHDRAWAL_QUOTES.may_load(store, quote_id)?)
+}
+
+pub fn query_screened_scripts(
+    store: &dyn Storage,
+    start_after: Option<Binary>,
+    limit: Option<u32>,
+) -> ContractResult<Vec<Binary>> {
+    let limit = limit.unwrap_or(DEFAULT_QUERY_LIMIT).min(MAX_QUERY_LIMIT) as usize;
+    let start = start_after
+        .as_ref()
+        .map(|script| Bound::exclusive(script.as_slice()));
+    Ok(SCREENED_SCRIPTS
+        .keys(store, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(Binary::from))
+        .collect::<StdResult<_>>()?)
+}
+
+pub fn query_held_withdrawals(
+    store: &dyn Storage,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> ContractResult<Vec<(u64, HeldWithdrawal)>> {
+    let limit = limit.unwrap_or(DEFAULT_QUERY_LIMIT).min(MAX_QUERY_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+    Ok(HELD_WITHDRAWALS
+        .range(store, start, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<_>>()?)
+}
+
+pub fn query_hooks(
+    store: &dyn Storage,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> ContractResult<Vec<(u64, CheckpointHook)>> {
+    let limit = limit.unwrap_or(DEFAULT_QUERY_LIMIT).min(MAX_QUERY_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+    Ok(CHECKPOINT_HOOKS
+        .range(store, start, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<_>>()?)
+}
+
+pub fn query_tombstones(
+    store: &dyn Storage,
+    start_after: Option<Binary>,
+    limit: Option<u32>,
+) -> ContractResult<Vec<Tombstone>> {
+    let limit = limit.unwrap_or(DEFAULT_QUERY_LIMIT).min(MAX_QUERY_LIMIT) as usize;
+    let start = start_after
+        .as_ref()
+        .map(|key| Bound::exclusive(key.as_slice()));
+    Ok(TOMBSTONED_XPUBS
+        .range(store, start, None, Order::Ascending)
+        .take(limit)
+        .map(|entry| entry.map(|(_, tombstone)| tombstone))
+        .collect::<StdResult<_>>()?)
+}
+
+pub fn query_app_data_proof(
+    store: &dyn Storage,
+    checkpoint_index: u32,
+    data: Binary,
+) -> ContractResult<AppDataProof> {
+    let committed = APP_DATA
+        .may_load(store, checkpoint_index)?
+        .unwrap_or_default();
+    let position = committed
+        .iter()
+        .position(|item| *item == data)
+        .ok_or_else(|| ContractError::App("App data not committed".to_string()))?;
+    let internal_commitment = APP_DATA_INTERNAL_COMMITMENTS
+        .may_load(store, checkpoint_index)?
+        .ok_or_else(|| ContractError::App("Checkpoint has not been built yet".to_string()))?;
+
+    let leaves = app_data_leaves(&internal_commitment, &committed)?;
+    let leaf_index = position + 1;
+    Ok(AppDataProof {
+        checkpoint_index,
+        leaf_index: leaf_index as u32,
+        branch: merkle_branch(&leaves, leaf_index)
+            .into_iter()
+            .map(|hash| Binary::from(hash.to_vec()))
+            .collect(),
+        root: Binary::from(merkle_root(&leaves).to_vec()),
+        internal_commitment,
+    })
+}
+
+pub fn query_reclaimed_signature_bytes(store: &dyn Storage) -> ContractResult<u64> {
+    Ok(RECLAIMED_SIGNATURE_BYTES
+        .may_load(store)?
+        .unwrap_or_default())
+}
+
+pub fn query_relayer_fee_options(store: &dyn Storage) -> ContractResult<Vec<Coin>> {
+    Ok(CONFIG.load(store)?.relayer_fee_options)
+}
+
+pub fn query_validator_set(store: &dyn Storage) -> ContractResult<Vec<ValidatorInfo>> {
+    VALIDATORS
+        .range(store, None, None, Order::Ascending)
+        .map(|item| {
+            let (consensus_key, (voting_power, addr)) = item?;
+            Ok(ValidatorInfo {
+                addr,
+                consensus_key,
+                voting_power,
+            })
+        })
+        .collect()
+}
+
+pub fn query_scheduled_validator_set(
+    store: &dyn Storage,
+) -> ContractResult<Option<ScheduledValidatorSet>> {
+    Ok(SCHEDULED_VALIDATOR_SET.may_load(store)?)
+}
+
+pub fn query_swap_provider(
+    store: &dyn Storage,
+    provider: Addr,
+) -> ContractResult<Option<SwapProvider>> {
+    Ok(SWAP_PROVIDERS.may_load(store, &provider)?)
+}
+
+pub fn query_lightning_swap(
+    store: &dyn Storage,
+    swap_id: u64,
+) -> ContractResult<Option<LightningSwap>> {
+    Ok(LIGHTNING_SWAPS.may_load(store, swap_id)?)
+}
+
+pub fn query_export_state(
+    store: &dyn Storage,
+    section: StateSection,
+    start_after: Option<Binary>,
+    limit: Option<u32>,
+) -> ContractResult<Vec<StateEntry>> {
+    let limit = limit.unwrap_or(DEFAULT_QUERY_LIMIT).min(MAX_QUERY_LIMIT) as usize;
+    export_section(store, section, start_after, limit)
+}
+
+pub fn query_bridge_migration(store: &dyn Storage) -> ContractResult<Option<BridgeMigration>> {
+    Ok(BRIDGE_MIGRATION.may_load(store)?)
+}
+
+pub fn query_migration_handshake(
+    store: &dyn Storage,
+    predecessor: Addr,
+) -> ContractResult<MigrationHandshakeResponse> {
+    let accepted = MIGRATION_PREDECESSOR.may_load(store)? == Some(predecessor);
+    let checkpoints = CheckpointQueue::default();
+    let sigset = checkpoints.building(store)?.sigset.clone();
+    let threshold = checkpoints.config(store).sigset_threshold;
+    let reserve_script = sigset.output_script(&[0u8], threshold)?;
+    Ok(MigrationHandshakeResponse {
+        accepted,
+        sigset_index: sigset.index,
+        reserve_script: Binary::from(reserve_script.to_bytes()),
+    })
+}
+
+pub fn query_deposit_address_status(
+    store: &dyn Storage,
+    querier: &QuerierWrapper,
+    env: Env,
+    dest: Dest,
+    sigset_index: u32,
+) -> ContractResult<DepositAddressStatusResponse> {
+    let btc = Bitcoin::default();
+    let checkpoints = &btc.checkpoints;
+    let sigset = checkpoints.sigset(store, sigset_index)?;
+    let active_sigset = checkpoints.active_sigset(store)?;
+    let status = if sigset.index == active_sigset.index {
+        DepositAddressStatus::Active
+    } else if btc.is_sigset_expired_now(&env, querier, store, &sigset)? {
+        DepositAddressStatus::Expired
+    } else {
+        DepositAddressStatus::Stale
+    };
+
+    let dest_bytes = dest.commitment_bytes()?;
+    let replacement_address = match status {
+        DepositAddressStatus::Active => None,
+        _ => {
+            let threshold = checkpoints.config(store).sigset_threshold;
+            let commitment = dest.versioned_commitment_bytes(DEST_COMMITMENT_VERSION)?;
+            let script = active_sigset.output_script(&commitment, threshold)?;
+            Some(script_to_address(&script, NETWORK)?.to_string())
+        }
+    };
+
+    Ok(DepositAddressStatusResponse {
+        status,
+        latest_deposit_sigset_index: DEST_SIGSET_INDEXES.may_load(store, &dest_bytes)?,
+        replacement_address,
+    })
+}
+
+pub fn query_dest_commitment(dest: Dest) -> ContractResult<DestCommitmentResponse> {
+    Ok(DestCommitmentResponse {
+        version: DEST_COMMITMENT_VERSION,
+        commitment: Binary::from(dest.versioned_commitment_bytes(DEST_COMMITMENT_VERSION)?),
+        legacy_commitment: Binary::from(dest.commitment_bytes()?),
+    })
+}
+
+pub fn query_check_recovery_address(
+    store: &dyn Storage,
+    btc_address: String,
+) -> ContractResult<Option<String>> {
+    let config = Bitcoin::default().config(store)?;
+    let checked = address_to_script(&btc_address, NETWORK)
+        .and_then(|script| config.check_recovery_script(&script));
+    Ok(checked.err().map(|err| err.to_string()))
+}
+
+/// Applies the fee math of relaying a deposit and minting it to its
+/// destination, without a Bitcoin transaction.
+pub fn query_simulate_deposit(
+    env: &Env,
+    store: &dyn Storage,
+    querier: &QuerierWrapper,
+    api: &dyn Api,
+    value_sats: u64,
+    sigset_index: u32,
+    dest: Dest,
+) -> ContractResult<SimulateDepositResponse> {
+    let btc = Bitcoin::default();
+    let config = btc.config(store)?;
+    let checkpoint = btc.checkpoints.get(store, sigset_index)?;
+    if !checkpoint.deposits_enabled {
+        return Err(ContractError::App(
+            "Deposits are disabled for the given checkpoint".to_string(),
+        ));
+    }
+    if btc.is_sigset_expired_now(env, querier, store, &checkpoint.sigset)? {
+        return Err(ContractError::App(
+            "Deposits to the given sigset have expired".to_string(),
+        ));
+    }
+
+    let commitment = dest.versioned_commitment_bytes(DEST_COMMITMENT_VERSION)?;
+    let input = Input::new(
+        bitcoin::OutPoint::default(),
+        &checkpoint.sigset,
+        &commitment,
+        value_sats,
+        btc.checkpoints.config(store).sigset_threshold,
+    )?;
+    btc.check_relayable_utxo(env, store, value_sats, input.est_vsize())?;
+    let minted = Uint128::from(value_sats * config.units_per_sat);
+    let (miner_fee, deposit_fee) = btc.deposit_fees(
+        store,
+        sigset_index,
+        checkpoint.fee_rate,
+        input.est_vsize(),
+        minted,
+    )?;
+    let credited = minted
+        .checked_sub((miner_fee + deposit_fee).into())
+        .map_err(|_| {
+            ContractError::App("Deposit amount is too small to pay its spending fee".to_string())
+        })?;
+
+    let denom = get_full_btc_denom(store, CONFIG.load(store)?.token_factory_contract.as_str())?;
+    let fee_data = process_deduct_fee_for(
+        store,
+        querier,
+        api,
+        &dest.to_source_addr(),
+        Coin::new(credited.u128(), denom),
+    )?;
+    Ok(SimulateDepositResponse {
+        minted,
+        miner_fee: miner_fee.into(),
+        deposit_fee: deposit_fee.into(),
+        token_fee: fee_data.token_fee.amount,
+        relayer_fee: fee_data.relayer_fee.amount,
+        net_credit: fee_data.deducted_amount,
+    })
+}
+
+/// Replicates the checks `RelayDeposit` runs before processing a deposit,
+/// except those needing the transaction itself.
+#[allow(clippy::too_many_arguments)]
+pub fn query_check_relay_deposit(
+    env: &Env,
+    store: &dyn Storage,
+    querier: &QuerierWrapper,
+    txid: String,
+    vout: u32,
+    sigset_index: u32,
+    dest: Dest,
+    btc_height: u32,
+    script_pubkey: Option<Binary>,
+) -> ContractResult<CheckRelayDepositResponse> {
+    let btc = Bitcoin::default();
+    let reject = |rejection, script_pubkey| {
+        Ok(CheckRelayDepositResponse {
+            rejection: Some(rejection),
+            expired: false,
+            script_pubkey,
+        })
+    };
+    let txid = bitcoin::Txid::from_str(&txid).map_err(|err| ContractError::App(err.to_string()))?;
+
+    match btc.ensure_not_paused(store) {
+        Err(ContractError::BridgePaused { reason }) => {
+            return reject(RelayDepositRejection::BridgePaused { reason }, None)
+        }
+        result => result?,
+    }
+    let light_client = CONFIG.load(store)?.light_client_contract;
+    let sidechain_btc_height: u32 = querier.query_wasm_smart(light_client, &HeaderHeight {})?;
+    if sidechain_btc_height < btc_height {
+        return reject(
+            RelayDepositRejection::FutureHeight {
+                sidechain_btc_height,
+            },
+            None,
+        );
+    }
+    let min_confirmations = btc.config(store)?.min_confirmations;
+    if sidechain_btc_height - btc_height < min_confirmations {
+        return reject(
+            RelayDepositRejection::InsufficientConfirmations {
+                have: sidechain_btc_height - btc_height,
+                need: min_confirmations,
+            },
+            None,
+        );
+    }
+    if BRIDGE_MIGRATION.may_load(store)?.is_some() {
+        return reject(RelayDepositRejection::DepositsFrozen, None);
+    }
+    let Ok(checkpoint) = btc.checkpoints.get(store, sigset_index) else {
+        return reject(RelayDepositRejection::UnknownSigset, None);
+    };
+
+    let threshold = btc.checkpoints.config(store).sigset_threshold;
+    let mut scripts = vec![];
+    for version in [DEST_COMMITMENT_VERSION, LEGACY_DEST_COMMITMENT_VERSION] {
+        let commitment = dest.versioned_commitment_bytes(version)?;
+        scripts.push(checkpoint.sigset.output_script(&commitment, threshold)?);
+    }
+    let expected_script = Some(Binary::from(scripts[0].to_bytes()));
+    if let Some(script_pubkey) = script_pubkey {
+        if !scripts
+            .iter()
+            .any(|script| script.as_bytes() == script_pubkey.as_slice())
+        {
+            return reject(RelayDepositRejection::ScriptMismatch, expected_script);
+        }
+    }
+    if btc
+        .processed_outpoints
+        .contains(store, bitcoin::OutPoint::new(txid, vout))
+    {
+        return reject(RelayDepositRejection::AlreadyProcessed, expected_script);
+    }
+    if !checkpoint.deposits_enabled {
+        return reject(RelayDepositRejection::DepositsDisabled, expected_script);
+    }
+
+    Ok(CheckRelayDepositResponse {
+        rejection: None,
+        expired: btc.is_sigset_expired(env, store, &checkpoint.sigset, btc_height)?,
+        script_pubkey: expected_script,
+    })
+}
+
+pub fn query_bridge_paused(store: &dyn Storage) -> ContractResult<bool> {
+    Ok(BRIDGE_PAUSED.may_load(store)?.unwrap_or_default()
+        || HEADER_STALL_PAUSED.may_load(store)?.unwrap_or_default())
+}
+
+/// Runs `ClockEndBlock` on a dry run storage and compares the resulting state
+/// with the current one.
+pub fn query_simulate_end_block(
+    _env: &Env,
+    store: &dyn Storage,
+    querier: QuerierWrapper,
+    api: &dyn Api,
+) -> ContractResult<EndBlockReport> {
+    let btc = Bitcoin::default();
+    let building_before = if CHECKPOINTS.is_empty(store)? {
+        None
+    } else {
+        Some(btc.checkpoints.building(store)?)
+    };
+    let building_index_before = btc.checkpoints.index(store);
+    let fee_pool_before = FEE_POOL.may_load(store)?.unwrap_or_default();
+    let signers_before = SIGNERS
+        .keys(store, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<String>>>()?;
+
+    // the real block hash is unknown ahead of the block, it is only used as
+    // the timestamping commitment
+    let hash = sha256::Hash::hash(&_env.block.height.to_be_bytes());
+    let mut dry_run = DryRunStorage::new(store);
+    let response = clock_end_block(
+        _env,
+        &mut dry_run,
+        &querier,
+        api,
+        Binary::from(hash.to_byte_array().to_vec()),
+    )?;
+
+    let (checkpoint_advanced, checkpoint_fee, checkpoint_fee_rate) = match &building_before {
+        Some(_) => {
+            let checkpoint = btc.checkpoints.get(&dry_run, building_index_before)?;
+            if checkpoint.status == CheckpointStatus::Building {
+                (false, None, None)
+            } else {
+                (
+                    true,
+                    Some(checkpoint.checkpoint_tx_miner_fees()?),
+                    Some(checkpoint.fee_rate),
+                )
+            }
+        }
+        None => (!CHECKPOINTS.is_empty(&dry_run)?, None, None),
+    };
+    let (new_sigset_index, sigset_rotated) = if checkpoint_advanced {
+        let sigset = btc.checkpoints.building(&dry_run)?.sigset;
+        let rotated = building_before.map_or(true, |building| {
+            let pubkeys = |sigset: &SignatorySet| {
+                sigset
+                    .signatories
+                    .iter()
+                    .map(|signatory| signatory.pubkey.clone())
+                    .collect::<Vec<_>>()
+            };
+            pubkeys(&building.sigset) != pubkeys(&sigset)
+        });
+        (Some(sigset.index), rotated)
+    } else {
+        (None, false)
+    };
+    let removed_signers = signers_before
+        .into_iter()
+        .filter(|addr| !SIGNERS.has(&dry_run, addr))
+        .collect();
+
+    Ok(EndBlockReport {
+        checkpoint_advanced,
+        building_index: btc.checkpoints.index(&dry_run),
+        new_sigset_index,
+        sigset_rotated,
+        checkpoint_fee,
+        checkpoint_fee_rate,
+        fee_pool_change: FEE_POOL.may_load(&dry_run)?.unwrap_or_default() - fee_pool_before,
+        removed_signers,
+        bridge_paused: query_bridge_paused(&dry_run)?,
+        messages: response.messages.len() as u32,
+        events: response.events.into_iter().map(|event| event.ty).collect(),
+    })
+}
+
+pub fn query_owner_proposal(
+    _env: &Env,
+    store: &dyn Storage,
+    querier: QuerierWrapper,
+    title: String,
+    description: String,
+    msgs: Vec<ExecuteMsg>,
+) -> ContractResult<CosmosMsg> {
+    let owner = CONFIG.load(store)?.owner;
+    assert_cw3_multisig(&querier, &owner)?;
+    let msgs = msgs
+        .iter()
+        .map(|msg| -> StdResult<CosmosMsg> {
+            Ok(wasm_execute(_env.contract.address.as_str(), msg, vec![])?.into())
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(wasm_execute(
+        owner,
+        &Cw3ExecuteMsg::Propose {
+            title,
+            description,
+            msgs,
+        },
+        vec![],
+    )?
+    .into())
+}
+
+pub fn query_changes_since(store: &dyn Storage, height: u64) -> ContractResult<ChangesResponse> {
+    let mut changes = CHANGE_RECORDS
+        .iter(store)?
+        .rev()
+        .take_while(|record| {
+            record
+                .as_ref()
+                .map_or(true, |record| record.height > height)
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    changes.reverse();
+    let truncated = CHANGES_PRUNED_HEIGHT
+        .may_load(store)?
+        .map_or(false, |pruned_height| pruned_height > height);
+    Ok(ChangesResponse { changes, truncated })
+}
+
+pub fn query_fee_rate_history(
+    store: &dyn Storage,
+    limit: Option<u32>,
+) -> ContractResult<Vec<FeeRateChange>> {
+    let limit = limit.unwrap_or(DEFAULT_QUERY_LIMIT).min(MAX_QUERY_LIMIT) as usize;
+    Ok(FEE_RATE_HISTORY
+        .iter(store)?
+        .rev()
+        .take(limit)
+        .collect::<StdResult<_>>()?)
+}
+
+pub fn query_admin_log(
+    store: &dyn Storage,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> ContractResult<Vec<AdminLogEntry>> {
+    let limit = limit.unwrap_or(DEFAULT_QUERY_LIMIT).min(MAX_QUERY_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+    Ok(ADMIN_LOG
+        .range(store, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, entry)| entry))
+        .collect::<StdResult<_>>()?)
+}
+
+pub fn query_balance_snapshot(store: &dyn Storage) -> ContractResult<Option<BalanceSnapshot>> {
+    Ok(BALANCE_SNAPSHOT.may_load(store)?)
+}
+
+pub fn query_snapshot_balances(
+    store: &dyn Storage,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> ContractResult<Vec<(String, Uint128)>> {
+    let limit = limit.unwrap_or(DEFAULT_QUERY_LIMIT).min(MAX_QUERY_LIMIT) as usize;
+    let start = start_after.as_deref().map(Bound::exclusive);
+    Ok(SNAPSHOT_BALANCES
+        .range(store, start, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<_>>()?)
+}
+
+pub fn query_liabilities_proof(
+    store: &dyn Storage,
+    address: String,
+) -> ContractResult<LiabilitiesProof> {
+    let snapshot = BALANCE_SNAPSHOT.may_load(store)?;
+    let Some((snapshot, root)) = snapshot.and_then(|snapshot| {
+        let root = snapshot.liabilities_root.clone()?;
+        Some((snapshot, root))
+    }) else {
+        return Err(ContractError::App(
+            "No balance snapshot with a liabilities root".to_string(),
+        ));
+    };
+    let leaf_index = SNAPSHOT_LEAF_INDEXES
+        .may_load(store, &address)?
+        .ok_or_else(|| ContractError::App("Address is not in the balance snapshot".to_string()))?;
+
+    Ok(LiabilitiesProof {
+        balance: SNAPSHOT_BALANCES.load(store, &address)?,
+        address,
+        leaf_index,
+        branch: liabilities_branch(store, snapshot.holders, leaf_index)?,
+        root,
+        completed_at: snapshot.completed_at,
+    })
+}
+
+pub fn query_snapshot_top_balances(
+    store: &dyn Storage,
+    limit: Option<u32>,
+) -> ContractResult<Vec<(String, Uint128)>> {
+    let limit = limit.unwrap_or(DEFAULT_QUERY_LIMIT).min(MAX_QUERY_LIMIT) as usize;
+    Ok(SNAPSHOT_TOP_BALANCES
+        .keys(store, None, None, Order::Descending)
+        .take(limit)
+        .map(|item| item.map(|(balance, holder)| (holder, Uint128::new(balance))))
+        .collect::<StdResult<_>>()?)
+}
+
+pub fn query_snapshot_withdrawals(
+    store: &dyn Storage,
+    start_after: Option<u32>,
+    limit: Option<u32>,
+) -> ContractResult<Vec<SnapshotWithdrawal>> {
+    let limit = limit.unwrap_or(DEFAULT_QUERY_LIMIT).min(MAX_QUERY_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+    Ok(SNAPSHOT_WITHDRAWALS
+        .range(store, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, withdrawal)| withdrawal))
+        .collect::<StdResult<_>>()?)
+}
+
+pub fn query_disbursal_outputs(
+    store: &dyn Storage,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> ContractResult<Vec<DisbursalOutput>> {
+    let completed = BALANCE_SNAPSHOT
+        .may_load(store)?
+        .map_or(false, |snapshot| snapshot.completed_at.is_some());
+    if !completed {
+        return Err(ContractError::App(
+            "No completed balance snapshot".to_string(),
+        ));
+    }
+    let units_per_sat = Bitcoin::default().config(store)?.units_per_sat as u128;
+    let limit = limit.unwrap_or(DEFAULT_QUERY_LIMIT).min(MAX_QUERY_LIMIT) as usize;
+    let start = start_after.as_deref().map(Bound::exclusive);
+    let mut outputs = vec![];
+    for item in SNAPSHOT_BALANCES.range(store, start, None, Order::Ascending) {
+        if outputs.len() >= limit {
+            break;
+        }
+        let (address, balance) = item?;
+        let value = (balance.u128() / units_per_sat) as u64;
+        let Some(script_pubkey) = RECOVERY_SCRIPTS.may_load(store, &address)? else {
+            continue;
+        };
+        if value == 0 {
+            continue;
+        }
+        outputs.push(DisbursalOutput {
+            address,
+            script_pubkey,
+            value,
+        });
+    }
+    Ok(outputs)
+}
+
+pub fn query_nbtc_account(
+    store: &dyn Storage,
+    querier: &QuerierWrapper,
+    address: String,
+) -> ContractResult<NbtcAccountResponse> {
+    let denom = get_full_btc_denom(store, CONFIG.load(store)?.token_factory_contract.as_str())?;
+    let balance = querier.query_balance(&address, denom)?.amount;
+    Ok(NbtcAccountResponse {
+        balance,
+        snapshot_balance: SNAPSHOT_BALANCES.may_load(store, &address)?,
+        recovery_script: RECOVERY_SCRIPTS.may_load(store, &address)?,
+        last_activity: ACCOUNT_ACTIVITY.may_load(store, &address)?,
+        address,
+    })
+}
+
+pub fn query_nbtc_accounts(
+    store: &dyn Storage,
+    querier: &QuerierWrapper,
+    start_after: Option<(Uint128, String)>,
+    limit: Option<u32>,
+) -> ContractResult<Vec<NbtcAccountResponse>> {
+    let limit = limit.unwrap_or(DEFAULT_QUERY_LIMIT).min(MAX_QUERY_LIMIT) as usize;
+    let end = start_after
+        .as_ref()
+        .map(|(balance, holder)| Bound::exclusive((balance.u128(), holder.as_str())));
+    SNAPSHOT_TOP_BALANCES
+        .keys(store, None, end, Order::Descending)
+        .take(limit)
+        .map(|item| query_nbtc_account(store, querier, item?.1))
+        .collect()
+}
+
 pub fn query_value_locked(store: &dyn Storage) -> ContractResult<u64> {
     let checkpoints = CheckpointQueue::default();
     let last_completed = checkpoints.last_completed(store)?;
-    Ok(last_completed.reserve_output()?.unwrap().value)
+    Ok(last_completed.reserve_output()?.unwrap().value.to_sat())
 }
@@ -0,0 +1,157 @@
+use crate::{
+    app::Bitcoin,
+    constants::{MAX_CHECKPOINT_HOOK_FAILURES, RECEIVE_HOOK_REPLY_ID},
+    helper::bridge_time,
+    interface::{IbcTransfer, MintClaim},
+    state::{
+        CHECKPOINT_HOOKS, IBC_MEMO_REFUNDS, IBC_TRANSFERS, MINT_CLAIMS, MINT_CLAIM_COUNT,
+        MINT_CLAIM_EXPIRATIONS, PENDING_MINTS,
+    },
+};
+use common_bitcoin::error::{ContractError, ContractResult};
+use cosmwasm_std::{BankMsg, Env, Response, Storage, SubMsg, SubMsgResult};
+use ibc_proto::ibc::applications::transfer::v1::MsgTransferResponse;
+use prost::Message;
+
+pub fn receive_hook_reply(result: SubMsgResult) -> ContractResult<Response> {
+    // receive hooks are best effort, the destination already got its tokens
+    Ok(Response::new()
+        .add_attribute("action", "receive_hook_failed")
+        .add_attribute("error", result.unwrap_err()))
+}
+
+pub fn notification_reply(result: SubMsgResult) -> ContractResult<Response> {
+    // notifications must never block the bridge
+    Ok(Response::new()
+        .add_attribute("action", "notification_failed")
+        .add_attribute("error", result.unwrap_err()))
+}
+
+pub fn checkpoint_hook_reply(
+    store: &mut dyn Storage,
+    hook_id: u64,
+    result: SubMsgResult,
+) -> ContractResult<Response> {
+    // the hook may have been unregistered since the event was sent
+    let hook = CHECKPOINT_HOOKS.may_load(store, hook_id)?;
+    let err = match result {
+        SubMsgResult::Ok(_) => {
+            // only consecutive failures deregister a hook
+            if let Some(mut hook) = hook.filter(|hook| hook.failures > 0) {
+                hook.failures = 0;
+                CHECKPOINT_HOOKS.save(store, hook_id, &hook)?;
+            }
+            return Ok(Response::new()
+                .add_attribute("action", "checkpoint_hook_delivered")
+                .add_attribute("hook_id", hook_id.to_string()));
+        }
+        SubMsgResult::Err(err) => err,
+    };
+    let response = Response::new()
+        .add_attribute("action", "checkpoint_hook_failed")
+        .add_attribute("hook_id", hook_id.to_string())
+        .add_attribute("error", err);
+    let Some(mut hook) = hook else {
+        return Ok(response);
+    };
+    hook.failures += 1;
+    if hook.failures >= MAX_CHECKPOINT_HOOK_FAILURES {
+        CHECKPOINT_HOOKS.remove(store, hook_id);
+        return Ok(response.add_attribute("deregistered", hook.addr));
+    }
+    CHECKPOINT_HOOKS.save(store, hook_id, &hook)?;
+    Ok(response)
+}
+
+pub fn ibc_memo_transfer_reply(
+    env: &Env,
+    store: &mut dyn Storage,
+    refund_id: u64,
+    result: SubMsgResult,
+) -> ContractResult<Response> {
+    let (refund_address, amount, channel) = IBC_MEMO_REFUNDS
+        .may_load(store, refund_id)?
+        .ok_or_else(|| ContractError::App("No pending ibc memo transfer".to_string()))?;
+    IBC_MEMO_REFUNDS.remove(store, refund_id);
+    let response = Response::new().add_attribute("action", "ibc_memo_transfer");
+    match result {
+        SubMsgResult::Ok(res) => {
+            // track the packet until `IbcLifecycleComplete` settles it
+            let sequence = res
+                .data
+                .and_then(|data| MsgTransferResponse::decode(data.as_slice()).ok())
+                .map(|res| res.sequence);
+            if let Some(sequence) = sequence {
+                IBC_TRANSFERS.save(
+                    store,
+                    (&channel, sequence),
+                    &IbcTransfer {
+                        channel: channel.clone(),
+                        sequence,
+                        refund_address,
+                        coin: amount,
+                        sent_at: bridge_time(env),
+                    },
+                )?;
+            }
+            Ok(response.add_attribute("status", "sent"))
+        }
+        // the tokens were minted to the bridge, send them to the refund address
+        SubMsgResult::Err(err) => Ok(response
+            .add_attribute("status", "refunded")
+            .add_attribute("error", err)
+            .add_message(BankMsg::Send {
+                to_address: refund_address,
+                amount: vec![amount],
+            })),
+    }
+}
+
+pub fn mint_reply(
+    env: &Env,
+    store: &mut dyn Storage,
+    result: SubMsgResult,
+) -> ContractResult<Response> {
+    let (recipient, coin, hook) = PENDING_MINTS
+        .pop_front(store)?
+        .ok_or_else(|| ContractError::App("No pending mint".to_string()))?;
+    let mut response = Response::new().add_attribute("action", "mint");
+    let err = match result {
+        SubMsgResult::Ok(_) => {
+            if let Some(hook) = hook {
+                response = response.add_submessage(SubMsg::reply_on_error(
+                    hook.into_cosmos_msg(&recipient)?,
+                    RECEIVE_HOOK_REPLY_ID,
+                ));
+            }
+            return Ok(response.add_attribute("status", "minted"));
+        }
+        SubMsgResult::Err(err) => err,
+    };
+
+    // record the mint so the recipient can pull it with `ClaimMint`
+    let config = Bitcoin::default().config(store)?;
+    let expires_at = match config.mint_claim_expiry {
+        0 => None,
+        expiry => Some(bridge_time(env) + expiry),
+    };
+    let id = MINT_CLAIM_COUNT.may_load(store)?.unwrap_or_default();
+    MINT_CLAIM_COUNT.save(store, &(id + 1))?;
+    MINT_CLAIMS.save(
+        store,
+        (&recipient, id),
+        &MintClaim {
+            id,
+            recipient: recipient.clone(),
+            coin,
+            expires_at,
+        },
+    )?;
+    if let Some(expires_at) = expires_at {
+        MINT_CLAIM_EXPIRATIONS.push_back(store, &(recipient, id, expires_at))?;
+    }
+    Ok(response
+        .add_attribute("status", "claimable")
+        .add_attribute("claim_id", id.to_string())
+        .add_attribute("error", err))
+}
@@ -1,18 +1,33 @@
+use super::execute::record_new_recovery_txs;
 use crate::{
     app::Bitcoin,
-    constants::VALIDATOR_ADDRESS_PREFIX,
-    fee::process_deduct_fee,
-    helper::{convert_addr_by_prefix, fetch_staking_validator},
-    state::{BLOCK_HASHES, CONFIG, SIGNERS, VALIDATORS},
+    constants::{
+        IBC_MEMO_REPLY_ID_OFFSET, MAX_EXPIRED_MINT_CLAIMS, MINT_REPLY_ID, VALIDATOR_ADDRESS_PREFIX,
+    },
+    fee::process_deduct_fee_for,
+    helper::{bridge_time, checkpoint_hook_msgs, convert_addr_by_prefix, fetch_staking_validator},
+    interface::{Change, Dest, PendingTransfer},
+    msg::{BtcBridgeReceiveMsg, CheckpointHookMsg, IbcLifecycleComplete},
+    snapshot::step_balance_snapshot,
+    state::{
+        get_full_btc_denom, record_change, remove_pending_deposit, ACCOUNT_ACTIVITY, BLOCK_HASHES,
+        BRIDGE_PAUSED, CHECKPOINTS, CONFIG, HEADER_STALL_PAUSED, IBC_MEMO_REFUNDS,
+        IBC_MEMO_REFUND_COUNT, IBC_REFUNDS, IBC_TRANSFERS, LAST_HEADER, MINT_CLAIMS,
+        MINT_CLAIM_EXPIRATIONS, PENDING_DEPOSIT_EXPIRATIONS, PENDING_MINTS, PENDING_TRANSFERS,
+        RECOVERY_TXS, SIGNERS, VALIDATORS, WITHDRAWALS,
+    },
 };
 use common_bitcoin::{
+    dry_run::{apply_writes, DryRunStorage},
     error::{ContractError, ContractResult},
     msg::BondStatus,
 };
 use cosmwasm_std::{
-    wasm_execute, Api, Binary, Coin, Env, Order, QuerierWrapper, Response, Storage, Uint128,
+    wasm_execute, Api, Binary, Coin, CosmosMsg, Env, Event, Order, QuerierWrapper, Response,
+    StdResult, Storage, SubMsg, Uint128,
 };
 use ibc_proto::cosmos::staking::v1beta1::QueryValidatorResponse;
+use light_client_bitcoin::msg::QueryMsg::HeaderHeight;
 use prost::Message;
 
 pub fn clock_end_block(
@@ -28,66 +43,192 @@ pub fn clock_end_block(
 
     let mut btc = Bitcoin::default();
 
-    let pending_nbtc_transfers = btc.take_pending_completed(storage)?;
+    // Completed transfers are queued, and they are minted together with the
+    // rest of the queued end block work within a per-block budget. What is
+    // left carries over to the following blocks.
+    for pending in btc.take_pending_completed(storage)? {
+        for transfer in pending {
+            PENDING_TRANSFERS.push_back(storage, &transfer)?;
+        }
+    }
+    let mut budget = match btc.config(storage)?.max_end_block_items {
+        0 => u32::MAX,
+        max => max,
+    };
 
     let config = CONFIG.load(storage)?;
     let token_factory = config.token_factory_contract;
     let osor_entry_point_contract = config.osor_entry_point_contract;
+    let btc_denom = get_full_btc_denom(storage, token_factory.as_str())?;
 
     let mut msgs = vec![];
-    for pending in pending_nbtc_transfers {
-        for (dest, coin) in pending {
-            let fee_data = process_deduct_fee(storage, querier, api, coin.clone())?;
-            let denom = coin.denom.to_owned();
-
-            dest.build_cosmos_msg(
-                env,
-                &mut msgs,
-                Coin {
-                    denom: denom.clone(),
-                    amount: fee_data.deducted_amount,
-                },
-                env.contract.address.clone(),
-                token_factory.as_str(),
-                osor_entry_point_contract.clone(),
-            );
+    let mut sub_msgs = vec![];
+    while budget > 0 {
+        let Some(PendingTransfer {
+            dest,
+            coin,
+            sender_commitment,
+        }) = PENDING_TRANSFERS.pop_front(storage)?
+        else {
+            break;
+        };
+        budget -= 1;
+        // transfers queued before a `MigrateDenom` are minted as the new denom
+        let coin = Coin {
+            denom: btc_denom.clone(),
+            amount: coin.amount,
+        };
+        let fee_data =
+            process_deduct_fee_for(storage, querier, api, &dest.to_source_addr(), coin.clone())?;
+        let denom = coin.denom.to_owned();
 
-            if !fee_data.relayer_fee.amount.is_zero() {
-                msgs.push(
-                    wasm_execute(
-                        token_factory.as_str(),
-                        &tokenfactory::msg::ExecuteMsg::MintTokens {
-                            denom: denom.clone(),
-                            amount: fee_data.relayer_fee.amount,
-                            mint_to_address: config.relayer_fee_receiver.to_string(),
-                        },
-                        vec![],
-                    )?
-                    .into(),
-                );
-            }
+        let amount = Coin {
+            denom: denom.clone(),
+            amount: fee_data.deducted_amount,
+        };
+        dest.build_cosmos_msg(
+            env,
+            &mut msgs,
+            amount.clone(),
+            env.contract.address.clone(),
+            token_factory.as_str(),
+            osor_entry_point_contract.clone(),
+        );
+        // the ics20 transfer is the last message, refund it if it fails
+        let ibc_transfer = if dest.is_ibc_memo_transfer(env) {
+            msgs.pop()
+        } else {
+            None
+        };
+        // a failing mint to an address is left for its recipient to claim
+        let address_mint = match &dest {
+            Dest::Address(addr) => msgs.pop().map(|msg| (addr.clone(), msg)),
+            _ => None,
+        };
+        sub_msgs.extend(msgs.drain(..).map(SubMsg::new));
+        if let (Some(ibc_transfer), Dest::IbcMemo(ibc_dest)) = (ibc_transfer, &dest) {
+            let refund_id = IBC_MEMO_REFUND_COUNT.may_load(storage)?.unwrap_or_default();
+            IBC_MEMO_REFUND_COUNT.save(storage, &(refund_id + 1))?;
+            IBC_MEMO_REFUNDS.save(
+                storage,
+                refund_id,
+                &(
+                    dest.to_source_addr(),
+                    amount.clone(),
+                    ibc_dest.channel.clone(),
+                ),
+            )?;
+            sub_msgs.push(SubMsg::reply_always(
+                ibc_transfer,
+                IBC_MEMO_REPLY_ID_OFFSET + refund_id,
+            ));
+        }
 
-            if !fee_data.token_fee.amount.is_zero() {
-                msgs.push(
-                    wasm_execute(
-                        token_factory.as_str(),
-                        &tokenfactory::msg::ExecuteMsg::MintTokens {
-                            denom: denom.clone(),
-                            amount: fee_data.token_fee.amount,
-                            mint_to_address: config.token_fee_receiver.to_string(),
-                        },
-                        vec![],
-                    )?
-                    .into(),
-                );
+        // notify contract destinations once their tokens are minted, a
+        // failing hook must not block the minting
+        let hook = match &dest {
+            Dest::Address(addr) if querier.query_wasm_contract_info(addr).is_ok() => {
+                Some(BtcBridgeReceiveMsg {
+                    sender_commitment,
+                    amount: fee_data.deducted_amount,
+                })
             }
+            _ => None,
+        };
+        if let Some((recipient, mint)) = address_mint {
+            ACCOUNT_ACTIVITY.save(storage, recipient.as_str(), &bridge_time(env))?;
+            // the hook is sent by `mint_reply`, only if the mint succeeded
+            PENDING_MINTS.push_back(storage, &(recipient, amount, hook))?;
+            sub_msgs.push(SubMsg::reply_always(mint, MINT_REPLY_ID));
+        }
+
+        if !fee_data.relayer_fee.amount.is_zero() {
+            msgs.push(
+                wasm_execute(
+                    token_factory.as_str(),
+                    &tokenfactory::msg::ExecuteMsg::MintTokens {
+                        denom: denom.clone(),
+                        amount: fee_data.relayer_fee.amount,
+                        mint_to_address: config.relayer_fee_receiver.to_string(),
+                    },
+                    vec![],
+                )?
+                .into(),
+            );
+        }
+
+        if !fee_data.token_fee.amount.is_zero() {
+            msgs.push(
+                wasm_execute(
+                    token_factory.as_str(),
+                    &tokenfactory::msg::ExecuteMsg::MintTokens {
+                        denom: denom.clone(),
+                        amount: fee_data.token_fee.amount,
+                        mint_to_address: config.token_fee_receiver.to_string(),
+                    },
+                    vec![],
+                )?
+                .into(),
+            );
+        }
+        sub_msgs.extend(msgs.drain(..).map(SubMsg::new));
+    }
+    sub_msgs.extend(
+        take_expired_mint_claims(env, storage, token_factory.as_str(), &mut budget)?
+            .into_iter()
+            .map(SubMsg::new),
+    );
+    #[cfg(not(feature = "deterministic"))]
+    let timestamping_commitment = hash.to_vec();
+    #[cfg(feature = "deterministic")]
+    let timestamping_commitment =
+        crate::helper::deterministic_commitment(btc.checkpoints.index(storage));
+    let had_checkpoints = !CHECKPOINTS.is_empty(storage)?;
+    let prev_index = btc.checkpoints.index(storage);
+    let recovery_txs = RECOVERY_TXS.len(storage)?;
+    let offline_signers =
+        btc.begin_block_step(env, querier, storage, timestamping_commitment, &mut budget)?;
+    record_new_recovery_txs(storage, env, recovery_txs)?;
+    let index = btc.checkpoints.index(storage);
+    let mut events = vec![];
+    if had_checkpoints && index > prev_index {
+        record_change(
+            storage,
+            env.block.height,
+            Change::CheckpointSigning { index: prev_index },
+        )?;
+        let hook_msg = CheckpointHookMsg::CheckpointAdvanced { index: prev_index };
+        sub_msgs.extend(checkpoint_hook_msgs(storage, hook_msg)?);
+        let prev = btc.checkpoints.get(storage, prev_index)?;
+        if !prev.withdrawal_fees.is_empty() {
+            events.push(
+                Event::new("withdrawal_fees_reconciled")
+                    .add_attribute("checkpoint_index", prev_index.to_string())
+                    .add_attribute("withdrawals", prev.withdrawal_fees.len().to_string())
+                    .add_attribute("surplus", prev.withdrawal_fee_surplus.to_string()),
+            );
         }
+        let sigset = btc.checkpoints.get(storage, index)?.sigset;
+        if sigset.signatories != prev.sigset.signatories {
+            let hook_msg = CheckpointHookMsg::SigsetRotated {
+                index: sigset.index,
+            };
+            sub_msgs.extend(checkpoint_hook_msgs(storage, hook_msg)?);
+        }
+    }
+    if (!had_checkpoints && !CHECKPOINTS.is_empty(storage)?) || index > prev_index {
+        record_change(
+            storage,
+            env.block.height,
+            Change::CheckpointCreated { index },
+        )?;
     }
-    let offline_signers = btc.begin_block_step(env, querier, storage, hash.to_vec())?;
     for cons_key in &offline_signers {
         let (_, address) = VALIDATORS.load(storage, cons_key)?;
-        btc.punish_validator(storage, cons_key, address)?;
+        btc.punish_validator(storage, cons_key, address, bridge_time(env))?;
     }
+    prune_withdrawals(storage, &mut budget)?;
+    prune_pending_deposits(storage, bridge_time(env), &mut budget)?;
     BLOCK_HASHES.save(storage, &hash, &()).unwrap();
 
     let mut signer_addrs = Vec::new();
@@ -134,5 +275,224 @@ pub fn clock_end_block(
         }
     }
 
-    Ok(Response::new().add_messages(msgs))
+    let mut response = Response::new().add_submessages(sub_msgs).add_events(events);
+    if let Some(event) = check_header_staleness(env, storage, querier)? {
+        response = response.add_event(event);
+    }
+    // a failing snapshot step, e.g. on a bank query error, is skipped rather
+    // than halting the end block, and none of its writes are kept
+    let mut dry_run = DryRunStorage::new(storage);
+    match step_balance_snapshot(env, &mut dry_run, querier) {
+        Ok(event) => {
+            let writes = dry_run.into_writes();
+            apply_writes(storage, writes);
+            response = response.add_events(event);
+        }
+        Err(err) => {
+            response = response.add_event(
+                Event::new("balance_snapshot_error").add_attribute("error", err.to_string()),
+            );
+        }
+    }
+    let check_interval = btc.config(storage)?.invariant_check_interval;
+    if check_interval > 0 && env.block.height % check_interval == 0 {
+        response = response.add_events(check_invariants(storage, querier)?);
+    }
+
+    Ok(response)
+}
+
+/// Settles an ibc memo transfer once its packet is acknowledged or times out.
+/// The transfer module returns the nBTC of a failed packet to the bridge, where
+/// it is credited to the transfer's refund address.
+pub fn ibc_lifecycle_complete(
+    storage: &mut dyn Storage,
+    msg: IbcLifecycleComplete,
+) -> ContractResult<Response> {
+    let (channel, sequence, success) = match msg {
+        IbcLifecycleComplete::IbcAck {
+            channel,
+            sequence,
+            success,
+            ..
+        } => (channel, sequence, success),
+        IbcLifecycleComplete::IbcTimeout { channel, sequence } => (channel, sequence, false),
+    };
+    let response = Response::new()
+        .add_attribute("action", "ibc_lifecycle_complete")
+        .add_attribute("channel", &channel)
+        .add_attribute("sequence", sequence.to_string());
+    let Some(transfer) = IBC_TRANSFERS.may_load(storage, (&channel, sequence))? else {
+        return Ok(response.add_attribute("status", "unknown"));
+    };
+    IBC_TRANSFERS.remove(storage, (&channel, sequence));
+    if success {
+        return Ok(response.add_attribute("status", "acknowledged"));
+    }
+
+    IBC_REFUNDS.update(
+        storage,
+        (&transfer.refund_address, &transfer.coin.denom),
+        |amount| -> StdResult<_> { Ok(amount.unwrap_or_default() + transfer.coin.amount) },
+    )?;
+    Ok(response
+        .add_attribute("status", "refundable")
+        .add_attribute("refund_address", transfer.refund_address))
+}
+
+/// Removes the mint claims which expired unclaimed, returning the messages
+/// minting them to the recovery address.
+fn take_expired_mint_claims(
+    env: &Env,
+    storage: &mut dyn Storage,
+    token_factory: &str,
+    budget: &mut u32,
+) -> ContractResult<Vec<CosmosMsg>> {
+    let btc = Bitcoin::default();
+    let Some(recovery_address) = btc.config(storage)?.mint_claim_recovery_address else {
+        return Ok(vec![]);
+    };
+    let now = bridge_time(env);
+    let denom = get_full_btc_denom(storage, token_factory)?;
+    let mut msgs = vec![];
+    for _ in 0..MAX_EXPIRED_MINT_CLAIMS.min(*budget) {
+        match MINT_CLAIM_EXPIRATIONS.front(storage)? {
+            Some((_, _, expires_at)) if expires_at <= now => {}
+            _ => break,
+        }
+        let Some((recipient, id, _)) = MINT_CLAIM_EXPIRATIONS.pop_front(storage)? else {
+            break;
+        };
+        *budget -= 1;
+        // skip claims which were already pulled
+        let Some(claim) = MINT_CLAIMS.may_load(storage, (&recipient, id))? else {
+            continue;
+        };
+        MINT_CLAIMS.remove(storage, (&recipient, id));
+        msgs.push(
+            wasm_execute(
+                token_factory,
+                &tokenfactory::msg::ExecuteMsg::MintTokens {
+                    denom: denom.clone(),
+                    amount: claim.coin.amount,
+                    mint_to_address: recovery_address.to_string(),
+                },
+                vec![],
+            )?
+            .into(),
+        );
+    }
+    Ok(msgs)
+}
+
+/// Removes the records of withdrawals whose checkpoint is no longer
+/// `Building`, which can't be sponsored anymore. Records are pruned in id
+/// order, at most `budget` of them.
+pub fn prune_withdrawals(storage: &mut dyn Storage, budget: &mut u32) -> ContractResult<()> {
+    let building_index = Bitcoin::default().checkpoints.index(storage);
+    while *budget > 0 {
+        let Some(oldest) = WITHDRAWALS
+            .range(storage, None, None, Order::Ascending)
+            .next()
+        else {
+            break;
+        };
+        let (id, withdrawal) = oldest?;
+        if withdrawal.checkpoint_index >= building_index {
+            break;
+        }
+        WITHDRAWALS.remove(storage, id);
+        *budget -= 1;
+    }
+    Ok(())
+}
+
+/// Removes the pending deposits reported by relayers which expired before
+/// being relayed, oldest first, at most `budget` of them.
+pub fn prune_pending_deposits(
+    storage: &mut dyn Storage,
+    now: u64,
+    budget: &mut u32,
+) -> ContractResult<()> {
+    while *budget > 0 {
+        let Some(oldest) = PENDING_DEPOSIT_EXPIRATIONS
+            .keys(storage, None, None, Order::Ascending)
+            .next()
+        else {
+            break;
+        };
+        let (expires_at, txid, vout) = oldest?;
+        if expires_at > now {
+            break;
+        }
+        remove_pending_deposit(storage, &txid, vout)?;
+        *budget -= 1;
+    }
+    Ok(())
+}
+
+/// Pauses the bridge when the light client has not received a new header for
+/// longer than `max_header_staleness`, and resumes it once headers flow again,
+/// returning an event when the state changes.
+pub fn check_header_staleness(
+    env: &Env,
+    storage: &mut dyn Storage,
+    querier: &QuerierWrapper,
+) -> ContractResult<Option<Event>> {
+    let max_staleness = Bitcoin::default().config(storage)?.max_header_staleness;
+    let stalled = HEADER_STALL_PAUSED.may_load(storage)?.unwrap_or_default();
+    if max_staleness == 0 && !stalled {
+        return Ok(None);
+    }
+
+    let light_client = CONFIG.load(storage)?.light_client_contract;
+    let height: u32 = querier.query_wasm_smart(light_client, &HeaderHeight {})?;
+    let now = bridge_time(env);
+    let seen_at = match LAST_HEADER.may_load(storage)? {
+        Some((last_height, seen_at)) if last_height == height => seen_at,
+        _ => {
+            LAST_HEADER.save(storage, &(height, now))?;
+            now
+        }
+    };
+
+    let is_stale = max_staleness > 0 && now.saturating_sub(seen_at) > max_staleness;
+    if is_stale == stalled {
+        return Ok(None);
+    }
+    HEADER_STALL_PAUSED.save(storage, &is_stale)?;
+    let status = if is_stale { "paused" } else { "resumed" };
+    Ok(Some(
+        Event::new("header_stall")
+            .add_attribute("status", status)
+            .add_attribute("header_height", height.to_string()),
+    ))
+}
+
+pub fn verify_invariants(
+    storage: &mut dyn Storage,
+    querier: &QuerierWrapper,
+) -> ContractResult<Response> {
+    let events = check_invariants(storage, querier)?;
+    Ok(Response::new()
+        .add_attribute("action", "verify_invariants")
+        .add_attribute("violations", events.len().to_string())
+        .add_events(events))
+}
+
+/// Pauses the bridge if any accounting invariant is violated, returning one
+/// event per violation.
+fn check_invariants(
+    storage: &mut dyn Storage,
+    querier: &QuerierWrapper,
+) -> ContractResult<Vec<Event>> {
+    let btc = Bitcoin::default();
+    let violations = btc.check_invariants(storage, querier)?;
+    if !violations.is_empty() {
+        BRIDGE_PAUSED.save(storage, &true)?;
+    }
+    Ok(violations
+        .into_iter()
+        .map(|violation| Event::new("invariant_violation").add_attribute("violation", violation))
+        .collect())
 }
@@ -0,0 +1,347 @@
+use common_bitcoin::error::{ContractError, ContractResult};
+use cosmwasm_std::{Binary, Order, Storage};
+
+use crate::{
+    msg::{StateEntry, StateSection},
+    state::STATE_IMPORT,
+};
+
+/// How a store lays out its keys under its namespace.
+enum StoreKind {
+    /// A single value at the namespace itself.
+    Item,
+    /// Values under the length-prefixed namespace, as used by maps and deques.
+    Prefixed,
+}
+
+/// The stores making up each exported section, by namespace. Must be kept in
+/// sync with the namespaces in `state.rs`.
+fn section_stores(section: StateSection) -> &'static [(&'static str, StoreKind)] {
+    use StoreKind::*;
+    match section {
+        StateSection::Config => &[
+            ("config", Item),
+            ("checkpoint_config", Item),
+            ("bitcoin_config", Item),
+            ("token_fee_ratio", Item),
+            ("fee_pool", Item),
+            ("reward_pool", Item),
+            ("reward_balances", Prefixed),
+            ("foundation_keys", Item),
+            ("foundation_policy", Item),
+            ("state_version", Item),
+            ("bridge_paused", Item),
+            ("btc_subdenom", Item),
+            ("legacy_btc_denoms", Item),
+            ("last_header", Item),
+            ("header_stall_paused", Item),
+            ("small_utxos_allowed_until", Item),
+            ("admin_log", Prefixed),
+            ("admin_log_count", Item),
+            ("light_client_switched_at", Item),
+            ("bridge_migration", Item),
+            ("migration_predecessor", Item),
+            ("checkpoint_hooks", Prefixed),
+            ("checkpoint_hook_count", Item),
+        ],
+        StateSection::Checkpoints => &[
+            ("checkpoints", Prefixed),
+            ("building_index", Item),
+            ("confirmed_index", Item),
+            ("first_unhandled_confirmed_index", Item),
+            ("compacted_index", Item),
+            ("reclaimed_signature_bytes", Item),
+            ("checkpoint_confirmation_heights", Prefixed),
+            ("checkpoint_confirmation_times", Prefixed),
+            ("checkpoint_txids", Prefixed),
+            ("checkpoint_index_txids", Prefixed),
+            ("checkpoint_attestations", Prefixed),
+            ("checkpoint_memos", Prefixed),
+            ("app_data", Prefixed),
+            ("app_data_internal_commitments", Prefixed),
+            ("sigset_archive", Prefixed),
+            ("reserve_utxos", Prefixed),
+            ("change_records", Prefixed),
+            ("changes_pruned_height", Item),
+            ("fee_rate_history", Prefixed),
+        ],
+        StateSection::Outpoints => &[("outpoints", Prefixed), ("expiration_queue", Prefixed)],
+        StateSection::Accounts => &[
+            ("mint_records", Prefixed),
+            ("expired_deposits", Prefixed),
+            ("expired_deposits_by_dest", Prefixed),
+            ("pending_deposits", Prefixed),
+            ("pending_deposits_by_dest", Prefixed),
+            ("pending_deposit_expirations", Prefixed),
+            ("dest_stats", Prefixed),
+            ("dest_stats_by_total", Prefixed),
+            ("dest_sigset_indexes", Prefixed),
+            ("reorged_deposits", Prefixed),
+            ("bad_debt", Item),
+            ("burns", Prefixed),
+            ("burn_count", Item),
+            ("total_burned", Item),
+            ("fee_exemptions", Prefixed),
+            ("relayers", Prefixed),
+            ("compliance_taggers", Prefixed),
+            ("screened_scripts", Prefixed),
+            ("held_withdrawals", Prefixed),
+            ("held_withdrawal_count", Item),
+            ("swap_providers", Prefixed),
+            ("lightning_swaps", Prefixed),
+            ("lightning_swap_count", Item),
+            ("withdrawals", Prefixed),
+            ("withdrawal_count", Item),
+            ("withdrawal_quotes", Prefixed),
+            ("withdrawal_quote_count", Item),
+            ("withdrawal_allowances", Prefixed),
+            ("mint_claims", Prefixed),
+            ("mint_claim_count", Item),
+            ("mint_claim_expirations", Prefixed),
+            ("pending_mints", Prefixed),
+            ("pending_transfers", Prefixed),
+            ("ibc_memo_refunds", Prefixed),
+            ("ibc_memo_refund_count", Item),
+            ("ibc_transfers", Prefixed),
+            ("ibc_refunds", Prefixed),
+            ("balance_snapshot", Item),
+            ("snapshot_balances", Prefixed),
+            ("snapshot_top_balances", Prefixed),
+            ("snapshot_withdrawals", Prefixed),
+            ("snapshot_tree", Prefixed),
+            ("snapshot_leaf_indexes", Prefixed),
+            ("recovery_scripts", Prefixed),
+            ("account_activity", Prefixed),
+        ],
+        StateSection::RecoveryTxs => &[
+            ("recovery_txs", Prefixed),
+            ("dust_deposits", Prefixed),
+            ("signed_reserve_outputs", Prefixed),
+        ],
+        StateSection::SignatoryKeys => &[
+            ("sig_keys", Prefixed),
+            ("signers", Prefixed),
+            ("scheduled_validator_set", Item),
+            ("validators", Prefixed),
+            ("xpubs", Prefixed),
+            ("whitelist_native_validators", Prefixed),
+            ("jailed_signers", Prefixed),
+            ("tombstoned_xpubs", Prefixed),
+            ("tombstone_votes", Prefixed),
+            ("force_complete_votes", Prefixed),
+            ("forced_checkpoint_push", Item),
+            ("fast_withdrawal_checkpoint", Item),
+            ("sigset_btc_heights", Prefixed),
+        ],
+    }
+}
+
+/// Account stores which are not sent to a successor bridge: counters, which
+/// the successor derives from the migrated records instead, history whose ids
+/// would collide with the successor's own, and the privileges granted by this
+/// bridge's owner.
+const NOT_MIGRATED: &[&str] = &[
+    "burns",
+    "burn_count",
+    "total_burned",
+    "fee_exemptions",
+    "relayers",
+    "compliance_taggers",
+    "held_withdrawal_count",
+    "lightning_swap_count",
+    "withdrawals",
+    "withdrawal_count",
+    "withdrawal_quote_count",
+    "mint_claim_count",
+    "ibc_memo_refunds",
+    "ibc_memo_refund_count",
+];
+
+/// The range of raw keys `[start, end)` a store occupies.
+fn store_range(namespace: &str, kind: &StoreKind) -> (Vec<u8>, Vec<u8>) {
+    let mut start = match kind {
+        StoreKind::Item => vec![],
+        StoreKind::Prefixed => (namespace.len() as u16).to_be_bytes().to_vec(),
+    };
+    start.extend_from_slice(namespace.as_bytes());
+    let mut end = start.clone();
+    if let StoreKind::Item = kind {
+        // only the key itself
+        end.push(0);
+    } else {
+        // namespaces are ascii, so the last byte can not overflow
+        *end.last_mut().unwrap() += 1;
+    }
+    (start, end)
+}
+
+/// The key ranges of the stores of the given sections, sorted by key.
+fn section_ranges(sections: &[StateSection]) -> Vec<(Vec<u8>, Vec<u8>)> {
+    let mut ranges: Vec<_> = sections
+        .iter()
+        .flat_map(|section| section_stores(*section).iter())
+        .map(|(namespace, kind)| store_range(namespace, kind))
+        .collect();
+    ranges.sort();
+    ranges
+}
+
+/// The key ranges of the account stores sent to a successor bridge, sorted
+/// by key.
+fn migrated_ranges() -> Vec<(Vec<u8>, Vec<u8>)> {
+    let mut ranges: Vec<_> = section_stores(StateSection::Accounts)
+        .iter()
+        .filter(|(namespace, _)| !NOT_MIGRATED.contains(namespace))
+        .map(|(namespace, kind)| store_range(namespace, kind))
+        .collect();
+    ranges.sort();
+    ranges
+}
+
+/// Lists the raw storage entries of a section, in key order, starting after
+/// the raw key `start_after`.
+pub fn export_section(
+    store: &dyn Storage,
+    section: StateSection,
+    start_after: Option<Binary>,
+    limit: usize,
+) -> ContractResult<Vec<StateEntry>> {
+    export_ranges(store, section_ranges(&[section]), start_after, limit)
+}
+
+/// Lists the raw account entries sent to a successor bridge, in key order,
+/// starting after the raw key `start_after`.
+pub fn export_migrated(
+    store: &dyn Storage,
+    start_after: Option<Binary>,
+    limit: usize,
+) -> ContractResult<Vec<StateEntry>> {
+    export_ranges(store, migrated_ranges(), start_after, limit)
+}
+
+/// Whether none of the account stores sent by a predecessor bridge hold any
+/// entry yet.
+pub fn migrated_stores_empty(store: &dyn Storage) -> bool {
+    migrated_ranges().iter().all(|(start, end)| {
+        store
+            .range(Some(start), Some(end), Order::Ascending)
+            .next()
+            .is_none()
+    })
+}
+
+fn export_ranges(
+    store: &dyn Storage,
+    ranges: Vec<(Vec<u8>, Vec<u8>)>,
+    start_after: Option<Binary>,
+    limit: usize,
+) -> ContractResult<Vec<StateEntry>> {
+    let mut entries = vec![];
+    for (mut start, end) in ranges {
+        if let Some(start_after) = &start_after {
+            if start_after.as_slice() >= end.as_slice() {
+                continue;
+            }
+            if start_after.as_slice() >= start.as_slice() {
+                start = start_after.to_vec();
+                start.push(0);
+            }
+        }
+        for (key, value) in store
+            .range(Some(&start), Some(&end), Order::Ascending)
+            .take(limit - entries.len())
+        {
+            entries.push(StateEntry {
+                key: key.into(),
+                value: value.into(),
+            });
+        }
+        if entries.len() >= limit {
+            break;
+        }
+    }
+    Ok(entries)
+}
+
+/// Writes raw storage entries exported from another bridge contract.
+///
+/// Importing is only possible on a contract which has not built any
+/// checkpoint yet, and only until an import is finished.
+pub fn import_entries(
+    store: &mut dyn Storage,
+    entries: Vec<StateEntry>,
+    finish: bool,
+) -> ContractResult<()> {
+    match STATE_IMPORT.may_load(store)? {
+        Some(false) => {
+            return Err(ContractError::App(
+                "State import is already finished".to_string(),
+            ))
+        }
+        Some(true) => {}
+        None => {
+            let (start, end) = store_range("checkpoints", &StoreKind::Prefixed);
+            if store
+                .range(Some(&start), Some(&end), Order::Ascending)
+                .next()
+                .is_some()
+            {
+                return Err(ContractError::App(
+                    "State can only be imported into a fresh contract".to_string(),
+                ));
+            }
+        }
+    }
+
+    let ranges = section_ranges(&[
+        StateSection::Config,
+        StateSection::Checkpoints,
+        StateSection::Outpoints,
+        StateSection::Accounts,
+        StateSection::RecoveryTxs,
+        StateSection::SignatoryKeys,
+    ]);
+    write_entries(store, &ranges, entries, true)?;
+
+    STATE_IMPORT.save(store, &!finish)?;
+    Ok(())
+}
+
+/// Writes the raw account entries sent by a predecessor bridge migrating to
+/// this contract. Unlike `import_entries`, this works on a running bridge,
+/// since the successor must already have a signatory set to receive the
+/// reserve, but never overwrites an existing entry.
+pub fn import_migrated_entries(
+    store: &mut dyn Storage,
+    entries: Vec<StateEntry>,
+) -> ContractResult<()> {
+    write_entries(store, &migrated_ranges(), entries, false)
+}
+
+fn write_entries(
+    store: &mut dyn Storage,
+    ranges: &[(Vec<u8>, Vec<u8>)],
+    entries: Vec<StateEntry>,
+    overwrite: bool,
+) -> ContractResult<()> {
+    for entry in entries {
+        let key = entry.key.as_slice();
+        if !ranges
+            .iter()
+            .any(|(start, end)| key >= start.as_slice() && key < end.as_slice())
+        {
+            return Err(ContractError::App(format!(
+                "Key {} is not part of an exported section",
+                hex::encode(key)
+            )));
+        }
+        if !overwrite && store.get(key).is_some() {
+            return Err(ContractError::App(format!(
+                "Key {} is already set",
+                hex::encode(key)
+            )));
+        }
+        store.set(key, entry.value.as_slice());
+    }
+    Ok(())
+}
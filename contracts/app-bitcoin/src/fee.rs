@@ -9,8 +9,9 @@ use std::ops::Mul;
 use crate::{
     helper::denom_to_asset_info,
     msg::FeeData,
-    state::{Ratio, CONFIG, TOKEN_FEE_RATIO},
+    state::{Ratio, CONFIG, FEE_EXEMPTIONS, TOKEN_FEE_RATIO},
 };
+use common_bitcoin::error::{ContractError, ContractResult};
 
 pub fn process_deduct_fee(
     store: &dyn Storage,
@@ -58,6 +59,71 @@ pub fn process_deduct_fee(
     Ok(fee_data)
 }
 
+/// Like `process_deduct_fee`, but exempt addresses only pay miner fees, so the
+/// full amount is passed through.
+pub fn process_deduct_fee_for(
+    store: &dyn Storage,
+    querier: &QuerierWrapper,
+    api: &dyn Api,
+    addr: &str,
+    local_amount: Coin,
+) -> StdResult<FeeData> {
+    if !FEE_EXEMPTIONS.has(store, addr) {
+        return process_deduct_fee(store, querier, api, local_amount);
+    }
+
+    let zero_fee = Coin {
+        denom: local_amount.denom.clone(),
+        amount: Uint128::zero(),
+    };
+    Ok(FeeData {
+        deducted_amount: local_amount.amount,
+        token_fee: zero_fee.clone(),
+        relayer_fee: zero_fee,
+    })
+}
+
+/// Checks that the funds sent with `RelayDeposit` pay one of the accepted
+/// `relayer_fee_options` of the config, returning the coin to forward to the
+/// relayer fee receiver. No funds are accepted if no options are configured.
+pub fn check_relayer_fee_payment(
+    store: &dyn Storage,
+    funds: &[Coin],
+) -> ContractResult<Option<Coin>> {
+    let options = CONFIG.load(store)?.relayer_fee_options;
+    if options.is_empty() {
+        if !funds.is_empty() {
+            return Err(ContractError::App(
+                "Relaying deposits does not take a fee".to_string(),
+            ));
+        }
+        return Ok(None);
+    }
+
+    let paid = match funds {
+        [paid] => paid,
+        _ => {
+            return Err(ContractError::App(
+                "Relaying deposits requires paying exactly one relayer fee option".to_string(),
+            ))
+        }
+    };
+    let option = options
+        .iter()
+        .find(|option| option.denom == paid.denom)
+        .ok_or_else(|| {
+            ContractError::App(format!("{} is not an accepted relayer fee", paid.denom))
+        })?;
+    if paid.amount < option.amount {
+        return Err(ContractError::App(format!(
+            "Relayer fee must be at least {}",
+            option
+        )));
+    }
+
+    Ok(Some(paid.clone()))
+}
+
 pub fn deduct_relayer_fee(
     store: &dyn Storage,
     querier: &QuerierWrapper,
@@ -1,11 +1,92 @@
 use bech32::Bech32;
-use common_bitcoin::error::ContractResult;
-use cosmwasm_std::{to_json_vec, Api, Binary, Empty, QuerierWrapper, QueryRequest};
+use common_bitcoin::error::{ContractError, ContractResult};
+use cosmwasm_std::{
+    to_json_vec, wasm_execute, Addr, Api, Binary, Empty, Env, Order, QuerierWrapper, QueryRequest,
+    Storage, SubMsg, SystemResult,
+};
+use ibc_proto::cosmos::bank::v1beta1::{QueryDenomOwnersRequest, QueryDenomOwnersResponse};
+use ibc_proto::cosmos::base::query::v1beta1::PageRequest;
 use ibc_proto::cosmos::staking::v1beta1::QueryValidatorRequest;
 use oraiswap::asset::AssetInfo;
 use prost::Message;
 
-use crate::constants::VALIDATOR_ADDRESS_PREFIX;
+use crate::constants::{
+    CHECKPOINT_HOOK_GAS_LIMIT, CHECKPOINT_HOOK_REPLY_ID_OFFSET, NOTIFICATION_REPLY_ID,
+    VALIDATOR_ADDRESS_PREFIX,
+};
+use crate::msg::{
+    BridgeNotification, CheckpointHookExecuteMsg, CheckpointHookMsg, Cw3QueryMsg,
+    Cw3VoterListResponse, NotificationExecuteMsg,
+};
+use crate::state::{CHECKPOINT_HOOKS, CONFIG};
+
+/// The current time of the bridge clock, in seconds, used for checkpoint and
+/// signatory set timing. With the `deterministic` feature it only depends on
+/// the block height, so replaying the same blocks yields identical state.
+pub fn bridge_time(env: &Env) -> u64 {
+    #[cfg(feature = "deterministic")]
+    return crate::constants::DETERMINISTIC_GENESIS_TIME
+        + env.block.height * crate::constants::DETERMINISTIC_BLOCK_TIME;
+
+    #[cfg(not(feature = "deterministic"))]
+    return env.block.time.seconds();
+}
+
+/// A submessage sending the notification to the notification contract, if one
+/// is configured. Failures are ignored in the reply.
+pub fn notification_msg(
+    store: &dyn Storage,
+    notification: BridgeNotification,
+) -> ContractResult<Option<SubMsg>> {
+    let Some(contract) = CONFIG.load(store)?.notification_contract else {
+        return Ok(None);
+    };
+    let msg = wasm_execute(
+        contract,
+        &NotificationExecuteMsg::BtcBridgeNotification(notification),
+        vec![],
+    )?;
+    Ok(Some(SubMsg::reply_on_error(msg, NOTIFICATION_REPLY_ID)))
+}
+
+/// Submessages sending a checkpoint event to the hooks registered for it. The
+/// reply id of each carries its hook id, so that failures are counted against
+/// the hook, and a gas limit keeps a hook from exhausting the block's gas.
+pub fn checkpoint_hook_msgs(
+    store: &dyn Storage,
+    msg: CheckpointHookMsg,
+) -> ContractResult<Vec<SubMsg>> {
+    let event = msg.event();
+    let mut sub_msgs = vec![];
+    for hook in CHECKPOINT_HOOKS.range(store, None, None, Order::Ascending) {
+        let (id, hook) = hook?;
+        if !hook.events.contains(&event) {
+            continue;
+        }
+        let exec = wasm_execute(
+            hook.addr,
+            &CheckpointHookExecuteMsg::BtcBridgeCheckpointHook(msg.clone()),
+            vec![],
+        )?;
+        sub_msgs.push(
+            SubMsg::reply_always(exec, CHECKPOINT_HOOK_REPLY_ID_OFFSET + id)
+                .with_gas_limit(CHECKPOINT_HOOK_GAS_LIMIT),
+        );
+    }
+    Ok(sub_msgs)
+}
+
+/// The timestamping commitment of the checkpoint with the given index in
+/// deterministic mode, replacing the block hash.
+#[cfg(feature = "deterministic")]
+pub fn deterministic_commitment(index: u32) -> Vec<u8> {
+    use sha2::{Digest, Sha256};
+
+    let mut hasher = Sha256::new();
+    hasher.update(b"checkpoint");
+    hasher.update(index.to_be_bytes());
+    hasher.finalize().to_vec()
+}
 
 pub fn denom_to_asset_info(api: &dyn Api, denom: &str) -> AssetInfo {
     if let Ok(contract_addr) = api.addr_validate(denom) {
@@ -30,6 +111,67 @@ pub fn fetch_staking_validator(querier: &QuerierWrapper, addr: String) -> Contra
     Ok(buf)
 }
 
+/// Fetches a page of the holders of `denom` from the bank module.
+pub fn fetch_denom_owners(
+    querier: &QuerierWrapper,
+    denom: String,
+    key: Vec<u8>,
+    limit: u64,
+) -> ContractResult<QueryDenomOwnersResponse> {
+    let bin_request = to_json_vec(&QueryRequest::<Empty>::Stargate {
+        path: "/cosmos.bank.v1beta1.Query/DenomOwners".to_string(),
+        data: QueryDenomOwnersRequest {
+            denom,
+            pagination: Some(PageRequest {
+                key,
+                limit,
+                ..Default::default()
+            }),
+        }
+        .encode_to_vec()
+        .into(),
+    })?;
+    let buf = match querier.raw_query(&bin_request) {
+        SystemResult::Ok(cosmwasm_std::ContractResult::Ok(buf)) => buf,
+        _ => {
+            return Err(ContractError::App(
+                "Failed to query denom owners".to_string(),
+            ))
+        }
+    };
+    QueryDenomOwnersResponse::decode(buf.as_slice())
+        .map_err(|err| ContractError::App(format!("Invalid denom owners response: {}", err)))
+}
+
+/// Checks that the given contract is a cw3 multisig with at least one voter.
+pub fn assert_cw3_multisig(querier: &QuerierWrapper, multisig: &Addr) -> ContractResult<()> {
+    let voters: Cw3VoterListResponse = querier
+        .query_wasm_smart(
+            multisig,
+            &Cw3QueryMsg::ListVoters {
+                start_after: None,
+                limit: Some(1),
+            },
+        )
+        .map_err(|_| ContractError::App(format!("{} is not a cw3 multisig", multisig)))?;
+    if voters.voters.is_empty() {
+        return Err(ContractError::App(format!(
+            "cw3 multisig {} has no voters",
+            multisig
+        )));
+    }
+    Ok(())
+}
+
+/// Whether two bech32 addresses refer to the same account, regardless of
+/// their prefixes.
+pub fn is_same_account(a: &str, b: &str) -> bool {
+    match (bech32::decode(a), bech32::decode(b)) {
+        (Ok((_, a)), Ok((_, b))) => a == b,
+        _ => a == b,
+    }
+}
+
 pub fn convert_addr_by_prefix(address: &str, prefix: &str) -> String {
     let (_hrp, bech32_data) = bech32::decode(address).unwrap();
     let val_addr =
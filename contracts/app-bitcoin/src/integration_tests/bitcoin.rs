@@ -1,23 +1,30 @@
 use super::utils::{
-    get_wrapped_header_from_block_hash, populate_bitcoin_block, retry, test_bitcoin_client,
+    from_rpc, get_wrapped_header_from_block_hash, populate_bitcoin_block, regtest_address, retry,
+    test_bitcoin_client, to_rpc,
 };
+use crate::app::NETWORK;
 use crate::checkpoint::{Checkpoint, CheckpointStatus};
 use crate::constants::{BTC_NATIVE_TOKEN_DENOM, SIGSET_THRESHOLD};
 use crate::interface::{BitcoinConfig, CheckpointConfig, Dest};
 use crate::msg;
 use crate::recovery::SignedRecoveryTx;
+use crate::signatory::signatory_key_challenge;
 use crate::state::Ratio;
 use crate::tests::helper::{sign, MockApp};
+use bitcoin::bip32;
+use bitcoin::block::Header as BlockHeader;
+use bitcoin::consensus::encode::serialize_hex;
 use bitcoin::consensus::Decodable;
+use bitcoin::merkle_tree::{MerkleBlock, PartialMerkleTree};
 use bitcoin::secp256k1::Secp256k1;
-use bitcoin::util::bip32::{ExtendedPrivKey, ExtendedPubKey};
-use bitcoin::util::merkleblock::PartialMerkleTree;
-use bitcoin::{Address, BlockHeader, Transaction};
-use bitcoincore_rpc_async::{Client, RawTx, RpcApi as AsyncRpcApi};
+use bitcoin::{Amount, Transaction};
+use bitcoincore_rpc_async::bitcoin::{self as rpc_bitcoin, Address};
+use bitcoincore_rpc_async::{Client, RpcApi as AsyncRpcApi};
 use bitcoind::bitcoincore_rpc::RpcApi;
 use bitcoind::{BitcoinD, Conf};
 use common_bitcoin::adapter::Adapter;
 use common_bitcoin::adapter::WrappedBinary;
+use common_bitcoin::address::script_to_address;
 use common_bitcoin::xpub::Xpub;
 use cosmwasm_std::coins;
 use cosmwasm_std::{Addr, Binary, Coin, Uint128};
@@ -82,11 +89,12 @@ async fn relay_checkpoint(
 
     for cp in completed_cps {
         let tx = cp.into_inner();
-        let wrapped_txid = btc_client.send_raw_transaction(&tx).await;
+        let wrapped_txid = btc_client.send_raw_transaction(serialize_hex(&tx)).await;
         match wrapped_txid {
             Ok(txid) => {
                 println!("Relayed checkpoint {:?}", tx.ntxid().to_string());
-                let btc_tx = btc_client.get_raw_transaction(&txid, None).await.unwrap();
+                let btc_tx: Transaction =
+                    from_rpc(&btc_client.get_raw_transaction(&txid, None).await.unwrap());
 
                 let headers = mine_and_relay_headers(
                     btc_client,
@@ -100,14 +108,12 @@ async fn relay_checkpoint(
                 let block = headers[0].block_hash();
                 let block_height = headers[0].height();
                 let tx_proof = btc_client
-                    .get_tx_out_proof(&[btc_tx.txid()], Some(&block))
+                    .get_tx_out_proof(&[to_rpc(&btc_tx.txid())], Some(&to_rpc(&block)))
                     .await
                     .unwrap();
-                let proof = bitcoin::util::merkleblock::MerkleBlock::consensus_decode(
-                    &mut tx_proof.as_slice(),
-                )
-                .unwrap()
-                .txn;
+                let proof = MerkleBlock::consensus_decode(&mut tx_proof.as_slice())
+                    .unwrap()
+                    .txn;
 
                 app.execute(
                     sender.clone(),
@@ -115,7 +121,8 @@ async fn relay_checkpoint(
                     &msg::ExecuteMsg::RelayCheckpoint {
                         btc_height: block_height,
                         btc_proof: Adapter::from(proof),
-                        cp_index: checkpoint_index,
+                        cp_index: Some(checkpoint_index),
+                        ancestor_headers: vec![],
                     },
                     &[],
                 )
@@ -146,7 +153,7 @@ async fn relay_recovery(
     for recovery_tx in recovery_txs {
         let transaction = recovery_tx.tx.clone();
         let txid = btc_client
-            .send_raw_transaction(&transaction.into_inner())
+            .send_raw_transaction(serialize_hex(&transaction.into_inner()))
             .await;
         let headers = mine_and_relay_headers(
             btc_client,
@@ -159,13 +166,12 @@ async fn relay_recovery(
         .await;
         let header = &headers[0];
         let tx_proof = btc_client
-            .get_tx_out_proof(&[txid.unwrap()], Some(&header.block_hash()))
+            .get_tx_out_proof(&[txid.unwrap()], Some(&to_rpc(&header.block_hash())))
             .await
             .unwrap();
-        let proof =
-            bitcoin::util::merkleblock::MerkleBlock::consensus_decode(&mut tx_proof.as_slice())
-                .unwrap()
-                .txn;
+        let proof = MerkleBlock::consensus_decode(&mut tx_proof.as_slice())
+            .unwrap()
+            .txn;
         app.execute(
             sender.clone(),
             bitcoin_bridge_addr.clone(),
@@ -348,14 +354,14 @@ async fn test_unauthorized_when_trying_to_set_foundation_validators() {
     let network = bitcoin::Network::Bitcoin; // This is actually hard-coded
     let secp = Secp256k1::new();
     let foundation_xprivs = vec![
-        ExtendedPrivKey::new_master(network, &[4]).unwrap(),
-        ExtendedPrivKey::new_master(network, &[5]).unwrap(),
-        ExtendedPrivKey::new_master(network, &[6]).unwrap(),
+        bip32::Xpriv::new_master(network, &[4]).unwrap(),
+        bip32::Xpriv::new_master(network, &[5]).unwrap(),
+        bip32::Xpriv::new_master(network, &[6]).unwrap(),
     ];
     let foundation_xpubs = vec![
-        ExtendedPubKey::from_priv(&secp, &foundation_xprivs[0]),
-        ExtendedPubKey::from_priv(&secp, &foundation_xprivs[1]),
-        ExtendedPubKey::from_priv(&secp, &foundation_xprivs[2]),
+        bip32::Xpub::from_priv(&secp, &foundation_xprivs[0]),
+        bip32::Xpub::from_priv(&secp, &foundation_xprivs[1]),
+        bip32::Xpub::from_priv(&secp, &foundation_xprivs[2]),
     ];
     app.execute(
         validator_1.clone(),
@@ -666,27 +672,43 @@ async fn test_full_flow_happy_case_bitcoin() {
 
     let withdraw_to_bitcoin =
         |app: &mut MockApp, sender: Addr, btc_address: Address, coin: Coin| -> MockResult<_> {
+            // the bridge only takes addresses of its own network, the regtest
+            // wallet's script is paid the same
+            let btc_address = script_to_address(
+                bitcoin::Script::from_bytes(btc_address.script_pubkey().as_bytes()),
+                NETWORK,
+            )
+            .unwrap();
             app.execute(
                 sender,
                 bitcoin_bridge_addr.clone(),
                 &msg::ExecuteMsg::WithdrawToBitcoin {
                     btc_address: btc_address.to_string(),
                     fee: None,
+                    on_behalf_of: None,
+                    amount: None,
+                    priority: Default::default(),
                 },
                 &[coin],
             )
         };
 
-    let set_signatory_key = |app: &mut MockApp, sender: Addr, xpub: Xpub| -> MockResult<_> {
-        app.execute(
-            sender,
-            bitcoin_bridge_addr.clone(),
-            &msg::ExecuteMsg::SetSignatoryKey {
-                xpub: WrappedBinary(xpub),
-            },
-            &[],
-        )
-    };
+    let set_signatory_key =
+        |app: &mut MockApp, sender: Addr, xpriv: &bip32::Xpriv| -> MockResult<_> {
+            let secp = Secp256k1::signing_only();
+            let xpub = Xpub::new(bip32::Xpub::from_priv(&secp, xpriv));
+            let challenge = signatory_key_challenge(&bitcoin_bridge_addr, &sender, &xpub);
+            let sig = sign(&secp, xpriv, &[(challenge, 0)]).unwrap().remove(0);
+            app.execute(
+                sender,
+                bitcoin_bridge_addr.clone(),
+                &msg::ExecuteMsg::SetSignatoryKey {
+                    xpub: WrappedBinary(xpub),
+                    sig,
+                },
+                &[],
+            )
+        };
 
     let increase_block = |app: &mut MockApp, hash: Binary| -> MockResult<_> {
         app.sudo(
@@ -697,8 +719,8 @@ async fn test_full_flow_happy_case_bitcoin() {
 
     let sign_cp = |app: &mut MockApp,
                    sender: Addr,
-                   xpriv: &ExtendedPrivKey,
-                   xpub: ExtendedPubKey,
+                   xpriv: &bip32::Xpriv,
+                   xpub: bip32::Xpub,
                    cp_index: u32,
                    btc_height: u32|
      -> MockResult<_> {
@@ -721,6 +743,7 @@ async fn test_full_flow_happy_case_bitcoin() {
                 sigs,
                 checkpoint_index: cp_index,
                 btc_height,
+                offset: None,
             },
             &[],
         )
@@ -728,8 +751,8 @@ async fn test_full_flow_happy_case_bitcoin() {
 
     let sign_recovery = |app: &mut MockApp,
                          sender: Addr,
-                         xpriv: &ExtendedPrivKey,
-                         xpub: ExtendedPubKey|
+                         xpriv: &bip32::Xpriv,
+                         xpub: bip32::Xpub|
      -> MockResult<_> {
         let secp = Secp256k1::signing_only();
         let to_signs: Vec<([u8; 32], u32)> = app
@@ -788,16 +811,16 @@ async fn test_full_flow_happy_case_bitcoin() {
     let network = bitcoin::Network::Bitcoin; // This is actually hard-coded
     let secp = Secp256k1::new();
     let xprivs = vec![
-        ExtendedPrivKey::new_master(network, &[0]).unwrap(),
-        ExtendedPrivKey::new_master(network, &[1]).unwrap(),
-        ExtendedPrivKey::new_master(network, &[2]).unwrap(),
-        ExtendedPrivKey::new_master(network, &[3]).unwrap(),
+        bip32::Xpriv::new_master(network, &[0]).unwrap(),
+        bip32::Xpriv::new_master(network, &[1]).unwrap(),
+        bip32::Xpriv::new_master(network, &[2]).unwrap(),
+        bip32::Xpriv::new_master(network, &[3]).unwrap(),
     ];
     let xpubs = vec![
-        ExtendedPubKey::from_priv(&secp, &xprivs[0]),
-        ExtendedPubKey::from_priv(&secp, &xprivs[1]),
-        ExtendedPubKey::from_priv(&secp, &xprivs[2]),
-        ExtendedPubKey::from_priv(&secp, &xprivs[3]),
+        bip32::Xpub::from_priv(&secp, &xprivs[0]),
+        bip32::Xpub::from_priv(&secp, &xprivs[1]),
+        bip32::Xpub::from_priv(&secp, &xprivs[2]),
+        bip32::Xpub::from_priv(&secp, &xprivs[3]),
     ];
     let consensus_keys = vec![[0; 32], [1; 32], [2; 32], [3; 32]];
 
@@ -819,9 +842,9 @@ async fn test_full_flow_happy_case_bitcoin() {
         vec![consensus_keys[3]],
     )
     .unwrap();
-    set_signatory_key(&mut app, validator_4.clone(), Xpub::new(xpubs[3])).unwrap();
-    set_signatory_key(&mut app, validator_1.clone(), Xpub::new(xpubs[0])).unwrap();
-    set_signatory_key(&mut app, validator_2.clone(), Xpub::new(xpubs[1])).unwrap();
+    set_signatory_key(&mut app, validator_4.clone(), &xprivs[3]).unwrap();
+    set_signatory_key(&mut app, validator_1.clone(), &xprivs[0]).unwrap();
+    set_signatory_key(&mut app, validator_2.clone(), &xprivs[1]).unwrap();
     increase_block(&mut app, Binary::from([0; 32])).unwrap(); // should increase number of hash to be unique
 
     add_validators(
@@ -831,7 +854,7 @@ async fn test_full_flow_happy_case_bitcoin() {
         vec![consensus_keys[2]],
     )
     .unwrap();
-    set_signatory_key(&mut app, validator_3.clone(), Xpub::new(xpubs[2])).unwrap();
+    set_signatory_key(&mut app, validator_3.clone(), &xprivs[2]).unwrap();
 
     // Fetching checkpoint and creating deposit address
     let checkpoint: Checkpoint = app
@@ -849,8 +872,8 @@ async fn test_full_flow_happy_case_bitcoin() {
     let script = sigset
         .output_script(&dest.commitment_bytes().unwrap(), threshold)
         .unwrap();
-    let deposit_addr = bitcoin::Address::from_script(&script, bitcoin::Network::Regtest).unwrap();
-    let deposit_amount = bitcoin::Amount::from_btc(1.2).unwrap();
+    let deposit_addr = regtest_address(&script);
+    let deposit_amount = rpc_bitcoin::Amount::from_btc(1.2).unwrap();
 
     let btc_txid = wallet
         .send_to_address(
@@ -864,14 +887,16 @@ async fn test_full_flow_happy_case_bitcoin() {
             None,
         )
         .unwrap();
-    let btc_tx = btc_client
-        .get_raw_transaction(&btc_txid, None)
-        .await
-        .unwrap();
+    let btc_tx: Transaction = from_rpc(
+        &btc_client
+            .get_raw_transaction(&btc_txid, None)
+            .await
+            .unwrap(),
+    );
     let vout = btc_tx
         .output
         .iter()
-        .position(|o| o.value == deposit_amount.to_sat())
+        .position(|o| o.value.to_sat() == deposit_amount.to_sat())
         .unwrap();
 
     let expired_btc_txid = wallet
@@ -886,14 +911,16 @@ async fn test_full_flow_happy_case_bitcoin() {
             None,
         )
         .unwrap();
-    let expired_btc_tx = btc_client
-        .get_raw_transaction(&expired_btc_txid, None)
-        .await
-        .unwrap();
+    let expired_btc_tx: Transaction = from_rpc(
+        &btc_client
+            .get_raw_transaction(&expired_btc_txid, None)
+            .await
+            .unwrap(),
+    );
     let expired_vout = expired_btc_tx
         .output
         .iter()
-        .position(|o| o.value == deposit_amount.to_sat())
+        .position(|o| o.value.to_sat() == deposit_amount.to_sat())
         .unwrap();
 
     // mine one block to get proof
@@ -916,22 +943,27 @@ async fn test_full_flow_happy_case_bitcoin() {
 
     // this proof is for current depositing
     let tx_proof = btc_client
-        .get_tx_out_proof(&[btc_tx.txid()], Some(&headers[0].block_hash()))
+        .get_tx_out_proof(
+            &[to_rpc(&btc_tx.txid())],
+            Some(&to_rpc(&headers[0].block_hash())),
+        )
         .await
         .unwrap();
-    let proof = bitcoin::util::merkleblock::MerkleBlock::consensus_decode(&mut tx_proof.as_slice())
+    let proof = MerkleBlock::consensus_decode(&mut tx_proof.as_slice())
         .unwrap()
         .txn;
 
     // this proof is for expired tx
     let expired_tx_proof = btc_client
-        .get_tx_out_proof(&[expired_btc_tx.txid()], Some(&headers[0].block_hash()))
+        .get_tx_out_proof(
+            &[to_rpc(&expired_btc_tx.txid())],
+            Some(&to_rpc(&headers[0].block_hash())),
+        )
         .await
         .unwrap();
-    let expired_proof =
-        bitcoin::util::merkleblock::MerkleBlock::consensus_decode(&mut expired_tx_proof.as_slice())
-            .unwrap()
-            .txn;
+    let expired_proof = MerkleBlock::consensus_decode(&mut expired_tx_proof.as_slice())
+        .unwrap()
+        .txn;
 
     let deposit_fee: u64 = app
         .query(
@@ -1053,7 +1085,7 @@ async fn test_full_flow_happy_case_bitcoin() {
     .unwrap();
 
     // deposit
-    let deposit_addr = bitcoin::Address::from_script(&script, bitcoin::Network::Regtest).unwrap();
+    let deposit_addr = regtest_address(&script);
     let btc_txid = wallet
         .send_to_address(
             &deposit_addr,
@@ -1066,14 +1098,16 @@ async fn test_full_flow_happy_case_bitcoin() {
             None,
         )
         .unwrap();
-    let btc_tx = btc_client
-        .get_raw_transaction(&btc_txid, None)
-        .await
-        .unwrap();
+    let btc_tx: Transaction = from_rpc(
+        &btc_client
+            .get_raw_transaction(&btc_txid, None)
+            .await
+            .unwrap(),
+    );
     let vout = btc_tx
         .output
         .iter()
-        .position(|o| o.value == deposit_amount.to_sat())
+        .position(|o| o.value.to_sat() == deposit_amount.to_sat())
         .unwrap();
 
     let headers = mine_and_relay_headers(
@@ -1093,10 +1127,13 @@ async fn test_full_flow_happy_case_bitcoin() {
         .unwrap();
     assert_eq!(header_height, 1025);
     let tx_proof = btc_client
-        .get_tx_out_proof(&[btc_tx.txid()], Some(&headers[0].block_hash()))
+        .get_tx_out_proof(
+            &[to_rpc(&btc_tx.txid())],
+            Some(&to_rpc(&headers[0].block_hash())),
+        )
         .await
         .unwrap();
-    let proof = bitcoin::util::merkleblock::MerkleBlock::consensus_decode(&mut tx_proof.as_slice())
+    let proof = MerkleBlock::consensus_decode(&mut tx_proof.as_slice())
         .unwrap()
         .txn;
 
@@ -1330,7 +1367,7 @@ async fn test_full_flow_happy_case_bitcoin() {
 async fn test_happy_case_with_foundation_validators() {
     // Set up app
 
-    use bitcoin::{secp256k1::Message, util::bip32::ChildNumber, EcdsaSighashType, Sequence};
+    use bitcoin::{bip32::ChildNumber, secp256k1::Message, EcdsaSighashType, Sequence};
 
     use crate::threshold_sig::Pubkey;
 
@@ -1509,18 +1546,24 @@ async fn test_happy_case_with_foundation_validators() {
         )
     };
 
-    let set_signatory_key = |app: &mut MockApp, sender: Addr, xpub: Xpub| -> MockResult<_> {
-        app.execute(
-            sender,
-            bitcoin_bridge_addr.clone(),
-            &msg::ExecuteMsg::SetSignatoryKey {
-                xpub: WrappedBinary(xpub),
-            },
-            &[],
-        )
-    };
+    let set_signatory_key =
+        |app: &mut MockApp, sender: Addr, xpriv: &bip32::Xpriv| -> MockResult<_> {
+            let secp = Secp256k1::signing_only();
+            let xpub = Xpub::new(bip32::Xpub::from_priv(&secp, xpriv));
+            let challenge = signatory_key_challenge(&bitcoin_bridge_addr, &sender, &xpub);
+            let sig = sign(&secp, xpriv, &[(challenge, 0)]).unwrap().remove(0);
+            app.execute(
+                sender,
+                bitcoin_bridge_addr.clone(),
+                &msg::ExecuteMsg::SetSignatoryKey {
+                    xpub: WrappedBinary(xpub),
+                    sig,
+                },
+                &[],
+            )
+        };
 
-    let update_foundation_keys = |app: &mut MockApp, xpubs: Vec<ExtendedPubKey>| -> MockResult<_> {
+    let update_foundation_keys = |app: &mut MockApp, xpubs: Vec<bip32::Xpub>| -> MockResult<_> {
         app.execute(
             owner.clone(),
             bitcoin_bridge_addr.clone(),
@@ -1543,8 +1586,8 @@ async fn test_happy_case_with_foundation_validators() {
 
     let sign_cp = |app: &mut MockApp,
                    sender: Addr,
-                   xpriv: &ExtendedPrivKey,
-                   xpub: ExtendedPubKey,
+                   xpriv: &bip32::Xpriv,
+                   xpub: bip32::Xpub,
                    cp_index: u32,
                    btc_height: u32|
      -> MockResult<_> {
@@ -1567,6 +1610,7 @@ async fn test_happy_case_with_foundation_validators() {
                 sigs,
                 checkpoint_index: cp_index,
                 btc_height,
+                offset: None,
             },
             &[],
         )
@@ -1608,26 +1652,26 @@ async fn test_happy_case_with_foundation_validators() {
     let network = bitcoin::Network::Bitcoin; // This is actually hard-coded
     let secp = Secp256k1::new();
     let xprivs = vec![
-        ExtendedPrivKey::new_master(network, &[0]).unwrap(),
-        ExtendedPrivKey::new_master(network, &[1]).unwrap(),
-        ExtendedPrivKey::new_master(network, &[2]).unwrap(),
-        ExtendedPrivKey::new_master(network, &[3]).unwrap(),
+        bip32::Xpriv::new_master(network, &[0]).unwrap(),
+        bip32::Xpriv::new_master(network, &[1]).unwrap(),
+        bip32::Xpriv::new_master(network, &[2]).unwrap(),
+        bip32::Xpriv::new_master(network, &[3]).unwrap(),
     ];
     let xpubs = vec![
-        ExtendedPubKey::from_priv(&secp, &xprivs[0]),
-        ExtendedPubKey::from_priv(&secp, &xprivs[1]),
-        ExtendedPubKey::from_priv(&secp, &xprivs[2]),
-        ExtendedPubKey::from_priv(&secp, &xprivs[3]),
+        bip32::Xpub::from_priv(&secp, &xprivs[0]),
+        bip32::Xpub::from_priv(&secp, &xprivs[1]),
+        bip32::Xpub::from_priv(&secp, &xprivs[2]),
+        bip32::Xpub::from_priv(&secp, &xprivs[3]),
     ];
     let foundation_xprivs = vec![
-        ExtendedPrivKey::new_master(network, &[4]).unwrap(),
-        ExtendedPrivKey::new_master(network, &[5]).unwrap(),
-        ExtendedPrivKey::new_master(network, &[6]).unwrap(),
+        bip32::Xpriv::new_master(network, &[4]).unwrap(),
+        bip32::Xpriv::new_master(network, &[5]).unwrap(),
+        bip32::Xpriv::new_master(network, &[6]).unwrap(),
     ];
     let foundation_xpubs = vec![
-        ExtendedPubKey::from_priv(&secp, &foundation_xprivs[0]),
-        ExtendedPubKey::from_priv(&secp, &foundation_xprivs[1]),
-        ExtendedPubKey::from_priv(&secp, &foundation_xprivs[2]),
+        bip32::Xpub::from_priv(&secp, &foundation_xprivs[0]),
+        bip32::Xpub::from_priv(&secp, &foundation_xprivs[1]),
+        bip32::Xpub::from_priv(&secp, &foundation_xprivs[2]),
     ];
     let consensus_keys = vec![[0; 32], [1; 32], [2; 32], [3; 32]];
 
@@ -1650,9 +1694,9 @@ async fn test_happy_case_with_foundation_validators() {
         vec![consensus_keys[3]],
     )
     .unwrap();
-    set_signatory_key(&mut app, validator_4.clone(), Xpub::new(xpubs[3])).unwrap();
-    set_signatory_key(&mut app, validator_1.clone(), Xpub::new(xpubs[0])).unwrap();
-    set_signatory_key(&mut app, validator_2.clone(), Xpub::new(xpubs[1])).unwrap();
+    set_signatory_key(&mut app, validator_4.clone(), &xprivs[3]).unwrap();
+    set_signatory_key(&mut app, validator_1.clone(), &xprivs[0]).unwrap();
+    set_signatory_key(&mut app, validator_2.clone(), &xprivs[1]).unwrap();
     increase_block(&mut app, Binary::from([0; 32])).unwrap(); // should increase number of hash to be unique
 
     // Fetching checkpoint and creating deposit address
@@ -1671,8 +1715,8 @@ async fn test_happy_case_with_foundation_validators() {
     let script = sigset
         .output_script(&dest.commitment_bytes().unwrap(), threshold)
         .unwrap();
-    let deposit_addr = bitcoin::Address::from_script(&script, bitcoin::Network::Regtest).unwrap();
-    let deposit_amount = bitcoin::Amount::from_btc(1.2).unwrap();
+    let deposit_addr = regtest_address(&script);
+    let deposit_amount = rpc_bitcoin::Amount::from_btc(1.2).unwrap();
 
     let btc_txid = wallet
         .send_to_address(
@@ -1686,15 +1730,17 @@ async fn test_happy_case_with_foundation_validators() {
             None,
         )
         .unwrap();
-    let btc_tx = btc_client
-        .get_raw_transaction(&btc_txid, None)
-        .await
-        .unwrap();
-    println!("btc_tx: {:?}", btc_tx.raw_hex());
+    let btc_tx: Transaction = from_rpc(
+        &btc_client
+            .get_raw_transaction(&btc_txid, None)
+            .await
+            .unwrap(),
+    );
+    println!("btc_tx: {:?}", serialize_hex(&btc_tx));
     let vout = btc_tx
         .output
         .iter()
-        .position(|o| o.value == deposit_amount.to_sat())
+        .position(|o| o.value.to_sat() == deposit_amount.to_sat())
         .unwrap();
 
     // mine one block to get proof
@@ -1717,10 +1763,13 @@ async fn test_happy_case_with_foundation_validators() {
 
     // this proof is for current depositing
     let tx_proof = btc_client
-        .get_tx_out_proof(&[btc_tx.txid()], Some(&headers[0].block_hash()))
+        .get_tx_out_proof(
+            &[to_rpc(&btc_tx.txid())],
+            Some(&to_rpc(&headers[0].block_hash())),
+        )
         .await
         .unwrap();
-    let proof = bitcoin::util::merkleblock::MerkleBlock::consensus_decode(&mut tx_proof.as_slice())
+    let proof = MerkleBlock::consensus_decode(&mut tx_proof.as_slice())
         .unwrap()
         .txn;
 
@@ -1840,35 +1889,35 @@ async fn test_happy_case_with_foundation_validators() {
             txid: checkpoint_tx.txid(),
             vout: 0,
         },
-        script_sig: bitcoin::Script::default(),
+        script_sig: bitcoin::ScriptBuf::new(),
         sequence: Sequence(u32::MAX),
         witness: bitcoin::Witness::new(),
     };
-    let input_amount = if checkpoint_tx.output[0].value > 0 {
+    let input_amount = if checkpoint_tx.output[0].value > Amount::ZERO {
         checkpoint_tx.output[0].value
     } else {
         checkpoint_tx.output[1].value
     };
     let bitcoin_txout = bitcoin::TxOut {
-        value: input_amount - 1000,
-        script_pubkey: receive_fund_address.script_pubkey(),
+        value: input_amount - Amount::from_sat(1000),
+        script_pubkey: from_rpc(&receive_fund_address.script_pubkey()),
     };
     let bitcoin_transaction = bitcoin::Transaction {
-        version: 1,
-        lock_time: bitcoin::PackedLockTime(0),
+        version: bitcoin::transaction::Version::ONE,
+        lock_time: bitcoin::absolute::LockTime::ZERO,
         input: vec![bitcoin_txin],
         output: vec![bitcoin_txout],
     };
-    let mut sc = bitcoin::util::sighash::SighashCache::new(&bitcoin_transaction);
-    let sighash: bitcoin::Sighash = sc
-        .segwit_signature_hash(0, &redeem_script, input_amount, EcdsaSighashType::All)
+    let mut sc = bitcoin::sighash::SighashCache::new(&bitcoin_transaction);
+    let sighash: bitcoin::sighash::SegwitV0Sighash = sc
+        .p2wsh_signature_hash(0, &redeem_script, input_amount, EcdsaSighashType::All)
         .unwrap();
     // sign with foundation priv keys
     let mut sigs = vec![];
     let secp = Secp256k1::new();
     for i in 0..3 {
         let privkey = foundation_xprivs[i].private_key;
-        let message = Message::from_slice(&sighash.as_ref()).unwrap(); // Extract the [u8; 32] value from the tuple
+        let message = Message::from_digest_slice(&sighash.as_ref()).unwrap(); // Extract the [u8; 32] value from the tuple
         let mut sig = secp.sign_ecdsa(&message, &privkey).serialize_der().to_vec();
         sig.push(EcdsaSighashType::All.to_u32() as u8);
         sigs.push(sig.clone());
@@ -1876,16 +1925,16 @@ async fn test_happy_case_with_foundation_validators() {
 
     // [FAILED CASE] correct signatures but does not have enough voting power
     let fake_foundation_xprivs = vec![
-        ExtendedPrivKey::new_master(network, &[7]).unwrap(),
-        ExtendedPrivKey::new_master(network, &[8]).unwrap(),
-        ExtendedPrivKey::new_master(network, &[9]).unwrap(),
+        bip32::Xpriv::new_master(network, &[7]).unwrap(),
+        bip32::Xpriv::new_master(network, &[8]).unwrap(),
+        bip32::Xpriv::new_master(network, &[9]).unwrap(),
     ];
     let mut failed = false;
     let mut fake_sigs = vec![] as Vec<Vec<u8>>;
     let secp = Secp256k1::new();
     for i in 0..3 {
         let privkey = fake_foundation_xprivs[i].private_key;
-        let message = Message::from_slice(&sighash.as_ref()).unwrap(); // Extract the [u8; 32] value from the tuple
+        let message = Message::from_digest_slice(&sighash.as_ref()).unwrap(); // Extract the [u8; 32] value from the tuple
         let mut sig = secp.sign_ecdsa(&message, &privkey).serialize_der().to_vec();
         sig.push(EcdsaSighashType::All.to_u32() as u8);
         fake_sigs.push(sig.clone());
@@ -1901,25 +1950,25 @@ async fn test_happy_case_with_foundation_validators() {
             txid: checkpoint_tx.txid(),
             vout: 0,
         },
-        script_sig: bitcoin::Script::default(),
+        script_sig: bitcoin::ScriptBuf::new(),
         sequence: Sequence(u32::MAX),
         witness,
     };
     let bitcoin_txout = bitcoin::TxOut {
-        value: input_amount - 1000,
-        script_pubkey: receive_fund_address.script_pubkey(),
+        value: input_amount - Amount::from_sat(1000),
+        script_pubkey: from_rpc(&receive_fund_address.script_pubkey()),
     };
     let bitcoin_transaction_1 = bitcoin::Transaction {
-        version: 1,
-        lock_time: bitcoin::PackedLockTime(0),
+        version: bitcoin::transaction::Version::ONE,
+        lock_time: bitcoin::absolute::LockTime::ZERO,
         input: vec![bitcoin_txin],
         output: vec![bitcoin_txout],
     };
     let wrapped_txid = btc_client
-        .send_raw_transaction(&bitcoin_transaction_1)
+        .send_raw_transaction(serialize_hex(&bitcoin_transaction_1))
         .await;
-    println!("tx hex: {:?}", bitcoin_transaction_1.raw_hex());
-    println!("txin hex: {:?}", checkpoint_tx.raw_hex());
+    println!("tx hex: {:?}", serialize_hex(&bitcoin_transaction_1));
+    println!("txin hex: {:?}", serialize_hex(&*checkpoint_tx));
     match wrapped_txid {
         Ok(txid) => {
             println!("Withdraw txid: {}", txid);
@@ -1955,25 +2004,25 @@ async fn test_happy_case_with_foundation_validators() {
             txid: checkpoint_tx.txid(),
             vout: 0,
         },
-        script_sig: bitcoin::Script::default(),
+        script_sig: bitcoin::ScriptBuf::new(),
         sequence: Sequence(u32::MAX),
         witness,
     };
     let bitcoin_txout = bitcoin::TxOut {
-        value: input_amount - 1000,
-        script_pubkey: receive_fund_address.script_pubkey(),
+        value: input_amount - Amount::from_sat(1000),
+        script_pubkey: from_rpc(&receive_fund_address.script_pubkey()),
     };
     let bitcoin_transaction_1 = bitcoin::Transaction {
-        version: 1,
-        lock_time: bitcoin::PackedLockTime(0),
+        version: bitcoin::transaction::Version::ONE,
+        lock_time: bitcoin::absolute::LockTime::ZERO,
         input: vec![bitcoin_txin],
         output: vec![bitcoin_txout],
     };
     let wrapped_txid = btc_client
-        .send_raw_transaction(&bitcoin_transaction_1)
+        .send_raw_transaction(serialize_hex(&bitcoin_transaction_1))
         .await;
-    println!("tx hex: {:?}", bitcoin_transaction_1.raw_hex());
-    println!("txin hex: {:?}", checkpoint_tx.raw_hex());
+    println!("tx hex: {:?}", serialize_hex(&bitcoin_transaction_1));
+    println!("txin hex: {:?}", serialize_hex(&*checkpoint_tx));
     match wrapped_txid {
         Ok(txid) => {
             println!("Withdraw txid: {}", txid);
@@ -2009,25 +2058,25 @@ async fn test_happy_case_with_foundation_validators() {
             txid: checkpoint_tx.txid(),
             vout: 0,
         },
-        script_sig: bitcoin::Script::default(),
+        script_sig: bitcoin::ScriptBuf::new(),
         sequence: Sequence(u32::MAX),
         witness,
     };
     let bitcoin_txout = bitcoin::TxOut {
-        value: input_amount - 1000,
-        script_pubkey: receive_fund_address.script_pubkey(),
+        value: input_amount - Amount::from_sat(1000),
+        script_pubkey: from_rpc(&receive_fund_address.script_pubkey()),
     };
     let bitcoin_transaction_1 = bitcoin::Transaction {
-        version: 1,
-        lock_time: bitcoin::PackedLockTime(0),
+        version: bitcoin::transaction::Version::ONE,
+        lock_time: bitcoin::absolute::LockTime::ZERO,
         input: vec![bitcoin_txin],
         output: vec![bitcoin_txout],
     };
     let wrapped_txid = btc_client
-        .send_raw_transaction(&bitcoin_transaction_1)
+        .send_raw_transaction(serialize_hex(&bitcoin_transaction_1))
         .await;
-    println!("tx hex: {:?}", bitcoin_transaction_1.raw_hex());
-    println!("txin hex: {:?}", checkpoint_tx.raw_hex());
+    println!("tx hex: {:?}", serialize_hex(&bitcoin_transaction_1));
+    println!("txin hex: {:?}", serialize_hex(&*checkpoint_tx));
     match wrapped_txid {
         Ok(txid) => {
             println!("Withdraw txid: {}", txid);
@@ -2048,7 +2097,7 @@ async fn test_happy_case_with_foundation_validators() {
 async fn test_full_flow_with_foundation_validators() {
     // Set up app
 
-    use bitcoin::{secp256k1::Message, util::bip32::ChildNumber, EcdsaSighashType, Sequence};
+    use bitcoin::{bip32::ChildNumber, secp256k1::Message, EcdsaSighashType, Sequence};
 
     use crate::threshold_sig::Pubkey;
 
@@ -2227,18 +2276,24 @@ async fn test_full_flow_with_foundation_validators() {
         )
     };
 
-    let set_signatory_key = |app: &mut MockApp, sender: Addr, xpub: Xpub| -> MockResult<_> {
-        app.execute(
-            sender,
-            bitcoin_bridge_addr.clone(),
-            &msg::ExecuteMsg::SetSignatoryKey {
-                xpub: WrappedBinary(xpub),
-            },
-            &[],
-        )
-    };
+    let set_signatory_key =
+        |app: &mut MockApp, sender: Addr, xpriv: &bip32::Xpriv| -> MockResult<_> {
+            let secp = Secp256k1::signing_only();
+            let xpub = Xpub::new(bip32::Xpub::from_priv(&secp, xpriv));
+            let challenge = signatory_key_challenge(&bitcoin_bridge_addr, &sender, &xpub);
+            let sig = sign(&secp, xpriv, &[(challenge, 0)]).unwrap().remove(0);
+            app.execute(
+                sender,
+                bitcoin_bridge_addr.clone(),
+                &msg::ExecuteMsg::SetSignatoryKey {
+                    xpub: WrappedBinary(xpub),
+                    sig,
+                },
+                &[],
+            )
+        };
 
-    let update_foundation_keys = |app: &mut MockApp, xpubs: Vec<ExtendedPubKey>| -> MockResult<_> {
+    let update_foundation_keys = |app: &mut MockApp, xpubs: Vec<bip32::Xpub>| -> MockResult<_> {
         app.execute(
             owner.clone(),
             bitcoin_bridge_addr.clone(),
@@ -2261,8 +2316,8 @@ async fn test_full_flow_with_foundation_validators() {
 
     let sign_cp = |app: &mut MockApp,
                    sender: Addr,
-                   xpriv: &ExtendedPrivKey,
-                   xpub: ExtendedPubKey,
+                   xpriv: &bip32::Xpriv,
+                   xpub: bip32::Xpub,
                    cp_index: u32,
                    btc_height: u32|
      -> MockResult<_> {
@@ -2285,6 +2340,7 @@ async fn test_full_flow_with_foundation_validators() {
                 sigs,
                 checkpoint_index: cp_index,
                 btc_height,
+                offset: None,
             },
             &[],
         )
@@ -2326,26 +2382,26 @@ async fn test_full_flow_with_foundation_validators() {
     let network = bitcoin::Network::Bitcoin; // This is actually hard-coded
     let secp = Secp256k1::new();
     let xprivs = vec![
-        ExtendedPrivKey::new_master(network, &[0]).unwrap(),
-        ExtendedPrivKey::new_master(network, &[1]).unwrap(),
-        ExtendedPrivKey::new_master(network, &[2]).unwrap(),
-        ExtendedPrivKey::new_master(network, &[3]).unwrap(),
+        bip32::Xpriv::new_master(network, &[0]).unwrap(),
+        bip32::Xpriv::new_master(network, &[1]).unwrap(),
+        bip32::Xpriv::new_master(network, &[2]).unwrap(),
+        bip32::Xpriv::new_master(network, &[3]).unwrap(),
     ];
     let xpubs = vec![
-        ExtendedPubKey::from_priv(&secp, &xprivs[0]),
-        ExtendedPubKey::from_priv(&secp, &xprivs[1]),
-        ExtendedPubKey::from_priv(&secp, &xprivs[2]),
-        ExtendedPubKey::from_priv(&secp, &xprivs[3]),
+        bip32::Xpub::from_priv(&secp, &xprivs[0]),
+        bip32::Xpub::from_priv(&secp, &xprivs[1]),
+        bip32::Xpub::from_priv(&secp, &xprivs[2]),
+        bip32::Xpub::from_priv(&secp, &xprivs[3]),
     ];
     let foundation_xprivs = vec![
-        ExtendedPrivKey::new_master(network, &[4]).unwrap(),
-        ExtendedPrivKey::new_master(network, &[5]).unwrap(),
-        ExtendedPrivKey::new_master(network, &[6]).unwrap(),
+        bip32::Xpriv::new_master(network, &[4]).unwrap(),
+        bip32::Xpriv::new_master(network, &[5]).unwrap(),
+        bip32::Xpriv::new_master(network, &[6]).unwrap(),
     ];
     let foundation_xpubs = vec![
-        ExtendedPubKey::from_priv(&secp, &foundation_xprivs[0]),
-        ExtendedPubKey::from_priv(&secp, &foundation_xprivs[1]),
-        ExtendedPubKey::from_priv(&secp, &foundation_xprivs[2]),
+        bip32::Xpub::from_priv(&secp, &foundation_xprivs[0]),
+        bip32::Xpub::from_priv(&secp, &foundation_xprivs[1]),
+        bip32::Xpub::from_priv(&secp, &foundation_xprivs[2]),
     ];
     let consensus_keys = vec![[0; 32], [1; 32], [2; 32], [3; 32]];
 
@@ -2368,9 +2424,9 @@ async fn test_full_flow_with_foundation_validators() {
         vec![consensus_keys[3]],
     )
     .unwrap();
-    set_signatory_key(&mut app, validator_4.clone(), Xpub::new(xpubs[3])).unwrap();
-    set_signatory_key(&mut app, validator_1.clone(), Xpub::new(xpubs[0])).unwrap();
-    set_signatory_key(&mut app, validator_2.clone(), Xpub::new(xpubs[1])).unwrap();
+    set_signatory_key(&mut app, validator_4.clone(), &xprivs[3]).unwrap();
+    set_signatory_key(&mut app, validator_1.clone(), &xprivs[0]).unwrap();
+    set_signatory_key(&mut app, validator_2.clone(), &xprivs[1]).unwrap();
     increase_block(&mut app, Binary::from([0; 32])).unwrap(); // should increase number of hash to be unique
 
     // Fetching checkpoint and creating deposit address
@@ -2389,8 +2445,8 @@ async fn test_full_flow_with_foundation_validators() {
     let script = sigset
         .output_script(&dest.commitment_bytes().unwrap(), threshold)
         .unwrap();
-    let deposit_addr = bitcoin::Address::from_script(&script, bitcoin::Network::Regtest).unwrap();
-    let deposit_amount = bitcoin::Amount::from_btc(1.2).unwrap();
+    let deposit_addr = regtest_address(&script);
+    let deposit_amount = rpc_bitcoin::Amount::from_btc(1.2).unwrap();
 
     let btc_txid = wallet
         .send_to_address(
@@ -2404,15 +2460,17 @@ async fn test_full_flow_with_foundation_validators() {
             None,
         )
         .unwrap();
-    let btc_tx = btc_client
-        .get_raw_transaction(&btc_txid, None)
-        .await
-        .unwrap();
-    println!("btc_tx: {:?}", btc_tx.raw_hex());
+    let btc_tx: Transaction = from_rpc(
+        &btc_client
+            .get_raw_transaction(&btc_txid, None)
+            .await
+            .unwrap(),
+    );
+    println!("btc_tx: {:?}", serialize_hex(&btc_tx));
     let vout = btc_tx
         .output
         .iter()
-        .position(|o| o.value == deposit_amount.to_sat())
+        .position(|o| o.value.to_sat() == deposit_amount.to_sat())
         .unwrap();
 
     // mine one block to get proof
@@ -2435,10 +2493,13 @@ async fn test_full_flow_with_foundation_validators() {
 
     // this proof is for current depositing
     let tx_proof = btc_client
-        .get_tx_out_proof(&[btc_tx.txid()], Some(&headers[0].block_hash()))
+        .get_tx_out_proof(
+            &[to_rpc(&btc_tx.txid())],
+            Some(&to_rpc(&headers[0].block_hash())),
+        )
         .await
         .unwrap();
-    let proof = bitcoin::util::merkleblock::MerkleBlock::consensus_decode(&mut tx_proof.as_slice())
+    let proof = MerkleBlock::consensus_decode(&mut tx_proof.as_slice())
         .unwrap()
         .txn;
 
@@ -2555,16 +2616,16 @@ async fn test_full_flow_with_foundation_validators() {
     println!("script address: {:?}", script_address);
     // [TESTCASE] update new foundation validators
     let new_foundation_xprivs = vec![
-        ExtendedPrivKey::new_master(network, &[9]).unwrap(),
-        ExtendedPrivKey::new_master(network, &[10]).unwrap(),
-        ExtendedPrivKey::new_master(network, &[11]).unwrap(),
-        ExtendedPrivKey::new_master(network, &[12]).unwrap(),
+        bip32::Xpriv::new_master(network, &[9]).unwrap(),
+        bip32::Xpriv::new_master(network, &[10]).unwrap(),
+        bip32::Xpriv::new_master(network, &[11]).unwrap(),
+        bip32::Xpriv::new_master(network, &[12]).unwrap(),
     ];
     let new_foundation_xpubs = vec![
-        ExtendedPubKey::from_priv(&secp, &new_foundation_xprivs[0]),
-        ExtendedPubKey::from_priv(&secp, &new_foundation_xprivs[1]),
-        ExtendedPubKey::from_priv(&secp, &new_foundation_xprivs[2]),
-        ExtendedPubKey::from_priv(&secp, &new_foundation_xprivs[3]),
+        bip32::Xpub::from_priv(&secp, &new_foundation_xprivs[0]),
+        bip32::Xpub::from_priv(&secp, &new_foundation_xprivs[1]),
+        bip32::Xpub::from_priv(&secp, &new_foundation_xprivs[2]),
+        bip32::Xpub::from_priv(&secp, &new_foundation_xprivs[3]),
     ];
     let _ = update_foundation_keys(&mut app, new_foundation_xpubs.clone()).unwrap();
     increase_block(&mut app, Binary::from([4; 32])).unwrap(); // should increase number of hash to be unique
@@ -2580,15 +2641,17 @@ async fn test_full_flow_with_foundation_validators() {
             None,
         )
         .unwrap();
-    let btc_tx = btc_client
-        .get_raw_transaction(&btc_txid, None)
-        .await
-        .unwrap();
-    println!("btc_tx: {:?}", btc_tx.raw_hex());
+    let btc_tx: Transaction = from_rpc(
+        &btc_client
+            .get_raw_transaction(&btc_txid, None)
+            .await
+            .unwrap(),
+    );
+    println!("btc_tx: {:?}", serialize_hex(&btc_tx));
     let vout = btc_tx
         .output
         .iter()
-        .position(|o| o.value == deposit_amount.to_sat())
+        .position(|o| o.value.to_sat() == deposit_amount.to_sat())
         .unwrap();
     // mine one block to get proof
     let headers = mine_and_relay_headers(
@@ -2609,10 +2672,13 @@ async fn test_full_flow_with_foundation_validators() {
     assert_eq!(header_height, 1025);
     // this proof is for current depositing
     let tx_proof = btc_client
-        .get_tx_out_proof(&[btc_tx.txid()], Some(&headers[0].block_hash()))
+        .get_tx_out_proof(
+            &[to_rpc(&btc_tx.txid())],
+            Some(&to_rpc(&headers[0].block_hash())),
+        )
         .await
         .unwrap();
-    let proof = bitcoin::util::merkleblock::MerkleBlock::consensus_decode(&mut tx_proof.as_slice())
+    let proof = MerkleBlock::consensus_decode(&mut tx_proof.as_slice())
         .unwrap()
         .txn;
     relay_deposit(
@@ -2681,8 +2747,8 @@ async fn test_full_flow_with_foundation_validators() {
     let script = sigset
         .output_script(&dest.commitment_bytes().unwrap(), threshold)
         .unwrap();
-    let deposit_addr = bitcoin::Address::from_script(&script, bitcoin::Network::Regtest).unwrap();
-    let deposit_amount = bitcoin::Amount::from_btc(1.2).unwrap();
+    let deposit_addr = regtest_address(&script);
+    let deposit_amount = rpc_bitcoin::Amount::from_btc(1.2).unwrap();
     let btc_txid = wallet
         .send_to_address(
             &deposit_addr,
@@ -2695,15 +2761,17 @@ async fn test_full_flow_with_foundation_validators() {
             None,
         )
         .unwrap();
-    let btc_tx = btc_client
-        .get_raw_transaction(&btc_txid, None)
-        .await
-        .unwrap();
-    println!("btc_tx: {:?}", btc_tx.raw_hex());
+    let btc_tx: Transaction = from_rpc(
+        &btc_client
+            .get_raw_transaction(&btc_txid, None)
+            .await
+            .unwrap(),
+    );
+    println!("btc_tx: {:?}", serialize_hex(&btc_tx));
     let vout = btc_tx
         .output
         .iter()
-        .position(|o| o.value == deposit_amount.to_sat())
+        .position(|o| o.value.to_sat() == deposit_amount.to_sat())
         .unwrap();
     let btc_txid_1 = wallet
         .send_to_address(
@@ -2717,15 +2785,17 @@ async fn test_full_flow_with_foundation_validators() {
             None,
         )
         .unwrap();
-    let btc_tx_1 = btc_client
-        .get_raw_transaction(&btc_txid_1, None)
-        .await
-        .unwrap();
-    println!("btc_tx: {:?}", btc_tx_1.raw_hex());
+    let btc_tx_1: Transaction = from_rpc(
+        &btc_client
+            .get_raw_transaction(&btc_txid_1, None)
+            .await
+            .unwrap(),
+    );
+    println!("btc_tx: {:?}", serialize_hex(&btc_tx_1));
     let vout_1 = btc_tx_1
         .output
         .iter()
-        .position(|o| o.value == deposit_amount.to_sat())
+        .position(|o| o.value.to_sat() == deposit_amount.to_sat())
         .unwrap();
 
     // mine one block to get proof
@@ -2747,10 +2817,13 @@ async fn test_full_flow_with_foundation_validators() {
     assert_eq!(header_height, 1028);
     // this proof is for current depositing
     let tx_proof = btc_client
-        .get_tx_out_proof(&[btc_tx.txid()], Some(&headers[0].block_hash()))
+        .get_tx_out_proof(
+            &[to_rpc(&btc_tx.txid())],
+            Some(&to_rpc(&headers[0].block_hash())),
+        )
         .await
         .unwrap();
-    let proof = bitcoin::util::merkleblock::MerkleBlock::consensus_decode(&mut tx_proof.as_slice())
+    let proof = MerkleBlock::consensus_decode(&mut tx_proof.as_slice())
         .unwrap()
         .txn;
     relay_deposit(
@@ -2766,10 +2839,13 @@ async fn test_full_flow_with_foundation_validators() {
 
     // this proof is for current depositing
     let tx_proof = btc_client
-        .get_tx_out_proof(&[btc_tx_1.txid()], Some(&headers[0].block_hash()))
+        .get_tx_out_proof(
+            &[to_rpc(&btc_tx_1.txid())],
+            Some(&to_rpc(&headers[0].block_hash())),
+        )
         .await
         .unwrap();
-    let proof = bitcoin::util::merkleblock::MerkleBlock::consensus_decode(&mut tx_proof.as_slice())
+    let proof = MerkleBlock::consensus_decode(&mut tx_proof.as_slice())
         .unwrap()
         .txn;
     relay_deposit(
@@ -2837,7 +2913,7 @@ async fn test_full_flow_with_foundation_validators() {
         .unwrap();
     assert_eq!(confirmed_cp_index, 2);
 
-    let input_amount = if checkpoint_tx.output[0].value > 0 {
+    let input_amount = if checkpoint_tx.output[0].value > Amount::ZERO {
         checkpoint_tx.output[0].value
     } else {
         checkpoint_tx.output[1].value
@@ -2854,28 +2930,28 @@ async fn test_full_flow_with_foundation_validators() {
             txid: checkpoint_tx.txid(),
             vout: 0,
         },
-        script_sig: bitcoin::Script::default(),
+        script_sig: bitcoin::ScriptBuf::new(),
         sequence: Sequence(u32::MAX),
         witness: bitcoin::Witness::new(),
     };
     let bitcoin_txout = bitcoin::TxOut {
-        value: input_amount - 1000,
-        script_pubkey: receive_fund_address.script_pubkey(),
+        value: input_amount - Amount::from_sat(1000),
+        script_pubkey: from_rpc(&receive_fund_address.script_pubkey()),
     };
     let bitcoin_transaction = bitcoin::Transaction {
-        version: 1,
-        lock_time: bitcoin::PackedLockTime(0),
+        version: bitcoin::transaction::Version::ONE,
+        lock_time: bitcoin::absolute::LockTime::ZERO,
         input: vec![bitcoin_txin],
         output: vec![bitcoin_txout],
     };
-    let mut sc = bitcoin::util::sighash::SighashCache::new(&bitcoin_transaction);
+    let mut sc = bitcoin::sighash::SighashCache::new(&bitcoin_transaction);
 
-    let sighash: bitcoin::Sighash = sc
-        .segwit_signature_hash(0, &redeem_script, input_amount, EcdsaSighashType::All)
+    let sighash: bitcoin::sighash::SegwitV0Sighash = sc
+        .p2wsh_signature_hash(0, &redeem_script, input_amount, EcdsaSighashType::All)
         .unwrap();
     for i in 0..4 {
         let privkey = new_foundation_xprivs[i].private_key;
-        let message = Message::from_slice(&sighash.as_ref()).unwrap(); // Extract the [u8; 32] value from the tuple
+        let message = Message::from_digest_slice(&sighash.as_ref()).unwrap(); // Extract the [u8; 32] value from the tuple
         let mut sig = secp.sign_ecdsa(&message, &privkey).serialize_der().to_vec();
         sig.push(EcdsaSighashType::All.to_u32() as u8);
         sigs.push(sig.clone());
@@ -2900,23 +2976,25 @@ async fn test_full_flow_with_foundation_validators() {
             txid: checkpoint_tx.txid(),
             vout: 0,
         },
-        script_sig: bitcoin::Script::default(),
+        script_sig: bitcoin::ScriptBuf::new(),
         sequence: Sequence(u32::MAX),
         witness,
     };
     let bitcoin_txout = bitcoin::TxOut {
-        value: input_amount - 1000,
-        script_pubkey: receive_fund_address.script_pubkey(),
+        value: input_amount - Amount::from_sat(1000),
+        script_pubkey: from_rpc(&receive_fund_address.script_pubkey()),
     };
     let bitcoin_transaction = bitcoin::Transaction {
-        version: 1,
-        lock_time: bitcoin::PackedLockTime(0),
+        version: bitcoin::transaction::Version::ONE,
+        lock_time: bitcoin::absolute::LockTime::ZERO,
         input: vec![bitcoin_txin],
         output: vec![bitcoin_txout],
     };
-    let wrapped_txid = btc_client.send_raw_transaction(&bitcoin_transaction).await;
-    println!("tx hex: {:?}", bitcoin_transaction.raw_hex());
-    println!("txin hex: {:?}", checkpoint_tx.raw_hex());
+    let wrapped_txid = btc_client
+        .send_raw_transaction(serialize_hex(&bitcoin_transaction))
+        .await;
+    println!("tx hex: {:?}", serialize_hex(&bitcoin_transaction));
+    println!("txin hex: {:?}", serialize_hex(&*checkpoint_tx));
     match wrapped_txid {
         Ok(txid) => {
             println!("Withdraw txid: {}", txid);
@@ -3122,16 +3200,22 @@ async fn test_full_flow_native_validators() {
         )
     };
 
-    let set_signatory_key = |app: &mut MockApp, sender: Addr, xpub: Xpub| -> MockResult<_> {
-        app.execute(
-            sender,
-            bitcoin_bridge_addr.clone(),
-            &msg::ExecuteMsg::SetSignatoryKey {
-                xpub: WrappedBinary(xpub),
-            },
-            &[],
-        )
-    };
+    let set_signatory_key =
+        |app: &mut MockApp, sender: Addr, xpriv: &bip32::Xpriv| -> MockResult<_> {
+            let secp = Secp256k1::signing_only();
+            let xpub = Xpub::new(bip32::Xpub::from_priv(&secp, xpriv));
+            let challenge = signatory_key_challenge(&bitcoin_bridge_addr, &sender, &xpub);
+            let sig = sign(&secp, xpriv, &[(challenge, 0)]).unwrap().remove(0);
+            app.execute(
+                sender,
+                bitcoin_bridge_addr.clone(),
+                &msg::ExecuteMsg::SetSignatoryKey {
+                    xpub: WrappedBinary(xpub),
+                    sig,
+                },
+                &[],
+            )
+        };
 
     let increase_block = |app: &mut MockApp, hash: Binary| -> MockResult<_> {
         app.sudo(
@@ -3142,8 +3226,8 @@ async fn test_full_flow_native_validators() {
 
     let sign_cp = |app: &mut MockApp,
                    sender: Addr,
-                   xpriv: &ExtendedPrivKey,
-                   xpub: ExtendedPubKey,
+                   xpriv: &bip32::Xpriv,
+                   xpub: bip32::Xpub,
                    cp_index: u32,
                    btc_height: u32|
      -> MockResult<_> {
@@ -3166,6 +3250,7 @@ async fn test_full_flow_native_validators() {
                 sigs,
                 checkpoint_index: cp_index,
                 btc_height,
+                offset: None,
             },
             &[],
         )
@@ -3189,6 +3274,7 @@ async fn test_full_flow_native_validators() {
             light_client_contract: None,
             owner: None,
             osor_entry_point_contract: None,
+            relayer_fee_options: None,
         },
         &[],
     )
@@ -3227,20 +3313,20 @@ async fn test_full_flow_native_validators() {
     let network = bitcoin::Network::Bitcoin; // This is actually hard-coded
     let secp = Secp256k1::new();
     let xprivs = vec![
-        ExtendedPrivKey::new_master(network, &[0]).unwrap(),
-        ExtendedPrivKey::new_master(network, &[1]).unwrap(),
+        bip32::Xpriv::new_master(network, &[0]).unwrap(),
+        bip32::Xpriv::new_master(network, &[1]).unwrap(),
     ];
     let xpubs = vec![
-        ExtendedPubKey::from_priv(&secp, &xprivs[0]),
-        ExtendedPubKey::from_priv(&secp, &xprivs[1]),
+        bip32::Xpub::from_priv(&secp, &xprivs[0]),
+        bip32::Xpub::from_priv(&secp, &xprivs[1]),
     ];
 
     set_whitelist_validator(&mut app, validator_1.clone(), true).unwrap();
     set_whitelist_validator(&mut app, validator_2.clone(), true).unwrap();
     register_validator(&mut app, validator_1.clone()).unwrap();
     register_validator(&mut app, validator_2.clone()).unwrap();
-    set_signatory_key(&mut app, validator_1.clone(), Xpub::new(xpubs[0])).unwrap();
-    set_signatory_key(&mut app, validator_2.clone(), Xpub::new(xpubs[1])).unwrap();
+    set_signatory_key(&mut app, validator_1.clone(), &xprivs[0]).unwrap();
+    set_signatory_key(&mut app, validator_2.clone(), &xprivs[1]).unwrap();
     increase_block(&mut app, Binary::from([0; 32])).unwrap(); // should increase number of hash to be unique
 
     // Fetching checkpoint and creating deposit address
@@ -3259,8 +3345,8 @@ async fn test_full_flow_native_validators() {
     let script = sigset
         .output_script(&dest.commitment_bytes().unwrap(), threshold)
         .unwrap();
-    let deposit_addr = bitcoin::Address::from_script(&script, bitcoin::Network::Regtest).unwrap();
-    let deposit_amount = bitcoin::Amount::from_btc(1.2).unwrap();
+    let deposit_addr = regtest_address(&script);
+    let deposit_amount = rpc_bitcoin::Amount::from_btc(1.2).unwrap();
 
     let btc_txid = wallet
         .send_to_address(
@@ -3274,14 +3360,16 @@ async fn test_full_flow_native_validators() {
             None,
         )
         .unwrap();
-    let btc_tx = btc_client
-        .get_raw_transaction(&btc_txid, None)
-        .await
-        .unwrap();
+    let btc_tx: Transaction = from_rpc(
+        &btc_client
+            .get_raw_transaction(&btc_txid, None)
+            .await
+            .unwrap(),
+    );
     let vout = btc_tx
         .output
         .iter()
-        .position(|o| o.value == deposit_amount.to_sat())
+        .position(|o| o.value.to_sat() == deposit_amount.to_sat())
         .unwrap();
 
     // mine one block to get proof
@@ -3304,10 +3392,13 @@ async fn test_full_flow_native_validators() {
 
     // this proof is for current depositing
     let tx_proof = btc_client
-        .get_tx_out_proof(&[btc_tx.txid()], Some(&headers[0].block_hash()))
+        .get_tx_out_proof(
+            &[to_rpc(&btc_tx.txid())],
+            Some(&to_rpc(&headers[0].block_hash())),
+        )
         .await
         .unwrap();
-    let proof = bitcoin::util::merkleblock::MerkleBlock::consensus_decode(&mut tx_proof.as_slice())
+    let proof = MerkleBlock::consensus_decode(&mut tx_proof.as_slice())
         .unwrap()
         .txn;
 
@@ -3660,16 +3751,22 @@ async fn test_deposit_with_token_fee() {
         )
     };
 
-    let set_signatory_key = |app: &mut MockApp, sender: Addr, xpub: Xpub| -> MockResult<_> {
-        app.execute(
-            sender,
-            bitcoin_bridge_addr.clone(),
-            &msg::ExecuteMsg::SetSignatoryKey {
-                xpub: WrappedBinary(xpub),
-            },
-            &[],
-        )
-    };
+    let set_signatory_key =
+        |app: &mut MockApp, sender: Addr, xpriv: &bip32::Xpriv| -> MockResult<_> {
+            let secp = Secp256k1::signing_only();
+            let xpub = Xpub::new(bip32::Xpub::from_priv(&secp, xpriv));
+            let challenge = signatory_key_challenge(&bitcoin_bridge_addr, &sender, &xpub);
+            let sig = sign(&secp, xpriv, &[(challenge, 0)]).unwrap().remove(0);
+            app.execute(
+                sender,
+                bitcoin_bridge_addr.clone(),
+                &msg::ExecuteMsg::SetSignatoryKey {
+                    xpub: WrappedBinary(xpub),
+                    sig,
+                },
+                &[],
+            )
+        };
 
     let increase_block = |app: &mut MockApp, hash: Binary| -> MockResult<_> {
         app.sudo(
@@ -3680,8 +3777,8 @@ async fn test_deposit_with_token_fee() {
 
     let sign_cp = |app: &mut MockApp,
                    sender: Addr,
-                   xpriv: &ExtendedPrivKey,
-                   xpub: ExtendedPubKey,
+                   xpriv: &bip32::Xpriv,
+                   xpub: bip32::Xpub,
                    cp_index: u32,
                    btc_height: u32|
      -> MockResult<_> {
@@ -3704,6 +3801,7 @@ async fn test_deposit_with_token_fee() {
                 sigs,
                 checkpoint_index: cp_index,
                 btc_height,
+                offset: None,
             },
             &[],
         )
@@ -3727,6 +3825,7 @@ async fn test_deposit_with_token_fee() {
             light_client_contract: None,
             owner: None,
             osor_entry_point_contract: None,
+            relayer_fee_options: None,
         },
         &[],
     )
@@ -3766,12 +3865,12 @@ async fn test_deposit_with_token_fee() {
     let network = bitcoin::Network::Bitcoin; // This is actually hard-coded
     let secp = Secp256k1::new();
     let xprivs = vec![
-        ExtendedPrivKey::new_master(network, &[0]).unwrap(),
-        ExtendedPrivKey::new_master(network, &[1]).unwrap(),
+        bip32::Xpriv::new_master(network, &[0]).unwrap(),
+        bip32::Xpriv::new_master(network, &[1]).unwrap(),
     ];
     let xpubs = vec![
-        ExtendedPubKey::from_priv(&secp, &xprivs[0]),
-        ExtendedPubKey::from_priv(&secp, &xprivs[1]),
+        bip32::Xpub::from_priv(&secp, &xprivs[0]),
+        bip32::Xpub::from_priv(&secp, &xprivs[1]),
     ];
     let consensus_keys = vec![[0; 32], [1; 32]];
 
@@ -3786,8 +3885,8 @@ async fn test_deposit_with_token_fee() {
     )
     .unwrap();
 
-    set_signatory_key(&mut app, validator_1.clone(), Xpub::new(xpubs[0])).unwrap();
-    set_signatory_key(&mut app, validator_2.clone(), Xpub::new(xpubs[1])).unwrap();
+    set_signatory_key(&mut app, validator_1.clone(), &xprivs[0]).unwrap();
+    set_signatory_key(&mut app, validator_2.clone(), &xprivs[1]).unwrap();
     increase_block(&mut app, Binary::from([0; 32])).unwrap(); // should increase number of hash to be unique
 
     // Fetching checkpoint and creating deposit address
@@ -3806,8 +3905,8 @@ async fn test_deposit_with_token_fee() {
     let script = sigset
         .output_script(&dest.commitment_bytes().unwrap(), threshold)
         .unwrap();
-    let deposit_addr = bitcoin::Address::from_script(&script, bitcoin::Network::Regtest).unwrap();
-    let deposit_amount = bitcoin::Amount::from_btc(1.2).unwrap();
+    let deposit_addr = regtest_address(&script);
+    let deposit_amount = rpc_bitcoin::Amount::from_btc(1.2).unwrap();
 
     let btc_txid = wallet
         .send_to_address(
@@ -3821,14 +3920,16 @@ async fn test_deposit_with_token_fee() {
             None,
         )
         .unwrap();
-    let btc_tx = btc_client
-        .get_raw_transaction(&btc_txid, None)
-        .await
-        .unwrap();
+    let btc_tx: Transaction = from_rpc(
+        &btc_client
+            .get_raw_transaction(&btc_txid, None)
+            .await
+            .unwrap(),
+    );
     let vout = btc_tx
         .output
         .iter()
-        .position(|o| o.value == deposit_amount.to_sat())
+        .position(|o| o.value.to_sat() == deposit_amount.to_sat())
         .unwrap();
 
     // mine one block to get proof
@@ -3851,10 +3952,13 @@ async fn test_deposit_with_token_fee() {
 
     // this proof is for current depositing
     let tx_proof = btc_client
-        .get_tx_out_proof(&[btc_tx.txid()], Some(&headers[0].block_hash()))
+        .get_tx_out_proof(
+            &[to_rpc(&btc_tx.txid())],
+            Some(&to_rpc(&headers[0].block_hash())),
+        )
         .await
         .unwrap();
-    let proof = bitcoin::util::merkleblock::MerkleBlock::consensus_decode(&mut tx_proof.as_slice())
+    let proof = MerkleBlock::consensus_decode(&mut tx_proof.as_slice())
         .unwrap()
         .txn;
 
@@ -4123,16 +4227,22 @@ async fn test_withdraw_with_dynamic_fee() {
         )
     };
 
-    let set_signatory_key = |app: &mut MockApp, sender: Addr, xpub: Xpub| -> MockResult<_> {
-        app.execute(
-            sender,
-            bitcoin_bridge_addr.clone(),
-            &msg::ExecuteMsg::SetSignatoryKey {
-                xpub: WrappedBinary(xpub),
-            },
-            &[],
-        )
-    };
+    let set_signatory_key =
+        |app: &mut MockApp, sender: Addr, xpriv: &bip32::Xpriv| -> MockResult<_> {
+            let secp = Secp256k1::signing_only();
+            let xpub = Xpub::new(bip32::Xpub::from_priv(&secp, xpriv));
+            let challenge = signatory_key_challenge(&bitcoin_bridge_addr, &sender, &xpub);
+            let sig = sign(&secp, xpriv, &[(challenge, 0)]).unwrap().remove(0);
+            app.execute(
+                sender,
+                bitcoin_bridge_addr.clone(),
+                &msg::ExecuteMsg::SetSignatoryKey {
+                    xpub: WrappedBinary(xpub),
+                    sig,
+                },
+                &[],
+            )
+        };
 
     let increase_block = |app: &mut MockApp, hash: Binary| -> MockResult<_> {
         app.sudo(
@@ -4143,8 +4253,8 @@ async fn test_withdraw_with_dynamic_fee() {
 
     let sign_cp = |app: &mut MockApp,
                    sender: Addr,
-                   xpriv: &ExtendedPrivKey,
-                   xpub: ExtendedPubKey,
+                   xpriv: &bip32::Xpriv,
+                   xpub: bip32::Xpub,
                    cp_index: u32,
                    btc_height: u32|
      -> MockResult<_> {
@@ -4167,6 +4277,7 @@ async fn test_withdraw_with_dynamic_fee() {
                 sigs,
                 checkpoint_index: cp_index,
                 btc_height,
+                offset: None,
             },
             &[],
         )
@@ -4178,12 +4289,20 @@ async fn test_withdraw_with_dynamic_fee() {
                                coin: Coin,
                                fee: Option<u64>|
      -> MockResult<_> {
+        let btc_address = script_to_address(
+            bitcoin::Script::from_bytes(btc_address.script_pubkey().as_bytes()),
+            NETWORK,
+        )
+        .unwrap();
         app.execute(
             sender,
             bitcoin_bridge_addr.clone(),
             &msg::ExecuteMsg::WithdrawToBitcoin {
                 btc_address: btc_address.to_string(),
                 fee,
+                on_behalf_of: None,
+                amount: None,
+                priority: Default::default(),
             },
             &[coin],
         )
@@ -4207,6 +4326,7 @@ async fn test_withdraw_with_dynamic_fee() {
             light_client_contract: None,
             owner: None,
             osor_entry_point_contract: None,
+            relayer_fee_options: None,
         },
         &[],
     )
@@ -4246,12 +4366,12 @@ async fn test_withdraw_with_dynamic_fee() {
     let network = bitcoin::Network::Bitcoin; // This is actually hard-coded
     let secp = Secp256k1::new();
     let xprivs = vec![
-        ExtendedPrivKey::new_master(network, &[0]).unwrap(),
-        ExtendedPrivKey::new_master(network, &[1]).unwrap(),
+        bip32::Xpriv::new_master(network, &[0]).unwrap(),
+        bip32::Xpriv::new_master(network, &[1]).unwrap(),
     ];
     let xpubs = vec![
-        ExtendedPubKey::from_priv(&secp, &xprivs[0]),
-        ExtendedPubKey::from_priv(&secp, &xprivs[1]),
+        bip32::Xpub::from_priv(&secp, &xprivs[0]),
+        bip32::Xpub::from_priv(&secp, &xprivs[1]),
     ];
     let consensus_keys = vec![[0; 32], [1; 32]];
 
@@ -4266,8 +4386,8 @@ async fn test_withdraw_with_dynamic_fee() {
     )
     .unwrap();
 
-    set_signatory_key(&mut app, validator_1.clone(), Xpub::new(xpubs[0])).unwrap();
-    set_signatory_key(&mut app, validator_2.clone(), Xpub::new(xpubs[1])).unwrap();
+    set_signatory_key(&mut app, validator_1.clone(), &xprivs[0]).unwrap();
+    set_signatory_key(&mut app, validator_2.clone(), &xprivs[1]).unwrap();
     increase_block(&mut app, Binary::from([0; 32])).unwrap(); // should increase number of hash to be unique
 
     // Fetching checkpoint and creating deposit address
@@ -4286,8 +4406,8 @@ async fn test_withdraw_with_dynamic_fee() {
     let script = sigset
         .output_script(&dest.commitment_bytes().unwrap(), threshold)
         .unwrap();
-    let deposit_addr = bitcoin::Address::from_script(&script, bitcoin::Network::Regtest).unwrap();
-    let deposit_amount = bitcoin::Amount::from_btc(1.2).unwrap();
+    let deposit_addr = regtest_address(&script);
+    let deposit_amount = rpc_bitcoin::Amount::from_btc(1.2).unwrap();
 
     let btc_txid = wallet
         .send_to_address(
@@ -4301,14 +4421,16 @@ async fn test_withdraw_with_dynamic_fee() {
             None,
         )
         .unwrap();
-    let btc_tx = btc_client
-        .get_raw_transaction(&btc_txid, None)
-        .await
-        .unwrap();
+    let btc_tx: Transaction = from_rpc(
+        &btc_client
+            .get_raw_transaction(&btc_txid, None)
+            .await
+            .unwrap(),
+    );
     let vout = btc_tx
         .output
         .iter()
-        .position(|o| o.value == deposit_amount.to_sat())
+        .position(|o| o.value.to_sat() == deposit_amount.to_sat())
         .unwrap();
 
     // mine one block to get proof
@@ -4331,10 +4453,13 @@ async fn test_withdraw_with_dynamic_fee() {
 
     // this proof is for current depositing
     let tx_proof = btc_client
-        .get_tx_out_proof(&[btc_tx.txid()], Some(&headers[0].block_hash()))
+        .get_tx_out_proof(
+            &[to_rpc(&btc_tx.txid())],
+            Some(&to_rpc(&headers[0].block_hash())),
+        )
         .await
         .unwrap();
-    let proof = bitcoin::util::merkleblock::MerkleBlock::consensus_decode(&mut tx_proof.as_slice())
+    let proof = MerkleBlock::consensus_decode(&mut tx_proof.as_slice())
         .unwrap()
         .txn;
 
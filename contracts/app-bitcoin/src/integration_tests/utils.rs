@@ -1,6 +1,7 @@
 use std::path::PathBuf;
 
-use bitcoin::{BlockHash, BlockHeader};
+use bitcoin::block::Header as BlockHeader;
+use bitcoincore_rpc_async::bitcoin as rpc_bitcoin;
 use bitcoincore_rpc_async::{Auth, Client as BitcoinRpcClient, RpcApi};
 use common_bitcoin::adapter::Adapter;
 use light_client_bitcoin::header::WrappedHeader;
@@ -24,6 +25,31 @@ where
     }
 }
 
+/// Converts a value returned by the RPC clients, which build against an older
+/// `bitcoin`, to the type used by the contracts through its consensus encoding.
+pub fn from_rpc<T, U>(value: &T) -> U
+where
+    T: rpc_bitcoin::consensus::Encodable,
+    U: bitcoin::consensus::Decodable,
+{
+    bitcoin::consensus::deserialize(&rpc_bitcoin::consensus::serialize(value)).unwrap()
+}
+
+/// Converts a value used by the contracts to the type taken by the RPC clients.
+pub fn to_rpc<T, U>(value: &T) -> U
+where
+    T: bitcoin::consensus::Encodable,
+    U: rpc_bitcoin::consensus::Decodable,
+{
+    rpc_bitcoin::consensus::deserialize(&bitcoin::consensus::serialize(value)).unwrap()
+}
+
+/// The regtest address of the RPC wallet paying to `script`.
+pub fn regtest_address(script: &bitcoin::Script) -> rpc_bitcoin::Address {
+    let script = rpc_bitcoin::Script::from(script.to_bytes());
+    rpc_bitcoin::Address::from_script(&script, rpc_bitcoin::Network::Regtest).unwrap()
+}
+
 pub async fn test_bitcoin_client(rpc_url: String, cookie_file: PathBuf) -> BitcoinRpcClient {
     BitcoinRpcClient::new(rpc_url, Auth::CookieFile(cookie_file))
         .await
@@ -32,7 +58,7 @@ pub async fn test_bitcoin_client(rpc_url: String, cookie_file: PathBuf) -> Bitco
 
 pub async fn get_wrapped_header_from_block_hash(
     btc_client: &BitcoinRpcClient,
-    block_hash: &BlockHash,
+    block_hash: &rpc_bitcoin::BlockHash,
 ) -> WrappedHeader {
     let header_info = btc_client.get_block_header_info(block_hash).await.unwrap();
     let height = header_info.height as u32;
@@ -40,7 +66,7 @@ pub async fn get_wrapped_header_from_block_hash(
         .get_block_header(&header_info.hash)
         .await
         .unwrap();
-    WrappedHeader::new(Adapter::new(header), height)
+    WrappedHeader::new(Adapter::new(from_rpc(&header)), height)
 }
 
 #[derive(Debug)]
@@ -61,6 +87,6 @@ pub async fn populate_bitcoin_block(client: &BitcoinRpcClient) -> BitcoinBlockDa
 
     BitcoinBlockData {
         height: tip_height as u32,
-        block_header: tip_header,
+        block_header: from_rpc(&tip_header),
     }
 }
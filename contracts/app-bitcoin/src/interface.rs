@@ -12,12 +12,19 @@ use sha2::{Digest, Sha256};
 
 use crate::app::ConsensusKey;
 use crate::app::NETWORK;
+use crate::checkpoint::CheckpointStatus;
 use crate::constants::{
-    MAX_CHECKPOINT_AGE, MAX_CHECKPOINT_INTERVAL, MAX_DEPOSIT_AGE, MAX_FEE_RATE, MIN_DEPOSIT_AMOUNT,
-    MIN_FEE_RATE, MIN_WITHDRAWAL_AMOUNT, SIGSET_THRESHOLD, TRANSFER_FEE, USER_FEE_FACTOR,
+    BTC_BLOCK_INTERVAL, DEST_COMMITMENT_VERSION, LEGACY_DEST_COMMITMENT_VERSION,
+    MAX_CHECKPOINT_AGE, MAX_CHECKPOINT_INTERVAL, MAX_DEPOSIT_AGE, MAX_END_BLOCK_ITEMS,
+    MAX_FEE_RATE, MAX_RECOVERY_SCRIPT_LEN, MIN_DEPOSIT_AMOUNT, MIN_FEE_RATE, MIN_WITHDRAWAL_AMOUNT,
+    SIGSET_THRESHOLD, TRANSFER_FEE, USER_FEE_FACTOR,
 };
 use crate::msg::OsorMsg::UniversalSwap;
-use common_bitcoin::error::ContractResult;
+use crate::signatory::ScriptTemplate;
+use common_bitcoin::error::{ContractError, ContractResult};
+use common_bitcoin::xpub::Xpub;
+use ibc_proto::cosmos::base::v1beta1::Coin as ProtoCoin;
+use ibc_proto::ibc::applications::transfer::v1::MsgTransfer;
 use prost::Message;
 
 #[cw_serde]
@@ -30,10 +37,50 @@ pub struct IbcDest {
     pub memo: String,
 }
 
+/// An ICS20 transfer destination whose memo is forwarded as is, e.g. for
+/// packet-forward-middleware to route the tokens through further hops.
+#[cw_serde]
+pub struct IbcMemoDest {
+    pub channel: String,
+    pub receiver: String,
+    pub memo: String,
+    /// Local address credited if the transfer can not be sent.
+    pub refund_address: String,
+    pub timeout_timestamp: u64,
+}
+
+impl IbcMemoDest {
+    /// The memo of the transfer packet, asking the ibc hooks middleware to
+    /// report the packet's ack or timeout to `callback` with
+    /// `IbcLifecycleComplete`. Memos which are not json objects or already
+    /// name a callback are sent as is.
+    pub fn packet_memo(&self, callback: &str) -> String {
+        let memo = self.memo.trim();
+        let callback = format!("\"ibc_callback\":\"{}\"", callback);
+        if memo.is_empty() {
+            return format!("{{{}}}", callback);
+        }
+        let Some(fields) = memo
+            .strip_prefix('{')
+            .and_then(|memo| memo.strip_suffix('}'))
+        else {
+            return self.memo.clone();
+        };
+        if fields.contains("\"ibc_callback\"") {
+            return self.memo.clone();
+        }
+        if fields.trim().is_empty() {
+            return format!("{{{}}}", callback);
+        }
+        format!("{{{},{}}}", callback, fields)
+    }
+}
+
 #[cw_serde]
 pub enum Dest {
     Address(Addr),
     Ibc(IbcDest),
+    IbcMemo(IbcMemoDest),
 }
 
 impl Dest {
@@ -41,6 +88,7 @@ impl Dest {
         match self {
             Self::Address(addr) => addr.to_string(),
             Self::Ibc(dest) => dest.receiver.to_string(),
+            Self::IbcMemo(dest) => dest.receiver.to_string(),
         }
     }
 
@@ -48,6 +96,7 @@ impl Dest {
         match self {
             Self::Address(addr) => addr.to_string(),
             Self::Ibc(dest) => dest.sender.to_string(),
+            Self::IbcMemo(dest) => dest.refund_address.to_string(),
         }
     }
 
@@ -55,11 +104,42 @@ impl Dest {
         let bytes = match self {
             Self::Address(addr) => addr.as_bytes().into(),
             Self::Ibc(dest) => Sha256::digest(to_json_vec(dest)?).to_vec(),
+            // Hash the tagged enum so the commitment can't collide with an
+            // `Ibc` destination.
+            Self::IbcMemo(_) => Sha256::digest(to_json_vec(self)?).to_vec(),
         };
 
         Ok(bytes)
     }
 
+    /// The commitment bytes of the given encoding version. Versioned
+    /// commitments prefix the legacy bytes with the version, which can't
+    /// collide with legacy ones: addresses are ASCII and hashes are one byte
+    /// shorter.
+    pub fn versioned_commitment_bytes(&self, version: u8) -> ContractResult<Vec<u8>> {
+        match version {
+            LEGACY_DEST_COMMITMENT_VERSION => self.commitment_bytes(),
+            DEST_COMMITMENT_VERSION => {
+                let mut bytes = vec![version];
+                bytes.extend(self.commitment_bytes()?);
+                Ok(bytes)
+            }
+            _ => Err(ContractError::App(format!(
+                "Unknown destination commitment version: {}",
+                version
+            ))),
+        }
+    }
+
+    /// Whether minting to this destination ends with an ICS20 transfer which
+    /// should be refunded to the source address if it fails.
+    pub fn is_ibc_memo_transfer(&self, env: &Env) -> bool {
+        match self {
+            Self::IbcMemo(dest) => dest.timeout_timestamp > env.block.time.nanos(),
+            _ => false,
+        }
+    }
+
     pub fn build_cosmos_msg(
         &self,
         env: &Env,
@@ -154,10 +234,737 @@ impl Dest {
                     .into(),
                 );
             }
+            Self::IbcMemo(dest) => {
+                let mint_to_address = if self.is_ibc_memo_transfer(env) {
+                    bitcoin_bridge_addr.to_string()
+                } else {
+                    dest.refund_address.to_string()
+                };
+                msgs.push(
+                    wasm_execute(
+                        token_factory_addr,
+                        &tokenfactory::msg::ExecuteMsg::MintTokens {
+                            denom: coin.denom.to_owned(),
+                            amount: coin.amount,
+                            mint_to_address,
+                        },
+                        vec![],
+                    )
+                    .unwrap()
+                    .into(),
+                );
+                if !self.is_ibc_memo_transfer(env) {
+                    return;
+                }
+
+                let transfer = MsgTransfer {
+                    source_port: "transfer".to_string(),
+                    source_channel: dest.channel.to_string(),
+                    token: Some(ProtoCoin {
+                        denom: coin.denom,
+                        amount: coin.amount.to_string(),
+                    }),
+                    sender: bitcoin_bridge_addr.to_string(),
+                    receiver: dest.receiver.to_string(),
+                    timeout_height: None,
+                    timeout_timestamp: dest.timeout_timestamp,
+                    memo: dest.packet_memo(bitcoin_bridge_addr.as_str()),
+                };
+                msgs.push(CosmosMsg::Stargate {
+                    type_url: "/ibc.applications.transfer.v1.MsgTransfer".to_string(),
+                    value: transfer.encode_to_vec().into(),
+                });
+            }
         };
     }
 }
 
+/// An nBTC transfer waiting for its checkpoint to be signed before it is
+/// credited to `dest`.
+#[cw_serde]
+#[serde(from = "StoredPendingTransfer")]
+pub struct PendingTransfer {
+    pub dest: Dest,
+    pub coin: Coin,
+    /// Identifies who sent the transfer, passed to contract destinations in
+    /// their `BtcBridgeReceiveMsg`. For deposits this is the consensus
+    /// encoding of the deposit outpoint (the txid followed by the little
+    /// endian output index), empty for transfers queued before it was
+    /// recorded.
+    #[serde(default)]
+    pub sender_commitment: Binary,
+}
+
+impl PendingTransfer {
+    /// A transfer crediting the deposit spending `outpoint`.
+    pub fn deposit(dest: Dest, coin: Coin, outpoint: &bitcoin::OutPoint) -> Self {
+        PendingTransfer {
+            dest,
+            coin,
+            sender_commitment: Binary::from(bitcoin::consensus::encode::serialize(outpoint)),
+        }
+    }
+}
+
+/// The stored forms of a [`PendingTransfer`], transfers queued before the
+/// sender was recorded are stored as a `(Dest, Coin)` pair.
+#[derive(Deserialize)]
+#[serde(untagged, crate = "cosmwasm_schema::serde")]
+enum StoredPendingTransfer {
+    Transfer {
+        dest: Dest,
+        coin: Coin,
+        #[serde(default)]
+        sender_commitment: Binary,
+    },
+    Legacy(Dest, Coin),
+}
+
+impl From<StoredPendingTransfer> for PendingTransfer {
+    fn from(stored: StoredPendingTransfer) -> Self {
+        match stored {
+            StoredPendingTransfer::Transfer {
+                dest,
+                coin,
+                sender_commitment,
+            } => PendingTransfer {
+                dest,
+                coin,
+                sender_commitment,
+            },
+            StoredPendingTransfer::Legacy(dest, coin) => PendingTransfer {
+                dest,
+                coin,
+                sender_commitment: Binary::default(),
+            },
+        }
+    }
+}
+
+/// A record of nBTC credited by the bridge for a relayed deposit output.
+#[cw_serde]
+pub struct MintRecord {
+    pub dest: Dest,
+    /// The nBTC amount credited to `dest`, after deposit fees.
+    pub amount: Uint128,
+    /// The index of the checkpoint which spends the deposit.
+    pub checkpoint_index: u32,
+    /// The Bitcoin height the deposit was confirmed at.
+    pub btc_height: u32,
+    /// The hash of the block the deposit was confirmed in, unset for
+    /// deposits minted before it was recorded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub block_hash: Option<String>,
+}
+
+/// Where an input of a checkpoint transaction comes from.
+#[cw_serde]
+pub enum InputSource {
+    /// The reserve output of the previous checkpoint
+    Reserve,
+    /// A reserve output attached with `RegisterReserveUtxo`
+    RegisteredReserve,
+    /// A deposit relayed while the checkpoint was building
+    Deposit { dest: Dest, btc_height: u32 },
+    /// A deposit relayed to an earlier checkpoint which had no room left for
+    /// it, carried over as an excess input
+    ExcessRollover {
+        dest: Dest,
+        btc_height: u32,
+        from_checkpoint: u32,
+    },
+    /// A deposit without a mint record
+    Unknown,
+}
+
+/// An input of a checkpoint transaction and its provenance.
+#[cw_serde]
+pub struct CheckpointInput {
+    pub txid: String,
+    pub vout: u32,
+    /// The value of the spent output, in satoshis.
+    pub amount: u64,
+    pub sigset_index: u32,
+    /// The destination commitment in the input's redeem script.
+    pub commitment: Binary,
+    pub source: InputSource,
+}
+
+/// A minted deposit whose transaction was reorged out of the Bitcoin chain.
+#[cw_serde]
+pub struct ReorgedDeposit {
+    pub txid: String,
+    pub vout: u32,
+    pub dest: Dest,
+    /// The nBTC minted for the deposit.
+    pub amount: Uint128,
+    /// The part of `amount` burned from the receiver's balance.
+    pub burned: Uint128,
+    /// The part of `amount` which could not be burned and was added to the
+    /// bad debt.
+    pub deficit: Uint128,
+    pub reconciled_at: u64,
+}
+
+/// nBTC burned with `Burn`, which reduces the supply without paying out any
+/// BTC.
+#[cw_serde]
+pub struct BurnRecord {
+    pub burner: Addr,
+    pub amount: Uint128,
+    /// The part of `amount` which repaid the bad debt.
+    pub bad_debt_repaid: Uint128,
+    pub burned_at: u64,
+}
+
+/// A deposit relayed after `max_deposit_age`, waiting to be refunded to its
+/// depositor.
+#[cw_serde]
+pub struct ExpiredDeposit {
+    pub txid: String,
+    pub vout: u32,
+    pub dest: Dest,
+    /// The value of the deposit output, in satoshis.
+    pub value: u64,
+    /// The index of the signatory set the deposit was sent to.
+    pub sigset_index: u32,
+    /// The Bitcoin height the deposit was confirmed at.
+    #[serde(default)]
+    pub btc_height: u32,
+    /// The encoding version of the destination commitment in the deposit
+    /// script.
+    #[serde(default)]
+    pub commitment_version: u8,
+}
+
+/// The deposits minted to a destination, in satoshis.
+#[cw_serde]
+pub struct DestStats {
+    pub dest: Dest,
+    pub total_deposited: u64,
+    pub deposits: u64,
+    pub last_deposit_at: u64,
+    /// Start of the current `DEST_STATS_WINDOW`, which begins with the first
+    /// deposit after the previous one ended
+    pub window_started_at: u64,
+    /// The value deposited since `window_started_at`
+    pub window_volume: u64,
+}
+
+/// An unconfirmed deposit seen by a relayer, shown until it is relayed or
+/// `expires_at`. Nothing is minted for it.
+#[cw_serde]
+pub struct PendingDeposit {
+    pub txid: String,
+    pub vout: u32,
+    pub dest: Dest,
+    /// The value of the deposit output, in satoshis.
+    pub amount: u64,
+    pub relayer: Addr,
+    pub reported_at: u64,
+    pub expires_at: u64,
+}
+
+/// An ibc memo transfer sent by the bridge, waiting for its packet to be
+/// acknowledged or to time out.
+#[cw_serde]
+pub struct IbcTransfer {
+    pub channel: String,
+    pub sequence: u64,
+    /// Local address credited if the packet fails.
+    pub refund_address: String,
+    pub coin: Coin,
+    pub sent_at: u64,
+}
+
+/// nBTC which could not be minted to its destination when its checkpoint
+/// completed, waiting to be pulled by the recipient with `ClaimMint`.
+#[cw_serde]
+pub struct MintClaim {
+    pub id: u64,
+    pub recipient: Addr,
+    pub coin: Coin,
+    /// The bridge time after which the claim is minted to
+    /// `mint_claim_recovery_address` instead, if set.
+    pub expires_at: Option<u64>,
+}
+
+/// Proof that a hash committed with `CommitData` is included in the
+/// `OP_RETURN` output of a checkpoint transaction.
+#[cw_serde]
+pub struct AppDataProof {
+    pub checkpoint_index: u32,
+    /// The position of the hash among the Merkle leaves, the first leaf being
+    /// the SHA-256 of `internal_commitment`.
+    pub leaf_index: u32,
+    pub branch: Vec<Binary>,
+    /// The Merkle root, as found in the checkpoint's `OP_RETURN` output.
+    pub root: Binary,
+    pub internal_commitment: Binary,
+}
+
+/// The transaction a `SigningDuty` belongs to.
+#[cw_serde]
+pub enum SigningDutySource {
+    Checkpoint {
+        index: u32,
+        batch_index: u32,
+        tx_index: u32,
+    },
+    Recovery {
+        tx_index: u32,
+    },
+}
+
+/// The signatures a signer still owes for one transaction.
+#[cw_serde]
+pub struct SigningDuty {
+    pub source: SigningDutySource,
+    /// Seconds since the checkpoint's signatory set was created, `None` for
+    /// recovery transactions
+    pub age: Option<u64>,
+    /// The most voting power any input of the transaction still needs to
+    /// reach its threshold
+    pub remaining_power: u64,
+    /// The sighashes to sign, with the sigset index to derive the signing key
+    /// at
+    pub sighashes: Vec<(Binary, u32)>,
+}
+
+/// An overview of a checkpoint for reconciliation.
+#[cw_serde]
+pub struct CheckpointSummary {
+    pub index: u32,
+    pub status: CheckpointStatus,
+    pub fee_rate: u64,
+    pub fees_collected: u64,
+    /// The part of the miner fee paid from the fee pool
+    #[serde(default)]
+    pub fee_pool_top_up: u64,
+    /// The part of the withdrawal fees left over after paying their outputs
+    #[serde(default)]
+    pub withdrawal_fee_surplus: u64,
+    pub signed_at_btc_height: Option<u32>,
+    /// The checkpoint transaction id, set once the checkpoint stopped building
+    pub txid: Option<String>,
+    pub memo: Option<String>,
+}
+
+/// How the miner fee of the `Building` checkpoint is paid for, in satoshis.
+#[cw_serde]
+pub struct FeeCoverage {
+    /// The estimated miner fee of the checkpoint
+    pub miner_fee: u64,
+    /// The fees paid by users into the checkpoint
+    pub fees_collected: u64,
+    /// What the fee pool holds beyond `fees_collected`
+    pub fee_pool_surplus: u64,
+    /// The part of the miner fee which would be paid from the surplus
+    pub top_up: u64,
+    /// The share of the miner fee covered by both sources, in basis points,
+    /// may exceed 10000
+    pub coverage_bps: u64,
+}
+
+impl FeeCoverage {
+    /// Whether the checkpoint can pay its miner fee, otherwise it is delayed.
+    pub fn is_covered(&self) -> bool {
+        self.coverage_bps >= 10_000
+    }
+}
+
+/// The inputs of the adaptive scheduling of the `Building` checkpoint.
+#[cw_serde]
+pub struct CheckpointSchedule {
+    /// Whether `adaptive_scheduling` is enabled
+    pub adaptive: bool,
+    /// Seconds since the checkpoint was created
+    pub elapsed: u64,
+    pub min_checkpoint_interval: u64,
+    pub max_checkpoint_interval: u64,
+    /// The value of the checkpoint's deposits and withdrawals, in satoshis
+    pub pending_value: u64,
+    pub pending_value_threshold: u64,
+    pub fee_rate: u64,
+    /// The fee rate at `adaptive_fee_rate_percentile` of the recorded fee
+    /// rates, `None` if disabled or no fee rate was recorded
+    pub favorable_fee_rate: Option<u64>,
+    pub reached_capacity: bool,
+    /// Whether a `Fast` withdrawal was added to the checkpoint
+    pub has_fast_withdrawal: bool,
+    /// Whether any trigger lets the checkpoint advance before
+    /// `max_checkpoint_interval` in adaptive scheduling
+    pub triggered: bool,
+}
+
+/// A signatory's progress signing the inputs of a checkpoint which are ready
+/// to be signed.
+#[cw_serde]
+pub struct SigningProgress {
+    /// The inputs the signatory has signed, which is the offset of the next
+    /// chunk when signing in order
+    pub signed: u32,
+    /// The inputs the signatory is part of
+    pub total: u32,
+}
+
+/// The recovery transaction inputs spending outputs of one signatory set,
+/// which are signed together with `SubmitRecoveryGroupSignature`.
+#[cw_serde]
+pub struct RecoveryGroupStatus {
+    pub sigset_index: u32,
+    /// The positions in the recovery queue of the transactions with inputs
+    /// in the group
+    pub tx_indexes: Vec<u32>,
+    pub inputs: u32,
+    /// The inputs which reached their signing threshold
+    pub signed_inputs: u32,
+    /// The signatures the queried signatory still owes to the group
+    pub owed: Option<u32>,
+}
+
+/// A change of the checkpoint or recovery state relayers act on.
+#[cw_serde]
+pub enum Change {
+    CheckpointCreated {
+        index: u32,
+    },
+    CheckpointSigning {
+        index: u32,
+    },
+    /// The checkpoint transaction is fully signed and can be broadcast
+    CheckpointSigned {
+        index: u32,
+        txid: String,
+    },
+    CheckpointConfirmed {
+        index: u32,
+    },
+    RecoveryTxCreated {
+        txid: String,
+    },
+    RecoveryTxSigned {
+        txid: String,
+    },
+}
+
+#[cw_serde]
+pub struct ChangeRecord {
+    /// The block height the change was made at
+    pub height: u64,
+    pub change: Change,
+}
+
+/// Why the fee rate of the building checkpoint changed.
+#[cw_serde]
+pub enum FeeRateChangeCause {
+    /// Adjusted when pushing a checkpoint, up if signed checkpoints were not
+    /// confirmed within `target_checkpoint_inclusion` blocks, down if all
+    /// were confirmed
+    ConfirmationLag,
+    /// Set by the owner with `SetFeeRate`
+    Manual,
+}
+
+#[cw_serde]
+pub struct FeeRateChange {
+    /// The index of the checkpoint the new fee rate applies to
+    pub checkpoint_index: u32,
+    pub old_fee_rate: u64,
+    pub new_fee_rate: u64,
+    pub cause: FeeRateChangeCause,
+    pub timestamp: u64,
+}
+
+/// nBTC a spender may withdraw to Bitcoin on behalf of the granter
+#[cw_serde]
+pub struct WithdrawalAllowance {
+    pub amount: Uint128,
+    /// The bridge time after which the allowance can no longer be used
+    pub expiry: Option<u64>,
+}
+
+impl WithdrawalAllowance {
+    pub fn is_expired(&self, now: u64) -> bool {
+        self.expiry.map_or(false, |expiry| now >= expiry)
+    }
+}
+
+/// An owner action recorded in the admin log
+#[cw_serde]
+pub struct AdminLogEntry {
+    pub id: u64,
+    /// The block height the action was executed at
+    pub height: u64,
+    pub sender: Addr,
+    pub action: String,
+    /// SHA-256 of the JSON encoded execute msg
+    pub payload_hash: Binary,
+}
+
+/// The progress of an nBTC balance snapshot. Snapshots are built a page of
+/// holders per block, so balances moving during a snapshot may be counted at
+/// different times.
+#[cw_serde]
+pub struct BalanceSnapshot {
+    pub started_at: u64,
+    /// Set once every holder has been recorded
+    pub completed_at: Option<u64>,
+    /// Whether the entries of the previous snapshot are still being removed
+    pub clearing: bool,
+    /// The pagination key of the next page of holders
+    pub next_key: Option<Binary>,
+    pub holders: u64,
+    pub total: Uint128,
+    /// The number of entries in the top balances index
+    pub top_len: u32,
+    /// The number of withdrawal outputs of checkpoints not yet fully signed,
+    /// recorded once the balances are
+    #[serde(default)]
+    pub withdrawals: u32,
+    /// The total value of those withdrawal outputs, in satoshis
+    #[serde(default)]
+    pub withdrawals_total: u64,
+    /// The level and position of the next nodes of the liabilities tree to
+    /// hash, while the tree is being built
+    #[serde(default)]
+    pub tree_cursor: Option<(u8, u32)>,
+    /// The Merkle root over the balances of every holder, set once the
+    /// snapshot completes with at least one holder
+    #[serde(default)]
+    pub liabilities_root: Option<Binary>,
+}
+
+/// Proves the balance of a holder is included in the liabilities root of the
+/// balance snapshot. Leaves are ordered as holders were recorded.
+#[cw_serde]
+pub struct LiabilitiesProof {
+    pub address: String,
+    pub balance: Uint128,
+    pub leaf_index: u32,
+    /// The sibling hashes from the leaf up to the root
+    pub branch: Vec<Binary>,
+    pub root: Binary,
+    pub completed_at: Option<u64>,
+}
+
+/// A withdrawal output of a checkpoint which was not fully signed when the
+/// balance snapshot completed. Its nBTC is already burned, so an emergency
+/// disbursal pays it in place of the checkpoint.
+#[cw_serde]
+pub struct SnapshotWithdrawal {
+    pub checkpoint_index: u32,
+    pub script_pubkey: Binary,
+    /// The value of the output, in satoshis
+    pub value: u64,
+}
+
+/// An output paying a holder of the completed balance snapshot their balance
+/// in an emergency disbursal.
+#[cw_serde]
+pub struct DisbursalOutput {
+    pub address: String,
+    /// The recovery script set by the holder
+    pub script_pubkey: Binary,
+    /// The snapshot balance of the holder, in satoshis
+    pub value: u64,
+}
+
+/// The state transitions the next `ClockEndBlock` would make.
+#[cw_serde]
+pub struct EndBlockReport {
+    /// Whether the building checkpoint would advance to signing
+    pub checkpoint_advanced: bool,
+    /// The index of the building checkpoint after the block
+    pub building_index: u32,
+    /// The signatory set index of the new building checkpoint, if one was
+    /// pushed
+    pub new_sigset_index: Option<u32>,
+    /// Whether the new signatory set has different signatories than the
+    /// previous one
+    pub sigset_rotated: bool,
+    /// The miner fee and fee rate of the checkpoint moving to signing, in
+    /// satoshis
+    pub checkpoint_fee: Option<u64>,
+    pub checkpoint_fee_rate: Option<u64>,
+    /// The change of the fee pool balance, in satoshis
+    pub fee_pool_change: i64,
+    /// Signers which would be removed, e.g. for being offline
+    pub removed_signers: Vec<String>,
+    /// Whether the bridge would be paused after the block
+    pub bridge_paused: bool,
+    /// The number of messages dispatched by the block, e.g. nBTC mints
+    pub messages: u32,
+    /// The types of the events emitted by the block
+    pub events: Vec<String>,
+}
+
+/// A migration of the reserve and account state to a successor bridge
+/// contract.
+#[cw_serde]
+pub struct BridgeMigration {
+    pub successor: Addr,
+    /// The reserve output script of the successor's building signatory set,
+    /// which the migration checkpoint pays the reserve to
+    pub reserve_script: Binary,
+    pub successor_sigset_index: u32,
+    pub started_at: u64,
+    /// The checkpoint paying the reserve to the successor, once built. No
+    /// checkpoints are built afterwards.
+    pub checkpoint_index: Option<u32>,
+    /// The raw key of the last account entry sent to the successor
+    pub transferred_until: Option<Binary>,
+    pub state_transferred: bool,
+}
+
+/// What a predecessor bridge hands over to its successor with the last page
+/// of account state.
+#[cw_serde]
+pub struct MigrationHandover {
+    /// The output of the migration checkpoint paying the reserve to the
+    /// successor, attached to the successor's building checkpoint.
+    pub reserve: SignedReserveOutput,
+    /// The nBTC denom, whose admin is passed to the successor, which mints
+    /// and burns it from then on.
+    pub btc_denom: String,
+    /// The denoms nBTC was minted as before, which can still be withdrawn.
+    pub legacy_btc_denoms: Vec<String>,
+}
+
+/// A reserve output created outside of the checkpoint chain, e.g. by an
+/// emergency recovery, which was attached to a checkpoint to be spent.
+#[cw_serde]
+pub struct ReserveUtxo {
+    pub txid: String,
+    pub vout: u32,
+    /// The value of the output, in satoshis.
+    pub amount: u64,
+    /// The index of the signatory set whose reserve script the output pays to.
+    pub sigset_index: u32,
+    /// The index of the checkpoint spending the output.
+    pub checkpoint_index: u32,
+}
+
+/// An output paying to the reserve script, created by a fully signed
+/// recovery transaction, waiting to be attached to a checkpoint once the
+/// transaction can be broadcast.
+#[cw_serde]
+pub struct SignedReserveOutput {
+    pub txid: String,
+    pub vout: u32,
+    /// The value of the output, in satoshis.
+    pub amount: u64,
+    /// The index of the signatory set whose reserve script the output pays to.
+    pub sigset_index: u32,
+    /// The lock time of the recovery transaction.
+    pub lock_time: u32,
+    /// Whether the output is credited to the fee pool once attached.
+    pub credit_fee_pool: bool,
+    /// The swept dust deposits credited to their destinations once the
+    /// output is attached, net of their share of the miner fee.
+    #[serde(default)]
+    pub credits: Vec<PendingTransfer>,
+}
+
+/// A submarine swap provider, which pays the Lightning invoices of users
+/// withdrawing to Lightning in exchange for an on-chain HTLC output.
+#[cw_serde]
+pub struct SwapProvider {
+    /// The compressed public key the provider claims HTLC outputs with.
+    pub claim_pubkey: Binary,
+    /// The number of Bitcoin blocks, counted from the creation of a swap,
+    /// after which its HTLC output can be refunded.
+    pub htlc_timeout_blocks: u32,
+    /// Seconds the provider has to accept a swap before the user can reclaim
+    /// the escrowed nBTC.
+    pub accept_timeout: u64,
+}
+
+/// A withdrawal queued by `WithdrawToBitcoin`, kept so third parties can
+/// sponsor its miner fee while its output is still in the building checkpoint.
+#[cw_serde]
+pub struct QueuedWithdrawal {
+    pub sender: Addr,
+    pub script_pubkey: Binary,
+    /// The current value of the withdrawal output, in satoshis.
+    pub value: u64,
+    /// The miner fee deducted from the withdrawn amount, in units.
+    pub fee: u64,
+    /// The part of `fee` paid back to the withdrawal by sponsors, in units.
+    pub sponsored: u64,
+    /// The checkpoint the withdrawal was added to, its record is pruned once
+    /// the checkpoint stops building.
+    pub checkpoint_index: u32,
+    /// The index of the withdrawal's output in the checkpoint transaction.
+    pub output_index: u32,
+    /// The index of the withdrawal's fee in the checkpoint's
+    /// `withdrawal_fees`.
+    pub fee_index: u32,
+}
+
+/// A withdrawal miner fee locked by `PrepareWithdrawal` until `expires_at`.
+#[cw_serde]
+pub struct WithdrawalQuote {
+    pub sender: Addr,
+    pub btc_address: String,
+    /// The nBTC to withdraw, which must be sent with `ExecuteWithdrawal`.
+    pub amount: Uint128,
+    pub fee_rate: u64,
+    /// The miner fee charged at `fee_rate`, in units.
+    pub miner_fee: u64,
+    pub expires_at: u64,
+    /// The withdrawal queued by executing the quote. A quote can only be
+    /// executed once, so integrators can retry with the same quote id.
+    pub withdrawal_id: Option<u64>,
+}
+
+/// How the screened scripts list, maintained by the compliance taggers,
+/// applies to withdrawal destinations.
+#[cw_serde]
+#[derive(Default)]
+pub enum ScriptScreening {
+    #[default]
+    Disabled,
+    /// Only listed scripts may be withdrawn to
+    Whitelist,
+    /// Listed scripts may not be withdrawn to
+    Blacklist,
+}
+
+/// A withdrawal to a script rejected by script screening. The sent nBTC is
+/// held by the contract until the withdrawal is approved or refunded.
+#[cw_serde]
+pub struct HeldWithdrawal {
+    pub sender: Addr,
+    pub btc_address: String,
+    pub funds: Vec<Coin>,
+    pub fee: Option<u64>,
+    pub held_at: u64,
+}
+
+/// A withdrawal to Lightning waiting to be accepted by its swap provider. The
+/// nBTC is held in escrow by the contract meanwhile.
+#[cw_serde]
+pub struct LightningSwap {
+    pub id: u64,
+    pub sender: Addr,
+    pub provider: Addr,
+    /// The SHA-256 payment hash of the invoice the provider must pay.
+    pub payment_hash: Binary,
+    /// The compressed public key able to refund the HTLC output after its
+    /// timeout.
+    pub refund_pubkey: Binary,
+    pub amount: Uint128,
+    /// The miner fee requested for the withdrawal, as in `WithdrawToBitcoin`.
+    pub fee: Option<u64>,
+    pub created_at: u64,
+    /// The Bitcoin height after which the HTLC output can be refunded, fixed
+    /// when the swap is created. 0 for swaps created before it was recorded,
+    /// which count the timeout from their acceptance.
+    #[serde(default)]
+    pub lock_height: u32,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(crate = "cosmwasm_schema::serde")]
 pub struct Validator {
@@ -165,6 +972,45 @@ pub struct Validator {
     pub power: u64,
 }
 
+/// An entry of the owner-managed validator registry.
+#[cw_serde]
+pub struct ValidatorInfo {
+    pub addr: String,
+    pub consensus_key: ConsensusKey,
+    pub voting_power: u64,
+}
+
+/// A signer removed from the validator registry for missing checkpoints.
+#[cw_serde]
+pub struct JailedSigner {
+    pub consensus_key: ConsensusKey,
+    /// The signatory key the signer had set, restored on reinstatement
+    pub xpub: Option<Xpub>,
+    pub jailed_at: u64,
+    /// Set by `RequestReinstatement`, the signer is re-added to the registry
+    /// before the next signatory set is built
+    pub reinstatement_requested: bool,
+}
+
+/// A compromised signatory key removed from future signatory sets.
+#[cw_serde]
+pub struct Tombstone {
+    /// The normalized encoding of the xpub
+    pub xpub: Binary,
+    /// The consensus key the xpub was registered with, if it was registered
+    pub consensus_key: Option<ConsensusKey>,
+    pub evidence: String,
+    pub tombstoned_at: u64,
+}
+
+/// A validator set which replaces the registry before the signatory set of
+/// checkpoint `epoch` is built.
+#[cw_serde]
+pub struct ScheduledValidatorSet {
+    pub epoch: u32,
+    pub validators: Vec<ValidatorInfo>,
+}
+
 #[cw_serde]
 pub struct BitcoinConfig {
     /// The minimum number of checkpoints that must be produced before
@@ -207,6 +1053,173 @@ pub struct BitcoinConfig {
     pub fee_pool_target_balance: u64,
 
     pub fee_pool_reward_split: (u64, u64),
+
+    /// The number of distinct registered relayers which must submit matching
+    /// confirmation proofs for a checkpoint before it is considered
+    /// confirmed. A value of 0 or 1 keeps the single-relayer behavior.
+    #[serde(default)]
+    pub checkpoint_confirmation_quorum: u32,
+    /// If enabled, deposits relayed after `max_deposit_age` are held until
+    /// their depositor claims them with `ClaimExpiredDeposit` and a refund
+    /// script, rather than being moved to the latest signatory set by a
+    /// recovery transaction.
+    #[serde(default)]
+    pub claimable_expired_deposits: bool,
+    /// The share of a claimed expired deposit kept by the bridge, as a
+    /// (numerator, denominator) pair. It is paid to the reserve script of the
+    /// building signatory set and credited to the fee pool once attached to a
+    /// checkpoint. A denominator of 0 keeps nothing.
+    #[serde(default)]
+    pub expired_deposit_fee_share: (u64, u64),
+    /// Accounting invariants are verified every this many blocks in
+    /// `ClockEndBlock`. 0 only verifies them on `VerifyInvariants`.
+    #[serde(default)]
+    pub invariant_check_interval: u64,
+    /// The maximum number of queued work items `ClockEndBlock` processes:
+    /// minted nBTC transfers, expired mint claims, and signed reserve outputs
+    /// and dust deposits added to a pushed checkpoint. The rest carries over
+    /// to the following blocks. 0 means no limit.
+    #[serde(default = "default_max_end_block_items")]
+    pub max_end_block_items: u32,
+    /// Mints which fail when pushed to their destination can be claimed for
+    /// this many seconds, after which they go to
+    /// `mint_claim_recovery_address`, which must then be set. 0 means claims
+    /// never expire.
+    #[serde(default)]
+    pub mint_claim_expiry: u64,
+    #[serde(default)]
+    pub mint_claim_recovery_address: Option<Addr>,
+    /// Deposits and withdrawals are paused while no new Bitcoin header has
+    /// been relayed to the light client for this many seconds, since
+    /// confirmation depths can't be evaluated. 0 disables the check.
+    #[serde(default)]
+    pub max_header_staleness: u64,
+    /// Fee surcharge, in basis points of the deposit amount, charged per
+    /// checkpoint a deposit's sigset lags behind the building checkpoint,
+    /// since old reserves must later be migrated forward. Capped at
+    /// `MAX_SIGSET_LAG_SURCHARGE_BPS`.
+    #[serde(default)]
+    pub sigset_lag_surcharge_bps: u64,
+    /// Discount, in basis points, on the miner fee of deposits made to the
+    /// building checkpoint's sigset.
+    #[serde(default)]
+    pub latest_sigset_discount_bps: u64,
+    /// The number of holders added to the nBTC balance snapshot per
+    /// `ClockEndBlock`. 0 disables snapshots.
+    #[serde(default)]
+    pub balance_snapshot_page_size: u32,
+    /// Seconds between the completion of a balance snapshot and the start of
+    /// the next one
+    #[serde(default)]
+    pub balance_snapshot_interval: u64,
+    /// The number of largest holders indexed by the balance snapshot
+    #[serde(default)]
+    pub balance_snapshot_top_n: u32,
+    /// Seconds a signer removed for missing checkpoints must wait before it
+    /// can request reinstatement
+    #[serde(default)]
+    pub signer_reinstatement_cooldown: u64,
+    /// Expired deposits worth less than this many satoshis are queued to be
+    /// consolidated by a sweep transaction rather than recovered one by one.
+    /// 0 disables sweeps.
+    #[serde(default)]
+    pub dust_deposit_threshold: u64,
+    /// The number of queued dust deposits which triggers a sweep
+    #[serde(default)]
+    pub dust_sweep_min_inputs: u32,
+    /// The highest share of the swept value, in basis points, a sweep may pay
+    /// as miner fee. Sweeps wait for lower fee rates otherwise.
+    #[serde(default)]
+    pub dust_sweep_max_fee_bps: u64,
+    /// How `WithdrawToBitcoin` destinations are checked against the screened
+    /// scripts list. Withdrawals to rejected scripts are held for review.
+    #[serde(default)]
+    pub withdrawal_script_screening: ScriptScreening,
+    /// Whether withdrawals to a script already paid by the building
+    /// checkpoint get their own output instead of being merged into it.
+    /// Merging saves miner fees, but links the withdrawals on chain.
+    #[serde(default)]
+    pub separate_withdrawal_outputs: bool,
+    /// The script types accepted by `SetRecoveryAddress`, empty accepts all
+    /// standard types.
+    #[serde(default)]
+    pub recovery_script_types: Vec<ScriptType>,
+    /// Deposits worth less than this many satoshis, or less than the miner
+    /// fee of spending them at `max_fee_rate`, can't be relayed, so that
+    /// checkpoints can't be filled with inputs costing more than their value.
+    /// 0 disables the check.
+    #[serde(default)]
+    pub min_relayable_utxo_sats: u64,
+    /// Limits checked on fully relayed transactions before they are trusted
+    /// for deposits or fee-bumped checkpoints.
+    #[serde(default)]
+    pub relay_tx_limits: RelayTxLimits,
+    /// How the age of a deposit is measured against its signatory set.
+    #[serde(default)]
+    pub deposit_age_unit: DepositAgeUnit,
+    /// The maximum age of a deposit in Bitcoin blocks, used instead of
+    /// `max_deposit_age` when `deposit_age_unit` is `BtcBlocks`.
+    #[serde(default)]
+    pub max_deposit_age_blocks: u32,
+    /// How long the miner fee quoted by `PrepareWithdrawal` stays locked, in
+    /// seconds. 0 disables prepared withdrawals.
+    #[serde(default)]
+    pub withdrawal_quote_ttl: u64,
+    /// If enabled, the fee pool balance above `fee_pool_target_balance`
+    /// moves to the reward pool, which is credited to the relayers and
+    /// signatories of each confirmed checkpoint.
+    #[serde(default)]
+    pub reward_pool_enabled: bool,
+    /// The share of each reward distribution paid to the relayers who proved
+    /// the checkpoint's confirmation, in basis points. The rest is paid to
+    /// its signatories by voting power.
+    #[serde(default)]
+    pub relayer_reward_bps: u64,
+}
+
+/// The unit deposit expiry is measured in.
+#[cw_serde]
+#[derive(Copy, Default, Eq)]
+pub enum DepositAgeUnit {
+    /// Seconds of bridge time since the signatory set was created, compared
+    /// to `max_deposit_age`.
+    #[default]
+    Seconds,
+    /// Bitcoin blocks from the light client height the signatory set was
+    /// created at to the height the deposit was confirmed at, compared to
+    /// `max_deposit_age_blocks`. Deposits can't expire while Oraichain is
+    /// halted.
+    BtcBlocks,
+}
+
+/// Bounds of a relayed Bitcoin transaction. A limit of 0 is not checked.
+#[cw_serde]
+pub struct RelayTxLimits {
+    /// The highest accepted transaction version, versions below 1 are always
+    /// rejected.
+    pub max_version: i32,
+    pub max_inputs: u32,
+    pub max_outputs: u32,
+    /// The maximum weight of the transaction, in weight units.
+    pub max_weight: u64,
+    /// The maximum size of a single witness stack item, in bytes.
+    pub max_witness_item_size: u32,
+}
+
+impl Default for RelayTxLimits {
+    fn default() -> Self {
+        Self {
+            max_version: 3,
+            max_inputs: 1_000,
+            max_outputs: 1_000,
+            max_weight: 400_000,
+            max_witness_item_size: 3_600,
+        }
+    }
+}
+
+fn default_max_end_block_items() -> u32 {
+    MAX_END_BLOCK_ITEMS
 }
 
 impl BitcoinConfig {
@@ -226,6 +1239,101 @@ impl BitcoinConfig {
             max_deposit_age: MAX_DEPOSIT_AGE, // 2 weeks. Initially there may not be many deposits & withdraws
             fee_pool_target_balance: 100_000_000, // 1 BTC
             fee_pool_reward_split: (1, 10),
+            checkpoint_confirmation_quorum: 0,
+            claimable_expired_deposits: false,
+            expired_deposit_fee_share: (0, 0),
+            invariant_check_interval: 0,
+            max_end_block_items: MAX_END_BLOCK_ITEMS,
+            mint_claim_expiry: 0,
+            mint_claim_recovery_address: None,
+            max_header_staleness: 0,
+            sigset_lag_surcharge_bps: 0,
+            latest_sigset_discount_bps: 0,
+            balance_snapshot_page_size: 0,
+            balance_snapshot_interval: 0,
+            balance_snapshot_top_n: 0,
+            signer_reinstatement_cooldown: 7 * 24 * 60 * 60,
+            dust_deposit_threshold: 0,
+            dust_sweep_min_inputs: 10,
+            dust_sweep_max_fee_bps: 2_000,
+            withdrawal_script_screening: ScriptScreening::Disabled,
+            separate_withdrawal_outputs: false,
+            recovery_script_types: vec![],
+            min_relayable_utxo_sats: 0,
+            relay_tx_limits: RelayTxLimits::default(),
+            deposit_age_unit: DepositAgeUnit::Seconds,
+            max_deposit_age_blocks: (MAX_DEPOSIT_AGE / BTC_BLOCK_INTERVAL) as u32,
+            withdrawal_quote_ttl: 10 * 60,
+            reward_pool_enabled: true,
+            relayer_reward_bps: 1_000,
+        }
+    }
+
+    /// The part of an expired deposit of `value` satoshis kept by the bridge
+    /// when it is claimed.
+    pub fn expired_deposit_fee_share(&self, value: u64) -> u64 {
+        let (numerator, denominator) = self.expired_deposit_fee_share;
+        if denominator == 0 {
+            return 0;
+        }
+        (value as u128 * numerator.min(denominator) as u128 / denominator as u128) as u64
+    }
+
+    /// The maximum age of a deposit in Bitcoin blocks, converting
+    /// `max_deposit_age` if deposit age is measured in seconds.
+    pub fn deposit_age_limit_blocks(&self) -> u32 {
+        match self.deposit_age_unit {
+            DepositAgeUnit::Seconds => (self.max_deposit_age / BTC_BLOCK_INTERVAL) as u32,
+            DepositAgeUnit::BtcBlocks => self.max_deposit_age_blocks,
+        }
+    }
+
+    /// Checks that an emergency disbursal could pay to the given script: it
+    /// must be of a standard type allowed by `recovery_script_types`.
+    pub fn check_recovery_script(&self, script: &bitcoin::Script) -> ContractResult<()> {
+        if script.len() > MAX_RECOVERY_SCRIPT_LEN {
+            return Err(ContractError::App(format!(
+                "Recovery script is longer than {} bytes",
+                MAX_RECOVERY_SCRIPT_LEN
+            )));
+        }
+        let script_type = ScriptType::of(script).ok_or_else(|| {
+            ContractError::App("Recovery script is not of a standard type".to_string())
+        })?;
+        if !self.recovery_script_types.is_empty()
+            && !self.recovery_script_types.contains(&script_type)
+        {
+            return Err(ContractError::App(format!(
+                "Recovery scripts of type {:?} are not allowed",
+                script_type
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// A standard output script type.
+#[cw_serde]
+#[derive(Copy, Eq)]
+pub enum ScriptType {
+    P2pkh,
+    P2wpkh,
+    P2wsh,
+    P2tr,
+}
+
+impl ScriptType {
+    pub fn of(script: &bitcoin::Script) -> Option<Self> {
+        if script.is_p2pkh() {
+            Some(ScriptType::P2pkh)
+        } else if script.is_p2wpkh() {
+            Some(ScriptType::P2wpkh)
+        } else if script.is_p2wsh() {
+            Some(ScriptType::P2wsh)
+        } else if script.is_p2tr() {
+            Some(ScriptType::P2tr)
+        } else {
+            None
         }
     }
 }
@@ -336,6 +1444,141 @@ pub struct CheckpointConfig {
     /// issue is simply with relayers failing to report the confirmation of the
     /// checkpoint transactions.    
     pub max_unconfirmed_checkpoints: u32,
+
+    /// The fill ratio of `max_inputs` or `max_outputs`, as a tuple
+    /// `(numerator, denominator)`, at which the `Building` checkpoint is
+    /// advanced without waiting for `min_checkpoint_interval`, to keep up
+    /// during deposit or withdrawal surges.
+    ///
+    /// `None` disables capacity-based advancing.
+    #[serde(default)]
+    pub capacity_advance_ratio: Option<(u64, u64)>,
+
+    /// The minimum amount of time, in seconds, since the creation of the
+    /// `Building` checkpoint before it can be advanced for reaching capacity.
+    #[serde(default)]
+    pub min_capacity_checkpoint_interval: u64,
+
+    /// Whether checkpoint inputs are signed with `SIGHASH_ALL|ANYONECANPAY`,
+    /// letting a relayer attach an extra input to a stuck checkpoint
+    /// transaction to top up its miner fee without the signatories signing
+    /// again.
+    ///
+    /// Adding an input changes the checkpoint's txid, which invalidates a
+    /// successor that was already signed, so this is meant to be used with a
+    /// `max_unconfirmed_checkpoints` of 1. Fee-bumped checkpoints must be
+    /// relayed with `RelayFeeBumpedCheckpoint`.
+    #[serde(default)]
+    pub fee_bump_anyone_can_pay: bool,
+
+    /// If set, the signature shares of confirmed checkpoints are replaced by
+    /// their final witnesses once the confirmed index is this many
+    /// checkpoints ahead of them (and never within `max_offline_checkpoints`,
+    /// which offline signer detection relies on). `None` disables compaction.
+    #[serde(default)]
+    pub signature_compaction_delay: Option<u32>,
+
+    /// The minimum fee, in satoshis, paid with `CommitData` to include a hash
+    /// in the next checkpoint's `OP_RETURN` output. 0 disables `CommitData`.
+    #[serde(default)]
+    pub app_data_fee: u64,
+
+    /// Whether new signatory sets commit to this bridge instance in their
+    /// scripts, so deposits can not be replayed on a deployment with the same
+    /// keys, e.g. a testnet contract.
+    #[serde(default)]
+    pub commit_bridge_instance: bool,
+
+    /// The encoding of voting power in the redeem scripts of new signatory
+    /// sets. Existing sets keep the template they were created with.
+    #[serde(default)]
+    pub script_template: ScriptTemplate,
+
+    /// Whether a `Building` checkpoint past `min_checkpoint_interval` keeps
+    /// collecting until its pending value reaches
+    /// `adaptive_pending_value_threshold` or its fee rate is favorable, up to
+    /// `max_checkpoint_interval`. Checkpoints reaching capacity still advance.
+    #[serde(default)]
+    pub adaptive_scheduling: bool,
+
+    /// The value of pending deposits and withdrawals, in satoshis, at which
+    /// an adaptively scheduled checkpoint advances. 0 disables the trigger.
+    #[serde(default)]
+    pub adaptive_pending_value_threshold: u64,
+
+    /// The percentile of the recorded fee rates at or below which an
+    /// adaptively scheduled checkpoint advances. 0 disables the trigger.
+    #[serde(default)]
+    pub adaptive_fee_rate_percentile: u8,
+
+    /// The miner fee rates charged to withdrawals of each
+    /// `WithdrawalPriority`, as multiples of the `Building` checkpoint's fee
+    /// rate.
+    #[serde(default)]
+    pub priority_fee_multipliers: PriorityFeeMultipliers,
+}
+
+impl CheckpointConfig {
+    /// The fee rate charged to a withdrawal of the given priority, kept
+    /// within `min_fee_rate` and `max_fee_rate`.
+    pub fn priority_fee_rate(&self, fee_rate: u64, priority: WithdrawalPriority) -> u64 {
+        let (numerator, denominator) = self.priority_fee_multipliers.get(priority);
+        let fee_rate = fee_rate as u128 * numerator as u128 / denominator.max(1) as u128;
+        (fee_rate.min(u64::MAX as u128) as u64)
+            .min(self.max_fee_rate)
+            .max(self.min_fee_rate)
+    }
+}
+
+/// How soon a withdrawal should be paid out, trading off its miner fee.
+#[cw_serde]
+#[derive(Default, Copy)]
+pub enum WithdrawalPriority {
+    Economy,
+    #[default]
+    Standard,
+    /// Advances an adaptively scheduled `Building` checkpoint without waiting
+    /// for its other triggers.
+    Fast,
+}
+
+impl WithdrawalPriority {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WithdrawalPriority::Economy => "economy",
+            WithdrawalPriority::Standard => "standard",
+            WithdrawalPriority::Fast => "fast",
+        }
+    }
+}
+
+/// The fee rate multiplier of each withdrawal priority, as a tuple
+/// `(numerator, denominator)`.
+#[cw_serde]
+pub struct PriorityFeeMultipliers {
+    pub economy: (u64, u64),
+    pub standard: (u64, u64),
+    pub fast: (u64, u64),
+}
+
+impl PriorityFeeMultipliers {
+    pub fn get(&self, priority: WithdrawalPriority) -> (u64, u64) {
+        match priority {
+            WithdrawalPriority::Economy => self.economy,
+            WithdrawalPriority::Standard => self.standard,
+            WithdrawalPriority::Fast => self.fast,
+        }
+    }
+}
+
+impl Default for PriorityFeeMultipliers {
+    fn default() -> Self {
+        Self {
+            economy: (1, 1),
+            standard: (1, 1),
+            fast: (3, 2),
+        }
+    }
 }
 
 impl Default for CheckpointConfig {
@@ -353,6 +1596,17 @@ impl Default for CheckpointConfig {
             sigset_threshold: SIGSET_THRESHOLD,
             max_unconfirmed_checkpoints: 15,
             fee_rate: 0,
+            capacity_advance_ratio: None,
+            min_capacity_checkpoint_interval: 60,
+            fee_bump_anyone_can_pay: false,
+            signature_compaction_delay: None,
+            app_data_fee: 0,
+            commit_bridge_instance: false,
+            script_template: ScriptTemplate::Raw,
+            adaptive_scheduling: false,
+            adaptive_pending_value_threshold: 0,
+            adaptive_fee_rate_percentile: 0,
+            priority_fee_multipliers: PriorityFeeMultipliers::default(),
         }
     }
 }
@@ -372,3 +1626,25 @@ pub struct MintTokens {
     pub amount: Uint128,
     pub mint_to_address: String,
 }
+
+/// A checkpoint event which contracts can register a hook for.
+#[cw_serde]
+#[derive(Copy, Eq)]
+pub enum HookEvent {
+    /// The building checkpoint was pushed and started signing
+    CheckpointAdvanced,
+    /// A checkpoint transaction was confirmed on Bitcoin
+    CheckpointConfirmed,
+    /// A new checkpoint has a different set of signatories than the previous
+    /// one
+    SigsetRotated,
+}
+
+/// A contract receiving checkpoint events, see `ExecuteMsg::RegisterHook`.
+#[cw_serde]
+pub struct CheckpointHook {
+    pub addr: Addr,
+    pub events: Vec<HookEvent>,
+    /// Failed deliveries since the last successful one
+    pub failures: u32,
+}
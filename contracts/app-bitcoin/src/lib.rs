@@ -2,18 +2,28 @@ pub mod contract;
 pub mod msg;
 
 mod app;
+mod app_data;
 mod checkpoint;
 mod constants;
+mod descriptor;
 mod entrypoints;
+mod export;
 mod fee;
 mod helper;
 #[cfg(test)]
 mod integration_tests;
 mod interface;
+mod lightning;
+mod migration;
 mod outpoint_set;
+#[cfg(feature = "profiling")]
+pub mod profiling;
 mod recovery;
+mod reward;
 mod signatory;
+mod snapshot;
 mod state;
 #[cfg(test)]
 mod tests;
 mod threshold_sig;
+mod tx_check;
@@ -0,0 +1,49 @@
+use bitcoin::opcodes::all::{
+    OP_CHECKSIG, OP_CLTV, OP_DROP, OP_ELSE, OP_ENDIF, OP_EQUAL, OP_IF, OP_SHA256,
+};
+use bitcoin::script::{Builder, PushBytes};
+use bitcoin::ScriptBuf;
+use common_bitcoin::error::{ContractError, ContractResult};
+
+/// Builds the witness script of the HTLC output paying a submarine swap
+/// provider for a Lightning withdrawal.
+///
+/// The provider can spend the output with its claim key once it learns the
+/// preimage of `payment_hash`, which it does by paying the user's invoice.
+/// After `lock_height` the refund key can spend the output instead.
+pub fn htlc_script(
+    payment_hash: &[u8],
+    claim_pubkey: &[u8],
+    refund_pubkey: &[u8],
+    lock_height: u32,
+) -> ContractResult<ScriptBuf> {
+    if payment_hash.len() != 32 {
+        return Err(ContractError::App(
+            "Payment hash must be 32 bytes".to_string(),
+        ));
+    }
+    for pubkey in [claim_pubkey, refund_pubkey] {
+        bitcoin::PublicKey::from_slice(pubkey)
+            .map_err(|err| ContractError::App(format!("Invalid HTLC public key: {}", err)))?;
+    }
+
+    let push = |bytes| {
+        <&PushBytes>::try_from(bytes)
+            .map_err(|err| ContractError::App(format!("Invalid HTLC script push: {}", err)))
+    };
+    let script = Builder::new()
+        .push_opcode(OP_SHA256)
+        .push_slice(push(payment_hash)?)
+        .push_opcode(OP_EQUAL)
+        .push_opcode(OP_IF)
+        .push_slice(push(claim_pubkey)?)
+        .push_opcode(OP_ELSE)
+        .push_int(lock_height as i64)
+        .push_opcode(OP_CLTV)
+        .push_opcode(OP_DROP)
+        .push_slice(push(refund_pubkey)?)
+        .push_opcode(OP_ENDIF)
+        .push_opcode(OP_CHECKSIG)
+        .into_script();
+    Ok(script)
+}
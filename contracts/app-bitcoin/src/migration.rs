@@ -0,0 +1,103 @@
+use crate::{
+    constants::CONTRACT_STATE_VERSION,
+    state::{
+        BITCOIN_CONFIG, CHECKPOINTS, CHECKPOINT_CONFIG, EXPIRED_DEPOSITS, EXPIRED_DEPOSITS_BY_DEST,
+        FOUNDATION_KEYS, RECOVERY_TXS, STATE_VERSION,
+    },
+};
+use common_bitcoin::error::{ContractError, ContractResult};
+use cosmwasm_std::{Order, StdResult, Storage};
+
+/// A migration of the stored state from the version at its index in
+/// [`MIGRATIONS`] to the next one.
+type Migration = fn(&mut dyn Storage) -> ContractResult<()>;
+
+/// Migrations to run in order, `MIGRATIONS[v]` upgrades state version `v` to
+/// `v + 1`. Its length must equal [`CONTRACT_STATE_VERSION`].
+const MIGRATIONS: [Migration; CONTRACT_STATE_VERSION as usize] =
+    [migrate_v0_to_v1, migrate_v1_to_v2];
+
+/// The version of the stored state. Contracts instantiated before state
+/// versioning existed are at version 0.
+pub fn state_version(store: &dyn Storage) -> ContractResult<u32> {
+    Ok(STATE_VERSION.may_load(store)?.unwrap_or_default())
+}
+
+/// Runs every migration between the stored state version and
+/// [`CONTRACT_STATE_VERSION`], returning the version migrated from.
+pub fn migrate_state(store: &mut dyn Storage) -> ContractResult<u32> {
+    let from_version = state_version(store)?;
+    if from_version > CONTRACT_STATE_VERSION {
+        return Err(ContractError::App(format!(
+            "Cannot downgrade state from version {} to {}",
+            from_version, CONTRACT_STATE_VERSION
+        )));
+    }
+
+    for migration in MIGRATIONS[from_version as usize..].iter() {
+        migration(store)?;
+    }
+    STATE_VERSION.save(store, &CONTRACT_STATE_VERSION)?;
+
+    Ok(from_version)
+}
+
+/// Rewrites the checkpoint and recovery transaction queues and the configs
+/// with their current layouts, so fields added with serde defaults (e.g.
+/// `Input::sighash_type`) are persisted. Every entry is decoded before any is
+/// written, failing the migration instead of leaving a partially rewritten
+/// queue if an entry cannot be read.
+fn migrate_v0_to_v1(store: &mut dyn Storage) -> ContractResult<()> {
+    let checkpoints = (0..CHECKPOINTS.len(store)?)
+        .map(|i| {
+            CHECKPOINTS.get(store, i)?.ok_or_else(|| {
+                ContractError::App(format!("Missing checkpoint at queue position {}", i))
+            })
+        })
+        .collect::<ContractResult<Vec<_>>>()?;
+    let recovery_txs = (0..RECOVERY_TXS.len(store)?)
+        .map(|i| {
+            RECOVERY_TXS.get(store, i)?.ok_or_else(|| {
+                ContractError::App(format!("Missing recovery tx at queue position {}", i))
+            })
+        })
+        .collect::<ContractResult<Vec<_>>>()?;
+
+    for (i, checkpoint) in checkpoints.iter().enumerate() {
+        CHECKPOINTS.set(store, i as u32, checkpoint)?;
+    }
+    for (i, recovery_tx) in recovery_txs.iter().enumerate() {
+        RECOVERY_TXS.set(store, i as u32, recovery_tx)?;
+    }
+
+    let checkpoint_config = CHECKPOINT_CONFIG.load(store)?;
+    CHECKPOINT_CONFIG.save(store, &checkpoint_config)?;
+    let bitcoin_config = BITCOIN_CONFIG.load(store)?;
+    BITCOIN_CONFIG.save(store, &bitcoin_config)?;
+
+    if FOUNDATION_KEYS.may_load(store)?.is_none() {
+        FOUNDATION_KEYS.save(store, &Vec::new())?;
+    }
+
+    Ok(())
+}
+
+/// Indexes the expired deposits waiting to be claimed by their destination.
+fn migrate_v1_to_v2(store: &mut dyn Storage) -> ContractResult<()> {
+    let deposits = EXPIRED_DEPOSITS
+        .range(store, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, deposit)| deposit))
+        .collect::<StdResult<Vec<_>>>()?;
+    for deposit in deposits {
+        EXPIRED_DEPOSITS_BY_DEST.save(
+            store,
+            (
+                &deposit.dest.commitment_bytes()?,
+                &deposit.txid,
+                deposit.vout,
+            ),
+            &(),
+        )?;
+    }
+    Ok(())
+}
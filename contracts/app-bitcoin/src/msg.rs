@@ -1,17 +1,21 @@
-use bitcoin::{util::merkleblock::PartialMerkleTree, Transaction};
+use bitcoin::{merkle_tree::PartialMerkleTree, ScriptBuf, Transaction};
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::{Addr, Binary, Coin, Uint128};
+use cosmwasm_std::{to_json_binary, Addr, Binary, Coin, CosmosMsg, StdResult, Uint128, WasmMsg};
 use oraiswap::asset::AssetInfo;
 use token_bindings::Metadata;
 
 use crate::{
     app::ConsensusKey,
-    interface::{BitcoinConfig, CheckpointConfig, Dest},
+    interface::{
+        BitcoinConfig, CheckpointConfig, Dest, HookEvent, MigrationHandover, WithdrawalPriority,
+    },
+    signatory::{FoundationPolicy, Signatory, SignatorySet},
     state::Ratio,
     threshold_sig::Signature,
 };
 use common_bitcoin::adapter::{Adapter, WrappedBinary};
 use common_bitcoin::xpub::Xpub;
+use light_client_bitcoin::header::WrappedHeader;
 
 #[cw_serde]
 pub struct FeeData {
@@ -43,6 +47,16 @@ pub struct Config {
     pub light_client_contract: Addr,
     pub swap_router_contract: Option<Addr>,
     pub osor_entry_point_contract: Option<Addr>,
+    /// Light client to fail over to with `SwitchLightClient`
+    #[serde(default)]
+    pub secondary_light_client_contract: Option<Addr>,
+    /// Contract notified of deposits, signed checkpoints and confirmations
+    #[serde(default)]
+    pub notification_contract: Option<Addr>,
+    /// Fees `RelayDeposit` callers may pay the relayer fee receiver with,
+    /// one of which must be sent. Empty makes relaying deposits free
+    #[serde(default)]
+    pub relayer_fee_options: Vec<Coin>,
 }
 
 #[cw_serde]
@@ -57,6 +71,21 @@ pub struct ConfigResponse {
     pub light_client_contract: Addr,
     pub swap_router_contract: Option<Addr>,
     pub osor_entry_point_contract: Option<Addr>,
+    pub secondary_light_client_contract: Option<Addr>,
+    pub notification_contract: Option<Addr>,
+    pub relayer_fee_options: Vec<Coin>,
+}
+
+#[cw_serde]
+pub struct FullConfigResponse {
+    pub config: ConfigResponse,
+    pub bitcoin_config: BitcoinConfig,
+    pub checkpoint_config: CheckpointConfig,
+    pub light_client_contract: Addr,
+    pub token_factory_contract: Addr,
+    /// The full nBTC denom
+    pub btc_denom: String,
+    pub state_version: u32,
 }
 
 #[cw_serde]
@@ -64,6 +93,88 @@ pub enum OsorMsg {
     UniversalSwap { memo: String },
 }
 
+/// Hook sent to contract destinations once their bridged BTC has been minted.
+#[cw_serde]
+pub struct BtcBridgeReceiveMsg {
+    pub sender_commitment: Binary,
+    pub amount: Uint128,
+}
+
+impl BtcBridgeReceiveMsg {
+    /// Serializes the message as the execute msg expected by the receiver.
+    pub fn into_binary(self) -> StdResult<Binary> {
+        to_json_binary(&ReceiverExecuteMsg::BtcBridgeReceive(self))
+    }
+
+    /// Creates a cosmos message sending this struct to the named contract.
+    pub fn into_cosmos_msg<T: Into<String>>(self, contract_addr: T) -> StdResult<CosmosMsg> {
+        Ok(WasmMsg::Execute {
+            contract_addr: contract_addr.into(),
+            msg: self.into_binary()?,
+            funds: vec![],
+        }
+        .into())
+    }
+}
+
+/// Execute msg of a contract receiving bridged BTC.
+#[cw_serde]
+pub enum ReceiverExecuteMsg {
+    BtcBridgeReceive(BtcBridgeReceiveMsg),
+}
+
+/// A bridge event sent to the notification contract.
+#[cw_serde]
+pub enum BridgeNotification {
+    /// A deposit was relayed, amount in satoshis
+    DepositProcessed {
+        txid: String,
+        vout: u32,
+        receiver: String,
+        amount: u64,
+    },
+    /// A checkpoint paying out withdrawals was fully signed and can be
+    /// broadcast
+    WithdrawalBroadcast {
+        checkpoint_index: u32,
+        txid: String,
+    },
+    CheckpointConfirmed {
+        index: u32,
+        btc_height: u32,
+    },
+}
+
+/// Execute msg of a notification hub contract.
+#[cw_serde]
+pub enum NotificationExecuteMsg {
+    BtcBridgeNotification(BridgeNotification),
+}
+
+/// A checkpoint event sent to the hooks registered for it.
+#[cw_serde]
+pub enum CheckpointHookMsg {
+    CheckpointAdvanced { index: u32 },
+    CheckpointConfirmed { index: u32, btc_height: u32 },
+    SigsetRotated { index: u32 },
+}
+
+impl CheckpointHookMsg {
+    pub fn event(&self) -> HookEvent {
+        match self {
+            CheckpointHookMsg::CheckpointAdvanced { .. } => HookEvent::CheckpointAdvanced,
+            CheckpointHookMsg::CheckpointConfirmed { .. } => HookEvent::CheckpointConfirmed,
+            CheckpointHookMsg::SigsetRotated { .. } => HookEvent::SigsetRotated,
+        }
+    }
+}
+
+/// Execute msg of a contract registered as a checkpoint hook.
+#[cw_serde]
+pub enum CheckpointHookExecuteMsg {
+    BtcBridgeCheckpointHook(CheckpointHookMsg),
+}
+
 #[cw_serde]
 pub enum ExecuteMsg {
     UpdateConfig {
@@ -77,6 +188,7 @@ pub enum ExecuteMsg {
         swap_router_contract: Option<Addr>,
         token_factory_contract: Option<Addr>,
         osor_entry_point_contract: Option<Addr>,
+        relayer_fee_options: Option<Vec<Coin>>,
     },
     UpdateBitcoinConfig {
         config: BitcoinConfig,
@@ -92,9 +204,36 @@ pub enum ExecuteMsg {
         voting_powers: Vec<u64>,
         consensus_keys: Vec<ConsensusKey>,
     },
+    /// Removes validators from the registry. Only signatory sets created
+    /// afterwards are affected
+    RemoveValidators {
+        addrs: Vec<String>,
+    },
+    UpdateValidatorPower {
+        addr: String,
+        voting_power: u64,
+    },
+    /// Schedules the validator registry to be replaced by `validators` before
+    /// the signatory set of checkpoint `epoch` is built
+    SetValidatorSet {
+        epoch: u32,
+        validators: Vec<crate::interface::ValidatorInfo>,
+    },
+    /// Requests a signer removed for missing checkpoints to be added back to
+    /// the registry before the next signatory set is built, once its
+    /// reinstatement cooldown has passed
+    RequestReinstatement {},
+    /// Sets the Bitcoin address the sender's nBTC is paid to by an emergency
+    /// disbursal
+    SetRecoveryAddress {
+        btc_address: String,
+    },
     UpdateFoundationKeys {
         xpubs: Vec<WrappedBinary<Xpub>>,
     },
+    UpdateFoundationPolicy {
+        policy: FoundationPolicy,
+    },
     RelayDeposit {
         btc_tx: Adapter<Transaction>,
         btc_height: u32,
@@ -103,32 +242,169 @@ pub enum ExecuteMsg {
         sigset_index: u32,
         dest: Dest,
     },
+    /// Reports an unconfirmed deposit so frontends can show it before it is
+    /// relayed. Only accepted from registered relayers
+    ReportPendingDeposit {
+        txid: String,
+        vout: u32,
+        dest: Dest,
+        amount: u64,
+    },
+    /// Relays the confirmation of a completed checkpoint transaction. The
+    /// checkpoint index is derived from the proof when omitted
     RelayCheckpoint {
+        btc_height: u32,
+        btc_proof: Adapter<PartialMerkleTree>,
+        cp_index: Option<u32>,
+        /// Headers connecting the block at `btc_height` to the light client's
+        /// chain, for blocks it no longer stores. Starts at `btc_height`
+        #[serde(default)]
+        ancestor_headers: Vec<WrappedHeader>,
+    },
+    /// Relays the confirmation of a checkpoint transaction which had extra
+    /// inputs attached to top up its miner fee
+    RelayFeeBumpedCheckpoint {
         btc_height: u32,
         btc_proof: Adapter<PartialMerkleTree>,
         cp_index: u32,
+        btc_tx: Adapter<Transaction>,
     },
     WithdrawToBitcoin {
         btc_address: String,
         fee: Option<u64>,
+        /// Withdraws `amount` of this account's nBTC under the withdrawal
+        /// allowance it granted the sender, instead of the sent nBTC
+        #[serde(default)]
+        on_behalf_of: Option<Addr>,
+        /// The part of the sent nBTC to withdraw, the rest is refunded.
+        /// Without it all the sent nBTC is withdrawn and the sub-satoshi
+        /// remainder is paid as miner fee, so no dust is left
+        #[serde(default)]
+        amount: Option<Uint128>,
+        /// Charges the miner fee at the rate of this priority tier in the
+        /// checkpoint config
+        #[serde(default)]
+        priority: WithdrawalPriority,
+    },
+    /// Allows `spender` to withdraw up to `amount` of the sender's nBTC to
+    /// Bitcoin until the bridge time `expiry`, replacing any previous
+    /// allowance. A zero amount revokes it
+    SetWithdrawalAllowance {
+        spender: Addr,
+        amount: Uint128,
+        expiry: Option<u64>,
+    },
+    /// Locks the miner fee of withdrawing `amount` nBTC to `btc_address` for
+    /// `withdrawal_quote_ttl` seconds, returning the quote id in the
+    /// `quote_id` attribute
+    PrepareWithdrawal {
+        btc_address: String,
+        amount: Uint128,
+    },
+    /// Queues the withdrawal of a prepared quote at its locked miner fee, the
+    /// quoted nBTC amount must be sent. Fails without effect if the quote was
+    /// already executed
+    ExecuteWithdrawal {
+        quote_id: u64,
+    },
+    /// Pays back the miner fee of a queued withdrawal with the sent nBTC,
+    /// refunding any amount beyond what is still outstanding
+    SponsorWithdrawalFee {
+        withdrawal_id: u64,
+    },
+    /// Burns the sent nBTC without withdrawing it, e.g. to unwind
+    /// protocol-owned liquidity. The burned amount first repays the bad debt
+    Burn {},
+    /// Pays `fee` satoshis of the sent nBTC to commit a 32-byte hash in the
+    /// `OP_RETURN` output of the building checkpoint, refunding the rest
+    CommitData {
+        data: Binary,
+        fee: u64,
+    },
+    /// Escrows the sent nBTC to be withdrawn to Lightning through a
+    /// registered swap provider, which pays the invoice of `payment_hash`
+    WithdrawToLightning {
+        provider: Addr,
+        payment_hash: Binary,
+        refund_pubkey: Binary,
+        fee: Option<u64>,
+    },
+    /// Sent by the swap provider to accept a Lightning withdrawal, paying its
+    /// nBTC out to an HTLC output in the building checkpoint
+    AcceptLightningSwap {
+        swap_id: u64,
     },
+    /// Returns the nBTC of a Lightning withdrawal not accepted in time
+    RefundLightningSwap {
+        swap_id: u64,
+    },
+    /// Registers a swap provider, or removes it if `swap_provider` is `None`
+    SetSwapProvider {
+        provider: Addr,
+        swap_provider: Option<crate::interface::SwapProvider>,
+    },
+    /// Signs the checkpoint's inputs the signatory is part of. With an
+    /// `offset`, `sigs` only covers those inputs starting at that position, so
+    /// signatures can be submitted in several chunks
     SubmitCheckpointSignature {
         xpub: WrappedBinary<Xpub>,
         sigs: Vec<Signature>,
         checkpoint_index: u32,
         btc_height: u32,
+        #[serde(default)]
+        offset: Option<u32>,
+    },
+    SubmitCheckpointPsbt {
+        xpub: WrappedBinary<Xpub>,
+        psbt: Binary,
+        checkpoint_index: u32,
+        btc_height: u32,
     },
     SubmitRecoverySignature {
         xpub: WrappedBinary<Xpub>,
         sigs: Vec<Signature>,
     },
+    /// Signs the recovery transaction inputs of one signatory set, in the
+    /// order of `SigningRecoveryGroup`
+    SubmitRecoveryGroupSignature {
+        xpub: WrappedBinary<Xpub>,
+        sigset_index: u32,
+        sigs: Vec<Signature>,
+    },
     SetSignatoryKey {
         xpub: WrappedBinary<Xpub>,
+        /// Signature over `signatory_key_challenge` by the key derived from
+        /// `xpub` at index 0.
+        sig: Signature,
+    },
+    /// Removes a compromised signatory key from future signatory sets and
+    /// pushes the next checkpoint right away, moving the reserve to scripts
+    /// without the key. Applied immediately when sent by the owner, or once
+    /// validators with more than 2/3 of the voting power sent it
+    TombstoneSignatory {
+        xpub: WrappedBinary<Xpub>,
+        evidence: String,
+    },
+    /// Completes a signing checkpoint with a fully signed transaction
+    /// assembled off-chain, when its on-chain signature accounting can't be
+    /// completed. Applied once validators with more than 2/3 of the voting
+    /// power sent the same transaction
+    ForceCompleteCheckpoint {
+        index: u32,
+        final_tx: Adapter<Transaction>,
     },
     RegisterDenom {
         subdenom: String,
         metadata: Option<Metadata>,
     },
+    UpdateDenomMetadata {
+        metadata: Metadata,
+    },
+    /// Creates `new_subdenom` and mints nBTC as it from now on. Balances of
+    /// the previous denom can still be withdrawn. The bridge must be paused.
+    MigrateDenom {
+        new_subdenom: String,
+    },
     ChangeBtcDenomOwner {
         new_owner: String,
     },
@@ -139,6 +415,216 @@ pub enum ExecuteMsg {
         val_addr: Addr,
         permission: bool,
     },
+    /// Exempts a deposit destination or withdrawer from token and relayer
+    /// fees. Miner fees are still paid.
+    SetFeeExemption {
+        addr: String,
+        exempt: bool,
+    },
+    /// Writes storage entries exported from another bridge contract. Only
+    /// allowed on a contract without checkpoints, until `finish` is set.
+    ImportState {
+        entries: Vec<StateEntry>,
+        finish: bool,
+    },
+    /// Starts migrating this bridge to `successor`, which must expect the
+    /// migration. Deposits are frozen, and the next checkpoint pays the whole
+    /// reserve to the successor's signatory set
+    StartBridgeMigration {
+        successor: Addr,
+    },
+    /// Sends the next page of account state to the successor, once the
+    /// migration checkpoint has been confirmed. The last page hands the
+    /// reserve and the nBTC denom over to the successor
+    TransferBridgeState {
+        limit: Option<u32>,
+    },
+    /// Cancels a bridge migration whose checkpoint has not been built yet,
+    /// unfreezing deposits
+    CancelBridgeMigration {},
+    /// Allows `predecessor` to migrate its reserve and accounts to this
+    /// bridge, or revokes it when `None`. Only a bridge which holds no
+    /// migrated account state yet may expect a migration
+    ExpectBridgeMigration {
+        predecessor: Option<Addr>,
+    },
+    /// Account state sent by the predecessor bridge during a migration, the
+    /// last page with the handover
+    ReceiveMigratedState {
+        entries: Vec<StateEntry>,
+        handover: Option<MigrationHandover>,
+    },
+    /// Attaches a reserve output created outside of the checkpoint chain, e.g.
+    /// by an emergency recovery, to the building checkpoint. The transaction
+    /// creating it must be confirmed, and the output must pay to the reserve
+    /// script of the signatory set at `sigset_index`.
+    RegisterReserveUtxo {
+        btc_tx: Adapter<Transaction>,
+        btc_height: u32,
+        btc_proof: Adapter<PartialMerkleTree>,
+        vout: u32,
+        sigset_index: u32,
+    },
+    /// Pauses or resumes deposits and withdrawals, e.g. to resume them after
+    /// an invariant violation was resolved
+    SetBridgePaused {
+        paused: bool,
+    },
+    /// Overrides the fee rate of the building checkpoint, within the
+    /// configured `min_fee_rate` and `max_fee_rate`
+    SetFeeRate {
+        fee_rate: u64,
+    },
+    /// Accepts deposits below `min_relayable_utxo_sats` until the given
+    /// bridge time, e.g. while Bitcoin fees are low. `None` ends the window
+    AllowSmallUtxos {
+        until: Option<u64>,
+    },
+    /// Transfers ownership to a cw3 multisig, e.g. a cw3-flex-multisig of a
+    /// DAO, after checking it has voters
+    SetOwnerMultisig {
+        multisig: Addr,
+    },
+    /// Archives the signatory sets of checkpoints pruned before sets were
+    /// archived automatically, so they stay available to `SigsetAt`
+    BackfillSigsets {
+        sigsets: Vec<BackfilledSigset>,
+    },
+    /// Refunds a deposit which was relayed after `max_deposit_age` to the
+    /// given script. Must be sent by the deposit's depositor.
+    ClaimExpiredDeposit {
+        txid: String,
+        vout: u32,
+        refund_script: Adapter<ScriptBuf>,
+    },
+    /// Mints the sender's nBTC which could not be pushed to it when its
+    /// checkpoint completed
+    ClaimMint {},
+    /// Sends the sender the nBTC of its failed ibc memo transfers
+    ClaimIbcRefund {},
+    /// Mints the sender's rewards for relaying and signing confirmed
+    /// checkpoints
+    ClaimRewards {},
+    SetRelayer {
+        relayer: Addr,
+        permission: bool,
+    },
+    /// Sets the contract notified of bridge events, `None` removes it
+    SetNotificationContract {
+        contract: Option<Addr>,
+    },
+    /// Sets the light client to fail over to, `None` removes it
+    SetSecondaryLightClient {
+        contract: Option<Addr>,
+    },
+    /// Makes the secondary light client the active one, after checking both
+    /// light clients have the same header at the lower of their heights. The
+    /// previous light client becomes the secondary one.
+    SwitchLightClient {},
+    /// Allows or disallows an address to tag checkpoints
+    SetComplianceTagger {
+        tagger: Addr,
+        permission: bool,
+    },
+    /// Attaches a memo to a checkpoint, replacing any previous one. Must be
+    /// sent by the owner or a compliance tagger
+    TagCheckpoint {
+        index: u32,
+        memo: String,
+    },
+    /// Adds or removes the script of a Bitcoin address from the screened
+    /// scripts list. Must be sent by the owner or a compliance tagger
+    SetScreenedScript {
+        btc_address: String,
+        listed: bool,
+    },
+    /// Queues a held withdrawal as requested, or refunds its nBTC to the
+    /// sender. Must be sent by the owner or a compliance tagger
+    ReviewHeldWithdrawal {
+        id: u64,
+        approve: bool,
+    },
+    /// Registers a contract to receive the given checkpoint events, replacing
+    /// the events of an existing hook of the contract. A hook failing too
+    /// many times is deregistered
+    RegisterHook {
+        addr: String,
+        events: Vec<HookEvent>,
+    },
+    UnregisterHook {
+        addr: String,
+    },
+    /// Reverses the mints of deposits whose blocks were reorged out of the
+    /// Bitcoin chain, burning the minted nBTC the receiver still holds and
+    /// recording the rest as bad debt. The light client must have replaced
+    /// the block each deposit was minted from, and the replacing block must
+    /// have `min_confirmations`
+    ReconcileReorgedDeposits {
+        deposits: Vec<ReorgedDepositId>,
+    },
+}
+
+/// A minted deposit output, by the txid and output index it was relayed with
+#[cw_serde]
+pub struct ReorgedDepositId {
+    pub txid: String,
+    pub vout: u32,
+}
+
+/// A pruned checkpoint's signatory set, proven by the checkpoint's
+/// transaction: its txid must be the recorded one, and its reserve output
+/// must pay to the set's reserve script.
+#[cw_serde]
+pub struct BackfilledSigset {
+    pub sigset: SignatorySet,
+    pub checkpoint_tx: Adapter<Transaction>,
+}
+
+impl ExecuteMsg {
+    /// The name under which the message is recorded in the admin log, for
+    /// messages restricted to the owner
+    pub fn admin_action(&self) -> Option<&'static str> {
+        let action = match self {
+            ExecuteMsg::UpdateConfig { .. } => "update_config",
+            ExecuteMsg::UpdateBitcoinConfig { .. } => "update_bitcoin_config",
+            ExecuteMsg::UpdateCheckpointConfig { .. } => "update_checkpoint_config",
+            #[cfg(not(feature = "native-validator"))]
+            ExecuteMsg::AddValidators { .. } => "add_validators",
+            ExecuteMsg::RemoveValidators { .. } => "remove_validators",
+            ExecuteMsg::UpdateValidatorPower { .. } => "update_validator_power",
+            ExecuteMsg::SetValidatorSet { .. } => "set_validator_set",
+            ExecuteMsg::UpdateFoundationKeys { .. } => "update_foundation_keys",
+            ExecuteMsg::UpdateFoundationPolicy { .. } => "update_foundation_policy",
+            ExecuteMsg::SetSwapProvider { .. } => "set_swap_provider",
+            ExecuteMsg::RegisterDenom { .. } => "register_denom",
+            ExecuteMsg::UpdateDenomMetadata { .. } => "update_denom_metadata",
+            ExecuteMsg::MigrateDenom { .. } => "migrate_denom",
+            ExecuteMsg::ChangeBtcDenomOwner { .. } => "change_btc_denom_owner",
+            ExecuteMsg::SetWhitelistValidator { .. } => "set_whitelist_validator",
+            ExecuteMsg::SetFeeExemption { .. } => "set_fee_exemption",
+            ExecuteMsg::ImportState { .. } => "import_state",
+            ExecuteMsg::RegisterReserveUtxo { .. } => "register_reserve_utxo",
+            ExecuteMsg::StartBridgeMigration { .. } => "start_bridge_migration",
+            ExecuteMsg::TransferBridgeState { .. } => "transfer_bridge_state",
+            ExecuteMsg::CancelBridgeMigration { .. } => "cancel_bridge_migration",
+            ExecuteMsg::ExpectBridgeMigration { .. } => "expect_bridge_migration",
+            ExecuteMsg::SetBridgePaused { .. } => "set_bridge_paused",
+            ExecuteMsg::SetFeeRate { .. } => "set_fee_rate",
+            ExecuteMsg::AllowSmallUtxos { .. } => "allow_small_utxos",
+            ExecuteMsg::SetOwnerMultisig { .. } => "set_owner_multisig",
+            ExecuteMsg::BackfillSigsets { .. } => "backfill_sigsets",
+            ExecuteMsg::SetRelayer { .. } => "set_relayer",
+            ExecuteMsg::SetComplianceTagger { .. } => "set_compliance_tagger",
+            ExecuteMsg::SetNotificationContract { .. } => "set_notification_contract",
+            ExecuteMsg::SetSecondaryLightClient { .. } => "set_secondary_light_client",
+            ExecuteMsg::SwitchLightClient {} => "switch_light_client",
+            ExecuteMsg::RegisterHook { .. } => "register_hook",
+            ExecuteMsg::UnregisterHook { .. } => "unregister_hook",
+            ExecuteMsg::ReconcileReorgedDeposits { .. } => "reconcile_reorged_deposits",
+            _ => return None,
+        };
+        Some(action)
+    }
 }
 
 #[cw_serde]
@@ -150,12 +636,23 @@ pub enum QueryMsg {
     BitcoinConfig {},
     #[returns(CheckpointConfig)]
     CheckpointConfig {},
+    /// All configs read from the same state, along with the contracts and
+    /// denom the bridge works with
+    #[returns(FullConfigResponse)]
+    FullConfig {},
     #[returns(Option<WrappedBinary<Xpub>>)]
     SignatoryKey { addr: Addr },
     #[returns(u64)]
     DepositFees { index: Option<u32> },
     #[returns(u64)]
     CheckpointFees { index: Option<u32> },
+    /// How much of the `Building` checkpoint's miner fee is covered by its
+    /// collected fees and the fee pool
+    #[returns(crate::interface::FeeCoverage)]
+    FeeCoverage {},
+    /// What adaptive scheduling decides on for the `Building` checkpoint
+    #[returns(crate::interface::CheckpointSchedule)]
+    CheckpointSchedule {},
     #[returns(u64)]
     WithdrawalFees { address: String, index: Option<u32> },
     #[returns(Vec<Adapter<Transaction>>)]
@@ -164,17 +661,63 @@ pub enum QueryMsg {
     SignedRecoveryTxs {},
     #[returns(Adapter<Transaction>)]
     CheckpointTx { index: Option<u32> },
+    #[returns(Binary)]
+    CheckpointPsbt { index: u32 },
+    /// The inputs of a checkpoint transaction, with the reserve output or
+    /// deposit each spends
+    #[returns(Vec<crate::interface::CheckpointInput>)]
+    CheckpointInputs { index: u32 },
+    /// The final txid of the completed checkpoint with the given index
+    #[returns(String)]
+    CheckpointTxid { index: u32 },
+    /// The index of the completed checkpoint with the given txid, if any
+    #[returns(Option<u32>)]
+    CheckpointIndexByTxid { txid: String },
+    /// The checkpoint transaction serialized without witnesses, i.e. the
+    /// bytes its txid is the double SHA-256 of
+    #[returns(Binary)]
+    CheckpointTxidPreimage { index: u32 },
+    /// A fully-signed transaction as hex, ready to be broadcast. `index` is
+    /// the checkpoint index, or the position in the recovery queue
+    #[returns(RawTxResponse)]
+    RawTxHex { kind: RawTxKind, index: u32 },
     #[returns(crate::checkpoint::Checkpoint)]
     CheckpointByIndex { index: u32 },
+    /// An overview of a checkpoint, including its memo
+    #[returns(crate::interface::CheckpointSummary)]
+    CheckpointSummary { index: u32 },
     #[returns(crate::checkpoint::Checkpoint)]
     BuildingCheckpoint {},
     #[returns(Vec<([u8; 32], u32)>)] // Fix: Added closing angle bracket
     SigningRecoveryTxs { xpub: WrappedBinary<Xpub> },
+    /// The recovery transaction messages `xpub` still has to sign for the
+    /// inputs of the signatory set at `sigset_index`
+    #[returns(Vec<([u8; 32], u32)>)]
+    SigningRecoveryGroup {
+        xpub: WrappedBinary<Xpub>,
+        sigset_index: u32,
+    },
+    /// The signing progress of the recovery transactions, grouped by the
+    /// signatory set their inputs spend from
+    #[returns(Vec<crate::interface::RecoveryGroupStatus>)]
+    RecoveryGroups { xpub: Option<WrappedBinary<Xpub>> },
+    /// Everything the signer of `xpub` still has to sign across the signing
+    /// checkpoint and the recovery transactions, oldest first and then
+    /// closest to its threshold first
+    #[returns(Vec<crate::interface::SigningDuty>)]
+    SigningDuties { xpub: WrappedBinary<Xpub> },
     #[returns(Vec<([u8; 32], u32)>)] // Fix: Added closing angle bracket
     SigningTxsAtCheckpointIndex {
         xpub: WrappedBinary<Xpub>,
         checkpoint_index: u32,
     },
+    /// How many of the checkpoint inputs ready to be signed the signatory has
+    /// signed, for chunked submissions
+    #[returns(crate::interface::SigningProgress)]
+    CheckpointSigningProgress {
+        xpub: WrappedBinary<Xpub>,
+        checkpoint_index: u32,
+    },
     #[returns(bool)]
     ProcessedOutpoint { key: String },
     // Query index
@@ -186,13 +729,565 @@ pub enum QueryMsg {
     CompletedIndex {},
     #[returns(Option<u32>)]
     UnhandledConfirmedIndex {},
+    /// Whether the confirmation of the checkpoint at `index` was relayed
+    #[returns(bool)]
+    IsCheckpointConfirmed { index: u32 },
     // End query index
     #[returns(crate::interface::ChangeRates)]
     ChangeRates { interval: u64 },
     #[returns(u64)]
     ValueLocked {},
+    /// Expected seconds until a withdrawal requested now is mined on Bitcoin
+    #[returns(u64)]
+    EstimatedWithdrawalEta {},
     #[returns(bool)]
     CheckEligibleValidator { val_addr: Addr },
+    /// Whether a signer was removed for missing checkpoints, and when it may
+    /// request reinstatement
+    #[returns(JailStatusResponse)]
+    JailStatus { addr: Addr },
+    /// Tombstoned signatory keys
+    #[returns(Vec<crate::interface::Tombstone>)]
+    Tombstones {
+        start_after: Option<Binary>,
+        limit: Option<u32>,
+    },
+    /// The nBTC credited by the bridge for a processed deposit output
+    #[returns(Option<crate::interface::MintRecord>)]
+    MintRecord { txid: String, vout: u32 },
+    /// Deposits reconciled with `ReconcileReorgedDeposits`, by (txid, vout)
+    #[returns(Vec<crate::interface::ReorgedDeposit>)]
+    ReorgedDeposits {
+        start_after: Option<(String, u32)>,
+        limit: Option<u32>,
+    },
+    /// The nBTC minted for reorged deposits which could not be burned and was
+    /// not repaid by `Burn`, in nBTC units
+    #[returns(Uint128)]
+    BadDebt {},
+    /// nBTC burned with `Burn`, by id
+    #[returns(Vec<(u64, crate::interface::BurnRecord)>)]
+    Burns {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// The total nBTC burned with `Burn`, in nBTC units
+    #[returns(Uint128)]
+    TotalBurned {},
+    /// Expired deposits waiting to be claimed by the given destination
+    #[returns(Vec<crate::interface::ExpiredDeposit>)]
+    ExpiredDeposits {
+        dest: crate::interface::Dest,
+        /// The (txid, vout) of the last deposit of the previous page
+        start_after: Option<(String, u32)>,
+        limit: Option<u32>,
+    },
+    /// The deposits minted to the given destination
+    #[returns(Option<crate::interface::DestStats>)]
+    DestStats { dest: crate::interface::Dest },
+    /// The destinations with the most value deposited, largest first
+    #[returns(Vec<crate::interface::DestStats>)]
+    TopDestinations { limit: Option<u32> },
+    /// Unconfirmed deposits to the given destination reported by relayers
+    #[returns(Vec<crate::interface::PendingDeposit>)]
+    PendingDeposits {
+        dest: crate::interface::Dest,
+        /// The (txid, vout) of the last deposit of the previous page
+        start_after: Option<(String, u32)>,
+        limit: Option<u32>,
+    },
+    /// Mints waiting to be pulled by the given recipient with `ClaimMint`
+    #[returns(Vec<crate::interface::MintClaim>)]
+    MintClaims { recipient: String },
+    /// The balances of the fee pool and the reward pool
+    #[returns(PoolsResponse)]
+    Pools {},
+    /// Rewards credited to the given address and not yet claimed, in nBTC
+    /// units
+    #[returns(Uint128)]
+    RewardBalance { address: String },
+    /// The withdrawal allowance `granter` gave `spender`, if any
+    #[returns(Option<crate::interface::WithdrawalAllowance>)]
+    WithdrawalAllowance { granter: String, spender: String },
+    /// The withdrawal allowances given by `granter`, by spender
+    #[returns(Vec<(Addr, crate::interface::WithdrawalAllowance)>)]
+    WithdrawalAllowances {
+        granter: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Ibc memo transfers sent by the bridge and not yet acknowledged, by
+    /// (channel, sequence)
+    #[returns(Vec<crate::interface::IbcTransfer>)]
+    IbcTransfers {
+        start_after: Option<(String, u64)>,
+        limit: Option<u32>,
+    },
+    /// nBTC waiting to be claimed by the given address with `ClaimIbcRefund`
+    #[returns(Vec<Coin>)]
+    IbcRefunds { address: String },
+    /// The fees `RelayDeposit` callers may choose from
+    #[returns(Vec<Coin>)]
+    RelayerFeeOptions {},
+    #[returns(Vec<String>)]
+    FeeExemptions {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    #[returns(MetricsResponse)]
+    Metrics {},
+    /// The foundation spending policy of the active signatory set
+    #[returns(FoundationPolicyResponse)]
+    FoundationPolicy {},
+    /// The signatory set of the given checkpoint, including pruned ones which
+    /// were archived
+    #[returns(SigsetResponse)]
+    SigsetAt { index: u32 },
+    /// Whether deposits and withdrawals are paused, either by the owner or an
+    /// invariant violation, or while the light client is stalled
+    #[returns(bool)]
+    BridgePaused {},
+    /// Runs the next `ClockEndBlock` without persisting its state changes and
+    /// reports the transitions it would make
+    #[returns(crate::interface::EndBlockReport)]
+    SimulateEndBlock {},
+    /// The checkpoint and recovery changes made after the given block height,
+    /// for relayers to poll instead of querying the full state
+    #[returns(ChangesResponse)]
+    ChangesSince { height: u64 },
+    /// The most recent fee rate changes with their causes, newest first
+    #[returns(Vec<crate::interface::FeeRateChange>)]
+    FeeRateHistory { limit: Option<u32> },
+    /// Owner actions in the order they were made, paginated by entry id
+    #[returns(Vec<crate::interface::AdminLogEntry>)]
+    AdminLog {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// The active and secondary light clients
+    #[returns(LightClientsResponse)]
+    LightClients {},
+    /// An output script descriptor of the reserve of the given signatory set,
+    /// along with the keys involved, to monitor reserve funds independently
+    #[returns(WalletDescriptorResponse)]
+    WalletDescriptor { sigset_index: u32 },
+    /// The progress of the nBTC balance snapshot
+    #[returns(Option<crate::interface::BalanceSnapshot>)]
+    BalanceSnapshot {},
+    /// The balances recorded by the balance snapshot, by holder address
+    #[returns(Vec<(String, Uint128)>)]
+    SnapshotBalances {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// The inclusion proof of a holder's balance in the liabilities root of
+    /// the completed balance snapshot
+    #[returns(crate::interface::LiabilitiesProof)]
+    LiabilitiesProof { address: String },
+    /// The largest balances of the balance snapshot, largest first
+    #[returns(Vec<(String, Uint128)>)]
+    SnapshotTopBalances { limit: Option<u32> },
+    /// The withdrawals of checkpoints not yet fully signed when the balance
+    /// snapshot completed, which an emergency disbursal pays along with the
+    /// balances
+    #[returns(Vec<crate::interface::SnapshotWithdrawal>)]
+    SnapshotWithdrawals {
+        start_after: Option<u32>,
+        limit: Option<u32>,
+    },
+    /// The outputs an emergency disbursal pays to the holders of the completed
+    /// balance snapshot, by address. Holders without a recovery script or
+    /// with less than a satoshi are left out.
+    #[returns(Vec<crate::interface::DisbursalOutput>)]
+    DisbursalOutputs {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// The nBTC account of a holder as covered by an emergency disbursal
+    #[returns(NbtcAccountResponse)]
+    NbtcAccount { address: String },
+    /// The nBTC accounts of the top balances index of the balance snapshot,
+    /// largest first, starting after the given (balance, holder)
+    #[returns(Vec<NbtcAccountResponse>)]
+    NbtcAccounts {
+        start_after: Option<(Uint128, String)>,
+        limit: Option<u32>,
+    },
+    /// The message proposing the given owner messages on the owner cw3
+    /// multisig, ready to be submitted by one of its voters
+    #[returns(CosmosMsg)]
+    OwnerProposal {
+        title: String,
+        description: String,
+        msgs: Vec<ExecuteMsg>,
+    },
+    /// The stable code of every error the contract can return, errors are
+    /// prefixed with `[E<code>]`
+    #[returns(Vec<common_bitcoin::msg::ErrorCode>)]
+    ErrorCodes {},
+    /// Whether deposits to the address of `dest` on the given signatory set
+    /// are still safe
+    #[returns(DepositAddressStatusResponse)]
+    DepositAddressStatus {
+        dest: crate::interface::Dest,
+        sigset_index: u32,
+    },
+    /// The preferred commitment encoding of `dest` for new deposit addresses,
+    /// and its legacy encoding which is still accepted
+    #[returns(DestCommitmentResponse)]
+    DestCommitment { dest: crate::interface::Dest },
+    /// Why `SetRecoveryAddress` would reject the address, `None` if it would
+    /// be accepted
+    #[returns(Option<String>)]
+    CheckRecoveryAddress { btc_address: String },
+    /// The nBTC credited for a deposit of `value_sats` to the deposit address
+    /// of `dest` on the given sigset, if it were relayed now
+    #[returns(SimulateDepositResponse)]
+    SimulateDeposit {
+        value_sats: u64,
+        sigset_index: u32,
+        dest: crate::interface::Dest,
+    },
+    /// Runs the checks of `RelayDeposit` on a deposit output without relaying
+    /// it, so relayers can skip deposits which would be rejected. The output
+    /// script is only checked if `script_pubkey` is given.
+    #[returns(CheckRelayDepositResponse)]
+    CheckRelayDeposit {
+        txid: String,
+        vout: u32,
+        sigset_index: u32,
+        dest: crate::interface::Dest,
+        btc_height: u32,
+        script_pubkey: Option<Binary>,
+    },
+    /// Raw storage entries of a section, paginated by raw key
+    #[returns(Vec<StateEntry>)]
+    ExportState {
+        section: StateSection,
+        start_after: Option<Binary>,
+        limit: Option<u32>,
+    },
+    /// The migration of this bridge to a successor, if started
+    #[returns(Option<crate::interface::BridgeMigration>)]
+    BridgeMigration {},
+    /// Queried by a predecessor bridge before migrating here, returns whether
+    /// the migration is expected and the reserve script to pay
+    #[returns(MigrationHandshakeResponse)]
+    MigrationHandshake { predecessor: Addr },
+    #[returns(Option<crate::interface::SwapProvider>)]
+    SwapProvider { provider: Addr },
+    #[returns(Option<crate::interface::LightningSwap>)]
+    LightningSwap { swap_id: u64 },
+    /// A withdrawal whose checkpoint is still building
+    #[returns(Option<crate::interface::QueuedWithdrawal>)]
+    Withdrawal { withdrawal_id: u64 },
+    /// A withdrawal fee quote made by `PrepareWithdrawal`
+    #[returns(Option<crate::interface::WithdrawalQuote>)]
+    WithdrawalQuote { quote_id: u64 },
+    /// The scripts of the screened scripts list
+    #[returns(Vec<Binary>)]
+    ScreenedScripts {
+        start_after: Option<Binary>,
+        limit: Option<u32>,
+    },
+    /// Withdrawals held for review, by id
+    #[returns(Vec<(u64, crate::interface::HeldWithdrawal)>)]
+    HeldWithdrawals {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Inclusion proof of a hash committed with `CommitData`, once the
+    /// checkpoint committing it has been built
+    #[returns(crate::interface::AppDataProof)]
+    AppDataProof { checkpoint_index: u32, data: Binary },
+    /// Total bytes of checkpoint state reclaimed by signature compaction
+    #[returns(u64)]
+    ReclaimedSignatureBytes {},
+    /// The current validator registry
+    #[returns(Vec<crate::interface::ValidatorInfo>)]
+    ValidatorSet {},
+    #[returns(Option<crate::interface::ScheduledValidatorSet>)]
+    ScheduledValidatorSet {},
+    /// The version of the stored state layout
+    #[returns(u32)]
+    StateVersion {},
+    /// Contracts registered for checkpoint events, by hook id
+    #[returns(Vec<(u64, crate::interface::CheckpointHook)>)]
+    Hooks {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+}
+
+/// Numeric gauges of the bridge, meant to be scraped by monitoring.
+#[cw_serde]
+#[derive(Default)]
+pub struct MetricsResponse {
+    pub total_checkpoints: u32,
+    pub building_index: u32,
+    pub unconfirmed_checkpoints: u32,
+    /// Average number of seconds between signing and confirmation of the
+    /// last 20 confirmed checkpoints, by the times of the Bitcoin blocks they
+    /// were signed and confirmed at.
+    pub avg_confirmation_time: u64,
+    /// Fee rate of the building checkpoint, in satoshis per virtual byte.
+    pub fee_rate: u64,
+    pub fee_pool: i64,
+    /// Value of the reserve output of the last completed checkpoint, in
+    /// satoshis.
+    pub reserve_value: u64,
+    pub pending_withdrawals: u32,
+    pub header_height: u32,
+    pub header_time: u32,
+}
+
+#[cw_serde]
+pub struct FoundationPolicyResponse {
+    /// `None` if the active signatory set predates foundation policies
+    pub policy: Option<FoundationPolicy>,
+    pub foundation_signatories: Vec<Signatory>,
+    pub sigset_index: u32,
+}
+
+#[cw_serde]
+pub enum DepositAddressStatus {
+    /// The address belongs to the active signatory set
+    Active,
+    /// The address belongs to an older signatory set, deposits to it are
+    /// still accepted but should move to the replacement address
+    Stale,
+    /// Deposits to the address are past `max_deposit_age` and will not be
+    /// minted
+    Expired,
+}
+
+#[cw_serde]
+pub struct DepositAddressStatusResponse {
+    pub status: DepositAddressStatus,
+    /// The newest signatory set index a deposit to the destination was
+    /// relayed to, if any
+    pub latest_deposit_sigset_index: Option<u32>,
+    /// The deposit address of the destination on the active signatory set,
+    /// unless the queried address is active
+    pub replacement_address: Option<String>,
+}
+
+#[cw_serde]
+pub struct DestCommitmentResponse {
+    /// The version byte of `commitment`
+    pub version: u8,
+    /// The commitment to push in the deposit script of new addresses
+    pub commitment: Binary,
+    /// The unversioned commitment of addresses derived before commitments
+    /// were versioned
+    pub legacy_commitment: Binary,
+}
+
+/// Amounts in micro-nBTC.
+#[cw_serde]
+pub struct SimulateDepositResponse {
+    /// The deposited value before any fee
+    pub minted: Uint128,
+    /// The deposit's share of the checkpoint miner fee
+    pub miner_fee: Uint128,
+    /// The surcharge of deposits to lagging sigsets
+    pub deposit_fee: Uint128,
+    pub token_fee: Uint128,
+    pub relayer_fee: Uint128,
+    /// The amount minted to the destination
+    pub net_credit: Uint128,
+}
+
+/// Why `RelayDeposit` would reject a deposit.
+#[cw_serde]
+pub enum RelayDepositRejection {
+    BridgePaused {
+        reason: String,
+    },
+    /// The deposit's block is above the light client's tip
+    FutureHeight {
+        sidechain_btc_height: u32,
+    },
+    InsufficientConfirmations {
+        have: u32,
+        need: u32,
+    },
+    /// Deposits are frozen while migrating to a successor bridge
+    DepositsFrozen,
+    /// There is no checkpoint for the sigset index
+    UnknownSigset,
+    /// The output does not pay to the deposit address of the destination
+    ScriptMismatch,
+    AlreadyProcessed,
+    DepositsDisabled,
+}
+
+#[cw_serde]
+pub struct CheckRelayDepositResponse {
+    /// Why the deposit would be rejected, `None` if it would be accepted
+    pub rejection: Option<RelayDepositRejection>,
+    /// Whether the deposit is past the expiry of its sigset. Expired deposits
+    /// are accepted, but held or sent to a recovery transaction instead of
+    /// minted.
+    pub expired: bool,
+    /// The script the output must pay to, unless the sigset is unknown
+    pub script_pubkey: Option<Binary>,
+}
+
+/// A kind of transaction returned by `RawTxHex`.
+#[cw_serde]
+#[derive(Copy)]
+pub enum RawTxKind {
+    CheckpointTx,
+    RecoveryTx,
+    DisbursalTx,
+}
+
+#[cw_serde]
+pub struct RawTxResponse {
+    /// The consensus-encoded transaction, as accepted by `sendrawtransaction`
+    pub hex: String,
+    pub txid: String,
+}
+
+/// A group of stores exported together by `ExportState`.
+#[cw_serde]
+#[derive(Copy)]
+pub enum StateSection {
+    Config,
+    Checkpoints,
+    Outpoints,
+    Accounts,
+    RecoveryTxs,
+    SignatoryKeys,
+}
+
+/// A raw storage entry, holding the JSON value of a store under its key.
+#[cw_serde]
+pub struct StateEntry {
+    pub key: Binary,
+    pub value: Binary,
+}
+
+#[cw_serde]
+pub struct PoolsResponse {
+    /// The fee pool balance, in nBTC units. Negative while it owes miner fees
+    pub fee_pool: i64,
+    /// The fee pool balance above which fees move to the reward pool, in
+    /// nBTC units
+    pub fee_pool_target: u64,
+    pub reward_pool: u64,
+}
+
+#[cw_serde]
+pub struct ChangesResponse {
+    /// The changes made after the requested height, oldest first
+    pub changes: Vec<crate::interface::ChangeRecord>,
+    /// Whether changes after the requested height were already dropped, in
+    /// which case the full state has to be queried again
+    pub truncated: bool,
+}
+
+#[cw_serde]
+pub struct SigsetResponse {
+    pub sigset: SignatorySet,
+    pub threshold: (u64, u64),
+    /// The redeem script of the set with an empty destination commitment.
+    /// Deposit scripts replace the empty push before `OP_DROP` with the
+    /// commitment of their destination.
+    pub redeem_script_template: Binary,
+}
+
+#[cw_serde]
+pub struct MigrationHandshakeResponse {
+    pub accepted: bool,
+    pub sigset_index: u32,
+    /// The reserve output script of the building signatory set
+    pub reserve_script: Binary,
+}
+
+#[cw_serde]
+pub struct JailStatusResponse {
+    pub jailed: bool,
+    pub jailed_at: Option<u64>,
+    /// The time from which the signer may request reinstatement
+    pub eligible_at: Option<u64>,
+    pub reinstatement_requested: bool,
+}
+
+#[cw_serde]
+pub struct NbtcAccountResponse {
+    pub address: String,
+    /// The current nBTC balance of the holder
+    pub balance: Uint128,
+    /// The balance recorded by the latest balance snapshot
+    pub snapshot_balance: Option<Uint128>,
+    /// The output script the holder is paid to by an emergency disbursal
+    pub recovery_script: Option<Binary>,
+    /// Bridge time of the last deposit minted to or withdrawal from the holder
+    pub last_activity: Option<u64>,
+}
+
+#[cw_serde]
+pub struct LightClientsResponse {
+    pub active: Addr,
+    pub secondary: Option<Addr>,
+    /// The Bitcoin height both light clients agreed on at the last switch
+    pub switched_at: Option<u32>,
+}
+
+#[cw_serde]
+pub struct WalletDescriptorResponse {
+    /// A `raw()` descriptor of the set's reserve output script, which Bitcoin
+    /// Core can import as a watch-only wallet
+    pub descriptor: String,
+    pub address: String,
+    /// The weighted multisig script the reserve output commits to. Each
+    /// signatory adds its truncated voting power to an accumulator if its
+    /// signature is valid, and the accumulator must exceed the threshold.
+    /// Foundation keys can spend through the `OP_ELSE` branch.
+    pub redeem_script: Binary,
+    pub keys: Vec<DescriptorKey>,
+}
+
+#[cw_serde]
+pub struct DescriptorKey {
+    pub pubkey: Binary,
+    /// The key in descriptor notation, i.e. the signatory xpub followed by the
+    /// sigset index it is derived at, or the foundation xpub itself. `None`
+    /// if the xpub is no longer registered.
+    pub key: Option<String>,
+    pub voting_power: u64,
+    pub foundation: bool,
+}
+
+/// The subset of the cw3 multisig interface used to govern the contract.
+#[cw_serde]
+pub enum Cw3ExecuteMsg {
+    Propose {
+        title: String,
+        description: String,
+        msgs: Vec<CosmosMsg>,
+    },
+}
+
+#[cw_serde]
+pub enum Cw3QueryMsg {
+    ListVoters {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+}
+
+#[cw_serde]
+pub struct Cw3Voter {
+    pub addr: String,
+    pub weight: u64,
+}
+
+#[cw_serde]
+pub struct Cw3VoterListResponse {
+    pub voters: Vec<Cw3Voter>,
 }
 
 #[cw_serde]
@@ -200,5 +1295,27 @@ pub struct MigrateMsg {}
 
 #[cw_serde]
 pub enum SudoMsg {
-    ClockEndBlock { hash: Binary },
+    ClockEndBlock {
+        hash: Binary,
+    },
+    /// Checks the accounting invariants, pausing deposits and withdrawals if
+    /// any is violated
+    VerifyInvariants {},
+    /// Sent by the ibc hooks middleware when a packet sent by the bridge is
+    /// acknowledged or times out
+    IbcLifecycleComplete(IbcLifecycleComplete),
+}
+
+#[cw_serde]
+pub enum IbcLifecycleComplete {
+    IbcAck {
+        channel: String,
+        sequence: u64,
+        ack: String,
+        success: bool,
+    },
+    IbcTimeout {
+        channel: String,
+        sequence: u64,
+    },
 }
@@ -0,0 +1,142 @@
+//! Storage access counts per entrypoint call, to compare the cost of
+//! optimizations in tests. Only compiled with the `profiling` feature.
+
+use std::cell::{Cell, RefCell};
+
+use cosmwasm_schema::serde::Serialize;
+use cosmwasm_std::{to_json_vec, Order, Record, Storage};
+
+thread_local! {
+    static PROFILES: RefCell<Vec<GasProfile>> = RefCell::new(vec![]);
+}
+
+/// The storage accesses of one entrypoint call.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GasProfile {
+    /// `execute`, `query` or `sudo`
+    pub entrypoint: &'static str,
+    /// The name of the message variant
+    pub msg: String,
+    pub reads: u64,
+    pub bytes_read: u64,
+    pub writes: u64,
+    pub bytes_written: u64,
+    pub removes: u64,
+    /// Entries returned by range iterators
+    pub iterated: u64,
+}
+
+/// Takes the profiles recorded on this thread, oldest first.
+pub fn take_profiles() -> Vec<GasProfile> {
+    PROFILES.with(|profiles| profiles.take())
+}
+
+/// The variant name of a message, as its first JSON key.
+pub fn msg_name<T: Serialize>(msg: &T) -> String {
+    let json = to_json_vec(msg).unwrap_or_default();
+    String::from_utf8_lossy(&json)
+        .split('"')
+        .nth(1)
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// Lets the immutable storage of a query be wrapped in a `ProfiledStorage`.
+pub struct ReadOnlyStorage<'a>(pub &'a dyn Storage);
+
+impl Storage for ReadOnlyStorage<'_> {
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        self.0.get(key)
+    }
+
+    fn range<'b>(
+        &'b self,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = Record> + 'b> {
+        self.0.range(start, end, order)
+    }
+
+    fn set(&mut self, _key: &[u8], _value: &[u8]) {
+        panic!("queries cannot write to storage")
+    }
+
+    fn remove(&mut self, _key: &[u8]) {
+        panic!("queries cannot write to storage")
+    }
+}
+
+/// A storage counting the accesses made through it.
+pub struct ProfiledStorage<'a> {
+    inner: &'a mut dyn Storage,
+    reads: Cell<u64>,
+    bytes_read: Cell<u64>,
+    writes: u64,
+    bytes_written: u64,
+    removes: u64,
+    iterated: Cell<u64>,
+}
+
+impl<'a> ProfiledStorage<'a> {
+    pub fn new(inner: &'a mut dyn Storage) -> Self {
+        Self {
+            inner,
+            reads: Cell::new(0),
+            bytes_read: Cell::new(0),
+            writes: 0,
+            bytes_written: 0,
+            removes: 0,
+            iterated: Cell::new(0),
+        }
+    }
+
+    /// Records the counts as the profile of the given call.
+    pub fn record(self, entrypoint: &'static str, msg: String) {
+        let profile = GasProfile {
+            entrypoint,
+            msg,
+            reads: self.reads.get(),
+            bytes_read: self.bytes_read.get(),
+            writes: self.writes,
+            bytes_written: self.bytes_written,
+            removes: self.removes,
+            iterated: self.iterated.get(),
+        };
+        PROFILES.with(|profiles| profiles.borrow_mut().push(profile));
+    }
+}
+
+impl Storage for ProfiledStorage<'_> {
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        let value = self.inner.get(key);
+        self.reads.set(self.reads.get() + 1);
+        let len = value.as_ref().map_or(0, Vec::len) as u64;
+        self.bytes_read.set(self.bytes_read.get() + len);
+        value
+    }
+
+    fn range<'b>(
+        &'b self,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = Record> + 'b> {
+        Box::new(self.inner.range(start, end, order).inspect(|(key, value)| {
+            self.iterated.set(self.iterated.get() + 1);
+            let len = (key.len() + value.len()) as u64;
+            self.bytes_read.set(self.bytes_read.get() + len);
+        }))
+    }
+
+    fn set(&mut self, key: &[u8], value: &[u8]) {
+        self.writes += 1;
+        self.bytes_written += (key.len() + value.len()) as u64;
+        self.inner.set(key, value)
+    }
+
+    fn remove(&mut self, key: &[u8]) {
+        self.removes += 1;
+        self.inner.remove(key)
+    }
+}
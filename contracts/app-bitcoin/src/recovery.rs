@@ -3,15 +3,34 @@ use super::{
     signatory::SignatorySet,
     threshold_sig::Signature,
 };
-use crate::{interface::Dest, state::RECOVERY_TXS};
-use bitcoin::{OutPoint, Transaction, TxOut};
+use crate::{
+    interface::{
+        BitcoinConfig, Dest, PendingTransfer, RecoveryGroupStatus, SignedReserveOutput,
+        SigningDuty, SigningDutySource,
+    },
+    state::{RECOVERY_TXS, SIGNED_RESERVE_OUTPUTS},
+};
+use bitcoin::{Amount, OutPoint, ScriptBuf, Transaction, TxOut, Txid};
 use common_bitcoin::{
     adapter::Adapter,
     error::{ContractError, ContractResult},
     xpub::Xpub,
 };
 use cosmwasm_schema::serde::{Deserialize, Serialize};
-use cosmwasm_std::{Api, Storage};
+use cosmwasm_std::{Api, Coin, Storage};
+use std::collections::BTreeMap;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(crate = "cosmwasm_schema::serde", rename_all = "snake_case")]
+pub enum RecoveryTxKind {
+    /// Moves a single expired deposit to a new signatory set, or refunds it
+    #[default]
+    Recovery,
+    /// Consolidates expired dust deposits into an output of the reserve,
+    /// which is attached to the checkpoints once signed and credits the
+    /// depositors
+    Sweep,
+}
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 #[serde(crate = "cosmwasm_schema::serde")]
@@ -20,6 +39,16 @@ pub struct RecoveryTx {
     old_sigset_index: u32,
     new_sigset_index: u32,
     dest: Dest,
+    #[serde(default)]
+    kind: RecoveryTxKind,
+    /// The output paying to the reserve script of the new signatory set,
+    /// recorded in `SIGNED_RESERVE_OUTPUTS` once the transaction is signed
+    #[serde(default)]
+    reserve_vout: Option<u32>,
+    /// The transfers credited once the reserve output is attached, see
+    /// `SignedReserveOutput::credits`
+    #[serde(default)]
+    credits: Vec<PendingTransfer>,
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
@@ -28,6 +57,19 @@ pub struct SignedRecoveryTx {
     pub tx: Adapter<Transaction>,
     pub sigset_index: u32,
     pub dest: Dest,
+    #[serde(default)]
+    pub kind: RecoveryTxKind,
+    /// The transaction can be broadcast once the Bitcoin chain reaches this
+    /// height, 0 if it is not timelocked.
+    #[serde(default)]
+    pub earliest_broadcast_height: u32,
+}
+
+/// The lock time of a transaction spending a deposit confirmed at
+/// `btc_height`, which can't be mined before the deposit expired by at least
+/// the maximum deposit age in blocks.
+pub fn recovery_lock_time(btc_height: u32, config: &BitcoinConfig) -> u32 {
+    btc_height.saturating_add(config.deposit_age_limit_blocks())
 }
 
 #[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
@@ -35,13 +77,52 @@ pub struct SignedRecoveryTx {
 pub struct RecoveryTxs {}
 
 pub struct RecoveryTxInput<'a> {
-    pub expired_tx: Transaction,
-    pub vout: u32,
+    pub expired_outpoint: OutPoint,
+    pub value: u64,
     pub old_sigset: &'a SignatorySet,
     pub new_sigset: &'a SignatorySet,
     pub threshold: (u64, u64),
     pub fee_rate: u64,
     pub dest: Dest,
+    /// The script to pay the recovered funds to. If `None`, they are paid to
+    /// the new signatory set, still committed to `dest`.
+    pub refund_script: Option<ScriptBuf>,
+    /// See `recovery_lock_time`
+    pub lock_time: u32,
+    /// The encoding version of `dest` in the expired deposit's script, also
+    /// used for the output paid to the new signatory set
+    pub commitment_version: u8,
+    /// The part of `value` paid to the reserve script of the new signatory
+    /// set and credited to the fee pool, see
+    /// `BitcoinConfig::expired_deposit_fee_share`
+    pub fee_pool_share: u64,
+}
+
+/// An expired dust deposit spent by a sweep transaction.
+pub struct SweepInput {
+    pub outpoint: OutPoint,
+    pub value: u64,
+    pub sigset: SignatorySet,
+    pub dest: Dest,
+    pub commitment_version: u8,
+}
+
+pub struct SweepTxInput<'a> {
+    pub inputs: Vec<SweepInput>,
+    pub new_sigset: &'a SignatorySet,
+    pub threshold: (u64, u64),
+    pub fee_rate: u64,
+    /// The highest share of the swept value, in basis points, paid as fee
+    pub max_fee_bps: u64,
+    /// The destination recorded on the sweep transaction, i.e. the bridge
+    /// itself. The depositors are credited through its reserve output
+    pub dest: Dest,
+    /// The latest `recovery_lock_time` of the swept deposits
+    pub lock_time: u32,
+    /// The nBTC denom and its units per satoshi the depositors are credited
+    /// in
+    pub denom: String,
+    pub units_per_sat: u64,
 }
 
 impl RecoveryTxs {
@@ -50,32 +131,48 @@ impl RecoveryTxs {
         store: &mut dyn Storage,
         args: RecoveryTxInput,
     ) -> ContractResult<()> {
-        let expired_output = args
-            .expired_tx
-            .output
-            .get(args.vout as usize)
-            .ok_or_else(|| ContractError::Signer("Invalid recovery tx vout".to_string()))?;
-
+        let commitment = args
+            .dest
+            .versioned_commitment_bytes(args.commitment_version)?;
         let input = Input::new(
-            OutPoint::new(args.expired_tx.txid(), args.vout),
+            args.expired_outpoint,
             args.old_sigset,
-            &args.dest.commitment_bytes()?,
-            expired_output.value,
+            &commitment,
+            args.value,
             args.threshold,
         )?;
-        let script_pubkey = args
-            .new_sigset
-            .output_script(args.dest.commitment_bytes()?.as_slice(), args.threshold)?;
-        let output = TxOut {
-            value: expired_output.value,
-            script_pubkey,
+        let script_pubkey = match args.refund_script {
+            Some(script) => script,
+            None => args.new_sigset.output_script(&commitment, args.threshold)?,
         };
+        let fee_pool_share = args.fee_pool_share.min(args.value);
+        let reserve_script = args.new_sigset.output_script(&[0u8], args.threshold)?;
+        if fee_pool_share > 0 && script_pubkey == reserve_script {
+            return Err(ContractError::App(
+                "Cannot refund to the reserve script".to_string(),
+            ));
+        }
 
-        let mut tx = BitcoinTx::default();
+        let mut tx = BitcoinTx::with_lock_time(args.lock_time);
         tx.input.push(input);
-        tx.output.push(Adapter::new(output));
+        tx.output.push(Adapter::new(TxOut {
+            value: Amount::from_sat(args.value - fee_pool_share),
+            script_pubkey,
+        }));
+        if fee_pool_share > 0 {
+            tx.output.push(Adapter::new(TxOut {
+                value: Amount::from_sat(fee_pool_share),
+                script_pubkey: reserve_script.clone(),
+            }));
+        }
 
         tx.deduct_fee(args.fee_rate * tx.est_vsize()?)?;
+        // outputs too small to pay their part of the fee are dropped
+        let reserve_vout = tx
+            .output
+            .iter()
+            .position(|output| fee_pool_share > 0 && output.script_pubkey == reserve_script)
+            .map(|vout| vout as u32);
 
         tx.populate_input_sig_message(0)?;
 
@@ -86,23 +183,98 @@ impl RecoveryTxs {
                 old_sigset_index: args.old_sigset.index,
                 new_sigset_index: args.new_sigset.index,
                 dest: args.dest,
+                kind: RecoveryTxKind::Recovery,
+                reserve_vout,
+                credits: vec![],
             },
         )?;
 
         Ok(())
     }
 
+    /// Queues a sweep transaction paying the given dust deposits to the
+    /// reserve script of the new signatory set. Each depositor is credited
+    /// its deposit less its share of the miner fee once the output is
+    /// attached. Returns `false` without queueing anything if the miner fee
+    /// would exceed `max_fee_bps` of the swept value.
+    pub fn create_sweep_tx(
+        &mut self,
+        store: &mut dyn Storage,
+        args: SweepTxInput,
+    ) -> ContractResult<bool> {
+        let mut tx = BitcoinTx::with_lock_time(args.lock_time);
+        let mut value = 0;
+        let mut old_sigset_index = args.new_sigset.index;
+        for input in &args.inputs {
+            tx.input.push(Input::new(
+                input.outpoint,
+                &input.sigset,
+                &input
+                    .dest
+                    .versioned_commitment_bytes(input.commitment_version)?,
+                input.value,
+                args.threshold,
+            )?);
+            value += input.value;
+            old_sigset_index = old_sigset_index.min(input.sigset.index);
+        }
+        tx.output.push(Adapter::new(TxOut {
+            value: Amount::from_sat(value),
+            script_pubkey: args.new_sigset.output_script(&[0u8], args.threshold)?,
+        }));
+
+        let fee = args.fee_rate * tx.est_vsize()?;
+        if fee as u128 * 10_000 > value as u128 * args.max_fee_bps as u128 {
+            return Ok(false);
+        }
+        tx.deduct_fee(fee)?;
+        tx.populate_sig_messages()?;
+
+        let swept = tx.output.first().map_or(0, |output| output.value.to_sat());
+        let credits = args
+            .inputs
+            .iter()
+            .map(|input| {
+                let sats = (input.value as u128 * swept as u128)
+                    .checked_div(value as u128)
+                    .unwrap_or(0);
+                PendingTransfer::deposit(
+                    input.dest.clone(),
+                    Coin::new(sats * args.units_per_sat as u128, &args.denom),
+                    &input.outpoint,
+                )
+            })
+            .filter(|transfer| !transfer.coin.amount.is_zero())
+            .collect();
+
+        RECOVERY_TXS.push_back(
+            store,
+            &RecoveryTx {
+                tx,
+                old_sigset_index,
+                new_sigset_index: args.new_sigset.index,
+                dest: args.dest,
+                kind: RecoveryTxKind::Sweep,
+                reserve_vout: (swept > 0).then_some(0),
+                credits,
+            },
+        )?;
+
+        Ok(true)
+    }
+
     pub fn to_sign(
         &self,
         store: &dyn Storage,
         xpub: &Xpub,
     ) -> ContractResult<Vec<([u8; 32], u32)>> {
+        let mut pubkeys = xpub.derived_pubkeys();
         let mut msgs = vec![];
 
         for tx in RECOVERY_TXS.iter(store)? {
             let tx = tx?;
             for input in &tx.tx.input {
-                let pubkey = xpub.derive_pubkey(input.sigset_index)?;
+                let pubkey = pubkeys.get(input.sigset_index)?;
                 if input.signatures.needs_sig(pubkey.into()) {
                     msgs.push((input.signatures.message(), input.sigset_index));
                 }
@@ -112,6 +284,26 @@ impl RecoveryTxs {
         Ok(msgs)
     }
 
+    /// The signatures owed by `xpub` on each recovery transaction, in queue
+    /// order.
+    pub fn duties(&self, store: &dyn Storage, xpub: &Xpub) -> ContractResult<Vec<SigningDuty>> {
+        let mut pubkeys = xpub.derived_pubkeys();
+        let mut duties = vec![];
+        for (tx_index, tx) in RECOVERY_TXS.iter(store)?.enumerate() {
+            let tx = tx?;
+            if let Some(duty) = tx.tx.signing_duty(
+                &mut pubkeys,
+                SigningDutySource::Recovery {
+                    tx_index: tx_index as u32,
+                },
+                None,
+            )? {
+                duties.push(duty);
+            }
+        }
+        Ok(duties)
+    }
+
     pub fn to_single_sign(
         &self,
         store: &dyn Storage,
@@ -137,14 +329,105 @@ impl RecoveryTxs {
         Ok(msgs)
     }
 
+    /// The messages `xpub` still has to sign for the recovery transaction
+    /// inputs spending outputs of the signatory set at `sigset_index`, in
+    /// queue order.
+    pub fn to_sign_group(
+        &self,
+        store: &dyn Storage,
+        xpub: &Xpub,
+        sigset_index: u32,
+    ) -> ContractResult<Vec<([u8; 32], u32)>> {
+        let pubkey = xpub.derive_pubkey(sigset_index)?;
+        let mut msgs = vec![];
+        for tx in RECOVERY_TXS.iter(store)? {
+            for input in tx?.tx.input {
+                if input.sigset_index == sigset_index && input.signatures.needs_sig(pubkey.into()) {
+                    msgs.push((input.signatures.message(), input.sigset_index));
+                }
+            }
+        }
+        Ok(msgs)
+    }
+
+    /// The signing progress of the recovery transaction inputs, grouped by
+    /// the signatory set they spend from. If `xpub` is given, also counts
+    /// the signatures it still owes to each group.
+    pub fn groups(
+        &self,
+        store: &dyn Storage,
+        xpub: Option<&Xpub>,
+    ) -> ContractResult<Vec<RecoveryGroupStatus>> {
+        let mut pubkeys = xpub.map(Xpub::derived_pubkeys);
+        let mut groups: BTreeMap<u32, RecoveryGroupStatus> = BTreeMap::new();
+        for (tx_index, tx) in RECOVERY_TXS.iter(store)?.enumerate() {
+            for input in tx?.tx.input {
+                let group =
+                    groups
+                        .entry(input.sigset_index)
+                        .or_insert_with(|| RecoveryGroupStatus {
+                            sigset_index: input.sigset_index,
+                            tx_indexes: vec![],
+                            inputs: 0,
+                            signed_inputs: 0,
+                            owed: None,
+                        });
+                if group.tx_indexes.last() != Some(&(tx_index as u32)) {
+                    group.tx_indexes.push(tx_index as u32);
+                }
+                group.inputs += 1;
+                if input.signatures.signed() {
+                    group.signed_inputs += 1;
+                }
+                if let Some(pubkeys) = &mut pubkeys {
+                    let pubkey = pubkeys.get(input.sigset_index)?;
+                    let owed = group.owed.get_or_insert(0);
+                    if input.signatures.needs_sig(pubkey.into()) {
+                        *owed += 1;
+                    }
+                }
+            }
+        }
+        Ok(groups.into_values().collect())
+    }
+
+    /// Signs the recovery transactions with the given signatures, returning
+    /// the txids of the transactions which became fully signed.
     pub fn sign(
         &mut self,
         api: &dyn Api,
         store: &mut dyn Storage,
         xpub: &Xpub,
         sigs: Vec<Signature>,
-    ) -> ContractResult<()> {
+    ) -> ContractResult<Vec<Txid>> {
+        self.sign_inputs(api, store, xpub, sigs, None)
+    }
+
+    /// Signs only the recovery transaction inputs spending outputs of the
+    /// signatory set at `sigset_index`, in the order of `to_sign_group`, so
+    /// groups progress independently of each other.
+    pub fn sign_group(
+        &mut self,
+        api: &dyn Api,
+        store: &mut dyn Storage,
+        xpub: &Xpub,
+        sigset_index: u32,
+        sigs: Vec<Signature>,
+    ) -> ContractResult<Vec<Txid>> {
+        self.sign_inputs(api, store, xpub, sigs, Some(sigset_index))
+    }
+
+    fn sign_inputs(
+        &mut self,
+        api: &dyn Api,
+        store: &mut dyn Storage,
+        xpub: &Xpub,
+        sigs: Vec<Signature>,
+        sigset_index: Option<u32>,
+    ) -> ContractResult<Vec<Txid>> {
+        let mut pubkeys = xpub.derived_pubkeys();
         let mut sig_index = 0;
+        let mut signed_txids = vec![];
 
         if sigs.is_empty() {
             return Err(ContractError::Signer(
@@ -156,19 +439,21 @@ impl RecoveryTxs {
             let mut tx = RECOVERY_TXS.get(store, i)?.ok_or_else(|| {
                 ContractError::Signer("Error getting recovery transaction".to_string())
             })?;
+            let was_signed = tx.tx.signed();
 
             for k in 0..tx.tx.input.len() {
                 let input = tx.tx.input.get_mut(k).unwrap();
-                let pubkey = xpub.derive_pubkey(input.sigset_index)?;
+                if sigset_index.map_or(false, |index| index != input.sigset_index) {
+                    continue;
+                }
+                let pubkey = pubkeys.get(input.sigset_index)?;
 
                 if !input.signatures.needs_sig(pubkey.into()) {
                     continue;
                 }
 
                 if sig_index >= sigs.len() {
-                    return Err(ContractError::Signer(
-                        "Not enough signatures supplied for recovery transaction".to_string(),
-                    ));
+                    return Err(ContractError::NotEnoughSignatures {});
                 }
                 let sig = &sigs[sig_index];
                 sig_index += 1;
@@ -181,17 +466,56 @@ impl RecoveryTxs {
                 }
             }
 
+            if !was_signed && tx.tx.signed() {
+                let txid = tx.tx.to_bitcoin_tx()?.txid();
+                if let Some(vout) = tx.reserve_vout {
+                    let txid = txid.to_string();
+                    SIGNED_RESERVE_OUTPUTS.save(
+                        store,
+                        (&txid, vout),
+                        &SignedReserveOutput {
+                            txid: txid.clone(),
+                            vout,
+                            amount: tx.tx.output[vout as usize].value.to_sat(),
+                            sigset_index: tx.new_sigset_index,
+                            lock_time: tx.tx.lock_time,
+                            credit_fee_pool: tx.kind == RecoveryTxKind::Recovery,
+                            credits: tx.credits.clone(),
+                        },
+                    )?;
+                }
+                signed_txids.push(txid);
+            }
             // update tx
             RECOVERY_TXS.set(store, i, &tx)?;
         }
 
         if sig_index != sigs.len() {
-            return Err(ContractError::Signer(
-                "Excess signatures supplied for recovery transaction".to_string(),
-            ));
+            return Err(ContractError::ExcessSignatures {});
         }
 
-        Ok(())
+        Ok(signed_txids)
+    }
+
+    /// The txid of the recovery transaction at `index` in the queue.
+    pub fn txid(&self, store: &dyn Storage, index: u32) -> ContractResult<Txid> {
+        let tx = RECOVERY_TXS
+            .get(store, index)?
+            .ok_or_else(|| ContractError::App("Recovery transaction not found".to_string()))?;
+        Ok(tx.tx.to_bitcoin_tx()?.txid())
+    }
+
+    /// The recovery transaction at `index` in the queue, once fully signed.
+    pub fn signed_tx(&self, store: &dyn Storage, index: u32) -> ContractResult<Transaction> {
+        let tx = RECOVERY_TXS
+            .get(store, index)?
+            .ok_or_else(|| ContractError::App("Recovery transaction not found".to_string()))?;
+        if !tx.tx.signed() {
+            return Err(ContractError::App(
+                "Recovery transaction is not fully signed".to_string(),
+            ));
+        }
+        tx.tx.to_bitcoin_tx()
     }
 
     pub fn signed(&self, store: &dyn Storage) -> ContractResult<Vec<SignedRecoveryTx>> {
@@ -204,6 +528,8 @@ impl RecoveryTxs {
                     tx: Adapter::new(tx.tx.to_bitcoin_tx()?),
                     sigset_index: tx.new_sigset_index,
                     dest: tx.dest.clone(),
+                    kind: tx.kind,
+                    earliest_broadcast_height: tx.tx.lock_time,
                 });
             }
         }
@@ -0,0 +1,86 @@
+use crate::{
+    interface::BitcoinConfig,
+    signatory::SignatorySet,
+    state::{FEE_POOL, REWARD_BALANCES, REWARD_POOL, SIG_KEYS, VALIDATORS},
+    threshold_sig::Pubkey,
+};
+use common_bitcoin::error::ContractResult;
+use cosmwasm_std::{Addr, Order, StdResult, Storage};
+
+/// Moves the part of the fee pool above `fee_pool_target_balance` into the
+/// reward pool. Returns the amount moved, in nBTC units.
+pub fn accrue_fee_pool_surplus(
+    store: &mut dyn Storage,
+    config: &BitcoinConfig,
+) -> ContractResult<u64> {
+    let target = (config.fee_pool_target_balance * config.units_per_sat) as i64;
+    let fee_pool = FEE_POOL.may_load(store)?.unwrap_or_default();
+    if fee_pool <= target {
+        return Ok(0);
+    }
+    let surplus = (fee_pool - target) as u64;
+    FEE_POOL.save(store, &target)?;
+    let pool = REWARD_POOL.may_load(store)?.unwrap_or_default();
+    REWARD_POOL.save(store, &(pool + surplus))?;
+    Ok(surplus)
+}
+
+/// Credits the reward pool to the relayers of a confirmed checkpoint and the
+/// signatories of its signatory set. Relayers split `relayer_reward_bps` of
+/// the pool evenly, signatories split the rest by voting power. Rounding
+/// dust and the shares of signatories which left the validator set stay in
+/// the pool. Returns the amount credited.
+pub fn distribute_rewards(
+    store: &mut dyn Storage,
+    config: &BitcoinConfig,
+    sigset: &SignatorySet,
+    relayers: &[Addr],
+) -> ContractResult<u64> {
+    let pool = REWARD_POOL.may_load(store)?.unwrap_or_default();
+    if pool == 0 {
+        return Ok(0);
+    }
+    let mut credits: Vec<(Addr, u64)> = vec![];
+
+    let relayer_share = if relayers.is_empty() {
+        0
+    } else {
+        (pool as u128 * config.relayer_reward_bps.min(10_000) as u128 / 10_000) as u64
+    };
+    for relayer in relayers {
+        credits.push((relayer.clone(), relayer_share / relayers.len() as u64));
+    }
+
+    let signatory_share = pool - relayer_share;
+    let total_vp = sigset.present_vp();
+    if total_vp > 0 {
+        let keys = SIG_KEYS
+            .range(store, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        for (cons_key, xpub) in keys {
+            let pubkey: Pubkey = xpub.derive_pubkey(sigset.index())?.into();
+            let Some(signatory) = sigset.iter().find(|signatory| signatory.pubkey == pubkey) else {
+                continue;
+            };
+            let Some((_, addr)) = VALIDATORS.may_load(store, &cons_key)? else {
+                continue;
+            };
+            let amount = (signatory_share as u128 * signatory.voting_power as u128
+                / total_vp as u128) as u64;
+            credits.push((Addr::unchecked(addr), amount));
+        }
+    }
+
+    let mut credited = 0;
+    for (addr, amount) in credits {
+        if amount == 0 {
+            continue;
+        }
+        REWARD_BALANCES.update(store, &addr, |balance| -> StdResult<_> {
+            Ok(balance.unwrap_or_default() + amount)
+        })?;
+        credited += amount;
+    }
+    REWARD_POOL.save(store, &(pool - credited))?;
+    Ok(credited)
+}
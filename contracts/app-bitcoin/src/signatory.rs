@@ -1,31 +1,58 @@
 use std::cmp::Ordering;
 
 use crate::app::ConsensusKey;
-use crate::constants::MAX_SIGNATORIES;
+use crate::constants::{INSTANCE_COMMITMENT_VERSION, MAX_SIGNATORIES};
 use crate::state::get_validators;
 use crate::state::FOUNDATION_KEYS;
+use crate::state::FOUNDATION_POLICY;
+use crate::state::JAILED_SIGNERS;
 use crate::state::SIG_KEYS;
+use crate::state::TOMBSTONED_XPUBS;
 use crate::state::XPUBS;
 
-use super::threshold_sig::Pubkey;
-use bitcoin::blockdata::opcodes::all::OP_EQUAL;
-use bitcoin::blockdata::opcodes::all::{
-    OP_ADD, OP_CHECKSIG, OP_DROP, OP_ELSE, OP_ENDIF, OP_GREATERTHAN, OP_IF, OP_SWAP,
+use super::threshold_sig::{Pubkey, SigScheme};
+use bitcoin::opcodes::all::{
+    OP_ADD, OP_CHECKSIG, OP_CSV, OP_DROP, OP_ELSE, OP_ENDIF, OP_EQUAL, OP_GREATERTHAN,
+    OP_GREATERTHANOREQUAL, OP_IF, OP_RETURN, OP_SWAP,
 };
-use bitcoin::blockdata::opcodes::{self, OP_FALSE};
-use bitcoin::blockdata::script::{read_scriptint, Instruction};
-use bitcoin::Script;
-use bitcoin_script::bitcoin_script as script;
+use bitcoin::opcodes::{Opcode, OP_FALSE};
+use bitcoin::script::{read_scriptint, Builder, Instruction, PushBytes};
+use bitcoin::{Script, ScriptBuf, Sequence};
 use common_bitcoin::{
     error::{ContractError, ContractResult},
     xpub::Xpub,
 };
 use cosmwasm_schema::schemars::JsonSchema;
 use cosmwasm_schema::serde::{Deserialize, Serialize};
+use cosmwasm_std::Addr;
+use cosmwasm_std::Binary;
 use cosmwasm_std::Order;
+use cosmwasm_std::StdResult;
 use cosmwasm_std::Storage;
+use sha2::{Digest, Sha256};
 // use ed::Encode;
 
+/// The key of an xpub in the set of submitted signatory keys, which ignores
+/// its derivation metadata.
+pub fn normalized_xpub_key(xpub: &Xpub) -> Vec<u8> {
+    let mut normalized_xpub = *xpub;
+    normalized_xpub.key.child_number = 0.into();
+    normalized_xpub.key.depth = 0;
+    normalized_xpub.key.parent_fingerprint = Default::default();
+    normalized_xpub.encode().to_vec()
+}
+
+/// The message a validator must sign with the key derived from their xpub at
+/// index 0 when registering it with `SetSignatoryKey`, proving they control
+/// the key and binding it to this contract and their own address.
+pub fn signatory_key_challenge(contract: &Addr, signer: &Addr, xpub: &Xpub) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(contract.as_bytes());
+    hasher.update(signer.as_bytes());
+    hasher.update(xpub.encode());
+    hasher.finalize().into()
+}
+
 /// The maximum number of signatories in a signatory set.
 ///
 /// Signatory sets will be constructed by iterating over the validator set in
@@ -49,6 +76,94 @@ pub struct Signatory {
     pub pubkey: Pubkey,
 }
 
+/// The spending policy of the foundation escape hatch in the reserve redeem
+/// script.
+///
+/// The foundation keys can spend a reserve output once `threshold` of them
+/// have signed and, if `inactivity_timelock` is non-zero, the output has been
+/// left unspent for that many seconds (enforced with `OP_CHECKSEQUENCEVERIFY`).
+/// Since every checkpoint spends the previous reserve output, this only
+/// becomes spendable after the checkpointing process has stalled.
+#[derive(Clone, Debug, PartialOrd, PartialEq, Eq, Ord, Deserialize, Serialize, JsonSchema)]
+#[serde(crate = "cosmwasm_schema::serde")]
+#[schemars(crate = "cosmwasm_schema::schemars")]
+pub struct FoundationPolicy {
+    /// The ratio of foundation keys which must sign, as (numerator,
+    /// denominator).
+    pub threshold: (u64, u64),
+    /// The relative timelock of the foundation path, in seconds. It is rounded
+    /// up to the 512 second granularity of BIP68.
+    pub inactivity_timelock: u64,
+}
+
+impl FoundationPolicy {
+    /// Checks the policy can be encoded in a redeem script.
+    pub fn validate(&self) -> ContractResult<()> {
+        let (numerator, denominator) = self.threshold;
+        if numerator == 0 || denominator == 0 || numerator > denominator {
+            return Err(ContractError::App(
+                "Invalid foundation policy threshold".to_string(),
+            ));
+        }
+        self.sequence()?;
+        Ok(())
+    }
+
+    /// The number of signatures required out of `n` foundation keys.
+    pub fn required_signatures(&self, n: u64) -> u64 {
+        let (numerator, denominator) = self.threshold;
+        (n * numerator + denominator - 1) / denominator
+    }
+
+    /// The relative timelock encoded as an input sequence number.
+    pub fn sequence(&self) -> ContractResult<Option<Sequence>> {
+        if self.inactivity_timelock == 0 {
+            return Ok(None);
+        }
+        let seconds: u32 = self.inactivity_timelock.try_into()?;
+        let sequence = Sequence::from_seconds_ceil(seconds)
+            .map_err(|_| ContractError::App("Foundation timelock is too long".to_string()))?;
+        Ok(Some(sequence))
+    }
+}
+
+/// How voting power is encoded as signature weights in the redeem script of a
+/// signatory set.
+#[derive(
+    Clone, Copy, Debug, Default, PartialOrd, PartialEq, Eq, Ord, Deserialize, Serialize, JsonSchema,
+)]
+#[serde(crate = "cosmwasm_schema::serde", rename_all = "snake_case")]
+#[schemars(crate = "cosmwasm_schema::schemars")]
+pub enum ScriptTemplate {
+    /// Voting power truncated to 23 bits, taking up to 4 bytes per weight.
+    #[default]
+    Raw,
+    /// Voting power normalized to `precision` units of the set's present
+    /// voting power, rounded down, so each weight takes at most 3 bytes and
+    /// small weights a single opcode.
+    ///
+    /// Rounding costs each signer less than one unit, so signing requires
+    /// slightly more than the threshold (see
+    /// [`SignatorySet::signing_threshold`]).
+    Bucketed { precision: u8 },
+}
+
+impl ScriptTemplate {
+    /// Checks the rounding loss of bucketed weights stays small compared to
+    /// the threshold, even when every signatory rounds down.
+    pub fn validate(&self) -> ContractResult<()> {
+        if let Self::Bucketed { precision } = self {
+            if (*precision as u64) < 5 * MAX_SIGNATORIES {
+                return Err(ContractError::App(format!(
+                    "Bucketed script precision must be at least {}",
+                    5 * MAX_SIGNATORIES
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
 /// A signatory set is a set of signers who secure a UTXO in the network
 /// reserve.
 ///
@@ -87,9 +202,42 @@ pub struct SignatorySet {
     /// add default
     #[serde(default)]
     pub foundation_signatories: Vec<Signatory>,
+
+    /// The foundation spending policy at the time this set was created. Sets
+    /// created before policies existed use the signatory threshold and no
+    /// timelock.
+    #[serde(default)]
+    pub foundation_policy: Option<FoundationPolicy>,
+
+    /// The commitment to the bridge instance which created this set, pushed
+    /// after the destination commitment. Sets created before instance
+    /// commitments were enabled have none.
+    #[serde(default)]
+    pub instance_commitment: Option<Binary>,
+
+    /// The encoding of voting power in the redeem script. Sets created before
+    /// templates were configurable use the raw template.
+    #[serde(default)]
+    pub script_template: ScriptTemplate,
 }
 
-type IterItem<'a> = std::result::Result<Instruction<'a>, bitcoin::blockdata::script::Error>;
+type IterItem<'a> = std::result::Result<Instruction<'a>, bitcoin::script::Error>;
+
+/// Borrows `bytes` as a script data push.
+fn push_bytes(bytes: &[u8]) -> ContractResult<&PushBytes> {
+    bytes
+        .try_into()
+        .map_err(|_| ContractError::App("Script push exceeds the size limit".into()))
+}
+
+/// The commitment of a signatory set to the bridge contract which created it:
+/// a version byte, the set's index and the hash of the contract address.
+pub fn instance_commitment(contract: &Addr, index: u32) -> Binary {
+    let mut commitment = vec![INSTANCE_COMMITMENT_VERSION];
+    commitment.extend(index.to_be_bytes());
+    commitment.extend(Sha256::digest(contract.as_bytes()));
+    Binary::from(commitment)
+}
 
 impl SignatorySet {
     /// Creates a signatory set based on the current validator set.
@@ -105,6 +253,9 @@ impl SignatorySet {
             index,
             signatories: vec![],
             foundation_signatories: vec![],
+            foundation_policy: FOUNDATION_POLICY.may_load(store)?,
+            instance_commitment: None,
+            script_template: ScriptTemplate::Raw,
         };
 
         let val_set = get_validators(store)?;
@@ -142,7 +293,7 @@ impl SignatorySet {
 
     // FIXME: make this function can pick up foundation sigsets
     pub fn from_script(
-        script: &bitcoin::Script,
+        script: &Script,
         threshold_ratio: (u64, u64),
     ) -> ContractResult<(Self, Vec<u8>)> {
         fn take_instruction<'a>(
@@ -162,7 +313,7 @@ impl SignatorySet {
                 return Err(ContractError::App("Expected OP_PUSHBYTES".into()));
             };
 
-            Ok(bytes)
+            Ok(bytes.as_bytes())
         }
 
         fn take_key<'a>(ins: &mut impl Iterator<Item = IterItem<'a>>) -> ContractResult<Pubkey> {
@@ -182,13 +333,13 @@ impl SignatorySet {
 
         fn take_op<'a>(
             ins: &mut impl Iterator<Item = IterItem<'a>>,
-            expected_op: opcodes::All,
-        ) -> ContractResult<opcodes::All> {
+            expected_op: Opcode,
+        ) -> ContractResult<Opcode> {
             let instruction = take_instruction(ins)?;
 
             let op = match instruction {
                 Instruction::Op(op) => op,
-                Instruction::PushBytes(&[]) => OP_FALSE,
+                Instruction::PushBytes(bytes) if bytes.is_empty() => OP_FALSE,
                 _ => return Err(ContractError::App(format!("Expected {:?}", expected_op))),
             };
 
@@ -260,7 +411,7 @@ impl SignatorySet {
                 .clone()
                 .map_err(|_| ContractError::App("Failed to read script".into()))?;
 
-            if let Instruction::Op(opcodes::all::OP_SWAP) = next {
+            if let Instruction::Op(OP_SWAP) = next {
                 sigs.push(take_nth_signatory(&mut ins)?);
             } else {
                 break;
@@ -269,6 +420,10 @@ impl SignatorySet {
 
         let expected_threshold = take_threshold(&mut ins)?;
         let commitment = take_commitment(&mut ins)?;
+        let instance_commitment = match ins.peek() {
+            Some(Ok(Instruction::PushBytes(_))) => Some(Binary::from(take_commitment(&mut ins)?)),
+            _ => None,
+        };
 
         let total_vp: u64 = sigs.iter().map(|s| s.voting_power).sum();
         let mut sigset = Self {
@@ -278,6 +433,11 @@ impl SignatorySet {
             create_time: 0,
             index: 0,
             foundation_signatories: vec![],
+            foundation_policy: None,
+            instance_commitment,
+            // bucketed weights parse as the raw weights of a set whose
+            // present voting power is the precision, giving the same script
+            script_template: ScriptTemplate::Raw,
         };
 
         for _ in 0..100 {
@@ -334,6 +494,47 @@ impl SignatorySet {
         ((self.present_vp as u128) * numerator as u128 / denominator as u128) as u64
     }
 
+    /// The scheme its signatories sign with. Every script template is spent
+    /// with ECDSA multisig so far.
+    pub fn sig_scheme(&self) -> SigScheme {
+        match self.script_template {
+            ScriptTemplate::Raw | ScriptTemplate::Bucketed { .. } => SigScheme::Ecdsa,
+        }
+    }
+
+    /// The voting power signers must exceed for their weights to satisfy the
+    /// redeem script. Bucketed weights lose less than one unit per signatory
+    /// to rounding, which is made up for with extra voting power.
+    pub fn signing_threshold(&self, threshold: (u64, u64)) -> u64 {
+        match self.script_template {
+            ScriptTemplate::Raw => self.signature_threshold(threshold),
+            ScriptTemplate::Bucketed { precision } => {
+                let units = self.script_threshold(threshold) + self.signatories.len() as u64;
+                ((self.present_vp as u128) * units as u128 / precision as u128) as u64
+            }
+        }
+    }
+
+    /// The weight of a signatory's voting power in the redeem script.
+    fn script_weight(&self, voting_power: u64) -> u64 {
+        match self.script_template {
+            ScriptTemplate::Raw => voting_power >> self.get_truncation(23),
+            ScriptTemplate::Bucketed { precision } => {
+                ((voting_power as u128) * precision as u128 / self.present_vp.max(1) as u128) as u64
+            }
+        }
+    }
+
+    /// The weight the signed weights must exceed in the redeem script.
+    fn script_threshold(&self, threshold: (u64, u64)) -> u64 {
+        match self.script_template {
+            ScriptTemplate::Raw => self.signature_threshold(threshold) >> self.get_truncation(23),
+            // the exact threshold is a share of `precision` units, signers'
+            // rounded down weights exceeding it always exceed the threshold
+            ScriptTemplate::Bucketed { precision } => precision as u64 * threshold.0 / threshold.1,
+        }
+    }
+
     /// The quorum threshold required for the signatory set to be valid.
     pub fn quorum_threshold(&self) -> u64 {
         self.possible_vp / 2
@@ -377,19 +578,17 @@ impl SignatorySet {
     /// this signatory set.
     ///
     /// This script is essentially a weighted multisig script, where each
-    /// signatory has a weight equal to their voting power. It is specified in
-    /// the input witness when the UTXO is spent. The output contains a hash of
-    /// this script, since it is a pay-to-witness-script-hash (P2WSH) output.
-    pub fn redeem_script(&self, dest: &[u8], threshold: (u64, u64)) -> ContractResult<Script> {
-        // We will truncate voting power values to 23 bits, to reduce the amount
-        // of bytes used in the resulting encoded script. In practice, this
-        // should be enough precision for effective voting power threshold
-        // checking. We use 23 bits since Bitcoin script reserves one bit as the
-        // sign bit, making our resulting integer value use 3 bytes. The value
-        // returned here is the number of bits of precision to remove from our
-        // 64-bit voting power values.
-        let truncation = self.get_truncation(23);
-
+    /// signatory has a weight derived from their voting power according to
+    /// the set's `script_template`. It is specified in the input witness when
+    /// the UTXO is spent. The output contains a hash of this script, since it
+    /// is a pay-to-witness-script-hash (P2WSH) output.
+    pub fn redeem_script(&self, dest: &[u8], threshold: (u64, u64)) -> ContractResult<ScriptBuf> {
+        // With the raw template, voting power values are truncated to 23 bits,
+        // to reduce the amount of bytes used in the resulting encoded script.
+        // In practice, this should be enough precision for effective voting
+        // power threshold checking. We use 23 bits since Bitcoin script
+        // reserves one bit as the sign bit, making our resulting integer value
+        // use 3 bytes.
         let mut iter = self.signatories.iter();
 
         // First signatory
@@ -397,7 +596,7 @@ impl SignatorySet {
             ContractError::App("Cannot create redeem script for empty signatory set".to_string())
         })?;
 
-        let truncated_voting_power = signatory.voting_power >> truncation;
+        let truncated_voting_power = self.script_weight(signatory.voting_power);
 
         let mut bytes = vec![];
         // Push the pubkey onto the stack, check the signature against it, and
@@ -405,43 +604,47 @@ impl SignatorySet {
         // otherwise leave 0 (this number will be an accumulator of voting power
         // which had valid signatures, and will be added to as we check the
         // remaining signatures).
-        let script = script! {
-            <[0].as_slice()>
-            OP_EQUAL
-            OP_IF
-                <signatory.pubkey.as_slice()> OP_CHECKSIG
-                OP_IF
-                    <truncated_voting_power as i64>
-                OP_ELSE
-                    0
-                OP_ENDIF
-        };
+        let script = Builder::new()
+            .push_slice([0u8])
+            .push_opcode(OP_EQUAL)
+            .push_opcode(OP_IF)
+            .push_slice(push_bytes(signatory.pubkey.as_slice())?)
+            .push_opcode(OP_CHECKSIG)
+            .push_opcode(OP_IF)
+            .push_int(truncated_voting_power as i64)
+            .push_opcode(OP_ELSE)
+            .push_int(0)
+            .push_opcode(OP_ENDIF)
+            .into_script();
         bytes.extend(script.into_bytes());
 
         // All other signatories
         for signatory in iter {
-            let truncated_voting_power = signatory.voting_power >> truncation;
+            let truncated_voting_power = self.script_weight(signatory.voting_power);
             // Swap to move the current voting power accumulator down the stack
             // (leaving the next signature at the top of the stack), push the
             // pubkey onto the stack, check the signature against it, and add to
             // the voting power accumulator if the signature was valid.
-            let script = script! {
-                OP_SWAP
-                <signatory.pubkey.as_slice()> OP_CHECKSIG
-                OP_IF
-                    <truncated_voting_power as i64> OP_ADD
-                OP_ENDIF
-            };
+            let script = Builder::new()
+                .push_opcode(OP_SWAP)
+                .push_slice(push_bytes(signatory.pubkey.as_slice())?)
+                .push_opcode(OP_CHECKSIG)
+                .push_opcode(OP_IF)
+                .push_int(truncated_voting_power as i64)
+                .push_opcode(OP_ADD)
+                .push_opcode(OP_ENDIF)
+                .into_script();
             bytes.extend(&script.into_bytes());
         }
 
         // Threshold check
-        let truncated_threshold = self.signature_threshold(threshold) >> truncation;
+        let truncated_threshold = self.script_threshold(threshold);
         // Check that accumulator of voting power which had valid signatures
         // (now a final sum) is greater than the threshold.
-        let script = script! {
-            <truncated_threshold as i64> OP_GREATERTHAN
-        };
+        let script = Builder::new()
+            .push_int(truncated_threshold as i64)
+            .push_opcode(OP_GREATERTHAN)
+            .into_script();
         bytes.extend(&script.into_bytes());
 
         // Depositor data commitment, vector is the same
@@ -449,9 +652,22 @@ impl SignatorySet {
         // Add a commitment of arbitrary data so that deposits can be tied to a
         // specific destination, then remove it from the stack so that the final
         // value on the stack is the threshold check result.
-        let script = script!(<dest> OP_DROP);
+        let script = Builder::new()
+            .push_slice(push_bytes(dest)?)
+            .push_opcode(OP_DROP)
+            .into_script();
         bytes.extend(&script.into_bytes());
 
+        // Tie the script to the bridge instance, so the same keys used by
+        // another deployment produce different deposit addresses.
+        if let Some(instance_commitment) = &self.instance_commitment {
+            let script = Builder::new()
+                .push_slice(push_bytes(instance_commitment.as_slice())?)
+                .push_opcode(OP_DROP)
+                .into_script();
+            bytes.extend(&script.into_bytes());
+        }
+
         if self.foundation_signatories.len() > 0 {
             let mut iter = self.foundation_signatories.iter();
 
@@ -464,46 +680,69 @@ impl SignatorySet {
 
             let mut total_voting_power = signatory.voting_power;
 
-            let script = script! {
-                OP_ELSE
-                    <signatory.pubkey.as_slice()> OP_CHECKSIG
-                    OP_IF
-                        <signatory.voting_power as i64>
-                    OP_ELSE
-                        0
-                    OP_ENDIF
+            let script = Builder::new().push_opcode(OP_ELSE).into_script();
+            bytes.extend(script.into_bytes());
+
+            // Only allow the foundation path once the reserve output has been
+            // left unspent for the policy's timelock.
+            let policy_sequence = match &self.foundation_policy {
+                Some(policy) => policy.sequence()?,
+                None => None,
             };
+            if let Some(sequence) = policy_sequence {
+                let script = Builder::new()
+                    .push_int(sequence.to_consensus_u32() as i64)
+                    .push_opcode(OP_CSV)
+                    .push_opcode(OP_DROP)
+                    .into_script();
+                bytes.extend(script.into_bytes());
+            }
+
+            let script = Builder::new()
+                .push_slice(push_bytes(signatory.pubkey.as_slice())?)
+                .push_opcode(OP_CHECKSIG)
+                .push_opcode(OP_IF)
+                .push_int(signatory.voting_power as i64)
+                .push_opcode(OP_ELSE)
+                .push_int(0)
+                .push_opcode(OP_ENDIF)
+                .into_script();
             bytes.extend(script.into_bytes());
 
             for signatory in iter {
-                let script = script! {
-                    OP_SWAP
-                    <signatory.pubkey.as_slice()> OP_CHECKSIG
-                    OP_IF
-                        <signatory.voting_power as i64> OP_ADD
-                    OP_ENDIF
-                };
+                let script = Builder::new()
+                    .push_opcode(OP_SWAP)
+                    .push_slice(push_bytes(signatory.pubkey.as_slice())?)
+                    .push_opcode(OP_CHECKSIG)
+                    .push_opcode(OP_IF)
+                    .push_int(signatory.voting_power as i64)
+                    .push_opcode(OP_ADD)
+                    .push_opcode(OP_ENDIF)
+                    .into_script();
                 total_voting_power += signatory.voting_power;
                 bytes.extend(&script.into_bytes());
             }
 
-            let truncated_threshold = ((total_voting_power as f64) * (threshold.0 as f64)
-                / (threshold.1 as f64))
-                .ceil() as u64;
+            let truncated_threshold = match &self.foundation_policy {
+                Some(policy) => policy.required_signatures(total_voting_power),
+                None => ((total_voting_power as f64) * (threshold.0 as f64) / (threshold.1 as f64))
+                    .ceil() as u64,
+            };
             // Check that accumulator of voting power which had valid signatures
             // (now a final sum) is greater than the threshold.
-            let script = script! {
-                <truncated_threshold as i64> OP_GREATERTHANOREQUAL
-            };
+            let script = Builder::new()
+                .push_int(truncated_threshold as i64)
+                .push_opcode(OP_GREATERTHANOREQUAL)
+                .into_script();
             bytes.extend(&script.into_bytes());
         } else {
-            let script = script! {
-                OP_ELSE
-                    OP_RETURN
-            };
+            let script = Builder::new()
+                .push_opcode(OP_ELSE)
+                .push_opcode(OP_RETURN)
+                .into_script();
             bytes.extend(script.into_bytes());
         }
-        let script = script!(OP_ENDIF);
+        let script = Builder::new().push_opcode(OP_ENDIF).into_script();
         bytes.extend(script.into_bytes());
 
         Ok(bytes.into())
@@ -512,8 +751,8 @@ impl SignatorySet {
     /// Hashes the weighted multisig redeem script to create a P2WSH output
     /// script, which is what is used as the script pubkey in deposit outputs
     /// and reserve outputs.
-    pub fn output_script(&self, dest: &[u8], threshold: (u64, u64)) -> ContractResult<Script> {
-        Ok(self.redeem_script(dest, threshold)?.to_v0_p2wsh())
+    pub fn output_script(&self, dest: &[u8], threshold: (u64, u64)) -> ContractResult<ScriptBuf> {
+        Ok(self.redeem_script(dest, threshold)?.to_p2wsh())
     }
 
     /// Calculates the number of bits of precision to remove from voting power
@@ -547,7 +786,16 @@ impl SignatorySet {
     /// for signatories beyond the threshold, but for fee estimation we err on
     /// the side of paying too much.
     pub fn est_witness_vsize(&self) -> u64 {
-        self.signatories.len() as u64 * 79 + 39
+        let instance_commitment_size = self
+            .instance_commitment
+            .as_ref()
+            .map_or(0, |commitment| commitment.len() as u64 + 2);
+        // bucketed weights take at least one byte less to push
+        let signatory_size = match self.script_template {
+            ScriptTemplate::Raw => 79,
+            ScriptTemplate::Bucketed { .. } => 78,
+        };
+        self.signatories.len() as u64 * signatory_size + 39 + instance_commitment_size
     }
 }
 
@@ -585,11 +833,12 @@ impl SignatoryKeys {
         consensus_key: ConsensusKey,
         xpub: Xpub,
     ) -> ContractResult<()> {
-        let mut normalized_xpub = xpub;
-        normalized_xpub.key.child_number = 0.into();
-        normalized_xpub.key.depth = 0;
-        normalized_xpub.key.parent_fingerprint = Default::default();
-        let xpub_key = &normalized_xpub.encode();
+        let xpub_key = &normalized_xpub_key(&xpub);
+        if TOMBSTONED_XPUBS.has(store, xpub_key) {
+            return Err(ContractError::App(
+                "Signatory key is tombstoned".to_string(),
+            ));
+        }
         if XPUBS.has(store, xpub_key) {
             return Err(ContractError::App("Duplicate signatory key".to_string()));
         }
@@ -600,6 +849,49 @@ impl SignatoryKeys {
         Ok(())
     }
 
+    /// Removes a compromised key from the collection, returning the consensus
+    /// key it was registered with. The key stays in the set of submitted keys
+    /// so it can't be submitted again, and is dropped from jailed signers so
+    /// it isn't restored by a reinstatement.
+    pub fn tombstone(
+        &mut self,
+        store: &mut dyn Storage,
+        xpub: &Xpub,
+    ) -> ContractResult<Option<ConsensusKey>> {
+        let xpub_key = normalized_xpub_key(xpub);
+        let mut owner = None;
+        for entry in SIG_KEYS.range_raw(store, None, None, Order::Ascending) {
+            let (cons_key, key) = entry?;
+            if normalized_xpub_key(&key) == xpub_key {
+                let cons_key: ConsensusKey = cons_key
+                    .as_slice()
+                    .try_into()
+                    .map_err(|_| ContractError::App("Invalid consensus key".to_string()))?;
+                owner = Some(cons_key);
+                break;
+            }
+        }
+        if let Some(cons_key) = &owner {
+            SIG_KEYS.remove(store, cons_key);
+        }
+        XPUBS.save(store, &xpub_key, &())?;
+
+        let jailed = JAILED_SIGNERS
+            .range(store, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        for (addr, mut signer) in jailed {
+            if signer
+                .xpub
+                .as_ref()
+                .map_or(false, |key| normalized_xpub_key(key) == xpub_key)
+            {
+                signer.xpub = None;
+                JAILED_SIGNERS.save(store, &addr, &signer)?;
+            }
+        }
+        Ok(owner)
+    }
+
     /// Returns the signatory extended public key associated with the given
     /// consensus key, if one exists.    
     pub fn get(&self, store: &dyn Storage, cons_key: ConsensusKey) -> ContractResult<Option<Xpub>> {
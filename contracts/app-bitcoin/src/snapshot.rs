@@ -0,0 +1,310 @@
+use crate::{
+    app::Bitcoin,
+    app_data::hash_pair,
+    helper::{bridge_time, fetch_denom_owners},
+    interface::{BalanceSnapshot, SnapshotWithdrawal},
+    state::{
+        get_full_btc_denom, BALANCE_SNAPSHOT, CONFIG, SNAPSHOT_BALANCES, SNAPSHOT_LEAF_INDEXES,
+        SNAPSHOT_TOP_BALANCES, SNAPSHOT_TREE, SNAPSHOT_WITHDRAWALS,
+    },
+};
+use bitcoin::hashes::{sha256, Hash};
+use common_bitcoin::error::{ContractError, ContractResult};
+use cosmwasm_std::{Binary, Env, Event, Order, QuerierWrapper, StdResult, Storage, Uint128};
+
+/// Advances the nBTC balance snapshot by one page, first removing the entries
+/// of the previous snapshot, then recording the next page of holders from
+/// the bank module and finally hashing the liabilities tree over their
+/// balances. Returns an event once a snapshot completes.
+pub fn step_balance_snapshot(
+    env: &Env,
+    store: &mut dyn Storage,
+    querier: &QuerierWrapper,
+) -> ContractResult<Option<Event>> {
+    let config = Bitcoin::default().config(store)?;
+    if config.balance_snapshot_page_size == 0 {
+        return Ok(None);
+    }
+    let now = bridge_time(env);
+    let mut snapshot = match BALANCE_SNAPSHOT.may_load(store)? {
+        Some(snapshot) => match snapshot.completed_at {
+            Some(completed_at) if now < completed_at + config.balance_snapshot_interval => {
+                return Ok(None)
+            }
+            Some(_) => new_snapshot(now),
+            None => snapshot,
+        },
+        None => new_snapshot(now),
+    };
+    let page_size = config.balance_snapshot_page_size as usize;
+
+    if snapshot.clearing {
+        let stale = SNAPSHOT_BALANCES
+            .keys(store, None, None, Order::Ascending)
+            .take(page_size)
+            .collect::<StdResult<Vec<_>>>()?;
+        for holder in &stale {
+            SNAPSHOT_BALANCES.remove(store, holder);
+            SNAPSHOT_LEAF_INDEXES.remove(store, holder);
+        }
+        let stale_nodes = SNAPSHOT_TREE
+            .keys(store, None, None, Order::Ascending)
+            .take(page_size)
+            .collect::<StdResult<Vec<_>>>()?;
+        for node in &stale_nodes {
+            SNAPSHOT_TREE.remove(store, *node);
+        }
+        if stale.len() < page_size && stale_nodes.len() < page_size {
+            // the top index holds at most `balance_snapshot_top_n` entries
+            let top = SNAPSHOT_TOP_BALANCES
+                .keys(store, None, None, Order::Ascending)
+                .collect::<StdResult<Vec<_>>>()?;
+            for (balance, holder) in top {
+                SNAPSHOT_TOP_BALANCES.remove(store, (balance, &holder));
+            }
+            // bounded by the outputs of the two newest checkpoints
+            let withdrawals = SNAPSHOT_WITHDRAWALS
+                .keys(store, None, None, Order::Ascending)
+                .collect::<StdResult<Vec<_>>>()?;
+            for position in withdrawals {
+                SNAPSHOT_WITHDRAWALS.remove(store, position);
+            }
+            snapshot.clearing = false;
+        }
+        BALANCE_SNAPSHOT.save(store, &snapshot)?;
+        return Ok(None);
+    }
+
+    if snapshot.tree_cursor.is_none() {
+        let denom = get_full_btc_denom(store, CONFIG.load(store)?.token_factory_contract.as_str())?;
+        let key = snapshot.next_key.take().unwrap_or_default().to_vec();
+        let page = fetch_denom_owners(querier, denom, key, page_size as u64)?;
+        for owner in page.denom_owners {
+            let balance = owner
+                .balance
+                .and_then(|coin| coin.amount.parse::<u128>().ok())
+                .unwrap_or_default();
+            if balance == 0 {
+                continue;
+            }
+            SNAPSHOT_BALANCES.save(store, &owner.address, &Uint128::new(balance))?;
+            let leaf_index = snapshot.holders as u32;
+            SNAPSHOT_LEAF_INDEXES.save(store, &owner.address, &leaf_index)?;
+            let leaf = liability_leaf(&owner.address, balance);
+            SNAPSHOT_TREE.save(store, (0, leaf_index), &Binary::from(leaf.to_vec()))?;
+            snapshot.holders += 1;
+            snapshot.total += Uint128::new(balance);
+            if config.balance_snapshot_top_n > 0 {
+                index_top_balance(
+                    store,
+                    &mut snapshot,
+                    config.balance_snapshot_top_n,
+                    balance,
+                    &owner.address,
+                )?;
+            }
+        }
+
+        let next_key = page
+            .pagination
+            .map(|page| page.next_key)
+            .unwrap_or_default();
+        if !next_key.is_empty() {
+            snapshot.next_key = Some(Binary::from(next_key));
+            BALANCE_SNAPSHOT.save(store, &snapshot)?;
+            return Ok(None);
+        }
+        snapshot.tree_cursor = Some((0, 0));
+    }
+
+    if !hash_liabilities_tree(store, &mut snapshot, page_size)? {
+        BALANCE_SNAPSHOT.save(store, &snapshot)?;
+        return Ok(None);
+    }
+
+    record_pending_withdrawals(store, &mut snapshot)?;
+    snapshot.completed_at = Some(now);
+    BALANCE_SNAPSHOT.save(store, &snapshot)?;
+    Ok(Some(
+        Event::new("balance_snapshot")
+            .add_attribute("holders", snapshot.holders.to_string())
+            .add_attribute("total", snapshot.total)
+            .add_attribute("withdrawals", snapshot.withdrawals.to_string())
+            .add_attribute("withdrawals_total", snapshot.withdrawals_total.to_string())
+            .add_attribute(
+                "liabilities_root",
+                snapshot
+                    .liabilities_root
+                    .map(|root| root.to_base64())
+                    .unwrap_or_default(),
+            ),
+    ))
+}
+
+/// The leaf of a holder in the liabilities tree: the SHA-256 of the holder's
+/// address followed by its balance as 16 big-endian bytes.
+pub fn liability_leaf(holder: &str, balance: u128) -> [u8; 32] {
+    let mut preimage = holder.as_bytes().to_vec();
+    preimage.extend_from_slice(&balance.to_be_bytes());
+    sha256::Hash::hash(&preimage).to_byte_array()
+}
+
+/// The number of nodes at a level of a tree with `leaves` leaves, the last
+/// node of odd levels being paired with itself.
+fn level_len(leaves: u64, level: u8) -> u32 {
+    let mut len = leaves;
+    for _ in 0..level {
+        len = (len + 1) / 2;
+    }
+    len as u32
+}
+
+fn tree_node(store: &dyn Storage, level: u8, position: u32) -> ContractResult<[u8; 32]> {
+    SNAPSHOT_TREE
+        .load(store, (level, position))?
+        .to_vec()
+        .try_into()
+        .map_err(|_| ContractError::App("Invalid liabilities tree node".to_string()))
+}
+
+/// Hashes up to `budget` pairs of nodes of the liabilities tree, from the
+/// leaves up. Returns whether the root was reached.
+pub fn hash_liabilities_tree(
+    store: &mut dyn Storage,
+    snapshot: &mut BalanceSnapshot,
+    budget: usize,
+) -> ContractResult<bool> {
+    let Some((mut level, mut position)) = snapshot.tree_cursor else {
+        return Ok(true);
+    };
+    if snapshot.holders == 0 {
+        snapshot.tree_cursor = None;
+        return Ok(true);
+    }
+
+    let mut len = level_len(snapshot.holders, level);
+    for _ in 0..budget {
+        if len == 1 {
+            snapshot.liabilities_root = Some(SNAPSHOT_TREE.load(store, (level, 0))?);
+            snapshot.tree_cursor = None;
+            return Ok(true);
+        }
+        let left = tree_node(store, level, position)?;
+        let right = if position + 1 < len {
+            tree_node(store, level, position + 1)?
+        } else {
+            left
+        };
+        let parent = hash_pair(&left, &right);
+        SNAPSHOT_TREE.save(
+            store,
+            (level + 1, position / 2),
+            &Binary::from(parent.to_vec()),
+        )?;
+        position += 2;
+        if position >= len {
+            level += 1;
+            position = 0;
+            len = level_len(snapshot.holders, level);
+        }
+    }
+    snapshot.tree_cursor = Some((level, position));
+    Ok(false)
+}
+
+/// The sibling hashes from the leaf at `leaf_index` up to the root of the
+/// liabilities tree.
+pub fn liabilities_branch(
+    store: &dyn Storage,
+    holders: u64,
+    leaf_index: u32,
+) -> ContractResult<Vec<Binary>> {
+    let mut branch = vec![];
+    let mut index = leaf_index;
+    let mut level = 0;
+    let mut len = level_len(holders, 0);
+    while len > 1 {
+        let sibling = if index ^ 1 < len { index ^ 1 } else { index };
+        branch.push(SNAPSHOT_TREE.load(store, (level, sibling))?);
+        index /= 2;
+        level += 1;
+        len = level_len(holders, level);
+    }
+    Ok(branch)
+}
+
+/// Records the withdrawal outputs of the `Signing` and `Building` checkpoints.
+/// Their nBTC was burned but they only reach Bitcoin once the checkpoint is
+/// signed, so a halted bridge would leave them unpaid.
+fn record_pending_withdrawals(
+    store: &mut dyn Storage,
+    snapshot: &mut BalanceSnapshot,
+) -> ContractResult<()> {
+    let checkpoints = Bitcoin::default().checkpoints;
+    let mut pending = vec![];
+    if let Some(signing) = checkpoints.signing(store)? {
+        // skip the reserve and timestamping commitment outputs
+        pending.push((checkpoints.index(store) - 1, signing.checkpoint_tx()?, 2));
+    }
+    if let Ok(building) = checkpoints.building(store) {
+        pending.push((checkpoints.index(store), building.checkpoint_tx()?, 0));
+    }
+
+    for (checkpoint_index, tx, skip) in pending {
+        for output in tx.output.iter().skip(skip) {
+            SNAPSHOT_WITHDRAWALS.save(
+                store,
+                snapshot.withdrawals,
+                &SnapshotWithdrawal {
+                    checkpoint_index,
+                    script_pubkey: Binary::from(output.script_pubkey.to_bytes()),
+                    value: output.value.to_sat(),
+                },
+            )?;
+            snapshot.withdrawals += 1;
+            snapshot.withdrawals_total += output.value.to_sat();
+        }
+    }
+    Ok(())
+}
+
+fn new_snapshot(now: u64) -> BalanceSnapshot {
+    BalanceSnapshot {
+        started_at: now,
+        completed_at: None,
+        clearing: true,
+        next_key: None,
+        holders: 0,
+        total: Uint128::zero(),
+        top_len: 0,
+        withdrawals: 0,
+        withdrawals_total: 0,
+        tree_cursor: None,
+        liabilities_root: None,
+    }
+}
+
+/// Adds a holder to the top balances index, evicting the smallest entry once
+/// it holds more than `top_n` entries.
+fn index_top_balance(
+    store: &mut dyn Storage,
+    snapshot: &mut BalanceSnapshot,
+    top_n: u32,
+    balance: u128,
+    holder: &str,
+) -> ContractResult<()> {
+    SNAPSHOT_TOP_BALANCES.save(store, (balance, holder), &())?;
+    snapshot.top_len += 1;
+    if snapshot.top_len <= top_n {
+        return Ok(());
+    }
+    let smallest = SNAPSHOT_TOP_BALANCES
+        .keys(store, None, None, Order::Ascending)
+        .next()
+        .transpose()?;
+    if let Some((balance, holder)) = smallest {
+        SNAPSHOT_TOP_BALANCES.remove(store, (balance, &holder));
+        snapshot.top_len -= 1;
+    }
+    Ok(())
+}
@@ -1,14 +1,24 @@
 use crate::{
     app::ConsensusKey,
     checkpoint::Checkpoint,
-    constants::BTC_NATIVE_TOKEN_DENOM,
-    interface::{BitcoinConfig, CheckpointConfig, Validator},
-    msg::Config,
+    constants::{
+        BTC_NATIVE_TOKEN_DENOM, DEST_STATS_WINDOW, MAX_CHANGE_RECORDS, MAX_FEE_RATE_HISTORY,
+    },
+    interface::{
+        AdminLogEntry, BalanceSnapshot, BitcoinConfig, BridgeMigration, BurnRecord, Change,
+        ChangeRecord, CheckpointConfig, CheckpointHook, Dest, DestStats, ExpiredDeposit,
+        FeeRateChange, HeldWithdrawal, IbcTransfer, JailedSigner, LightningSwap, MintClaim,
+        MintRecord, PendingDeposit, PendingTransfer, QueuedWithdrawal, ReorgedDeposit, ReserveUtxo,
+        ScheduledValidatorSet, SignedReserveOutput, SnapshotWithdrawal, SwapProvider, Tombstone,
+        Validator, WithdrawalAllowance, WithdrawalQuote,
+    },
+    msg::{BtcBridgeReceiveMsg, Config},
     recovery::RecoveryTx,
+    signatory::{FoundationPolicy, SignatorySet},
 };
 use common_bitcoin::{deque::DequeExtension, error::ContractResult, xpub::Xpub};
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, Order, Storage};
+use cosmwasm_std::{Addr, Binary, Coin, Order, StdResult, Storage, Uint128};
 use cw_storage_plus::{Item, Map};
 
 #[cw_serde]
@@ -19,6 +29,9 @@ pub struct Ratio {
 
 pub const CONFIG: Item<Config> = Item::new("config");
 
+/// Version of the stored state layout, see `CONTRACT_STATE_VERSION`
+pub const STATE_VERSION: Item<u32> = Item::new("state_version");
+
 /// TODO: store in smart contract
 pub const CHECKPOINT_CONFIG: Item<CheckpointConfig> = Item::new("checkpoint_config");
 pub const BITCOIN_CONFIG: Item<BitcoinConfig> = Item::new("bitcoin_config");
@@ -29,11 +42,45 @@ pub const VALIDATORS: Map<&ConsensusKey, (u64, String)> = Map::new("validators")
 /// Mapping validator Address => ConsensusKey
 pub const SIGNERS: Map<&str, ConsensusKey> = Map::new("signers");
 
+/// Validator set replacing the registry at its epoch
+pub const SCHEDULED_VALIDATOR_SET: Item<ScheduledValidatorSet> =
+    Item::new("scheduled_validator_set");
+
+/// Signers removed for missing checkpoints, by address
+pub const JAILED_SIGNERS: Map<&str, JailedSigner> = Map::new("jailed_signers");
+
+/// Compromised signatory keys which can't be part of a signatory set again,
+/// keyed by normalized xpub
+pub const TOMBSTONED_XPUBS: Map<&[u8], Tombstone> = Map::new("tombstoned_xpubs");
+
+/// Addresses of the validators voting to tombstone a signatory key, keyed by
+/// normalized xpub
+pub const TOMBSTONE_VOTES: Map<&[u8], Vec<String>> = Map::new("tombstone_votes");
+
+/// Addresses of the validators voting to force complete a checkpoint, keyed
+/// by checkpoint index and the wtxid of the proposed transaction
+pub const FORCE_COMPLETE_VOTES: Map<(u32, &str), Vec<String>> = Map::new("force_complete_votes");
+
+/// The light client height when the signatory set of each checkpoint was
+/// created, for measuring deposit age in Bitcoin blocks
+pub const SIGSET_BTC_HEIGHTS: Map<u32, u32> = Map::new("sigset_btc_heights");
+
+/// Set when a signatory key is tombstoned, the next checkpoint is pushed
+/// without waiting for the checkpoint interval
+pub const FORCED_CHECKPOINT_PUSH: Item<bool> = Item::new("forced_checkpoint_push");
+
+/// The index of the latest `Building` checkpoint a `Fast` withdrawal was
+/// added to
+pub const FAST_WITHDRAWAL_CHECKPOINT: Item<u32> = Item::new("fast_withdrawal_checkpoint");
+
 // by_cons Map<ConsensusKey, Xpub>
 pub const SIG_KEYS: Map<&ConsensusKey, Xpub> = Map::new("sig_keys");
 
 pub const FOUNDATION_KEYS: Item<Vec<Xpub>> = Item::new("foundation_keys");
 
+/// Spending policy of the foundation keys, snapshotted into new signatory sets
+pub const FOUNDATION_POLICY: Item<FoundationPolicy> = Item::new("foundation_policy");
+
 /// The collection also includes an set of all signatory extended public keys,
 /// which is used to prevent duplicate keys from being submitted.
 /// xpubs Map<Xpub::encode(), ()>
@@ -41,6 +88,27 @@ pub const XPUBS: Map<&[u8], ()> = Map::new("xpubs");
 
 pub const RECOVERY_TXS: DequeExtension<RecoveryTx> = DequeExtension::new("recovery_txs");
 
+/// Deposit statistics of every destination, by destination commitment
+pub const DEST_STATS: Map<&[u8], DestStats> = Map::new("dest_stats");
+
+/// Destinations ordered by total deposited value, keyed by (total,
+/// destination commitment)
+pub const DEST_STATS_BY_TOTAL: Map<(u64, &[u8]), ()> = Map::new("dest_stats_by_total");
+
+/// Unconfirmed deposits reported by relayers, by (txid, vout)
+pub const PENDING_DEPOSITS: Map<(&str, u32), PendingDeposit> = Map::new("pending_deposits");
+
+/// Index of `PENDING_DEPOSITS` by (destination commitment, txid, vout)
+pub const PENDING_DEPOSITS_BY_DEST: Map<(&[u8], &str, u32), ()> =
+    Map::new("pending_deposits_by_dest");
+
+/// Index of `PENDING_DEPOSITS` by (expiration, txid, vout), for pruning
+pub const PENDING_DEPOSIT_EXPIRATIONS: Map<(u64, &str, u32), ()> =
+    Map::new("pending_deposit_expirations");
+
+/// Expired dust deposits waiting for a sweep, by (txid, vout)
+pub const DUST_DEPOSITS: Map<(&str, u32), ExpiredDeposit> = Map::new("dust_deposits");
+
 /// A queue of outpoints to expire, sorted by expiration timestamp.
 pub const EXPIRATION_QUEUE: Map<(u64, &str), ()> = Map::new("expiration_queue");
 
@@ -49,13 +117,98 @@ pub const OUTPOINTS: Map<&str, ()> = Map::new("outpoints");
 
 pub const FEE_POOL: Item<i64> = Item::new("fee_pool");
 
+/// Fee pool surplus waiting to be credited to relayers and signatories, in
+/// nBTC units
+pub const REWARD_POOL: Item<u64> = Item::new("reward_pool");
+
+/// Rewards credited to each relayer and signatory, claimable as nBTC
+pub const REWARD_BALANCES: Map<&Addr, u64> = Map::new("reward_balances");
+
+/// Withdrawal allowances by (granter, spender)
+pub const WITHDRAWAL_ALLOWANCES: Map<(&Addr, &Addr), WithdrawalAllowance> =
+    Map::new("withdrawal_allowances");
+
 pub const CHECKPOINTS: DequeExtension<Checkpoint> = DequeExtension::new("checkpoints");
 /// Checkpoint building index
 pub const BUILDING_INDEX: Item<u32> = Item::new("building_index");
 /// Checkpoint confirmed index
 pub const CONFIRMED_INDEX: Item<u32> = Item::new("confirmed_index");
+/// Bitcoin height of the block confirming each relayed checkpoint
+pub const CHECKPOINT_CONFIRMATION_HEIGHTS: Map<u32, u32> =
+    Map::new("checkpoint_confirmation_heights");
+/// Seconds between the Bitcoin blocks each relayed checkpoint was signed and
+/// confirmed at, by their header times
+pub const CHECKPOINT_CONFIRMATION_TIMES: Map<u32, u64> = Map::new("checkpoint_confirmation_times");
+/// Checkpoint index of each completed checkpoint transaction, by txid
+pub const CHECKPOINT_TXIDS: Map<&str, u32> = Map::new("checkpoint_txids");
+/// The final txid of each completed checkpoint, by index. The txid of a
+/// confirmed fee-bumped checkpoint replaces the signed one.
+pub const CHECKPOINT_INDEX_TXIDS: Map<u32, String> = Map::new("checkpoint_index_txids");
+/// The newest signatory set index a deposit was relayed to, by destination
+/// commitment
+pub const DEST_SIGSET_INDEXES: Map<&[u8], u32> = Map::new("dest_sigset_indexes");
+
+/// Registered submarine swap providers for Lightning withdrawals
+pub const SWAP_PROVIDERS: Map<&Addr, SwapProvider> = Map::new("swap_providers");
+/// Lightning withdrawals escrowed until their provider accepts them, by id
+pub const LIGHTNING_SWAPS: Map<u64, LightningSwap> = Map::new("lightning_swaps");
+pub const LIGHTNING_SWAP_COUNT: Item<u64> = Item::new("lightning_swap_count");
+
+/// Withdrawals to Bitcoin addresses, by id. Records are pruned once their
+/// checkpoint stops building
+pub const WITHDRAWALS: Map<u64, QueuedWithdrawal> = Map::new("withdrawals");
+pub const WITHDRAWAL_COUNT: Item<u64> = Item::new("withdrawal_count");
+
+/// Withdrawal fees locked by `PrepareWithdrawal`, by quote id
+pub const WITHDRAWAL_QUOTES: Map<u64, WithdrawalQuote> = Map::new("withdrawal_quotes");
+pub const WITHDRAWAL_QUOTE_COUNT: Item<u64> = Item::new("withdrawal_quote_count");
+
+/// Externally created reserve outputs attached to a checkpoint, by outpoint
+pub const RESERVE_UTXOS: Map<(&str, u32), ReserveUtxo> = Map::new("reserve_utxos");
+/// Reserve outputs of signed recovery transactions waiting to be attached to
+/// a checkpoint, by outpoint
+pub const SIGNED_RESERVE_OUTPUTS: Map<(&str, u32), SignedReserveOutput> =
+    Map::new("signed_reserve_outputs");
+/// Signatory sets of checkpoints which were pruned from the queue, by index
+pub const SIGSET_ARCHIVE: Map<u32, SignatorySet> = Map::new("sigset_archive");
 /// Checkpoint unhandled confirmed index
 pub const FIRST_UNHANDLED_CONFIRMED_INDEX: Item<u32> = Item::new("first_unhandled_confirmed_index");
+/// The index of the next checkpoint to have its signatures compacted
+pub const COMPACTED_INDEX: Item<u32> = Item::new("compacted_index");
+/// Total bytes of checkpoint state reclaimed by signature compaction
+pub const RECLAIMED_SIGNATURE_BYTES: Item<u64> = Item::new("reclaimed_signature_bytes");
+
+/// Whether a state import is in progress (`true`) or finished (`false`)
+pub const STATE_IMPORT: Item<bool> = Item::new("state_import");
+
+/// The migration of this bridge to a successor, once started
+pub const BRIDGE_MIGRATION: Item<BridgeMigration> = Item::new("bridge_migration");
+
+/// The predecessor bridge allowed to migrate its reserve and accounts here
+pub const MIGRATION_PREDECESSOR: Item<Addr> = Item::new("migration_predecessor");
+
+/// Set when an accounting invariant check fails, deposits and withdrawals are
+/// rejected until the owner clears it
+pub const BRIDGE_PAUSED: Item<bool> = Item::new("bridge_paused");
+
+/// The light client's header height and the bridge time it was first seen at
+pub const LAST_HEADER: Item<(u32, u64)> = Item::new("last_header");
+
+/// Set in `ClockEndBlock` while the light client has not advanced for longer
+/// than `max_header_staleness`, cleared once it advances again
+pub const HEADER_STALL_PAUSED: Item<bool> = Item::new("header_stall_paused");
+
+/// The bridge time until which deposits below `min_relayable_utxo_sats` are
+/// accepted, set by the owner during low-fee windows
+pub const SMALL_UTXOS_ALLOWED_UNTIL: Item<u64> = Item::new("small_utxos_allowed_until");
+
+/// The token factory subdenom nBTC is minted as, `BTC_NATIVE_TOKEN_DENOM`
+/// unless changed by `MigrateDenom`
+pub const BTC_SUBDENOM: Item<String> = Item::new("btc_subdenom");
+
+/// Full denoms nBTC was minted as before a `MigrateDenom`, which can still be
+/// withdrawn
+pub const LEGACY_BTC_DENOMS: Item<Vec<String>> = Item::new("legacy_btc_denoms");
 
 /// Fee
 pub const TOKEN_FEE_RATIO: Item<Ratio> = Item::new("token_fee_ratio");
@@ -63,9 +216,150 @@ pub const TOKEN_FEE_RATIO: Item<Ratio> = Item::new("token_fee_ratio");
 /// End block hash mapping, this is just unique hash string
 pub const BLOCK_HASHES: Map<&[u8], ()> = Map::new("block_hashes");
 
+/// nBTC transfers of completed checkpoints waiting to be minted in
+/// `ClockEndBlock`
+pub const PENDING_TRANSFERS: DequeExtension<PendingTransfer> =
+    DequeExtension::new("pending_transfers");
+
+/// Refund address, amount and source channel of each ibc memo transfer
+/// awaiting its reply, keyed by the refund id its reply is sent with
+pub const IBC_MEMO_REFUNDS: Map<u64, (String, Coin, String)> = Map::new("ibc_memo_refunds");
+
+/// The id of the next ibc memo transfer refund
+pub const IBC_MEMO_REFUND_COUNT: Item<u64> = Item::new("ibc_memo_refund_count");
+
+/// Sent ibc memo transfers awaiting their ack or timeout, keyed by (source
+/// channel, packet sequence)
+pub const IBC_TRANSFERS: Map<(&str, u64), IbcTransfer> = Map::new("ibc_transfers");
+
+/// nBTC of failed ibc memo transfers, claimable by their refund address with
+/// `ClaimIbcRefund`, keyed by (refund address, denom)
+pub const IBC_REFUNDS: Map<(&str, &str), Uint128> = Map::new("ibc_refunds");
+
+/// Recipient, amount and receive hook of each pushed mint awaiting its reply
+pub const PENDING_MINTS: DequeExtension<(Addr, Coin, Option<BtcBridgeReceiveMsg>)> =
+    DequeExtension::new("pending_mints");
+
+/// Contracts receiving checkpoint events, keyed by hook id
+pub const CHECKPOINT_HOOKS: Map<u64, CheckpointHook> = Map::new("checkpoint_hooks");
+
+pub const CHECKPOINT_HOOK_COUNT: Item<u64> = Item::new("checkpoint_hook_count");
+
+/// Mints waiting to be pulled with `ClaimMint`, keyed by (recipient, id)
+pub const MINT_CLAIMS: Map<(&Addr, u64), MintClaim> = Map::new("mint_claims");
+
+pub const MINT_CLAIM_COUNT: Item<u64> = Item::new("mint_claim_count");
+
+/// Expiring mint claims as (recipient, id, expires_at), in creation order
+pub const MINT_CLAIM_EXPIRATIONS: DequeExtension<(Addr, u64, u64)> =
+    DequeExtension::new("mint_claim_expirations");
+
+/// The most recent changes relayers act on, oldest first
+pub const CHANGE_RECORDS: DequeExtension<ChangeRecord> = DequeExtension::new("change_records");
+
+/// The most recent fee rate changes, oldest first
+pub const FEE_RATE_HISTORY: DequeExtension<FeeRateChange> = DequeExtension::new("fee_rate_history");
+
+/// The height of the newest change record dropped from `CHANGE_RECORDS`
+pub const CHANGES_PRUNED_HEIGHT: Item<u64> = Item::new("changes_pruned_height");
+
+/// Append-only log of owner actions, keyed by entry id
+pub const ADMIN_LOG: Map<u64, AdminLogEntry> = Map::new("admin_log");
+
+pub const ADMIN_LOG_COUNT: Item<u64> = Item::new("admin_log_count");
+
+/// Bitcoin height the light clients were checked to agree on when last
+/// switched
+pub const LIGHT_CLIENT_SWITCHED_AT: Item<u32> = Item::new("light_client_switched_at");
+
+/// Progress of the nBTC balance snapshot built across end blocks
+pub const BALANCE_SNAPSHOT: Item<BalanceSnapshot> = Item::new("balance_snapshot");
+
+/// The nBTC balance of every holder in the balance snapshot
+pub const SNAPSHOT_BALANCES: Map<&str, Uint128> = Map::new("snapshot_balances");
+
+/// The largest holders of the balance snapshot, keyed by (balance, holder)
+pub const SNAPSHOT_TOP_BALANCES: Map<(u128, &str), ()> = Map::new("snapshot_top_balances");
+
+/// Nodes of the Merkle tree over the balances of the balance snapshot, by
+/// (level, position). Level 0 holds the leaves.
+pub const SNAPSHOT_TREE: Map<(u8, u32), Binary> = Map::new("snapshot_tree");
+
+/// The leaf position of every holder in the balance snapshot tree
+pub const SNAPSHOT_LEAF_INDEXES: Map<&str, u32> = Map::new("snapshot_leaf_indexes");
+
+/// The withdrawal outputs recorded by the balance snapshot, by position
+pub const SNAPSHOT_WITHDRAWALS: Map<u32, SnapshotWithdrawal> = Map::new("snapshot_withdrawals");
+
+/// The output script each nBTC holder chose to receive their share of an
+/// emergency disbursal, by holder address
+pub const RECOVERY_SCRIPTS: Map<&str, Binary> = Map::new("recovery_scripts");
+
+/// Bridge time of the last deposit minted to or withdrawal from each holder
+pub const ACCOUNT_ACTIVITY: Map<&str, u64> = Map::new("account_activity");
+
+/// 32-byte hashes paid to be committed by the checkpoint at each index
+pub const APP_DATA: Map<u32, Vec<Binary>> = Map::new("app_data");
+
+/// The bridge's own timestamping commitment of each checkpoint carrying app
+/// data, the first leaf of its `OP_RETURN` Merkle tree
+pub const APP_DATA_INTERNAL_COMMITMENTS: Map<u32, Binary> =
+    Map::new("app_data_internal_commitments");
+
 /// Whitelist validators
 pub const WHITELIST_VALIDATORS: Map<Addr, ()> = Map::new("whitelist_native_validators");
 
+/// Relayers allowed to attest checkpoint confirmations
+pub const RELAYERS: Map<&Addr, ()> = Map::new("relayers");
+
+/// Addresses allowed to tag checkpoints with memos, maintain the screened
+/// scripts list and review held withdrawals besides the owner
+pub const COMPLIANCE_TAGGERS: Map<&Addr, ()> = Map::new("compliance_taggers");
+
+/// Output scripts withdrawals are screened against, as a whitelist or a
+/// blacklist depending on `withdrawal_script_screening`
+pub const SCREENED_SCRIPTS: Map<&[u8], ()> = Map::new("screened_scripts");
+
+/// Withdrawals to rejected scripts waiting for review, by id
+pub const HELD_WITHDRAWALS: Map<u64, HeldWithdrawal> = Map::new("held_withdrawals");
+
+pub const HELD_WITHDRAWAL_COUNT: Item<u64> = Item::new("held_withdrawal_count");
+
+/// Memos attached to checkpoints for reconciliation, e.g. batch ids or audit
+/// references
+pub const CHECKPOINT_MEMOS: Map<u32, String> = Map::new("checkpoint_memos");
+
+/// Checkpoint confirmation attestations (cp_index, relayer) => btc_height
+pub const CHECKPOINT_ATTESTATIONS: Map<(u32, &Addr), u32> = Map::new("checkpoint_attestations");
+
+/// Mint records of processed deposits, keyed by (txid, vout)
+pub const MINT_RECORDS: Map<(&str, u32), MintRecord> = Map::new("mint_records");
+
+/// Expired deposits waiting to be claimed, keyed by (txid, vout)
+pub const EXPIRED_DEPOSITS: Map<(&str, u32), ExpiredDeposit> = Map::new("expired_deposits");
+
+/// Index of `EXPIRED_DEPOSITS` by (destination commitment, txid, vout)
+pub const EXPIRED_DEPOSITS_BY_DEST: Map<(&[u8], &str, u32), ()> =
+    Map::new("expired_deposits_by_dest");
+
+/// Deposits reconciled after a reorg, keyed by (txid, vout)
+pub const REORGED_DEPOSITS: Map<(&str, u32), ReorgedDeposit> = Map::new("reorged_deposits");
+
+/// nBTC minted for reorged deposits which could not be burned again, a loss
+/// socialized across all holders
+pub const BAD_DEBT: Item<Uint128> = Item::new("bad_debt");
+
+/// nBTC burned without a withdrawal, by id. Records are never removed
+pub const BURNS: Map<u64, BurnRecord> = Map::new("burns");
+
+pub const BURN_COUNT: Item<u64> = Item::new("burn_count");
+
+/// The sum of all `BURNS`
+pub const TOTAL_BURNED: Item<Uint128> = Item::new("total_burned");
+
+/// Addresses (e.g. market makers) which skip token and relayer fees
+pub const FEE_EXEMPTIONS: Map<&str, ()> = Map::new("fee_exemptions");
+
 pub fn get_validators(store: &dyn Storage) -> ContractResult<Vec<Validator>> {
     VALIDATORS
         .range(store, None, None, Order::Ascending)
@@ -76,6 +370,169 @@ pub fn get_validators(store: &dyn Storage) -> ContractResult<Vec<Validator>> {
         .collect()
 }
 
-pub fn get_full_btc_denom(token_factory_addr: &str) -> String {
-    format!("factory/{}/{}", token_factory_addr, BTC_NATIVE_TOKEN_DENOM)
+/// Replaces the validator registry with the scheduled set if it is due for
+/// the signatory set of checkpoint `next_index`. Returns whether the set was
+/// applied.
+pub fn apply_scheduled_validator_set(
+    store: &mut dyn Storage,
+    next_index: u32,
+) -> ContractResult<bool> {
+    let scheduled = match SCHEDULED_VALIDATOR_SET.may_load(store)? {
+        Some(scheduled) if scheduled.epoch <= next_index => scheduled,
+        _ => return Ok(false),
+    };
+
+    let signers = SIGNERS
+        .range(store, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for (addr, cons_key) in signers {
+        SIGNERS.remove(store, &addr);
+        VALIDATORS.remove(store, &cons_key);
+    }
+    for validator in scheduled.validators {
+        SIGNERS.save(store, &validator.addr, &validator.consensus_key)?;
+        VALIDATORS.save(
+            store,
+            &validator.consensus_key,
+            &(validator.voting_power, validator.addr),
+        )?;
+    }
+    SCHEDULED_VALIDATOR_SET.remove(store);
+
+    Ok(true)
+}
+
+/// Appends a change record, dropping the oldest ones beyond
+/// `MAX_CHANGE_RECORDS`.
+pub fn record_change(store: &mut dyn Storage, height: u64, change: Change) -> StdResult<()> {
+    CHANGE_RECORDS.push_back(store, &ChangeRecord { height, change })?;
+    while CHANGE_RECORDS.len(store)? > MAX_CHANGE_RECORDS {
+        if let Some(record) = CHANGE_RECORDS.pop_front(store)? {
+            CHANGES_PRUNED_HEIGHT.save(store, &record.height)?;
+        }
+    }
+    Ok(())
+}
+
+/// Adds a minted deposit of `value` satoshis to the statistics of its
+/// destination.
+pub fn record_dest_deposit(
+    store: &mut dyn Storage,
+    dest: &Dest,
+    value: u64,
+    now: u64,
+) -> ContractResult<()> {
+    let key = dest.commitment_bytes()?;
+    let mut stats = match DEST_STATS.may_load(store, &key)? {
+        Some(stats) => {
+            DEST_STATS_BY_TOTAL.remove(store, (stats.total_deposited, key.as_slice()));
+            stats
+        }
+        None => DestStats {
+            dest: dest.clone(),
+            total_deposited: 0,
+            deposits: 0,
+            last_deposit_at: 0,
+            window_started_at: now,
+            window_volume: 0,
+        },
+    };
+    if now >= stats.window_started_at + DEST_STATS_WINDOW {
+        stats.window_started_at = now;
+        stats.window_volume = 0;
+    }
+    stats.total_deposited += value;
+    stats.deposits += 1;
+    stats.last_deposit_at = now;
+    stats.window_volume += value;
+    DEST_STATS.save(store, &key, &stats)?;
+    DEST_STATS_BY_TOTAL.save(store, (stats.total_deposited, key.as_slice()), &())?;
+    Ok(())
+}
+
+/// Records a pending deposit along with its indexes, replacing an earlier
+/// report of the same output.
+pub fn save_pending_deposit(
+    store: &mut dyn Storage,
+    deposit: &PendingDeposit,
+) -> ContractResult<()> {
+    remove_pending_deposit(store, &deposit.txid, deposit.vout)?;
+    let txid = deposit.txid.as_str();
+    PENDING_DEPOSITS.save(store, (txid, deposit.vout), deposit)?;
+    let dest_key = deposit.dest.commitment_bytes()?;
+    PENDING_DEPOSITS_BY_DEST.save(store, (&dest_key, txid, deposit.vout), &())?;
+    PENDING_DEPOSIT_EXPIRATIONS.save(store, (deposit.expires_at, txid, deposit.vout), &())?;
+    Ok(())
+}
+
+/// Removes a pending deposit and its indexes, if it was reported.
+pub fn remove_pending_deposit(
+    store: &mut dyn Storage,
+    txid: &str,
+    vout: u32,
+) -> ContractResult<()> {
+    let Some(deposit) = PENDING_DEPOSITS.may_load(store, (txid, vout))? else {
+        return Ok(());
+    };
+    PENDING_DEPOSITS.remove(store, (txid, vout));
+    let dest_key = deposit.dest.commitment_bytes()?;
+    PENDING_DEPOSITS_BY_DEST.remove(store, (&dest_key, txid, vout));
+    PENDING_DEPOSIT_EXPIRATIONS.remove(store, (deposit.expires_at, txid, vout));
+    Ok(())
+}
+
+/// Maps the completed checkpoint at `index` and its txid to each other.
+pub fn record_checkpoint_txid(store: &mut dyn Storage, index: u32, txid: &str) -> StdResult<()> {
+    CHECKPOINT_TXIDS.save(store, txid, &index)?;
+    CHECKPOINT_INDEX_TXIDS.save(store, index, &txid.to_string())
+}
+
+/// Appends a fee rate change, dropping the oldest ones beyond
+/// `MAX_FEE_RATE_HISTORY`.
+pub fn record_fee_rate_change(store: &mut dyn Storage, change: FeeRateChange) -> StdResult<()> {
+    FEE_RATE_HISTORY.push_back(store, &change)?;
+    while FEE_RATE_HISTORY.len(store)? > MAX_FEE_RATE_HISTORY {
+        FEE_RATE_HISTORY.pop_front(store)?;
+    }
+    Ok(())
+}
+
+/// Appends an owner action to the admin log.
+pub fn record_admin_action(
+    store: &mut dyn Storage,
+    height: u64,
+    sender: Addr,
+    action: &str,
+    payload_hash: Binary,
+) -> StdResult<()> {
+    let id = ADMIN_LOG_COUNT.may_load(store)?.unwrap_or_default();
+    ADMIN_LOG.save(
+        store,
+        id,
+        &AdminLogEntry {
+            id,
+            height,
+            sender,
+            action: action.to_string(),
+            payload_hash,
+        },
+    )?;
+    ADMIN_LOG_COUNT.save(store, &(id + 1))
+}
+
+pub fn get_full_btc_denom(store: &dyn Storage, token_factory_addr: &str) -> StdResult<String> {
+    let subdenom = BTC_SUBDENOM
+        .may_load(store)?
+        .unwrap_or_else(|| BTC_NATIVE_TOKEN_DENOM.to_string());
+    Ok(format!("factory/{}/{}", token_factory_addr, subdenom))
+}
+
+/// Whether `denom` is the current nBTC denom or one it was migrated from.
+pub fn is_btc_denom(store: &dyn Storage, token_factory_addr: &str, denom: &str) -> StdResult<bool> {
+    Ok(denom == get_full_btc_denom(store, token_factory_addr)?
+        || LEGACY_BTC_DENOMS
+            .may_load(store)?
+            .unwrap_or_default()
+            .iter()
+            .any(|legacy| legacy == denom))
 }
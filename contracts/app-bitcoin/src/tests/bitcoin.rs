@@ -1,29 +1,40 @@
 use super::helper::sign;
-use crate::app::Bitcoin;
+use crate::app::{Bitcoin, NETWORK};
 use crate::checkpoint::{BatchType, Input};
-use crate::constants::BTC_NATIVE_TOKEN_DENOM;
-use crate::entrypoints::query_single_signing_txs_at_checkpoint_index;
-use crate::interface::{BitcoinConfig, CheckpointConfig, Dest};
-use crate::msg::Config;
+use crate::constants::{
+    BTC_NATIVE_TOKEN_DENOM, DEST_COMMITMENT_VERSION, LEGACY_DEST_COMMITMENT_VERSION,
+    MAX_CHANGE_RECORDS,
+};
+use crate::entrypoints::{
+    claim_mint, mint_reply, query_admin_log, query_changes_since, query_mint_claims,
+    query_single_signing_txs_at_checkpoint_index, update_bitcoin_config,
+};
+use crate::interface::{BitcoinConfig, Change, CheckpointConfig, Dest, PendingTransfer};
+use crate::msg::{BtcBridgeReceiveMsg, ExecuteMsg};
 use crate::state::{
-    BITCOIN_CONFIG, BUILDING_INDEX, CHECKPOINT_CONFIG, CONFIG, CONFIRMED_INDEX, FEE_POOL,
-    FIRST_UNHANDLED_CONFIRMED_INDEX, FOUNDATION_KEYS, SIGNERS, VALIDATORS,
+    record_change, BITCOIN_CONFIG, BUILDING_INDEX, CHECKPOINT_ATTESTATIONS, CHECKPOINT_CONFIG,
+    CHECKPOINT_CONFIRMATION_HEIGHTS, CHECKPOINT_TXIDS, CONFIG, CONFIRMED_INDEX, FEE_POOL,
+    FIRST_UNHANDLED_CONFIRMED_INDEX, FOUNDATION_KEYS, MINT_CLAIMS, PENDING_MINTS, RELAYERS,
+    SIGNERS, VALIDATORS,
 };
-use crate::tests::helper::set_time;
+use crate::tests::helper::{set_time, test_config};
+use bitcoin::bip32;
 use bitcoin::hashes::Hash;
-use bitcoin::util::bip32::ExtendedPubKey;
-use bitcoin::Script;
-use bitcoin::{secp256k1::Secp256k1, util::bip32::ExtendedPrivKey, OutPoint, Txid};
+use bitcoin::merkle_tree::PartialMerkleTree;
+use bitcoin::ScriptBuf;
+use bitcoin::{secp256k1::Secp256k1, OutPoint, Txid};
 use common_bitcoin::adapter::Adapter;
-use common_bitcoin::error::ContractResult;
+use common_bitcoin::address::script_to_address;
+use common_bitcoin::dry_run::DryRunStorage;
+use common_bitcoin::error::{ContractError, ContractResult, ERROR_CODES};
 use common_bitcoin::xpub::Xpub;
-use cosmwasm_std::testing::{mock_dependencies, MockApi, MockQuerier};
+use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info, MockApi, MockQuerier};
 use cosmwasm_std::{
-    from_json, to_json_binary, Addr, Api, Coin, DepsMut, Empty, Env, QuerierResult, QuerierWrapper,
-    Storage, SystemError, SystemResult, Uint128, WasmQuery,
+    from_json, to_json_binary, Addr, Api, Binary, Coin, DepsMut, Empty, Env, Order, QuerierResult,
+    QuerierWrapper, StdResult, Storage, SubMsgResponse, SubMsgResult, SystemError, SystemResult,
+    Uint128, WasmQuery,
 };
-use light_client_bitcoin::msg::QueryMsg::{HeaderHeight, Network};
-use oraiswap::asset::AssetInfo;
+use light_client_bitcoin::msg::QueryMsg::{HeaderHeight, Network, VerifyTxWithProof};
 use std::cell::RefCell;
 
 use crate::interface::IbcDest;
@@ -44,6 +55,9 @@ fn handle_wasm_query(height: u32) -> Box<dyn Fn(&WasmQuery) -> QuerierResult> {
                     HeaderHeight {} => SystemResult::Ok(cosmwasm_std::ContractResult::Ok(
                         to_json_binary(&height).unwrap(),
                     )),
+                    VerifyTxWithProof { .. } => SystemResult::Ok(cosmwasm_std::ContractResult::Ok(
+                        to_json_binary(&()).unwrap(),
+                    )),
                     _ => SystemResult::Err(SystemError::UnsupportedRequest {
                         kind: "QueryMsg".to_string(),
                     }),
@@ -65,22 +79,7 @@ fn check_change_rates() -> ContractResult<()> {
     let mut block_height = 10;
 
     let bitcoin_config = BitcoinConfig::default();
-    CONFIG.save(
-        deps.as_mut().storage,
-        &Config {
-            owner: Addr::unchecked("owner"),
-            relayer_fee_receiver: Addr::unchecked("relayer_fee_receiver"),
-            token_fee_receiver: Addr::unchecked("token_fee_receiver"),
-            relayer_fee_token: AssetInfo::NativeToken {
-                denom: "orai".to_string(),
-            },
-            relayer_fee: Uint128::from(0u128),
-            token_factory_contract: Addr::unchecked("token_factory_contract"),
-            light_client_contract: Addr::unchecked("light_client_contract"),
-            swap_router_contract: None,
-            osor_entry_point_contract: None,
-        },
-    )?;
+    CONFIG.save(deps.as_mut().storage, &test_config())?;
     BITCOIN_CONFIG.save(deps.as_mut().storage, &bitcoin_config)?;
     FEE_POOL.save(deps.as_mut().storage, &0)?;
     CHECKPOINT_CONFIG.save(deps.as_mut().storage, &CheckpointConfig::default())?;
@@ -110,12 +109,12 @@ fn check_change_rates() -> ContractResult<()> {
     let secp = Secp256k1::new();
     let network = bitcoin::Network::Bitcoin;
     let xpriv = vec![
-        ExtendedPrivKey::new_master(network, &[0])?,
-        ExtendedPrivKey::new_master(network, &[1])?,
+        bip32::Xpriv::new_master(network, &[0])?,
+        bip32::Xpriv::new_master(network, &[1])?,
     ];
     let xpub = vec![
-        ExtendedPubKey::from_priv(&secp, &xpriv[0]),
-        ExtendedPubKey::from_priv(&secp, &xpriv[1]),
+        bip32::Xpub::from_priv(&secp, &xpriv[0]),
+        bip32::Xpub::from_priv(&secp, &xpriv[1]),
     ];
 
     let push_deposit = |store: &mut dyn Storage| -> ContractResult<()> {
@@ -146,7 +145,7 @@ fn check_change_rates() -> ContractResult<()> {
         let mut btc = btc.borrow_mut();
         btc.add_withdrawal(
             store,
-            Adapter::new(Script::new()),
+            Adapter::new(ScriptBuf::new()),
             459_459_927_000_000u128.into(),
             None,
         )?;
@@ -174,6 +173,7 @@ fn check_change_rates() -> ContractResult<()> {
                 store,
                 &Xpub::new(xpub[i]),
                 sigs,
+                None,
                 sigset_index,
                 btc_height,
             )?;
@@ -199,7 +199,7 @@ fn check_change_rates() -> ContractResult<()> {
             let mock_querier = QuerierWrapper::new(&mock_query);
             *block_height += 1;
             let mut btc = btc.borrow_mut();
-            btc.begin_block_step(&env, &mock_querier, store, vec![1, 2, 3])?;
+            btc.begin_block_step(&env, &mock_querier, store, vec![1, 2, 3], &mut u32::MAX)?;
             Ok(())
         };
 
@@ -309,6 +309,138 @@ fn check_change_rates() -> ContractResult<()> {
     Ok(())
 }
 
+#[test]
+fn test_compact_checkpoint() -> ContractResult<()> {
+    use crate::checkpoint::{Checkpoint, CheckpointStatus};
+    use crate::signatory::{Signatory, SignatorySet};
+    use crate::state::CHECKPOINTS;
+
+    let mut deps = mock_dependencies();
+    let deps_mut = deps.as_mut();
+    let (store, api) = (deps_mut.storage, deps_mut.api);
+    BUILDING_INDEX.save(store, &0)?;
+
+    let secp = Secp256k1::new();
+    let xpriv = bip32::Xpriv::new_master(bitcoin::Network::Bitcoin, &[0])?;
+    let xpub = Xpub::new(bip32::Xpub::from_priv(&secp, &xpriv));
+    let mut sigset = SignatorySet::default();
+    sigset.signatories.push(Signatory {
+        voting_power: 100,
+        pubkey: xpub.derive_pubkey(0)?.into(),
+    });
+    sigset.present_vp = 100;
+    sigset.possible_vp = 100;
+    let mut checkpoint = Checkpoint::new(sigset.clone())?;
+    let checkpoint_tx = &mut checkpoint.batches[BatchType::Checkpoint][0];
+    checkpoint_tx.input.push(Input::new(
+        OutPoint::default(),
+        &sigset,
+        &[0u8],
+        100_000,
+        CheckpointConfig::default().sigset_threshold,
+    )?);
+    checkpoint_tx.populate_sig_messages()?;
+    checkpoint.status = CheckpointStatus::Signing;
+    CHECKPOINTS.push_back(store, &checkpoint)?;
+
+    let mut btc = Bitcoin::default();
+    let to_sign = btc.checkpoints.get(store, 0)?.to_sign(&xpub)?;
+    let sigs = sign(&Secp256k1::signing_only(), &xpriv, &to_sign)?;
+    assert!(btc
+        .checkpoints
+        .sign(api, store, &xpub, sigs, None, 0, 10)?
+        .is_some());
+
+    // compacting a signed checkpoint keeps its transaction intact
+    let mut signed_cp = btc.checkpoints.get(store, 0)?;
+    let signed_tx = signed_cp.checkpoint_tx()?;
+    assert!(signed_cp.compact()? > 0);
+    assert_eq!(signed_cp.checkpoint_tx()?, signed_tx);
+    assert_eq!(signed_cp.compact()?, 0);
+
+    Ok(())
+}
+
+#[test]
+fn test_sign_checkpoint_in_chunks() -> ContractResult<()> {
+    use crate::checkpoint::{Checkpoint, CheckpointStatus};
+    use crate::signatory::{Signatory, SignatorySet};
+    use crate::state::CHECKPOINTS;
+    use std::ops::Range;
+
+    let mut deps = mock_dependencies();
+    let deps_mut = deps.as_mut();
+    let (store, api) = (deps_mut.storage, deps_mut.api);
+    BUILDING_INDEX.save(store, &0)?;
+
+    let secp = Secp256k1::new();
+    let xpriv = bip32::Xpriv::new_master(bitcoin::Network::Bitcoin, &[0])?;
+    let xpub = Xpub::new(bip32::Xpub::from_priv(&secp, &xpriv));
+    let mut sigset = SignatorySet::default();
+    sigset.signatories.push(Signatory {
+        voting_power: 100,
+        pubkey: xpub.derive_pubkey(0)?.into(),
+    });
+    sigset.present_vp = 100;
+    sigset.possible_vp = 100;
+    let mut checkpoint = Checkpoint::new(sigset.clone())?;
+    let checkpoint_tx = &mut checkpoint.batches[BatchType::Checkpoint][0];
+    for vout in 0..3 {
+        checkpoint_tx.input.push(Input::new(
+            OutPoint::new(Txid::all_zeros(), vout),
+            &sigset,
+            &[0u8],
+            100_000,
+            CheckpointConfig::default().sigset_threshold,
+        )?);
+    }
+    checkpoint_tx.populate_sig_messages()?;
+    checkpoint.status = CheckpointStatus::Signing;
+    CHECKPOINTS.push_back(store, &checkpoint)?;
+
+    let mut btc = Bitcoin::default();
+    let to_sign = btc.checkpoints.get(store, 0)?.to_sign(&xpub)?;
+    let sigs = sign(&Secp256k1::signing_only(), &xpriv, &to_sign)?;
+    assert_eq!(sigs.len(), 3);
+    let progress = |store: &dyn Storage| -> ContractResult<(u32, u32)> {
+        let progress = Bitcoin::default()
+            .checkpoints
+            .get(store, 0)?
+            .signing_progress(&xpub)?;
+        Ok((progress.signed, progress.total))
+    };
+    assert_eq!(progress(store)?, (0, 3));
+
+    // chunks may be submitted in any order, each at its input offset
+    let mut sign_chunk = |store: &mut dyn Storage, offset: u32, chunk: Range<usize>| {
+        btc.checkpoints
+            .sign(api, store, &xpub, sigs[chunk].to_vec(), Some(offset), 0, 10)
+    };
+    assert!(sign_chunk(store, 0, 0..1)?.is_none());
+    assert_eq!(progress(store)?, (1, 3));
+    assert!(sign_chunk(store, 2, 2..3)?.is_none());
+    assert_eq!(progress(store)?, (2, 3));
+
+    // a chunk overlapping signed inputs is rejected without effect
+    assert!(matches!(
+        sign_chunk(store, 0, 0..2),
+        Err(ContractError::AlreadySigned {})
+    ));
+    assert_eq!(progress(store)?, (2, 3));
+    // as is one past the signatory's inputs
+    assert!(matches!(
+        sign_chunk(store, 3, 1..2),
+        Err(ContractError::ExcessSignatures {})
+    ));
+
+    // the last chunk completes the checkpoint
+    assert!(sign_chunk(store, 1, 1..2)?.is_some());
+    assert_eq!(progress(store)?, (3, 3));
+    assert!(btc.checkpoints.get(store, 0)?.signed());
+
+    Ok(())
+}
+
 #[test]
 fn test_take_pending() -> ContractResult<()> {
     let mut deps = mock_dependencies();
@@ -319,22 +451,7 @@ fn test_take_pending() -> ContractResult<()> {
     let mock_querier = QuerierWrapper::new(&mock_query);
 
     let bitcoin_config = BitcoinConfig::default();
-    CONFIG.save(
-        deps.as_mut().storage,
-        &Config {
-            owner: Addr::unchecked("owner"),
-            relayer_fee_receiver: Addr::unchecked("relayer_fee_receiver"),
-            token_fee_receiver: Addr::unchecked("token_fee_receiver"),
-            relayer_fee_token: AssetInfo::NativeToken {
-                denom: "orai".to_string(),
-            },
-            relayer_fee: Uint128::from(0u128),
-            token_factory_contract: Addr::unchecked("token_factory_contract"),
-            light_client_contract: Addr::unchecked("light_client_contract"),
-            swap_router_contract: None,
-            osor_entry_point_contract: None,
-        },
-    )?;
+    CONFIG.save(deps.as_mut().storage, &test_config())?;
     BITCOIN_CONFIG.save(deps.as_mut().storage, &bitcoin_config)?;
     FEE_POOL.save(deps.as_mut().storage, &0)?;
     CHECKPOINT_CONFIG.save(deps.as_mut().storage, &CheckpointConfig::default())?;
@@ -365,12 +482,12 @@ fn test_take_pending() -> ContractResult<()> {
     let secp = Secp256k1::new();
     let network = bitcoin::Network::Bitcoin;
     let xpriv = vec![
-        ExtendedPrivKey::new_master(network, &[0])?,
-        ExtendedPrivKey::new_master(network, &[1])?,
+        bip32::Xpriv::new_master(network, &[0])?,
+        bip32::Xpriv::new_master(network, &[1])?,
     ];
     let xpub = vec![
-        ExtendedPubKey::from_priv(&secp, &xpriv[0]),
-        ExtendedPubKey::from_priv(&secp, &xpriv[1]),
+        bip32::Xpub::from_priv(&secp, &xpriv[0]),
+        bip32::Xpub::from_priv(&secp, &xpriv[1]),
     ];
 
     let push_deposit = |store: &mut dyn Storage, dest: Dest, coin: Coin| -> ContractResult<()> {
@@ -389,7 +506,11 @@ fn test_take_pending() -> ContractResult<()> {
         let btc = btc.borrow_mut();
         let mut building_mut = btc.checkpoints.building(store)?;
         building_mut.fees_collected += 100_000_000u64 - (coin.amount.u128() as u64);
-        building_mut.pending.push((dest, coin));
+        building_mut.pending.push(PendingTransfer {
+            dest,
+            coin,
+            sender_commitment: Binary::default(),
+        });
         let building_checkpoint_batch = &mut building_mut.batches[BatchType::Checkpoint];
         let checkpoint_tx = building_checkpoint_batch.get_mut(0).unwrap();
         checkpoint_tx.input.push(input);
@@ -416,6 +537,7 @@ fn test_take_pending() -> ContractResult<()> {
                 store,
                 &Xpub::new(xpub[i]),
                 sigs,
+                None,
                 sigset_index,
                 btc_height,
             )?;
@@ -452,7 +574,7 @@ fn test_take_pending() -> ContractResult<()> {
             mock_query.update_wasm(handle_wasm_query(block_height.clone()));
             *block_height += 1;
             let mut btc = btc.borrow_mut();
-            btc.begin_block_step(&env, &mock_querier, store, vec![1, 2, 3])?;
+            btc.begin_block_step(&env, &mock_querier, store, vec![1, 2, 3], &mut u32::MAX)?;
 
             Ok(())
         };
@@ -570,31 +692,31 @@ fn test_take_pending() -> ContractResult<()> {
     assert_eq!(cp_dests[1].len(), 1); // cp_dest confirmed
     assert_eq!(cp_dests[2].len(), 0); // cp_dest completed
     assert_eq!(
-        cp_dests[0][0].0,
+        cp_dests[0][0].dest,
         Dest::Ibc(IbcDest {
             sender: "sender1".to_string(),
             ..dest.clone()
         })
     );
-    assert_eq!(cp_dests[0][0].1.amount.u128(), 95_000_000u128);
+    assert_eq!(cp_dests[0][0].coin.amount.u128(), 95_000_000u128);
 
     assert_eq!(
-        cp_dests[0][1].0,
+        cp_dests[0][1].dest,
         Dest::Ibc(IbcDest {
             sender: "sender2".to_string(),
             ..dest.clone()
         })
     );
-    assert_eq!(cp_dests[0][1].1.amount.u128(), 95_000_000u128);
+    assert_eq!(cp_dests[0][1].coin.amount.u128(), 95_000_000u128);
 
     assert_eq!(
-        cp_dests[1][0].0,
+        cp_dests[1][0].dest,
         Dest::Ibc(IbcDest {
             sender: "sender2".to_string(),
             ..dest.clone()
         })
     );
-    assert_eq!(cp_dests[1][0].1.amount.u128(), 98_000_000u128);
+    assert_eq!(cp_dests[1][0].coin.amount.u128(), 98_000_000u128);
 
     // // assert confirmed checkpoints pending. Should not have anything because we have removed them already in take_pending()
     let checkpoints = &btc.borrow().checkpoints;
@@ -604,3 +726,2788 @@ fn test_take_pending() -> ContractResult<()> {
     assert_eq!(second_cp.pending.iter().count(), 0);
     Ok(())
 }
+
+#[test]
+fn test_metrics_confirmation_time() -> ContractResult<()> {
+    use crate::checkpoint::{Checkpoint, CheckpointQueue, CheckpointStatus};
+    use crate::entrypoints::query_metrics;
+    use crate::signatory::SignatorySet;
+    use crate::state::{CHECKPOINTS, CHECKPOINT_CONFIRMATION_TIMES};
+    use bitcoin::block::{Header as BlockHeader, Version};
+    use bitcoin::{BlockHash, CompactTarget, TxMerkleNode};
+    use light_client_bitcoin::header::WrappedHeader;
+    use light_client_bitcoin::msg::{HeaderAtResponse, QueryMsg as LightClientQueryMsg};
+    use light_client_bitcoin::uint::Uint256;
+
+    let mut deps = mock_dependencies();
+    CONFIG.save(deps.as_mut().storage, &test_config())?;
+    BITCOIN_CONFIG.save(
+        deps.as_mut().storage,
+        &BitcoinConfig {
+            reward_pool_enabled: false,
+            ..BitcoinConfig::default()
+        },
+    )?;
+    // blocks are mined every 10 minutes
+    let mut mock_query = MockQuerier::<Empty>::new(&[]);
+    mock_query.update_wasm(|query| {
+        let WasmQuery::Smart { msg, .. } = query else {
+            unreachable!()
+        };
+        let res = match from_json::<LightClientQueryMsg>(msg).unwrap() {
+            LightClientQueryMsg::HeaderAt { height } => {
+                let header = BlockHeader {
+                    version: Version::ONE,
+                    prev_blockhash: BlockHash::all_zeros(),
+                    merkle_root: TxMerkleNode::all_zeros(),
+                    time: height * 600,
+                    bits: CompactTarget::from_consensus(0x1d00ffff),
+                    nonce: 0,
+                };
+                to_json_binary(&Some(HeaderAtResponse {
+                    header: WrappedHeader::from_header(&header, height),
+                    chain_work: Adapter::new(Uint256::from_u64(1)),
+                    target: Adapter::new(Uint256::from_u64(1)),
+                }))
+            }
+            LightClientQueryMsg::HeaderHeight {} => to_json_binary(&110u32),
+            LightClientQueryMsg::HeaderTime {} => to_json_binary(&66_000u32),
+            _ => unreachable!(),
+        };
+        SystemResult::Ok(cosmwasm_std::ContractResult::Ok(res.unwrap()))
+    });
+    let mock_querier = QuerierWrapper::new(&mock_query);
+
+    for signed_at in [100, 104] {
+        CHECKPOINTS.push_back(
+            deps.as_mut().storage,
+            &Checkpoint {
+                status: CheckpointStatus::Complete,
+                fee_rate: 10,
+                signed_at_btc_height: Some(signed_at),
+                deposits_enabled: true,
+                sigset: SignatorySet::default(),
+                fees_collected: 0,
+                fee_pool_top_up: 0,
+                withdrawal_fee_surplus: 0,
+                withdrawal_fees: vec![],
+                pending: vec![],
+                batches: vec![],
+                forced_tx: None,
+                sig_scheme: Default::default(),
+            },
+        )?;
+    }
+
+    let proof = Adapter::new(PartialMerkleTree::from_txids(&[Txid::all_zeros()], &[true]));
+    let relayer = Addr::unchecked("relayer");
+    let mut btc = Bitcoin::default();
+    for (cp_index, confirmed_at) in [(0, 103), (1, 108)] {
+        assert!(btc.relay_checkpoint(
+            &mock_querier,
+            deps.as_mut().storage,
+            confirmed_at,
+            proof.clone(),
+            Some(cp_index),
+            vec![],
+            &relayer,
+            true,
+        )?);
+    }
+    assert_eq!(
+        CHECKPOINT_CONFIRMATION_TIMES.load(deps.as_ref().storage, 0)?,
+        3 * 600
+    );
+    assert_eq!(
+        CHECKPOINT_CONFIRMATION_TIMES.load(deps.as_ref().storage, 1)?,
+        4 * 600
+    );
+
+    // the average is reported in seconds
+    CheckpointQueue::default().reset(deps.as_mut().storage)?;
+    let metrics = query_metrics(deps.as_ref().storage, mock_querier)?;
+    assert_eq!(metrics.avg_confirmation_time, 2100);
+    assert_eq!(metrics.header_height, 110);
+    Ok(())
+}
+
+#[test]
+fn test_relay_checkpoint_quorum() -> ContractResult<()> {
+    let mut deps = mock_dependencies();
+    let mock_query = MockQuerier::<Empty>::new(&[]);
+    let mock_querier = QuerierWrapper::new(&mock_query);
+
+    CONFIG.save(deps.as_mut().storage, &test_config())?;
+    BITCOIN_CONFIG.save(
+        deps.as_mut().storage,
+        &BitcoinConfig {
+            checkpoint_confirmation_quorum: 2,
+            ..BitcoinConfig::default()
+        },
+    )?;
+
+    let relayers = [
+        Addr::unchecked("relayer1"),
+        Addr::unchecked("relayer2"),
+        Addr::unchecked("relayer3"),
+    ];
+    for relayer in relayers.iter() {
+        RELAYERS.save(deps.as_mut().storage, relayer, &())?;
+    }
+    let proof = Adapter::new(PartialMerkleTree::from_txids(&[Txid::all_zeros()], &[true]));
+
+    let mut btc = Bitcoin::default();
+
+    // unregistered relayers can not attest
+    assert!(btc
+        .relay_checkpoint(
+            &mock_querier,
+            deps.as_mut().storage,
+            10,
+            proof.clone(),
+            Some(0),
+            vec![],
+            &Addr::unchecked("stranger"),
+            true,
+        )
+        .is_err());
+
+    let confirmed = btc.relay_checkpoint(
+        &mock_querier,
+        deps.as_mut().storage,
+        10,
+        proof.clone(),
+        Some(0),
+        vec![],
+        &relayers[0],
+        true,
+    )?;
+    assert!(!confirmed);
+    // attesting twice with the same relayer does not count towards the quorum
+    let confirmed = btc.relay_checkpoint(
+        &mock_querier,
+        deps.as_mut().storage,
+        10,
+        proof.clone(),
+        Some(0),
+        vec![],
+        &relayers[0],
+        true,
+    )?;
+    assert!(!confirmed);
+    // mismatched btc height does not count towards the quorum
+    let confirmed = btc.relay_checkpoint(
+        &mock_querier,
+        deps.as_mut().storage,
+        11,
+        proof.clone(),
+        Some(0),
+        vec![],
+        &relayers[1],
+        true,
+    )?;
+    assert!(!confirmed);
+    assert_eq!(CONFIRMED_INDEX.may_load(deps.as_ref().storage)?, None);
+
+    let confirmed = btc.relay_checkpoint(
+        &mock_querier,
+        deps.as_mut().storage,
+        10,
+        proof.clone(),
+        Some(0),
+        vec![],
+        &relayers[2],
+        true,
+    )?;
+    assert!(confirmed);
+    assert_eq!(CONFIRMED_INDEX.load(deps.as_ref().storage)?, 0);
+    assert_eq!(
+        CHECKPOINT_ATTESTATIONS
+            .prefix(0)
+            .keys(deps.as_ref().storage, None, None, Order::Ascending)
+            .count(),
+        0
+    );
+
+    // relaying an already confirmed checkpoint again is a no-op
+    let confirmed = btc.relay_checkpoint(
+        &mock_querier,
+        deps.as_mut().storage,
+        12,
+        proof,
+        Some(0),
+        vec![],
+        &Addr::unchecked("stranger"),
+        true,
+    )?;
+    assert!(confirmed);
+    assert_eq!(
+        CHECKPOINT_CONFIRMATION_HEIGHTS.load(deps.as_ref().storage, 0)?,
+        10
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_relay_checkpoint_derives_index() -> ContractResult<()> {
+    let mut deps = mock_dependencies();
+    let mock_query = MockQuerier::<Empty>::new(&[]);
+    let mock_querier = QuerierWrapper::new(&mock_query);
+
+    CONFIG.save(deps.as_mut().storage, &test_config())?;
+    BITCOIN_CONFIG.save(deps.as_mut().storage, &BitcoinConfig::default())?;
+
+    let relayer = Addr::unchecked("relayer");
+    let proof = Adapter::new(PartialMerkleTree::from_txids(&[Txid::all_zeros()], &[true]));
+    let mut btc = Bitcoin::default();
+
+    // proofs of unknown transactions need an explicit index
+    assert!(btc
+        .relay_checkpoint(
+            &mock_querier,
+            deps.as_mut().storage,
+            10,
+            proof.clone(),
+            None,
+            vec![],
+            &relayer,
+            true,
+        )
+        .is_err());
+
+    CHECKPOINT_TXIDS.save(deps.as_mut().storage, &Txid::all_zeros().to_string(), &2)?;
+    // a later checkpoint was already confirmed out of order
+    CHECKPOINT_CONFIRMATION_HEIGHTS.save(deps.as_mut().storage, 3, &11)?;
+
+    // the given index must agree with the proof
+    assert!(btc
+        .relay_checkpoint(
+            &mock_querier,
+            deps.as_mut().storage,
+            10,
+            proof.clone(),
+            Some(1),
+            vec![],
+            &relayer,
+            true,
+        )
+        .is_err());
+
+    let confirmed = btc.relay_checkpoint(
+        &mock_querier,
+        deps.as_mut().storage,
+        10,
+        proof,
+        None,
+        vec![],
+        &relayer,
+        true,
+    )?;
+    assert!(confirmed);
+    assert_eq!(
+        CHECKPOINT_CONFIRMATION_HEIGHTS.load(deps.as_ref().storage, 2)?,
+        10
+    );
+    assert_eq!(CONFIRMED_INDEX.load(deps.as_ref().storage)?, 3);
+
+    Ok(())
+}
+
+#[test]
+fn test_mint_claims() -> ContractResult<()> {
+    let mut deps = mock_dependencies();
+    let mut env = mock_env();
+    CONFIG.save(deps.as_mut().storage, &test_config())?;
+    BITCOIN_CONFIG.save(
+        deps.as_mut().storage,
+        &BitcoinConfig {
+            mint_claim_expiry: 100,
+            mint_claim_recovery_address: Some(Addr::unchecked("recovery")),
+            ..BitcoinConfig::default()
+        },
+    )?;
+
+    let recipient = Addr::unchecked("recipient");
+    let coin = Coin::new(1000, BTC_NATIVE_TOKEN_DENOM);
+    let hook = BtcBridgeReceiveMsg {
+        sender_commitment: Binary::from(b"sender".to_vec()),
+        amount: coin.amount,
+    };
+    for _ in 0..2 {
+        PENDING_MINTS.push_back(
+            deps.as_mut().storage,
+            &(recipient.clone(), coin.clone(), Some(hook.clone())),
+        )?;
+    }
+    // a successful push leaves nothing to claim and notifies the recipient
+    let res = mint_reply(
+        &env,
+        deps.as_mut().storage,
+        SubMsgResult::Ok(SubMsgResponse {
+            events: vec![],
+            data: None,
+        }),
+    )?;
+    assert_eq!(res.messages.len(), 1);
+    assert_eq!(
+        res.messages[0].msg,
+        hook.clone().into_cosmos_msg(&recipient)?
+    );
+    assert!(query_mint_claims(
+        deps.as_ref().storage,
+        deps.as_ref().api,
+        "recipient".to_string()
+    )?
+    .is_empty());
+
+    // the recipient is not notified of a failed mint
+    let res = mint_reply(
+        &env,
+        deps.as_mut().storage,
+        SubMsgResult::Err("rejected".to_string()),
+    )?;
+    assert!(res.messages.is_empty());
+    let claims = query_mint_claims(
+        deps.as_ref().storage,
+        deps.as_ref().api,
+        "recipient".to_string(),
+    )?;
+    assert_eq!(claims.len(), 1);
+    assert_eq!(claims[0].coin, coin);
+    assert_eq!(claims[0].expires_at, Some(env.block.time.seconds() + 100));
+
+    // only the recipient can pull its claims
+    assert!(claim_mint(&env, deps.as_mut().storage, mock_info("other", &[])).is_err());
+    let res = claim_mint(&env, deps.as_mut().storage, mock_info("recipient", &[]))?;
+    assert_eq!(res.messages.len(), 1);
+    assert!(MINT_CLAIMS
+        .may_load(deps.as_ref().storage, (&recipient, 0))?
+        .is_none());
+
+    // expired claims are left for recovery
+    PENDING_MINTS.push_back(deps.as_mut().storage, &(recipient.clone(), coin, None))?;
+    mint_reply(
+        &env,
+        deps.as_mut().storage,
+        SubMsgResult::Err("rejected".to_string()),
+    )?;
+    env.block.time = env.block.time.plus_seconds(100);
+    assert!(claim_mint(&env, deps.as_mut().storage, mock_info("recipient", &[])).is_err());
+
+    // claims can't expire without a recovery address
+    assert!(update_bitcoin_config(
+        deps.as_mut().storage,
+        mock_info("owner", &[]),
+        BitcoinConfig {
+            mint_claim_expiry: 100,
+            ..BitcoinConfig::default()
+        },
+    )
+    .is_err());
+    update_bitcoin_config(
+        deps.as_mut().storage,
+        mock_info("owner", &[]),
+        BitcoinConfig::default(),
+    )?;
+
+    Ok(())
+}
+
+#[test]
+fn test_migrate_denom() -> ContractResult<()> {
+    use crate::entrypoints::migrate_denom;
+    use crate::interface::MintClaim;
+    use crate::state::{get_full_btc_denom, is_btc_denom, BRIDGE_PAUSED};
+    use cosmwasm_std::{CosmosMsg, WasmMsg};
+
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+    let store = deps.as_mut().storage;
+    CONFIG.save(store, &test_config())?;
+    let old_denom = get_full_btc_denom(store, "token_factory_contract")?;
+
+    // a claim recorded under the old denom
+    let recipient = Addr::unchecked("recipient");
+    MINT_CLAIMS.save(
+        store,
+        (&recipient, 0),
+        &MintClaim {
+            id: 0,
+            recipient: recipient.clone(),
+            coin: Coin::new(1000, old_denom.clone()),
+            expires_at: None,
+        },
+    )?;
+
+    // the bridge must be paused
+    assert!(migrate_denom(store, mock_info("owner", &[]), "nbtc".to_string()).is_err());
+    BRIDGE_PAUSED.save(store, &true)?;
+    // the current denom can't be migrated to
+    assert!(migrate_denom(
+        store,
+        mock_info("owner", &[]),
+        BTC_NATIVE_TOKEN_DENOM.to_string()
+    )
+    .is_err());
+    migrate_denom(store, mock_info("owner", &[]), "nbtc".to_string())?;
+    let new_denom = get_full_btc_denom(store, "token_factory_contract")?;
+    assert_eq!(new_denom, "factory/token_factory_contract/nbtc");
+    assert!(is_btc_denom(store, "token_factory_contract", &old_denom)?);
+    assert!(is_btc_denom(store, "token_factory_contract", &new_denom)?);
+    // nor can a legacy denom
+    assert!(migrate_denom(
+        store,
+        mock_info("owner", &[]),
+        BTC_NATIVE_TOKEN_DENOM.to_string()
+    )
+    .is_err());
+
+    // the old claim is minted as the new denom
+    let res = claim_mint(&env, store, mock_info("recipient", &[]))?;
+    assert_eq!(res.messages.len(), 1);
+    let CosmosMsg::Wasm(WasmMsg::Execute { msg, .. }) = &res.messages[0].msg else {
+        panic!("expected a tokenfactory mint");
+    };
+    match from_json(msg)? {
+        tokenfactory::msg::ExecuteMsg::MintTokens { denom, amount, .. } => {
+            assert_eq!(denom, new_denom);
+            assert_eq!(amount, Uint128::new(1000));
+        }
+        _ => panic!("expected a tokenfactory mint"),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn pending_transfer_sender_commitment() -> ContractResult<()> {
+    let dest = Dest::Address(Addr::unchecked("vault"));
+    let coin = Coin::new(1000, BTC_NATIVE_TOKEN_DENOM);
+    let outpoint = OutPoint::new(Txid::from_slice(&[7; 32])?, 2);
+
+    // a deposit is sent by its outpoint, not by its destination
+    let transfer = PendingTransfer::deposit(dest.clone(), coin.clone(), &outpoint);
+    let mut expected = vec![7u8; 32];
+    expected.extend_from_slice(&2u32.to_le_bytes());
+    assert_eq!(transfer.sender_commitment, Binary::from(expected));
+    assert_ne!(
+        transfer.sender_commitment.to_vec(),
+        dest.commitment_bytes()?
+    );
+    let stored: PendingTransfer = from_json(to_json_binary(&transfer)?)?;
+    assert_eq!(stored, transfer);
+
+    // transfers stored as pairs decode without a sender
+    let legacy: PendingTransfer = from_json(to_json_binary(&(dest.clone(), coin.clone()))?)?;
+    assert_eq!(
+        legacy,
+        PendingTransfer {
+            dest,
+            coin,
+            sender_commitment: Binary::default(),
+        }
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_error_codes() {
+    let mut codes: Vec<u32> = ERROR_CODES.iter().map(|(code, _)| *code).collect();
+    codes.sort_unstable();
+    codes.dedup();
+    assert_eq!(codes.len(), ERROR_CODES.len());
+
+    let err = ContractError::InsufficientConfirmations { have: 1, need: 3 };
+    assert_eq!(err.code(), 33);
+    let coded = err.coded();
+    assert_eq!(
+        coded.to_string(),
+        "[E33] Block is not sufficiently confirmed: 1 of 3 confirmations"
+    );
+    // coding twice keeps the original code
+    assert_eq!(coded.coded().code(), 33);
+    assert!(ERROR_CODES.contains(&(33, "InsufficientConfirmations")));
+
+    // the table is generated from the same list as `code`
+    for err in [
+        ContractError::OutputAlreadyRelayed {
+            txid: Txid::all_zeros().to_string(),
+            vout: 1,
+        },
+        ContractError::NoCompletedCheckpoints {},
+        ContractError::ExcessSignatures {},
+        ContractError::HeadersNotConnected { height: 5 },
+        ContractError::App("app".to_string()),
+    ] {
+        let name = format!("{:?}", err);
+        let name = name.split([' ', '(']).next().unwrap();
+        assert!(ERROR_CODES.contains(&(err.code(), name)));
+    }
+    let names: std::collections::HashSet<_> = ERROR_CODES.iter().map(|(_, name)| name).collect();
+    assert_eq!(names.len(), ERROR_CODES.len());
+}
+
+#[test]
+fn test_dry_run_storage() -> ContractResult<()> {
+    use cw_storage_plus::Bound;
+
+    let mut deps = mock_dependencies();
+    SIGNERS.save(deps.as_mut().storage, "a", &[1; 32])?;
+    SIGNERS.save(deps.as_mut().storage, "b", &[2; 32])?;
+    FEE_POOL.save(deps.as_mut().storage, &100)?;
+
+    let mut dry_run = DryRunStorage::new(deps.as_ref().storage);
+    SIGNERS.remove(&mut dry_run, "a");
+    SIGNERS.save(&mut dry_run, "c", &[3; 32])?;
+    FEE_POOL.save(&mut dry_run, &50)?;
+
+    let keys = |store: &dyn Storage, order| {
+        SIGNERS
+            .keys(store, None, None, order)
+            .collect::<StdResult<Vec<String>>>()
+    };
+    assert_eq!(keys(&dry_run, Order::Ascending)?, vec!["b", "c"]);
+    assert_eq!(keys(&dry_run, Order::Descending)?, vec!["c", "b"]);
+    assert_eq!(FEE_POOL.load(&dry_run)?, 50);
+
+    // the underlying store is untouched
+    assert_eq!(
+        keys(deps.as_ref().storage, Order::Ascending)?,
+        vec!["a", "b"]
+    );
+    assert_eq!(FEE_POOL.load(deps.as_ref().storage)?, 100);
+
+    // writes interleave with the base records and shadow them
+    SIGNERS.save(deps.as_mut().storage, "d", &[4; 32])?;
+    let mut dry_run = DryRunStorage::new(deps.as_ref().storage);
+    SIGNERS.save(&mut dry_run, "b", &[5; 32])?;
+    SIGNERS.save(&mut dry_run, "c", &[3; 32])?;
+    SIGNERS.save(&mut dry_run, "e", &[6; 32])?;
+    SIGNERS.remove(&mut dry_run, "d");
+    let records = |order| {
+        SIGNERS
+            .range(&dry_run, None, None, order)
+            .collect::<StdResult<Vec<_>>>()
+    };
+    let expected = vec![
+        ("a".to_string(), [1; 32]),
+        ("b".to_string(), [5; 32]),
+        ("c".to_string(), [3; 32]),
+        ("e".to_string(), [6; 32]),
+    ];
+    assert_eq!(records(Order::Ascending)?, expected);
+    let mut reversed = expected.clone();
+    reversed.reverse();
+    assert_eq!(records(Order::Descending)?, reversed);
+    // only the records within the bounds are merged
+    let bounded = |order| {
+        SIGNERS
+            .keys(
+                &dry_run,
+                Some(Bound::exclusive("a")),
+                Some(Bound::inclusive("d")),
+                order,
+            )
+            .collect::<StdResult<Vec<String>>>()
+    };
+    assert_eq!(bounded(Order::Ascending)?, vec!["b", "c"]);
+    assert_eq!(bounded(Order::Descending)?, vec!["c", "b"]);
+
+    Ok(())
+}
+
+#[test]
+fn test_simulate_end_block() -> ContractResult<()> {
+    use crate::entrypoints::query_simulate_end_block;
+    use crate::state::{BLOCK_HASHES, CHECKPOINTS};
+
+    let mut deps = mock_dependencies();
+    let mut mock_query = MockQuerier::<Empty>::new(&[]);
+    mock_query.update_wasm(handle_wasm_query(10));
+    let querier = QuerierWrapper::new(&mock_query);
+    let deps_mut = deps.as_mut();
+    let (store, api) = (deps_mut.storage, deps_mut.api);
+    CONFIG.save(store, &test_config())?;
+    BITCOIN_CONFIG.save(store, &BitcoinConfig::default())?;
+    CHECKPOINT_CONFIG.save(store, &CheckpointConfig::default())?;
+    FEE_POOL.save(store, &0)?;
+    FOUNDATION_KEYS.save(store, &Vec::new())?;
+    BUILDING_INDEX.save(store, &0)?;
+
+    let env = set_time(0);
+    // without validators no checkpoint is pushed
+    let report = query_simulate_end_block(&env, store, querier, api)?;
+    assert!(!report.checkpoint_advanced);
+    assert_eq!(report.new_sigset_index, None);
+
+    let consensus_key = [0; 32];
+    VALIDATORS.save(store, &consensus_key, &(100, "validator".to_string()))?;
+    SIGNERS.save(store, "validator", &consensus_key)?;
+    let secp = Secp256k1::new();
+    let xpriv = bip32::Xpriv::new_master(bitcoin::Network::Bitcoin, &[0])?;
+    let xpub = Xpub::new(bip32::Xpub::from_priv(&secp, &xpriv));
+    Bitcoin::default().set_signatory_key(&querier, store, Addr::unchecked("validator"), xpub)?;
+
+    // the first block pushes the first checkpoint with a new signatory set
+    let report = query_simulate_end_block(&env, store, querier, api)?;
+    assert!(report.checkpoint_advanced);
+    assert_eq!(report.building_index, 0);
+    assert_eq!(report.new_sigset_index, Some(0));
+    assert!(report.sigset_rotated);
+    assert_eq!(report.checkpoint_fee, None);
+    assert_eq!(report.fee_pool_change, 0);
+    assert!(report.removed_signers.is_empty());
+    assert!(!report.bridge_paused);
+
+    // nothing is persisted
+    assert!(CHECKPOINTS.is_empty(store)?);
+    assert!(BLOCK_HASHES.is_empty(store));
+    assert_eq!(query_simulate_end_block(&env, store, querier, api)?, report);
+
+    Ok(())
+}
+
+#[test]
+fn test_owner_multisig() -> ContractResult<()> {
+    use crate::entrypoints::{query_owner_proposal, set_owner_multisig};
+    use crate::msg::{Cw3ExecuteMsg, Cw3Voter, Cw3VoterListResponse};
+    use cosmwasm_std::{CosmosMsg, WasmMsg};
+
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+    let mut mock_query = MockQuerier::<Empty>::new(&[]);
+    mock_query.update_wasm(|wasm_query: &WasmQuery| -> QuerierResult {
+        let WasmQuery::Smart { contract_addr, .. } = wasm_query else {
+            unreachable!()
+        };
+        let voters = match contract_addr.as_str() {
+            "multisig" => vec![Cw3Voter {
+                addr: "voter".to_string(),
+                weight: 1,
+            }],
+            "empty_multisig" => vec![],
+            _ => {
+                return SystemResult::Err(SystemError::NoSuchContract {
+                    addr: contract_addr.clone(),
+                })
+            }
+        };
+        SystemResult::Ok(cosmwasm_std::ContractResult::Ok(
+            to_json_binary(&Cw3VoterListResponse { voters }).unwrap(),
+        ))
+    });
+    let querier = QuerierWrapper::new(&mock_query);
+    let store = deps.as_mut().storage;
+    CONFIG.save(store, &test_config())?;
+
+    // proposals can only be built for a multisig owner
+    let msgs = vec![ExecuteMsg::SetBridgePaused { paused: true }];
+    let proposal = |store: &dyn Storage| {
+        query_owner_proposal(
+            &env,
+            store,
+            querier,
+            "pause".to_string(),
+            "pause the bridge".to_string(),
+            msgs.clone(),
+        )
+    };
+    assert!(proposal(store).is_err());
+
+    let multisig = Addr::unchecked("multisig");
+    let err =
+        set_owner_multisig(store, &querier, mock_info("other", &[]), multisig.clone()).unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+    for invalid in ["account", "empty_multisig"] {
+        assert!(set_owner_multisig(
+            store,
+            &querier,
+            mock_info("owner", &[]),
+            Addr::unchecked(invalid)
+        )
+        .is_err());
+    }
+    set_owner_multisig(store, &querier, mock_info("owner", &[]), multisig.clone())?;
+    assert_eq!(CONFIG.load(store)?.owner, multisig);
+    // the previous owner lost its rights
+    let err =
+        set_owner_multisig(store, &querier, mock_info("owner", &[]), multisig.clone()).unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+
+    let CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr, msg, ..
+    }) = proposal(store)?
+    else {
+        panic!("expected a proposal to the multisig");
+    };
+    assert_eq!(contract_addr, "multisig");
+    let Cw3ExecuteMsg::Propose { title, msgs, .. } = from_json(msg)?;
+    assert_eq!(title, "pause");
+    assert_eq!(msgs.len(), 1);
+    let CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr, msg, ..
+    }) = &msgs[0]
+    else {
+        panic!("expected a bridge message");
+    };
+    assert_eq!(contract_addr, env.contract.address.as_str());
+    assert_eq!(
+        from_json::<ExecuteMsg>(msg)?,
+        ExecuteMsg::SetBridgePaused { paused: true }
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_changes_since() -> ContractResult<()> {
+    let mut deps = mock_dependencies();
+    let store = deps.as_mut().storage;
+    for height in 1..=MAX_CHANGE_RECORDS as u64 + 5 {
+        record_change(
+            store,
+            height,
+            Change::CheckpointCreated {
+                index: height as u32,
+            },
+        )?;
+    }
+
+    let res = query_changes_since(store, 200)?;
+    assert!(!res.truncated);
+    assert_eq!(
+        res.changes
+            .iter()
+            .map(|record| record.height)
+            .collect::<Vec<_>>(),
+        vec![201, 202, 203, 204, 205]
+    );
+    assert!(query_changes_since(store, 205)?.changes.is_empty());
+
+    // the five oldest records were dropped
+    let res = query_changes_since(store, 3)?;
+    assert!(res.truncated);
+    assert_eq!(res.changes.len(), MAX_CHANGE_RECORDS as usize);
+    assert_eq!(res.changes[0].height, 6);
+    Ok(())
+}
+
+#[test]
+fn test_admin_log() -> ContractResult<()> {
+    let mut deps = mock_dependencies();
+    CONFIG.save(deps.as_mut().storage, &test_config())?;
+
+    let mut env = mock_env();
+    for (height, paused) in [(100, true), (101, false)] {
+        env.block.height = height;
+        crate::contract::execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("owner", &[]),
+            ExecuteMsg::SetBridgePaused { paused },
+        )?;
+    }
+    // messages open to anyone are not logged
+    crate::contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("user", &[]),
+        ExecuteMsg::ClaimMint {},
+    )
+    .ok();
+
+    let log = query_admin_log(deps.as_ref().storage, None, None)?;
+    assert_eq!(log.len(), 2);
+    assert_eq!(log[0].height, 100);
+    assert_eq!(log[0].sender, Addr::unchecked("owner"));
+    assert_eq!(log[0].action, "set_bridge_paused");
+    assert_ne!(log[0].payload_hash, log[1].payload_hash);
+
+    let log = query_admin_log(deps.as_ref().storage, Some(0), None)?;
+    assert_eq!(log.len(), 1);
+    assert_eq!(log[0].id, 1);
+    Ok(())
+}
+
+#[test]
+fn test_request_reinstatement() -> ContractResult<()> {
+    use crate::entrypoints::{query_jail_status, request_reinstatement};
+    use crate::state::{JAILED_SIGNERS, WHITELIST_VALIDATORS};
+
+    let mut deps = mock_dependencies();
+    let mut env = mock_env();
+    let signer = Addr::unchecked("signer");
+    BITCOIN_CONFIG.save(
+        deps.as_mut().storage,
+        &BitcoinConfig {
+            signer_reinstatement_cooldown: 100,
+            ..BitcoinConfig::default()
+        },
+    )?;
+    let cons_key = [1u8; 32];
+    VALIDATORS.save(deps.as_mut().storage, &cons_key, &(100, signer.to_string()))?;
+    SIGNERS.save(deps.as_mut().storage, signer.as_str(), &cons_key)?;
+
+    let now = env.block.time.seconds();
+    let mut btc = Bitcoin::default();
+    btc.punish_validator(deps.as_mut().storage, &cons_key, signer.to_string(), now)?;
+    assert!(!VALIDATORS.has(deps.as_ref().storage, &cons_key));
+    let status = query_jail_status(deps.as_ref().storage, signer.clone())?;
+    assert!(status.jailed);
+    assert_eq!(status.eligible_at, Some(now + 100));
+
+    let info = mock_info(signer.as_str(), &[]);
+    let err = request_reinstatement(deps.as_mut().storage, env.clone(), info.clone());
+    assert!(matches!(err, Err(ContractError::App(_))));
+
+    env.block.time = env.block.time.plus_seconds(100);
+    let err = request_reinstatement(deps.as_mut().storage, env.clone(), info.clone());
+    assert!(matches!(err, Err(ContractError::ValidatorUnwhitelisted {})));
+
+    WHITELIST_VALIDATORS.save(deps.as_mut().storage, signer.clone(), &())?;
+    request_reinstatement(deps.as_mut().storage, env, info)?;
+    assert!(
+        JAILED_SIGNERS
+            .load(deps.as_ref().storage, signer.as_str())?
+            .reinstatement_requested
+    );
+    Ok(())
+}
+
+#[test]
+fn test_hold_screened_withdrawal() -> ContractResult<()> {
+    use crate::entrypoints::{
+        query_held_withdrawals, review_held_withdrawal, set_screened_script, withdraw_to_bitcoin,
+    };
+    use crate::interface::ScriptScreening;
+    use crate::state::get_full_btc_denom;
+    use bitcoin::WPubkeyHash;
+    use cosmwasm_std::{BankMsg, CosmosMsg};
+
+    let mut deps = mock_dependencies();
+    let querier = QuerierWrapper::new(&MockQuerier::<Empty>::new(&[]));
+    let env = mock_env();
+    CONFIG.save(deps.as_mut().storage, &test_config())?;
+    BITCOIN_CONFIG.save(
+        deps.as_mut().storage,
+        &BitcoinConfig {
+            withdrawal_script_screening: ScriptScreening::Whitelist,
+            ..BitcoinConfig::default()
+        },
+    )?;
+    let script = ScriptBuf::new_p2wpkh(&WPubkeyHash::from_slice(&[1; 20]).unwrap());
+    let btc_address = script_to_address(&script, NETWORK)?.to_string();
+    let denom = get_full_btc_denom(deps.as_ref().storage, "token_factory_contract")?;
+    let funds = vec![Coin::new(1000, denom)];
+
+    // an unlisted script is held for review rather than burned
+    withdraw_to_bitcoin(
+        &mut deps.storage,
+        &querier,
+        &deps.api,
+        mock_info("sender", &funds),
+        env.clone(),
+        btc_address.clone(),
+        None,
+        None,
+        Default::default(),
+    )?;
+    let held = query_held_withdrawals(deps.as_ref().storage, None, None)?;
+    assert_eq!(held.len(), 1);
+    assert_eq!(held[0].1.funds, funds);
+
+    let err = set_screened_script(
+        deps.as_mut().storage,
+        mock_info("sender", &[]),
+        btc_address.clone(),
+        true,
+    );
+    assert!(matches!(err, Err(ContractError::Unauthorized {})));
+    set_screened_script(
+        deps.as_mut().storage,
+        mock_info("owner", &[]),
+        btc_address,
+        true,
+    )?;
+
+    let res = review_held_withdrawal(
+        &mut deps.storage,
+        &querier,
+        &deps.api,
+        env,
+        mock_info("owner", &[]),
+        held[0].0,
+        false,
+    )?;
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: "sender".to_string(),
+            amount: funds,
+        })
+    );
+    assert!(query_held_withdrawals(deps.as_ref().storage, None, None)?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_withdrawal_allowance() -> ContractResult<()> {
+    use crate::entrypoints::{
+        query_withdrawal_allowance, query_withdrawal_allowances, set_withdrawal_allowance,
+        withdraw_on_behalf,
+    };
+    use crate::interface::WithdrawalAllowance;
+
+    let mut deps = mock_dependencies();
+    let querier = QuerierWrapper::new(&MockQuerier::<Empty>::new(&[]));
+    let mut env = mock_env();
+    BITCOIN_CONFIG.save(deps.as_mut().storage, &BitcoinConfig::default())?;
+    let btc_address = script_to_address(
+        &ScriptBuf::new_p2wpkh(&bitcoin::WPubkeyHash::from_slice(&[1; 20]).unwrap()),
+        NETWORK,
+    )?
+    .to_string();
+    let expiry = env.block.time.seconds() + 100;
+
+    set_withdrawal_allowance(
+        deps.as_mut().storage,
+        env.clone(),
+        mock_info("granter", &[]),
+        Addr::unchecked("spender"),
+        Uint128::new(1000),
+        Some(expiry),
+    )?;
+    let allowance = WithdrawalAllowance {
+        amount: Uint128::new(1000),
+        expiry: Some(expiry),
+    };
+    assert_eq!(
+        query_withdrawal_allowance(
+            deps.as_ref().storage,
+            &deps.api,
+            "granter".to_string(),
+            "spender".to_string()
+        )?,
+        Some(allowance.clone())
+    );
+    assert_eq!(
+        query_withdrawal_allowances(
+            deps.as_ref().storage,
+            &deps.api,
+            "granter".to_string(),
+            None,
+            None
+        )?,
+        vec![(Addr::unchecked("spender"), allowance)]
+    );
+
+    let mut withdraw = |env: Env, spender: &str, amount: u128| {
+        withdraw_on_behalf(
+            &mut deps.storage,
+            &querier,
+            &deps.api,
+            mock_info(spender, &[]),
+            env,
+            Addr::unchecked("granter"),
+            btc_address.clone(),
+            Some(Uint128::new(amount)),
+            None,
+            Default::default(),
+        )
+    };
+    // only the spender may withdraw, up to the allowance and before it expires
+    assert!(withdraw(env.clone(), "stranger", 500).is_err());
+    assert!(withdraw(env.clone(), "spender", 1001).is_err());
+    env.block.time = env.block.time.plus_seconds(100);
+    assert!(withdraw(env.clone(), "spender", 500).is_err());
+
+    set_withdrawal_allowance(
+        deps.as_mut().storage,
+        env,
+        mock_info("granter", &[]),
+        Addr::unchecked("spender"),
+        Uint128::zero(),
+        None,
+    )?;
+    assert!(query_withdrawal_allowances(
+        deps.as_ref().storage,
+        &deps.api,
+        "granter".to_string(),
+        None,
+        None
+    )?
+    .is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_versioned_dest_commitment() -> ContractResult<()> {
+    let dests = [
+        Dest::Address(Addr::unchecked("orai1depositor")),
+        Dest::Ibc(IbcDest {
+            source_port: "transfer".to_string(),
+            source_channel: "channel-0".to_string(),
+            receiver: "cosmos1receiver".to_string(),
+            sender: "orai1sender".to_string(),
+            timeout_timestamp: 0,
+            memo: "".to_string(),
+        }),
+    ];
+    for dest in &dests {
+        let legacy = dest.versioned_commitment_bytes(LEGACY_DEST_COMMITMENT_VERSION)?;
+        assert_eq!(legacy, dest.commitment_bytes()?);
+
+        let versioned = dest.versioned_commitment_bytes(DEST_COMMITMENT_VERSION)?;
+        assert_eq!(versioned[0], DEST_COMMITMENT_VERSION);
+        assert_eq!(&versioned[1..], legacy.as_slice());
+        assert_ne!(versioned, legacy);
+    }
+    assert!(matches!(
+        dests[0].versioned_commitment_bytes(DEST_COMMITMENT_VERSION + 1),
+        Err(ContractError::App(_))
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_checkpoint_hooks() -> ContractResult<()> {
+    use crate::constants::{CHECKPOINT_HOOK_REPLY_ID_OFFSET, MAX_CHECKPOINT_HOOK_FAILURES};
+    use crate::entrypoints::{checkpoint_hook_reply, query_hooks, register_hook};
+    use crate::helper::checkpoint_hook_msgs;
+    use crate::interface::HookEvent;
+    use crate::msg::CheckpointHookMsg;
+
+    let mut deps = mock_dependencies();
+    CONFIG.save(deps.as_mut().storage, &test_config())?;
+    for (addr, events) in [
+        ("confirmed_hook", vec![HookEvent::CheckpointConfirmed]),
+        (
+            "rotation_hook",
+            vec![HookEvent::SigsetRotated, HookEvent::CheckpointConfirmed],
+        ),
+    ] {
+        register_hook(
+            deps.as_mut().storage,
+            &deps.api,
+            mock_info("owner", &[]),
+            addr.to_string(),
+            events,
+        )?;
+    }
+    // registering again replaces the events of the existing hook
+    register_hook(
+        deps.as_mut().storage,
+        &deps.api,
+        mock_info("owner", &[]),
+        "rotation_hook".to_string(),
+        vec![HookEvent::SigsetRotated],
+    )?;
+    assert_eq!(query_hooks(deps.as_ref().storage, None, None)?.len(), 2);
+
+    let msg = CheckpointHookMsg::CheckpointConfirmed {
+        index: 1,
+        btc_height: 100,
+    };
+    let sub_msgs = checkpoint_hook_msgs(deps.as_ref().storage, msg)?;
+    assert_eq!(sub_msgs.len(), 1);
+    assert_eq!(sub_msgs[0].id, CHECKPOINT_HOOK_REPLY_ID_OFFSET);
+
+    // a successful delivery resets the failure count
+    for _ in 0..MAX_CHECKPOINT_HOOK_FAILURES - 1 {
+        checkpoint_hook_reply(
+            deps.as_mut().storage,
+            0,
+            SubMsgResult::Err("hook failed".to_string()),
+        )?;
+    }
+    checkpoint_hook_reply(
+        deps.as_mut().storage,
+        0,
+        SubMsgResult::Ok(SubMsgResponse {
+            events: vec![],
+            data: None,
+        }),
+    )?;
+    let hooks = query_hooks(deps.as_ref().storage, None, None)?;
+    assert_eq!(hooks.len(), 2);
+    assert_eq!(hooks[0].1.failures, 0);
+
+    // consecutively failing hooks are dropped without failing the reply
+    for _ in 0..MAX_CHECKPOINT_HOOK_FAILURES {
+        checkpoint_hook_reply(
+            deps.as_mut().storage,
+            0,
+            SubMsgResult::Err("hook failed".to_string()),
+        )?;
+    }
+    let hooks = query_hooks(deps.as_ref().storage, None, None)?;
+    assert_eq!(hooks.len(), 1);
+    assert_eq!(hooks[0].1.addr, Addr::unchecked("rotation_hook"));
+    Ok(())
+}
+
+#[test]
+fn test_check_recovery_script() {
+    use crate::interface::ScriptType;
+    use bitcoin::{PubkeyHash, ScriptHash, WPubkeyHash};
+
+    let p2wpkh = ScriptBuf::new_p2wpkh(&WPubkeyHash::from_slice(&[1; 20]).unwrap());
+    let p2pkh = ScriptBuf::new_p2pkh(&PubkeyHash::from_slice(&[1; 20]).unwrap());
+    let p2sh = ScriptBuf::new_p2sh(&ScriptHash::from_slice(&[1; 20]).unwrap());
+
+    let mut config = BitcoinConfig::default();
+    assert!(config.check_recovery_script(&p2wpkh).is_ok());
+    assert!(config.check_recovery_script(&p2pkh).is_ok());
+    // p2sh hides a script which may not be standard to spend
+    assert!(config.check_recovery_script(&p2sh).is_err());
+    assert!(config
+        .check_recovery_script(&ScriptBuf::from(vec![0x51; 40]))
+        .is_err());
+
+    config.recovery_script_types = vec![ScriptType::P2wpkh, ScriptType::P2tr];
+    assert!(config.check_recovery_script(&p2wpkh).is_ok());
+    assert!(config.check_recovery_script(&p2pkh).is_err());
+}
+
+#[test]
+fn test_ibc_refunds() -> ContractResult<()> {
+    use crate::entrypoints::{claim_ibc_refund, ibc_lifecycle_complete, query_ibc_refunds};
+    use crate::interface::IbcTransfer;
+    use crate::msg::IbcLifecycleComplete;
+    use crate::state::IBC_TRANSFERS;
+
+    let mut deps = mock_dependencies();
+    for sequence in [1, 2] {
+        IBC_TRANSFERS.save(
+            deps.as_mut().storage,
+            ("channel-0", sequence),
+            &IbcTransfer {
+                channel: "channel-0".to_string(),
+                sequence,
+                refund_address: "refund".to_string(),
+                coin: Coin::new(1000, "nbtc"),
+                sent_at: 0,
+            },
+        )?;
+    }
+
+    ibc_lifecycle_complete(
+        deps.as_mut().storage,
+        IbcLifecycleComplete::IbcAck {
+            channel: "channel-0".to_string(),
+            sequence: 1,
+            ack: String::new(),
+            success: true,
+        },
+    )?;
+    assert!(query_ibc_refunds(deps.as_ref().storage, "refund".to_string())?.is_empty());
+
+    ibc_lifecycle_complete(
+        deps.as_mut().storage,
+        IbcLifecycleComplete::IbcTimeout {
+            channel: "channel-0".to_string(),
+            sequence: 2,
+        },
+    )?;
+    assert_eq!(
+        query_ibc_refunds(deps.as_ref().storage, "refund".to_string())?,
+        vec![Coin::new(1000, "nbtc")]
+    );
+    assert!(IBC_TRANSFERS.is_empty(deps.as_ref().storage));
+
+    let res = claim_ibc_refund(deps.as_mut().storage, mock_info("refund", &[]))?;
+    assert_eq!(res.messages.len(), 1);
+    assert!(query_ibc_refunds(deps.as_ref().storage, "refund".to_string())?.is_empty());
+    assert!(claim_ibc_refund(deps.as_mut().storage, mock_info("refund", &[])).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_ibc_memo_transfer_replies() -> ContractResult<()> {
+    use crate::entrypoints::ibc_memo_transfer_reply;
+    use crate::state::{IBC_MEMO_REFUNDS, IBC_TRANSFERS};
+    use cosmwasm_std::{BankMsg, CosmosMsg};
+    use ibc_proto::ibc::applications::transfer::v1::MsgTransferResponse;
+    use prost::Message;
+
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+    for (id, refund_address) in [(0, "refund0"), (1, "refund1")] {
+        IBC_MEMO_REFUNDS.save(
+            deps.as_mut().storage,
+            id,
+            &(
+                refund_address.to_string(),
+                Coin::new(1000, "nbtc"),
+                "channel-0".to_string(),
+            ),
+        )?;
+    }
+
+    // replies are matched by their id, not by the order they arrive in
+    let res = ibc_memo_transfer_reply(
+        &env,
+        deps.as_mut().storage,
+        1,
+        SubMsgResult::Err("no route".to_string()),
+    )?;
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: "refund1".to_string(),
+            amount: vec![Coin::new(1000, "nbtc")],
+        })
+    );
+
+    // a sent packet is tracked by its sequence until its ack or timeout
+    ibc_memo_transfer_reply(
+        &env,
+        deps.as_mut().storage,
+        0,
+        SubMsgResult::Ok(SubMsgResponse {
+            events: vec![],
+            data: Some(MsgTransferResponse { sequence: 7 }.encode_to_vec().into()),
+        }),
+    )?;
+    let transfer = IBC_TRANSFERS.load(deps.as_ref().storage, ("channel-0", 7))?;
+    assert_eq!(transfer.refund_address, "refund0");
+    assert!(IBC_MEMO_REFUNDS.is_empty(deps.as_ref().storage));
+
+    // a reply can only be settled once
+    assert!(ibc_memo_transfer_reply(
+        &env,
+        deps.as_mut().storage,
+        0,
+        SubMsgResult::Err("no route".to_string()),
+    )
+    .is_err());
+    Ok(())
+}
+
+#[test]
+fn test_ibc_memo_packet_memo() {
+    use crate::interface::IbcMemoDest;
+
+    let dest = |memo: &str| IbcMemoDest {
+        channel: "channel-0".to_string(),
+        receiver: "receiver".to_string(),
+        memo: memo.to_string(),
+        refund_address: "refund".to_string(),
+        timeout_timestamp: 0,
+    };
+    assert_eq!(
+        dest("").packet_memo("bridge"),
+        r#"{"ibc_callback":"bridge"}"#
+    );
+    assert_eq!(
+        dest(r#"{"forward":{"receiver":"noble1"}}"#).packet_memo("bridge"),
+        r#"{"ibc_callback":"bridge","forward":{"receiver":"noble1"}}"#
+    );
+    // memos which can't take a callback are sent untouched
+    assert_eq!(dest("hello").packet_memo("bridge"), "hello");
+    let named = r#"{"ibc_callback":"other"}"#;
+    assert_eq!(dest(named).packet_memo("bridge"), named);
+}
+
+#[test]
+fn test_tombstone_signatory() -> ContractResult<()> {
+    use crate::entrypoints::tombstone_signatory;
+    use crate::state::{FORCED_CHECKPOINT_PUSH, SIG_KEYS, TOMBSTONED_XPUBS};
+    use common_bitcoin::adapter::WrappedBinary;
+
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+    CONFIG.save(deps.as_mut().storage, &test_config())?;
+
+    let secp = Secp256k1::new();
+    let mut btc = Bitcoin::default();
+    let mut xpubs = vec![];
+    for i in 0..3u8 {
+        let addr = format!("validator{}", i);
+        let cons_key = [i; 32];
+        VALIDATORS.save(deps.as_mut().storage, &cons_key, &(100, addr.clone()))?;
+        SIGNERS.save(deps.as_mut().storage, &addr, &cons_key)?;
+        let xpriv = bip32::Xpriv::new_master(bitcoin::Network::Bitcoin, &[i])?;
+        let xpub = Xpub::new(bip32::Xpub::from_priv(&secp, &xpriv));
+        btc.signatory_keys
+            .insert(deps.as_mut().storage, cons_key, xpub)?;
+        xpubs.push(xpub);
+    }
+
+    let tombstone = |store: &mut dyn Storage, sender: &str| {
+        tombstone_signatory(
+            store,
+            &env,
+            mock_info(sender, &[]),
+            WrappedBinary(xpubs[0]),
+            "leaked".to_string(),
+        )
+    };
+    assert!(tombstone(deps.as_mut().storage, "stranger").is_err());
+
+    // 2/3 of the voting power is not enough
+    tombstone(deps.as_mut().storage, "validator1")?;
+    tombstone(deps.as_mut().storage, "validator2")?;
+    assert!(SIG_KEYS.has(deps.as_ref().storage, &[0u8; 32]));
+
+    tombstone(deps.as_mut().storage, "validator0")?;
+    assert!(!SIG_KEYS.has(deps.as_ref().storage, &[0u8; 32]));
+    assert!(FORCED_CHECKPOINT_PUSH.load(deps.as_ref().storage)?);
+    let tombstones = TOMBSTONED_XPUBS
+        .range(deps.as_ref().storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    assert_eq!(tombstones.len(), 1);
+    assert_eq!(tombstones[0].1.consensus_key, Some([0u8; 32]));
+
+    // the key can't be tombstoned twice nor registered again
+    assert!(tombstone(deps.as_mut().storage, "owner").is_err());
+    assert!(btc
+        .signatory_keys
+        .insert(deps.as_mut().storage, [3u8; 32], xpubs[0])
+        .is_err());
+    Ok(())
+}
+
+#[test]
+fn test_check_invariants() -> ContractResult<()> {
+    use crate::checkpoint::Checkpoint;
+    use crate::entrypoints::{query_bridge_paused, set_bridge_paused, verify_invariants};
+    use crate::signatory::{Signatory, SignatorySet};
+    use crate::state::{get_full_btc_denom, CHECKPOINTS, PENDING_TRANSFERS};
+
+    let mut deps = mock_dependencies();
+    let config = test_config();
+    let bitcoin_config = BitcoinConfig::default();
+    CONFIG.save(deps.as_mut().storage, &config)?;
+    BITCOIN_CONFIG.save(deps.as_mut().storage, &bitcoin_config)?;
+    CHECKPOINT_CONFIG.save(deps.as_mut().storage, &CheckpointConfig::default())?;
+    FEE_POOL.save(deps.as_mut().storage, &0)?;
+    BUILDING_INDEX.save(deps.as_mut().storage, &0)?;
+
+    let secp = Secp256k1::new();
+    let xpriv = bip32::Xpriv::new_master(bitcoin::Network::Bitcoin, &[0])?;
+    let xpub = Xpub::new(bip32::Xpub::from_priv(&secp, &xpriv));
+    let mut sigset = SignatorySet::default();
+    sigset.signatories.push(Signatory {
+        voting_power: 100,
+        pubkey: xpub.derive_pubkey(0)?.into(),
+    });
+    sigset.present_vp = 100;
+    sigset.possible_vp = 100;
+    let mut checkpoint = Checkpoint::new(sigset.clone())?;
+    checkpoint.batches[BatchType::Checkpoint][0]
+        .input
+        .push(Input::new(
+            OutPoint::default(),
+            &sigset,
+            &[0u8],
+            100_000,
+            CheckpointConfig::default().sigset_threshold,
+        )?);
+    CHECKPOINTS.push_back(deps.as_mut().storage, &checkpoint)?;
+
+    let denom = get_full_btc_denom(
+        deps.as_ref().storage,
+        config.token_factory_contract.as_str(),
+    )?;
+    let backing = 100_000 * bitcoin_config.units_per_sat as u128;
+    let supply =
+        |amount: u128| MockQuerier::<Empty>::new(&[("holder", &[Coin::new(amount, &denom)])]);
+    let btc = Bitcoin::default();
+
+    // fully backed supply
+    let querier = supply(backing);
+    let res = verify_invariants(deps.as_mut().storage, &QuerierWrapper::new(&querier))?;
+    assert!(res.events.is_empty());
+    assert!(!query_bridge_paused(deps.as_ref().storage)?);
+
+    // the fee pool must be backed by the surplus of the reserve
+    FEE_POOL.save(deps.as_mut().storage, &1)?;
+    let violations = btc.check_invariants(deps.as_ref().storage, &QuerierWrapper::new(&querier))?;
+    assert_eq!(violations.len(), 1);
+    assert!(violations[0].starts_with("Fee pool 1 exceeds reserve surplus"));
+    let querier = supply(backing - 1);
+    assert!(btc
+        .check_invariants(deps.as_ref().storage, &QuerierWrapper::new(&querier))?
+        .is_empty());
+    FEE_POOL.save(deps.as_mut().storage, &0)?;
+
+    // pending mints count towards the supply
+    PENDING_TRANSFERS.push_back(
+        deps.as_mut().storage,
+        &PendingTransfer::deposit(
+            Dest::Address(Addr::unchecked("receiver")),
+            Coin::new(2, &denom),
+            &OutPoint::default(),
+        ),
+    )?;
+    let res = verify_invariants(deps.as_mut().storage, &QuerierWrapper::new(&querier))?;
+    assert_eq!(res.events.len(), 1);
+    assert_eq!(res.events[0].ty, "invariant_violation");
+    // a violation pauses the bridge
+    assert!(query_bridge_paused(deps.as_ref().storage)?);
+    assert!(btc.ensure_not_paused(deps.as_ref().storage).is_err());
+
+    // checkpoint transactions can't pay out more than they spend
+    let mut checkpoint = CHECKPOINTS.front(deps.as_ref().storage)?.unwrap();
+    checkpoint.batches[BatchType::Checkpoint][0]
+        .output
+        .push(Adapter::new(bitcoin::TxOut {
+            value: bitcoin::Amount::from_sat(200_000),
+            script_pubkey: ScriptBuf::new(),
+        }));
+    CHECKPOINTS.set(deps.as_mut().storage, 0, &checkpoint)?;
+    let violations = btc.check_invariants(deps.as_ref().storage, &QuerierWrapper::new(&querier))?;
+    assert!(violations
+        .iter()
+        .any(|violation| violation.contains("exceeding inputs of 100000 sats")));
+
+    // only the owner can unpause the bridge
+    assert!(matches!(
+        set_bridge_paused(deps.as_mut().storage, mock_info("anyone", &[]), false),
+        Err(ContractError::Unauthorized {})
+    ));
+    set_bridge_paused(deps.as_mut().storage, mock_info("owner", &[]), false)?;
+    assert!(!query_bridge_paused(deps.as_ref().storage)?);
+    set_bridge_paused(deps.as_mut().storage, mock_info("owner", &[]), true)?;
+    assert!(query_bridge_paused(deps.as_ref().storage)?);
+
+    Ok(())
+}
+
+#[test]
+fn test_register_reserve_utxo() -> ContractResult<()> {
+    use crate::checkpoint::Checkpoint;
+    use crate::entrypoints::register_reserve_utxo;
+    use crate::signatory::{Signatory, SignatorySet};
+    use crate::state::{CHECKPOINTS, RESERVE_UTXOS};
+
+    let mut deps = mock_dependencies();
+    CONFIG.save(deps.as_mut().storage, &test_config())?;
+    BITCOIN_CONFIG.save(deps.as_mut().storage, &BitcoinConfig::default())?;
+    CHECKPOINT_CONFIG.save(deps.as_mut().storage, &CheckpointConfig::default())?;
+    BUILDING_INDEX.save(deps.as_mut().storage, &0)?;
+
+    let secp = Secp256k1::new();
+    let xpriv = bip32::Xpriv::new_master(bitcoin::Network::Bitcoin, &[0])?;
+    let xpub = Xpub::new(bip32::Xpub::from_priv(&secp, &xpriv));
+    let mut sigset = SignatorySet::default();
+    sigset.signatories.push(Signatory {
+        voting_power: 100,
+        pubkey: xpub.derive_pubkey(0)?.into(),
+    });
+    sigset.present_vp = 100;
+    sigset.possible_vp = 100;
+    CHECKPOINTS.push_back(deps.as_mut().storage, &Checkpoint::new(sigset.clone())?)?;
+
+    let reserve_script =
+        sigset.output_script(&[0u8], CheckpointConfig::default().sigset_threshold)?;
+    let btc_tx = bitcoin::Transaction {
+        version: bitcoin::transaction::Version::ONE,
+        lock_time: bitcoin::absolute::LockTime::ZERO,
+        input: vec![],
+        output: vec![
+            bitcoin::TxOut {
+                value: bitcoin::Amount::from_sat(50_000),
+                script_pubkey: reserve_script,
+            },
+            bitcoin::TxOut {
+                value: bitcoin::Amount::from_sat(60_000),
+                script_pubkey: ScriptBuf::new(),
+            },
+        ],
+    };
+    let txid = btc_tx.txid();
+    let btc_proof = Adapter::new(PartialMerkleTree::from_txids(&[txid], &[true]));
+
+    let mut mock_query = MockQuerier::<Empty>::new(&[]);
+    mock_query.update_wasm(handle_wasm_query(100));
+    let querier = QuerierWrapper::new(&mock_query);
+    let register = |store: &mut dyn Storage, sender: &str, btc_height: u32, vout: u32| {
+        register_reserve_utxo(
+            store,
+            &querier,
+            mock_info(sender, &[]),
+            Adapter::new(btc_tx.clone()),
+            btc_height,
+            btc_proof.clone(),
+            vout,
+            0,
+        )
+    };
+
+    assert!(matches!(
+        register(deps.as_mut().storage, "anyone", 90, 0),
+        Err(ContractError::Unauthorized {})
+    ));
+    // the transaction must be confirmed
+    assert!(matches!(
+        register(deps.as_mut().storage, "owner", 100, 0),
+        Err(ContractError::InsufficientConfirmations { .. })
+    ));
+    // and the output must pay to the reserve script
+    assert!(register(deps.as_mut().storage, "owner", 90, 1).is_err());
+    assert!(register(deps.as_mut().storage, "owner", 90, 2).is_err());
+
+    register(deps.as_mut().storage, "owner", 90, 0)?;
+    let reserve_utxo = RESERVE_UTXOS.load(deps.as_ref().storage, (&txid.to_string(), 0))?;
+    assert_eq!(reserve_utxo.amount, 50_000);
+    assert_eq!(reserve_utxo.checkpoint_index, 0);
+    let building = CHECKPOINTS.front(deps.as_ref().storage)?.unwrap();
+    let input = building.batches[BatchType::Checkpoint][0]
+        .input
+        .last()
+        .unwrap()
+        .clone();
+    assert_eq!(*input.prevout, OutPoint::new(txid, 0));
+    assert_eq!(input.amount, 50_000);
+
+    assert!(register(deps.as_mut().storage, "owner", 90, 0).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_deposit_address_status() -> ContractResult<()> {
+    use crate::checkpoint::Checkpoint;
+    use crate::entrypoints::query_deposit_address_status;
+    use crate::export::export_section;
+    use crate::msg::{DepositAddressStatus, StateSection};
+    use crate::signatory::{Signatory, SignatorySet};
+    use crate::state::{CHECKPOINTS, DEST_SIGSET_INDEXES};
+
+    let mut deps = mock_dependencies();
+    let store = deps.as_mut().storage;
+    BITCOIN_CONFIG.save(store, &BitcoinConfig::default())?;
+    CHECKPOINT_CONFIG.save(store, &CheckpointConfig::default())?;
+    let secp = Secp256k1::new();
+    let xpriv = bip32::Xpriv::new_master(bitcoin::Network::Testnet, &[0]).unwrap();
+    let xpub = Xpub::new(bip32::Xpub::from_priv(&secp, &xpriv));
+    for index in 0..2 {
+        let mut sigset = SignatorySet::default();
+        sigset.index = index;
+        sigset.create_time = index as u64 * 1_000;
+        sigset.signatories.push(Signatory {
+            pubkey: xpub.derive_pubkey(index)?.into(),
+            voting_power: 100,
+        });
+        sigset.possible_vp = 100;
+        sigset.present_vp = 100;
+        CHECKPOINTS.push_back(store, &Checkpoint::new(sigset)?)?;
+    }
+    BUILDING_INDEX.save(store, &1)?;
+
+    let dest = Dest::Address(Addr::unchecked("depositor"));
+    let dest_bytes = dest.commitment_bytes()?;
+    DEST_SIGSET_INDEXES.save(store, &dest_bytes, &0)?;
+    let querier = QuerierWrapper::new(&deps.querier);
+    let status = |time: u64, sigset_index: u32| {
+        query_deposit_address_status(
+            deps.as_ref().storage,
+            &querier,
+            set_time(time),
+            dest.clone(),
+            sigset_index,
+        )
+    };
+
+    let active = status(2_000, 1)?;
+    assert_eq!(active.status, DepositAddressStatus::Active);
+    assert_eq!(active.replacement_address, None);
+    assert_eq!(active.latest_deposit_sigset_index, Some(0));
+
+    let stale = status(2_000, 0)?;
+    assert_eq!(stale.status, DepositAddressStatus::Stale);
+    let threshold = CheckpointConfig::default().sigset_threshold;
+    let commitment = dest.versioned_commitment_bytes(DEST_COMMITMENT_VERSION)?;
+    let active_script = CHECKPOINTS
+        .back(deps.as_ref().storage)?
+        .unwrap()
+        .sigset
+        .output_script(&commitment, threshold)?;
+    assert_eq!(
+        stale.replacement_address,
+        Some(script_to_address(&active_script, NETWORK)?.to_string())
+    );
+
+    let max_deposit_age = BitcoinConfig::default().max_deposit_age;
+    assert_eq!(
+        status(max_deposit_age + 1, 0)?.status,
+        DepositAddressStatus::Expired
+    );
+    assert!(status(2_000, 2).is_err());
+
+    // the index is part of the exported account state
+    let accounts = export_section(deps.as_ref().storage, StateSection::Accounts, None, 100)?;
+    assert!(accounts
+        .iter()
+        .any(|entry| entry.value == to_json_binary(&0u32).unwrap()
+            && entry.key.ends_with(&dest_bytes)));
+
+    Ok(())
+}
+
+#[test]
+fn test_end_block_budget_default() -> ContractResult<()> {
+    // configs saved before the budget existed get the same default as new
+    // ones
+    let mut legacy = serde_json::to_value(BitcoinConfig::default()).unwrap();
+    legacy
+        .as_object_mut()
+        .unwrap()
+        .remove("max_end_block_items")
+        .unwrap();
+    let config: BitcoinConfig = serde_json::from_value(legacy).unwrap();
+    assert_eq!(config, BitcoinConfig::default());
+    assert_eq!(
+        config.max_end_block_items,
+        crate::constants::MAX_END_BLOCK_ITEMS
+    );
+    Ok(())
+}
+
+#[test]
+fn test_sponsor_withdrawal_fee() -> ContractResult<()> {
+    use crate::checkpoint::Checkpoint;
+    use crate::entrypoints::{prune_withdrawals, sponsor_withdrawal_fee, withdraw_to_bitcoin};
+    use crate::signatory::SignatorySet;
+    use crate::state::{get_full_btc_denom, CHECKPOINTS, FEE_EXEMPTIONS, WITHDRAWALS};
+    use cosmwasm_std::{BankMsg, CosmosMsg};
+
+    let mut deps = mock_dependencies();
+    let querier = QuerierWrapper::new(&MockQuerier::<Empty>::new(&[]));
+    let env = mock_env();
+    let config = test_config();
+    let bitcoin_config = BitcoinConfig {
+        min_withdrawal_checkpoints: 0,
+        ..BitcoinConfig::default()
+    };
+    CONFIG.save(deps.as_mut().storage, &config)?;
+    BITCOIN_CONFIG.save(deps.as_mut().storage, &bitcoin_config)?;
+    CHECKPOINT_CONFIG.save(deps.as_mut().storage, &CheckpointConfig::default())?;
+    FEE_POOL.save(deps.as_mut().storage, &0)?;
+    BUILDING_INDEX.save(deps.as_mut().storage, &0)?;
+    CHECKPOINTS.push_back(
+        deps.as_mut().storage,
+        &Checkpoint::new(SignatorySet::default())?,
+    )?;
+    FEE_EXEMPTIONS.save(deps.as_mut().storage, "sender", &())?;
+
+    let denom = get_full_btc_denom(
+        deps.as_ref().storage,
+        config.token_factory_contract.as_str(),
+    )?;
+    let script = ScriptBuf::new_p2wpkh(&bitcoin::WPubkeyHash::from_slice(&[1; 20]).unwrap());
+    let btc_address = script_to_address(&script, NETWORK)?.to_string();
+    // both withdrawals pay to the same merged output
+    for _ in 0..2 {
+        withdraw_to_bitcoin(
+            &mut deps.storage,
+            &querier,
+            &deps.api,
+            mock_info("sender", &[Coin::new(100_000_000_000, &denom)]),
+            env.clone(),
+            btc_address.clone(),
+            None,
+            None,
+            Default::default(),
+        )?;
+    }
+    let first = WITHDRAWALS.load(deps.as_ref().storage, 1)?;
+    let second = WITHDRAWALS.load(deps.as_ref().storage, 2)?;
+    assert_eq!(first.output_index, second.output_index);
+    assert_eq!((first.fee_index, second.fee_index), (0, 1));
+
+    let sponsor = |deps: DepsMut, funds: &[Coin], id: u64| {
+        sponsor_withdrawal_fee(deps.storage, mock_env(), mock_info("sponsor", funds), id)
+    };
+    assert!(sponsor(deps.as_mut(), &[Coin::new(1_000_000, "uatom")], 2).is_err());
+
+    let units_per_sat = bitcoin_config.units_per_sat;
+    let paid = second.fee + 5 * units_per_sat;
+    let sponsored_sats = second.fee / units_per_sat;
+    let res = sponsor(deps.as_mut(), &[Coin::new(paid as u128, &denom)], 2)?;
+    assert_eq!(
+        res.messages[1].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: "sponsor".to_string(),
+            amount: vec![Coin::new(
+                (paid - sponsored_sats * units_per_sat) as u128,
+                &denom
+            )],
+        })
+    );
+    let sponsored = WITHDRAWALS.load(deps.as_ref().storage, 2)?;
+    assert_eq!(sponsored.value, second.value + sponsored_sats);
+    assert_eq!(sponsored.sponsored, sponsored_sats * units_per_sat);
+
+    // only the sponsored withdrawal's fee record and shared output are raised
+    let building = CHECKPOINTS.front(deps.as_ref().storage)?.unwrap();
+    assert_eq!(building.withdrawal_fees[0].value, first.value);
+    assert_eq!(
+        building.withdrawal_fees[1].value,
+        second.value + sponsored_sats
+    );
+    assert_eq!(
+        building.batches[BatchType::Checkpoint][0].output[second.output_index as usize]
+            .value
+            .to_sat(),
+        first.value + second.value + sponsored_sats
+    );
+    // the fee is fully paid back
+    assert!(sponsor(deps.as_mut(), &[Coin::new(paid as u128, &denom)], 2).is_err());
+
+    // once the checkpoint stops building, withdrawals can't be sponsored and
+    // their records are pruned within the budget
+    CHECKPOINTS.push_back(
+        deps.as_mut().storage,
+        &Checkpoint::new(SignatorySet::default())?,
+    )?;
+    BUILDING_INDEX.save(deps.as_mut().storage, &1)?;
+    assert!(sponsor(deps.as_mut(), &[Coin::new(paid as u128, &denom)], 1).is_err());
+    let mut budget = 1;
+    prune_withdrawals(deps.as_mut().storage, &mut budget)?;
+    assert_eq!(budget, 0);
+    assert!(!WITHDRAWALS.has(deps.as_ref().storage, 1));
+    assert!(WITHDRAWALS.has(deps.as_ref().storage, 2));
+    prune_withdrawals(deps.as_mut().storage, &mut 10)?;
+    assert!(WITHDRAWALS.is_empty(deps.as_ref().storage));
+
+    Ok(())
+}
+
+#[test]
+fn test_header_stall_pause() -> ContractResult<()> {
+    use crate::entrypoints::{check_header_staleness, query_bridge_paused};
+
+    let mut deps = mock_dependencies();
+    CONFIG.save(deps.as_mut().storage, &test_config())?;
+    BITCOIN_CONFIG.save(
+        deps.as_mut().storage,
+        &BitcoinConfig {
+            max_header_staleness: 600,
+            ..BitcoinConfig::default()
+        },
+    )?;
+    let check = |storage: &mut dyn Storage, height: u32, time: u64| {
+        let mut querier = MockQuerier::<Empty>::new(&[]);
+        querier.update_wasm(handle_wasm_query(height));
+        check_header_staleness(&set_time(time), storage, &QuerierWrapper::new(&querier))
+    };
+
+    assert!(check(deps.as_mut().storage, 100, 1000)?.is_none());
+    assert!(check(deps.as_mut().storage, 100, 1600)?.is_none());
+
+    // the bridge pauses once the light client is stalled for too long
+    let event = check(deps.as_mut().storage, 100, 1601)?.unwrap();
+    assert_eq!(event.ty, "header_stall");
+    assert_eq!(event.attributes[0].value, "paused");
+    assert!(check(deps.as_mut().storage, 100, 2000)?.is_none());
+    assert!(query_bridge_paused(deps.as_ref().storage)?);
+    assert!(Bitcoin::default()
+        .ensure_not_paused(deps.as_ref().storage)
+        .is_err());
+
+    // and resumes as soon as a new header is relayed
+    let event = check(deps.as_mut().storage, 101, 2100)?.unwrap();
+    assert_eq!(event.attributes[0].value, "resumed");
+    assert!(!query_bridge_paused(deps.as_ref().storage)?);
+    // the staleness is measured from when the new header was first seen
+    assert!(check(deps.as_mut().storage, 101, 2700)?.is_none());
+    assert_eq!(
+        check(deps.as_mut().storage, 101, 2701)?.unwrap().attributes[0].value,
+        "paused"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_withdrawal_amount_rounding() -> ContractResult<()> {
+    use crate::checkpoint::Checkpoint;
+    use crate::entrypoints::withdraw_to_bitcoin;
+    use crate::signatory::SignatorySet;
+    use crate::state::{get_full_btc_denom, CHECKPOINTS, FEE_EXEMPTIONS, WITHDRAWALS};
+    use cosmwasm_std::{BankMsg, CosmosMsg};
+
+    let mut deps = mock_dependencies();
+    let querier = QuerierWrapper::new(&MockQuerier::<Empty>::new(&[]));
+    let config = test_config();
+    let bitcoin_config = BitcoinConfig {
+        min_withdrawal_checkpoints: 0,
+        ..BitcoinConfig::default()
+    };
+    CONFIG.save(deps.as_mut().storage, &config)?;
+    BITCOIN_CONFIG.save(deps.as_mut().storage, &bitcoin_config)?;
+    CHECKPOINT_CONFIG.save(deps.as_mut().storage, &CheckpointConfig::default())?;
+    FEE_POOL.save(deps.as_mut().storage, &0)?;
+    BUILDING_INDEX.save(deps.as_mut().storage, &0)?;
+    CHECKPOINTS.push_back(
+        deps.as_mut().storage,
+        &Checkpoint::new(SignatorySet::default())?,
+    )?;
+    FEE_EXEMPTIONS.save(deps.as_mut().storage, "sender", &())?;
+
+    let denom = get_full_btc_denom(
+        deps.as_ref().storage,
+        config.token_factory_contract.as_str(),
+    )?;
+    let btc_address = script_to_address(
+        &ScriptBuf::new_p2wpkh(&bitcoin::WPubkeyHash::from_slice(&[1; 20]).unwrap()),
+        NETWORK,
+    )?
+    .to_string();
+    let units_per_sat = bitcoin_config.units_per_sat;
+    let sent = 100_000_000_000 + units_per_sat / 3;
+    let withdraw = |deps: DepsMut, amount: Option<u128>| {
+        withdraw_to_bitcoin(
+            deps.storage,
+            &querier,
+            deps.api,
+            mock_info("sender", &[Coin::new(sent as u128, &denom)]),
+            mock_env(),
+            btc_address.clone(),
+            None,
+            amount.map(Uint128::new),
+            Default::default(),
+        )
+    };
+
+    // without an amount the sub-satoshi remainder is paid as miner fee
+    let res = withdraw(deps.as_mut(), None)?;
+    assert_eq!(res.messages.len(), 1);
+    let whole = WITHDRAWALS.load(deps.as_ref().storage, 1)?;
+    assert_eq!(whole.value * units_per_sat + whole.fee, sent);
+    assert_eq!(FEE_POOL.load(deps.as_ref().storage)?, whole.fee as i64);
+
+    // a given amount is withdrawn as is and the rest of the sent nBTC refunded
+    let res = withdraw(deps.as_mut(), Some(sent as u128 - 1000))?;
+    assert_eq!(
+        res.messages[1].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: "sender".to_string(),
+            amount: vec![Coin::new(1000, &denom)],
+        })
+    );
+    let partial = WITHDRAWALS.load(deps.as_ref().storage, 2)?;
+    let minimum_fee = whole.fee - units_per_sat / 3;
+    assert_eq!(partial.fee, minimum_fee);
+    assert_eq!(partial.value, (sent - 1000 - minimum_fee) / units_per_sat);
+
+    assert!(withdraw(deps.as_mut(), Some(sent as u128 + 1)).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_simulate_deposit() -> ContractResult<()> {
+    use crate::checkpoint::Checkpoint;
+    use crate::entrypoints::query_simulate_deposit;
+    use crate::signatory::{Signatory, SignatorySet};
+    use crate::state::{Ratio, CHECKPOINTS, TOKEN_FEE_RATIO};
+
+    let mut deps = mock_dependencies();
+    let store = deps.as_mut().storage;
+    let bitcoin_config = BitcoinConfig {
+        sigset_lag_surcharge_bps: 100,
+        latest_sigset_discount_bps: 5_000,
+        ..BitcoinConfig::default()
+    };
+    CONFIG.save(store, &test_config())?;
+    BITCOIN_CONFIG.save(store, &bitcoin_config)?;
+    CHECKPOINT_CONFIG.save(store, &CheckpointConfig::default())?;
+    TOKEN_FEE_RATIO.save(
+        store,
+        &Ratio {
+            nominator: 1,
+            denominator: 10,
+        },
+    )?;
+    let secp = Secp256k1::new();
+    let xpriv = bip32::Xpriv::new_master(bitcoin::Network::Bitcoin, &[0])?;
+    let xpub = Xpub::new(bip32::Xpub::from_priv(&secp, &xpriv));
+    for index in 0..2 {
+        let mut sigset = SignatorySet::default();
+        sigset.index = index;
+        sigset.signatories.push(Signatory {
+            voting_power: 100,
+            pubkey: xpub.derive_pubkey(index)?.into(),
+        });
+        sigset.present_vp = 100;
+        sigset.possible_vp = 100;
+        CHECKPOINTS.push_back(store, &Checkpoint::new(sigset)?)?;
+    }
+    BUILDING_INDEX.save(store, &1)?;
+
+    let querier = QuerierWrapper::new(&deps.querier);
+    let dest = Dest::Address(Addr::unchecked("depositor"));
+    let simulate = |time: u64, sigset_index: u32| {
+        query_simulate_deposit(
+            &set_time(time),
+            deps.as_ref().storage,
+            &querier,
+            &deps.api,
+            100_000,
+            sigset_index,
+            dest.clone(),
+        )
+    };
+
+    let lagging = simulate(0, 0)?;
+    let minted = Uint128::from(100_000 * bitcoin_config.units_per_sat);
+    assert_eq!(lagging.minted, minted);
+    assert_eq!(
+        lagging.deposit_fee,
+        minted.multiply_ratio(100u64, 10_000u64)
+    );
+    assert!(!lagging.miner_fee.is_zero());
+    let after_miner_fees = minted - lagging.miner_fee - lagging.deposit_fee;
+    assert_eq!(
+        lagging.token_fee,
+        after_miner_fees.multiply_ratio(1u64, 10u64)
+    );
+    assert!(lagging.relayer_fee.is_zero());
+    assert_eq!(lagging.net_credit, after_miner_fees - lagging.token_fee);
+
+    // deposits to the building sigset pay no surcharge and half the miner fee
+    let latest = simulate(0, 1)?;
+    assert!(latest.deposit_fee.is_zero());
+    assert_eq!(
+        latest.miner_fee,
+        lagging.miner_fee - lagging.miner_fee.multiply_ratio(1u64, 2u64)
+    );
+    assert!(latest.net_credit > lagging.net_credit);
+
+    // deposits the relay would reject can't be simulated
+    assert!(simulate(bitcoin_config.max_deposit_age + 1, 0).is_err());
+    assert!(simulate(0, 2).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_min_relayable_utxo() -> ContractResult<()> {
+    use crate::entrypoints::allow_small_utxos;
+
+    let mut deps = mock_dependencies();
+    CONFIG.save(deps.as_mut().storage, &test_config())?;
+    BITCOIN_CONFIG.save(
+        deps.as_mut().storage,
+        &BitcoinConfig {
+            min_relayable_utxo_sats: 1_000,
+            ..BitcoinConfig::default()
+        },
+    )?;
+    CHECKPOINT_CONFIG.save(deps.as_mut().storage, &CheckpointConfig::default())?;
+    let btc = Bitcoin::default();
+    let check = |store: &dyn Storage, time: u64, value: u64, input_vsize: u64| {
+        btc.check_relayable_utxo(&set_time(time), store, value, input_vsize)
+    };
+
+    assert!(check(deps.as_ref().storage, 0, 999, 1).is_err());
+    check(deps.as_ref().storage, 0, 1_000, 1)?;
+    // inputs costing more than the minimum to spend raise it
+    let max_fee_rate = CheckpointConfig::default().max_fee_rate;
+    let spend_cost = 100 * max_fee_rate;
+    assert!(spend_cost > 1_000);
+    assert!(check(deps.as_ref().storage, 0, spend_cost - 1, 100).is_err());
+    check(deps.as_ref().storage, 0, spend_cost, 100)?;
+
+    // the owner can accept them for a while
+    allow_small_utxos(deps.as_mut().storage, mock_info("owner", &[]), Some(100))?;
+    check(deps.as_ref().storage, 100, 1, 100)?;
+    assert!(check(deps.as_ref().storage, 101, 1, 100).is_err());
+    allow_small_utxos(deps.as_mut().storage, mock_info("owner", &[]), None)?;
+    assert!(check(deps.as_ref().storage, 0, 1, 100).is_err());
+
+    // 0 disables the check
+    BITCOIN_CONFIG.save(deps.as_mut().storage, &BitcoinConfig::default())?;
+    check(deps.as_ref().storage, 0, 1, 100)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_checkpoint_inputs() -> ContractResult<()> {
+    use crate::checkpoint::Checkpoint;
+    use crate::entrypoints::query_checkpoint_inputs;
+    use crate::interface::{InputSource, MintRecord, ReserveUtxo};
+    use crate::signatory::{Signatory, SignatorySet};
+    use crate::state::{CHECKPOINTS, MINT_RECORDS, RESERVE_UTXOS};
+
+    let mut deps = mock_dependencies();
+    let store = deps.as_mut().storage;
+    CHECKPOINT_CONFIG.save(store, &CheckpointConfig::default())?;
+    let threshold = CheckpointConfig::default().sigset_threshold;
+    let secp = Secp256k1::new();
+    let xpriv = bip32::Xpriv::new_master(bitcoin::Network::Bitcoin, &[0])?;
+    let xpub = Xpub::new(bip32::Xpub::from_priv(&secp, &xpriv));
+    let mut sigset = SignatorySet::default();
+    sigset.signatories.push(Signatory {
+        voting_power: 100,
+        pubkey: xpub.derive_pubkey(0)?.into(),
+    });
+    sigset.present_vp = 100;
+    sigset.possible_vp = 100;
+
+    let dest = Dest::Address(Addr::unchecked("depositor"));
+    let commitment = dest.versioned_commitment_bytes(DEST_COMMITMENT_VERSION)?;
+    let outpoint = |byte: u8| OutPoint::new(Txid::from_byte_array([byte; 32]), 0);
+    let mut checkpoint = Checkpoint::new(sigset.clone())?;
+    let inputs = &mut checkpoint.batches[BatchType::Checkpoint][0].input;
+    inputs.push(Input::new(outpoint(1), &sigset, &[0u8], 1_000, threshold)?);
+    inputs.push(Input::new(outpoint(2), &sigset, &[0u8], 2_000, threshold)?);
+    for byte in 3..6 {
+        inputs.push(Input::new(
+            outpoint(byte),
+            &sigset,
+            &commitment,
+            3_000,
+            threshold,
+        )?);
+    }
+    CHECKPOINTS.push_back(store, &Checkpoint::new(sigset.clone())?)?;
+    CHECKPOINTS.push_back(store, &checkpoint)?;
+    BUILDING_INDEX.save(store, &1)?;
+
+    let txid = |byte: u8| outpoint(byte).txid.to_string();
+    RESERVE_UTXOS.save(
+        store,
+        (&txid(2), 0),
+        &ReserveUtxo {
+            txid: txid(2),
+            vout: 0,
+            amount: 2_000,
+            sigset_index: 0,
+            checkpoint_index: 1,
+        },
+    )?;
+    for (byte, checkpoint_index) in [(3, 1), (4, 0)] {
+        MINT_RECORDS.save(
+            store,
+            (&txid(byte), 0),
+            &MintRecord {
+                dest: dest.clone(),
+                amount: Uint128::new(3_000),
+                checkpoint_index,
+                btc_height: 10,
+                block_hash: None,
+            },
+        )?;
+    }
+
+    let inputs = query_checkpoint_inputs(deps.as_ref().storage, 1)?;
+    let sources: Vec<_> = inputs.iter().map(|input| input.source.clone()).collect();
+    assert_eq!(
+        sources,
+        vec![
+            InputSource::Reserve,
+            InputSource::RegisteredReserve,
+            InputSource::Deposit {
+                dest: dest.clone(),
+                btc_height: 10,
+            },
+            InputSource::ExcessRollover {
+                dest: dest.clone(),
+                btc_height: 10,
+                from_checkpoint: 0,
+            },
+            InputSource::Unknown,
+        ]
+    );
+    assert_eq!(inputs[2].txid, txid(3));
+    assert_eq!(inputs[2].amount, 3_000);
+    assert_eq!(inputs[2].commitment.to_vec(), commitment);
+    assert!(query_checkpoint_inputs(deps.as_ref().storage, 0)?.is_empty());
+    assert!(query_checkpoint_inputs(deps.as_ref().storage, 2).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_deposit_age_in_btc_blocks() -> ContractResult<()> {
+    use crate::constants::BTC_BLOCK_INTERVAL;
+    use crate::interface::DepositAgeUnit;
+    use crate::recovery::recovery_lock_time;
+    use crate::signatory::SignatorySet;
+    use crate::state::SIGSET_BTC_HEIGHTS;
+
+    let mut deps = mock_dependencies();
+    CONFIG.save(deps.as_mut().storage, &test_config())?;
+    let seconds = BitcoinConfig::default();
+    BITCOIN_CONFIG.save(deps.as_mut().storage, &seconds)?;
+    let btc = Bitcoin::default();
+    let mut sigset = SignatorySet::default();
+    sigset.create_time = 1_000;
+    let expired = |store: &dyn Storage, time: u64, btc_height: u32| {
+        btc.is_sigset_expired(&set_time(time), store, &sigset, btc_height)
+    };
+
+    // by default the age is bridge time since the sigset was created
+    let deadline = 1_000 + seconds.max_deposit_age;
+    assert!(!expired(deps.as_ref().storage, deadline, u32::MAX)?);
+    assert!(expired(deps.as_ref().storage, deadline + 1, 0)?);
+    assert_eq!(
+        recovery_lock_time(100, &seconds),
+        100 + (seconds.max_deposit_age / BTC_BLOCK_INTERVAL) as u32
+    );
+
+    let blocks = BitcoinConfig {
+        deposit_age_unit: DepositAgeUnit::BtcBlocks,
+        max_deposit_age_blocks: 10,
+        ..BitcoinConfig::default()
+    };
+    BITCOIN_CONFIG.save(deps.as_mut().storage, &blocks)?;
+    // sigsets without a recorded height still expire by bridge time
+    assert!(expired(deps.as_ref().storage, deadline + 1, 0)?);
+
+    // a halted chain doesn't expire deposits confirmed in time
+    SIGSET_BTC_HEIGHTS.save(deps.as_mut().storage, 0, &100)?;
+    assert!(!expired(deps.as_ref().storage, deadline * 100, 110)?);
+    assert!(expired(deps.as_ref().storage, 0, 111)?);
+    assert_eq!(recovery_lock_time(100, &blocks), 110);
+
+    // the current age is measured at the light client height
+    let mut querier = MockQuerier::<Empty>::new(&[]);
+    querier.update_wasm(handle_wasm_query(111));
+    let querier = QuerierWrapper::new(&querier);
+    assert!(btc.is_sigset_expired_now(&set_time(0), &querier, deps.as_ref().storage, &sigset)?);
+
+    Ok(())
+}
+
+#[test]
+fn test_prepared_withdrawal() -> ContractResult<()> {
+    use crate::checkpoint::Checkpoint;
+    use crate::entrypoints::{execute_withdrawal, prepare_withdrawal, query_withdrawal_quote};
+    use crate::signatory::SignatorySet;
+    use crate::state::{get_full_btc_denom, CHECKPOINTS, FEE_EXEMPTIONS, WITHDRAWALS};
+
+    let mut deps = mock_dependencies();
+    let querier = QuerierWrapper::new(&MockQuerier::<Empty>::new(&[]));
+    let config = test_config();
+    let bitcoin_config = BitcoinConfig {
+        min_withdrawal_checkpoints: 0,
+        ..BitcoinConfig::default()
+    };
+    CONFIG.save(deps.as_mut().storage, &config)?;
+    BITCOIN_CONFIG.save(deps.as_mut().storage, &bitcoin_config)?;
+    CHECKPOINT_CONFIG.save(deps.as_mut().storage, &CheckpointConfig::default())?;
+    FEE_POOL.save(deps.as_mut().storage, &0)?;
+    BUILDING_INDEX.save(deps.as_mut().storage, &0)?;
+    CHECKPOINTS.push_back(
+        deps.as_mut().storage,
+        &Checkpoint::new(SignatorySet::default())?,
+    )?;
+    FEE_EXEMPTIONS.save(deps.as_mut().storage, "sender", &())?;
+
+    let denom = get_full_btc_denom(
+        deps.as_ref().storage,
+        config.token_factory_contract.as_str(),
+    )?;
+    let btc_address = script_to_address(
+        &ScriptBuf::new_p2wpkh(&bitcoin::WPubkeyHash::from_slice(&[1; 20]).unwrap()),
+        NETWORK,
+    )?
+    .to_string();
+    let amount = Uint128::new(100_000_000_000);
+    let prepare = |deps: DepsMut| {
+        prepare_withdrawal(
+            deps.storage,
+            set_time(0),
+            mock_info("sender", &[]),
+            btc_address.clone(),
+            amount,
+        )
+    };
+    let execute = |deps: DepsMut, sender: &str, sent: u128, time: u64, quote_id: u64| {
+        execute_withdrawal(
+            deps.storage,
+            &querier,
+            deps.api,
+            mock_info(sender, &[Coin::new(sent, &denom)]),
+            set_time(time),
+            quote_id,
+        )
+    };
+
+    prepare(deps.as_mut())?;
+    let quote = query_withdrawal_quote(deps.as_ref().storage, 1)?.unwrap();
+    assert_eq!(quote.amount, amount);
+    assert_eq!(quote.expires_at, bitcoin_config.withdrawal_quote_ttl);
+
+    // the miner fee stays locked while the checkpoint fee rate rises
+    let mut building = CHECKPOINTS.front(deps.as_ref().storage)?.unwrap();
+    building.fee_rate = quote.fee_rate * 2;
+    CHECKPOINTS.set(deps.as_mut().storage, 0, &building)?;
+
+    assert!(matches!(
+        execute(deps.as_mut(), "other", amount.u128(), 0, 1),
+        Err(ContractError::Unauthorized {})
+    ));
+    assert!(execute(deps.as_mut(), "sender", amount.u128() - 1, 0, 1).is_err());
+    execute(deps.as_mut(), "sender", amount.u128(), 0, 1)?;
+    let withdrawal = WITHDRAWALS.load(deps.as_ref().storage, 1)?;
+    assert_eq!(withdrawal.fee, quote.miner_fee);
+    assert_eq!(
+        query_withdrawal_quote(deps.as_ref().storage, 1)?
+            .unwrap()
+            .withdrawal_id,
+        Some(1)
+    );
+    // executing again has no effect
+    assert!(execute(deps.as_mut(), "sender", amount.u128(), 0, 1).is_err());
+    assert!(!WITHDRAWALS.has(deps.as_ref().storage, 2));
+
+    // expired quotes can't be executed
+    prepare(deps.as_mut())?;
+    let expired = bitcoin_config.withdrawal_quote_ttl + 1;
+    assert!(execute(deps.as_mut(), "sender", amount.u128(), expired, 2).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_reconcile_reorged_deposits() -> ContractResult<()> {
+    use crate::checkpoint::{Checkpoint, CheckpointStatus};
+    use crate::entrypoints::{query_bad_debt, query_reorged_deposits, reconcile_reorged_deposits};
+    use crate::interface::MintRecord;
+    use crate::msg::ReorgedDepositId;
+    use crate::signatory::SignatorySet;
+    use crate::state::{get_full_btc_denom, CHECKPOINTS, MINT_RECORDS};
+    use bitcoin::BlockHash;
+    use common_bitcoin::adapter::WrappedBinary;
+    use cosmwasm_std::{CosmosMsg, WasmMsg};
+    use light_client_bitcoin::msg::QueryMsg::BlockHashAtHeight;
+
+    let mut deps = mock_dependencies();
+    let store = deps.as_mut().storage;
+    CONFIG.save(store, &test_config())?;
+    BITCOIN_CONFIG.save(
+        store,
+        &BitcoinConfig {
+            min_confirmations: 3,
+            ..BitcoinConfig::default()
+        },
+    )?;
+    CHECKPOINT_CONFIG.save(store, &CheckpointConfig::default())?;
+    let mut completed = Checkpoint::new(SignatorySet::default())?;
+    completed.status = CheckpointStatus::Complete;
+    CHECKPOINTS.push_back(store, &completed)?;
+    CHECKPOINTS.push_back(store, &Checkpoint::new(SignatorySet::default())?)?;
+    BUILDING_INDEX.save(store, &1)?;
+
+    let hash = |byte: u8| BlockHash::from_byte_array([byte; 32]);
+    let txid = |byte: u8| Txid::from_byte_array([byte; 32]).to_string();
+    let dest = Dest::Address(Addr::unchecked("depositor"));
+    for (byte, checkpoint_index, block_hash) in [
+        (1, 0, Some(hash(1))),
+        (2, 0, Some(hash(2))),
+        (3, 0, None),
+        (4, 1, Some(hash(1))),
+    ] {
+        MINT_RECORDS.save(
+            store,
+            (&txid(byte), 0),
+            &MintRecord {
+                dest: dest.clone(),
+                amount: Uint128::new(3_000),
+                checkpoint_index,
+                btc_height: 10,
+                block_hash: block_hash.map(|hash| hash.to_string()),
+            },
+        )?;
+    }
+
+    // the light client has replaced block 1 with block 2 at height 10
+    let denom = get_full_btc_denom(store, "token_factory_contract")?;
+    let querier_at = |tip: u32| {
+        let mut querier = MockQuerier::<Empty>::new(&[("depositor", &[Coin::new(1_000, &denom)])]);
+        querier.update_wasm(move |query: &WasmQuery| -> QuerierResult {
+            let WasmQuery::Smart { msg, .. } = query else {
+                unreachable!()
+            };
+            let response = match from_json(msg).unwrap() {
+                HeaderHeight {} => to_json_binary(&tip),
+                BlockHashAtHeight { height } => {
+                    to_json_binary(&(height <= tip).then(|| WrappedBinary(hash(2))))
+                }
+                _ => {
+                    return SystemResult::Err(SystemError::UnsupportedRequest {
+                        kind: "QueryMsg".to_string(),
+                    })
+                }
+            };
+            SystemResult::Ok(cosmwasm_std::ContractResult::Ok(response.unwrap()))
+        });
+        querier
+    };
+    let reconcile = |store: &mut dyn Storage, tip: u32, sender: &str, byte: u8| {
+        let querier = querier_at(tip);
+        reconcile_reorged_deposits(
+            store,
+            &QuerierWrapper::new(&querier),
+            mock_env(),
+            mock_info(sender, &[]),
+            vec![ReorgedDepositId {
+                txid: txid(byte),
+                vout: 0,
+            }],
+        )
+    };
+
+    assert!(reconcile(store, 13, "depositor", 1).is_err());
+    // the replacing block needs min_confirmations
+    assert!(matches!(
+        reconcile(store, 12, "owner", 1),
+        Err(ContractError::InsufficientConfirmations { have: 2, need: 3 })
+    ));
+    // the deposit's block is still the light client's
+    assert!(reconcile(store, 13, "owner", 2).is_err());
+    // deposits minted without a block hash can not be checked
+    assert!(reconcile(store, 13, "owner", 3).is_err());
+    // nor can deposits not minted yet
+    assert!(reconcile(store, 13, "owner", 4).is_err());
+    assert!(reconcile(store, 13, "owner", 5).is_err());
+    assert!(query_reorged_deposits(store, None, None)?.is_empty());
+    assert!(query_bad_debt(store)?.is_zero());
+
+    // the held nBTC is burned, the rest is bad debt
+    let res = reconcile(store, 13, "owner", 1)?;
+    assert_eq!(res.messages.len(), 1);
+    let CosmosMsg::Wasm(WasmMsg::Execute { msg, .. }) = &res.messages[0].msg else {
+        panic!("expected a tokenfactory burn");
+    };
+    match from_json(msg)? {
+        tokenfactory::msg::ExecuteMsg::BurnTokens {
+            amount,
+            burn_from_address,
+            ..
+        } => {
+            assert_eq!(amount, Uint128::new(1_000));
+            assert_eq!(burn_from_address, "depositor");
+        }
+        _ => panic!("expected a tokenfactory burn"),
+    }
+    assert_eq!(query_bad_debt(store)?, Uint128::new(2_000));
+    assert!(MINT_RECORDS.may_load(store, (&txid(1), 0))?.is_none());
+    let reorged = query_reorged_deposits(store, None, None)?;
+    assert_eq!(reorged.len(), 1);
+    assert_eq!(reorged[0].txid, txid(1));
+    assert_eq!(reorged[0].burned, Uint128::new(1_000));
+    assert_eq!(reorged[0].deficit, Uint128::new(2_000));
+
+    // a deposit is only reconciled once
+    assert!(reconcile(store, 13, "owner", 1).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_check_relay_deposit() -> ContractResult<()> {
+    use crate::checkpoint::Checkpoint;
+    use crate::entrypoints::query_check_relay_deposit;
+    use crate::interface::BridgeMigration;
+    use crate::msg::RelayDepositRejection;
+    use crate::signatory::{Signatory, SignatorySet};
+    use crate::state::{BRIDGE_MIGRATION, BRIDGE_PAUSED, CHECKPOINTS};
+
+    let mut deps = mock_dependencies();
+    let store = deps.as_mut().storage;
+    CONFIG.save(store, &test_config())?;
+    BITCOIN_CONFIG.save(
+        store,
+        &BitcoinConfig {
+            min_confirmations: 3,
+            ..BitcoinConfig::default()
+        },
+    )?;
+    CHECKPOINT_CONFIG.save(store, &CheckpointConfig::default())?;
+    let secp = Secp256k1::new();
+    let xpriv = bip32::Xpriv::new_master(bitcoin::Network::Testnet, &[0]).unwrap();
+    let xpub = Xpub::new(bip32::Xpub::from_priv(&secp, &xpriv));
+    for index in 0..2 {
+        let mut sigset = SignatorySet::default();
+        sigset.index = index;
+        sigset.create_time = index as u64 * 1_000;
+        sigset.signatories.push(Signatory {
+            pubkey: xpub.derive_pubkey(index)?.into(),
+            voting_power: 100,
+        });
+        sigset.possible_vp = 100;
+        sigset.present_vp = 100;
+        CHECKPOINTS.push_back(store, &Checkpoint::new(sigset)?)?;
+    }
+    BUILDING_INDEX.save(store, &1)?;
+
+    let mut querier = MockQuerier::<Empty>::new(&[]);
+    querier.update_wasm(|query: &WasmQuery| -> QuerierResult {
+        let WasmQuery::Smart { msg, .. } = query else {
+            unreachable!()
+        };
+        match from_json(msg).unwrap() {
+            HeaderHeight {} => SystemResult::Ok(cosmwasm_std::ContractResult::Ok(
+                to_json_binary(&20u32).unwrap(),
+            )),
+            _ => SystemResult::Err(SystemError::UnsupportedRequest {
+                kind: "QueryMsg".to_string(),
+            }),
+        }
+    });
+    let querier = QuerierWrapper::new(&querier);
+    let dest = Dest::Address(Addr::unchecked("depositor"));
+    let txid = Txid::from_byte_array([1; 32]);
+    let script_at = |store: &dyn Storage, sigset_index: u32, version: u8| -> ContractResult<_> {
+        let commitment = dest.versioned_commitment_bytes(version)?;
+        let threshold = CheckpointConfig::default().sigset_threshold;
+        let script = CHECKPOINTS
+            .get(store, sigset_index)?
+            .unwrap()
+            .sigset
+            .output_script(&commitment, threshold)?;
+        Ok(Binary::from(script.to_bytes()))
+    };
+    let check = |store: &dyn Storage,
+                 time: u64,
+                 sigset_index: u32,
+                 btc_height: u32,
+                 script_pubkey: Option<Binary>| {
+        query_check_relay_deposit(
+            &set_time(time),
+            store,
+            &querier,
+            txid.to_string(),
+            0,
+            sigset_index,
+            dest.clone(),
+            btc_height,
+            script_pubkey,
+        )
+    };
+    let expected_script = script_at(store, 1, DEST_COMMITMENT_VERSION)?;
+
+    let accepted = check(store, 2_000, 1, 10, Some(expected_script.clone()))?;
+    assert_eq!(accepted.rejection, None);
+    assert!(!accepted.expired);
+    assert_eq!(accepted.script_pubkey, Some(expected_script.clone()));
+    // addresses committing to the legacy destination encoding still match
+    let legacy_script = script_at(store, 1, LEGACY_DEST_COMMITMENT_VERSION)?;
+    assert_eq!(
+        check(store, 2_000, 1, 10, Some(legacy_script))?.rejection,
+        None
+    );
+    // deposits to an expired sigset are accepted but not minted
+    let max_deposit_age = BitcoinConfig::default().max_deposit_age;
+    let expired = check(store, max_deposit_age + 1, 0, 10, None)?;
+    assert_eq!(expired.rejection, None);
+    assert!(expired.expired);
+
+    let rejection = |store: &dyn Storage, sigset_index, btc_height, script_pubkey| {
+        check(store, 2_000, sigset_index, btc_height, script_pubkey).map(|res| res.rejection)
+    };
+    assert_eq!(
+        rejection(store, 1, 21, None)?,
+        Some(RelayDepositRejection::FutureHeight {
+            sidechain_btc_height: 20
+        })
+    );
+    assert_eq!(
+        rejection(store, 1, 18, None)?,
+        Some(RelayDepositRejection::InsufficientConfirmations { have: 2, need: 3 })
+    );
+    assert_eq!(
+        rejection(store, 2, 10, None)?,
+        Some(RelayDepositRejection::UnknownSigset)
+    );
+    let other_script = script_at(store, 0, DEST_COMMITMENT_VERSION)?;
+    let mismatch = check(store, 2_000, 1, 10, Some(other_script))?;
+    assert_eq!(
+        mismatch.rejection,
+        Some(RelayDepositRejection::ScriptMismatch)
+    );
+    assert_eq!(mismatch.script_pubkey, Some(expected_script));
+
+    let store = deps.as_mut().storage;
+    let mut checkpoint = CHECKPOINTS.get(store, 0)?.unwrap();
+    checkpoint.deposits_enabled = false;
+    CHECKPOINTS.set(store, 0, &checkpoint)?;
+    assert_eq!(
+        rejection(store, 0, 10, None)?,
+        Some(RelayDepositRejection::DepositsDisabled)
+    );
+    Bitcoin::default()
+        .processed_outpoints
+        .insert(store, OutPoint::new(txid, 0), u64::MAX)?;
+    assert_eq!(
+        rejection(store, 1, 10, None)?,
+        Some(RelayDepositRejection::AlreadyProcessed)
+    );
+
+    BRIDGE_MIGRATION.save(
+        store,
+        &BridgeMigration {
+            successor: Addr::unchecked("successor"),
+            reserve_script: Binary::from(vec![0; 34]),
+            successor_sigset_index: 0,
+            started_at: 0,
+            checkpoint_index: None,
+            transferred_until: None,
+            state_transferred: false,
+        },
+    )?;
+    assert_eq!(
+        rejection(store, 1, 10, None)?,
+        Some(RelayDepositRejection::DepositsFrozen)
+    );
+    // a paused bridge rejects deposits before anything else
+    BRIDGE_PAUSED.save(store, &true)?;
+    assert!(matches!(
+        rejection(store, 1, 21, None)?,
+        Some(RelayDepositRejection::BridgePaused { .. })
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_pending_deposits() -> ContractResult<()> {
+    use crate::constants::PENDING_DEPOSIT_TTL;
+    use crate::entrypoints::{
+        prune_pending_deposits, query_pending_deposits, report_pending_deposit,
+    };
+    use crate::state::{
+        remove_pending_deposit, PENDING_DEPOSITS, PENDING_DEPOSIT_EXPIRATIONS, RELAYERS,
+    };
+
+    let mut deps = mock_dependencies();
+    let store = deps.as_mut().storage;
+    RELAYERS.save(store, &Addr::unchecked("relayer"), &())?;
+    let txid = |byte: u8| Txid::from_byte_array([byte; 32]).to_string();
+    let alice = Dest::Address(Addr::unchecked("alice"));
+    let bob = Dest::Address(Addr::unchecked("bob"));
+    let report = |store: &mut dyn Storage, time, sender: &str, txid: String, vout, dest: &Dest| {
+        report_pending_deposit(
+            store,
+            set_time(time),
+            mock_info(sender, &[]),
+            txid,
+            vout,
+            dest.clone(),
+            10_000,
+        )
+    };
+    let pending =
+        |store: &dyn Storage, time, dest: &Dest, start_after, limit| -> ContractResult<Vec<_>> {
+            Ok(
+                query_pending_deposits(store, set_time(time), dest.clone(), start_after, limit)?
+                    .into_iter()
+                    .map(|deposit| (deposit.txid, deposit.vout))
+                    .collect(),
+            )
+        };
+
+    assert!(matches!(
+        report(store, 0, "depositor", txid(1), 0, &alice),
+        Err(ContractError::Unauthorized {})
+    ));
+    // the txid is stored as relayed, whatever its case
+    report(store, 0, "relayer", txid(1).to_uppercase(), 0, &alice)?;
+    report(store, 100, "relayer", txid(1), 1, &alice)?;
+    report(store, 200, "relayer", txid(2), 0, &bob)?;
+    assert_eq!(
+        pending(store, 200, &alice, None, None)?,
+        vec![(txid(1), 0), (txid(1), 1)]
+    );
+    assert_eq!(pending(store, 200, &bob, None, None)?, vec![(txid(2), 0)]);
+
+    // a new report of the same output replaces the previous one
+    report(store, 300, "relayer", txid(1), 0, &bob)?;
+    assert_eq!(pending(store, 300, &alice, None, None)?, vec![(txid(1), 1)]);
+    assert_eq!(
+        pending(store, 300, &bob, None, Some(1))?,
+        vec![(txid(1), 0)]
+    );
+    assert_eq!(
+        pending(store, 300, &bob, Some((txid(1), 0)), None)?,
+        vec![(txid(2), 0)]
+    );
+    assert_eq!(
+        PENDING_DEPOSITS.load(store, (&txid(1), 0))?.expires_at,
+        300 + PENDING_DEPOSIT_TTL
+    );
+
+    // expired deposits are hidden until they are pruned
+    assert!(pending(store, 100 + PENDING_DEPOSIT_TTL, &alice, None, None)?.is_empty());
+    let now = 250 + PENDING_DEPOSIT_TTL;
+    let mut budget = 1;
+    prune_pending_deposits(store, now, &mut budget)?;
+    assert_eq!(budget, 0);
+    assert!(!PENDING_DEPOSITS.has(store, (&txid(1), 1)));
+    assert!(PENDING_DEPOSITS.has(store, (&txid(2), 0)));
+    let mut budget = 10;
+    prune_pending_deposits(store, now, &mut budget)?;
+    assert_eq!(budget, 9);
+    assert_eq!(pending(store, 0, &bob, None, None)?, vec![(txid(1), 0)]);
+    assert_eq!(
+        PENDING_DEPOSIT_EXPIRATIONS
+            .keys(store, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?,
+        vec![(300 + PENDING_DEPOSIT_TTL, txid(1), 0)]
+    );
+
+    // relaying the deposit removes it, and it can not be reported again
+    remove_pending_deposit(store, &txid(1), 0)?;
+    assert!(pending(store, 0, &bob, None, None)?.is_empty());
+    assert!(PENDING_DEPOSIT_EXPIRATIONS
+        .keys(store, None, None, Order::Ascending)
+        .next()
+        .is_none());
+    Bitcoin::default().processed_outpoints.insert(
+        store,
+        OutPoint::new(Txid::from_byte_array([1; 32]), 0),
+        u64::MAX,
+    )?;
+    assert!(matches!(
+        report(store, 400, "relayer", txid(1), 0, &bob),
+        Err(ContractError::OutputAlreadyRelayed { vout: 0, .. })
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_dest_stats() -> ContractResult<()> {
+    use crate::constants::DEST_STATS_WINDOW;
+    use crate::entrypoints::{query_dest_stats, query_top_destinations};
+    use crate::state::{record_dest_deposit, DEST_STATS_BY_TOTAL};
+
+    let mut deps = mock_dependencies();
+    let store = deps.as_mut().storage;
+    let dest = |name: &str| Dest::Address(Addr::unchecked(name));
+    record_dest_deposit(store, &dest("alice"), 1_000, 0)?;
+    record_dest_deposit(store, &dest("bob"), 2_500, 50)?;
+    record_dest_deposit(store, &dest("alice"), 2_000, 100)?;
+    record_dest_deposit(store, &dest("carol"), 500, 200)?;
+
+    let alice = query_dest_stats(store, set_time(200), dest("alice"))?.unwrap();
+    assert_eq!(alice.total_deposited, 3_000);
+    assert_eq!(alice.deposits, 2);
+    assert_eq!(alice.last_deposit_at, 100);
+    assert_eq!(alice.window_started_at, 0);
+    assert_eq!(alice.window_volume, 3_000);
+    assert_eq!(query_dest_stats(store, set_time(200), dest("dave"))?, None);
+
+    let top = |store: &dyn Storage, time, limit| -> ContractResult<Vec<_>> {
+        Ok(query_top_destinations(store, set_time(time), limit)?
+            .into_iter()
+            .map(|stats| (stats.dest, stats.total_deposited))
+            .collect())
+    };
+    assert_eq!(
+        top(store, 200, None)?,
+        vec![
+            (dest("alice"), 3_000),
+            (dest("bob"), 2_500),
+            (dest("carol"), 500)
+        ]
+    );
+    assert_eq!(top(store, 200, Some(1))?, vec![(dest("alice"), 3_000)]);
+
+    // a new deposit moves the destination in the index
+    record_dest_deposit(store, &dest("bob"), 1_000, 300)?;
+    assert_eq!(
+        top(store, 300, None)?,
+        vec![
+            (dest("bob"), 3_500),
+            (dest("alice"), 3_000),
+            (dest("carol"), 500)
+        ]
+    );
+    assert_eq!(
+        DEST_STATS_BY_TOTAL
+            .keys(store, None, None, Order::Ascending)
+            .count(),
+        3
+    );
+
+    // the window volume resets once the window has passed
+    let later = query_dest_stats(store, set_time(DEST_STATS_WINDOW), dest("alice"))?.unwrap();
+    assert_eq!(later.window_volume, 0);
+    assert_eq!(later.total_deposited, 3_000);
+    record_dest_deposit(store, &dest("alice"), 700, DEST_STATS_WINDOW + 10)?;
+    let alice = query_dest_stats(store, set_time(DEST_STATS_WINDOW + 10), dest("alice"))?.unwrap();
+    assert_eq!(alice.window_started_at, DEST_STATS_WINDOW + 10);
+    assert_eq!(alice.window_volume, 700);
+    assert_eq!(alice.total_deposited, 3_700);
+    assert_eq!(alice.deposits, 3);
+
+    Ok(())
+}
+
+#[test]
+fn test_burn() -> ContractResult<()> {
+    use crate::entrypoints::{burn, query_bad_debt, query_burns, query_total_burned};
+    use crate::state::{get_full_btc_denom, BAD_DEBT};
+    use cosmwasm_std::{CosmosMsg, WasmMsg};
+
+    let mut deps = mock_dependencies();
+    let store = deps.as_mut().storage;
+    CONFIG.save(store, &test_config())?;
+    BAD_DEBT.save(store, &Uint128::new(1_500))?;
+    let denom = get_full_btc_denom(store, "token_factory_contract")?;
+    let burn_from = |store: &mut dyn Storage, time, sender: &str, funds: &[Coin]| {
+        burn(store, set_time(time), mock_info(sender, funds))
+    };
+
+    assert!(burn_from(store, 0, "alice", &[]).is_err());
+    assert!(burn_from(store, 0, "alice", &[Coin::new(1_000, "uorai")]).is_err());
+
+    // the burn first repays the bad debt
+    let res = burn_from(store, 10, "alice", &[Coin::new(1_000, &denom)])?;
+    let CosmosMsg::Wasm(WasmMsg::Execute { msg, .. }) = &res.messages[0].msg else {
+        panic!("expected a tokenfactory burn");
+    };
+    match from_json(msg)? {
+        tokenfactory::msg::ExecuteMsg::BurnTokens {
+            amount,
+            burn_from_address,
+            ..
+        } => {
+            assert_eq!(amount, Uint128::new(1_000));
+            assert_eq!(burn_from_address, mock_env().contract.address.to_string());
+        }
+        _ => panic!("expected a tokenfactory burn"),
+    }
+    assert_eq!(query_bad_debt(store)?, Uint128::new(500));
+
+    // once repaid, the rest of the burn only reduces the supply
+    burn_from(store, 20, "bob", &[Coin::new(2_000, &denom)])?;
+    assert!(query_bad_debt(store)?.is_zero());
+    burn_from(store, 30, "alice", &[Coin::new(300, &denom)])?;
+    assert!(query_bad_debt(store)?.is_zero());
+    assert_eq!(query_total_burned(store)?, Uint128::new(3_300));
+
+    let burns = query_burns(store, None, None)?;
+    assert_eq!(
+        burns
+            .iter()
+            .map(|(id, record)| (
+                *id,
+                record.burner.as_str(),
+                record.amount.u128(),
+                record.bad_debt_repaid.u128(),
+                record.burned_at
+            ))
+            .collect::<Vec<_>>(),
+        vec![
+            (1, "alice", 1_000, 1_000, 10),
+            (2, "bob", 2_000, 500, 20),
+            (3, "alice", 300, 0, 30),
+        ]
+    );
+    assert_eq!(query_burns(store, Some(1), Some(1))?, burns[1..2]);
+
+    Ok(())
+}
@@ -1,19 +1,48 @@
-use bitcoin::util::bip32::ExtendedPubKey;
-use cosmwasm_std::{testing::mock_dependencies, Binary, Storage};
+use bitcoin::{
+    bip32,
+    hashes::Hash,
+    secp256k1::{Message, Secp256k1, SecretKey},
+    sighash::{EcdsaSighashType, SighashCache},
+};
+use cosmwasm_std::{
+    testing::{mock_dependencies, mock_info},
+    Addr, Binary, Storage, Uint128,
+};
 
 use crate::{
-    checkpoint::{adjust_fee_rate, BitcoinTx, Checkpoint, CheckpointQueue, CheckpointStatus},
-    constants::DEFAULT_FEE_RATE,
-    interface::{BitcoinConfig, CheckpointConfig},
+    app_data::{
+        app_data_leaves, commit_app_data, merkle_branch, merkle_root, verify_merkle_branch,
+    },
+    checkpoint::{
+        adjust_fee_rate, BatchType, BitcoinTx, Checkpoint, CheckpointQueue, CheckpointStatus,
+        Input, InputSighashType, WithdrawalFee,
+    },
+    constants::{DEFAULT_FEE_RATE, MAX_FEE_RATE_HISTORY, MAX_QUERY_LIMIT},
+    entrypoints::{
+        query_checkpoint_summary, query_fee_rate_history, set_compliance_tagger, set_fee_rate,
+        tag_checkpoint,
+    },
+    helper::bridge_time,
+    interface::{
+        BalanceSnapshot, BitcoinConfig, CheckpointConfig, FeeRateChange, FeeRateChangeCause,
+        PriorityFeeMultipliers, WithdrawalPriority,
+    },
     signatory::{Signatory, SignatoryKeys, SignatorySet},
+    snapshot::{hash_liabilities_tree, liabilities_branch, liability_leaf},
     state::{
-        BITCOIN_CONFIG, BUILDING_INDEX, CHECKPOINTS, CHECKPOINT_CONFIG, CONFIRMED_INDEX, FEE_POOL,
-        FIRST_UNHANDLED_CONFIRMED_INDEX, FOUNDATION_KEYS, SIGNERS, VALIDATORS,
+        record_fee_rate_change, BITCOIN_CONFIG, BUILDING_INDEX, CHECKPOINTS, CHECKPOINT_CONFIG,
+        CONFIG, CONFIRMED_INDEX, FAST_WITHDRAWAL_CHECKPOINT, FEE_POOL, FEE_RATE_HISTORY,
+        FIRST_UNHANDLED_CONFIRMED_INDEX, FOUNDATION_KEYS, SIGNERS, SIGSET_ARCHIVE, SNAPSHOT_TREE,
+        VALIDATORS,
     },
-    tests::helper::push_bitcoin_tx_output,
+    tests::helper::{push_bitcoin_tx_output, set_time, test_config},
     threshold_sig::Pubkey,
 };
-use common_bitcoin::{error::ContractResult, xpub::Xpub};
+use common_bitcoin::{
+    adapter::Adapter,
+    error::{ContractError, ContractResult},
+    xpub::Xpub,
+};
 
 fn cons_keys_real_validators() -> Vec<[u8; 32]> {
     vec![
@@ -43,15 +72,15 @@ fn cons_keys_real_validators() -> Vec<[u8; 32]> {
 
 fn xpub_real_validators() -> Vec<Xpub> {
     let mut xpubs = vec![];
-    let decode_base64 = ExtendedPubKey::decode(&Binary::from_base64("BIiyHgAAAAAAAAAAAJwuXJlnKyOcQ/hBOlDMZ/lo3XYZ0acAAsFXSXO00X44AwGI2HzHhD8JFKX0md9zGNRq0H6q0kxBU2qKTjA5zcYN").unwrap().to_vec()).unwrap();
+    let decode_base64 = bip32::Xpub::decode(&Binary::from_base64("BIiyHgAAAAAAAAAAAJwuXJlnKyOcQ/hBOlDMZ/lo3XYZ0acAAsFXSXO00X44AwGI2HzHhD8JFKX0md9zGNRq0H6q0kxBU2qKTjA5zcYN").unwrap().to_vec()).unwrap();
     let xpub: Xpub = Xpub::new(decode_base64);
     xpubs.push(xpub);
 
-    let decode_base64 = ExtendedPubKey::decode(&Binary::from_base64("BIiyHgAAAAAAAAAAAJf1C4vBY96sVBQo0nIrImUWq0MuNzFEknM7rqUzL2UgA645Rw7OhhV5Y2LGs72m127rxtzkPLVgG7Au2/ynrBEM").unwrap().to_vec()).unwrap();
+    let decode_base64 = bip32::Xpub::decode(&Binary::from_base64("BIiyHgAAAAAAAAAAAJf1C4vBY96sVBQo0nIrImUWq0MuNzFEknM7rqUzL2UgA645Rw7OhhV5Y2LGs72m127rxtzkPLVgG7Au2/ynrBEM").unwrap().to_vec()).unwrap();
     let xpub: Xpub = Xpub::new(decode_base64);
     xpubs.push(xpub);
 
-    let decode_base64 = ExtendedPubKey::decode(&Binary::from_base64("BIiyHgAAAAAAAAAAAILSFhI3O5Z/I9/d2Gcj390ZbrUMOxMQBMrQxZOcL9B8A3gEq8AXH3ve8fBPSHd4UL7QnqdHew0BaShnRx7ygjVO").unwrap().to_vec()).unwrap();
+    let decode_base64 = bip32::Xpub::decode(&Binary::from_base64("BIiyHgAAAAAAAAAAAILSFhI3O5Z/I9/d2Gcj390ZbrUMOxMQBMrQxZOcL9B8A3gEq8AXH3ve8fBPSHd4UL7QnqdHew0BaShnRx7ygjVO").unwrap().to_vec()).unwrap();
     let xpub: Xpub = Xpub::new(decode_base64);
     xpubs.push(xpub);
     xpubs
@@ -227,7 +256,7 @@ fn deduct_fee() {
     bitcoin_tx.deduct_fee(100).unwrap();
 
     assert_eq!(bitcoin_tx.output.len(), 1);
-    assert_eq!(bitcoin_tx.output.first().unwrap().value, 9900);
+    assert_eq!(bitcoin_tx.output.first().unwrap().value.to_sat(), 9900);
 }
 
 #[test]
@@ -240,7 +269,7 @@ fn deduct_fee_multi_pass() {
     bitcoin_tx.deduct_fee(30).unwrap();
 
     assert_eq!(bitcoin_tx.output.len(), 1);
-    assert_eq!(bitcoin_tx.output.first().unwrap().value, 472);
+    assert_eq!(bitcoin_tx.output.first().unwrap().value.to_sat(), 472);
 }
 
 #[test]
@@ -253,6 +282,162 @@ fn deduct_fee_multi_pass_empty_result() {
     bitcoin_tx.deduct_fee(200).unwrap();
 }
 
+#[test]
+fn populate_sig_messages_of_all_inputs() -> ContractResult<()> {
+    let threshold = CheckpointConfig::default().sigset_threshold;
+    let mut bitcoin_tx = BitcoinTx::default();
+    for (vout, sigset) in [sigset(0), sigset(1), sigset(0)].iter().enumerate() {
+        bitcoin_tx.input.push(Input::new(
+            bitcoin::OutPoint::new(bitcoin::Txid::all_zeros(), vout as u32),
+            sigset,
+            &[vout as u8],
+            10_000 * (vout as u64 + 1),
+            threshold,
+        )?);
+    }
+    bitcoin_tx.input[2].sighash_type = InputSighashType::AllPlusAnyoneCanPay;
+    push_bitcoin_tx_output(&mut bitcoin_tx, 50_000);
+
+    // sharing the sighash midstate gives the sighashes of each input alone
+    let mut one_by_one = bitcoin_tx.clone();
+    for i in 0..one_by_one.input.len() {
+        one_by_one.populate_input_sig_message(i)?;
+    }
+    bitcoin_tx.populate_sig_messages()?;
+    for (input, expected) in bitcoin_tx.input.iter().zip(&one_by_one.input) {
+        assert_eq!(input.signatures.message(), expected.signatures.message());
+    }
+    assert_ne!(
+        bitcoin_tx.input[0].signatures.message(),
+        bitcoin_tx.input[2].signatures.message()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn derived_pubkeys_cache() -> ContractResult<()> {
+    let secp = bitcoin::secp256k1::Secp256k1::new();
+    let xpriv = bip32::Xpriv::new_master(bitcoin::Network::Bitcoin, &[0])?;
+    let xpub = Xpub::new(bip32::Xpub::from_priv(&secp, &xpriv));
+
+    let mut pubkeys = xpub.derived_pubkeys();
+    for sigset_index in [3, 0, 3, 7, 0] {
+        assert_eq!(
+            pubkeys.get(sigset_index)?,
+            xpub.derive_pubkey(sigset_index)?
+        );
+    }
+    assert_ne!(pubkeys.get(0)?, pubkeys.get(3)?);
+
+    Ok(())
+}
+
+#[test]
+fn take_withdrawal_fees() {
+    let mut bitcoin_tx = BitcoinTx::default();
+    push_bitcoin_tx_output(&mut bitcoin_tx, 10000);
+    push_bitcoin_tx_output(&mut bitcoin_tx, 20000);
+
+    let mut cp = Checkpoint::default();
+    for (value, fee_rate) in [(10000, 40), (20000, 40), (20000, 50)] {
+        cp.withdrawal_fees.push(WithdrawalFee {
+            script_pubkey: Adapter::new(bitcoin::ScriptBuf::new()),
+            value,
+            fee_rate,
+            est_vsize: 9,
+            fee: 9 * fee_rate,
+        });
+    }
+
+    let taken = cp.take_withdrawal_fees(&bitcoin_tx.output[1..]);
+    assert_eq!(taken.len(), 1);
+    assert_eq!(taken[0].value, 20000);
+    assert_eq!(taken[0].fee_rate, 40);
+    assert_eq!(cp.withdrawal_fees.len(), 2);
+    assert_eq!(cp.withdrawal_fees[1].fee_rate, 50);
+}
+
+#[test]
+fn take_withdrawal_fees_of_merged_output() {
+    let mut bitcoin_tx = BitcoinTx::default();
+    // two withdrawals to the same script merged into one output
+    push_bitcoin_tx_output(&mut bitcoin_tx, 30000);
+
+    let mut cp = Checkpoint::default();
+    for (value, fee_rate) in [(10000, 40), (20000, 50), (5000, 60)] {
+        cp.withdrawal_fees.push(WithdrawalFee {
+            script_pubkey: Adapter::new(bitcoin::ScriptBuf::new()),
+            value,
+            fee_rate,
+            est_vsize: 9,
+            fee: 9 * fee_rate,
+        });
+    }
+
+    let taken = cp.take_withdrawal_fees(&bitcoin_tx.output);
+    assert_eq!(taken.len(), 2);
+    assert_eq!(
+        taken.iter().map(|snapshot| snapshot.value).sum::<u64>(),
+        30000
+    );
+    assert_eq!(cp.withdrawal_fees.len(), 1);
+    assert_eq!(cp.withdrawal_fees[0].fee_rate, 60);
+}
+
+#[test]
+fn settle_fees() {
+    let mut bitcoin_tx = BitcoinTx::default();
+    push_bitcoin_tx_output(&mut bitcoin_tx, 10000);
+    push_bitcoin_tx_output(&mut bitcoin_tx, 20000);
+    push_bitcoin_tx_output(&mut bitcoin_tx, 5000);
+    let units_per_sat = 1_000;
+
+    let mut cp = Checkpoint {
+        fee_rate: 30,
+        ..Checkpoint::default()
+    };
+    for (value, fee_rate) in [(10000, 40), (20000, 50), (5000, 20)] {
+        cp.withdrawal_fees.push(WithdrawalFee {
+            script_pubkey: Adapter::new(bitcoin::ScriptBuf::new()),
+            value,
+            fee_rate,
+            est_vsize: 9,
+            fee: 9 * fee_rate * units_per_sat,
+        });
+        cp.fees_collected += 9 * fee_rate;
+    }
+
+    // the second withdrawal did not fit and takes its fee along
+    let (moved, moved_fees) = cp.settle_fees(600, &bitcoin_tx.output[1..2], units_per_sat);
+    assert_eq!(moved.len(), 1);
+    assert_eq!(moved[0].value, 20000);
+    assert_eq!(moved_fees, 9 * 50);
+    assert_eq!(cp.fees_collected, 9 * 40 + 9 * 20);
+    assert_eq!(cp.fee_pool_top_up, 600 - 9 * 60);
+    // the first quote exceeds its final cost, the last one falls short
+    assert_eq!(cp.withdrawal_fee_surplus, 9 * (40 - 30));
+}
+
+#[test]
+fn reached_capacity() {
+    let mut cp = Checkpoint::new(SignatorySet::default()).unwrap();
+    let mut config = CheckpointConfig {
+        max_outputs: 4,
+        ..CheckpointConfig::default()
+    };
+    for _ in 0..3 {
+        push_bitcoin_tx_output(&mut cp.batches[BatchType::Checkpoint][0], 10000);
+    }
+    assert!(!cp.reached_capacity(&config));
+
+    config.capacity_advance_ratio = Some((3, 4));
+    assert!(cp.reached_capacity(&config));
+
+    config.capacity_advance_ratio = Some((1, 1));
+    assert!(!cp.reached_capacity(&config));
+}
+
 //TODO: More fee deduction tests
 
 fn create_queue_with_status(
@@ -271,8 +456,13 @@ fn create_queue_with_status(
             deposits_enabled: true,
             sigset: SignatorySet::default(),
             fees_collected: 0,
+            fee_pool_top_up: 0,
+            withdrawal_fee_surplus: 0,
+            withdrawal_fees: vec![],
             pending: vec![],
             batches: vec![],
+            forced_tx: None,
+            sig_scheme: Default::default(),
         };
 
         CHECKPOINTS.push_back(store, &cp).unwrap();
@@ -301,6 +491,25 @@ fn completed_with_signing() {
     assert_eq!(cp[0].status, CheckpointStatus::Complete);
 }
 
+#[test]
+fn estimated_withdrawal_eta() {
+    let mut deps = mock_dependencies();
+    CHECKPOINT_CONFIG
+        .save(deps.as_mut().storage, &CheckpointConfig::default())
+        .unwrap();
+    let queue = create_queue_with_status(deps.as_mut().storage, 10, true).unwrap();
+    // all checkpoints were created at 0, so the cadence falls back to the
+    // minimum checkpoint interval (300s), plus 2 blocks of inclusion
+    let eta = queue
+        .estimated_withdrawal_eta(deps.as_ref().storage, 100)
+        .unwrap();
+    assert_eq!(eta, 200 + 2 * 600);
+    let eta = queue
+        .estimated_withdrawal_eta(deps.as_ref().storage, 1000)
+        .unwrap();
+    assert_eq!(eta, 2 * 600);
+}
+
 #[test]
 fn completed_without_signing() {
     let mut deps = mock_dependencies();
@@ -381,6 +590,33 @@ fn num_unconfirmed() {
     assert_eq!(queue.num_unconfirmed(deps.as_ref().storage).unwrap(), 10);
 }
 
+#[test]
+fn unconfirmed_limit() {
+    let mut deps = mock_dependencies();
+    let store = deps.as_mut().storage;
+    let queue = create_queue_with_status(store, 10, false).unwrap();
+    CONFIRMED_INDEX.save(store, &7).unwrap();
+
+    // fee bumping does not override the configured limit
+    let mut config = CheckpointConfig {
+        fee_bump_anyone_can_pay: true,
+        max_unconfirmed_checkpoints: 3,
+        ..Default::default()
+    };
+    CHECKPOINT_CONFIG.save(store, &config).unwrap();
+    assert_eq!(queue.num_unconfirmed(store).unwrap(), 2);
+    assert!(!queue.reached_unconfirmed_limit(store).unwrap());
+
+    config.max_unconfirmed_checkpoints = 2;
+    CHECKPOINT_CONFIG.save(store, &config).unwrap();
+    assert!(queue.reached_unconfirmed_limit(store).unwrap());
+
+    CONFIRMED_INDEX.save(store, &9).unwrap();
+    config.max_unconfirmed_checkpoints = 1;
+    CHECKPOINT_CONFIG.save(store, &config).unwrap();
+    assert!(!queue.reached_unconfirmed_limit(store).unwrap());
+}
+
 #[test]
 fn first_unconfirmed_index() {
     let mut deps = mock_dependencies();
@@ -451,6 +687,166 @@ fn test_adjust_fee_rate() {
     assert_eq!(adjust_fee_rate(300, true, &config), 375);
 }
 
+#[test]
+fn input_sighash_type() {
+    use bitcoin::EcdsaSighashType;
+
+    assert_eq!(InputSighashType::default(), InputSighashType::All);
+    assert_eq!(
+        EcdsaSighashType::from(InputSighashType::All),
+        EcdsaSighashType::All
+    );
+    assert_eq!(
+        EcdsaSighashType::from(InputSighashType::AllPlusAnyoneCanPay),
+        EcdsaSighashType::AllPlusAnyoneCanPay
+    );
+    assert_eq!(EcdsaSighashType::AllPlusAnyoneCanPay.to_u32(), 0x81);
+}
+
+#[test]
+fn sigset_falls_back_to_archive() {
+    let mut deps = mock_dependencies();
+    let store = deps.as_mut().storage;
+    let queue = CheckpointQueue::default();
+    BUILDING_INDEX.save(store, &6).unwrap();
+    for i in 5..=6 {
+        CHECKPOINTS
+            .push_back(store, &Checkpoint::new(sigset(i)).unwrap())
+            .unwrap();
+    }
+    SIGSET_ARCHIVE.save(store, 3, &sigset(3)).unwrap();
+
+    assert_eq!(queue.sigset(store, 6).unwrap(), sigset(6));
+    assert_eq!(queue.sigset(store, 3).unwrap(), sigset(3));
+    assert!(queue.sigset(store, 4).is_err());
+}
+
+#[test]
+fn backfill_sigsets_verifies_checkpoint_tx() -> ContractResult<()> {
+    use crate::{
+        entrypoints::backfill_sigsets, msg::BackfilledSigset, state::record_checkpoint_txid,
+    };
+
+    let mut deps = mock_dependencies();
+    let store = deps.as_mut().storage;
+    CONFIG.save(store, &test_config())?;
+    CHECKPOINT_CONFIG.save(store, &CheckpointConfig::default())?;
+    BUILDING_INDEX.save(store, &6)?;
+    CHECKPOINTS.push_back(store, &Checkpoint::new(sigset(6))?)?;
+
+    let threshold = CheckpointConfig::default().sigset_threshold;
+    let checkpoint_tx = |sigset: &SignatorySet| bitcoin::Transaction {
+        version: bitcoin::transaction::Version::ONE,
+        lock_time: bitcoin::absolute::LockTime::ZERO,
+        input: vec![],
+        output: vec![bitcoin::TxOut {
+            value: bitcoin::Amount::from_sat(100_000),
+            script_pubkey: sigset.output_script(&[0u8], threshold).unwrap(),
+        }],
+    };
+    record_checkpoint_txid(store, 3, &checkpoint_tx(&sigset(3)).txid().to_string())?;
+    let backfill = |store: &mut dyn Storage, sigset: SignatorySet, tx: bitcoin::Transaction| {
+        backfill_sigsets(
+            store,
+            mock_info("owner", &[]),
+            vec![BackfilledSigset {
+                sigset,
+                checkpoint_tx: Adapter::new(tx),
+            }],
+        )
+    };
+
+    // the transaction must be the recorded checkpoint transaction
+    assert!(backfill(store, sigset(3), checkpoint_tx(&sigset(4))).is_err());
+    // and pay its reserve to the backfilled set
+    let mut forged = sigset(4);
+    forged.index = 3;
+    assert!(backfill(store, forged, checkpoint_tx(&sigset(3))).is_err());
+    // checkpoints still in the queue are not backfilled
+    assert!(backfill(store, sigset(6), checkpoint_tx(&sigset(6))).is_err());
+    // nor are checkpoints without a recorded txid
+    assert!(backfill(store, sigset(4), checkpoint_tx(&sigset(4))).is_err());
+
+    backfill(store, sigset(3), checkpoint_tx(&sigset(3)))?;
+    assert_eq!(SIGSET_ARCHIVE.load(store, 3)?, sigset(3));
+    assert!(backfill(store, sigset(3), checkpoint_tx(&sigset(3))).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn force_complete_signing_checkpoint() -> ContractResult<()> {
+    let mut deps = mock_dependencies();
+    let deps_mut = deps.as_mut();
+    let (store, api) = (deps_mut.storage, deps_mut.api);
+    let mut queue = CheckpointQueue::default();
+    BUILDING_INDEX.save(store, &1)?;
+    let mut signing = Checkpoint::new(sigset(0))?;
+    signing.status = CheckpointStatus::Signing;
+    let threshold = CheckpointConfig::default().sigset_threshold;
+    let input = Input::new(
+        bitcoin::OutPoint::new(bitcoin::Txid::all_zeros(), 0),
+        &sigset(0),
+        &[0u8],
+        20_000,
+        threshold,
+    )?;
+    let redeem_script = input.redeem_script.clone();
+    signing.batches[BatchType::Checkpoint][0].input.push(input);
+    push_bitcoin_tx_output(&mut signing.batches[BatchType::Checkpoint][0], 10_000);
+    CHECKPOINTS.push_back(store, &signing)?;
+    CHECKPOINTS.push_back(store, &Checkpoint::new(sigset(1))?)?;
+
+    let unsigned_tx = signing.batches[BatchType::Checkpoint][0].to_bitcoin_tx()?;
+    let sighash = SighashCache::new(&unsigned_tx).p2wsh_signature_hash(
+        0,
+        &redeem_script,
+        bitcoin::Amount::from_sat(20_000),
+        EcdsaSighashType::All,
+    )?;
+    let signed_by = |key: u8| {
+        let secp = Secp256k1::new();
+        let secret = SecretKey::from_slice(&[key; 32]).unwrap();
+        let sig = secp.sign_ecdsa(&Message::from_digest(sighash.to_byte_array()), &secret);
+        let mut element = sig.serialize_der().to_vec();
+        element.push(EcdsaSighashType::All as u8);
+        let mut tx = unsigned_tx.clone();
+        tx.input[0].witness =
+            bitcoin::Witness::from_slice(&[element, vec![0], redeem_script.to_bytes()]);
+        tx
+    };
+
+    // the signatures must be valid for the checkpoint's signatory set
+    assert!(queue
+        .force_complete(api, store, 0, Adapter::new(unsigned_tx.clone()), 100)
+        .is_err());
+    assert!(queue
+        .force_complete(api, store, 0, Adapter::new(signed_by(2)), 100)
+        .is_err());
+    let final_tx = signed_by(1);
+    let mut other_tx = final_tx.clone();
+    other_tx.output[0].value -= bitcoin::Amount::from_sat(1);
+    assert!(queue
+        .force_complete(api, store, 0, Adapter::new(other_tx), 100)
+        .is_err());
+    assert!(queue
+        .force_complete(api, store, 1, Adapter::new(final_tx.clone()), 100)
+        .is_err());
+
+    let txid = queue.force_complete(api, store, 0, Adapter::new(final_tx.clone()), 100)?;
+    assert_eq!(txid, final_tx.txid());
+    let checkpoint = queue.get(store, 0)?;
+    assert_eq!(checkpoint.status, CheckpointStatus::Complete);
+    assert_eq!(checkpoint.signed_at_btc_height, Some(100));
+    assert_eq!(checkpoint.checkpoint_tx()?.into_inner(), final_tx);
+    assert_eq!(queue.txid(store, 0)?, txid);
+    assert!(queue.txid(store, 1).is_err());
+    assert!(queue
+        .force_complete(api, store, 0, Adapter::new(final_tx), 100)
+        .is_err());
+    Ok(())
+}
+
 fn sigset(n: u32) -> SignatorySet {
     let mut sigset = SignatorySet::default();
     sigset.index = n;
@@ -473,3 +869,372 @@ fn sigset(n: u32) -> SignatorySet {
 
     sigset
 }
+
+#[test]
+fn app_data_merkle_branches() -> ContractResult<()> {
+    let internal_commitment = vec![7u8; 32];
+    for count in 1..6u8 {
+        let data: Vec<Binary> = (0..count).map(|i| Binary::from(vec![i; 32])).collect();
+        let leaves = app_data_leaves(&internal_commitment, &data)?;
+        let root = merkle_root(&leaves);
+        for (index, leaf) in leaves.iter().enumerate() {
+            let branch = merkle_branch(&leaves, index);
+            assert!(verify_merkle_branch(*leaf, index, &branch, root));
+            assert!(!verify_merkle_branch([9u8; 32], index, &branch, root));
+        }
+    }
+
+    // without app data the internal commitment is used as is
+    let mut deps = mock_dependencies();
+    assert_eq!(
+        commit_app_data(deps.as_mut().storage, 0, internal_commitment.clone())?,
+        internal_commitment
+    );
+    assert!(app_data_leaves(&internal_commitment, &[Binary::from(vec![0u8; 31])]).is_err());
+    Ok(())
+}
+
+#[test]
+fn commit_data_payment() -> ContractResult<()> {
+    use crate::entrypoints::commit_data;
+    use crate::state::{get_full_btc_denom, APP_DATA};
+    use cosmwasm_std::{testing::mock_env, BankMsg, Coin, CosmosMsg};
+
+    let mut deps = mock_dependencies();
+    let store = deps.as_mut().storage;
+    CONFIG.save(store, &test_config())?;
+    BITCOIN_CONFIG.save(store, &BitcoinConfig::default())?;
+    CHECKPOINT_CONFIG.save(
+        store,
+        &CheckpointConfig {
+            app_data_fee: 100,
+            ..CheckpointConfig::default()
+        },
+    )?;
+    FEE_POOL.save(store, &0)?;
+    BUILDING_INDEX.save(store, &0)?;
+    CHECKPOINTS.push_back(store, &Checkpoint::new(sigset(0))?)?;
+    let units_per_sat = BitcoinConfig::default().units_per_sat;
+    let denom = get_full_btc_denom(store, "token_factory_contract")?;
+    let data = Binary::from(vec![1u8; 32]);
+    let commit = |store: &mut dyn Storage, funds: &[Coin], fee| {
+        commit_data(
+            store,
+            mock_env(),
+            mock_info("timestamper", funds),
+            data.clone(),
+            fee,
+        )
+    };
+
+    // the fee can't overflow, nor can the sent funds
+    assert!(commit(store, &[Coin::new(1_000, &denom)], u64::MAX).is_err());
+    assert!(commit(store, &[Coin::new(u128::MAX, &denom)], 100).is_err());
+    let half = (u64::MAX / 2 + 1) as u128;
+    assert!(commit(
+        store,
+        &[Coin::new(half, &denom), Coin::new(half, &denom)],
+        100
+    )
+    .is_err());
+    // funds in other denoms are not kept
+    let paid = (100 * units_per_sat) as u128;
+    assert!(commit(
+        store,
+        &[Coin::new(paid, &denom), Coin::new(1, "uorai")],
+        100
+    )
+    .is_err());
+    assert!(commit(store, &[Coin::new(paid - 1, &denom)], 100).is_err());
+    assert!(APP_DATA.may_load(store, 0)?.is_none());
+
+    // nBTC beyond the fee is refunded
+    let res = commit(store, &[Coin::new(paid + 5, &denom)], 100)?;
+    assert_eq!(
+        res.messages[1].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: "timestamper".to_string(),
+            amount: vec![Coin::new(5, &denom)],
+        })
+    );
+    assert_eq!(APP_DATA.load(store, 0)?, vec![data.clone()]);
+    assert_eq!(FEE_POOL.load(store)?, paid as i64);
+    assert_eq!(CHECKPOINTS.get(store, 0)?.unwrap().fees_collected, 100);
+    assert!(commit(store, &[Coin::new(paid, &denom)], 100).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn tag_checkpoint_memo() -> ContractResult<()> {
+    let mut deps = mock_dependencies();
+    let store = deps.as_mut().storage;
+    create_queue_with_status(store, 2, false)?;
+    CONFIG.save(store, &test_config())?;
+
+    let err = tag_checkpoint(store, mock_info("auditor", &[]), 2, "batch-1".into()).unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+    set_compliance_tagger(
+        store,
+        mock_info("owner", &[]),
+        Addr::unchecked("auditor"),
+        true,
+    )?;
+    tag_checkpoint(store, mock_info("auditor", &[]), 2, "batch-1".into())?;
+    assert!(tag_checkpoint(store, mock_info("owner", &[]), 3, "batch-2".into()).is_err());
+    assert!(tag_checkpoint(store, mock_info("owner", &[]), 2, "x".repeat(257)).is_err());
+
+    let summary = query_checkpoint_summary(store, 2)?;
+    assert_eq!(summary.status, CheckpointStatus::Building);
+    assert_eq!(summary.txid, None);
+    assert_eq!(summary.memo, Some("batch-1".to_string()));
+    assert_eq!(query_checkpoint_summary(store, 1)?.memo, None);
+    Ok(())
+}
+
+#[test]
+fn fee_pool_tops_up_miner_fee() -> ContractResult<()> {
+    let mut deps = mock_dependencies();
+    let store = deps.as_mut().storage;
+    CHECKPOINT_CONFIG.save(store, &CheckpointConfig::default())?;
+    let mut queue = CheckpointQueue::default();
+    queue.reset(store)?;
+    let mut building = Checkpoint::new(sigset(0))?;
+    building.fees_collected = 1_000;
+    CHECKPOINTS.push_back(store, &building)?;
+    let config = BitcoinConfig::default();
+
+    // the pool only holds the collected fees, which fall short
+    FEE_POOL.save(store, &((1_000 * config.units_per_sat) as i64))?;
+    let coverage = queue.fee_coverage(store, &config, &[0; 32])?;
+    let miner_fee = coverage.miner_fee;
+    assert!(miner_fee > 1_000);
+    assert_eq!(coverage.fee_pool_surplus, 0);
+    assert_eq!(coverage.top_up, 0);
+    assert!(!coverage.is_covered());
+
+    // a surplus one satoshi short of the miner fee still delays it
+    FEE_POOL.save(store, &(((miner_fee - 1) * config.units_per_sat) as i64))?;
+    let coverage = queue.fee_coverage(store, &config, &[0; 32])?;
+    assert_eq!(coverage.top_up, miner_fee - 1_001);
+    assert!(!coverage.is_covered());
+
+    FEE_POOL.save(store, &((miner_fee * config.units_per_sat) as i64))?;
+    let coverage = queue.fee_coverage(store, &config, &[0; 32])?;
+    assert_eq!(coverage.fee_pool_surplus, miner_fee - 1_000);
+    assert_eq!(coverage.top_up, miner_fee - 1_000);
+    assert_eq!(coverage.coverage_bps, 10_000);
+    assert!(coverage.is_covered());
+
+    Ok(())
+}
+
+#[test]
+fn adaptive_schedule_triggers() -> ContractResult<()> {
+    let mut deps = mock_dependencies();
+    let store = deps.as_mut().storage;
+    CHECKPOINT_CONFIG.save(
+        store,
+        &CheckpointConfig {
+            adaptive_scheduling: true,
+            adaptive_pending_value_threshold: 50_000,
+            adaptive_fee_rate_percentile: 25,
+            ..CheckpointConfig::default()
+        },
+    )?;
+    let mut queue = CheckpointQueue::default();
+    queue.reset(store)?;
+    let mut building = Checkpoint::new(sigset(0))?;
+    building.fee_rate = 20;
+    push_bitcoin_tx_output(&mut building.batches[BatchType::Checkpoint][0], 30_000);
+    CHECKPOINTS.push_back(store, &building)?;
+    let env = set_time(100);
+
+    let schedule = queue.schedule(&env, store)?;
+    assert_eq!(schedule.pending_value, 30_000);
+    assert_eq!(schedule.favorable_fee_rate, None);
+    assert!(!schedule.triggered);
+
+    for fee_rate in [10, 15, 30, 40, 50] {
+        record_fee_rate_change(
+            store,
+            FeeRateChange {
+                checkpoint_index: 0,
+                old_fee_rate: 0,
+                new_fee_rate: fee_rate,
+                cause: FeeRateChangeCause::Manual,
+                timestamp: 0,
+            },
+        )?;
+    }
+    // the 25th percentile is 15 sat/vB, above the checkpoint's fee rate
+    let schedule = queue.schedule(&env, store)?;
+    assert_eq!(schedule.favorable_fee_rate, Some(15));
+    assert!(!schedule.triggered);
+
+    let mut building = CHECKPOINTS.pop_back(store)?.unwrap();
+    building.fee_rate = 15;
+    push_bitcoin_tx_output(&mut building.batches[BatchType::Checkpoint][0], 20_000);
+    CHECKPOINTS.push_back(store, &building)?;
+    let schedule = queue.schedule(&env, store)?;
+    assert_eq!(schedule.pending_value, 50_000);
+    assert!(schedule.triggered);
+
+    Ok(())
+}
+
+#[test]
+fn fee_rate_history() -> ContractResult<()> {
+    let mut deps = mock_dependencies();
+    let store = deps.as_mut().storage;
+    CONFIG.save(store, &test_config())?;
+    let config = CheckpointConfig {
+        min_fee_rate: 10,
+        max_fee_rate: 100,
+        ..CheckpointConfig::default()
+    };
+    CHECKPOINT_CONFIG.save(store, &config)?;
+    let mut queue = CheckpointQueue::default();
+    queue.reset(store)?;
+    let mut building = Checkpoint::new(sigset(0))?;
+    building.fee_rate = 20;
+    CHECKPOINTS.push_back(store, &building)?;
+    let env = set_time(100);
+
+    // manual fee rates stay within the configured bounds
+    assert!(set_fee_rate(store, &env, mock_info("owner", &[]), 5).is_err());
+    assert!(set_fee_rate(store, &env, mock_info("owner", &[]), 101).is_err());
+    // setting the current fee rate is not a change
+    set_fee_rate(store, &env, mock_info("owner", &[]), 20)?;
+    assert!(query_fee_rate_history(store, None)?.is_empty());
+
+    set_fee_rate(store, &env, mock_info("owner", &[]), 30)?;
+    assert_eq!(queue.building(store)?.fee_rate, 30);
+    assert_eq!(
+        query_fee_rate_history(store, None)?,
+        vec![FeeRateChange {
+            checkpoint_index: 0,
+            old_fee_rate: 20,
+            new_fee_rate: 30,
+            cause: FeeRateChangeCause::Manual,
+            timestamp: bridge_time(&env),
+        }]
+    );
+
+    // the history is bounded, and queried newest first
+    for fee_rate in 0..MAX_FEE_RATE_HISTORY as u64 {
+        record_fee_rate_change(
+            store,
+            FeeRateChange {
+                checkpoint_index: 1,
+                old_fee_rate: fee_rate,
+                new_fee_rate: fee_rate + 1,
+                cause: FeeRateChangeCause::ConfirmationLag,
+                timestamp: 200,
+            },
+        )?;
+    }
+    assert_eq!(FEE_RATE_HISTORY.len(store)?, MAX_FEE_RATE_HISTORY);
+    let history = query_fee_rate_history(store, Some(2))?;
+    assert_eq!(history.len(), 2);
+    assert_eq!(history[0].new_fee_rate, MAX_FEE_RATE_HISTORY as u64);
+    assert_eq!(history[1].new_fee_rate, MAX_FEE_RATE_HISTORY as u64 - 1);
+    assert!(query_fee_rate_history(store, Some(1_000))?.len() <= MAX_QUERY_LIMIT as usize);
+    assert!(FEE_RATE_HISTORY
+        .iter(store)?
+        .all(|change| change.unwrap().cause == FeeRateChangeCause::ConfirmationLag));
+
+    Ok(())
+}
+
+#[test]
+fn withdrawal_priority_fee_rates() -> ContractResult<()> {
+    let mut deps = mock_dependencies();
+    let store = deps.as_mut().storage;
+    let config = CheckpointConfig {
+        min_fee_rate: 10,
+        max_fee_rate: 100,
+        adaptive_scheduling: true,
+        priority_fee_multipliers: PriorityFeeMultipliers {
+            economy: (1, 2),
+            ..PriorityFeeMultipliers::default()
+        },
+        ..CheckpointConfig::default()
+    };
+    assert_eq!(
+        config.priority_fee_rate(40, WithdrawalPriority::Economy),
+        20
+    );
+    assert_eq!(
+        config.priority_fee_rate(16, WithdrawalPriority::Economy),
+        10
+    );
+    assert_eq!(
+        config.priority_fee_rate(40, WithdrawalPriority::Standard),
+        40
+    );
+    assert_eq!(config.priority_fee_rate(40, WithdrawalPriority::Fast), 60);
+    assert_eq!(config.priority_fee_rate(80, WithdrawalPriority::Fast), 100);
+
+    CHECKPOINT_CONFIG.save(store, &config)?;
+    let mut queue = CheckpointQueue::default();
+    queue.reset(store)?;
+    CHECKPOINTS.push_back(store, &Checkpoint::new(sigset(0))?)?;
+    let env = set_time(100);
+    assert!(!queue.schedule(&env, store)?.triggered);
+
+    FAST_WITHDRAWAL_CHECKPOINT.save(store, &0)?;
+    let schedule = queue.schedule(&env, store)?;
+    assert!(schedule.has_fast_withdrawal);
+    assert!(schedule.triggered);
+
+    Ok(())
+}
+
+#[test]
+fn liabilities_tree_matches_merkle_root() -> ContractResult<()> {
+    let mut deps = mock_dependencies();
+    let store = deps.as_mut().storage;
+    let holders: Vec<_> = (0..5u128)
+        .map(|i| (format!("holder{}", i), 1_000 * (i + 1)))
+        .collect();
+    let leaves: Vec<_> = holders
+        .iter()
+        .map(|(holder, balance)| liability_leaf(holder, *balance))
+        .collect();
+    for (i, leaf) in leaves.iter().enumerate() {
+        SNAPSHOT_TREE.save(store, (0, i as u32), &Binary::from(leaf.to_vec()))?;
+    }
+    let mut snapshot = BalanceSnapshot {
+        started_at: 0,
+        completed_at: None,
+        clearing: false,
+        next_key: None,
+        holders: holders.len() as u64,
+        total: Uint128::zero(),
+        top_len: 0,
+        withdrawals: 0,
+        withdrawals_total: 0,
+        tree_cursor: Some((0, 0)),
+        liabilities_root: None,
+    };
+
+    // two pairs per step take several steps for 5 leaves
+    let mut steps = 1;
+    while !hash_liabilities_tree(store, &mut snapshot, 2)? {
+        steps += 1;
+    }
+    assert!(steps > 1);
+    let root = merkle_root(&leaves);
+    assert_eq!(snapshot.liabilities_root, Some(Binary::from(root.to_vec())));
+
+    for index in 0..leaves.len() {
+        let branch: Vec<[u8; 32]> = liabilities_branch(store, snapshot.holders, index as u32)?
+            .into_iter()
+            .map(|hash| hash.to_vec().try_into().unwrap())
+            .collect();
+        assert_eq!(branch, merkle_branch(&leaves, index));
+        assert!(verify_merkle_branch(leaves[index], index, &branch, root));
+    }
+    Ok(())
+}
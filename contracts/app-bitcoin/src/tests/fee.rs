@@ -1,30 +1,21 @@
 use crate::{
-    fee::process_deduct_fee,
-    state::{Ratio, CONFIG, TOKEN_FEE_RATIO},
+    app::{apply_latest_sigset_discount, calc_deposit_fee},
+    entrypoints::{query_relayer_fee_options, set_fee_exemption, update_config},
+    fee::{check_relayer_fee_payment, process_deduct_fee, process_deduct_fee_for},
+    interface::BitcoinConfig,
+    state::{Ratio, CONFIG, FEE_EXEMPTIONS, TOKEN_FEE_RATIO},
+    tests::helper::test_config,
 };
 use common_bitcoin::error::ContractResult;
-use cosmwasm_std::{testing::mock_dependencies, Addr, Coin, Uint128};
-use oraiswap::asset::AssetInfo;
+use cosmwasm_std::{
+    testing::{mock_dependencies, mock_info},
+    Coin, Uint128,
+};
 
 #[test]
 fn test_fee_collected() -> ContractResult<()> {
     let mut deps = mock_dependencies();
-    CONFIG.save(
-        deps.as_mut().storage,
-        &crate::msg::Config {
-            owner: Addr::unchecked("owner"),
-            relayer_fee_receiver: Addr::unchecked("relayer_fee_receiver"),
-            token_fee_receiver: Addr::unchecked("token_fee_receiver"),
-            relayer_fee_token: AssetInfo::NativeToken {
-                denom: "orai".to_string(),
-            },
-            relayer_fee: Uint128::from(0u128),
-            token_factory_contract: Addr::unchecked("token_factory_contract"),
-            light_client_contract: Addr::unchecked("light_client_contract"),
-            swap_router_contract: None,
-            osor_entry_point_contract: None,
-        },
-    )?;
+    CONFIG.save(deps.as_mut().storage, &test_config())?;
     TOKEN_FEE_RATIO.save(
         deps.as_mut().storage,
         &Ratio {
@@ -86,3 +77,134 @@ fn test_fee_collected() -> ContractResult<()> {
     assert_eq!(fee.relayer_fee.amount, Uint128::from(0u128));
     Ok(())
 }
+
+#[test]
+fn test_fee_exemption() -> ContractResult<()> {
+    let mut deps = mock_dependencies();
+    CONFIG.save(deps.as_mut().storage, &test_config())?;
+    TOKEN_FEE_RATIO.save(
+        deps.as_mut().storage,
+        &Ratio {
+            nominator: 1,
+            denominator: 1000,
+        },
+    )?;
+    // exemptions must name a valid address
+    let deps_mut = deps.as_mut();
+    assert!(set_fee_exemption(
+        deps_mut.storage,
+        deps_mut.api,
+        mock_info("owner", &[]),
+        "Market_Maker".to_string(),
+        true,
+    )
+    .is_err());
+    set_fee_exemption(
+        deps_mut.storage,
+        deps_mut.api,
+        mock_info("owner", &[]),
+        "market_maker".to_string(),
+        true,
+    )?;
+    assert!(FEE_EXEMPTIONS.has(deps.as_ref().storage, "market_maker"));
+
+    let amount = Coin {
+        denom: "btc".to_string(),
+        amount: Uint128::from(5000u128),
+    };
+    let fee = process_deduct_fee_for(
+        deps.as_ref().storage,
+        &deps.as_ref().querier,
+        deps.as_ref().api,
+        "market_maker",
+        amount.clone(),
+    )?;
+    assert_eq!(fee.deducted_amount, Uint128::from(5000u128));
+    assert_eq!(fee.token_fee.amount, Uint128::from(0u128));
+    assert_eq!(fee.relayer_fee.amount, Uint128::from(0u128));
+
+    let fee = process_deduct_fee_for(
+        deps.as_ref().storage,
+        &deps.as_ref().querier,
+        deps.as_ref().api,
+        "user",
+        amount,
+    )?;
+    assert_eq!(fee.deducted_amount, Uint128::from(4995u128));
+    assert_eq!(fee.token_fee.amount, Uint128::from(5u128));
+    Ok(())
+}
+
+#[test]
+fn test_relayer_fee_options() -> ContractResult<()> {
+    let mut deps = mock_dependencies();
+    CONFIG.save(deps.as_mut().storage, &test_config())?;
+    let orai = |amount: u128| Coin::new(amount, "orai");
+    let nbtc = |amount: u128| Coin::new(amount, "nbtc");
+    let set_options = |storage: &mut dyn cosmwasm_std::Storage, options: Vec<Coin>| {
+        update_config(
+            storage,
+            mock_info("owner", &[]),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            Some(options),
+        )
+    };
+
+    // relaying is free without options
+    assert_eq!(check_relayer_fee_payment(deps.as_ref().storage, &[])?, None);
+    assert!(check_relayer_fee_payment(deps.as_ref().storage, &[orai(1)]).is_err());
+
+    // options are set with the rest of the relayer fee config
+    assert!(set_options(deps.as_mut().storage, vec![orai(0)]).is_err());
+    assert!(set_options(deps.as_mut().storage, vec![orai(1000), orai(10)]).is_err());
+    set_options(deps.as_mut().storage, vec![orai(1000), nbtc(10)])?;
+    assert_eq!(
+        query_relayer_fee_options(deps.as_ref().storage)?,
+        vec![orai(1000), nbtc(10)]
+    );
+    assert!(check_relayer_fee_payment(deps.as_ref().storage, &[]).is_err());
+    assert!(check_relayer_fee_payment(deps.as_ref().storage, &[orai(999)]).is_err());
+    assert!(check_relayer_fee_payment(deps.as_ref().storage, &[Coin::new(1000, "atom")]).is_err());
+    assert!(check_relayer_fee_payment(deps.as_ref().storage, &[orai(1000), nbtc(10)]).is_err());
+    assert_eq!(
+        check_relayer_fee_payment(deps.as_ref().storage, &[nbtc(10)])?,
+        Some(nbtc(10))
+    );
+    assert_eq!(
+        check_relayer_fee_payment(deps.as_ref().storage, &[orai(1500)])?,
+        Some(orai(1500))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_sigset_lag_deposit_fees() {
+    let config = BitcoinConfig {
+        sigset_lag_surcharge_bps: 30,
+        latest_sigset_discount_bps: 2_500,
+        ..BitcoinConfig::default()
+    };
+    let amount = Uint128::from(1_000_000u128);
+
+    assert_eq!(calc_deposit_fee(amount, 0, &config), 0);
+    assert_eq!(calc_deposit_fee(amount, 2, &config), 6_000);
+    // the surcharge is capped no matter how old the sigset is
+    assert_eq!(calc_deposit_fee(amount, 1_000, &config), 100_000);
+
+    assert_eq!(apply_latest_sigset_discount(1_000, 0, &config), 750);
+    assert_eq!(apply_latest_sigset_discount(1_000, 1, &config), 1_000);
+
+    let disabled = BitcoinConfig::default();
+    assert_eq!(calc_deposit_fee(amount, 5, &disabled), 0);
+    assert_eq!(apply_latest_sigset_discount(1_000, 0, &disabled), 1_000);
+}
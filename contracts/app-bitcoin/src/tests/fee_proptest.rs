@@ -0,0 +1,143 @@
+use bitcoin::{Amount, ScriptBuf};
+use cosmwasm_std::Uint128;
+use proptest::prelude::*;
+
+use crate::{
+    checkpoint::{adjust_fee_rate, BatchType, BitcoinTx, Checkpoint, Output},
+    fee::deduct_fee,
+    interface::CheckpointConfig,
+    signatory::SignatorySet,
+    state::Ratio,
+};
+
+/// Outputs as (value, script length) pairs, scripts of different lengths have
+/// different dust values.
+fn outputs() -> impl Strategy<Value = Vec<(u64, usize)>> {
+    prop::collection::vec((0u64..2_000_000, 1usize..64), 1..20)
+}
+
+/// Builds a transaction paying the given outputs, the first byte of each
+/// script is the index of the output so it can be told apart once outputs
+/// are removed.
+fn tx_with_outputs(outputs: &[(u64, usize)]) -> BitcoinTx {
+    let mut tx = BitcoinTx::default();
+    for (i, (value, script_len)) in outputs.iter().enumerate() {
+        let mut script = vec![0x51; *script_len];
+        script[0] = i as u8;
+        tx.output.push(Output::new(bitcoin::TxOut {
+            value: Amount::from_sat(*value),
+            script_pubkey: ScriptBuf::from(script),
+        }));
+    }
+    tx
+}
+
+fn total_value(tx: &BitcoinTx) -> u64 {
+    tx.output.iter().map(|output| output.value.to_sat()).sum()
+}
+
+proptest! {
+    #[test]
+    fn deduct_fee_never_creates_value(outputs in outputs(), fee in 0u64..5_000_000) {
+        let mut tx = tx_with_outputs(&outputs);
+        let before = total_value(&tx);
+        tx.deduct_fee(fee).unwrap();
+        let after = total_value(&tx);
+        prop_assert!(after <= before);
+
+        if fee > 0 && !tx.output.is_empty() {
+            // every remaining output pays the same share and keeps at least
+            // its dust value
+            let len = tx.output.len() as u64;
+            let share_of = |output: &Output| {
+                outputs[output.script_pubkey.as_bytes()[0] as usize].0 - output.value.to_sat()
+            };
+            let share = share_of(&tx.output[0]);
+            for output in &tx.output {
+                prop_assert_eq!(share_of(output), share);
+                prop_assert!(output.value >= output.script_pubkey.dust_value());
+            }
+            // rounding leaves less than one satoshi per output unpaid
+            prop_assert!(share * len <= fee);
+            prop_assert!(fee - share * len < len);
+        }
+    }
+
+    #[test]
+    fn deduct_zero_fee_is_noop(outputs in outputs()) {
+        let mut tx = tx_with_outputs(&outputs);
+        let before = tx.clone();
+        tx.deduct_fee(0).unwrap();
+        prop_assert_eq!(tx, before);
+    }
+
+    #[test]
+    fn est_vsize_grows_with_outputs(outputs in outputs(), script_len in 0usize..64) {
+        let mut tx = tx_with_outputs(&outputs);
+        let vsize = tx.est_vsize().unwrap();
+        prop_assert!(vsize >= tx.to_bitcoin_tx().unwrap().vsize() as u64);
+
+        tx.output.push(Output::new(bitcoin::TxOut {
+            value: Amount::ZERO,
+            script_pubkey: ScriptBuf::from(vec![0x51; script_len]),
+        }));
+        // at least the value and the script length prefix
+        prop_assert!(tx.est_vsize().unwrap() >= vsize + 9 + script_len as u64);
+    }
+
+    #[test]
+    fn total_output_respects_max_outputs(outputs in outputs(), max_outputs in 1u64..30) {
+        let mut checkpoint = Checkpoint::new(SignatorySet::default()).unwrap();
+        checkpoint.batches[BatchType::Checkpoint][0] = tx_with_outputs(&outputs);
+        let config = CheckpointConfig {
+            max_outputs,
+            ..CheckpointConfig::default()
+        };
+
+        let (in_amount, out_amount) = checkpoint.calc_total_input_and_output(&config).unwrap();
+        prop_assert_eq!(in_amount, 0);
+        let expected: u64 = outputs
+            .iter()
+            .take(max_outputs as usize)
+            .map(|(value, _)| value)
+            .sum();
+        prop_assert_eq!(out_amount, expected);
+    }
+
+    #[test]
+    fn adjust_fee_rate_stays_in_bounds(
+        prev_fee_rate in 0u64..10_000,
+        min_fee_rate in 0u64..500,
+        range in 0u64..5_000,
+        up in any::<bool>(),
+    ) {
+        let config = CheckpointConfig {
+            min_fee_rate,
+            max_fee_rate: min_fee_rate + range,
+            ..CheckpointConfig::default()
+        };
+        let fee_rate = adjust_fee_rate(prev_fee_rate, up, &config);
+        prop_assert!(fee_rate >= config.min_fee_rate && fee_rate <= config.max_fee_rate);
+
+        let in_bounds = prev_fee_rate.clamp(config.min_fee_rate, config.max_fee_rate);
+        if up {
+            prop_assert!(fee_rate >= in_bounds);
+        } else {
+            prop_assert!(fee_rate <= in_bounds);
+        }
+    }
+
+    #[test]
+    fn token_fee_never_exceeds_amount(
+        amount in 0u128..u64::MAX as u128,
+        nominator in 0u64..10_000,
+        extra in 0u64..10_000,
+    ) {
+        let ratio = Ratio {
+            nominator,
+            denominator: nominator + extra,
+        };
+        let fee = deduct_fee(ratio, Uint128::new(amount));
+        prop_assert!(fee <= Uint128::new(amount));
+    }
+}
@@ -1,12 +1,13 @@
 use crate::checkpoint::{BitcoinTx, Output};
 use crate::msg::{self};
 use cosmwasm_std::{testing::mock_env, Env, Timestamp};
-use cosmwasm_std::{Addr, Coin};
+use cosmwasm_std::{Addr, Coin, Uint128};
 use cosmwasm_testing_util::MockResult;
+use oraiswap::asset::AssetInfo;
 
 use crate::threshold_sig::Signature;
+use bitcoin::bip32::{ChildNumber, Xpriv};
 use bitcoin::secp256k1::{Message, Secp256k1};
-use bitcoin::util::bip32::{ChildNumber, ExtendedPrivKey};
 use common_bitcoin::error::ContractResult;
 
 use derive_more::{Deref, DerefMut};
@@ -15,7 +16,7 @@ use derive_more::{Deref, DerefMut};
 /// correct private keys for each signature.
 pub fn sign(
     secp: &Secp256k1<bitcoin::secp256k1::SignOnly>,
-    xpriv: &ExtendedPrivKey,
+    xpriv: &Xpriv,
     to_sign: &[([u8; 32], u32)],
 ) -> ContractResult<Vec<Signature>> {
     Ok(to_sign
@@ -26,7 +27,7 @@ pub fn sign(
                 .private_key;
 
             let signature = secp
-                .sign_ecdsa(&Message::from_slice(&msg[..])?, &privkey)
+                .sign_ecdsa(&Message::from_digest_slice(&msg[..])?, &privkey)
                 .serialize_compact()
                 .to_vec();
             Ok(Signature(signature))
@@ -36,8 +37,8 @@ pub fn sign(
 
 pub fn push_bitcoin_tx_output(tx: &mut BitcoinTx, value: u64) {
     let tx_out = bitcoin::TxOut {
-        value,
-        script_pubkey: bitcoin::Script::new(),
+        value: bitcoin::Amount::from_sat(value),
+        script_pubkey: bitcoin::ScriptBuf::new(),
     };
     tx.output.push(Output::new(tx_out));
 }
@@ -48,6 +49,26 @@ pub fn set_time(seconds: u64) -> Env {
     env
 }
 
+/// The contract config unit tests run with, before any test-specific changes.
+pub fn test_config() -> msg::Config {
+    msg::Config {
+        owner: Addr::unchecked("owner"),
+        relayer_fee_receiver: Addr::unchecked("relayer_fee_receiver"),
+        token_fee_receiver: Addr::unchecked("token_fee_receiver"),
+        relayer_fee_token: AssetInfo::NativeToken {
+            denom: "orai".to_string(),
+        },
+        relayer_fee: Uint128::zero(),
+        token_factory_contract: Addr::unchecked("token_factory_contract"),
+        light_client_contract: Addr::unchecked("light_client_contract"),
+        swap_router_contract: None,
+        osor_entry_point_contract: None,
+        secondary_light_client_contract: None,
+        notification_contract: None,
+        relayer_fee_options: vec![],
+    }
+}
+
 #[cfg(not(feature = "test-tube"))]
 pub type TestMockApp = cosmwasm_testing_util::MultiTestMockApp;
 #[cfg(feature = "test-tube")]
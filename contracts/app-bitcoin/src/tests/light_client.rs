@@ -0,0 +1,78 @@
+use bitcoin::{hashes::Hash, BlockHash};
+use common_bitcoin::{adapter::WrappedBinary, error::ContractResult};
+use cosmwasm_std::{
+    from_json,
+    testing::{mock_dependencies, mock_info, MockQuerier},
+    to_json_binary, Addr, Empty, QuerierResult, QuerierWrapper, SystemResult, WasmQuery,
+};
+use light_client_bitcoin::msg::QueryMsg::{self, BlockHashAtHeight, HeaderHeight};
+
+use crate::{
+    entrypoints::switch_light_client,
+    msg::Config,
+    state::{CONFIG, LIGHT_CLIENT_SWITCHED_AT},
+    tests::helper::test_config,
+};
+
+/// The primary light client is at height 10, the secondary at height 12 with
+/// the hash of block 10 starting with `secondary_byte`.
+fn light_clients(secondary_byte: u8) -> Box<dyn Fn(&WasmQuery) -> QuerierResult> {
+    Box::new(move |query: &WasmQuery| {
+        let WasmQuery::Smart { contract_addr, msg } = query else {
+            unreachable!()
+        };
+        let secondary = contract_addr == "secondary_light_client";
+        let res = match from_json::<QueryMsg>(msg).unwrap() {
+            HeaderHeight {} => to_json_binary(&if secondary { 12u32 } else { 10 }),
+            BlockHashAtHeight { height } => {
+                assert_eq!(height, 10);
+                let byte = if secondary { secondary_byte } else { 1 };
+                to_json_binary(&Some(WrappedBinary(BlockHash::from_byte_array([byte; 32]))))
+            }
+            _ => unreachable!(),
+        };
+        SystemResult::Ok(cosmwasm_std::ContractResult::Ok(res.unwrap()))
+    })
+}
+
+#[test]
+fn switch_light_client_requires_agreeing_headers() -> ContractResult<()> {
+    let mut deps = mock_dependencies();
+    let store = deps.as_mut().storage;
+    CONFIG.save(
+        store,
+        &Config {
+            secondary_light_client_contract: Some(Addr::unchecked("secondary_light_client")),
+            ..test_config()
+        },
+    )?;
+
+    let mut querier = MockQuerier::<Empty>::new(&[]);
+    querier.update_wasm(light_clients(2));
+    let err = switch_light_client(
+        store,
+        &QuerierWrapper::new(&querier),
+        mock_info("owner", &[]),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Light clients disagree on the header at height 10"
+    );
+    assert!(LIGHT_CLIENT_SWITCHED_AT.may_load(store)?.is_none());
+
+    querier.update_wasm(light_clients(1));
+    switch_light_client(
+        store,
+        &QuerierWrapper::new(&querier),
+        mock_info("owner", &[]),
+    )?;
+    let config = CONFIG.load(store)?;
+    assert_eq!(config.light_client_contract, "secondary_light_client");
+    assert_eq!(
+        config.secondary_light_client_contract,
+        Some(Addr::unchecked("light_client_contract"))
+    );
+    assert_eq!(LIGHT_CLIENT_SWITCHED_AT.load(store)?, 10);
+    Ok(())
+}
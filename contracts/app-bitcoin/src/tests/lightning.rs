@@ -0,0 +1,198 @@
+use bitcoin::opcodes::all::{OP_CLTV, OP_SHA256};
+use bitcoin::script::Instruction;
+use bitcoin::secp256k1::{PublicKey, Secp256k1, SecretKey};
+use common_bitcoin::error::{ContractError, ContractResult};
+use cosmwasm_std::testing::{mock_dependencies, mock_info, MockQuerier};
+use cosmwasm_std::{
+    from_json, to_json_binary, Addr, BankMsg, Binary, Coin, CosmosMsg, DepsMut, QuerierWrapper,
+    SystemError, SystemResult, WasmQuery,
+};
+
+use crate::checkpoint::{BatchType, Checkpoint};
+use crate::entrypoints::{accept_lightning_swap, refund_lightning_swap, withdraw_to_lightning};
+use crate::interface::{BitcoinConfig, CheckpointConfig, SwapProvider};
+use crate::lightning::htlc_script;
+use crate::signatory::SignatorySet;
+use crate::state::{
+    get_full_btc_denom, BITCOIN_CONFIG, BUILDING_INDEX, CHECKPOINTS, CHECKPOINT_CONFIG, CONFIG,
+    FEE_EXEMPTIONS, FEE_POOL, LIGHTNING_SWAPS, SWAP_PROVIDERS,
+};
+use crate::tests::helper::{set_time, test_config};
+use light_client_bitcoin::msg::QueryMsg;
+
+#[test]
+fn htlc_script_paths() {
+    let secp = Secp256k1::new();
+    let pubkey = |byte| {
+        PublicKey::from_secret_key(&secp, &SecretKey::from_slice(&[byte; 32]).unwrap()).serialize()
+    };
+    let claim_pubkey = pubkey(1);
+    let refund_pubkey = pubkey(2);
+    let payment_hash = [7u8; 32];
+
+    let script = htlc_script(&payment_hash, &claim_pubkey, &refund_pubkey, 850_000).unwrap();
+    let instructions = script
+        .instructions()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(instructions[0], Instruction::Op(OP_SHA256));
+    assert_eq!(
+        instructions[1],
+        Instruction::PushBytes((&payment_hash).into())
+    );
+    assert!(instructions.contains(&Instruction::Op(OP_CLTV)));
+    assert!(instructions.contains(&Instruction::PushBytes((&claim_pubkey).into())));
+    assert!(instructions.contains(&Instruction::PushBytes((&refund_pubkey).into())));
+
+    assert!(htlc_script(&[7u8; 20], &claim_pubkey, &refund_pubkey, 850_000).is_err());
+    assert!(htlc_script(&payment_hash, &[5u8; 33], &refund_pubkey, 850_000).is_err());
+}
+
+fn compressed_pubkey(byte: u8) -> Binary {
+    let secp = Secp256k1::new();
+    Binary::from(
+        PublicKey::from_secret_key(&secp, &SecretKey::from_slice(&[byte; 32]).unwrap())
+            .serialize()
+            .to_vec(),
+    )
+}
+
+fn header_height_querier(height: u32) -> MockQuerier {
+    let mut querier = MockQuerier::new(&[]);
+    querier.update_wasm(move |query| match query {
+        WasmQuery::Smart { msg, .. } => match from_json::<QueryMsg>(msg).unwrap() {
+            QueryMsg::HeaderHeight {} => SystemResult::Ok(cosmwasm_std::ContractResult::Ok(
+                to_json_binary(&height).unwrap(),
+            )),
+            _ => SystemResult::Err(SystemError::UnsupportedRequest {
+                kind: "QueryMsg".to_string(),
+            }),
+        },
+        _ => unreachable!(),
+    });
+    querier
+}
+
+#[test]
+fn lightning_swap_claim_and_refund() -> ContractResult<()> {
+    let mut deps = mock_dependencies();
+    let config = test_config();
+    let denom = get_full_btc_denom(
+        deps.as_ref().storage,
+        config.token_factory_contract.as_str(),
+    )?;
+    CONFIG.save(deps.as_mut().storage, &config)?;
+    BITCOIN_CONFIG.save(
+        deps.as_mut().storage,
+        &BitcoinConfig {
+            min_withdrawal_checkpoints: 0,
+            ..BitcoinConfig::default()
+        },
+    )?;
+    CHECKPOINT_CONFIG.save(deps.as_mut().storage, &CheckpointConfig::default())?;
+    FEE_POOL.save(deps.as_mut().storage, &0)?;
+    BUILDING_INDEX.save(deps.as_mut().storage, &0)?;
+    CHECKPOINTS.push_back(
+        deps.as_mut().storage,
+        &Checkpoint::new(SignatorySet::default())?,
+    )?;
+    FEE_EXEMPTIONS.save(deps.as_mut().storage, "user", &())?;
+
+    let provider = Addr::unchecked("provider");
+    let swap_provider = SwapProvider {
+        claim_pubkey: compressed_pubkey(1),
+        htlc_timeout_blocks: 144,
+        accept_timeout: 600,
+    };
+    SWAP_PROVIDERS.save(deps.as_mut().storage, &provider, &swap_provider)?;
+
+    let payment_hash = Binary::from(vec![7u8; 32]);
+    let refund_pubkey = compressed_pubkey(2);
+    let create = |deps: DepsMut, querier: &MockQuerier, time: u64| {
+        withdraw_to_lightning(
+            deps.storage,
+            &QuerierWrapper::new(querier),
+            set_time(time),
+            mock_info("user", &[Coin::new(100_000_000_000, &denom)]),
+            provider.clone(),
+            payment_hash.clone(),
+            refund_pubkey.clone(),
+            None,
+        )
+    };
+
+    // the HTLC timeout is counted from the height the swap was created at
+    create(deps.as_mut(), &header_height_querier(1_000), 1_000)?;
+    assert_eq!(
+        LIGHTNING_SWAPS.load(deps.as_ref().storage, 1)?.lock_height,
+        1_144
+    );
+
+    let querier = header_height_querier(1_100);
+    let accept = |deps: DepsMut, sender: &str, swap_id: u64| {
+        accept_lightning_swap(
+            deps.storage,
+            &QuerierWrapper::new(&querier),
+            deps.api,
+            set_time(1_200),
+            mock_info(sender, &[]),
+            swap_id,
+        )
+    };
+    assert!(matches!(
+        accept(deps.as_mut(), "user", 1),
+        Err(ContractError::Unauthorized {})
+    ));
+    let res = accept(deps.as_mut(), "provider", 1)?;
+    let expected_script = htlc_script(
+        &payment_hash,
+        &swap_provider.claim_pubkey,
+        &refund_pubkey,
+        1_144,
+    )?;
+    assert!(res
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "lock_height" && attr.value == "1144"));
+    assert!(!LIGHTNING_SWAPS.has(deps.as_ref().storage, 1));
+    let building = CHECKPOINTS.front(deps.as_ref().storage)?.unwrap();
+    assert_eq!(
+        building.batches[BatchType::Checkpoint][0].output[0].script_pubkey,
+        expected_script.to_p2wsh()
+    );
+
+    // swaps can't be accepted once their HTLC timeout has passed
+    create(deps.as_mut(), &header_height_querier(1_000), 1_000)?;
+    let late = header_height_querier(1_144);
+    let deps_mut = deps.as_mut();
+    assert!(accept_lightning_swap(
+        deps_mut.storage,
+        &QuerierWrapper::new(&late),
+        deps_mut.api,
+        set_time(1_200),
+        mock_info("provider", &[]),
+        2,
+    )
+    .is_err());
+
+    // unaccepted swaps are refunded to their sender after the accept timeout
+    let refund = |deps: DepsMut, sender: &str, time: u64| {
+        refund_lightning_swap(deps.storage, set_time(time), mock_info(sender, &[]), 2)
+    };
+    assert!(matches!(
+        refund(deps.as_mut(), "provider", 1_600),
+        Err(ContractError::Unauthorized {})
+    ));
+    assert!(refund(deps.as_mut(), "user", 1_599).is_err());
+    let res = refund(deps.as_mut(), "user", 1_600)?;
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: "user".to_string(),
+            amount: vec![Coin::new(100_000_000_000, &denom)],
+        })
+    );
+    assert!(!LIGHTNING_SWAPS.has(deps.as_ref().storage, 2));
+
+    Ok(())
+}
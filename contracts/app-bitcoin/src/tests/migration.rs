@@ -0,0 +1,323 @@
+use cosmwasm_std::{testing::mock_dependencies, Addr};
+
+use crate::{
+    checkpoint::Checkpoint,
+    constants::CONTRACT_STATE_VERSION,
+    entrypoints::{query_config, query_full_config},
+    export::{export_section, import_entries},
+    interface::{BitcoinConfig, CheckpointConfig},
+    migration::{migrate_state, state_version},
+    msg::StateSection,
+    signatory::SignatorySet,
+    state::{
+        Ratio, BITCOIN_CONFIG, BTC_SUBDENOM, BUILDING_INDEX, CHECKPOINTS, CHECKPOINT_CONFIG,
+        CONFIG, FOUNDATION_KEYS, STATE_VERSION, TOKEN_FEE_RATIO,
+    },
+    tests::helper::test_config,
+};
+
+#[test]
+fn migrate_state_from_unversioned() {
+    let mut deps = mock_dependencies();
+    let store = deps.as_mut().storage;
+    CHECKPOINT_CONFIG
+        .save(store, &CheckpointConfig::default())
+        .unwrap();
+    BITCOIN_CONFIG
+        .save(store, &BitcoinConfig::default())
+        .unwrap();
+    for _ in 0..3 {
+        let checkpoint = Checkpoint::new(SignatorySet::default()).unwrap();
+        CHECKPOINTS.push_back(store, &checkpoint).unwrap();
+    }
+    assert_eq!(state_version(store).unwrap(), 0);
+
+    assert_eq!(migrate_state(store).unwrap(), 0);
+    assert_eq!(state_version(store).unwrap(), CONTRACT_STATE_VERSION);
+    assert_eq!(CHECKPOINTS.len(store).unwrap(), 3);
+    assert_eq!(FOUNDATION_KEYS.load(store).unwrap(), vec![]);
+
+    // migrating again is a no-op
+    assert_eq!(migrate_state(store).unwrap(), CONTRACT_STATE_VERSION);
+
+    STATE_VERSION
+        .save(store, &(CONTRACT_STATE_VERSION + 1))
+        .unwrap();
+    assert!(migrate_state(store).is_err());
+}
+
+#[test]
+fn full_config() {
+    let mut deps = mock_dependencies();
+    let store = deps.as_mut().storage;
+    CONFIG.save(store, &test_config()).unwrap();
+    TOKEN_FEE_RATIO
+        .save(
+            store,
+            &Ratio {
+                nominator: 1,
+                denominator: 100,
+            },
+        )
+        .unwrap();
+    let bitcoin_config = BitcoinConfig {
+        min_deposit_amount: 1_234,
+        ..BitcoinConfig::default()
+    };
+    BITCOIN_CONFIG.save(store, &bitcoin_config).unwrap();
+    CHECKPOINT_CONFIG
+        .save(store, &CheckpointConfig::default())
+        .unwrap();
+    BTC_SUBDENOM.save(store, &"nbtc".to_string()).unwrap();
+    STATE_VERSION.save(store, &CONTRACT_STATE_VERSION).unwrap();
+
+    let full = query_full_config(store).unwrap();
+    assert_eq!(full.config, query_config(store).unwrap());
+    assert_eq!(full.bitcoin_config, bitcoin_config);
+    assert_eq!(full.checkpoint_config, CheckpointConfig::default());
+    assert_eq!(
+        full.light_client_contract,
+        Addr::unchecked("light_client_contract")
+    );
+    assert_eq!(
+        full.token_factory_contract,
+        Addr::unchecked("token_factory_contract")
+    );
+    assert_eq!(full.btc_denom, "factory/token_factory_contract/nbtc");
+    assert_eq!(full.state_version, CONTRACT_STATE_VERSION);
+}
+
+#[test]
+fn export_import_checkpoints() {
+    let mut source = mock_dependencies();
+    let store = source.as_mut().storage;
+    BUILDING_INDEX.save(store, &2).unwrap();
+    for _ in 0..3 {
+        let checkpoint = Checkpoint::new(SignatorySet::default()).unwrap();
+        CHECKPOINTS.push_back(store, &checkpoint).unwrap();
+    }
+    CHECKPOINT_CONFIG
+        .save(store, &CheckpointConfig::default())
+        .unwrap();
+
+    let mut entries = vec![];
+    loop {
+        let start_after = entries
+            .last()
+            .map(|entry: &crate::msg::StateEntry| entry.key.clone());
+        let page = export_section(store, StateSection::Checkpoints, start_after, 2).unwrap();
+        if page.is_empty() {
+            break;
+        }
+        entries.extend(page);
+    }
+    // 3 checkpoints, the deque head and tail, and the building index
+    assert_eq!(entries.len(), 6);
+
+    let mut target = mock_dependencies();
+    let target_store = target.as_mut().storage;
+    import_entries(target_store, entries.clone(), true).unwrap();
+    assert_eq!(BUILDING_INDEX.load(target_store).unwrap(), 2);
+    assert_eq!(
+        CHECKPOINTS.back(target_store).unwrap(),
+        CHECKPOINTS.back(store).unwrap()
+    );
+    assert!(CHECKPOINT_CONFIG.may_load(target_store).unwrap().is_none());
+
+    // a finished import can not be resumed
+    assert!(import_entries(target_store, entries, false).is_err());
+}
+
+#[test]
+fn receive_migrated_accounts() {
+    use crate::entrypoints::{expect_bridge_migration, receive_migrated_state};
+    use crate::export::export_migrated;
+    use crate::interface::{MigrationHandover, MintClaim, SignedReserveOutput};
+    use crate::state::{
+        BTC_SUBDENOM, CONFIG, LEGACY_BTC_DENOMS, MIGRATION_PREDECESSOR, MINT_CLAIMS,
+        MINT_CLAIM_COUNT, RECOVERY_SCRIPTS, RELAYERS, SIGNED_RESERVE_OUTPUTS,
+    };
+    use crate::tests::helper::test_config;
+    use cosmwasm_std::{coin, testing::mock_info, Addr, Binary};
+
+    let mut source = mock_dependencies();
+    let relayer = Addr::unchecked("relayer");
+    let recipient = Addr::unchecked("recipient");
+    let store = source.as_mut().storage;
+    RELAYERS.save(store, &relayer, &()).unwrap();
+    RECOVERY_SCRIPTS
+        .save(store, "depositor", &Binary::from(vec![1; 22]))
+        .unwrap();
+    MINT_CLAIMS
+        .save(
+            store,
+            (&recipient, 4),
+            &MintClaim {
+                id: 4,
+                recipient: recipient.clone(),
+                coin: coin(1, "nbtc"),
+                expires_at: None,
+            },
+        )
+        .unwrap();
+    let accounts =
+        export_section(source.as_ref().storage, StateSection::Accounts, None, 10).unwrap();
+    let migrated = export_migrated(source.as_ref().storage, None, 10).unwrap();
+    // privileges granted by the predecessor's owner are not migrated
+    assert_eq!(migrated.len(), accounts.len() - 1);
+
+    let mut target = mock_dependencies();
+    let store = target.as_mut().storage;
+    CONFIG.save(store, &test_config()).unwrap();
+    let owner = mock_info("owner", &[]);
+    let predecessor = mock_info("predecessor", &[]);
+    // only the expected predecessor may send state
+    assert!(receive_migrated_state(store, predecessor.clone(), migrated.clone(), None).is_err());
+
+    // only a bridge without account state may expect a migration
+    RECOVERY_SCRIPTS
+        .save(store, "other", &Binary::from(vec![2; 22]))
+        .unwrap();
+    assert!(
+        expect_bridge_migration(store, owner.clone(), Some(Addr::unchecked("predecessor")))
+            .is_err()
+    );
+    RECOVERY_SCRIPTS.remove(store, "other");
+    expect_bridge_migration(store, owner, Some(Addr::unchecked("predecessor"))).unwrap();
+
+    // only migrated account entries are accepted
+    assert!(receive_migrated_state(store, predecessor.clone(), accounts, None).is_err());
+    receive_migrated_state(store, predecessor.clone(), migrated.clone(), None).unwrap();
+    assert!(!RELAYERS.has(store, &relayer));
+    assert!(RECOVERY_SCRIPTS.has(store, "depositor"));
+    // entries are never overwritten
+    assert!(receive_migrated_state(store, predecessor.clone(), migrated, None).is_err());
+
+    let denom = "factory/token_factory_contract/nbtc".to_string();
+    let reserve = SignedReserveOutput {
+        txid: "00".repeat(32),
+        vout: 0,
+        amount: 100_000,
+        sigset_index: 0,
+        lock_time: 0,
+        credit_fee_pool: false,
+        credits: vec![],
+    };
+    let handover = |btc_denom: &str| MigrationHandover {
+        reserve: reserve.clone(),
+        btc_denom: btc_denom.to_string(),
+        legacy_btc_denoms: vec!["factory/token_factory_contract/old".to_string()],
+    };
+    // the denom must be one this bridge can mint
+    assert!(receive_migrated_state(
+        store,
+        predecessor.clone(),
+        vec![],
+        Some(handover("factory/other/obtc"))
+    )
+    .is_err());
+    receive_migrated_state(store, predecessor, vec![], Some(handover(&denom))).unwrap();
+    assert!(MIGRATION_PREDECESSOR.may_load(store).unwrap().is_none());
+    assert_eq!(
+        SIGNED_RESERVE_OUTPUTS
+            .load(store, (&reserve.txid, 0))
+            .unwrap(),
+        reserve
+    );
+    assert_eq!(BTC_SUBDENOM.load(store).unwrap(), "nbtc");
+    // the predecessor's former denom and this bridge's own stay withdrawable
+    assert_eq!(
+        LEGACY_BTC_DENOMS.load(store).unwrap(),
+        vec![
+            "factory/token_factory_contract/old".to_string(),
+            "factory/token_factory_contract/obtc".to_string(),
+        ]
+    );
+    // new mint claims do not collide with the migrated ones
+    assert_eq!(MINT_CLAIM_COUNT.load(store).unwrap(), 5);
+}
+
+#[test]
+fn cancel_bridge_migration() {
+    use crate::entrypoints::cancel_bridge_migration;
+    use crate::interface::BridgeMigration;
+    use crate::state::{BRIDGE_MIGRATION, CONFIG};
+    use crate::tests::helper::test_config;
+    use cosmwasm_std::{testing::mock_info, Addr, Binary};
+
+    let mut deps = mock_dependencies();
+    let store = deps.as_mut().storage;
+    CONFIG.save(store, &test_config()).unwrap();
+    let owner = mock_info("owner", &[]);
+    assert!(cancel_bridge_migration(store, owner.clone()).is_err());
+
+    let mut migration = BridgeMigration {
+        successor: Addr::unchecked("successor"),
+        reserve_script: Binary::from(vec![0; 34]),
+        successor_sigset_index: 0,
+        started_at: 0,
+        checkpoint_index: Some(3),
+        transferred_until: None,
+        state_transferred: false,
+    };
+    BRIDGE_MIGRATION.save(store, &migration).unwrap();
+    // the reserve was already paid to the successor
+    assert!(cancel_bridge_migration(store, owner.clone()).is_err());
+
+    migration.checkpoint_index = None;
+    BRIDGE_MIGRATION.save(store, &migration).unwrap();
+    cancel_bridge_migration(store, owner).unwrap();
+    assert!(BRIDGE_MIGRATION.may_load(store).unwrap().is_none());
+}
+
+#[test]
+fn checkpoint_stored_before_bitcoin_upgrade() {
+    use crate::checkpoint::BatchType;
+    use bitcoin::{OutPoint, ScriptBuf};
+    use cosmwasm_std::{from_json, to_json_vec};
+    use std::str::FromStr;
+
+    // a building checkpoint spending a deposit to a two signatory set, as
+    // stored with bitcoin 0.29
+    let sigset = r#"{"create_time":1700000000,"present_vp":5000000,"possible_vp":5000000,"index":0,"signatories":[{"voting_power":3000000,"pubkey":{"bytes":[3,27,132,197,86,123,18,100,64,153,93,62,213,170,186,5,101,215,30,24,52,96,72,25,255,156,23,245,233,213,221,7,143]}},{"voting_power":2000000,"pubkey":{"bytes":[2,77,75,108,209,54,16,50,202,155,210,174,185,217,0,170,77,69,217,234,216,10,201,66,51,116,196,81,167,37,77,7,102]}}]}"#;
+    let input = r#"{"prevout":"TDsqHw6djHpbTY8OWx5u/6G048x/Dp4rLqq99aDjml4BAAAA","script_pubkey":"IgAgQjYhCbYabizJNMhpS9G1I4tF9BOx4q11OSCrURPjjuw=","redeem_script":"ZQEAh2MhAxuExVZ7EmRAmV0+1aq6BWXXHhg0YEgZ/5wX9enV3QePrGMDwMYtZwBofCECTUts0TYQMsqb0q652QCqTUXZ6tgKyUIzdMRRpyVNB2asYwOAhB6TaAPV3DKgAQB1Z2po","sigset_index":0,"dest":[0],"amount":100000,"est_witness_vsize":197,"signatures":{"threshold":3333333,"signed":0,"message":[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0],"len":2,"sigs":[[{"bytes":[3,27,132,197,86,123,18,100,64,153,93,62,213,170,186,5,101,215,30,24,52,96,72,25,255,156,23,245,233,213,221,7,143]},{"power":3000000,"sig":null}],[{"bytes":[2,77,75,108,209,54,16,50,202,155,210,174,185,217,0,170,77,69,217,234,216,10,201,66,51,116,196,81,167,37,77,7,102]},{"power":2000000,"sig":null}]]}}"#;
+    let stored = format!(
+        r#"{{"status":"building","pending":[],"batches":[{{"signed_txs":0,"batch":[{{"lock_time":0,"signed_inputs":0,"input":[{}],"output":["UMMAAAAAAAAWABR1HnboGZGW1FSUHEXRs6Mj8UM71g=="]}}]}}],"fee_rate":10,"signed_at_btc_height":null,"deposits_enabled":true,"fees_collected":0,"sigset":{}}}"#,
+        input, sigset
+    );
+
+    let sigset: SignatorySet = from_json(sigset.as_bytes()).unwrap();
+    assert_eq!(sigset.len(), 2);
+    assert_eq!(sigset.signature_threshold((2, 3)), 3_333_333);
+
+    let checkpoint: Checkpoint = from_json(stored.as_bytes()).unwrap();
+    assert_eq!(checkpoint.sigset, sigset);
+    let tx = &checkpoint.batches[BatchType::Checkpoint][0];
+    let input = &tx.input[0];
+    assert_eq!(
+        *input.prevout,
+        OutPoint::from_str("5e9ae3a0f5bdaa2e2b9e0e7fcce3b4a1ff6e1e5b0e8f4d5b7a8c9d0e1f2a3b4c:1")
+            .unwrap()
+    );
+    // the scripts built now match the ones built before the upgrade
+    assert_eq!(
+        *input.redeem_script,
+        sigset.redeem_script(&input.dest, (2, 3)).unwrap()
+    );
+    assert_eq!(
+        *input.script_pubkey,
+        sigset.output_script(&input.dest, (2, 3)).unwrap()
+    );
+    assert_eq!(tx.output[0].value.to_sat(), 50_000);
+    assert_eq!(
+        tx.output[0].script_pubkey,
+        ScriptBuf::from_hex("0014751e76e8199196d454941c45d1b3a323f1433bd6").unwrap()
+    );
+    assert_eq!(
+        tx.to_bitcoin_tx().unwrap().input[0].previous_output,
+        *input.prevout
+    );
+
+    let encoded = to_json_vec(&checkpoint).unwrap();
+    assert_eq!(from_json::<Checkpoint>(&encoded).unwrap(), checkpoint);
+}
@@ -1,6 +1,17 @@
 mod bitcoin;
 mod checkpoint;
 mod fee;
+#[cfg(feature = "proptests")]
+mod fee_proptest;
 pub mod helper;
+mod light_client;
+mod lightning;
 mod memo;
+mod migration;
+#[cfg(feature = "profiling")]
+mod profiling;
+mod recovery;
+mod reward;
 mod signatory;
+mod snapshot;
+mod tx_check;
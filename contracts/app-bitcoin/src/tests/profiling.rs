@@ -0,0 +1,34 @@
+use cosmwasm_std::{
+    testing::{mock_dependencies, mock_env},
+    Uint128,
+};
+
+use crate::{
+    contract::query,
+    msg::QueryMsg,
+    profiling::{take_profiles, GasProfile},
+    state::BAD_DEBT,
+};
+
+#[test]
+fn query_storage_accesses_are_recorded() {
+    let mut deps = mock_dependencies();
+    BAD_DEBT
+        .save(deps.as_mut().storage, &Uint128::new(5))
+        .unwrap();
+    take_profiles();
+
+    query(deps.as_ref(), mock_env(), QueryMsg::BadDebt {}).unwrap();
+
+    assert_eq!(
+        take_profiles(),
+        vec![GasProfile {
+            entrypoint: "query",
+            msg: "bad_debt".to_string(),
+            reads: 1,
+            bytes_read: 3,
+            ..GasProfile::default()
+        }]
+    );
+    assert!(take_profiles().is_empty());
+}
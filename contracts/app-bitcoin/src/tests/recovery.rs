@@ -0,0 +1,574 @@
+use bitcoin::{
+    bip32,
+    consensus::deserialize,
+    hashes::{hex::FromHex, Hash},
+    secp256k1::Secp256k1,
+    OutPoint, ScriptBuf, Sequence, Transaction, Txid,
+};
+use cosmwasm_std::{testing::mock_dependencies, Addr, Storage};
+
+use crate::{
+    app::Bitcoin,
+    checkpoint::{BatchType, Checkpoint, CheckpointStatus, Input},
+    entrypoints::{query_expired_deposits, query_raw_tx_hex, query_signing_duties},
+    interface::{BitcoinConfig, CheckpointConfig, Dest, ExpiredDeposit, SigningDutySource},
+    migration::migrate_state,
+    msg::RawTxKind,
+    recovery::{recovery_lock_time, RecoveryTxInput, RecoveryTxs, SweepInput, SweepTxInput},
+    signatory::{Signatory, SignatorySet},
+    state::{
+        BITCOIN_CONFIG, BUILDING_INDEX, CHECKPOINTS, CHECKPOINT_CONFIG, EXPIRED_DEPOSITS,
+        EXPIRED_DEPOSITS_BY_DEST, FEE_POOL, RECOVERY_TXS, RESERVE_UTXOS, SIGNED_RESERVE_OUTPUTS,
+        STATE_VERSION,
+    },
+    tests::helper::{set_time, sign},
+};
+use common_bitcoin::{adapter::WrappedBinary, error::ContractResult, xpub::Xpub};
+
+fn signer(seed: u8) -> (bip32::Xpriv, Xpub) {
+    let secp = Secp256k1::new();
+    let xpriv = bip32::Xpriv::new_master(bitcoin::Network::Bitcoin, &[seed]).unwrap();
+    let xpub = Xpub::new(bip32::Xpub::from_priv(&secp, &xpriv));
+    (xpriv, xpub)
+}
+
+/// A signatory set with `xpub` as its only signatory.
+fn sigset(index: u32, xpub: &Xpub) -> SignatorySet {
+    let mut sigset = SignatorySet::default();
+    sigset.index = index;
+    sigset.signatories.push(Signatory {
+        pubkey: xpub.derive_pubkey(index).unwrap().into(),
+        voting_power: 100,
+    });
+    sigset.possible_vp = 100;
+    sigset.present_vp = 100;
+    sigset
+}
+
+fn setup(store: &mut dyn Storage, sigset: SignatorySet) {
+    BITCOIN_CONFIG
+        .save(store, &BitcoinConfig::default())
+        .unwrap();
+    CHECKPOINT_CONFIG
+        .save(store, &CheckpointConfig::default())
+        .unwrap();
+    FEE_POOL.save(store, &0).unwrap();
+    BUILDING_INDEX.save(store, &0).unwrap();
+    CHECKPOINTS
+        .push_back(store, &Checkpoint::new(sigset).unwrap())
+        .unwrap();
+}
+
+fn expired_deposit(txid: u8, vout: u32, dest: Dest) -> ExpiredDeposit {
+    ExpiredDeposit {
+        txid: Txid::from_slice(&[txid; 32]).unwrap().to_string(),
+        vout,
+        dest,
+        value: 100_000,
+        sigset_index: 0,
+        btc_height: 0,
+        commitment_version: 0,
+    }
+}
+
+#[test]
+fn expired_deposit_fee_share() {
+    let mut config = BitcoinConfig::default();
+    assert_eq!(config.expired_deposit_fee_share(100_000), 0);
+    config.expired_deposit_fee_share = (1, 10);
+    assert_eq!(config.expired_deposit_fee_share(100_000), 10_000);
+    // the share can't exceed the deposit
+    config.expired_deposit_fee_share = (3, 2);
+    assert_eq!(config.expired_deposit_fee_share(100_000), 100_000);
+}
+
+#[test]
+fn claimed_expired_deposit_credits_fee_pool() -> ContractResult<()> {
+    let mut deps = mock_dependencies();
+    let deps_mut = deps.as_mut();
+    let (store, api) = (deps_mut.storage, deps_mut.api);
+    let (xpriv, xpub) = signer(0);
+    let sigset = sigset(0, &xpub);
+    setup(store, sigset.clone());
+    let threshold = CheckpointConfig::default().sigset_threshold;
+
+    let refund_script = ScriptBuf::new_p2wpkh(&bitcoin::WPubkeyHash::from_slice(&[1; 20]).unwrap());
+    let mut recovery_txs = RecoveryTxs::default();
+    let input = |fee_pool_share, refund_script| RecoveryTxInput {
+        expired_outpoint: OutPoint::new(Txid::from_slice(&[2; 32]).unwrap(), 0),
+        value: 100_000,
+        old_sigset: &sigset,
+        new_sigset: &sigset,
+        threshold,
+        fee_rate: 1,
+        dest: Dest::Address(Addr::unchecked("depositor")),
+        refund_script: Some(refund_script),
+        lock_time: 10,
+        commitment_version: 0,
+        fee_pool_share,
+    };
+
+    // the share can't be refunded to the reserve script itself
+    let reserve_script = sigset.output_script(&[0u8], threshold)?;
+    assert!(recovery_txs
+        .create_recovery_tx(store, input(10_000, reserve_script.clone()))
+        .is_err());
+
+    recovery_txs.create_recovery_tx(store, input(10_000, refund_script.clone()))?;
+    assert!(recovery_txs.signed(store)?.is_empty());
+
+    let secp = Secp256k1::signing_only();
+    let to_sign = recovery_txs.to_sign(store, &xpub)?;
+    let txids = recovery_txs.sign(api, store, &xpub, sign(&secp, &xpriv, &to_sign)?)?;
+    assert_eq!(txids.len(), 1);
+
+    let signed = recovery_txs.signed(store)?.remove(0);
+    assert_eq!(signed.tx.output.len(), 2);
+    assert_eq!(signed.tx.output[0].script_pubkey, refund_script);
+    assert_eq!(signed.tx.output[1].script_pubkey, reserve_script);
+    // both outputs pay part of the miner fee
+    assert!(signed.tx.output[0].value.to_sat() < 90_000);
+    assert!(signed.tx.output[1].value.to_sat() < 10_000);
+
+    let txid = txids[0].to_string();
+    let reserve_output = SIGNED_RESERVE_OUTPUTS.load(store, (&txid, 1))?;
+    assert_eq!(reserve_output.amount, signed.tx.output[1].value.to_sat());
+    assert!(reserve_output.credit_fee_pool);
+
+    // outputs are only attached once the recovery transaction can be
+    // broadcast
+    let mut btc = Bitcoin::default();
+    btc.attach_signed_reserve_outputs(store, 9, &mut u32::MAX)?;
+    assert!(SIGNED_RESERVE_OUTPUTS.has(store, (&txid, 1)));
+    assert_eq!(FEE_POOL.load(store)?, 0);
+
+    // outputs beyond the end block budget wait for the next checkpoint
+    btc.attach_signed_reserve_outputs(store, 10, &mut 0)?;
+    assert!(SIGNED_RESERVE_OUTPUTS.has(store, (&txid, 1)));
+
+    let mut budget = 2;
+    btc.attach_signed_reserve_outputs(store, 10, &mut budget)?;
+    assert_eq!(budget, 1);
+    assert!(!SIGNED_RESERVE_OUTPUTS.has(store, (&txid, 1)));
+    assert_eq!(
+        RESERVE_UTXOS.load(store, (&txid, 1))?.amount,
+        reserve_output.amount
+    );
+    assert_eq!(
+        FEE_POOL.load(store)?,
+        (reserve_output.amount * BitcoinConfig::default().units_per_sat) as i64
+    );
+    assert_eq!(RECOVERY_TXS.len(store)?, 1);
+
+    Ok(())
+}
+
+#[test]
+fn recovery_without_fee_share_has_no_reserve_output() -> ContractResult<()> {
+    let mut deps = mock_dependencies();
+    let deps_mut = deps.as_mut();
+    let (store, api) = (deps_mut.storage, deps_mut.api);
+    let (xpriv, xpub) = signer(0);
+    let sigset = sigset(0, &xpub);
+    setup(store, sigset.clone());
+
+    let mut recovery_txs = RecoveryTxs::default();
+    recovery_txs.create_recovery_tx(
+        store,
+        RecoveryTxInput {
+            expired_outpoint: OutPoint::default(),
+            value: 100_000,
+            old_sigset: &sigset,
+            new_sigset: &sigset,
+            threshold: CheckpointConfig::default().sigset_threshold,
+            fee_rate: 1,
+            dest: Dest::Address(Addr::unchecked("depositor")),
+            refund_script: None,
+            lock_time: 0,
+            commitment_version: 0,
+            fee_pool_share: 0,
+        },
+    )?;
+    let secp = Secp256k1::signing_only();
+    let to_sign = recovery_txs.to_sign(store, &xpub)?;
+    recovery_txs.sign(api, store, &xpub, sign(&secp, &xpriv, &to_sign)?)?;
+
+    assert_eq!(recovery_txs.signed(store)?[0].tx.output.len(), 1);
+    assert!(SIGNED_RESERVE_OUTPUTS.is_empty(store));
+
+    Ok(())
+}
+
+#[test]
+fn timelocked_recovery_tx() -> ContractResult<()> {
+    let mut deps = mock_dependencies();
+    let deps_mut = deps.as_mut();
+    let (store, api) = (deps_mut.storage, deps_mut.api);
+    let (xpriv, xpub) = signer(0);
+    let sigset = sigset(0, &xpub);
+    setup(store, sigset.clone());
+
+    let config = BitcoinConfig::default();
+    let lock_time = recovery_lock_time(100, &config);
+    assert_eq!(lock_time, 100 + config.deposit_age_limit_blocks());
+    assert_eq!(recovery_lock_time(u32::MAX, &config), u32::MAX);
+
+    let mut recovery_txs = RecoveryTxs::default();
+    for (txid, lock_time) in [(1, 0), (2, lock_time)] {
+        recovery_txs.create_recovery_tx(
+            store,
+            RecoveryTxInput {
+                expired_outpoint: OutPoint::new(Txid::from_slice(&[txid; 32]).unwrap(), 0),
+                value: 100_000,
+                old_sigset: &sigset,
+                new_sigset: &sigset,
+                threshold: CheckpointConfig::default().sigset_threshold,
+                fee_rate: 1,
+                dest: Dest::Address(Addr::unchecked("depositor")),
+                refund_script: None,
+                lock_time,
+                commitment_version: 0,
+                fee_pool_share: 0,
+            },
+        )?;
+    }
+    let secp = Secp256k1::signing_only();
+    let to_sign = recovery_txs.to_sign(store, &xpub)?;
+    recovery_txs.sign(api, store, &xpub, sign(&secp, &xpriv, &to_sign)?)?;
+    let signed = recovery_txs.signed(store)?;
+
+    // inputs stay final without a lock time
+    assert_eq!(signed[0].earliest_broadcast_height, 0);
+    assert_eq!(signed[0].tx.lock_time.to_consensus_u32(), 0);
+    assert_eq!(signed[0].tx.input[0].sequence, Sequence::MAX);
+
+    // and enable it otherwise, or it would not be enforced
+    assert_eq!(signed[1].earliest_broadcast_height, lock_time);
+    assert_eq!(signed[1].tx.lock_time.to_consensus_u32(), lock_time);
+    assert_eq!(
+        signed[1].tx.input[0].sequence,
+        Sequence::ENABLE_LOCKTIME_NO_RBF
+    );
+
+    Ok(())
+}
+
+#[test]
+fn recovery_groups_sign_independently() -> ContractResult<()> {
+    let mut deps = mock_dependencies();
+    let deps_mut = deps.as_mut();
+    let (store, api) = (deps_mut.storage, deps_mut.api);
+    let (xpriv, xpub) = signer(0);
+    let old_sigset = sigset(0, &xpub);
+    let new_sigset = sigset(1, &xpub);
+    setup(store, old_sigset.clone());
+
+    // one recovery transaction spending from each signatory set
+    let mut recovery_txs = RecoveryTxs::default();
+    for (txid, old_sigset) in [(1, &old_sigset), (2, &new_sigset)] {
+        recovery_txs.create_recovery_tx(
+            store,
+            RecoveryTxInput {
+                expired_outpoint: OutPoint::new(Txid::from_slice(&[txid; 32]).unwrap(), 0),
+                value: 100_000,
+                old_sigset,
+                new_sigset: &new_sigset,
+                threshold: CheckpointConfig::default().sigset_threshold,
+                fee_rate: 1,
+                dest: Dest::Address(Addr::unchecked("depositor")),
+                refund_script: None,
+                lock_time: 0,
+                commitment_version: 0,
+                fee_pool_share: 0,
+            },
+        )?;
+    }
+
+    let groups = recovery_txs.groups(store, Some(&xpub))?;
+    assert_eq!(groups.len(), 2);
+    for (index, group) in groups.iter().enumerate() {
+        assert_eq!(group.sigset_index, index as u32);
+        assert_eq!(group.tx_indexes, vec![index as u32]);
+        assert_eq!((group.inputs, group.signed_inputs), (1, 0));
+        assert_eq!(group.owed, Some(1));
+    }
+    assert!(recovery_txs
+        .groups(store, None)?
+        .iter()
+        .all(|group| group.owed.is_none()));
+
+    let secp = Secp256k1::signing_only();
+    let first = recovery_txs.to_sign_group(store, &xpub, 0)?;
+    let second = recovery_txs.to_sign_group(store, &xpub, 1)?;
+    assert_eq!((first.len(), second.len()), (1, 1));
+    assert_ne!(first, second);
+
+    // a bad submission for one group does not block the other
+    let wrong = sign(&secp, &xpriv, &second)?;
+    assert!(recovery_txs
+        .sign_group(api, store, &xpub, 0, wrong.clone())
+        .is_err());
+    let txids = recovery_txs.sign_group(api, store, &xpub, 1, wrong)?;
+    assert_eq!(txids.len(), 1);
+
+    let groups = recovery_txs.groups(store, Some(&xpub))?;
+    assert_eq!((groups[0].signed_inputs, groups[0].owed), (0, Some(1)));
+    assert_eq!((groups[1].signed_inputs, groups[1].owed), (1, Some(0)));
+    assert!(recovery_txs.to_sign_group(store, &xpub, 1)?.is_empty());
+    assert_eq!(recovery_txs.signed(store)?.len(), 1);
+
+    let txids = recovery_txs.sign_group(api, store, &xpub, 0, sign(&secp, &xpriv, &first)?)?;
+    assert_eq!(txids.len(), 1);
+    assert_eq!(recovery_txs.signed(store)?.len(), 2);
+
+    Ok(())
+}
+
+#[test]
+fn sweep_credits_dust_depositors() -> ContractResult<()> {
+    let mut deps = mock_dependencies();
+    let deps_mut = deps.as_mut();
+    let (store, api) = (deps_mut.storage, deps_mut.api);
+    let (xpriv, xpub) = signer(0);
+    let sigset = sigset(0, &xpub);
+    setup(store, sigset.clone());
+    let units_per_sat = BitcoinConfig::default().units_per_sat;
+
+    let deposits = [("a", 3, 3_000), ("b", 4, 1_000)];
+    let args = |max_fee_bps| SweepTxInput {
+        inputs: deposits
+            .iter()
+            .map(|(dest, txid, value)| SweepInput {
+                outpoint: OutPoint::new(Txid::from_slice(&[*txid; 32]).unwrap(), 0),
+                value: *value,
+                sigset: sigset.clone(),
+                dest: Dest::Address(Addr::unchecked(*dest)),
+                commitment_version: 0,
+            })
+            .collect(),
+        new_sigset: &sigset,
+        threshold: CheckpointConfig::default().sigset_threshold,
+        fee_rate: 1,
+        max_fee_bps,
+        dest: Dest::Address(Addr::unchecked("bridge")),
+        lock_time: 0,
+        denom: "nbtc".to_string(),
+        units_per_sat,
+    };
+
+    // sweeps wait for fee rates the dust can pay
+    let mut recovery_txs = RecoveryTxs::default();
+    assert!(!recovery_txs.create_sweep_tx(store, args(1))?);
+    assert!(RECOVERY_TXS.is_empty(store)?);
+    assert!(recovery_txs.create_sweep_tx(store, args(5_000))?);
+
+    let secp = Secp256k1::signing_only();
+    let to_sign = recovery_txs.to_sign(store, &xpub)?;
+    assert_eq!(to_sign.len(), 2);
+    let txids = recovery_txs.sign(api, store, &xpub, sign(&secp, &xpriv, &to_sign)?)?;
+    let txid = txids[0].to_string();
+
+    // the swept output is registered and credits each depositor its share
+    let output = SIGNED_RESERVE_OUTPUTS.load(store, (&txid, 0))?;
+    assert!(!output.credit_fee_pool);
+    assert!(output.amount < 4_000);
+    let credited: Vec<_> = output
+        .credits
+        .iter()
+        .map(|transfer| (transfer.dest.clone(), transfer.coin.amount.u128()))
+        .collect();
+    let share = |value: u64| (value * output.amount / 4_000 * units_per_sat) as u128;
+    assert_eq!(
+        credited,
+        vec![
+            (Dest::Address(Addr::unchecked("a")), share(3_000)),
+            (Dest::Address(Addr::unchecked("b")), share(1_000)),
+        ]
+    );
+
+    // and the credits are minted with the checkpoint spending the output
+    let mut btc = Bitcoin::default();
+    btc.attach_signed_reserve_outputs(store, 0, &mut u32::MAX)?;
+    assert!(RESERVE_UTXOS.has(store, (&txid, 0)));
+    assert_eq!(CHECKPOINTS.get(store, 0)?.unwrap().pending, output.credits);
+    assert_eq!(FEE_POOL.load(store)?, 0);
+
+    Ok(())
+}
+
+#[test]
+fn raw_recovery_tx_hex() -> ContractResult<()> {
+    let mut deps = mock_dependencies();
+    let deps_mut = deps.as_mut();
+    let (store, api) = (deps_mut.storage, deps_mut.api);
+    let (xpriv, xpub) = signer(0);
+    let sigset = sigset(0, &xpub);
+    setup(store, sigset.clone());
+
+    let mut recovery_txs = RecoveryTxs::default();
+    recovery_txs.create_recovery_tx(
+        store,
+        RecoveryTxInput {
+            expired_outpoint: OutPoint::default(),
+            value: 100_000,
+            old_sigset: &sigset,
+            new_sigset: &sigset,
+            threshold: CheckpointConfig::default().sigset_threshold,
+            fee_rate: 1,
+            dest: Dest::Address(Addr::unchecked("depositor")),
+            refund_script: None,
+            lock_time: 0,
+            commitment_version: 0,
+            fee_pool_share: 0,
+        },
+    )?;
+    // unsigned transactions can't be broadcast
+    assert!(query_raw_tx_hex(store, RawTxKind::RecoveryTx, 0).is_err());
+
+    let secp = Secp256k1::signing_only();
+    let to_sign = recovery_txs.to_sign(store, &xpub)?;
+    recovery_txs.sign(api, store, &xpub, sign(&secp, &xpriv, &to_sign)?)?;
+
+    let signed_tx = recovery_txs.signed(store)?.remove(0).tx.into_inner();
+    let raw = query_raw_tx_hex(store, RawTxKind::RecoveryTx, 0)?;
+    let tx: Transaction = deserialize(&Vec::from_hex(&raw.hex).unwrap()).unwrap();
+    assert_eq!(tx, signed_tx);
+    assert_eq!(raw.txid, signed_tx.txid().to_string());
+    assert!(query_raw_tx_hex(store, RawTxKind::RecoveryTx, 1).is_err());
+    assert!(query_raw_tx_hex(store, RawTxKind::DisbursalTx, 0).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn signing_duties() -> ContractResult<()> {
+    let mut deps = mock_dependencies();
+    let deps_mut = deps.as_mut();
+    let (store, api) = (deps_mut.storage, deps_mut.api);
+    let (xpriv, xpub) = signer(0);
+    let sigset = sigset(0, &xpub);
+    setup(store, sigset.clone());
+    let threshold = CheckpointConfig::default().sigset_threshold;
+
+    // a checkpoint being signed with one input
+    let mut signing = CHECKPOINTS.get(store, 0)?.unwrap();
+    let checkpoint_tx = &mut signing.batches[BatchType::Checkpoint][0];
+    checkpoint_tx.input.push(Input::new(
+        OutPoint::default(),
+        &sigset,
+        &[0u8],
+        100_000,
+        threshold,
+    )?);
+    checkpoint_tx.populate_sig_messages()?;
+    signing.status = CheckpointStatus::Signing;
+    CHECKPOINTS.set(store, 0, &signing)?;
+    CHECKPOINTS.push_back(store, &Checkpoint::new(sigset.clone())?)?;
+    BUILDING_INDEX.save(store, &1)?;
+
+    let mut recovery_txs = RecoveryTxs::default();
+    recovery_txs.create_recovery_tx(
+        store,
+        RecoveryTxInput {
+            expired_outpoint: OutPoint::default(),
+            value: 100_000,
+            old_sigset: &sigset,
+            new_sigset: &sigset,
+            threshold,
+            fee_rate: 1,
+            dest: Dest::Address(Addr::unchecked("depositor")),
+            refund_script: None,
+            lock_time: 0,
+            commitment_version: 0,
+            fee_pool_share: 0,
+        },
+    )?;
+
+    // the checkpoint, which has an age, comes before the recovery transaction
+    let env = set_time(sigset.create_time + 1000);
+    let duties = query_signing_duties(&env, store, WrappedBinary(xpub))?;
+    assert_eq!(duties.len(), 2);
+    assert_eq!(
+        duties[0].source,
+        SigningDutySource::Checkpoint {
+            index: 0,
+            batch_index: 0,
+            tx_index: 0,
+        }
+    );
+    assert_eq!(duties[0].age, Some(1000));
+    assert_eq!(duties[0].sighashes.len(), 1);
+    assert!(duties[0].remaining_power > 0);
+    assert_eq!(
+        duties[1].source,
+        SigningDutySource::Recovery { tx_index: 0 }
+    );
+    assert_eq!(duties[1].age, None);
+
+    // signed transactions are no longer a duty
+    let secp = Secp256k1::signing_only();
+    let to_sign = recovery_txs.to_sign(store, &xpub)?;
+    recovery_txs.sign(api, store, &xpub, sign(&secp, &xpriv, &to_sign)?)?;
+    let duties = query_signing_duties(&env, store, WrappedBinary(xpub))?;
+    assert_eq!(duties.len(), 1);
+    assert!(matches!(
+        duties[0].source,
+        SigningDutySource::Checkpoint { .. }
+    ));
+
+    // signers outside the signatory set owe nothing
+    let (_, other) = signer(1);
+    assert!(query_signing_duties(&env, store, WrappedBinary(other))?.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn expired_deposits_by_dest() -> ContractResult<()> {
+    let mut deps = mock_dependencies();
+    let store = deps.as_mut().storage;
+    let alice = Dest::Address(Addr::unchecked("alice"));
+    let bob = Dest::Address(Addr::unchecked("bob"));
+
+    let deposits = vec![
+        expired_deposit(1, 0, alice.clone()),
+        expired_deposit(1, 1, bob.clone()),
+        expired_deposit(2, 0, alice.clone()),
+        expired_deposit(3, 2, alice.clone()),
+    ];
+    for deposit in &deposits {
+        EXPIRED_DEPOSITS.save(store, (&deposit.txid, deposit.vout), deposit)?;
+    }
+    // deposits held before the index existed are indexed by the migration
+    STATE_VERSION.save(store, &1)?;
+    migrate_state(store)?;
+
+    let mut alice_deposits = vec![];
+    let mut start_after = None;
+    loop {
+        let page = query_expired_deposits(store, alice.clone(), start_after, Some(2))?;
+        if page.is_empty() {
+            break;
+        }
+        start_after = page
+            .last()
+            .map(|deposit| (deposit.txid.clone(), deposit.vout));
+        alice_deposits.extend(page);
+    }
+    let mut expected: Vec<_> = deposits
+        .iter()
+        .filter(|deposit| deposit.dest == alice)
+        .cloned()
+        .collect();
+    expected.sort_by(|a, b| (&a.txid, a.vout).cmp(&(&b.txid, b.vout)));
+    assert_eq!(alice_deposits, expected);
+
+    assert_eq!(
+        query_expired_deposits(store, bob.clone(), None, None)?,
+        vec![deposits[1].clone()]
+    );
+    EXPIRED_DEPOSITS_BY_DEST.remove(store, (&bob.commitment_bytes()?, &deposits[1].txid, 1));
+    assert!(query_expired_deposits(store, bob, None, None)?.is_empty());
+
+    Ok(())
+}
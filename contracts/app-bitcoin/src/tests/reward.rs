@@ -0,0 +1,61 @@
+use bitcoin::{bip32, secp256k1::Secp256k1};
+use common_bitcoin::{error::ContractResult, xpub::Xpub};
+use cosmwasm_std::{testing::mock_dependencies, Addr};
+
+use crate::{
+    interface::BitcoinConfig,
+    reward::{accrue_fee_pool_surplus, distribute_rewards},
+    signatory::{Signatory, SignatorySet},
+    state::{FEE_POOL, REWARD_BALANCES, REWARD_POOL, SIG_KEYS, VALIDATORS},
+};
+
+#[test]
+fn fee_pool_surplus_rewards_relayers_and_signatories() -> ContractResult<()> {
+    let mut deps = mock_dependencies();
+    let store = deps.as_mut().storage;
+    let config = BitcoinConfig {
+        fee_pool_target_balance: 1_000,
+        units_per_sat: 10,
+        relayer_reward_bps: 1_000,
+        ..BitcoinConfig::default()
+    };
+
+    FEE_POOL.save(store, &5_000)?;
+    assert_eq!(accrue_fee_pool_surplus(store, &config)?, 0);
+    FEE_POOL.save(store, &110_000)?;
+    assert_eq!(accrue_fee_pool_surplus(store, &config)?, 100_000);
+    assert_eq!(FEE_POOL.load(store)?, 10_000);
+    assert_eq!(REWARD_POOL.load(store)?, 100_000);
+
+    let secp = Secp256k1::new();
+    let mut sigset = SignatorySet::default();
+    for (i, voting_power) in [(0u8, 30u64), (1, 10)] {
+        let xpriv = bip32::Xpriv::new_master(bitcoin::Network::Bitcoin, &[i])?;
+        let xpub = Xpub::new(bip32::Xpub::from_priv(&secp, &xpriv));
+        sigset.signatories.push(Signatory {
+            voting_power,
+            pubkey: xpub.derive_pubkey(sigset.index)?.into(),
+        });
+        sigset.present_vp += voting_power;
+        SIG_KEYS.save(store, &[i; 32], &xpub)?;
+        VALIDATORS.save(store, &[i; 32], &(voting_power, format!("signer{}", i)))?;
+    }
+
+    let relayers = [Addr::unchecked("relayer1"), Addr::unchecked("relayer2")];
+    assert_eq!(
+        distribute_rewards(store, &config, &sigset, &relayers)?,
+        100_000
+    );
+    let balance = |store: &dyn cosmwasm_std::Storage, addr: &str| {
+        REWARD_BALANCES
+            .may_load(store, &Addr::unchecked(addr))
+            .unwrap()
+            .unwrap_or_default()
+    };
+    assert_eq!(balance(store, "relayer1"), 5_000);
+    assert_eq!(balance(store, "relayer2"), 5_000);
+    assert_eq!(balance(store, "signer0"), 67_500);
+    assert_eq!(balance(store, "signer1"), 22_500);
+    assert_eq!(REWARD_POOL.load(store)?, 0);
+    Ok(())
+}
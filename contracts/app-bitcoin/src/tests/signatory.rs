@@ -1,10 +1,20 @@
-use bitcoin::{hashes::hex::FromHex, Script};
+use bitcoin::{
+    opcodes::all::{OP_CSV, OP_DROP},
+    script::Builder,
+    ScriptBuf,
+};
 
 use crate::{
-    signatory::{Signatory, SignatorySet},
-    threshold_sig::Pubkey,
+    descriptor::{descriptor_checksum, raw_descriptor},
+    interface::{ScheduledValidatorSet, ValidatorInfo},
+    signatory::{instance_commitment, FoundationPolicy, ScriptTemplate, Signatory, SignatorySet},
+    state::{
+        apply_scheduled_validator_set, get_validators, SCHEDULED_VALIDATOR_SET, SIGNERS, VALIDATORS,
+    },
+    threshold_sig::{Pubkey, SigScheme, ThresholdSig},
 };
 use common_bitcoin::error::ContractResult;
+use cosmwasm_std::{from_json, testing::mock_dependencies, Addr};
 
 fn mock_signatory_set() -> SignatorySet {
     let pk = |bytes| Pubkey::new(bytes).unwrap().into();
@@ -37,6 +47,9 @@ fn mock_signatory_set() -> SignatorySet {
             },
         ],
         foundation_signatories: vec![],
+        foundation_policy: None,
+        instance_commitment: None,
+        script_template: ScriptTemplate::Raw,
     };
     sigsets
 }
@@ -89,7 +102,7 @@ fn test_output_script() {
 
 #[test]
 fn from_script() {
-    let script = bitcoin::Script::from_hex("0100876321028891f36b691a40036f2b3ecb17c13780a932503ef2c39f3faed9b95bf71ea27fac630339e0116700687c2102f6fee7ad7dc87d0a636ae1584273c849bf540f4c1780434a0430888b0c5b151cac63033c910e93687c2102d207371a1e9a588e447d91dc12a8f3479f1f9ff8da748aae04bb5d07f0737790ac630371730893687c2103713e9bb6025fa9dc3c26507762cffd2a9524ff48f1d84c6753caa581347e5e10ac63031def0793687c2103d8fc0412a866bfb14d3fbc9e1b714ca31141d0f7e211d0fa634d53dda9789ecaac6303d1f00693687c2102c7961e04206af92f4b4cf3f19b43722f301e4915a49f5ca2908d9af5ce343830ac6303496f0693687c2103205472bb87799cb9140b5d471cc045b65821a4e75591026a8411ee3ac3e27027ac6303fe500693687c2102c923df10e8141072504b1f9513ee6796dc4d748d774ce9396942b63d42d3d575ac6303ed1f0593687c21031e8124547a5f28e04652d61fab1053ba8af41b682ccecdf5fa58595add7c7d9eac6303d4a00493687c21038060738940b9b3513851aa45df9f8b9d8e3304ef5abc5f8c1928bf4f1c8601adac630347210493687c21022e1efe78c688bceb7a36bf8af0e905da65e1942b84afe31716a356a91c0d9c05ac6303c5620393687c21020598956ed409e190b763bed8ed1ec3a18138c582c761eb8a4cf60861bfb44f13ac6303b3550393687c2102c8b2e54cafced96b1438e9ee6ebddc27c4aca68f14b2199eb8b8da111b584c2cac63036c330393687c2102d8a4c0accefa93b6a8d390a81dbffa4d05cd0a844371b2bed0ba1b1b65e14300ac6303521d0393687c2102460ccc0db97b1027e4fe2ab178f015a786b6b8f016b580f495dde3230f34984cac630304060393687c2102def64dfc155e17988ea6dee5a5659e2ec0a19fce54af90ca84dcd4df53b1a222ac630341d20293687c21030c9057c92c19f749c891037379766c0642d03bd1c50e3b262fc7d954c232f4d8ac630356c30293687c21027e1ebe3dd4fbbf250a8161a8a7af19815d5c07363e220f28f81c535c3950c7cbac6303d3ab0293687c210235e1d72961cb475971e2bc437ac21f9be13c83f1aa039e64f406aae87e2b4816ac6303bdaa0293687c210295d565c8ae94d46d439b4591dcd146742f918893292c23c49d000c4023bad4ffac630308aa029368030fb34aa0010075676a68").unwrap();
+    let script = ScriptBuf::from_hex("0100876321028891f36b691a40036f2b3ecb17c13780a932503ef2c39f3faed9b95bf71ea27fac630339e0116700687c2102f6fee7ad7dc87d0a636ae1584273c849bf540f4c1780434a0430888b0c5b151cac63033c910e93687c2102d207371a1e9a588e447d91dc12a8f3479f1f9ff8da748aae04bb5d07f0737790ac630371730893687c2103713e9bb6025fa9dc3c26507762cffd2a9524ff48f1d84c6753caa581347e5e10ac63031def0793687c2103d8fc0412a866bfb14d3fbc9e1b714ca31141d0f7e211d0fa634d53dda9789ecaac6303d1f00693687c2102c7961e04206af92f4b4cf3f19b43722f301e4915a49f5ca2908d9af5ce343830ac6303496f0693687c2103205472bb87799cb9140b5d471cc045b65821a4e75591026a8411ee3ac3e27027ac6303fe500693687c2102c923df10e8141072504b1f9513ee6796dc4d748d774ce9396942b63d42d3d575ac6303ed1f0593687c21031e8124547a5f28e04652d61fab1053ba8af41b682ccecdf5fa58595add7c7d9eac6303d4a00493687c21038060738940b9b3513851aa45df9f8b9d8e3304ef5abc5f8c1928bf4f1c8601adac630347210493687c21022e1efe78c688bceb7a36bf8af0e905da65e1942b84afe31716a356a91c0d9c05ac6303c5620393687c21020598956ed409e190b763bed8ed1ec3a18138c582c761eb8a4cf60861bfb44f13ac6303b3550393687c2102c8b2e54cafced96b1438e9ee6ebddc27c4aca68f14b2199eb8b8da111b584c2cac63036c330393687c2102d8a4c0accefa93b6a8d390a81dbffa4d05cd0a844371b2bed0ba1b1b65e14300ac6303521d0393687c2102460ccc0db97b1027e4fe2ab178f015a786b6b8f016b580f495dde3230f34984cac630304060393687c2102def64dfc155e17988ea6dee5a5659e2ec0a19fce54af90ca84dcd4df53b1a222ac630341d20293687c21030c9057c92c19f749c891037379766c0642d03bd1c50e3b262fc7d954c232f4d8ac630356c30293687c21027e1ebe3dd4fbbf250a8161a8a7af19815d5c07363e220f28f81c535c3950c7cbac6303d3ab0293687c210235e1d72961cb475971e2bc437ac21f9be13c83f1aa039e64f406aae87e2b4816ac6303bdaa0293687c210295d565c8ae94d46d439b4591dcd146742f918893292c23c49d000c4023bad4ffac630308aa029368030fb34aa0010075676a68").unwrap();
 
     let (sigset, commitment) = SignatorySet::from_script(&script, (2, 3)).unwrap();
 
@@ -248,8 +261,171 @@ fn from_script() {
                     ])
                 }
             ],
-            foundation_signatories: vec![]
+            foundation_signatories: vec![],
+            foundation_policy: None,
+            instance_commitment: None,
+            script_template: ScriptTemplate::Raw,
         }
     );
     assert_eq!(commitment, vec![0]);
 }
+
+#[test]
+fn instance_commitment_script() {
+    let dest = [7u8; 32];
+    let plain = mock_signatory_set();
+    let mut sigset = mock_signatory_set();
+    sigset.instance_commitment = Some(instance_commitment(&Addr::unchecked("bridge"), 25));
+
+    let script = sigset.redeem_script(&dest, (2, 3)).unwrap();
+    assert_ne!(script, plain.redeem_script(&dest, (2, 3)).unwrap());
+    let mut other = sigset.clone();
+    other.instance_commitment = Some(instance_commitment(&Addr::unchecked("other_bridge"), 25));
+    assert_ne!(script, other.redeem_script(&dest, (2, 3)).unwrap());
+
+    let (parsed, commitment) = SignatorySet::from_script(&script, (2, 3)).unwrap();
+    assert_eq!(commitment, dest.to_vec());
+    assert_eq!(parsed.instance_commitment, sigset.instance_commitment);
+}
+
+#[test]
+fn bucketed_script_template() {
+    let dest = [7u8; 32];
+    let raw = mock_signatory_set();
+    let mut sigset = mock_signatory_set();
+    sigset.script_template = ScriptTemplate::Bucketed { precision: 255 };
+
+    let raw_script = raw.redeem_script(&dest, (2, 3)).unwrap();
+    let script = sigset.redeem_script(&dest, (2, 3)).unwrap();
+    assert!(script.len() < raw_script.len());
+    assert!(sigset.est_witness_vsize() < raw.est_witness_vsize());
+
+    // weights of 63, 85 and 106 units against a threshold of 170 units
+    let (parsed, commitment) = SignatorySet::from_script(&script, (2, 3)).unwrap();
+    assert_eq!(commitment, dest.to_vec());
+    let weights: Vec<_> = parsed.iter().map(|s| s.voting_power).collect();
+    assert_eq!(weights, vec![63, 85, 106]);
+    assert_eq!(parsed.signature_threshold((2, 3)), 170);
+    assert_eq!(parsed.redeem_script(&dest, (2, 3)).unwrap(), script);
+
+    // the two largest signatories exceed the threshold with their weights, so
+    // their voting power must exceed the signing threshold too
+    assert!(sigset.signing_threshold((2, 3)) > raw.signing_threshold((2, 3)));
+    assert!(4000 + 5000 > sigset.signing_threshold((2, 3)));
+    assert!(3000 + 5000 < sigset.signing_threshold((2, 3)));
+
+    assert!(ScriptTemplate::Bucketed { precision: 50 }
+        .validate()
+        .is_err());
+    assert!(ScriptTemplate::Bucketed { precision: 255 }
+        .validate()
+        .is_ok());
+}
+
+#[test]
+fn foundation_policy_script() {
+    let mut sigset = mock_signatory_set();
+    let signatories = sigset.signatories.clone();
+    sigset.foundation_signatories = signatories
+        .into_iter()
+        .map(|signatory| Signatory {
+            voting_power: 1,
+            ..signatory
+        })
+        .collect();
+    let legacy_script = sigset.redeem_script(&[0], (2, 3)).unwrap();
+
+    let policy = FoundationPolicy {
+        threshold: (1, 3),
+        inactivity_timelock: 60 * 60 * 24,
+    };
+    policy.validate().unwrap();
+    assert_eq!(policy.required_signatures(3), 1);
+    sigset.foundation_policy = Some(policy.clone());
+    let script = sigset.redeem_script(&[0], (2, 3)).unwrap();
+
+    assert_ne!(script, legacy_script);
+    let sequence = policy.sequence().unwrap().unwrap();
+    let csv = Builder::new()
+        .push_int(sequence.to_consensus_u32() as i64)
+        .push_opcode(OP_CSV)
+        .push_opcode(OP_DROP)
+        .into_script();
+    assert!(script
+        .as_bytes()
+        .windows(csv.len())
+        .any(|window| window == csv.as_bytes()));
+
+    assert!(FoundationPolicy {
+        threshold: (0, 3),
+        inactivity_timelock: 0,
+    }
+    .validate()
+    .is_err());
+    assert!(FoundationPolicy {
+        threshold: (2, 3),
+        inactivity_timelock: u64::MAX,
+    }
+    .validate()
+    .is_err());
+}
+
+#[test]
+fn scheduled_validator_set() -> ContractResult<()> {
+    let mut deps = mock_dependencies();
+    let store = deps.as_mut().storage;
+
+    SIGNERS.save(store, "val1", &[1; 32])?;
+    VALIDATORS.save(store, &[1; 32], &(10, "val1".to_string()))?;
+    SCHEDULED_VALIDATOR_SET.save(
+        store,
+        &ScheduledValidatorSet {
+            epoch: 2,
+            validators: vec![ValidatorInfo {
+                addr: "val2".to_string(),
+                consensus_key: [2; 32],
+                voting_power: 20,
+            }],
+        },
+    )?;
+
+    assert!(!apply_scheduled_validator_set(store, 1)?);
+    assert_eq!(get_validators(store)?[0].pubkey, [1; 32]);
+
+    assert!(apply_scheduled_validator_set(store, 2)?);
+    let validators = get_validators(store)?;
+    assert_eq!(validators.len(), 1);
+    assert_eq!(validators[0].pubkey, [2; 32]);
+    assert_eq!(validators[0].power, 20);
+    assert!(!SIGNERS.has(store, "val1"));
+    assert!(SCHEDULED_VALIDATOR_SET.may_load(store)?.is_none());
+
+    Ok(())
+}
+
+#[test]
+fn raw_descriptor_checksum() -> ContractResult<()> {
+    // test vector from BIP-380
+    assert_eq!(descriptor_checksum("raw(deadbeef)")?, "89f8spxm");
+    assert!(descriptor_checksum("raw(é)").is_err());
+
+    let script = ScriptBuf::from_hex("deadbeef").unwrap();
+    assert_eq!(raw_descriptor(&script)?, "raw(deadbeef)#89f8spxm");
+    Ok(())
+}
+
+#[test]
+fn threshold_sig_scheme_defaults_to_ecdsa() -> ContractResult<()> {
+    let sigset = mock_signatory_set();
+    let ts = ThresholdSig::from_sigset(&sigset);
+    assert_eq!(ts.scheme, SigScheme::Ecdsa);
+
+    // signing states stored before schemes were recorded
+    let legacy = format!(
+        r#"{{"threshold":0,"signed":0,"message":{:?},"len":0,"sigs":[]}}"#,
+        [0u8; 32]
+    );
+    let ts: ThresholdSig = from_json(legacy.as_bytes())?;
+    assert_eq!(ts.scheme, SigScheme::Ecdsa);
+    Ok(())
+}
@@ -0,0 +1,356 @@
+use cosmwasm_std::{
+    from_json,
+    testing::{mock_dependencies, MockQuerier},
+    to_json_binary, Binary, Empty, Querier, QuerierResult, QuerierWrapper, QueryRequest, Storage,
+    SystemError, SystemResult, Uint128, WasmQuery,
+};
+use ibc_proto::cosmos::{
+    bank::v1beta1::{DenomOwner, QueryDenomOwnersRequest, QueryDenomOwnersResponse},
+    base::{query::v1beta1::PageResponse, v1beta1::Coin},
+};
+use light_client_bitcoin::msg::QueryMsg as LightClientQueryMsg;
+use prost::Message;
+
+use crate::{
+    app_data::hash_pair,
+    checkpoint::{BatchType, Checkpoint, CheckpointStatus, Output},
+    entrypoints::{
+        clock_end_block, query_disbursal_outputs, query_liabilities_proof,
+        query_snapshot_top_balances, query_snapshot_withdrawals,
+    },
+    interface::{BitcoinConfig, CheckpointConfig, DisbursalOutput, SnapshotWithdrawal},
+    signatory::SignatorySet,
+    snapshot::{liability_leaf, step_balance_snapshot},
+    state::{
+        BALANCE_SNAPSHOT, BITCOIN_CONFIG, BUILDING_INDEX, CHECKPOINTS, CHECKPOINT_CONFIG, CONFIG,
+        FEE_POOL, FOUNDATION_KEYS, RECOVERY_SCRIPTS,
+    },
+    tests::helper::{set_time, test_config},
+};
+use common_bitcoin::error::ContractResult;
+
+/// Serves the bank module's denom owners from `holders`, by pages indexed
+/// by position, and the light client's height.
+struct BankQuerier {
+    holders: Vec<(&'static str, u128)>,
+    fail: bool,
+    base: MockQuerier,
+}
+
+impl BankQuerier {
+    fn new(holders: Vec<(&'static str, u128)>, fail: bool) -> Self {
+        let mut base = MockQuerier::<Empty>::new(&[]);
+        base.update_wasm(|query: &WasmQuery| -> QuerierResult {
+            let WasmQuery::Smart { msg, .. } = query else {
+                unreachable!()
+            };
+            match from_json::<LightClientQueryMsg>(msg).unwrap() {
+                LightClientQueryMsg::HeaderHeight {} => SystemResult::Ok(
+                    cosmwasm_std::ContractResult::Ok(to_json_binary(&10u32).unwrap()),
+                ),
+                _ => SystemResult::Err(SystemError::UnsupportedRequest {
+                    kind: "QueryMsg".to_string(),
+                }),
+            }
+        });
+        Self {
+            holders,
+            fail,
+            base,
+        }
+    }
+}
+
+impl Querier for BankQuerier {
+    fn raw_query(&self, bin_request: &[u8]) -> QuerierResult {
+        let request: QueryRequest<Empty> = from_json(bin_request).unwrap();
+        let QueryRequest::Stargate { path, data } = request else {
+            return self.base.raw_query(bin_request);
+        };
+        assert_eq!(path, "/cosmos.bank.v1beta1.Query/DenomOwners");
+        if self.fail {
+            return SystemResult::Err(SystemError::Unknown {});
+        }
+        let request = QueryDenomOwnersRequest::decode(data.as_slice()).unwrap();
+        let page = request.pagination.unwrap();
+        let start = match page.key.as_slice() {
+            [] => 0,
+            key => u64::from_be_bytes(key.try_into().unwrap()) as usize,
+        };
+        let end = (start + page.limit as usize).min(self.holders.len());
+        let next_key = if end < self.holders.len() {
+            (end as u64).to_be_bytes().to_vec()
+        } else {
+            vec![]
+        };
+        let response = QueryDenomOwnersResponse {
+            denom_owners: self.holders[start..end]
+                .iter()
+                .map(|(address, balance)| DenomOwner {
+                    address: address.to_string(),
+                    balance: Some(Coin {
+                        denom: request.denom.clone(),
+                        amount: balance.to_string(),
+                    }),
+                })
+                .collect(),
+            pagination: Some(PageResponse { next_key, total: 0 }),
+        };
+        SystemResult::Ok(cosmwasm_std::ContractResult::Ok(Binary::from(
+            response.encode_to_vec(),
+        )))
+    }
+}
+
+fn setup(store: &mut dyn Storage) -> ContractResult<()> {
+    CONFIG.save(store, &test_config())?;
+    BITCOIN_CONFIG.save(
+        store,
+        &BitcoinConfig {
+            balance_snapshot_page_size: 2,
+            balance_snapshot_interval: 100,
+            balance_snapshot_top_n: 2,
+            ..BitcoinConfig::default()
+        },
+    )?;
+    CHECKPOINT_CONFIG.save(store, &CheckpointConfig::default())?;
+    FEE_POOL.save(store, &0)?;
+    FOUNDATION_KEYS.save(store, &Vec::new())?;
+    BUILDING_INDEX.save(store, &0)?;
+    Ok(())
+}
+
+#[test]
+fn balance_snapshot() -> ContractResult<()> {
+    let mut deps = mock_dependencies();
+    let store = deps.as_mut().storage;
+    setup(store)?;
+    let units = BitcoinConfig::default().units_per_sat as u128;
+    let holders = vec![
+        ("a", 500 * units),
+        ("b", 0),
+        ("c", 300 * units),
+        ("d", 700 * units),
+        ("e", units / 2),
+    ];
+    let querier = BankQuerier::new(holders, false);
+    let querier = QuerierWrapper::new(&querier);
+    let env = set_time(0);
+
+    // the snapshot is built over several blocks
+    let mut steps = 0;
+    let event = loop {
+        steps += 1;
+        assert!(steps < 10);
+        if let Some(event) = step_balance_snapshot(&env, store, &querier)? {
+            break event;
+        }
+    };
+    // clearing, three pages of holders, the last one starting to hash the
+    // liabilities tree, and one more step of hashing
+    assert_eq!(steps, 5);
+    assert_eq!(event.ty, "balance_snapshot");
+    let snapshot = BALANCE_SNAPSHOT.load(store)?;
+    assert_eq!(snapshot.holders, 4);
+    assert_eq!(snapshot.total, Uint128::new(1500 * units + units / 2));
+    assert_eq!(snapshot.completed_at, Some(0));
+
+    // the top index keeps the largest balances
+    assert_eq!(
+        query_snapshot_top_balances(store, None)?,
+        vec![
+            ("d".to_string(), Uint128::new(700 * units)),
+            ("a".to_string(), Uint128::new(500 * units)),
+        ]
+    );
+
+    // balances are proven against the liabilities root
+    let proof = query_liabilities_proof(store, "c".to_string())?;
+    let mut hash = liability_leaf("c", 300 * units);
+    let mut index = proof.leaf_index;
+    for sibling in &proof.branch {
+        let sibling: [u8; 32] = sibling.to_vec().try_into().unwrap();
+        hash = if index % 2 == 0 {
+            hash_pair(&hash, &sibling)
+        } else {
+            hash_pair(&sibling, &hash)
+        };
+        index /= 2;
+    }
+    assert_eq!(proof.root.to_vec(), hash.to_vec());
+    assert!(query_liabilities_proof(store, "b".to_string()).is_err());
+
+    // the next snapshot waits for the interval
+    assert!(step_balance_snapshot(&env, store, &querier)?.is_none());
+    assert_eq!(BALANCE_SNAPSHOT.load(store)?, snapshot);
+
+    Ok(())
+}
+
+#[test]
+fn disbursal_outputs() -> ContractResult<()> {
+    let mut deps = mock_dependencies();
+    let store = deps.as_mut().storage;
+    setup(store)?;
+    let units = BitcoinConfig::default().units_per_sat as u128;
+    let querier = BankQuerier::new(
+        vec![
+            ("a", 500 * units),
+            ("c", 300 * units),
+            ("d", 700 * units),
+            ("e", units / 2),
+        ],
+        false,
+    );
+    let querier = QuerierWrapper::new(&querier);
+    let env = set_time(0);
+
+    let script = |byte: u8| Binary::from(vec![byte; 22]);
+    for (holder, byte) in [("a", 1), ("d", 2), ("e", 3)] {
+        RECOVERY_SCRIPTS.save(store, holder, &script(byte))?;
+    }
+    // a snapshot in progress is not paid
+    step_balance_snapshot(&env, store, &querier)?;
+    step_balance_snapshot(&env, store, &querier)?;
+    assert!(query_disbursal_outputs(store, None, None).is_err());
+    while step_balance_snapshot(&env, store, &querier)?.is_none() {}
+
+    // holders without a recovery script or a whole satoshi are left out
+    let expected = vec![
+        DisbursalOutput {
+            address: "a".to_string(),
+            script_pubkey: script(1),
+            value: 500,
+        },
+        DisbursalOutput {
+            address: "d".to_string(),
+            script_pubkey: script(2),
+            value: 700,
+        },
+    ];
+    assert_eq!(query_disbursal_outputs(store, None, None)?, expected);
+    assert_eq!(
+        query_disbursal_outputs(store, None, Some(1))?,
+        expected[..1]
+    );
+    assert_eq!(
+        query_disbursal_outputs(store, Some("a".to_string()), None)?,
+        expected[1..]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn failed_snapshot_step_is_skipped() -> ContractResult<()> {
+    let mut deps = mock_dependencies();
+    let deps_mut = deps.as_mut();
+    let (store, api) = (deps_mut.storage, deps_mut.api);
+    setup(store)?;
+    let failing = BankQuerier::new(vec![("a", 1)], true);
+    let failing = QuerierWrapper::new(&failing);
+    let env = set_time(0);
+
+    // the first step only clears the previous snapshot
+    step_balance_snapshot(&env, store, &failing)?;
+    let snapshot = BALANCE_SNAPSHOT.load(store)?;
+    assert!(step_balance_snapshot(&env, store, &failing).is_err());
+
+    // the end block goes on without the step's writes
+    let res = clock_end_block(&env, store, &failing, api, Binary::from(vec![1]))?;
+    assert!(res
+        .events
+        .iter()
+        .any(|event| event.ty == "balance_snapshot_error"));
+    assert_eq!(BALANCE_SNAPSHOT.load(store)?, snapshot);
+
+    // and the snapshot resumes once the bank query succeeds
+    let querier = BankQuerier::new(vec![("a", 1)], false);
+    let querier = QuerierWrapper::new(&querier);
+    let res = clock_end_block(&env, store, &querier, api, Binary::from(vec![2]))?;
+    assert!(res
+        .events
+        .iter()
+        .any(|event| event.ty == "balance_snapshot"));
+
+    Ok(())
+}
+
+#[test]
+fn pending_withdrawals() -> ContractResult<()> {
+    let mut deps = mock_dependencies();
+    let store = deps.as_mut().storage;
+    setup(store)?;
+    let querier = BankQuerier::new(vec![("a", 1)], false);
+    let querier = QuerierWrapper::new(&querier);
+
+    let checkpoint = |status, outputs: &[(u8, u64)]| -> ContractResult<Checkpoint> {
+        let mut checkpoint = Checkpoint::new(SignatorySet::default())?;
+        checkpoint.status = status;
+        for (byte, value) in outputs {
+            checkpoint.batches[BatchType::Checkpoint][0]
+                .output
+                .push(Output::new(bitcoin::TxOut {
+                    value: bitcoin::Amount::from_sat(*value),
+                    script_pubkey: bitcoin::ScriptBuf::from_bytes(vec![*byte; 22]),
+                }));
+        }
+        Ok(checkpoint)
+    };
+    // the signing checkpoint starts with its reserve and commitment outputs
+    let signing = checkpoint(
+        CheckpointStatus::Signing,
+        &[(0, 50_000), (0, 0), (1, 1_000), (2, 2_000)],
+    )?;
+    CHECKPOINTS.push_back(store, &signing)?;
+    CHECKPOINTS.push_back(
+        store,
+        &checkpoint(CheckpointStatus::Building, &[(3, 3_000)])?,
+    )?;
+    BUILDING_INDEX.save(store, &1)?;
+
+    let env = set_time(0);
+    let event = loop {
+        if let Some(event) = step_balance_snapshot(&env, store, &querier)? {
+            break event;
+        }
+    };
+    let withdrawal = |checkpoint_index, byte, value| SnapshotWithdrawal {
+        checkpoint_index,
+        script_pubkey: Binary::from(vec![byte; 22]),
+        value,
+    };
+    let expected = vec![
+        withdrawal(0, 1, 1_000),
+        withdrawal(0, 2, 2_000),
+        withdrawal(1, 3, 3_000),
+    ];
+    assert_eq!(query_snapshot_withdrawals(store, None, None)?, expected);
+    assert_eq!(
+        query_snapshot_withdrawals(store, Some(0), Some(1))?,
+        expected[1..2]
+    );
+    let snapshot = BALANCE_SNAPSHOT.load(store)?;
+    assert_eq!(snapshot.withdrawals, 3);
+    assert_eq!(snapshot.withdrawals_total, 6_000);
+    assert!(event
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "withdrawals_total" && attr.value == "6000"));
+
+    // once the checkpoint is signed, the next snapshot drops its outputs
+    let mut complete = CHECKPOINTS.get(store, 0)?.unwrap();
+    complete.status = CheckpointStatus::Complete;
+    CHECKPOINTS.set(store, 0, &complete)?;
+    let env = set_time(100);
+    while step_balance_snapshot(&env, store, &querier)?.is_none() {}
+    assert_eq!(
+        query_snapshot_withdrawals(store, None, None)?,
+        vec![withdrawal(1, 3, 3_000)]
+    );
+    let snapshot = BALANCE_SNAPSHOT.load(store)?;
+    assert_eq!(snapshot.withdrawals, 1);
+    assert_eq!(snapshot.withdrawals_total, 3_000);
+
+    Ok(())
+}
@@ -0,0 +1,137 @@
+use bitcoin::hashes::Hash;
+use bitcoin::opcodes::all::{OP_CHECKSIG, OP_DUP, OP_RETURN};
+use bitcoin::script::{Builder, PushBytes};
+use bitcoin::transaction::Version;
+use bitcoin::{
+    absolute::LockTime, Amount, OutPoint, PubkeyHash, ScriptBuf, Sequence, Transaction, TxIn,
+    TxOut, Witness,
+};
+use common_bitcoin::error::TxError;
+
+use crate::interface::RelayTxLimits;
+use crate::tx_check::check_relayed_tx;
+
+fn valid_tx() -> Transaction {
+    Transaction {
+        version: Version::TWO,
+        lock_time: LockTime::ZERO,
+        input: vec![TxIn {
+            previous_output: OutPoint::default(),
+            script_sig: ScriptBuf::default(),
+            sequence: Sequence(u32::MAX),
+            witness: Witness::from_slice(&[vec![0; 72], vec![2; 33]]),
+        }],
+        output: vec![TxOut {
+            value: Amount::from_sat(10_000),
+            script_pubkey: ScriptBuf::new_p2wpkh(&bitcoin::WPubkeyHash::all_zeros()),
+        }],
+    }
+}
+
+#[test]
+fn test_check_relayed_tx() {
+    let limits = RelayTxLimits::default();
+    check_relayed_tx(&valid_tx(), &limits).unwrap();
+
+    // a corpus of decodable transactions which break one rule each
+    let cases: Vec<(Box<dyn Fn(&mut Transaction)>, TxError)> = vec![
+        (Box::new(|tx| tx.version = Version(0)), TxError::Version(0)),
+        (
+            Box::new(|tx| tx.version = Version(-1)),
+            TxError::Version(-1),
+        ),
+        (Box::new(|tx| tx.version = Version(4)), TxError::Version(4)),
+        (Box::new(|tx| tx.input.clear()), TxError::NoInputs),
+        (Box::new(|tx| tx.output.clear()), TxError::NoOutputs),
+        (
+            Box::new(|tx| tx.input = vec![tx.input[0].clone(); 1_001]),
+            TxError::TooManyInputs {
+                count: 1_001,
+                max: 1_000,
+            },
+        ),
+        (
+            Box::new(|tx| tx.output = vec![tx.output[0].clone(); 1_001]),
+            TxError::TooManyOutputs {
+                count: 1_001,
+                max: 1_000,
+            },
+        ),
+        (
+            Box::new(|tx| {
+                tx.input[0].witness = Witness::from_slice(&[vec![0; 72], vec![0; 3_601]]);
+            }),
+            TxError::WitnessItemTooLarge {
+                input: 0,
+                item: 1,
+                size: 3_601,
+                max: 3_600,
+            },
+        ),
+        (
+            Box::new(|tx| {
+                tx.input[0].script_sig = Builder::new().push_opcode(OP_DUP).into_script();
+            }),
+            TxError::NonStandardScriptSig(0),
+        ),
+        (
+            // truncated push
+            Box::new(|tx| tx.input[0].script_sig = ScriptBuf::from(vec![0x4c, 0x10, 0x00])),
+            TxError::NonStandardScriptSig(0),
+        ),
+        (
+            Box::new(|tx| {
+                tx.output[0].script_pubkey = Builder::new()
+                    .push_opcode(OP_DUP)
+                    .push_opcode(OP_CHECKSIG)
+                    .into_script();
+            }),
+            TxError::NonStandardOutput(0),
+        ),
+        (
+            Box::new(|tx| {
+                tx.output.push(TxOut {
+                    value: Amount::ZERO,
+                    script_pubkey: Builder::new()
+                        .push_opcode(OP_RETURN)
+                        .push_slice(<&PushBytes>::try_from([0; 81].as_slice()).unwrap())
+                        .into_script(),
+                });
+            }),
+            TxError::NonStandardOutput(1),
+        ),
+    ];
+    for (malform, expected) in cases {
+        let mut tx = valid_tx();
+        malform(&mut tx);
+        assert_eq!(check_relayed_tx(&tx, &limits), Err(expected));
+    }
+
+    let mut tx = valid_tx();
+    tx.input[0].witness = Witness::from_slice(&[vec![0; 400_000]]);
+    assert!(matches!(
+        check_relayed_tx(
+            &tx,
+            &RelayTxLimits {
+                max_witness_item_size: 0,
+                ..limits.clone()
+            }
+        ),
+        Err(TxError::TooHeavy { .. })
+    ));
+
+    // standard null data and legacy outputs are accepted
+    let mut tx = valid_tx();
+    tx.output.push(TxOut {
+        value: Amount::ZERO,
+        script_pubkey: Builder::new()
+            .push_opcode(OP_RETURN)
+            .push_slice(<&PushBytes>::try_from([0; 80].as_slice()).unwrap())
+            .into_script(),
+    });
+    tx.output.push(TxOut {
+        value: Amount::from_sat(10_000),
+        script_pubkey: ScriptBuf::new_p2pkh(&PubkeyHash::all_zeros()),
+    });
+    check_relayed_tx(&tx, &limits).unwrap();
+}
@@ -1,11 +1,11 @@
 use super::constants::SIGSET_THRESHOLD;
 use super::signatory::SignatorySet;
-use bitcoin::blockdata::transaction::EcdsaSighashType;
 use bitcoin::secp256k1::{
     self,
     constants::{MESSAGE_SIZE, PUBLIC_KEY_SIZE},
     ecdsa, PublicKey,
 };
+use bitcoin::sighash::EcdsaSighashType;
 use common_bitcoin::error::{ContractError, ContractResult};
 use cosmwasm_schema::cw_serde;
 use cosmwasm_schema::schemars::JsonSchema;
@@ -17,10 +17,78 @@ use cosmwasm_std::Api;
 /// A sighash to be signed by a set of signers.
 pub type Message = [u8; MESSAGE_SIZE];
 
-/// A compact secp256k1 ECDSA signature.
+/// A signature share in the encoding of its scheme, e.g. a compact secp256k1
+/// ECDSA signature.
 #[cw_serde]
 pub struct Signature(#[serde(serialize_with = "<[_]>::serialize")] pub Vec<u8>);
 
+/// How signatories sign sighashes and how their signatures are placed in a
+/// witness.
+pub trait SignatureScheme {
+    /// Verifies the signature of `pubkey` for the message.
+    fn verify(api: &dyn Api, msg: &[u8], pubkey: &Pubkey, sig: &Signature) -> ContractResult<()>;
+
+    /// Encodes a signature as the witness element of a spend.
+    fn witness_element(sig: &Signature, sighash_type: EcdsaSighashType) -> ContractResult<Vec<u8>>;
+}
+
+/// Compact secp256k1 ECDSA signatures, spent as DER signatures with the
+/// sighash type appended.
+pub struct Ecdsa;
+
+impl SignatureScheme for Ecdsa {
+    fn verify(api: &dyn Api, msg: &[u8], pubkey: &Pubkey, sig: &Signature) -> ContractResult<()> {
+        let verified = api.secp256k1_verify(msg, &sig.0, pubkey.as_slice())?;
+
+        if !verified {
+            return Err(ContractError::App("Can not verify signature".to_string()));
+        }
+
+        Ok(())
+    }
+
+    fn witness_element(sig: &Signature, sighash_type: EcdsaSighashType) -> ContractResult<Vec<u8>> {
+        let sig = ecdsa::Signature::from_compact(&sig.0)?;
+        let mut v = sig.serialize_der().to_vec();
+        v.push(sighash_type.to_u32() as u8);
+        Ok(v)
+    }
+}
+
+/// The signature scheme recorded with a signing state, so states signed under
+/// an older scheme keep verifying after new schemes are added.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+#[serde(crate = "cosmwasm_schema::serde", rename_all = "snake_case")]
+#[schemars(crate = "cosmwasm_schema::schemars")]
+pub enum SigScheme {
+    #[default]
+    Ecdsa,
+}
+
+impl SigScheme {
+    pub fn verify(
+        &self,
+        api: &dyn Api,
+        msg: &[u8],
+        pubkey: &Pubkey,
+        sig: &Signature,
+    ) -> ContractResult<()> {
+        match self {
+            SigScheme::Ecdsa => Ecdsa::verify(api, msg, pubkey, sig),
+        }
+    }
+
+    pub fn witness_element(
+        &self,
+        sig: &Signature,
+        sighash_type: EcdsaSighashType,
+    ) -> ContractResult<Vec<u8>> {
+        match self {
+            SigScheme::Ecdsa => Ecdsa::witness_element(sig, sighash_type),
+        }
+    }
+}
+
 /// A compressed secp256k1 public key.
 #[derive(Clone, Debug, PartialOrd, PartialEq, Eq, Ord, Deserialize, Serialize, JsonSchema)]
 #[serde(crate = "cosmwasm_schema::serde")]
@@ -111,6 +179,11 @@ pub struct ThresholdSig {
     /// A map of entries containing the pubkey and voting power of each signer,
     /// and the signature if they have signed.
     pub sigs: Vec<(Pubkey, Share)>,
+
+    /// The scheme the signatures are made with. States created before
+    /// schemes were recorded are ECDSA.
+    #[serde(default)]
+    pub scheme: SigScheme,
 }
 
 impl ThresholdSig {
@@ -147,7 +220,10 @@ impl ThresholdSig {
     /// Populates the set of signers based on the public keys and voting power
     /// in the given `SignatorySet`.
     pub fn from_sigset(signatories: &SignatorySet) -> Self {
-        let mut ts = ThresholdSig::default();
+        let mut ts = ThresholdSig {
+            scheme: signatories.sig_scheme(),
+            ..Default::default()
+        };
         let mut total_vp = 0;
 
         for signatory in signatories.iter() {
@@ -165,6 +241,9 @@ impl ThresholdSig {
 
         ts.threshold =
             ((total_vp as u128) * SIGSET_THRESHOLD.0 as u128 / SIGSET_THRESHOLD.1 as u128) as u64;
+        ts.threshold = ts
+            .threshold
+            .max(signatories.signing_threshold(SIGSET_THRESHOLD));
 
         ts
     }
@@ -247,10 +326,11 @@ impl ThresholdSig {
             .1;
 
         if share.sig.is_some() {
-            return Err(ContractError::App("Pubkey already signed".into()))?;
+            return Err(ContractError::AlreadySigned {});
         }
 
-        Self::secp_verify(api, self.message.as_slice(), &pubkey, sig)?;
+        self.scheme
+            .verify(api, self.message.as_slice(), &pubkey, sig)?;
 
         share.sig = Some(sig.clone());
         self.signed += share.power;
@@ -258,27 +338,20 @@ impl ThresholdSig {
         Ok(())
     }
 
-    /// Verifies the given signature for the message, using the given signer's
-    /// pubkey.
-    /// Verifies the given signature for the message, using the given signer's
-    /// pubkey.
+    /// Verifies the given ECDSA signature for the message, using the given
+    /// signer's pubkey.
     pub fn secp_verify(
         api: &dyn Api,
         msg: &[u8],
         pubkey: &Pubkey,
         sig: &Signature,
     ) -> ContractResult<()> {
-        let verified = api.secp256k1_verify(msg, &sig.0, pubkey.as_slice())?;
-
-        if !verified {
-            return Err(ContractError::App("Can not verify signature".to_string()));
-        }
-
-        Ok(())
+        Ecdsa::verify(api, msg, pubkey, sig)
     }
 
     pub fn verify(&self, api: &dyn Api, pubkey: &Pubkey, sig: &Signature) -> ContractResult<()> {
-        Self::secp_verify(api, self.message.as_slice(), pubkey, sig)
+        self.scheme
+            .verify(api, self.message.as_slice(), pubkey, sig)
     }
 
     /// Returns a vector of signatures (or empty bytes for unsigned entries) in
@@ -289,7 +362,7 @@ impl ThresholdSig {
     /// script.
     // TODO: this shouldn't know so much about bitcoin-specific structure,
     // decouple by exposing a power-ordered iterator of Option<Signature>
-    pub fn to_witness(&self) -> ContractResult<Vec<Vec<u8>>> {
+    pub fn to_witness(&self, sighash_type: EcdsaSighashType) -> ContractResult<Vec<Vec<u8>>> {
         if !self.signed() {
             return Ok(vec![]);
         }
@@ -309,14 +382,63 @@ impl ThresholdSig {
             .into_iter()
             .map(|(_, share)| {
                 share.sig.map_or(Ok(vec![]), |sig| {
-                    let sig = ecdsa::Signature::from_compact(&sig.0)?;
-                    let mut v = sig.serialize_der().to_vec();
-                    v.push(EcdsaSighashType::All.to_u32() as u8);
-                    Ok(v)
+                    self.scheme.witness_element(&sig, sighash_type)
                 })
             })
             .collect()
     }
+
+    /// Verifies signature elements of a witness, in the order produced by
+    /// `to_witness`, against the given message instead of the stored one.
+    ///
+    /// Returns an error if any signature is invalid or uses another sighash
+    /// type, or if the valid signatures do not reach the threshold.
+    pub fn verify_witness(
+        &self,
+        api: &dyn Api,
+        message: &Message,
+        elements: &[Vec<u8>],
+        sighash_type: EcdsaSighashType,
+    ) -> ContractResult<()> {
+        if elements.len() != self.sigs.len() {
+            return Err(ContractError::App(
+                "Witness does not have an element per signer".to_string(),
+            ));
+        }
+
+        let mut entries: Vec<_> = self.sigs.iter().collect();
+        entries.sort_by(|a, b| {
+            if a.1.power == b.1.power {
+                a.0.bytes.cmp(&b.0.bytes)
+            } else {
+                a.1.power.cmp(&b.1.power)
+            }
+        });
+
+        let mut signed = 0;
+        for ((pubkey, share), element) in entries.into_iter().zip(elements) {
+            let Some((hash_ty, der)) = element.split_last() else {
+                continue;
+            };
+            if *hash_ty != sighash_type.to_u32() as u8 {
+                return Err(ContractError::App(
+                    "Signature uses the wrong sighash type".to_string(),
+                ));
+            }
+            let sig = ecdsa::Signature::from_der(der)?;
+            let sig = Signature(sig.serialize_compact().to_vec());
+            self.scheme.verify(api, message, pubkey, &sig)?;
+            signed += share.power;
+        }
+
+        if signed <= self.threshold {
+            return Err(ContractError::App(
+                "Signatures do not reach the threshold".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
 }
 
 use std::fmt::Debug;
@@ -328,6 +450,7 @@ impl Debug for ThresholdSig {
             .field("message", &self.message)
             .field("len", &self.len)
             .field("sigs", &"TODO")
+            .field("scheme", &self.scheme)
             .finish()
     }
 }
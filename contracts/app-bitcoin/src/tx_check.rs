@@ -0,0 +1,88 @@
+use bitcoin::{opcodes::all::OP_PUSHNUM_16, script::Instruction};
+use bitcoin::{Script, Transaction};
+use common_bitcoin::error::TxError;
+
+use crate::{
+    constants::{MAX_STANDARD_OP_RETURN_SIZE, MAX_STANDARD_SCRIPT_SIG_SIZE},
+    interface::RelayTxLimits,
+};
+
+/// Checks a relayed transaction against the given limits and Bitcoin Core's
+/// standardness rules, so that transactions which decode but could never be
+/// relayed on the Bitcoin network don't reach the vsize and fee math.
+pub fn check_relayed_tx(tx: &Transaction, limits: &RelayTxLimits) -> Result<(), TxError> {
+    let version = tx.version.0;
+    if version < 1 || (limits.max_version > 0 && version > limits.max_version) {
+        return Err(TxError::Version(version));
+    }
+    if tx.input.is_empty() {
+        return Err(TxError::NoInputs);
+    }
+    if tx.output.is_empty() {
+        return Err(TxError::NoOutputs);
+    }
+    if limits.max_inputs > 0 && tx.input.len() > limits.max_inputs as usize {
+        return Err(TxError::TooManyInputs {
+            count: tx.input.len(),
+            max: limits.max_inputs,
+        });
+    }
+    if limits.max_outputs > 0 && tx.output.len() > limits.max_outputs as usize {
+        return Err(TxError::TooManyOutputs {
+            count: tx.output.len(),
+            max: limits.max_outputs,
+        });
+    }
+    let weight = tx.weight().to_wu() as usize;
+    if limits.max_weight > 0 && weight as u64 > limits.max_weight {
+        return Err(TxError::TooHeavy {
+            weight,
+            max: limits.max_weight,
+        });
+    }
+
+    for (i, input) in tx.input.iter().enumerate() {
+        if input.script_sig.len() > MAX_STANDARD_SCRIPT_SIG_SIZE || !is_push_only(&input.script_sig)
+        {
+            return Err(TxError::NonStandardScriptSig(i));
+        }
+        if limits.max_witness_item_size == 0 {
+            continue;
+        }
+        for (j, item) in input.witness.iter().enumerate() {
+            if item.len() > limits.max_witness_item_size as usize {
+                return Err(TxError::WitnessItemTooLarge {
+                    input: i,
+                    item: j,
+                    size: item.len(),
+                    max: limits.max_witness_item_size,
+                });
+            }
+        }
+    }
+
+    for (i, output) in tx.output.iter().enumerate() {
+        if !is_standard_output(&output.script_pubkey) {
+            return Err(TxError::NonStandardOutput(i));
+        }
+    }
+    Ok(())
+}
+
+/// Whether the script only pushes data, as required of script signatures.
+fn is_push_only(script: &Script) -> bool {
+    script.instructions().all(|instruction| match instruction {
+        Ok(Instruction::PushBytes(_)) => true,
+        Ok(Instruction::Op(op)) => op.to_u8() <= OP_PUSHNUM_16.to_u8(),
+        Err(_) => false,
+    })
+}
+
+/// Whether the output script is of a type relayed by Bitcoin Core. Bare
+/// multisig outputs are not accepted.
+fn is_standard_output(script: &Script) -> bool {
+    if script.is_op_return() {
+        return script.len() <= MAX_STANDARD_OP_RETURN_SIZE;
+    }
+    script.is_p2pkh() || script.is_p2sh() || script.is_p2pk() || script.is_witness_program()
+}
@@ -1,6 +1,15 @@
 pub const MAX_LENGTH: u64 = 24_192; // ~6 months
 pub const MAX_HEADERS_RELAY_ONE_TIME: u64 = 1000;
+/// The number of rejected header relays kept as evidence
+pub const MAX_HEADER_REJECTIONS: u32 = 500;
+// query pagination
+pub const DEFAULT_QUERY_LIMIT: u32 = 10;
+pub const MAX_QUERY_LIMIT: u32 = 30;
+/// The most headers connecting a pruned block to the stored chain in a proof
+pub const MAX_ANCESTOR_SEGMENT_LEN: usize = 2016;
 pub const MAX_TIME_INCREASE: u32 = 2 * 60 * 60;
+/// How far a header's timestamp may be ahead of the clock, as in Bitcoin Core
+pub const MAX_FUTURE_BLOCK_TIME: u32 = 2 * 60 * 60;
 pub const RETARGET_INTERVAL: u32 = 2016;
 pub const TARGET_SPACING: u32 = 10 * 60;
 pub const TARGET_TIMESPAN: u32 = RETARGET_INTERVAL * TARGET_SPACING;
@@ -1,17 +1,23 @@
 use crate::{
     entrypoints::{
-        query_header_config, query_header_height, query_network, query_sidechain_block_hash,
-        query_verify_tx_with_proof, relay_headers, update_config, update_header_config,
+        bond_relayer_stake, fund_relay_rewards, query_block_hash_at_height, query_chain_work,
+        query_filter_header, query_header_at, query_header_config, query_header_height,
+        query_header_rejections, query_header_time, query_median_time_past, query_network,
+        query_relay_rewards, query_relayer_stake, query_sidechain_block_hash,
+        query_verify_filter_header_chain, query_verify_tx_with_proof,
+        query_verify_txid_with_ancestor_proof, relay_filter_headers, relay_headers,
+        set_relay_reward, set_relayer_stake, slash_relayer, unbond_relayer_stake, update_config,
+        update_header_config, withdraw_relay_rewards, withdraw_relayer_stake,
     },
     header::HeaderQueue,
     state::CONFIG,
 };
-use common_bitcoin::error::ContractError;
+use common_bitcoin::{error::ContractError, msg::error_codes};
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{to_json_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult};
 use light_client_bitcoin::{
-    interface::HeaderConfig,
+    interface::{ClockSource, HeaderConfig},
     msg::{Config, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg},
 };
 
@@ -27,7 +33,15 @@ pub fn instantiate(
     _msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
     cw2::set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
-    CONFIG.save(deps.storage, &Config { owner: info.sender })?;
+    CONFIG.save(
+        deps.storage,
+        &Config {
+            owner: info.sender,
+            clock_source: ClockSource::default(),
+            relayer_stake: None,
+            relayer_unbonding_period: 0,
+        },
+    )?;
 
     // Set up header
     #[cfg(feature = "mainnet")]
@@ -42,17 +56,39 @@ pub fn instantiate(
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
-    match msg {
-        ExecuteMsg::RelayHeaders { headers } => relay_headers(deps.storage, headers),
+    let result = match msg {
+        ExecuteMsg::RelayHeaders { headers } => relay_headers(deps.storage, &env, info, headers),
+        ExecuteMsg::RelayFilterHeaders { headers } => {
+            relay_filter_headers(deps.storage, info, headers)
+        }
         ExecuteMsg::UpdateHeaderConfig { config } => {
             update_header_config(deps.storage, info, config)
         }
-        ExecuteMsg::UpdateConfig { owner } => update_config(deps.storage, info, owner),
-    }
+        ExecuteMsg::UpdateConfig {
+            owner,
+            clock_source,
+        } => update_config(deps.storage, info, owner, clock_source),
+        ExecuteMsg::SetRelayReward { reward } => set_relay_reward(deps.storage, info, reward),
+        ExecuteMsg::FundRelayRewards {} => fund_relay_rewards(deps.storage, info),
+        ExecuteMsg::WithdrawRelayRewards { amount } => {
+            withdraw_relay_rewards(deps.storage, info, amount)
+        }
+        ExecuteMsg::SetRelayerStake {
+            stake,
+            unbonding_period,
+        } => set_relayer_stake(deps.storage, info, stake, unbonding_period),
+        ExecuteMsg::BondRelayerStake {} => bond_relayer_stake(deps.storage, info),
+        ExecuteMsg::UnbondRelayerStake {} => unbond_relayer_stake(deps.storage, &env, info),
+        ExecuteMsg::WithdrawRelayerStake {} => withdraw_relayer_stake(deps.storage, &env, info),
+        ExecuteMsg::SlashRelayer { relayer, amount } => {
+            slash_relayer(deps.storage, info, relayer, amount)
+        }
+    };
+    result.map_err(ContractError::coded)
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -60,10 +96,39 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::HeaderConfig {} => to_json_binary(&query_header_config(deps.storage)?),
         QueryMsg::HeaderHeight {} => to_json_binary(&query_header_height(deps.storage)?),
+        QueryMsg::HeaderTime {} => to_json_binary(&query_header_time(deps.storage)?),
+        QueryMsg::MedianTimePast {} => to_json_binary(&query_median_time_past(deps.storage)?),
         QueryMsg::Network {} => to_json_binary(&query_network()?),
+        QueryMsg::ErrorCodes {} => to_json_binary(&error_codes()),
         QueryMsg::SidechainBlockHash {} => {
             to_json_binary(&query_sidechain_block_hash(deps.storage)?)
         }
+        QueryMsg::BlockHashAtHeight { height } => {
+            to_json_binary(&query_block_hash_at_height(deps.storage, height)?)
+        }
+        QueryMsg::HeaderAt { height } => to_json_binary(&query_header_at(deps.storage, height)?),
+        QueryMsg::ChainWork {
+            from_height,
+            to_height,
+        } => to_json_binary(&query_chain_work(deps.storage, from_height, to_height)?),
+        QueryMsg::FilterHeader { height } => {
+            to_json_binary(&query_filter_header(deps.storage, height)?)
+        }
+        QueryMsg::RelayRewards {} => to_json_binary(&query_relay_rewards(deps.storage)?),
+        QueryMsg::HeaderRejections { sender, limit } => {
+            to_json_binary(&query_header_rejections(deps.storage, sender, limit)?)
+        }
+        QueryMsg::RelayerStake { relayer } => {
+            to_json_binary(&query_relayer_stake(deps.storage, relayer)?)
+        }
+        QueryMsg::VerifyFilterHeaderChain {
+            start_height,
+            end_height,
+        } => to_json_binary(&query_verify_filter_header_chain(
+            deps.storage,
+            start_height,
+            end_height,
+        )?),
         QueryMsg::VerifyTxWithProof {
             btc_tx,
             btc_height,
@@ -74,6 +139,18 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
             btc_height,
             btc_proof,
         )?),
+        QueryMsg::VerifyTxidWithAncestorProof {
+            txid,
+            btc_height,
+            btc_proof,
+            headers,
+        } => to_json_binary(&query_verify_txid_with_ancestor_proof(
+            deps.storage,
+            txid.into_inner(),
+            btc_height,
+            btc_proof,
+            headers,
+        )?),
     }
 }
 
@@ -1,19 +1,349 @@
-use common_bitcoin::error::ContractResult;
-use cosmwasm_std::{Addr, MessageInfo, Response, Storage};
+use common_bitcoin::{
+    adapter::WrappedBinary,
+    dry_run::{apply_writes, DryRunStorage},
+    error::{ContractError, ContractResult},
+};
+use cosmwasm_std::{Addr, BankMsg, Coin, Env, MessageInfo, Order, Response, Storage, Uint128};
 
 use crate::{
     header::{HeaderList, HeaderQueue},
-    state::CONFIG,
+    state::{
+        header_height, record_header_rejection, CONFIG, RELAYER_STAKES, RELAY_REWARD,
+        RELAY_REWARD_POOL, REWARDED_HEIGHT,
+    },
+};
+use light_client_bitcoin::{
+    header::WrappedHeader,
+    interface::{ClockSource, FilterHeaderEntry, HeaderConfig, HeaderRejection, RelayerStake},
+    msg::Config,
 };
-use light_client_bitcoin::{header::WrappedHeader, interface::HeaderConfig};
 
+/// Adds the relayed headers to the header queue. Headers failing validation
+/// fail the relay, unless a stake is required: the relayer is then bonded,
+/// and the relay is recorded in `HEADER_REJECTIONS` as slashable evidence
+/// instead, leaving the queue untouched.
 pub fn relay_headers(
     store: &mut dyn Storage,
+    env: &Env,
+    info: MessageInfo,
     headers: Vec<WrappedHeader>,
 ) -> ContractResult<Response> {
+    let config = CONFIG.load(store)?;
+    if config.relayer_stake.is_some() && !is_bonded_relayer(store, &config, &info.sender)? {
+        return Err(ContractError::App(
+            "Relaying headers requires a bonded relayer stake".to_string(),
+        ));
+    }
+    let now = match config.clock_source {
+        ClockSource::Disabled => None,
+        ClockSource::BlockTime => Some(env.block.time.seconds() as u32),
+    };
+    let prev_height = header_height(store)?;
+    let first = headers
+        .first()
+        .map(|header| (header.height, header.block_hash()));
+
+    let mut dry_run = DryRunStorage::new(store);
     let mut header_queue = HeaderQueue::default();
-    header_queue.add(store, HeaderList::from(headers))?;
-    Ok(Response::new().add_attribute("action", "add_headers"))
+    let result = header_queue.add(&mut dry_run, HeaderList::from(headers), now);
+    let writes = dry_run.into_writes();
+    if let Err(err) = result {
+        // only relayers with stake at risk can add to the evidence log
+        let (Some((height, header_hash)), Some(_)) = (first, &config.relayer_stake) else {
+            return Err(err);
+        };
+        let reason = err.to_string();
+        record_header_rejection(
+            store,
+            HeaderRejection {
+                sender: info.sender,
+                height,
+                header_hash: WrappedBinary(header_hash),
+                reason: reason.clone(),
+                rejected_at: env.block.time.seconds(),
+            },
+        )?;
+        return Ok(Response::new()
+            .add_attribute("action", "reject_headers")
+            .add_attribute("reason", reason));
+    }
+    apply_writes(store, writes);
+
+    let mut response = Response::new().add_attribute("action", "add_headers");
+    if let Some(reward) = take_relay_reward(store, prev_height)? {
+        response = response
+            .add_attribute("relay_reward", reward.to_string())
+            .add_message(BankMsg::Send {
+                to_address: info.sender.to_string(),
+                amount: vec![reward],
+            });
+    }
+    Ok(response)
+}
+
+/// Takes the relay reward out of the pool if the relayed headers extended the
+/// chain past `prev_height` and past every height a reward was paid for, so
+/// relaying the same heights again, e.g. on a competing branch, earns nothing.
+pub fn take_relay_reward(
+    store: &mut dyn Storage,
+    prev_height: u32,
+) -> ContractResult<Option<Coin>> {
+    let Some(reward) = RELAY_REWARD.may_load(store)? else {
+        return Ok(None);
+    };
+    let height = header_height(store)?;
+    let rewarded_height = REWARDED_HEIGHT.may_load(store)?.unwrap_or_default();
+    if height <= prev_height.max(rewarded_height) {
+        return Ok(None);
+    }
+    REWARDED_HEIGHT.save(store, &height)?;
+
+    let pool = RELAY_REWARD_POOL.may_load(store)?.unwrap_or_default();
+    if pool < reward.amount {
+        return Ok(None);
+    }
+    RELAY_REWARD_POOL.save(store, &(pool - reward.amount))?;
+    Ok(Some(reward))
+}
+
+pub fn set_relay_reward(
+    store: &mut dyn Storage,
+    info: MessageInfo,
+    reward: Option<Coin>,
+) -> ContractResult<Response> {
+    assert_eq!(info.sender, CONFIG.load(store)?.owner);
+    let pool = RELAY_REWARD_POOL.may_load(store)?.unwrap_or_default();
+    let denom = RELAY_REWARD.may_load(store)?.map(|reward| reward.denom);
+    if !pool.is_zero() && denom != reward.as_ref().map(|reward| reward.denom.clone()) {
+        return Err(ContractError::App(
+            "Relay reward denom can not change while the pool is funded".to_string(),
+        ));
+    }
+    match reward {
+        Some(reward) => RELAY_REWARD.save(store, &reward)?,
+        None => RELAY_REWARD.remove(store),
+    }
+    Ok(Response::new().add_attribute("action", "set_relay_reward"))
+}
+
+pub fn fund_relay_rewards(store: &mut dyn Storage, info: MessageInfo) -> ContractResult<Response> {
+    let reward = RELAY_REWARD
+        .may_load(store)?
+        .ok_or_else(|| ContractError::App("Relay rewards are disabled".to_string()))?;
+    if let Some(coin) = info.funds.iter().find(|coin| coin.denom != reward.denom) {
+        return Err(ContractError::App(format!(
+            "Relay rewards are paid in {}, not {}",
+            reward.denom, coin.denom
+        )));
+    }
+    let amount: Uint128 = info
+        .funds
+        .iter()
+        .filter(|coin| coin.denom == reward.denom)
+        .map(|coin| coin.amount)
+        .sum();
+    if amount.is_zero() {
+        return Err(ContractError::App(format!(
+            "No {} attached to fund relay rewards",
+            reward.denom
+        )));
+    }
+    let pool = RELAY_REWARD_POOL.may_load(store)?.unwrap_or_default();
+    RELAY_REWARD_POOL.save(store, &(pool + amount))?;
+    Ok(Response::new()
+        .add_attribute("action", "fund_relay_rewards")
+        .add_attribute("amount", amount))
+}
+
+/// Sends up to `amount` of the relay reward pool, all of it by default, to
+/// the owner.
+pub fn withdraw_relay_rewards(
+    store: &mut dyn Storage,
+    info: MessageInfo,
+    amount: Option<Uint128>,
+) -> ContractResult<Response> {
+    let config = CONFIG.load(store)?;
+    assert_eq!(info.sender, config.owner);
+    let reward = RELAY_REWARD
+        .may_load(store)?
+        .ok_or_else(|| ContractError::App("Relay rewards are disabled".to_string()))?;
+    let pool = RELAY_REWARD_POOL.may_load(store)?.unwrap_or_default();
+    let withdrawn = amount.unwrap_or(pool).min(pool);
+    RELAY_REWARD_POOL.save(store, &(pool - withdrawn))?;
+
+    let mut response = Response::new()
+        .add_attribute("action", "withdraw_relay_rewards")
+        .add_attribute("amount", withdrawn);
+    if !withdrawn.is_zero() {
+        response = response.add_message(BankMsg::Send {
+            to_address: config.owner.to_string(),
+            amount: vec![Coin::new(withdrawn.u128(), reward.denom)],
+        });
+    }
+    Ok(response)
+}
+
+pub fn set_relayer_stake(
+    store: &mut dyn Storage,
+    info: MessageInfo,
+    stake: Option<Coin>,
+    unbonding_period: u64,
+) -> ContractResult<Response> {
+    let mut config = CONFIG.load(store)?;
+    assert_eq!(info.sender, config.owner);
+    let denom = config
+        .relayer_stake
+        .as_ref()
+        .map(|stake| stake.denom.clone());
+    let has_stakes = RELAYER_STAKES
+        .keys(store, None, None, Order::Ascending)
+        .next()
+        .is_some();
+    if has_stakes && denom.is_some() && denom != stake.as_ref().map(|stake| stake.denom.clone()) {
+        return Err(ContractError::App(
+            "Relayer stake denom can not change while stake is bonded".to_string(),
+        ));
+    }
+    config.relayer_stake = stake;
+    config.relayer_unbonding_period = unbonding_period;
+    CONFIG.save(store, &config)?;
+    Ok(Response::new().add_attribute("action", "set_relayer_stake"))
+}
+
+pub fn bond_relayer_stake(store: &mut dyn Storage, info: MessageInfo) -> ContractResult<Response> {
+    let stake = CONFIG
+        .load(store)?
+        .relayer_stake
+        .ok_or_else(|| ContractError::App("Relayer stake is not required".to_string()))?;
+    let amount: Uint128 = info
+        .funds
+        .iter()
+        .filter(|coin| coin.denom == stake.denom)
+        .map(|coin| coin.amount)
+        .sum();
+    if amount.is_zero() {
+        return Err(ContractError::App(format!(
+            "No {} attached to bond",
+            stake.denom
+        )));
+    }
+    let mut bonded = RELAYER_STAKES
+        .may_load(store, &info.sender)?
+        .unwrap_or(RelayerStake {
+            amount: Uint128::zero(),
+            unbonding_at: None,
+        });
+    bonded.amount += amount;
+    bonded.unbonding_at = None;
+    RELAYER_STAKES.save(store, &info.sender, &bonded)?;
+    Ok(Response::new()
+        .add_attribute("action", "bond_relayer_stake")
+        .add_attribute("amount", bonded.amount))
+}
+
+pub fn unbond_relayer_stake(
+    store: &mut dyn Storage,
+    env: &Env,
+    info: MessageInfo,
+) -> ContractResult<Response> {
+    let mut bonded = RELAYER_STAKES
+        .may_load(store, &info.sender)?
+        .ok_or_else(|| ContractError::App("No relayer stake bonded".to_string()))?;
+    if bonded.unbonding_at.is_none() {
+        bonded.unbonding_at = Some(env.block.time.seconds());
+        RELAYER_STAKES.save(store, &info.sender, &bonded)?;
+    }
+    Ok(Response::new().add_attribute("action", "unbond_relayer_stake"))
+}
+
+pub fn withdraw_relayer_stake(
+    store: &mut dyn Storage,
+    env: &Env,
+    info: MessageInfo,
+) -> ContractResult<Response> {
+    let config = CONFIG.load(store)?;
+    let bonded = RELAYER_STAKES
+        .may_load(store, &info.sender)?
+        .ok_or_else(|| ContractError::App("No relayer stake bonded".to_string()))?;
+    let Some(unbonding_at) = bonded.unbonding_at else {
+        return Err(ContractError::App(
+            "Relayer stake is not unbonding".to_string(),
+        ));
+    };
+    if env.block.time.seconds() < unbonding_at + config.relayer_unbonding_period {
+        return Err(ContractError::App(
+            "Relayer stake is still unbonding".to_string(),
+        ));
+    }
+    RELAYER_STAKES.remove(store, &info.sender);
+    let mut response = Response::new().add_attribute("action", "withdraw_relayer_stake");
+    if let (Some(stake), false) = (config.relayer_stake, bonded.amount.is_zero()) {
+        response = response.add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: vec![Coin::new(bonded.amount.u128(), stake.denom)],
+        });
+    }
+    Ok(response)
+}
+
+pub fn slash_relayer(
+    store: &mut dyn Storage,
+    info: MessageInfo,
+    relayer: Addr,
+    amount: Uint128,
+) -> ContractResult<Response> {
+    let config = CONFIG.load(store)?;
+    assert_eq!(info.sender, config.owner);
+    let stake = config
+        .relayer_stake
+        .ok_or_else(|| ContractError::App("Relayer stake is not required".to_string()))?;
+    let mut bonded = RELAYER_STAKES
+        .may_load(store, &relayer)?
+        .ok_or_else(|| ContractError::App("No relayer stake bonded".to_string()))?;
+    let slashed = amount.min(bonded.amount);
+    bonded.amount -= slashed;
+    RELAYER_STAKES.save(store, &relayer, &bonded)?;
+
+    let mut response = Response::new()
+        .add_attribute("action", "slash_relayer")
+        .add_attribute("relayer", relayer)
+        .add_attribute("amount", slashed);
+    if !slashed.is_zero() {
+        response = response.add_message(BankMsg::Send {
+            to_address: config.owner.to_string(),
+            amount: vec![Coin::new(slashed.u128(), stake.denom)],
+        });
+    }
+    Ok(response)
+}
+
+/// Whether `sender` has the stake required to relay headers bonded, never
+/// while no stake is required.
+fn is_bonded_relayer(store: &dyn Storage, config: &Config, sender: &Addr) -> ContractResult<bool> {
+    let Some(stake) = &config.relayer_stake else {
+        return Ok(false);
+    };
+    let bonded = RELAYER_STAKES.may_load(store, sender)?;
+    Ok(bonded.map_or(false, |bonded| {
+        bonded.unbonding_at.is_none() && bonded.amount >= stake.amount
+    }))
+}
+
+/// Stores relayed filter headers. Filter headers are not committed to by the
+/// block headers, so only bonded relayers, or the owner while no stake is
+/// required, may relay them.
+pub fn relay_filter_headers(
+    store: &mut dyn Storage,
+    info: MessageInfo,
+    headers: Vec<FilterHeaderEntry>,
+) -> ContractResult<Response> {
+    let config = CONFIG.load(store)?;
+    if info.sender != config.owner && !is_bonded_relayer(store, &config, &info.sender)? {
+        return Err(ContractError::Unauthorized {});
+    }
+    let mut header_queue = HeaderQueue::default();
+    header_queue.add_filter_headers(store, headers)?;
+    Ok(Response::new().add_attribute("action", "add_filter_headers"))
 }
 
 pub fn update_header_config(
@@ -31,6 +361,7 @@ pub fn update_config(
     store: &mut dyn Storage,
     info: MessageInfo,
     owner: Option<Addr>,
+    clock_source: Option<ClockSource>,
 ) -> ContractResult<Response> {
     let mut config = CONFIG.load(store)?;
     assert_eq!(info.sender, config.owner);
@@ -38,6 +369,9 @@ pub fn update_config(
     if let Some(owner) = owner {
         config.owner = owner;
     }
+    if let Some(clock_source) = clock_source {
+        config.clock_source = clock_source;
+    }
 
     CONFIG.save(store, &config)?;
     Ok(Response::new().add_attribute("action", "update_config"))
@@ -1,15 +1,24 @@
-use bitcoin::{util::merkleblock::PartialMerkleTree, BlockHash, Transaction};
+use bitcoin::{merkle_tree::PartialMerkleTree, BlockHash, Transaction, TxMerkleNode, Txid};
 use common_bitcoin::{
     adapter::{Adapter, WrappedBinary},
     error::{ContractError, ContractResult},
 };
-use cosmwasm_std::Storage;
+use cosmwasm_std::{Addr, Storage};
 
 use crate::{
+    constants::{DEFAULT_QUERY_LIMIT, MAX_QUERY_LIMIT},
     header::HeaderQueue,
-    state::{header_height, HEADER_CONFIG},
+    state::{
+        header_height, HEADER_CONFIG, HEADER_REJECTIONS, RELAYER_STAKES, RELAY_REWARD,
+        RELAY_REWARD_POOL, REWARDED_HEIGHT,
+    },
+};
+use light_client_bitcoin::{
+    header::WrappedHeader,
+    interface::{FilterHeaderEntry, HeaderConfig, HeaderRejection, RelayerStake},
+    msg::{ChainWorkResponse, HeaderAtResponse, RelayRewardsResponse},
+    uint::Uint256,
 };
-use light_client_bitcoin::interface::HeaderConfig;
 
 pub fn query_header_config(store: &dyn Storage) -> ContractResult<HeaderConfig> {
     let header_config = HEADER_CONFIG.load(store)?;
@@ -25,28 +34,145 @@ pub fn query_header_height(store: &dyn Storage) -> ContractResult<u32> {
     header_height(store)
 }
 
+pub fn query_header_time(store: &dyn Storage) -> ContractResult<u32> {
+    let header_queue = HeaderQueue::default();
+    header_queue.time(store)
+}
+
+pub fn query_median_time_past(store: &dyn Storage) -> ContractResult<u32> {
+    let header_queue = HeaderQueue::default();
+    header_queue
+        .median_time_past(store, header_queue.height(store)?)?
+        .ok_or_else(|| ContractError::Header("Not enough headers for median time past".into()))
+}
+
 pub fn query_sidechain_block_hash(store: &dyn Storage) -> ContractResult<WrappedBinary<BlockHash>> {
     let headers = HeaderQueue::default();
     let hash = WrappedBinary(headers.hash(store)?);
     Ok(hash)
 }
 
+pub fn query_block_hash_at_height(
+    store: &dyn Storage,
+    height: u32,
+) -> ContractResult<Option<WrappedBinary<BlockHash>>> {
+    let header_queue = HeaderQueue::default();
+    let header = header_queue.get_by_height(store, height, None)?;
+    Ok(header.map(|work_header| WrappedBinary(work_header.block_hash())))
+}
+
+pub fn query_filter_header(
+    store: &dyn Storage,
+    height: u32,
+) -> ContractResult<Option<FilterHeaderEntry>> {
+    let header_queue = HeaderQueue::default();
+    header_queue.filter_header(store, height)
+}
+
+pub fn query_verify_filter_header_chain(
+    store: &dyn Storage,
+    start_height: u32,
+    end_height: u32,
+) -> ContractResult<()> {
+    let header_queue = HeaderQueue::default();
+    header_queue.verify_filter_header_chain(store, start_height, end_height)
+}
+
+pub fn query_header_at_height(
+    store: &dyn Storage,
+    height: u32,
+) -> ContractResult<Option<WrappedHeader>> {
+    let header_queue = HeaderQueue::default();
+    let header = header_queue.get_by_height(store, height, None)?;
+    Ok(header.map(|work_header| work_header.header))
+}
+
+pub fn query_header_at(
+    store: &dyn Storage,
+    height: u32,
+) -> ContractResult<Option<HeaderAtResponse>> {
+    let header_queue = HeaderQueue::default();
+    let header = header_queue.get_by_height(store, height, None)?;
+    Ok(header.map(|work_header| HeaderAtResponse {
+        target: Adapter::new(work_header.header.target()),
+        chain_work: work_header.chain_work,
+        header: work_header.header,
+    }))
+}
+
+pub fn query_chain_work(
+    store: &dyn Storage,
+    from_height: u32,
+    to_height: u32,
+) -> ContractResult<ChainWorkResponse> {
+    if from_height > to_height {
+        return Err(ContractError::Header(
+            "from_height must not be above to_height".into(),
+        ));
+    }
+    let header_queue = HeaderQueue::default();
+    let initial_height = header_queue.get_initial_height(store)?;
+    let chain_work_at = |height: u32| -> ContractResult<Uint256> {
+        let header = header_queue
+            .get_by_height(store, height, Some(initial_height))?
+            .ok_or_else(|| ContractError::Header(format!("No header at height {}", height)))?;
+        Ok(*header.chain_work)
+    };
+    let from_work = chain_work_at(from_height)?;
+    let to_work = chain_work_at(to_height)?;
+    Ok(ChainWorkResponse {
+        work: Adapter::new(to_work - from_work),
+        chain_work: Adapter::new(to_work),
+    })
+}
+
 pub fn query_verify_tx_with_proof(
     store: &dyn Storage,
     btc_tx: Adapter<Transaction>,
     btc_height: u32,
     btc_proof: Adapter<PartialMerkleTree>,
+) -> ContractResult<()> {
+    query_verify_txid_with_proof(store, btc_tx.txid(), btc_height, btc_proof)
+}
+
+pub fn query_verify_txid_with_proof(
+    store: &dyn Storage,
+    txid: Txid,
+    btc_height: u32,
+    btc_proof: Adapter<PartialMerkleTree>,
 ) -> ContractResult<()> {
     let header_queue = HeaderQueue::default();
     let btc_header = header_queue
         .get_by_height(store, btc_height, None)?
         .ok_or_else(|| ContractError::App("Invalid bitcoin block height".to_string()))?;
+    verify_merkle_proof(txid, btc_header.merkle_root(), btc_proof)
+}
+
+/// Like `query_verify_txid_with_proof`, for a block pruned from the header
+/// queue which `headers` connect to a stored descendant.
+pub fn query_verify_txid_with_ancestor_proof(
+    store: &dyn Storage,
+    txid: Txid,
+    btc_height: u32,
+    btc_proof: Adapter<PartialMerkleTree>,
+    headers: Vec<WrappedHeader>,
+) -> ContractResult<()> {
+    let header_queue = HeaderQueue::default();
+    let btc_header = header_queue.verify_ancestor_segment(store, btc_height, &headers)?;
+    verify_merkle_proof(txid, btc_header.header.merkle_root, btc_proof)
+}
+
+fn verify_merkle_proof(
+    txid: Txid,
+    merkle_root: TxMerkleNode,
+    btc_proof: Adapter<PartialMerkleTree>,
+) -> ContractResult<()> {
     let mut txids = vec![];
     let mut block_indexes = vec![];
     let proof_merkle_root = btc_proof
         .extract_matches(&mut txids, &mut block_indexes)
         .map_err(|_| ContractError::BitcoinMerkleBlockError)?;
-    if proof_merkle_root != btc_header.merkle_root() {
+    if proof_merkle_root != merkle_root {
         return Err(ContractError::App(
             "Bitcoin merkle proof does not match header".to_string(),
         ))?;
@@ -56,10 +182,47 @@ pub fn query_verify_tx_with_proof(
             "Bitcoin merkle proof contains an invalid number of txids".to_string(),
         ))?;
     }
-    if txids[0] != btc_tx.txid() {
+    if txids[0] != txid {
         return Err(ContractError::App(
             "Bitcoin merkle proof does not match transaction".to_string(),
         ))?;
     }
     Ok(())
 }
+
+pub fn query_header_rejections(
+    store: &dyn Storage,
+    sender: Option<Addr>,
+    limit: Option<u32>,
+) -> ContractResult<Vec<HeaderRejection>> {
+    let limit = limit.unwrap_or(DEFAULT_QUERY_LIMIT).min(MAX_QUERY_LIMIT) as usize;
+    let mut rejections = vec![];
+    for rejection in HEADER_REJECTIONS.iter(store)?.rev() {
+        let rejection = rejection?;
+        if sender
+            .as_ref()
+            .map_or(true, |sender| rejection.sender == *sender)
+        {
+            rejections.push(rejection);
+        }
+        if rejections.len() == limit {
+            break;
+        }
+    }
+    Ok(rejections)
+}
+
+pub fn query_relayer_stake(
+    store: &dyn Storage,
+    relayer: Addr,
+) -> ContractResult<Option<RelayerStake>> {
+    Ok(RELAYER_STAKES.may_load(store, &relayer)?)
+}
+
+pub fn query_relay_rewards(store: &dyn Storage) -> ContractResult<RelayRewardsResponse> {
+    Ok(RelayRewardsResponse {
+        reward: RELAY_REWARD.may_load(store)?,
+        pool: RELAY_REWARD_POOL.may_load(store)?.unwrap_or_default(),
+        rewarded_height: REWARDED_HEIGHT.may_load(store)?.unwrap_or_default(),
+    })
+}
@@ -1,18 +1,19 @@
-use crate::constants::MAX_HEADERS_RELAY_ONE_TIME;
+use crate::constants::{
+    MAX_ANCESTOR_SEGMENT_LEN, MAX_FUTURE_BLOCK_TIME, MAX_HEADERS_RELAY_ONE_TIME,
+};
 use crate::state::header_height;
 use crate::state::CURRENT_WORK;
+use crate::state::FILTER_HEADERS;
 use crate::state::HEADERS;
 use crate::state::HEADER_CONFIG;
-use bitcoin::blockdata::block::BlockHeader;
 use common_bitcoin::adapter::Adapter;
 use common_bitcoin::error::ContractError;
 use common_bitcoin::error::ContractResult;
 use light_client_bitcoin::header::{WorkHeader, WrappedHeader};
-use light_client_bitcoin::interface::HeaderConfig;
+use light_client_bitcoin::interface::{FilterHeaderEntry, HeaderConfig};
+use light_client_bitcoin::uint::Uint256;
 use std::collections::HashMap;
 
-use bitcoin::util::uint::Uint256;
-use bitcoin::util::BitArray;
 use bitcoin::BlockHash;
 use cosmwasm_schema::serde::{Deserialize, Serialize};
 use cosmwasm_std::Storage;
@@ -82,8 +83,16 @@ impl HeaderQueue {
     /// If the headers are invalid (e.g. by not including a valid proof-of-work,
     /// using a difficulty other than what was expected, using invalid
     /// timestamps, etc.), an error will be returned and the header queue will
-    /// not be modified.    
-    pub fn add(&mut self, store: &mut dyn Storage, headers: HeaderList) -> ContractResult<()> {
+    /// not be modified.
+    ///
+    /// If `now` is given, headers more than `MAX_FUTURE_BLOCK_TIME` seconds
+    /// ahead of it are rejected.
+    pub fn add(
+        &mut self,
+        store: &mut dyn Storage,
+        headers: HeaderList,
+        now: Option<u32>,
+    ) -> ContractResult<()> {
         let headers: Vec<_> = headers.into();
 
         if headers.len() as u64 > MAX_HEADERS_RELAY_ONE_TIME {
@@ -92,7 +101,7 @@ impl HeaderQueue {
             ));
         }
 
-        self.add_into_iter(store, headers)
+        self.add_into_iter(store, headers, now)
             .map_err(|err| ContractError::App(err.to_string()))
     }
 
@@ -109,7 +118,12 @@ impl HeaderQueue {
     /// using a difficulty other than what was expected, using invalid
     /// timestamps, etc.), an error will be returned and the header queue will
     /// not be modified.
-    pub fn add_into_iter<T>(&mut self, store: &mut dyn Storage, headers: T) -> ContractResult<()>
+    pub fn add_into_iter<T>(
+        &mut self,
+        store: &mut dyn Storage,
+        headers: T,
+        now: Option<u32>,
+    ) -> ContractResult<()>
     where
         T: IntoIterator<Item = WrappedHeader>,
     {
@@ -128,17 +142,17 @@ impl HeaderQueue {
                 .ok_or_else(|| ContractError::Header("Header not found".into()))?;
 
             if first_replaced.block_hash() == first.block_hash() {
-                return Err(ContractError::Header("Provided redundant header.".into()));
+                return Err(ContractError::RedundantHeader {
+                    height: first.height,
+                });
             }
 
             removed_work = self.pop_back_to(store, first.height)?;
         }
 
-        let added_work = self.verify_and_add_headers(store, &headers)?;
+        let added_work = self.verify_and_add_headers(store, &headers, now)?;
         if added_work <= removed_work {
-            return Err(ContractError::Header(
-                "New best chain must include more work than old best chain.".into(),
-            ));
+            return Err(ContractError::InsufficientWork {});
         }
 
         // Prune the header queue if it has grown too large.
@@ -166,6 +180,7 @@ impl HeaderQueue {
         &mut self,
         store: &mut dyn Storage,
         headers: &[WrappedHeader],
+        now: Option<u32>,
     ) -> ContractResult<Uint256> {
         let first_height = headers
             .first()
@@ -181,7 +196,9 @@ impl HeaderQueue {
         // get header right before first header of headers (which are going to be relayed)
         let prev_header = [self
             .get_by_height(store, first_height - 1, None)?
-            .ok_or_else(|| ContractError::Header("Headers not connect to chain".into()))?
+            .ok_or(ContractError::HeadersNotConnected {
+                height: first_height,
+            })?
             .header];
 
         // create tupple of headers
@@ -212,10 +229,7 @@ impl HeaderQueue {
                 ));
             }
 
-            // make sure header is <= median timestamp of last 11 headers
-            if HEADERS.len(store)? >= 11 {
-                self.validate_time(store, header)?;
-            }
+            self.validate_time(store, header, now)?;
 
             let initial_height = self.get_initial_height(store)?;
 
@@ -319,11 +333,8 @@ impl HeaderQueue {
         let timespan = (header.time() - prev_retarget)
             .clamp(config.target_timespan / 4, config.target_timespan * 4);
 
-        let target_timespan = WrappedHeader::u32_to_u256(config.target_timespan);
-        let timespan = WrappedHeader::u32_to_u256(timespan);
-
-        let target = header.target() * timespan / target_timespan;
-        let target_u32 = BlockHeader::compact_target_from_u256(&target);
+        let target = header.target() * timespan / config.target_timespan;
+        let target_u32 = WrappedHeader::compact_target_from_u256(&target);
         let target = WrappedHeader::u256_from_compact(target_u32);
 
         Ok(target.min(WrappedHeader::u256_from_compact(config.max_target)))
@@ -345,54 +356,55 @@ impl HeaderQueue {
         Ok(work)
     }
 
-    /// Validate the timestamp of the passed header.
-    fn validate_time(
+    /// The median timestamp of the 11 headers ending at `height`, or `None`
+    /// if the header queue does not hold that many headers up to `height`.
+    pub fn median_time_past(
         &self,
         store: &dyn Storage,
-        current_header: &WrappedHeader,
-    ) -> ContractResult<()> {
-        let mut prev_stamps: Vec<u32> = Vec::with_capacity(11);
+        height: u32,
+    ) -> ContractResult<Option<u32>> {
         let initial_height = self.get_initial_height(store)?;
-        let height = self.height(store)?;
-        for prev_height in height - 10..=height {
-            let current_item = match self.get_by_height(store, prev_height, Some(initial_height))? {
-                Some(inner) => inner.time(),
-                None => {
-                    return Err(ContractError::Header(
-                        "Deque does not contain any elements".into(),
-                    ))
-                }
-            };
-            prev_stamps.push(current_item);
+        if height < initial_height + 10 {
+            return Ok(None);
         }
 
+        let mut prev_stamps: Vec<u32> = Vec::with_capacity(11);
+        for prev_height in height - 10..=height {
+            match self.get_by_height(store, prev_height, Some(initial_height))? {
+                Some(inner) => prev_stamps.push(inner.time()),
+                None => return Ok(None),
+            }
+        }
         prev_stamps.sort_unstable();
 
-        let median_stamp = match prev_stamps.get(5) {
-            Some(inner) => inner,
-            None => {
+        Ok(Some(prev_stamps[5]))
+    }
+
+    /// Validate the timestamp of the passed header, which must be greater
+    /// than the median time past of the previous 11 headers and, if `now` is
+    /// given, at most `MAX_FUTURE_BLOCK_TIME` seconds ahead of it.
+    fn validate_time(
+        &self,
+        store: &dyn Storage,
+        current_header: &WrappedHeader,
+        now: Option<u32>,
+    ) -> ContractResult<()> {
+        let median_stamp = self.median_time_past(store, current_header.height() - 1)?;
+        if let Some(median_stamp) = median_stamp {
+            if current_header.time() <= median_stamp {
                 return Err(ContractError::Header(
-                    "Median timestamp does not exist".into(),
+                    "Header contains an invalid timestamp".into(),
                 ));
             }
-        };
-
-        if current_header.time() <= *median_stamp {
-            return Err(ContractError::Header(
-                "Header contains an invalid timestamp".into(),
-            ));
         }
 
-        // TODO: compare timestamps with max_time_increase over the current
-        // clock time (not the previous header's time)
-        // if max(current_header.time(), previous_header.time())
-        //     - min(current_header.time(), previous_header.time())
-        //     > self.config.max_time_increase
-        // {
-        //     return Err(ContractError::Header(
-        //         "Timestamp is too far ahead of previous timestamp".into(),
-        //     ));
-        // }
+        if let Some(now) = now {
+            if current_header.time() > now.saturating_add(MAX_FUTURE_BLOCK_TIME) {
+                return Err(ContractError::Header(
+                    "Header timestamp is too far in the future".into(),
+                ));
+            }
+        }
 
         Ok(())
     }
@@ -410,6 +422,14 @@ impl HeaderQueue {
         }
     }
 
+    /// The timestamp of the last header in the header queue.
+    pub fn time(&self, store: &dyn Storage) -> ContractResult<u32> {
+        match HEADERS.back(store)? {
+            Some(inner) => Ok(inner.time()),
+            None => Err(ContractError::Header("HeaderQueue is empty".into())),
+        }
+    }
+
     /// The number of headers in the header queue.
     // TODO: remove this attribute, not sure why clippy is complaining when is_empty is defined
     #[allow(clippy::len_without_is_empty)]
@@ -464,6 +484,57 @@ impl HeaderQueue {
         Ok(header)
     }
 
+    /// Verifies a segment of headers connecting a block which is no longer
+    /// stored, at `height`, to the stored chain: the headers must be
+    /// consecutive from `height`, have valid proof-of-work, and the stored
+    /// header following the last of them must commit to its hash. Returns the
+    /// header at `height`.
+    pub fn verify_ancestor_segment<'a>(
+        &self,
+        store: &dyn Storage,
+        height: u32,
+        headers: &'a [WrappedHeader],
+    ) -> ContractResult<&'a WrappedHeader> {
+        if headers.is_empty() || headers.len() > MAX_ANCESTOR_SEGMENT_LEN {
+            return Err(ContractError::Header(format!(
+                "Header segment must contain between 1 and {} headers",
+                MAX_ANCESTOR_SEGMENT_LEN
+            )));
+        }
+        let max_target = WrappedHeader::u256_from_compact(self.config(store)?.max_target);
+        for (i, header) in headers.iter().enumerate() {
+            if header.height() != height + i as u32 {
+                return Err(ContractError::Header(
+                    "Header segment is not consecutive".into(),
+                ));
+            }
+            if i > 0 && header.prev_blockhash() != headers[i - 1].block_hash() {
+                return Err(ContractError::Header(
+                    "Header segment does not form a chain".into(),
+                ));
+            }
+            if header.target() > max_target {
+                return Err(ContractError::Header(
+                    "Header segment target is above the maximum".into(),
+                ));
+            }
+            header.validate_pow(&header.target())?;
+        }
+
+        let last = &headers[headers.len() - 1];
+        let anchor = self
+            .get_by_height(store, last.height() + 1, None)?
+            .ok_or_else(|| {
+                ContractError::Header("Header segment does not reach the stored chain".into())
+            })?;
+        if anchor.header.prev_blockhash() != last.block_hash() {
+            return Err(ContractError::Header(
+                "Header segment does not connect to the stored chain".into(),
+            ));
+        }
+        Ok(&headers[0])
+    }
+
     /// The height of the configured trusted header.    
     pub fn trusted_height(&self, store: &dyn Storage) -> ContractResult<u32> {
         let config = HEADER_CONFIG.load(store)?;
@@ -487,6 +558,126 @@ impl HeaderQueue {
         Ok(())
     }
 
+    /// Stores relayed compact block filter headers for blocks in the header
+    /// queue.
+    ///
+    /// Filter headers are not committed to by the block headers, so each one
+    /// is checked against the filter header of the previous block if that is
+    /// known. The first filter header relayed after a gap is trusted as an
+    /// anchor until the previous filter header is relayed, an anchor which
+    /// does not extend it is replaced by one that does. Consumers should check
+    /// the chain they rely on with `verify_filter_header_chain`.
+    pub fn add_filter_headers(
+        &mut self,
+        store: &mut dyn Storage,
+        entries: Vec<FilterHeaderEntry>,
+    ) -> ContractResult<()> {
+        if entries.len() as u64 > MAX_HEADERS_RELAY_ONE_TIME {
+            return Err(ContractError::Header(
+                "Exceed maximum filter headers relay at one time".into(),
+            ));
+        }
+
+        for entry in entries {
+            let header = self
+                .get_by_height(store, entry.height, None)?
+                .ok_or_else(|| ContractError::Header("Block header not found".into()))?;
+            if header.block_hash() != entry.block_hash.0 {
+                return Err(ContractError::Header(
+                    "Filter header block hash does not match header queue".into(),
+                ));
+            }
+
+            let prev = match entry.height.checked_sub(1) {
+                Some(prev_height) => self.filter_header(store, prev_height)?,
+                None => None,
+            };
+            if let Some(prev) = &prev {
+                if !entry.extends(&prev.filter_header) {
+                    return Err(ContractError::Header(
+                        "Filter header does not extend the previous filter header".into(),
+                    ));
+                }
+            }
+
+            if let Some(existing) = self.filter_header(store, entry.height)? {
+                if existing == entry {
+                    continue;
+                }
+                let broken_anchor =
+                    prev.map_or(false, |prev| !existing.extends(&prev.filter_header));
+                if !broken_anchor {
+                    return Err(ContractError::Header("Conflicting filter header".into()));
+                }
+            }
+
+            FILTER_HEADERS.save(store, entry.height, &entry)?;
+        }
+
+        Ok(())
+    }
+
+    /// Get the filter header for the block at the given height, if one was
+    /// relayed for the block currently in the header queue at that height.
+    pub fn filter_header(
+        &self,
+        store: &dyn Storage,
+        height: u32,
+    ) -> ContractResult<Option<FilterHeaderEntry>> {
+        let entry = match FILTER_HEADERS.may_load(store, height)? {
+            Some(entry) => entry,
+            None => return Ok(None),
+        };
+
+        let initial_height = self.get_initial_height(store)?;
+        if height < initial_height {
+            return Ok(None);
+        }
+
+        match self.get_by_height(store, height, Some(initial_height))? {
+            Some(header) if header.block_hash() == entry.block_hash.0 => Ok(Some(entry)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Verifies that filter headers are known for every block from
+    /// `start_height` to `end_height` and that each one extends the previous.
+    pub fn verify_filter_header_chain(
+        &self,
+        store: &dyn Storage,
+        start_height: u32,
+        end_height: u32,
+    ) -> ContractResult<()> {
+        if start_height > end_height {
+            return Err(ContractError::Header("Invalid filter header range".into()));
+        }
+        if (end_height - start_height) as u64 > MAX_HEADERS_RELAY_ONE_TIME {
+            return Err(ContractError::Header(
+                "Filter header range too large".into(),
+            ));
+        }
+
+        let missing =
+            |height| ContractError::Header(format!("Missing filter header at {}", height));
+        let mut prev = self
+            .filter_header(store, start_height)?
+            .ok_or_else(|| missing(start_height))?;
+        for height in start_height + 1..=end_height {
+            let entry = self
+                .filter_header(store, height)?
+                .ok_or_else(|| missing(height))?;
+            if !entry.extends(&prev.filter_header) {
+                return Err(ContractError::Header(format!(
+                    "Filter header chain broken at {}",
+                    height
+                )));
+            }
+            prev = entry;
+        }
+
+        Ok(())
+    }
+
     /// The network the header queue is configured for.
     pub fn network(&self) -> bitcoin::Network {
         // TODO: should be dynamic, from config
@@ -0,0 +1,130 @@
+use common_bitcoin::error::{ContractError, ContractResult};
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+use cosmwasm_std::{
+    from_json, to_json_binary, Binary, DepsMut, Env, Ibc3ChannelOpenResponse, IbcBasicResponse,
+    IbcChannel, IbcChannelCloseMsg, IbcChannelConnectMsg, IbcChannelOpenMsg,
+    IbcChannelOpenResponse, IbcOrder, IbcPacketAckMsg, IbcPacketReceiveMsg, IbcPacketTimeoutMsg,
+    IbcReceiveResponse, Storage,
+};
+use light_client_bitcoin::{
+    constants::IBC_APP_VERSION,
+    msg::{IbcQueryAck, IbcQueryMsg},
+};
+
+use crate::entrypoints::{query_header_at_height, query_verify_tx_with_proof};
+
+fn validate_channel(
+    channel: &IbcChannel,
+    counterparty_version: Option<&str>,
+) -> ContractResult<()> {
+    if channel.order != IbcOrder::Unordered {
+        return Err(ContractError::App(
+            "Header query channels must be unordered".to_string(),
+        ));
+    }
+    if channel.version != IBC_APP_VERSION {
+        return Err(ContractError::App(format!(
+            "Channel version must be {}",
+            IBC_APP_VERSION
+        )));
+    }
+    if let Some(version) = counterparty_version {
+        if version != IBC_APP_VERSION {
+            return Err(ContractError::App(format!(
+                "Counterparty version must be {}",
+                IBC_APP_VERSION
+            )));
+        }
+    }
+    Ok(())
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_channel_open(
+    _deps: DepsMut,
+    _env: Env,
+    msg: IbcChannelOpenMsg,
+) -> Result<IbcChannelOpenResponse, ContractError> {
+    validate_channel(msg.channel(), msg.counterparty_version())?;
+    Ok(Some(Ibc3ChannelOpenResponse {
+        version: IBC_APP_VERSION.to_string(),
+    }))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_channel_connect(
+    _deps: DepsMut,
+    _env: Env,
+    msg: IbcChannelConnectMsg,
+) -> Result<IbcBasicResponse, ContractError> {
+    let channel = msg.channel();
+    validate_channel(channel, msg.counterparty_version())?;
+    Ok(IbcBasicResponse::new()
+        .add_attribute("action", "ibc_channel_connect")
+        .add_attribute("channel_id", &channel.endpoint.channel_id))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_channel_close(
+    _deps: DepsMut,
+    _env: Env,
+    msg: IbcChannelCloseMsg,
+) -> Result<IbcBasicResponse, ContractError> {
+    Ok(IbcBasicResponse::new()
+        .add_attribute("action", "ibc_channel_close")
+        .add_attribute("channel_id", &msg.channel().endpoint.channel_id))
+}
+
+/// Answers a header query packet. Failing queries are acknowledged with an
+/// error instead of failing the transaction, so relayers always get an ack.
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_packet_receive(
+    deps: DepsMut,
+    _env: Env,
+    msg: IbcPacketReceiveMsg,
+) -> Result<IbcReceiveResponse, ContractError> {
+    let ack = match handle_query_packet(deps.storage, &msg.packet.data) {
+        Ok(result) => IbcQueryAck::Result(result),
+        Err(err) => IbcQueryAck::Error(err.to_string()),
+    };
+    Ok(IbcReceiveResponse::new()
+        .set_ack(to_json_binary(&ack)?)
+        .add_attribute("action", "ibc_packet_receive")
+        .add_attribute("success", matches!(ack, IbcQueryAck::Result(_)).to_string()))
+}
+
+fn handle_query_packet(store: &dyn Storage, data: &Binary) -> ContractResult<Binary> {
+    let result = match from_json::<IbcQueryMsg>(data)? {
+        IbcQueryMsg::HeaderAtHeight { height } => {
+            to_json_binary(&query_header_at_height(store, height)?)?
+        }
+        IbcQueryMsg::VerifyTxWithProof {
+            btc_tx,
+            btc_height,
+            btc_proof,
+        } => to_json_binary(&query_verify_tx_with_proof(
+            store, btc_tx, btc_height, btc_proof,
+        )?)?,
+    };
+    Ok(result)
+}
+
+/// The light client never sends packets, so there is nothing to acknowledge.
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_packet_ack(
+    _deps: DepsMut,
+    _env: Env,
+    _msg: IbcPacketAckMsg,
+) -> Result<IbcBasicResponse, ContractError> {
+    Ok(IbcBasicResponse::new())
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_packet_timeout(
+    _deps: DepsMut,
+    _env: Env,
+    _msg: IbcPacketTimeoutMsg,
+) -> Result<IbcBasicResponse, ContractError> {
+    Ok(IbcBasicResponse::new())
+}
@@ -1,4 +1,6 @@
+use super::convert;
 use crate::tests::helper::MockApp;
+use bitcoin::block::Header as BlockHeader;
 use bitcoincore_rpc_async::jsonrpc::error::RpcError;
 use bitcoind::bitcoincore_rpc::RpcApi;
 use bitcoind::{BitcoinD, Conf, P2P};
@@ -18,7 +20,7 @@ where
 async fn test_relay_bulk_headers() {
     use std::str::FromStr;
 
-    use bitcoin::{BlockHash, BlockHeader, TxMerkleNode};
+    use bitcoin::{block::Version, BlockHash, CompactTarget, TxMerkleNode};
     // Set up app
 
     let (mut app, accounts) = MockApp::new(&[
@@ -45,7 +47,7 @@ async fn test_relay_bulk_headers() {
         retargeting: true,
         min_difficulty_blocks: true,
         trusted_header: Adapter::from(BlockHeader {
-            bits: 420466436,
+            bits: CompactTarget::from_consensus(420466436),
             nonce: 732839121,
             time: 1723142223,
             merkle_root: TxMerkleNode::from_str(
@@ -56,7 +58,7 @@ async fn test_relay_bulk_headers() {
                 "000000000000000591b541ed7088c4ce52fd10a0b99a4b5db377a3c1ab198756",
             )
             .unwrap(),
-            version: 654221312,
+            version: Version::from_consensus(654221312),
         }),
     };
     app.execute(
@@ -123,7 +125,7 @@ fn reorg() {
         .unwrap()
         .height;
 
-    let tip_header = node_1.client.get_block_header(&tip_hash).unwrap();
+    let tip_header: BlockHeader = convert(&node_1.client.get_block_header(&tip_hash).unwrap());
 
     let header_config = HeaderConfig {
         max_length: 2000,
@@ -155,7 +157,7 @@ fn reorg() {
             .unwrap();
 
         let tip_hash = node_1.client.get_best_block_hash().unwrap();
-        let tip_header = node_1.client.get_block_header(&tip_hash).unwrap();
+        let tip_header: BlockHeader = convert(&node_1.client.get_block_header(&tip_hash).unwrap());
         let tip_height_info = node_1.client.get_block_header_info(&tip_hash).unwrap();
         let tip_height = tip_height_info.height;
 
@@ -186,7 +188,7 @@ fn reorg() {
         .unwrap();
 
     let tip_hash = node_1.client.get_best_block_hash().unwrap();
-    let tip_header = node_1.client.get_block_header(&tip_hash).unwrap();
+    let tip_header: BlockHeader = convert(&node_1.client.get_block_header(&tip_hash).unwrap());
     let tip_header_info = node_1.client.get_block_header_info(&tip_hash).unwrap();
     let tip_height = tip_header_info.height;
 
@@ -205,7 +207,7 @@ fn reorg() {
         node_2.client.generate_to_address(1, &bob_address).unwrap();
 
         let tip_hash = node_2.client.get_best_block_hash().unwrap();
-        let tip_header = node_2.client.get_block_header(&tip_hash).unwrap();
+        let tip_header: BlockHeader = convert(&node_2.client.get_block_header(&tip_hash).unwrap());
         let tip_header_info = node_2.client.get_block_header_info(&tip_hash).unwrap();
         let tip_height = tip_header_info.height;
 
@@ -258,7 +260,7 @@ fn reorg_competing_chain_similar() {
         .unwrap()
         .height;
 
-    let tip_header = node_1.client.get_block_header(&tip_hash).unwrap();
+    let tip_header: BlockHeader = convert(&node_1.client.get_block_header(&tip_hash).unwrap());
 
     let header_config = HeaderConfig {
         max_length: 2000,
@@ -290,7 +292,7 @@ fn reorg_competing_chain_similar() {
             .unwrap();
 
         let tip_hash = node_1.client.get_best_block_hash().unwrap();
-        let tip_header = node_1.client.get_block_header(&tip_hash).unwrap();
+        let tip_header: BlockHeader = convert(&node_1.client.get_block_header(&tip_hash).unwrap());
         let tip_header_info = node_1.client.get_block_header_info(&tip_hash).unwrap();
         let tip_height = tip_header_info.height;
 
@@ -318,7 +320,7 @@ fn reorg_competing_chain_similar() {
         node_1.client.generate_to_address(1, &bob_address).unwrap();
 
         let tip_hash = node_1.client.get_best_block_hash().unwrap();
-        let tip_header = node_1.client.get_block_header(&tip_hash).unwrap();
+        let tip_header: BlockHeader = convert(&node_1.client.get_block_header(&tip_hash).unwrap());
         let tip_header_info = node_1.client.get_block_header_info(&tip_hash).unwrap();
         let tip_height = tip_header_info.height;
 
@@ -341,7 +343,7 @@ fn reorg_competing_chain_similar() {
             .unwrap();
 
         let tip_hash = node_2.client.get_best_block_hash().unwrap();
-        let tip_header = node_2.client.get_block_header(&tip_hash).unwrap();
+        let tip_header: BlockHeader = convert(&node_2.client.get_block_header(&tip_hash).unwrap());
         let tip_header_info = node_2.client.get_block_header_info(&tip_hash).unwrap();
         let tip_height = tip_header_info.height;
 
@@ -394,7 +396,7 @@ fn reorg_deep() {
         .unwrap()
         .height;
 
-    let tip_header = node_1.client.get_block_header(&tip_hash).unwrap();
+    let tip_header: BlockHeader = convert(&node_1.client.get_block_header(&tip_hash).unwrap());
 
     let header_config = HeaderConfig {
         max_length: 2000,
@@ -426,7 +428,7 @@ fn reorg_deep() {
             .unwrap();
 
         let tip_hash = node_1.client.get_best_block_hash().unwrap();
-        let tip_header = node_1.client.get_block_header(&tip_hash).unwrap();
+        let tip_header: BlockHeader = convert(&node_1.client.get_block_header(&tip_hash).unwrap());
         let tip_header_info = node_1.client.get_block_header_info(&tip_hash).unwrap();
         let tip_height = tip_header_info.height;
 
@@ -457,7 +459,7 @@ fn reorg_deep() {
             .unwrap();
 
         let tip_hash = node_1.client.get_best_block_hash().unwrap();
-        let tip_header = node_1.client.get_block_header(&tip_hash).unwrap();
+        let tip_header: BlockHeader = convert(&node_1.client.get_block_header(&tip_hash).unwrap());
         let tip_header_info = node_1.client.get_block_header_info(&tip_hash).unwrap();
         let tip_height = tip_header_info.height;
 
@@ -477,7 +479,7 @@ fn reorg_deep() {
         node_2.client.generate_to_address(1, &bob_address).unwrap();
 
         let tip_hash = node_2.client.get_best_block_hash().unwrap();
-        let tip_header = node_2.client.get_block_header(&tip_hash).unwrap();
+        let tip_header: BlockHeader = convert(&node_2.client.get_block_header(&tip_hash).unwrap());
         let tip_header_info = node_2.client.get_block_header_info(&tip_hash).unwrap();
         let tip_height = tip_header_info.height;
 
@@ -1 +1,12 @@
 mod header;
+
+/// Converts a type of the `bitcoin` version used by `bitcoind` through its
+/// consensus encoding.
+fn convert<T, U>(value: &T) -> U
+where
+    T: bitcoind::bitcoincore_rpc::bitcoin::consensus::Encodable,
+    U: bitcoin::consensus::Decodable,
+{
+    let bytes = bitcoind::bitcoincore_rpc::bitcoin::consensus::serialize(value);
+    bitcoin::consensus::deserialize(&bytes).unwrap()
+}
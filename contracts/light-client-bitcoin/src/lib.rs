@@ -1,5 +1,6 @@
 pub mod contract;
 pub mod header;
+pub mod ibc;
 
 mod constants;
 mod entrypoints;
@@ -1,8 +1,14 @@
-use bitcoin::util::uint::Uint256;
 use common_bitcoin::{adapter::Adapter, deque::DequeExtension, error::ContractResult};
-use cosmwasm_std::Storage;
-use cw_storage_plus::Item;
-use light_client_bitcoin::{header::WorkHeader, interface::HeaderConfig, msg::Config};
+use cosmwasm_std::{Addr, Coin, StdResult, Storage, Uint128};
+use cw_storage_plus::{Item, Map};
+use light_client_bitcoin::{
+    header::WorkHeader,
+    interface::{FilterHeaderEntry, HeaderConfig, HeaderRejection, RelayerStake},
+    msg::Config,
+    uint::Uint256,
+};
+
+use crate::constants::MAX_HEADER_REJECTIONS;
 
 pub const CONFIG: Item<Config> = Item::new("config");
 pub const HEADER_CONFIG: Item<HeaderConfig> = Item::new("header");
@@ -20,6 +26,34 @@ pub const HEADER_CONFIG: Item<HeaderConfig> = Item::new("header");
 pub const HEADERS: DequeExtension<WorkHeader> = DequeExtension::new("headers");
 /// Header current work
 pub const CURRENT_WORK: Item<Adapter<Uint256>> = Item::new("current_work");
+/// Compact block filter headers by block height. An entry is only valid while
+/// its block hash matches the header at that height in `HEADERS`.
+pub const FILTER_HEADERS: Map<u32, FilterHeaderEntry> = Map::new("filter_headers");
+/// The reward paid per `RelayHeaders` call extending the chain, unset while
+/// rewards are disabled
+pub const RELAY_REWARD: Item<Coin> = Item::new("relay_reward");
+/// The funds of the reward denom set aside for relay rewards
+pub const RELAY_REWARD_POOL: Item<Uint128> = Item::new("relay_reward_pool");
+/// The highest header height a relay reward was paid for
+pub const REWARDED_HEIGHT: Item<u32> = Item::new("rewarded_height");
+/// The most recent rejected header relays, oldest first
+pub const HEADER_REJECTIONS: DequeExtension<HeaderRejection> =
+    DequeExtension::new("header_rejections");
+/// Bonded stake of header relayers
+pub const RELAYER_STAKES: Map<&Addr, RelayerStake> = Map::new("relayer_stakes");
+
+/// Appends a rejected header relay, dropping the oldest ones beyond
+/// `MAX_HEADER_REJECTIONS`.
+pub fn record_header_rejection(
+    store: &mut dyn Storage,
+    rejection: HeaderRejection,
+) -> StdResult<()> {
+    HEADER_REJECTIONS.push_back(store, &rejection)?;
+    while HEADER_REJECTIONS.len(store)? > MAX_HEADER_REJECTIONS {
+        HEADER_REJECTIONS.pop_front(store)?;
+    }
+    Ok(())
+}
 
 /// The height of the last header in the header queue.    
 pub fn header_height(store: &dyn Storage) -> ContractResult<u32> {
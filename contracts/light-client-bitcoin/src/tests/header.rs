@@ -1,24 +1,41 @@
+use std::str::FromStr;
+
+use bitcoin::block::{Header as BlockHeader, Version};
 use bitcoin::consensus::Decodable;
-use bitcoin::hashes::hex::FromHex;
 use bitcoin::hashes::sha256d::Hash;
-use bitcoin::BlockHash;
-use bitcoin::{hash_types::TxMerkleNode, BlockHeader};
+use bitcoin::hashes::Hash as _;
+use bitcoin::merkle_tree::PartialMerkleTree;
+use bitcoin::{BlockHash, CompactTarget, FilterHash, FilterHeader, TxMerkleNode, Txid};
 use chrono::{TimeZone, Utc};
-use common_bitcoin::adapter::Adapter;
-use cosmwasm_std::{from_json, testing::mock_dependencies, to_json_binary, Binary};
-
+use common_bitcoin::adapter::{Adapter, WrappedBinary};
+use common_bitcoin::error::ContractResult;
+use cosmwasm_std::{
+    coin, from_json,
+    testing::{mock_dependencies, mock_env, mock_info},
+    to_json_binary, Addr, BankMsg, Binary, CosmosMsg, Storage, Uint128,
+};
+
+use crate::constants::MAX_FUTURE_BLOCK_TIME;
+use crate::entrypoints::{
+    bond_relayer_stake, fund_relay_rewards, query_chain_work, query_header_at, query_header_height,
+    query_header_rejections, query_median_time_past, query_relay_rewards,
+    query_verify_txid_with_ancestor_proof, query_verify_txid_with_proof, relay_filter_headers,
+    relay_headers, set_relay_reward, set_relayer_stake, slash_relayer, take_relay_reward,
+    withdraw_relay_rewards,
+};
 use crate::header::HeaderQueue;
-use crate::state::{HEADERS, HEADER_CONFIG};
+use crate::state::{CONFIG, HEADERS, HEADER_CONFIG};
 use light_client_bitcoin::header::WrappedHeader;
-use light_client_bitcoin::interface::HeaderConfig;
+use light_client_bitcoin::interface::{ClockSource, FilterHeaderEntry, HeaderConfig};
+use light_client_bitcoin::msg::Config;
 
 #[test]
 fn primitive_adapter_encode_decode() {
     let stamp = Utc.with_ymd_and_hms(2009, 1, 10, 17, 39, 13).unwrap();
     //Bitcoin block 42
     let header = BlockHeader {
-        version: 0x1,
-        prev_blockhash: BlockHash::from_hex(
+        version: Version::ONE,
+        prev_blockhash: BlockHash::from_str(
             "00000000ad2b48c7032b6d7d4f2e19e54d79b1c159f5599056492f2cd7bb528b",
         )
         .unwrap(),
@@ -26,7 +43,7 @@ fn primitive_adapter_encode_decode() {
             .parse()
             .unwrap(),
         time: stamp.timestamp() as u32,
-        bits: 486_604_799,
+        bits: CompactTarget::from_consensus(486_604_799),
         nonce: 3_600_650_283,
     };
 
@@ -44,6 +61,29 @@ fn primitive_adapter_encode_decode() {
     assert_eq!(header_wasm, header);
 }
 
+#[test]
+fn work_header_stored_before_bitcoin_upgrade() {
+    use light_client_bitcoin::header::WorkHeader;
+    use light_client_bitcoin::uint::Uint256;
+
+    // block 1 and the chain work up to it, as stored with bitcoin 0.29
+    let stored = r#"{"chain_work":"AgACAAIAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=","header":{"height":1,"header":"AQAAAG/ijAq28bNywaaiRq5j90+THoNl4VoInGjWGQAAAAAAmCBR/R5Lp0S7vmgOH+4UZ3uho8NUC/exzbYG6FcjPg5hvGZJ//8AHQHjYpk="}}"#;
+    let work_header: WorkHeader = from_json(stored.as_bytes()).unwrap();
+
+    assert_eq!(work_header.height(), 1);
+    assert_eq!(
+        work_header.block_hash(),
+        BlockHash::from_str("00000000839a8e6886ab5951d76f411475428afc90947ee320161bbf18eb6048")
+            .unwrap()
+    );
+    assert_eq!(work_header.work(), Uint256::from_u64(0x1_0001_0001));
+    assert_eq!(*work_header.chain_work, Uint256::from_u64(0x2_0002_0002));
+    assert_eq!(
+        to_json_binary(&work_header).unwrap(),
+        Binary::from(stored.as_bytes())
+    );
+}
+
 #[test]
 fn add_multiple() {
     let mut deps = mock_dependencies();
@@ -51,119 +91,119 @@ fn add_multiple() {
     let stamp = Utc.with_ymd_and_hms(2009, 1, 10, 17, 44, 37).unwrap();
 
     let header_43 = BlockHeader {
-        version: 0x1,
-        prev_blockhash: BlockHash::from_hex(
+        version: Version::ONE,
+        prev_blockhash: BlockHash::from_str(
             "00000000314e90489514c787d615cea50003af2023796ccdd085b6bcc1fa28f5",
         )
         .unwrap(),
-        merkle_root: TxMerkleNode::from_hex(
+        merkle_root: TxMerkleNode::from_str(
             "2f5c03ce19e9a855ac93087a1b68fe6592bcf4bd7cbb9c1ef264d886a785894e",
         )
         .unwrap(),
         time: stamp.timestamp() as u32,
-        bits: 486_604_799,
+        bits: CompactTarget::from_consensus(486_604_799),
         nonce: 2_093_702_200,
     };
 
     let stamp = Utc.with_ymd_and_hms(2009, 1, 10, 17, 59, 21).unwrap();
 
     let header_44 = BlockHeader {
-        version: 0x1,
-        prev_blockhash: BlockHash::from_hex(
+        version: Version::ONE,
+        prev_blockhash: BlockHash::from_str(
             "00000000ac21f2862aaab177fd3c5c8b395de842f84d88c9cf3420b2d393e550",
         )
         .unwrap(),
-        merkle_root: TxMerkleNode::from_hex(
+        merkle_root: TxMerkleNode::from_str(
             "439aee1e1aa6923ad61c1990459f88de1faa3e18b4ee125f99b94b82e1e0af5f",
         )
         .unwrap(),
         time: stamp.timestamp() as u32,
-        bits: 486_604_799,
+        bits: CompactTarget::from_consensus(486_604_799),
         nonce: 429_798_192,
     };
 
     let stamp = Utc.with_ymd_and_hms(2009, 1, 10, 18, 11, 8).unwrap();
 
     let header_45 = BlockHeader {
-        version: 0x1,
-        prev_blockhash: BlockHash::from_hex(
+        version: Version::ONE,
+        prev_blockhash: BlockHash::from_str(
             "000000002978eecde8d020f7f057083bc990002fff495121d7dc1c26d00c00f8",
         )
         .unwrap(),
-        merkle_root: TxMerkleNode::from_hex(
+        merkle_root: TxMerkleNode::from_str(
             "f69778085f1e78a1ea1cfcfe3b61ffb5c99870f5ae382e41ec43cf165d66a6d9",
         )
         .unwrap(),
         time: stamp.timestamp() as u32,
-        bits: 486_604_799,
+        bits: CompactTarget::from_consensus(486_604_799),
         nonce: 2_771_238_433,
     };
 
     let stamp = Utc.with_ymd_and_hms(2009, 1, 10, 18, 23, 13).unwrap();
 
     let header_46 = BlockHeader {
-        version: 0x1,
-        prev_blockhash: BlockHash::from_hex(
+        version: Version::ONE,
+        prev_blockhash: BlockHash::from_str(
             "000000009189006e461d2f4037a819d00217412ac01900ddbf09461100b836bb",
         )
         .unwrap(),
-        merkle_root: TxMerkleNode::from_hex(
+        merkle_root: TxMerkleNode::from_str(
             "ddd4d06365155ab4caaaee552fb3d8643207bd06efe14f920698a6dd4eb22ffa",
         )
         .unwrap(),
         time: stamp.timestamp() as u32,
-        bits: 486_604_799,
+        bits: CompactTarget::from_consensus(486_604_799),
         nonce: 1_626_117_377,
     };
 
     let stamp = Utc.with_ymd_and_hms(2009, 1, 10, 18, 41, 28).unwrap();
 
     let header_47 = BlockHeader {
-        version: 0x1,
-        prev_blockhash: BlockHash::from_hex(
+        version: Version::ONE,
+        prev_blockhash: BlockHash::from_str(
             "0000000002d5f429a2e3a9d9f82b777469696deb64038803c87833aa8ee9c08e",
         )
         .unwrap(),
-        merkle_root: TxMerkleNode::from_hex(
+        merkle_root: TxMerkleNode::from_str(
             "d17b9c9c609309049dfb9005edd7011f02d7875ca7dab6effddf4648bb70eff6",
         )
         .unwrap(),
         time: stamp.timestamp() as u32,
-        bits: 486_604_799,
+        bits: CompactTarget::from_consensus(486_604_799),
         nonce: 2_957_174_816,
     };
 
     let stamp = Utc.with_ymd_and_hms(2009, 1, 10, 18, 45, 40).unwrap();
 
     let header_48 = BlockHeader {
-        version: 0x1,
-        prev_blockhash: BlockHash::from_hex(
+        version: Version::ONE,
+        prev_blockhash: BlockHash::from_str(
             "000000001a5c4531f86aa874e711e1882038336e2610f70ce750cdd690c57a81",
         )
         .unwrap(),
-        merkle_root: TxMerkleNode::from_hex(
+        merkle_root: TxMerkleNode::from_str(
             "32edede0b7d0c37340a665de057f418df634452f6bb80dcb8a5ff0aeddf1158a",
         )
         .unwrap(),
         time: stamp.timestamp() as u32,
-        bits: 486_604_799,
+        bits: CompactTarget::from_consensus(486_604_799),
         nonce: 3_759_171_867,
     };
 
     let stamp = Utc.with_ymd_and_hms(2009, 1, 10, 18, 56, 42).unwrap();
 
     let header_49 = BlockHeader {
-        version: 0x1,
-        prev_blockhash: BlockHash::from_hex(
+        version: Version::ONE,
+        prev_blockhash: BlockHash::from_str(
             "0000000088960278f4060b8747027b2aac0eb443aedbb1b75d1a72cf71826e89",
         )
         .unwrap(),
-        merkle_root: TxMerkleNode::from_hex(
+        merkle_root: TxMerkleNode::from_str(
             "194c9715279d8626bc66f2b6552f2ae67b3df3a00b88553245b12bffffad5b59",
         )
         .unwrap(),
         time: stamp.timestamp() as u32,
-        bits: 486_604_799,
+        bits: CompactTarget::from_consensus(486_604_799),
         nonce: 3_014_810_412,
     };
 
@@ -188,17 +228,17 @@ fn add_multiple() {
         retargeting: true,
         min_difficulty_blocks: false,
         trusted_header: BlockHeader {
-            version: 1,
-            prev_blockhash: BlockHash::from_hex(
+            version: Version::ONE,
+            prev_blockhash: BlockHash::from_str(
                 "00000000ad2b48c7032b6d7d4f2e19e54d79b1c159f5599056492f2cd7bb528b",
             )
             .unwrap(),
-            merkle_root: TxMerkleNode::from_hex(
+            merkle_root: TxMerkleNode::from_str(
                 "27c4d937dca276fb2b61e579902e8a876fd5b5abc17590410ced02d5a9f8e483",
             )
             .unwrap(),
             time: 1231609153,
-            bits: 486604799,
+            bits: CompactTarget::from_consensus(486604799),
             nonce: 3600650283,
         }
         .into(),
@@ -213,7 +253,8 @@ fn add_multiple() {
 
     let mut q = HeaderQueue::default();
     q.configure(deps.as_mut().storage, test_config).unwrap();
-    q.add(deps.as_mut().storage, header_list.into()).unwrap();
+    q.add(deps.as_mut().storage, header_list.into(), None)
+        .unwrap();
 }
 
 #[test]
@@ -222,19 +263,19 @@ fn add_into_iterator() {
     let stamp = Utc.with_ymd_and_hms(2009, 1, 10, 17, 44, 37).unwrap();
 
     let header = BlockHeader {
-        version: 0x1,
-        prev_blockhash: Hash::from_hex(
+        version: Version::ONE,
+        prev_blockhash: Hash::from_str(
             "00000000314e90489514c787d615cea50003af2023796ccdd085b6bcc1fa28f5",
         )
         .unwrap()
         .into(),
-        merkle_root: Hash::from_hex(
+        merkle_root: Hash::from_str(
             "2f5c03ce19e9a855ac93087a1b68fe6592bcf4bd7cbb9c1ef264d886a785894e",
         )
         .unwrap()
         .into(),
         time: stamp.timestamp() as u32,
-        bits: 486_604_799,
+        bits: CompactTarget::from_consensus(486_604_799),
         nonce: 2_093_702_200,
     };
 
@@ -249,19 +290,19 @@ fn add_into_iterator() {
         retargeting: true,
         min_difficulty_blocks: false,
         trusted_header: BlockHeader {
-            version: 1,
-            prev_blockhash: Hash::from_hex(
+            version: Version::ONE,
+            prev_blockhash: Hash::from_str(
                 "00000000ad2b48c7032b6d7d4f2e19e54d79b1c159f5599056492f2cd7bb528b",
             )
             .unwrap()
             .into(),
-            merkle_root: Hash::from_hex(
+            merkle_root: Hash::from_str(
                 "27c4d937dca276fb2b61e579902e8a876fd5b5abc17590410ced02d5a9f8e483",
             )
             .unwrap()
             .into(),
             time: 1231609153,
-            bits: 486604799,
+            bits: CompactTarget::from_consensus(486604799),
             nonce: 3600650283,
         }
         .into(),
@@ -277,7 +318,8 @@ fn add_into_iterator() {
     let header_list = [WrappedHeader::new(adapter, 43)];
     let mut q = HeaderQueue::default();
     q.configure(deps.as_mut().storage, test_config).unwrap();
-    q.add_into_iter(deps.as_mut().storage, header_list).unwrap();
+    q.add_into_iter(deps.as_mut().storage, header_list, None)
+        .unwrap();
 }
 
 #[test]
@@ -287,17 +329,17 @@ fn add_wrong_bits_non_retarget() {
     let stamp = Utc.with_ymd_and_hms(2009, 1, 10, 17, 44, 37).unwrap();
 
     let header = BlockHeader {
-        version: 0x1,
-        prev_blockhash: BlockHash::from_hex(
+        version: Version::ONE,
+        prev_blockhash: BlockHash::from_str(
             "00000000314e90489514c787d615cea50003af2023796ccdd085b6bcc1fa28f5",
         )
         .unwrap(),
-        merkle_root: TxMerkleNode::from_hex(
+        merkle_root: TxMerkleNode::from_str(
             "2f5c03ce19e9a855ac93087a1b68fe6592bcf4bd7cbb9c1ef264d886a785894e",
         )
         .unwrap(),
         time: stamp.timestamp() as u32,
-        bits: 486_604_420,
+        bits: CompactTarget::from_consensus(486_604_420),
         nonce: 2_093_702_200,
     };
 
@@ -312,19 +354,19 @@ fn add_wrong_bits_non_retarget() {
         retargeting: true,
         min_difficulty_blocks: false,
         trusted_header: BlockHeader {
-            version: 1,
-            prev_blockhash: Hash::from_hex(
+            version: Version::ONE,
+            prev_blockhash: Hash::from_str(
                 "00000000ad2b48c7032b6d7d4f2e19e54d79b1c159f5599056492f2cd7bb528b",
             )
             .unwrap()
             .into(),
-            merkle_root: Hash::from_hex(
+            merkle_root: Hash::from_str(
                 "27c4d937dca276fb2b61e579902e8a876fd5b5abc17590410ced02d5a9f8e483",
             )
             .unwrap()
             .into(),
             time: 1231609153,
-            bits: 486604799,
+            bits: CompactTarget::from_consensus(486604799),
             nonce: 3600650283,
         }
         .into(),
@@ -341,5 +383,594 @@ fn add_wrong_bits_non_retarget() {
     let header_list = [WrappedHeader::new(adapter, 43)];
     let mut q = HeaderQueue::default();
     q.configure(deps.as_mut().storage, test_config).unwrap();
-    q.add_into_iter(deps.as_mut().storage, header_list).unwrap();
+    q.add_into_iter(deps.as_mut().storage, header_list, None)
+        .unwrap();
+}
+
+#[test]
+fn filter_header_chain() {
+    let mut deps = mock_dependencies();
+
+    let stamp = Utc.with_ymd_and_hms(2009, 1, 10, 17, 44, 37).unwrap();
+    let header_43 = BlockHeader {
+        version: Version::ONE,
+        prev_blockhash: BlockHash::from_str(
+            "00000000314e90489514c787d615cea50003af2023796ccdd085b6bcc1fa28f5",
+        )
+        .unwrap(),
+        merkle_root: TxMerkleNode::from_str(
+            "2f5c03ce19e9a855ac93087a1b68fe6592bcf4bd7cbb9c1ef264d886a785894e",
+        )
+        .unwrap(),
+        time: stamp.timestamp() as u32,
+        bits: CompactTarget::from_consensus(486_604_799),
+        nonce: 2_093_702_200,
+    };
+    let trusted_header = BlockHeader {
+        version: Version::ONE,
+        prev_blockhash: BlockHash::from_str(
+            "00000000ad2b48c7032b6d7d4f2e19e54d79b1c159f5599056492f2cd7bb528b",
+        )
+        .unwrap(),
+        merkle_root: TxMerkleNode::from_str(
+            "27c4d937dca276fb2b61e579902e8a876fd5b5abc17590410ced02d5a9f8e483",
+        )
+        .unwrap(),
+        time: 1231609153,
+        bits: CompactTarget::from_consensus(486604799),
+        nonce: 3600650283,
+    };
+    let test_config = HeaderConfig {
+        max_length: 2000,
+        max_time_increase: 8 * 60 * 60,
+        trusted_height: 42,
+        retarget_interval: 2016,
+        target_spacing: 10 * 60,
+        target_timespan: 2016 * (10 * 60),
+        max_target: 0x1d00ffff,
+        retargeting: true,
+        min_difficulty_blocks: false,
+        trusted_header: trusted_header.into(),
+    };
+
+    let mut q = HeaderQueue::default();
+    q.configure(deps.as_mut().storage, test_config).unwrap();
+    q.add(
+        deps.as_mut().storage,
+        vec![WrappedHeader::new(Adapter::new(header_43), 43)].into(),
+        None,
+    )
+    .unwrap();
+
+    let entry_42 = FilterHeaderEntry {
+        height: 42,
+        block_hash: WrappedBinary(trusted_header.block_hash()),
+        filter_hash: WrappedBinary(FilterHash::hash(&[42])),
+        filter_header: WrappedBinary(FilterHeader::hash(&[0])),
+    };
+    let filter_hash_43 = FilterHash::hash(&[43]);
+    let mut preimage = filter_hash_43.to_byte_array().to_vec();
+    preimage.extend_from_slice(&entry_42.filter_header.0.to_byte_array());
+    let entry_43 = FilterHeaderEntry {
+        height: 43,
+        block_hash: WrappedBinary(header_43.block_hash()),
+        filter_hash: WrappedBinary(filter_hash_43),
+        filter_header: WrappedBinary(FilterHeader::hash(&preimage)),
+    };
+
+    // only the owner relays filter headers while no stake is required
+    CONFIG
+        .save(
+            deps.as_mut().storage,
+            &Config {
+                owner: Addr::unchecked("owner"),
+                clock_source: ClockSource::Disabled,
+                relayer_stake: None,
+                relayer_unbonding_period: 0,
+            },
+        )
+        .unwrap();
+    assert!(relay_filter_headers(
+        deps.as_mut().storage,
+        mock_info("relayer", &[]),
+        vec![entry_42.clone()],
+    )
+    .is_err());
+
+    // a filter header relayed without its predecessor is trusted as an anchor
+    let bad_entry = FilterHeaderEntry {
+        filter_header: WrappedBinary(FilterHeader::hash(&[1])),
+        ..entry_43.clone()
+    };
+    relay_filter_headers(
+        deps.as_mut().storage,
+        mock_info("owner", &[]),
+        vec![bad_entry.clone()],
+    )
+    .unwrap();
+    q.add_filter_headers(deps.as_mut().storage, vec![entry_42.clone()])
+        .unwrap();
+    assert!(q
+        .verify_filter_header_chain(deps.as_ref().storage, 42, 43)
+        .is_err());
+
+    // a filter header which doesn't extend the previous one is rejected
+    let unrelated = FilterHeaderEntry {
+        filter_header: WrappedBinary(FilterHeader::hash(&[2])),
+        ..entry_43.clone()
+    };
+    assert!(q
+        .add_filter_headers(deps.as_mut().storage, vec![unrelated])
+        .is_err());
+
+    // the anchor is replaced by the filter header extending its predecessor
+    q.add_filter_headers(deps.as_mut().storage, vec![entry_43.clone()])
+        .unwrap();
+    // which can not be replaced again
+    assert!(q
+        .add_filter_headers(deps.as_mut().storage, vec![bad_entry])
+        .is_err());
+    q.verify_filter_header_chain(deps.as_ref().storage, 42, 43)
+        .unwrap();
+    assert_eq!(
+        q.filter_header(deps.as_ref().storage, 43).unwrap(),
+        Some(entry_43)
+    );
+    assert_eq!(q.filter_header(deps.as_ref().storage, 44).unwrap(), None);
+}
+
+#[test]
+fn chain_work_between_headers() {
+    let mut deps = mock_dependencies();
+
+    let stamp = Utc.with_ymd_and_hms(2009, 1, 10, 17, 44, 37).unwrap();
+    let header_43 = BlockHeader {
+        version: Version::ONE,
+        prev_blockhash: BlockHash::from_str(
+            "00000000314e90489514c787d615cea50003af2023796ccdd085b6bcc1fa28f5",
+        )
+        .unwrap(),
+        merkle_root: TxMerkleNode::from_str(
+            "2f5c03ce19e9a855ac93087a1b68fe6592bcf4bd7cbb9c1ef264d886a785894e",
+        )
+        .unwrap(),
+        time: stamp.timestamp() as u32,
+        bits: CompactTarget::from_consensus(486_604_799),
+        nonce: 2_093_702_200,
+    };
+    let trusted_header = BlockHeader {
+        version: Version::ONE,
+        prev_blockhash: BlockHash::from_str(
+            "00000000ad2b48c7032b6d7d4f2e19e54d79b1c159f5599056492f2cd7bb528b",
+        )
+        .unwrap(),
+        merkle_root: TxMerkleNode::from_str(
+            "27c4d937dca276fb2b61e579902e8a876fd5b5abc17590410ced02d5a9f8e483",
+        )
+        .unwrap(),
+        time: 1231609153,
+        bits: CompactTarget::from_consensus(486604799),
+        nonce: 3600650283,
+    };
+    let test_config = HeaderConfig {
+        max_length: 2000,
+        max_time_increase: 8 * 60 * 60,
+        trusted_height: 42,
+        retarget_interval: 2016,
+        target_spacing: 10 * 60,
+        target_timespan: 2016 * (10 * 60),
+        max_target: 0x1d00ffff,
+        retargeting: true,
+        min_difficulty_blocks: false,
+        trusted_header: trusted_header.into(),
+    };
+
+    let mut q = HeaderQueue::default();
+    q.configure(deps.as_mut().storage, test_config).unwrap();
+    q.add(
+        deps.as_mut().storage,
+        vec![WrappedHeader::new(Adapter::new(header_43), 43)].into(),
+        None,
+    )
+    .unwrap();
+
+    let work_42 = trusted_header.work();
+    let work_43 = header_43.work();
+    let at_43 = query_header_at(deps.as_ref().storage, 43).unwrap().unwrap();
+    assert_eq!(*at_43.chain_work, work_42 + work_43);
+    assert_eq!(*at_43.target, header_43.target());
+    assert!(query_header_at(deps.as_ref().storage, 44)
+        .unwrap()
+        .is_none());
+
+    let chain_work = query_chain_work(deps.as_ref().storage, 42, 43).unwrap();
+    assert_eq!(*chain_work.work, work_43);
+    assert_eq!(*chain_work.chain_work, work_42 + work_43);
+    assert!(query_chain_work(deps.as_ref().storage, 43, 42).is_err());
+    assert!(query_chain_work(deps.as_ref().storage, 42, 44).is_err());
+}
+
+#[test]
+fn relay_reward_paid_once_per_height() {
+    let mut deps = mock_dependencies();
+
+    let stamp = Utc.with_ymd_and_hms(2009, 1, 10, 17, 44, 37).unwrap();
+    let header_43 = BlockHeader {
+        version: Version::ONE,
+        prev_blockhash: BlockHash::from_str(
+            "00000000314e90489514c787d615cea50003af2023796ccdd085b6bcc1fa28f5",
+        )
+        .unwrap(),
+        merkle_root: TxMerkleNode::from_str(
+            "2f5c03ce19e9a855ac93087a1b68fe6592bcf4bd7cbb9c1ef264d886a785894e",
+        )
+        .unwrap(),
+        time: stamp.timestamp() as u32,
+        bits: CompactTarget::from_consensus(486_604_799),
+        nonce: 2_093_702_200,
+    };
+    let test_config = HeaderConfig {
+        max_length: 2000,
+        max_time_increase: 8 * 60 * 60,
+        trusted_height: 42,
+        retarget_interval: 2016,
+        target_spacing: 10 * 60,
+        target_timespan: 2016 * (10 * 60),
+        max_target: 0x1d00ffff,
+        retargeting: true,
+        min_difficulty_blocks: false,
+        trusted_header: BlockHeader {
+            version: Version::ONE,
+            prev_blockhash: BlockHash::from_str(
+                "00000000ad2b48c7032b6d7d4f2e19e54d79b1c159f5599056492f2cd7bb528b",
+            )
+            .unwrap(),
+            merkle_root: TxMerkleNode::from_str(
+                "27c4d937dca276fb2b61e579902e8a876fd5b5abc17590410ced02d5a9f8e483",
+            )
+            .unwrap(),
+            time: 1231609153,
+            bits: CompactTarget::from_consensus(486604799),
+            nonce: 3600650283,
+        }
+        .into(),
+    };
+    let mut q = HeaderQueue::default();
+    q.configure(deps.as_mut().storage, test_config).unwrap();
+    CONFIG
+        .save(
+            deps.as_mut().storage,
+            &Config {
+                owner: Addr::unchecked("owner"),
+                clock_source: ClockSource::Disabled,
+                relayer_stake: None,
+                relayer_unbonding_period: 0,
+            },
+        )
+        .unwrap();
+
+    let reward = coin(100, "orai");
+    set_relay_reward(
+        deps.as_mut().storage,
+        mock_info("owner", &[]),
+        Some(reward.clone()),
+    )
+    .unwrap();
+    assert!(fund_relay_rewards(deps.as_mut().storage, mock_info("funder", &[])).is_err());
+    // other denoms would be stuck in the contract
+    assert!(fund_relay_rewards(
+        deps.as_mut().storage,
+        mock_info("funder", &[coin(150, "orai"), coin(1, "uatom")]),
+    )
+    .is_err());
+    fund_relay_rewards(
+        deps.as_mut().storage,
+        mock_info("funder", &[coin(150, "orai")]),
+    )
+    .unwrap();
+
+    let res = relay_headers(
+        deps.as_mut().storage,
+        &mock_env(),
+        mock_info("relayer", &[]),
+        vec![WrappedHeader::new(Adapter::new(header_43), 43)],
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: "relayer".to_string(),
+            amount: vec![reward.clone()],
+        })
+    );
+
+    // relaying up to an already rewarded height, e.g. after a reorg, pays
+    // nothing
+    assert_eq!(take_relay_reward(deps.as_mut().storage, 42).unwrap(), None);
+
+    let rewards = query_relay_rewards(deps.as_ref().storage).unwrap();
+    assert_eq!(rewards.reward, Some(reward));
+    assert_eq!(rewards.pool.u128(), 50);
+    assert_eq!(rewards.rewarded_height, 43);
+
+    // the funded pool pins the reward denom
+    assert!(set_relay_reward(
+        deps.as_mut().storage,
+        mock_info("owner", &[]),
+        Some(coin(1, "uatom")),
+    )
+    .is_err());
+
+    // the owner can take the pool back, capped at its balance
+    let res = withdraw_relay_rewards(
+        deps.as_mut().storage,
+        mock_info("owner", &[]),
+        Some(Uint128::new(80)),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: "owner".to_string(),
+            amount: vec![coin(50, "orai")],
+        })
+    );
+    assert!(query_relay_rewards(deps.as_ref().storage)
+        .unwrap()
+        .pool
+        .is_zero());
+
+    // without a stake required, an invalid relay fails and leaves no evidence
+    assert!(relay_headers(
+        deps.as_mut().storage,
+        &mock_env(),
+        mock_info("relayer", &[]),
+        vec![WrappedHeader::new(Adapter::new(header_43), 43)],
+    )
+    .is_err());
+    assert!(query_header_rejections(deps.as_ref().storage, None, None)
+        .unwrap()
+        .is_empty());
+
+    // with a stake required, only bonded relayers can relay
+    set_relayer_stake(
+        deps.as_mut().storage,
+        mock_info("owner", &[]),
+        Some(coin(1_000, "orai")),
+        60,
+    )
+    .unwrap();
+    assert!(relay_headers(
+        deps.as_mut().storage,
+        &mock_env(),
+        mock_info("relayer", &[]),
+        vec![WrappedHeader::new(Adapter::new(header_43), 43)],
+    )
+    .is_err());
+    bond_relayer_stake(
+        deps.as_mut().storage,
+        mock_info("relayer", &[coin(1_000, "orai")]),
+    )
+    .unwrap();
+
+    // and their invalid relays are recorded as evidence
+    let res = relay_headers(
+        deps.as_mut().storage,
+        &mock_env(),
+        mock_info("relayer", &[]),
+        vec![WrappedHeader::new(Adapter::new(header_43), 43)],
+    )
+    .unwrap();
+    assert!(res.messages.is_empty());
+    assert_eq!(query_header_height(deps.as_ref().storage).unwrap(), 43);
+    let rejections = query_header_rejections(
+        deps.as_ref().storage,
+        Some(Addr::unchecked("relayer")),
+        None,
+    )
+    .unwrap();
+    assert_eq!(rejections.len(), 1);
+    assert_eq!(rejections[0].height, 43);
+    assert_eq!(rejections[0].header_hash.0, header_43.block_hash());
+    assert!(
+        query_header_rejections(deps.as_ref().storage, Some(Addr::unchecked("other")), None)
+            .unwrap()
+            .is_empty()
+    );
+
+    let res = slash_relayer(
+        deps.as_mut().storage,
+        mock_info("owner", &[]),
+        Addr::unchecked("relayer"),
+        coin(400, "orai").amount,
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: "owner".to_string(),
+            amount: vec![coin(400, "orai")],
+        })
+    );
+    // slashed below the required stake
+    assert!(relay_headers(
+        deps.as_mut().storage,
+        &mock_env(),
+        mock_info("relayer", &[]),
+        vec![WrappedHeader::new(Adapter::new(header_43), 43)],
+    )
+    .is_err());
+}
+
+#[test]
+fn verify_proof_at_pruned_ancestor() {
+    let mut deps = mock_dependencies();
+
+    let stamp = Utc.with_ymd_and_hms(2009, 1, 10, 17, 44, 37).unwrap();
+    let header_43 = BlockHeader {
+        version: Version::ONE,
+        prev_blockhash: BlockHash::from_str(
+            "00000000314e90489514c787d615cea50003af2023796ccdd085b6bcc1fa28f5",
+        )
+        .unwrap(),
+        merkle_root: TxMerkleNode::from_str(
+            "2f5c03ce19e9a855ac93087a1b68fe6592bcf4bd7cbb9c1ef264d886a785894e",
+        )
+        .unwrap(),
+        time: stamp.timestamp() as u32,
+        bits: CompactTarget::from_consensus(486_604_799),
+        nonce: 2_093_702_200,
+    };
+    let header_42 = BlockHeader {
+        version: Version::ONE,
+        prev_blockhash: BlockHash::from_str(
+            "00000000ad2b48c7032b6d7d4f2e19e54d79b1c159f5599056492f2cd7bb528b",
+        )
+        .unwrap(),
+        merkle_root: TxMerkleNode::from_str(
+            "27c4d937dca276fb2b61e579902e8a876fd5b5abc17590410ced02d5a9f8e483",
+        )
+        .unwrap(),
+        time: 1231609153,
+        bits: CompactTarget::from_consensus(486604799),
+        nonce: 3600650283,
+    };
+    // the queue starts at block 43, block 42 is no longer stored
+    let test_config = HeaderConfig {
+        max_length: 2000,
+        max_time_increase: 8 * 60 * 60,
+        trusted_height: 43,
+        retarget_interval: 2016,
+        target_spacing: 10 * 60,
+        target_timespan: 2016 * (10 * 60),
+        max_target: 0x1d00ffff,
+        retargeting: true,
+        min_difficulty_blocks: false,
+        trusted_header: header_43.into(),
+    };
+    let mut q = HeaderQueue::default();
+    q.configure(deps.as_mut().storage, test_config).unwrap();
+
+    // block 42 only contains its coinbase transaction
+    let txid = Txid::from_raw_hash(header_42.merkle_root.to_raw_hash());
+    let proof = PartialMerkleTree::from_txids(&[txid], &[true]);
+    let segment = vec![WrappedHeader::new(Adapter::new(header_42), 42)];
+    assert!(query_verify_txid_with_proof(
+        deps.as_ref().storage,
+        txid,
+        42,
+        Adapter::new(proof.clone())
+    )
+    .is_err());
+    query_verify_txid_with_ancestor_proof(
+        deps.as_ref().storage,
+        txid,
+        42,
+        Adapter::new(proof.clone()),
+        segment.clone(),
+    )
+    .unwrap();
+
+    // the segment must start at the proven height
+    assert!(query_verify_txid_with_ancestor_proof(
+        deps.as_ref().storage,
+        txid,
+        41,
+        Adapter::new(proof.clone()),
+        vec![WrappedHeader::new(Adapter::new(header_42), 41)],
+    )
+    .is_err());
+    // a header without valid proof-of-work is rejected
+    let mut forged = header_42;
+    forged.nonce += 1;
+    assert!(q
+        .verify_ancestor_segment(
+            deps.as_ref().storage,
+            42,
+            &[WrappedHeader::new(Adapter::new(forged), 42)]
+        )
+        .is_err());
+    assert!(q
+        .verify_ancestor_segment(deps.as_ref().storage, 42, &[])
+        .is_err());
+}
+
+/// Mines a regtest difficulty header on top of `prev` with the given time
+/// and adds it to the header queue.
+fn relay_mined_header(
+    store: &mut dyn Storage,
+    prev: &BlockHeader,
+    time: u32,
+    now: Option<u32>,
+) -> ContractResult<BlockHeader> {
+    let mut header = BlockHeader {
+        version: Version::ONE,
+        prev_blockhash: prev.block_hash(),
+        merkle_root: TxMerkleNode::all_zeros(),
+        time,
+        bits: prev.bits,
+        nonce: 0,
+    };
+    while header.validate_pow(header.target()).is_err() {
+        header.nonce += 1;
+    }
+    let mut q = HeaderQueue::default();
+    let height = q.height(store)? + 1;
+    let wrapped = WrappedHeader::new(Adapter::new(header), height);
+    q.add(store, vec![wrapped].into(), now)?;
+    Ok(header)
+}
+
+#[test]
+fn median_time_past_and_future_bound() {
+    let mut deps = mock_dependencies();
+    let start = 1_600_000_000;
+    let trusted_header = BlockHeader {
+        version: Version::ONE,
+        prev_blockhash: BlockHash::all_zeros(),
+        merkle_root: TxMerkleNode::all_zeros(),
+        time: start,
+        bits: CompactTarget::from_consensus(0x207fffff),
+        nonce: 0,
+    };
+    let test_config = HeaderConfig {
+        max_length: 2000,
+        max_time_increase: 8 * 60 * 60,
+        trusted_height: 1,
+        retarget_interval: 2016,
+        target_spacing: 10 * 60,
+        target_timespan: 2016 * (10 * 60),
+        max_target: 0x207fffff,
+        retargeting: true,
+        min_difficulty_blocks: false,
+        trusted_header: trusted_header.into(),
+    };
+    let mut q = HeaderQueue::default();
+    q.configure(deps.as_mut().storage, test_config).unwrap();
+    let store = deps.as_mut().storage;
+
+    // the median time past needs 11 headers
+    let mut tip = trusted_header;
+    for i in 1..=10 {
+        tip = relay_mined_header(store, &tip, start + i * 600, None).unwrap();
+    }
+    assert_eq!(q.median_time_past(store, 10).unwrap(), None);
+    assert_eq!(
+        q.median_time_past(store, 11).unwrap(),
+        Some(start + 5 * 600)
+    );
+    assert_eq!(query_median_time_past(store).unwrap(), start + 5 * 600);
+
+    // the next header must be later than the median time past, even if it is
+    // earlier than its parent
+    assert!(relay_mined_header(store, &tip, start + 5 * 600, None).is_err());
+    tip = relay_mined_header(store, &tip, start + 5 * 600 + 1, None).unwrap();
+
+    // headers more than 2 hours ahead of the clock are rejected, regardless of
+    // `max_time_increase`
+    let now = start + 7000;
+    assert!(relay_mined_header(store, &tip, now + MAX_FUTURE_BLOCK_TIME + 1, Some(now)).is_err());
+    tip = relay_mined_header(store, &tip, now + MAX_FUTURE_BLOCK_TIME, Some(now)).unwrap();
+    // without a clock only the median time past applies
+    relay_mined_header(store, &tip, now + 4 * MAX_FUTURE_BLOCK_TIME, None).unwrap();
 }
@@ -0,0 +1,79 @@
+use std::str::FromStr;
+
+use bitcoin::block::{Header as BlockHeader, Version};
+use bitcoin::{BlockHash, CompactTarget, TxMerkleNode};
+use cosmwasm_std::{
+    from_json,
+    testing::{mock_dependencies, mock_env, mock_ibc_channel_open_init, mock_ibc_packet_recv},
+    IbcOrder,
+};
+use light_client_bitcoin::{
+    constants::IBC_APP_VERSION,
+    header::WrappedHeader,
+    interface::HeaderConfig,
+    msg::{IbcQueryAck, IbcQueryMsg},
+};
+
+use crate::{
+    header::HeaderQueue,
+    ibc::{ibc_channel_open, ibc_packet_receive},
+};
+
+#[test]
+fn ibc_header_query() {
+    let mut deps = mock_dependencies();
+
+    let trusted_header = BlockHeader {
+        version: Version::ONE,
+        prev_blockhash: BlockHash::from_str(
+            "00000000ad2b48c7032b6d7d4f2e19e54d79b1c159f5599056492f2cd7bb528b",
+        )
+        .unwrap(),
+        merkle_root: TxMerkleNode::from_str(
+            "27c4d937dca276fb2b61e579902e8a876fd5b5abc17590410ced02d5a9f8e483",
+        )
+        .unwrap(),
+        time: 1231609153,
+        bits: CompactTarget::from_consensus(486604799),
+        nonce: 3600650283,
+    };
+    let test_config = HeaderConfig {
+        max_length: 2000,
+        max_time_increase: 8 * 60 * 60,
+        trusted_height: 42,
+        retarget_interval: 2016,
+        target_spacing: 10 * 60,
+        target_timespan: 2016 * (10 * 60),
+        max_target: 0x1d00ffff,
+        retargeting: true,
+        min_difficulty_blocks: false,
+        trusted_header: trusted_header.into(),
+    };
+    let mut q = HeaderQueue::default();
+    q.configure(deps.as_mut().storage, test_config).unwrap();
+
+    let open_msg = mock_ibc_channel_open_init("channel-0", IbcOrder::Ordered, IBC_APP_VERSION);
+    assert!(ibc_channel_open(deps.as_mut(), mock_env(), open_msg).is_err());
+    let open_msg = mock_ibc_channel_open_init("channel-0", IbcOrder::Unordered, "ics20-1");
+    assert!(ibc_channel_open(deps.as_mut(), mock_env(), open_msg).is_err());
+    let open_msg = mock_ibc_channel_open_init("channel-0", IbcOrder::Unordered, IBC_APP_VERSION);
+    ibc_channel_open(deps.as_mut(), mock_env(), open_msg).unwrap();
+
+    let packet =
+        mock_ibc_packet_recv("channel-0", &IbcQueryMsg::HeaderAtHeight { height: 42 }).unwrap();
+    let res = ibc_packet_receive(deps.as_mut(), mock_env(), packet).unwrap();
+    let header = match from_json(res.acknowledgement).unwrap() {
+        IbcQueryAck::Result(result) => from_json::<Option<WrappedHeader>>(result).unwrap(),
+        IbcQueryAck::Error(err) => panic!("{}", err),
+    };
+    assert_eq!(header.unwrap().block_hash(), trusted_header.block_hash());
+
+    // failing queries are acknowledged with an error
+    let packet =
+        mock_ibc_packet_recv("channel-0", &IbcQueryMsg::HeaderAtHeight { height: 10 }).unwrap();
+    let res = ibc_packet_receive(deps.as_mut(), mock_env(), packet).unwrap();
+    assert!(matches!(
+        from_json(res.acknowledgement).unwrap(),
+        IbcQueryAck::Error(_)
+    ));
+}
@@ -1,2 +1,3 @@
 mod header;
 pub mod helper;
+mod ibc;
@@ -0,0 +1,100 @@
+use std::str::FromStr;
+
+use bitcoin::{Address, Network, Script, ScriptBuf};
+
+use crate::error::{ContractError, ContractResult};
+
+/// Parses a Bitcoin address, rejecting addresses of other networks and of
+/// unknown types (e.g. future witness versions).
+pub fn parse_address(address: &str, network: Network) -> ContractResult<Address> {
+    let parsed =
+        Address::from_str(address).map_err(|err| ContractError::Address(err.to_string()))?;
+    if !parsed.is_valid_for_network(network) {
+        return Err(ContractError::AddressNetwork {
+            address: address.to_string(),
+            network: network.to_string(),
+        });
+    }
+    let parsed = parsed.assume_checked();
+    if parsed.address_type().is_none() {
+        return Err(ContractError::AddressUnsupported(address.to_string()));
+    }
+
+    Ok(parsed)
+}
+
+/// Parses a Bitcoin address and returns the script_pubkey it pays to.
+pub fn address_to_script(address: &str, network: Network) -> ContractResult<ScriptBuf> {
+    Ok(parse_address(address, network)?.script_pubkey())
+}
+
+/// Returns the address of a script_pubkey, if it has a standard address
+/// representation.
+pub fn script_to_address(script: &Script, network: Network) -> ContractResult<Address> {
+    let address = Address::from_script(script, network)
+        .map_err(|err| ContractError::Address(err.to_string()))?;
+    if address.address_type().is_none() {
+        return Err(ContractError::AddressUnsupported(address.to_string()));
+    }
+
+    Ok(address)
+}
+
+/// The minimum value, in satoshis, of a standard output paying to the given
+/// script.
+pub fn dust_threshold(script: &Script) -> u64 {
+    script.dust_value().to_sat()
+}
+
+/// Whether an output of `value` satoshis paying to the given script would be
+/// rejected as dust by the Bitcoin network's relay policy.
+pub fn is_dust(script: &Script, value: u64) -> bool {
+    value < dust_threshold(script)
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::{hashes::Hash, Network, ScriptBuf, WPubkeyHash};
+
+    use super::*;
+
+    #[test]
+    fn parse_address_network() {
+        let mainnet = "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4";
+        let testnet = "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx";
+
+        let script = address_to_script(mainnet, Network::Bitcoin).unwrap();
+        assert_eq!(
+            script_to_address(&script, Network::Bitcoin)
+                .unwrap()
+                .to_string(),
+            mainnet
+        );
+        assert!(matches!(
+            address_to_script(testnet, Network::Bitcoin),
+            Err(ContractError::AddressNetwork { .. })
+        ));
+        assert!(matches!(
+            address_to_script(mainnet, Network::Testnet),
+            Err(ContractError::AddressNetwork { .. })
+        ));
+        // witness v1 programs other than taproot have no standard meaning yet
+        assert!(matches!(
+            address_to_script(
+                "bc1pw508d6qejxtdg4y5r3zarvary0c5xw7kw508d6qejxtdg4y5r3zarvary0c5xw7kt5nd6y",
+                Network::Bitcoin
+            ),
+            Err(ContractError::AddressUnsupported(_))
+        ));
+        assert!(address_to_script("not an address", Network::Bitcoin).is_err());
+    }
+
+    #[test]
+    fn dust() {
+        let script = ScriptBuf::new_p2wpkh(&WPubkeyHash::from_slice(&[1; 20]).unwrap());
+        let threshold = dust_threshold(&script);
+        assert_eq!(threshold, 294);
+        assert!(is_dust(&script, threshold - 1));
+        assert!(!is_dust(&script, threshold));
+    }
+}
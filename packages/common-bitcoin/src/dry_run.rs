@@ -0,0 +1,122 @@
+use cosmwasm_std::{Order, Record, Storage};
+use std::{cmp::Ordering, collections::BTreeMap, iter::Peekable, ops::Bound};
+
+/// A storage which buffers all writes in memory on top of a read-only store,
+/// so state transitions can be computed and inspected from a query without
+/// being persisted, or applied only once they succeeded.
+pub struct DryRunStorage<'a> {
+    base: &'a dyn Storage,
+    writes: Writes,
+}
+
+impl<'a> DryRunStorage<'a> {
+    pub fn new(base: &'a dyn Storage) -> Self {
+        Self {
+            base,
+            writes: BTreeMap::new(),
+        }
+    }
+
+    /// Takes the buffered writes, to apply them to the base store with
+    /// [`apply_writes`] once the dry run succeeded.
+    pub fn into_writes(self) -> Writes {
+        self.writes
+    }
+}
+
+/// Buffered writes by key, `None` marks a removed key
+pub type Writes = BTreeMap<Vec<u8>, Option<Vec<u8>>>;
+
+pub fn apply_writes(store: &mut dyn Storage, writes: Writes) {
+    for (key, value) in writes {
+        match value {
+            Some(value) => store.set(&key, &value),
+            None => store.remove(&key),
+        }
+    }
+}
+
+type WriteIter<'a> = Box<dyn Iterator<Item = (&'a Vec<u8>, &'a Option<Vec<u8>>)> + 'a>;
+
+/// Merges a range of the base store with the buffered writes in the same
+/// range, both iterated in `order`. A write shadows the base record with the
+/// same key, and a removal hides it.
+struct MergeIter<'a> {
+    base: Peekable<Box<dyn Iterator<Item = Record> + 'a>>,
+    writes: Peekable<WriteIter<'a>>,
+    order: Order,
+}
+
+impl Iterator for MergeIter<'_> {
+    type Item = Record;
+
+    fn next(&mut self) -> Option<Record> {
+        loop {
+            let write_key = match self.writes.peek() {
+                Some((key, _)) => *key,
+                None => return self.base.next(),
+            };
+            let ordering = match self.base.peek() {
+                Some((base_key, _)) => match self.order {
+                    Order::Ascending => base_key.cmp(write_key),
+                    Order::Descending => write_key.cmp(base_key),
+                },
+                None => Ordering::Greater,
+            };
+            match ordering {
+                Ordering::Less => return self.base.next(),
+                Ordering::Equal => {
+                    self.base.next();
+                }
+                Ordering::Greater => {}
+            }
+            let (key, value) = self.writes.next()?;
+            if let Some(value) = value {
+                return Some((key.clone(), value.clone()));
+            }
+        }
+    }
+}
+
+impl Storage for DryRunStorage<'_> {
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        match self.writes.get(key) {
+            Some(value) => value.clone(),
+            None => self.base.get(key),
+        }
+    }
+
+    fn range<'b>(
+        &'b self,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = Record> + 'b> {
+        let bounds = (
+            start.map_or(Bound::Unbounded, Bound::Included),
+            end.map_or(Bound::Unbounded, Bound::Excluded),
+        );
+        // an empty range, which `BTreeMap::range` would panic on
+        if matches!((start, end), (Some(start), Some(end)) if start > end) {
+            return Box::new(std::iter::empty());
+        }
+        let writes = self.writes.range::<[u8], _>(bounds);
+        let writes: WriteIter = match order {
+            Order::Ascending => Box::new(writes),
+            Order::Descending => Box::new(writes.rev()),
+        };
+        Box::new(MergeIter {
+            base: self.base.range(start, end, order).peekable(),
+            writes: writes.peekable(),
+            order,
+        })
+    }
+
+    fn set(&mut self, key: &[u8], value: &[u8]) {
+        self.writes.insert(key.to_vec(), Some(value.to_vec()));
+    }
+
+    fn remove(&mut self, key: &[u8]) {
+        self.writes.insert(key.to_vec(), None);
+    }
+}
@@ -12,12 +12,18 @@ pub enum ContractError {
     Coins(String),
     #[error("Address Error: {0}")]
     Address(String),
+    #[error("Address {address} is not valid on {network}")]
+    AddressNetwork { address: String, network: String },
+    #[error("Unsupported address type: {0}")]
+    AddressUnsupported(String),
     #[error(transparent)]
-    Bitcoin(#[from] bitcoin::Error),
+    Bitcoin(#[from] bitcoin::address::Error),
     #[error(transparent)]
-    ParseOutPoint(#[from] bitcoin::blockdata::transaction::ParseOutPointError),
+    BlockValidation(#[from] bitcoin::block::ValidationError),
     #[error(transparent)]
-    BitcoinHash(#[from] bitcoin::hashes::Error),
+    ParseOutPoint(#[from] bitcoin::transaction::ParseOutPointError),
+    #[error(transparent)]
+    BitcoinHash(#[from] bitcoin::hashes::FromSliceError),
     #[error(transparent)]
     BitcoinEncode(#[from] bitcoin::consensus::encode::Error),
     #[error("Unable to deduct fee: {0}")]
@@ -25,11 +31,11 @@ pub enum ContractError {
     #[error("{0}")]
     BitcoinRecoveryScript(String),
     #[error(transparent)]
-    Bip32(#[from] bitcoin::util::bip32::Error),
+    Bip32(#[from] bitcoin::bip32::Error),
     #[error("{0}")]
     Checkpoint(String),
     #[error(transparent)]
-    Sighash(#[from] bitcoin::util::sighash::Error),
+    Sighash(#[from] bitcoin::sighash::Error),
     #[error(transparent)]
     TryFrom(#[from] std::num::TryFromIntError),
     #[error("App Error: {0}")]
@@ -38,6 +44,8 @@ pub enum ContractError {
     Secp(#[from] bitcoin::secp256k1::Error),
     #[error("Could not verify merkle proof")]
     BitcoinMerkleBlockError,
+    #[error("Invalid relayed transaction: {0}")]
+    InvalidTx(#[from] TxError),
     #[error("{0}")]
     Header(String),
     #[error("{0}")]
@@ -56,11 +64,147 @@ pub enum ContractError {
     ValidatorNoConsensusPubKey {},
     #[error("Validator is not in bonded status")]
     ValidatorNotBonded {},
+    #[error("Block is not sufficiently confirmed: {have} of {need} confirmations")]
+    InsufficientConfirmations { have: u32, need: u32 },
+    #[error("Bridge is paused: {reason}")]
+    BridgePaused { reason: String },
+    #[error("Output {txid}:{vout} has already been relayed")]
+    OutputAlreadyRelayed { txid: String, vout: u32 },
+    #[error("Block height {height} is ahead of the light client tip {tip}")]
+    BlockInFuture { height: u32, tip: u32 },
+    #[error("Deposit of {value} sats is below the minimum relayable value of {min} sats")]
+    DepositTooSmall { value: u64, min: u64 },
+    #[error("No completed checkpoints yet")]
+    NoCompletedCheckpoints {},
+    #[error("Not enough signatures supplied")]
+    NotEnoughSignatures {},
+    #[error("Excess signatures supplied")]
+    ExcessSignatures {},
+    #[error("Pubkey already signed")]
+    AlreadySigned {},
+    #[error("Headers do not connect to the chain at height {height}")]
+    HeadersNotConnected { height: u32 },
+    #[error("Provided redundant header at height {height}")]
+    RedundantHeader { height: u32 },
+    #[error("New best chain must include more work than old best chain")]
+    InsufficientWork {},
+    /// An error tagged with its stable code, as returned by the entrypoints.
+    #[error("[E{code}] {msg}")]
+    Coded { code: u32, msg: String },
+}
+
+/// Why a relayed Bitcoin transaction was rejected by strict validation.
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+pub enum TxError {
+    #[error("version {0} is not standard")]
+    Version(i32),
+    #[error("no inputs")]
+    NoInputs,
+    #[error("no outputs")]
+    NoOutputs,
+    #[error("{count} inputs, more than {max}")]
+    TooManyInputs { count: usize, max: u32 },
+    #[error("{count} outputs, more than {max}")]
+    TooManyOutputs { count: usize, max: u32 },
+    #[error("weight {weight} is more than {max}")]
+    TooHeavy { weight: usize, max: u64 },
+    #[error("script signature of input {0} is not standard")]
+    NonStandardScriptSig(usize),
+    #[error("witness item {item} of input {input} is {size} bytes, more than {max}")]
+    WitnessItemTooLarge {
+        input: usize,
+        item: usize,
+        size: usize,
+        max: u32,
+    },
+    #[error("script of output {0} is not standard")]
+    NonStandardOutput(usize),
+}
+
+/// Lists the stable code of every `ContractError` variant, generating both
+/// `ContractError::code` and `ERROR_CODES` so the two can't drift apart. The
+/// match is exhaustive, so a new variant doesn't compile until it's given a
+/// code. Codes are never reused or renumbered.
+macro_rules! error_codes {
+    ($($code:literal => $variant:ident,)*) => {
+        /// The stable code and name of every `ContractError` variant.
+        pub const ERROR_CODES: &[(u32, &str)] = &[$(($code, stringify!($variant)),)*];
+
+        impl ContractError {
+            /// The stable numeric code of the error, see `ERROR_CODES`.
+            pub fn code(&self) -> u32 {
+                match self {
+                    $(Self::$variant { .. } => $code,)*
+                    Self::Coded { code, .. } => *code,
+                }
+            }
+        }
+    };
+}
+
+error_codes! {
+    1 => Std,
+    2 => Verify,
+    3 => TryFrom,
+    4 => Unauthorized,
+    10 => Account,
+    11 => Coins,
+    12 => Address,
+    13 => AddressNetwork,
+    14 => AddressUnsupported,
+    20 => Bitcoin,
+    21 => ParseOutPoint,
+    22 => BitcoinHash,
+    23 => BitcoinEncode,
+    24 => BitcoinFee,
+    25 => BitcoinRecoveryScript,
+    26 => Bip32,
+    27 => Secp,
+    28 => BitcoinMerkleBlockError,
+    29 => InvalidTx,
+    30 => Checkpoint,
+    31 => Sighash,
+    32 => InputIndexOutOfBounds,
+    33 => InsufficientConfirmations,
+    34 => OutputAlreadyRelayed,
+    35 => BlockInFuture,
+    36 => DepositTooSmall,
+    37 => NoCompletedCheckpoints,
+    38 => BlockValidation,
+    40 => App,
+    41 => BridgePaused,
+    50 => Header,
+    51 => HeadersNotConnected,
+    52 => RedundantHeader,
+    53 => InsufficientWork,
+    60 => Ibc,
+    70 => Signer,
+    71 => NotEnoughSignatures,
+    72 => ExcessSignatures,
+    73 => AlreadySigned,
+    80 => ValidatorUnwhitelisted,
+    81 => ValidatorJailed,
+    82 => ValidatorNoConsensusPubKey,
+    83 => ValidatorNotBonded,
+}
+
+impl ContractError {
+    /// Tags the error message with its code, so callers can branch on the
+    /// error type without parsing the message.
+    pub fn coded(self) -> Self {
+        match self {
+            Self::Coded { .. } => self,
+            err => Self::Coded {
+                code: err.code(),
+                msg: err.to_string(),
+            },
+        }
+    }
 }
 
 impl From<ContractError> for StdError {
     fn from(source: ContractError) -> Self {
-        Self::generic_err(source.to_string())
+        Self::generic_err(source.coded().to_string())
     }
 }
 
@@ -1,5 +1,7 @@
 pub mod adapter;
+pub mod address;
 pub mod deque;
+pub mod dry_run;
 pub mod error;
 pub mod msg;
 pub mod xpub;
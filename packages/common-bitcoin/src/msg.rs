@@ -21,3 +21,20 @@ pub struct ValidatorInfo {
     pub status: i32,
     pub tokens: Uint128,
 }
+
+/// A `ContractError` code, as listed by the `ErrorCodes` queries.
+#[cw_serde]
+pub struct ErrorCode {
+    pub code: u32,
+    pub name: String,
+}
+
+pub fn error_codes() -> Vec<ErrorCode> {
+    crate::error::ERROR_CODES
+        .iter()
+        .map(|(code, name)| ErrorCode {
+            code: *code,
+            name: name.to_string(),
+        })
+        .collect()
+}
@@ -1,10 +1,11 @@
+use bitcoin::bip32;
 use bitcoin::secp256k1;
-use bitcoin::util::bip32::ExtendedPubKey;
 use cosmwasm_schema::serde::{de, ser, Deserialize, Serialize};
 use cosmwasm_std::{Binary, StdError};
 use derive_more::Deref;
 use libsecp256k1_core::curve::{Affine, ECMultContext, Field, Scalar};
 use libsecp256k1_core::util::{TAG_PUBKEY_EVEN, TAG_PUBKEY_ODD};
+use std::collections::BTreeMap;
 
 use crate::error::ContractResult;
 
@@ -12,12 +13,12 @@ use crate::error::ContractResult;
 /// signatories sign transactions with.
 #[derive(Copy, Clone, PartialEq, Deref, Eq, Debug, PartialOrd, Ord, Hash)]
 pub struct Xpub {
-    pub key: ExtendedPubKey,
+    pub key: bip32::Xpub,
 }
 
 impl Xpub {
-    /// Creates a new `Xpub` from an `ExtendedPubKey`.
-    pub fn new(key: ExtendedPubKey) -> Self {
+    /// Creates a new `Xpub` from a `bip32::Xpub`.
+    pub fn new(key: bip32::Xpub) -> Self {
         Xpub { key }
     }
 
@@ -65,20 +66,50 @@ impl Xpub {
     /// Deterministically derive the public key for a signatory in a signatory set,
     /// based on the current signatory set index.
     pub fn derive_pubkey(&self, sigset_index: u32) -> ContractResult<secp256k1::PublicKey> {
-        let child_number = bitcoin::util::bip32::ChildNumber::from_normal_idx(sigset_index)?;
+        let child_number = bip32::ChildNumber::from_normal_idx(sigset_index)?;
         let (sk, _) = self.ckd_pub_tweak(child_number)?;
         self.add_exp_tweak(&sk)
     }
+
+    /// A cache of the public keys derived from this key, for deriving the
+    /// keys of many inputs which mostly share a few signatory sets.
+    pub fn derived_pubkeys(&self) -> DerivedPubkeys<'_> {
+        DerivedPubkeys {
+            xpub: self,
+            pubkeys: BTreeMap::new(),
+        }
+    }
+}
+
+/// Public keys derived from an `Xpub`, by signatory set index. Each
+/// derivation multiplies a curve point, so it is worth caching when signing
+/// or inspecting every input of a transaction.
+pub struct DerivedPubkeys<'a> {
+    xpub: &'a Xpub,
+    pubkeys: BTreeMap<u32, secp256k1::PublicKey>,
 }
 
-impl From<ExtendedPubKey> for Xpub {
-    fn from(key: ExtendedPubKey) -> Self {
+impl DerivedPubkeys<'_> {
+    /// The public key for the given signatory set index, derived on first
+    /// use.
+    pub fn get(&mut self, sigset_index: u32) -> ContractResult<secp256k1::PublicKey> {
+        if let Some(pubkey) = self.pubkeys.get(&sigset_index) {
+            return Ok(*pubkey);
+        }
+        let pubkey = self.xpub.derive_pubkey(sigset_index)?;
+        self.pubkeys.insert(sigset_index, pubkey);
+        Ok(pubkey)
+    }
+}
+
+impl From<bip32::Xpub> for Xpub {
+    fn from(key: bip32::Xpub) -> Self {
         Xpub { key }
     }
 }
 
-impl From<&ExtendedPubKey> for Xpub {
-    fn from(key: &ExtendedPubKey) -> Self {
+impl From<&bip32::Xpub> for Xpub {
+    fn from(key: &bip32::Xpub) -> Self {
         Xpub { key: *key }
     }
 }
@@ -101,7 +132,35 @@ impl<'de> Deserialize<'de> for Xpub {
         D: de::Deserializer<'de>,
     {
         let v = Binary::deserialize(deserializer)?;
-        let inner = ExtendedPubKey::decode(v.as_slice()).map_err(de::Error::custom)?;
+        let inner = bip32::Xpub::decode(v.as_slice()).map_err(de::Error::custom)?;
         Ok(inner.into())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::bip32::{ChildNumber, Xpriv};
+    use bitcoin::secp256k1::Secp256k1;
+    use bitcoin::Network;
+    use cosmwasm_std::{from_json, to_json_vec};
+
+    use super::*;
+
+    #[test]
+    fn stored_before_bitcoin_upgrade() {
+        // the xpub of the master key of seed [7; 32], as stored with bitcoin 0.29
+        let stored = r#""BIiyHgAAAAAAAAAAAK3d1PM2JhXt8O31xUp93M+t6KAI/XfeGvISksWySeCdA9YhllwzNRb8rGrg8JxZJwgw41SNGbM1yYEYX9vaEUIa""#;
+        let xpub: Xpub = from_json(stored.as_bytes()).unwrap();
+
+        let secp = Secp256k1::new();
+        let xpriv = Xpriv::new_master(Network::Bitcoin, &[7; 32]).unwrap();
+        assert_eq!(xpub.key, bip32::Xpub::from_priv(&secp, &xpriv));
+        assert_eq!(to_json_vec(&xpub).unwrap(), stored.as_bytes());
+
+        let child = xpub
+            .key
+            .derive_pub(&secp, &[ChildNumber::from_normal_idx(3).unwrap()])
+            .unwrap();
+        assert_eq!(xpub.derive_pubkey(3).unwrap(), child.public_key);
+    }
+}
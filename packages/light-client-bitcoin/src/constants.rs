@@ -5,3 +5,5 @@ pub const RETARGET_INTERVAL: u32 = 2016;
 pub const TARGET_SPACING: u32 = 10 * 60;
 pub const TARGET_TIMESPAN: u32 = RETARGET_INTERVAL * TARGET_SPACING;
 pub const MAX_TARGET: u32 = 0x1d00ffff;
+/// Version of the IBC header query protocol, negotiated on channel opening
+pub const IBC_APP_VERSION: &str = "btc-light-client-1";
@@ -1,11 +1,12 @@
-use bitcoin::blockdata::block::BlockHeader;
-use bitcoin::util::uint::Uint256;
+use bitcoin::block::Header as BlockHeader;
 use bitcoin::{BlockHash, TxMerkleNode};
 use common_bitcoin::adapter::Adapter;
 use common_bitcoin::error::ContractResult;
 use cosmwasm_schema::schemars::JsonSchema;
 use cosmwasm_schema::serde::{Deserialize, Serialize};
 
+use crate::uint::Uint256;
+
 /// A wrapper around a bitcoin::BlockHeader that implements the core orga
 /// traits, and includes the block's height.
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
@@ -39,7 +40,7 @@ impl WrappedHeader {
     /// The target - the value the hash must be less than to be valid
     /// proof-of-work.
     pub fn target(&self) -> Uint256 {
-        self.header.target()
+        self.header.target().into()
     }
 
     /// The block hash.
@@ -55,7 +56,7 @@ impl WrappedHeader {
     /// The total estimated number of work (measured in hashes) represented by
     /// the block header's proof-of-work.
     pub fn work(&self) -> Uint256 {
-        self.header.work()
+        self.header.work().into()
     }
 
     /// The height of the block header.
@@ -65,32 +66,23 @@ impl WrappedHeader {
 
     /// The target, in compact form.
     pub fn bits(&self) -> u32 {
-        self.header.bits
+        self.header.bits.to_consensus()
     }
 
     /// Converts a compact target to a Uint256.
     pub fn u256_from_compact(compact: u32) -> Uint256 {
-        BlockHeader::u256_from_compact_target(compact)
+        Uint256::from_compact(compact)
     }
 
     /// Converts a Uint256 to a compact target.
     pub fn compact_target_from_u256(target: &Uint256) -> u32 {
-        BlockHeader::compact_target_from_u256(target)
-    }
-
-    /// Converts a u32 to a Uint256.
-    pub fn u32_to_u256(value: u32) -> Uint256 {
-        let bytes = value.to_be_bytes();
-        let mut buffer = [0u8; 32];
-        buffer[32 - bytes.len()..].copy_from_slice(&bytes);
-
-        Uint256::from_be_bytes(buffer)
+        target.to_compact()
     }
 
     /// Validates the proof-of-work of the block header, returning an error if
     /// the proof-of-work is invalid.
     pub fn validate_pow(&self, required_target: &Uint256) -> ContractResult<BlockHash> {
-        Ok(self.header.validate_pow(required_target)?)
+        Ok(self.header.validate_pow(required_target.to_target())?)
     }
 }
 
@@ -127,7 +119,7 @@ impl WorkHeader {
     /// The estimated amount of work (measured in hashes) done in the header,
     /// not including work done in any previous headers.
     pub fn work(&self) -> Uint256 {
-        self.header.work()
+        self.header.work().into()
     }
 
     /// The height of the block header.
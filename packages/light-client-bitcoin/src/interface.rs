@@ -1,9 +1,11 @@
-use bitcoin::BlockHeader;
-use common_bitcoin::adapter::Adapter;
+use bitcoin::block::Header as BlockHeader;
+use bitcoin::hashes::Hash;
+use bitcoin::{BlockHash, FilterHash, FilterHeader};
+use common_bitcoin::adapter::{Adapter, WrappedBinary};
 use common_bitcoin::error::ContractResult;
 use cosmwasm_schema::schemars::JsonSchema;
 use cosmwasm_schema::serde::{Deserialize, Serialize};
-use cosmwasm_std::from_json;
+use cosmwasm_std::{from_json, Addr, Uint128};
 
 use crate::constants::{
     MAX_LENGTH, MAX_TARGET, MAX_TIME_INCREASE, RETARGET_INTERVAL, TARGET_SPACING, TARGET_TIMESPAN,
@@ -19,9 +21,8 @@ pub struct HeaderConfig {
     /// The maximum number of headers that can be stored in the header queue
     /// before pruning.
     pub max_length: u64,
-    /// The maximum amount of time (in seconds) that can pass between the
-    /// timestamp of the last header in the header queue and the timestamp of
-    /// the next header to be added.
+    /// The maximum amount of time (in seconds) a header's timestamp may be
+    /// ahead of the configured clock source.
     pub max_time_increase: u32,
     /// The height of the trusted header.
     pub trusted_height: u32,
@@ -81,3 +82,69 @@ impl HeaderConfig {
         work_header
     }
 }
+
+/// The clock relayed headers are checked against to reject timestamps too
+/// far in the future.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(crate = "cosmwasm_schema::serde", rename_all = "snake_case")]
+#[schemars(crate = "cosmwasm_schema::schemars")]
+pub enum ClockSource {
+    /// Header timestamps are only checked against the median time past.
+    #[default]
+    Disabled,
+    /// The time of the block relaying the headers.
+    BlockTime,
+}
+
+/// A `RelayHeaders` call of a bonded relayer which failed validation, kept as
+/// evidence against the relayer.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(crate = "cosmwasm_schema::serde")]
+#[schemars(crate = "cosmwasm_schema::schemars")]
+pub struct HeaderRejection {
+    pub sender: Addr,
+    /// The height of the first relayed header
+    pub height: u32,
+    /// The hash of the first relayed header
+    pub header_hash: WrappedBinary<BlockHash>,
+    pub reason: String,
+    /// The block time of the rejected relay, in seconds
+    pub rejected_at: u64,
+}
+
+/// The stake bonded by a header relayer.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(crate = "cosmwasm_schema::serde")]
+#[schemars(crate = "cosmwasm_schema::schemars")]
+pub struct RelayerStake {
+    pub amount: Uint128,
+    /// When unbonding started, the relayer can't relay headers while
+    /// unbonding
+    pub unbonding_at: Option<u64>,
+}
+
+/// A BIP-157 compact block filter header for the block at `height`, as
+/// relayed alongside the block headers.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(crate = "cosmwasm_schema::serde")]
+#[schemars(crate = "cosmwasm_schema::schemars")]
+pub struct FilterHeaderEntry {
+    pub height: u32,
+    /// The hash of the block the filter was built from.
+    pub block_hash: WrappedBinary<BlockHash>,
+    /// The double-SHA256 hash of the serialized BIP-158 basic filter.
+    pub filter_hash: WrappedBinary<FilterHash>,
+    /// The filter header, committing to `filter_hash` and the filter header
+    /// of the previous block.
+    pub filter_header: WrappedBinary<FilterHeader>,
+}
+
+impl FilterHeaderEntry {
+    /// Whether this entry's filter header correctly commits to its filter
+    /// hash and the given previous filter header.
+    pub fn extends(&self, prev_filter_header: &FilterHeader) -> bool {
+        let mut preimage = self.filter_hash.0.to_byte_array().to_vec();
+        preimage.extend_from_slice(&prev_filter_header.to_byte_array());
+        FilterHeader::hash(&preimage) == self.filter_header.0
+    }
+}
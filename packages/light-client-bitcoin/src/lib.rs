@@ -2,3 +2,4 @@ pub mod constants;
 pub mod header;
 pub mod interface;
 pub mod msg;
+pub mod uint;
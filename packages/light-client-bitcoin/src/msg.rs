@@ -1,12 +1,26 @@
-use crate::{header::WrappedHeader, interface::HeaderConfig};
-use bitcoin::{util::merkleblock::PartialMerkleTree, Transaction};
+use crate::{
+    header::WrappedHeader,
+    interface::{ClockSource, FilterHeaderEntry, HeaderConfig, HeaderRejection, RelayerStake},
+    uint::Uint256,
+};
+use bitcoin::{merkle_tree::PartialMerkleTree, Transaction};
 use common_bitcoin::adapter::{Adapter, WrappedBinary};
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::Addr;
+use cosmwasm_std::{Addr, Binary, Coin, Uint128};
 
 #[cw_serde]
 pub struct Config {
     pub owner: Addr,
+    #[serde(default)]
+    pub clock_source: ClockSource,
+    /// The stake a sender must have bonded to relay headers, unset while no
+    /// stake is required
+    #[serde(default)]
+    pub relayer_stake: Option<Coin>,
+    /// How long unbonded relayer stake stays slashable before it can be
+    /// withdrawn, in seconds
+    #[serde(default)]
+    pub relayer_unbonding_period: u64,
 }
 
 #[cw_serde]
@@ -14,9 +28,50 @@ pub struct InstantiateMsg {}
 
 #[cw_serde]
 pub enum ExecuteMsg {
-    RelayHeaders { headers: Vec<WrappedHeader> },
-    UpdateHeaderConfig { config: HeaderConfig },
-    UpdateConfig { owner: Option<Addr> },
+    RelayHeaders {
+        headers: Vec<WrappedHeader>,
+    },
+    RelayFilterHeaders {
+        headers: Vec<FilterHeaderEntry>,
+    },
+    UpdateHeaderConfig {
+        config: HeaderConfig,
+    },
+    UpdateConfig {
+        owner: Option<Addr>,
+        clock_source: Option<ClockSource>,
+    },
+    /// Sets the reward paid to the sender of each `RelayHeaders` call which
+    /// extends the chain to a height no reward was paid for yet. `None`
+    /// disables rewards.
+    SetRelayReward {
+        reward: Option<Coin>,
+    },
+    /// Adds the attached funds of the reward denom to the relay reward pool
+    FundRelayRewards {},
+    /// Sends up to `amount` of the relay reward pool, all of it by default,
+    /// to the owner
+    WithdrawRelayRewards {
+        amount: Option<Uint128>,
+    },
+    /// Sets the stake required to relay headers, `None` lets anyone relay
+    SetRelayerStake {
+        stake: Option<Coin>,
+        unbonding_period: u64,
+    },
+    /// Adds the attached funds of the stake denom to the sender's relayer
+    /// stake, cancelling any unbonding
+    BondRelayerStake {},
+    /// Starts unbonding the sender's relayer stake
+    UnbondRelayerStake {},
+    /// Sends the sender its relayer stake once the unbonding period passed
+    WithdrawRelayerStake {},
+    /// Takes up to `amount` of a relayer's stake, e.g. for repeated invalid
+    /// relays recorded in `HeaderRejections`, and sends it to the owner
+    SlashRelayer {
+        relayer: Addr,
+        amount: Uint128,
+    },
 }
 
 #[cw_serde]
@@ -26,17 +81,112 @@ pub enum QueryMsg {
     HeaderConfig {},
     #[returns(u32)]
     HeaderHeight {},
+    #[returns(u32)]
+    HeaderTime {},
+    /// The median timestamp of the last 11 headers, as used for lock-time
+    /// decisions
+    #[returns(u32)]
+    MedianTimePast {},
     #[returns(String)]
     Network {},
+    /// The stable code of every error the contract can return, errors are
+    /// prefixed with `[E<code>]`
+    #[returns(Vec<common_bitcoin::msg::ErrorCode>)]
+    ErrorCodes {},
     #[returns(WrappedBinary<bitcoin::BlockHash>)]
     SidechainBlockHash {},
+    /// The hash of the header at the given height, if it is in the header
+    /// queue
+    #[returns(Option<WrappedBinary<bitcoin::BlockHash>>)]
+    BlockHashAtHeight { height: u32 },
+    /// The header at the given height with its target and the cumulative
+    /// work of the chain up to it, if it is in the header queue
+    #[returns(Option<HeaderAtResponse>)]
+    HeaderAt { height: u32 },
+    /// The work done by the headers after `from_height` up to and including
+    /// `to_height`, both of which must be in the header queue
+    #[returns(ChainWorkResponse)]
+    ChainWork { from_height: u32, to_height: u32 },
+    #[returns(Option<FilterHeaderEntry>)]
+    FilterHeader { height: u32 },
+    #[returns(RelayRewardsResponse)]
+    RelayRewards {},
+    /// Rejected `RelayHeaders` calls of bonded relayers, newest first,
+    /// optionally only those of the given sender
+    #[returns(Vec<HeaderRejection>)]
+    HeaderRejections {
+        sender: Option<Addr>,
+        limit: Option<u32>,
+    },
+    #[returns(Option<RelayerStake>)]
+    RelayerStake { relayer: Addr },
+    /// Errors unless the stored filter headers from `start_height` to
+    /// `end_height` form an unbroken chain on the current header chain
+    #[returns(())]
+    VerifyFilterHeaderChain { start_height: u32, end_height: u32 },
     #[returns(())]
     VerifyTxWithProof {
         btc_tx: Adapter<Transaction>,
         btc_height: u32,
         btc_proof: Adapter<PartialMerkleTree>,
     },
+    /// Like `VerifyTxWithProof`, for a transaction known by its txid in a
+    /// block pruned from the header queue.
+    /// `headers` start at `btc_height` and run up to the first stored header,
+    /// which must commit to the hash of the last one
+    #[returns(())]
+    VerifyTxidWithAncestorProof {
+        txid: Adapter<bitcoin::Txid>,
+        btc_height: u32,
+        btc_proof: Adapter<PartialMerkleTree>,
+        headers: Vec<WrappedHeader>,
+    },
+}
+
+#[cw_serde]
+pub struct HeaderAtResponse {
+    pub header: WrappedHeader,
+    /// The cumulative work of the chain up to and including the header
+    pub chain_work: Adapter<Uint256>,
+    pub target: Adapter<Uint256>,
+}
+
+#[cw_serde]
+pub struct ChainWorkResponse {
+    pub work: Adapter<Uint256>,
+    /// The cumulative work of the chain up to and including `to_height`
+    pub chain_work: Adapter<Uint256>,
+}
+
+#[cw_serde]
+pub struct RelayRewardsResponse {
+    pub reward: Option<Coin>,
+    /// The funds left to pay rewards with
+    pub pool: Uint128,
+    /// The highest header height a reward was paid for
+    pub rewarded_height: u32,
 }
 
 #[cw_serde]
 pub enum MigrateMsg {}
+
+/// Packet data of a query sent to the light client over IBC. The result is
+/// returned in the packet acknowledgement as an [`IbcQueryAck`].
+#[cw_serde]
+pub enum IbcQueryMsg {
+    /// Answered with `Option<WrappedHeader>`
+    HeaderAtHeight { height: u32 },
+    /// Answered with `()` if the proof is valid against the header chain
+    VerifyTxWithProof {
+        btc_tx: Adapter<Transaction>,
+        btc_height: u32,
+        btc_proof: Adapter<PartialMerkleTree>,
+    },
+}
+
+#[cw_serde]
+pub enum IbcQueryAck {
+    /// JSON encoded result of the query
+    Result(Binary),
+    Error(String),
+}
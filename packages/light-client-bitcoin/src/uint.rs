@@ -0,0 +1,199 @@
+use std::cmp::Ordering;
+use std::io;
+use std::ops::{Add, Div, Mul, Sub};
+
+use bitcoin::consensus::encode::{self, Decodable, Encodable};
+use bitcoin::pow::{CompactTarget, Target, Work};
+
+/// A 256-bit unsigned integer for targets and chain work.
+///
+/// The `bitcoin` crate no longer exposes arithmetic on `Target`, which
+/// retargeting needs, nor a consensus encoding for `Work`. This type keeps
+/// both, and encodes as 32 little-endian bytes like the `Uint256` of
+/// `bitcoin` 0.29, so chain work stored before the upgrade still decodes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Uint256([u64; 4]);
+
+impl Uint256 {
+    /// Creates a value from little-endian limbs.
+    pub const fn from_limbs(limbs: [u64; 4]) -> Self {
+        Uint256(limbs)
+    }
+
+    pub fn zero() -> Self {
+        Uint256([0; 4])
+    }
+
+    pub fn from_u64(value: u64) -> Self {
+        Uint256([value, 0, 0, 0])
+    }
+
+    pub fn from_le_bytes(bytes: [u8; 32]) -> Self {
+        let mut limbs = [0u64; 4];
+        for (limb, chunk) in limbs.iter_mut().zip(bytes.chunks_exact(8)) {
+            *limb = u64::from_le_bytes(chunk.try_into().unwrap());
+        }
+        Uint256(limbs)
+    }
+
+    pub fn to_le_bytes(self) -> [u8; 32] {
+        let mut bytes = [0u8; 32];
+        for (chunk, limb) in bytes.chunks_exact_mut(8).zip(self.0) {
+            chunk.copy_from_slice(&limb.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// Decodes a compact target.
+    pub fn from_compact(bits: u32) -> Self {
+        Target::from_compact(CompactTarget::from_consensus(bits)).into()
+    }
+
+    /// Encodes the value as a compact target, losing precision.
+    pub fn to_compact(self) -> u32 {
+        self.to_target().to_compact_lossy().to_consensus()
+    }
+
+    pub fn to_target(self) -> Target {
+        Target::from_le_bytes(self.to_le_bytes())
+    }
+
+    pub fn to_work(self) -> Work {
+        Work::from_le_bytes(self.to_le_bytes())
+    }
+}
+
+impl From<Target> for Uint256 {
+    fn from(target: Target) -> Self {
+        Uint256::from_le_bytes(target.to_le_bytes())
+    }
+}
+
+impl From<Work> for Uint256 {
+    fn from(work: Work) -> Self {
+        Uint256::from_le_bytes(work.to_le_bytes())
+    }
+}
+
+impl Ord for Uint256 {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.iter().rev().cmp(other.0.iter().rev())
+    }
+}
+
+impl PartialOrd for Uint256 {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Add for Uint256 {
+    type Output = Uint256;
+
+    fn add(self, rhs: Self) -> Self {
+        let mut limbs = [0u64; 4];
+        let mut carry = false;
+        for (i, limb) in limbs.iter_mut().enumerate() {
+            let (sum, c1) = self.0[i].overflowing_add(rhs.0[i]);
+            let (sum, c2) = sum.overflowing_add(carry as u64);
+            *limb = sum;
+            carry = c1 || c2;
+        }
+        Uint256(limbs)
+    }
+}
+
+impl Sub for Uint256 {
+    type Output = Uint256;
+
+    fn sub(self, rhs: Self) -> Self {
+        let mut limbs = [0u64; 4];
+        let mut borrow = false;
+        for (i, limb) in limbs.iter_mut().enumerate() {
+            let (diff, b1) = self.0[i].overflowing_sub(rhs.0[i]);
+            let (diff, b2) = diff.overflowing_sub(borrow as u64);
+            *limb = diff;
+            borrow = b1 || b2;
+        }
+        Uint256(limbs)
+    }
+}
+
+impl Mul<u32> for Uint256 {
+    type Output = Uint256;
+
+    fn mul(self, rhs: u32) -> Self {
+        let mut limbs = [0u64; 4];
+        let mut carry = 0u128;
+        for (i, limb) in limbs.iter_mut().enumerate() {
+            let product = self.0[i] as u128 * rhs as u128 + carry;
+            *limb = product as u64;
+            carry = product >> 64;
+        }
+        Uint256(limbs)
+    }
+}
+
+impl Div<u32> for Uint256 {
+    type Output = Uint256;
+
+    fn div(self, rhs: u32) -> Self {
+        let mut limbs = [0u64; 4];
+        let mut rem = 0u128;
+        for (limb, value) in limbs.iter_mut().zip(self.0).rev() {
+            let dividend = (rem << 64) | value as u128;
+            *limb = (dividend / rhs as u128) as u64;
+            rem = dividend % rhs as u128;
+        }
+        Uint256(limbs)
+    }
+}
+
+impl Encodable for Uint256 {
+    fn consensus_encode<W: io::Write + ?Sized>(&self, writer: &mut W) -> Result<usize, io::Error> {
+        self.to_le_bytes().consensus_encode(writer)
+    }
+}
+
+impl Decodable for Uint256 {
+    fn consensus_decode<R: io::Read + ?Sized>(reader: &mut R) -> Result<Self, encode::Error> {
+        Ok(Uint256::from_le_bytes(Decodable::consensus_decode(reader)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arithmetic() {
+        let max = Uint256::from_limbs([u64::MAX, 0, 0, 0]);
+        let one = Uint256::from_u64(1);
+        assert_eq!(max + one, Uint256::from_limbs([0, 1, 0, 0]));
+        assert_eq!(max + one - one, max);
+        assert_eq!(max * 4, Uint256::from_limbs([u64::MAX - 3, 3, 0, 0]));
+        assert_eq!(max * 4 / 4, max);
+        assert!(max + one > max);
+        assert!(
+            Uint256::from_limbs([0, 0, 0, 1]) > Uint256::from_limbs([u64::MAX, u64::MAX, 0, 0])
+        );
+    }
+
+    #[test]
+    fn compact_round_trip() {
+        let target = Uint256::from_compact(0x1d00ffff);
+        assert_eq!(target, Target::MAX_ATTAINABLE_MAINNET.into());
+        assert_eq!(target.to_compact(), 0x1d00ffff);
+    }
+
+    #[test]
+    fn encodes_like_legacy_uint256() {
+        // chain work stored by `bitcoin` 0.29's `Uint256`
+        let mut legacy = [0u8; 32];
+        legacy[0] = 0x01;
+        legacy[8] = 0x02;
+        let value: Uint256 = encode::deserialize(&legacy).unwrap();
+        assert_eq!(value, Uint256::from_limbs([1, 2, 0, 0]));
+        assert_eq!(encode::serialize(&value), legacy.to_vec());
+    }
+}
@@ -4,7 +4,8 @@ use sha2::{Digest, Sha256};
 use tsify::Tsify;
 use wasm_bindgen::prelude::*;
 
-use crate::error::ContractResult;
+use crate::error::{ContractError, ContractResult};
+use crate::{DEST_COMMITMENT_VERSION, LEGACY_DEST_COMMITMENT_VERSION};
 
 #[derive(Clone, Debug, PartialOrd, PartialEq, Eq, Ord, Deserialize, Serialize, Tsify)]
 #[tsify(into_wasm_abi, from_wasm_abi)]
@@ -17,11 +18,24 @@ pub struct IbcDest {
     pub memo: String,
 }
 
+#[derive(Clone, Debug, PartialOrd, PartialEq, Eq, Ord, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct IbcMemoDest {
+    pub channel: String,
+    pub receiver: String,
+    pub memo: String,
+    pub refund_address: String,
+    pub timeout_timestamp: u64,
+}
+
 #[derive(Clone, Debug, PartialOrd, PartialEq, Eq, Ord, Deserialize, Serialize, Tsify)]
 #[tsify(into_wasm_abi, from_wasm_abi)]
 pub enum Dest {
     Address(String),
     Ibc(IbcDest),
+    // matches the snake_case tag the contract hashes into the commitment
+    #[serde(rename = "ibc_memo")]
+    IbcMemo(IbcMemoDest),
 }
 
 impl Dest {
@@ -29,6 +43,7 @@ impl Dest {
         match self {
             Self::Address(addr) => addr.to_string(),
             Self::Ibc(dest) => dest.receiver.to_string(),
+            Self::IbcMemo(dest) => dest.receiver.to_string(),
         }
     }
 
@@ -36,6 +51,7 @@ impl Dest {
         match self {
             Self::Address(addr) => addr.to_string(),
             Self::Ibc(dest) => dest.sender.to_string(),
+            Self::IbcMemo(dest) => dest.refund_address.to_string(),
         }
     }
 
@@ -43,15 +59,41 @@ impl Dest {
         let bytes = match self {
             Self::Address(addr) => addr.as_bytes().into(),
             Self::Ibc(dest) => Sha256::digest(serde_json_wasm::to_vec(dest)?).to_vec(),
+            Self::IbcMemo(_) => Sha256::digest(serde_json_wasm::to_vec(self)?).to_vec(),
         };
 
         Ok(bytes)
     }
+
+    /// The commitment bytes of the given encoding version, as the contract
+    /// derives them.
+    pub fn versioned_commitment_bytes(&self, version: u8) -> ContractResult<Vec<u8>> {
+        match version {
+            LEGACY_DEST_COMMITMENT_VERSION => self.commitment_bytes(),
+            DEST_COMMITMENT_VERSION => {
+                let mut bytes = vec![version];
+                bytes.extend(self.commitment_bytes()?);
+                Ok(bytes)
+            }
+            _ => Err(ContractError::App(format!(
+                "Unknown destination commitment version: {}",
+                version
+            ))),
+        }
+    }
 }
 
+/// The commitment new deposit addresses are derived from.
 #[wasm_bindgen]
 pub fn commitmentBytes(dest: Dest) -> ContractResult<Vec<u8>> {
-    dest.commitment_bytes()
+    dest.versioned_commitment_bytes(DEST_COMMITMENT_VERSION)
+}
+
+/// The commitment of the given version, for addresses derived before the
+/// preferred version changed.
+#[wasm_bindgen]
+pub fn versionedCommitmentBytes(dest: Dest, version: u8) -> ContractResult<Vec<u8>> {
+    dest.versioned_commitment_bytes(version)
 }
 
 #[wasm_bindgen]
@@ -11,6 +11,7 @@ mod helpers;
 mod interface;
 mod relayer;
 mod signatory;
+mod signer;
 mod threshold_sig;
 mod utils;
 
@@ -22,6 +23,11 @@ pub const BRIDGE_FEE_RATE: f64 = 0.0;
 pub const SIGSET_THRESHOLD: (u64, u64) = (2, 3);
 pub const HEADER_BATCH_SIZE: usize = 250;
 pub const MAX_SIGNATORIES: u64 = 20;
+/// The version of the unprefixed destination commitments used by deposit
+/// addresses derived before commitments were versioned
+pub const LEGACY_DEST_COMMITMENT_VERSION: u8 = 0;
+/// The destination commitment version preferred for new deposit addresses
+pub const DEST_COMMITMENT_VERSION: u8 = 1;
 
 pub const MAX_LENGTH: u64 = 24_192; // ~6 months
 pub const MAX_RELAY: u64 = 1000;
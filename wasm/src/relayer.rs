@@ -1,6 +1,6 @@
 use super::signatory::Signatory;
 use super::signatory::SignatorySet;
-use super::SIGSET_THRESHOLD;
+use super::{DEST_COMMITMENT_VERSION, SIGSET_THRESHOLD};
 use crate::error::ContractResult;
 use crate::interface::Dest;
 use crate::utils::time_now;
@@ -171,7 +171,11 @@ impl WatchedScripts {
         sigset: &SignatorySet,
         threshold: (u64, u64),
     ) -> ContractResult<::bitcoin::Script> {
-        sigset.output_script(dest.commitment_bytes()?.as_slice(), threshold)
+        sigset.output_script(
+            dest.versioned_commitment_bytes(DEST_COMMITMENT_VERSION)?
+                .as_slice(),
+            threshold,
+        )
     }
 }
 
@@ -0,0 +1,179 @@
+use std::str::FromStr;
+
+use bitcoin::secp256k1::{self, ecdsa, Secp256k1};
+use bitcoin::util::bip32::{ChildNumber, DerivationPath, ExtendedPrivKey, ExtendedPubKey};
+use wasm_bindgen::prelude::*;
+
+use crate::error::{ContractError, ContractResult};
+use crate::interface::Xpub;
+use crate::threshold_sig::Signature;
+
+/// The messages returned by the `SigningTxsAtCheckpointIndex` and
+/// `SigningRecoveryTxs` queries: each sighash along with the index of the
+/// signatory set whose key signs it.
+type ToSign = Vec<([u8; 32], u32)>;
+
+/// The signatory key at `derivation_path` below `xpriv`, which is the key
+/// whose xpub is registered with `SetSignatoryKey`.
+fn signatory_xpriv(
+    xpriv: &str,
+    derivation_path: Option<String>,
+) -> ContractResult<ExtendedPrivKey> {
+    let xpriv = ExtendedPrivKey::from_str(xpriv)?;
+    match derivation_path {
+        Some(path) => Ok(xpriv.derive_priv(&Secp256k1::new(), &DerivationPath::from_str(&path)?)?),
+        None => Ok(xpriv),
+    }
+}
+
+/// Parses a signature in compact or DER encoding and re-encodes it as the
+/// compact, low-s signature `SubmitCheckpointSignature` expects.
+fn normalize_signature(bytes: &[u8]) -> ContractResult<Signature> {
+    let mut sig = if bytes.len() == 64 {
+        ecdsa::Signature::from_compact(bytes)?
+    } else {
+        ecdsa::Signature::from_der(bytes)?
+    };
+    sig.normalize_s();
+    Ok(Signature(sig.serialize_compact().to_vec()))
+}
+
+/// Signs each message with the child of `xpriv` for the message's signatory
+/// set index, keeping the order of the messages.
+fn sign_messages(to_sign: ToSign, xpriv: &ExtendedPrivKey) -> ContractResult<Vec<Signature>> {
+    let secp = Secp256k1::new();
+    let mut sigs = Vec::with_capacity(to_sign.len());
+    for (msg, sigset_index) in to_sign {
+        let child = xpriv.derive_priv(&secp, &[ChildNumber::from_normal_idx(sigset_index)?])?;
+        let msg = secp256k1::Message::from_slice(&msg)?;
+        let sig = secp.sign_ecdsa(&msg, &child.private_key);
+        sigs.push(normalize_signature(&sig.serialize_compact())?);
+    }
+    Ok(sigs)
+}
+
+/// The xpub to register for the signatory key at `derivation_path` below
+/// `xpriv`.
+#[wasm_bindgen]
+pub fn signatoryXpub(xpriv: String, derivation_path: Option<String>) -> ContractResult<Xpub> {
+    let xpriv = signatory_xpriv(&xpriv, derivation_path)?;
+    Ok(Xpub {
+        key: ExtendedPubKey::from_priv(&Secp256k1::new(), &xpriv),
+    })
+}
+
+/// Signs the messages of a `SigningTxsAtCheckpointIndex` or
+/// `SigningRecoveryTxs` query with the signatory key at `derivation_path`
+/// below `xpriv`. The signatures are in the order of the messages, ready to be
+/// submitted as `sigs`.
+#[wasm_bindgen]
+pub fn signToSign(
+    to_sign: JsValue,
+    xpriv: String,
+    derivation_path: Option<String>,
+) -> ContractResult<JsValue> {
+    let to_sign: ToSign = serde_wasm_bindgen::from_value(to_sign)?;
+    let xpriv = signatory_xpriv(&xpriv, derivation_path)?;
+    let sigs = sign_messages(to_sign, &xpriv)?;
+    Ok(serde_wasm_bindgen::to_value(&sigs)?)
+}
+
+/// Like `signToSign`, for keys held by an external signer. `sign` is called
+/// with each message and the index of the signatory set whose key signs it,
+/// and returns the signature in compact or DER encoding.
+#[wasm_bindgen]
+pub fn signToSignWith(to_sign: JsValue, sign: &js_sys::Function) -> ContractResult<JsValue> {
+    let to_sign: ToSign = serde_wasm_bindgen::from_value(to_sign)?;
+
+    let mut sigs = Vec::with_capacity(to_sign.len());
+    for (msg, sigset_index) in to_sign {
+        let msg = js_sys::Uint8Array::from(msg.as_slice());
+        let sig = sign
+            .call2(&JsValue::NULL, &msg, &JsValue::from(sigset_index))
+            .map_err(|err| ContractError::App(format!("External signer failed: {:?}", err)))?;
+        let sig = js_sys::Uint8Array::new(&sig).to_vec();
+        sigs.push(normalize_signature(&sig)?);
+    }
+
+    Ok(serde_wasm_bindgen::to_value(&sigs)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::Network;
+
+    use super::*;
+
+    fn xpriv() -> ExtendedPrivKey {
+        ExtendedPrivKey::new_master(Network::Bitcoin, &[7; 32]).unwrap()
+    }
+
+    #[test]
+    fn signs_in_order_with_sigset_keys() {
+        let secp = Secp256k1::new();
+        let xpub = ExtendedPubKey::from_priv(&secp, &xpriv());
+        let to_sign: ToSign = vec![([1; 32], 3), ([2; 32], 0), ([1; 32], 0)];
+
+        let sigs = sign_messages(to_sign.clone(), &xpriv()).unwrap();
+        assert_eq!(sigs.len(), to_sign.len());
+        for ((msg, sigset_index), sig) in to_sign.iter().zip(&sigs) {
+            // the key the contract derives for the signatory set
+            let pubkey = xpub
+                .derive_pub(
+                    &secp,
+                    &[ChildNumber::from_normal_idx(*sigset_index).unwrap()],
+                )
+                .unwrap()
+                .public_key;
+            let sig = ecdsa::Signature::from_compact(&sig.0).unwrap();
+            let msg = secp256k1::Message::from_slice(msg).unwrap();
+            secp.verify_ecdsa(&msg, &sig, &pubkey).unwrap();
+        }
+        assert_ne!(sigs[0], sigs[2]);
+    }
+
+    #[test]
+    fn derivation_path_selects_signatory_key() {
+        let secp = Secp256k1::new();
+        let encoded = xpriv().to_string();
+        let derived = signatory_xpriv(&encoded, Some("m/44'/0'/0'".to_string())).unwrap();
+        assert_eq!(
+            derived,
+            xpriv()
+                .derive_priv(&secp, &DerivationPath::from_str("m/44'/0'/0'").unwrap())
+                .unwrap()
+        );
+        assert_eq!(signatory_xpriv(&encoded, None).unwrap(), xpriv());
+        assert!(signatory_xpriv(&encoded, Some("not a path".to_string())).is_err());
+    }
+
+    #[test]
+    fn normalizes_external_signatures() {
+        let secp = Secp256k1::new();
+        let key = xpriv().private_key;
+        let msg = secp256k1::Message::from_slice(&[9; 32]).unwrap();
+        let sig = secp.sign_ecdsa(&msg, &key);
+        let expected = Signature(sig.serialize_compact().to_vec());
+
+        // DER and compact encodings give the same signature
+        assert_eq!(normalize_signature(&sig.serialize_der()).unwrap(), expected);
+        assert_eq!(
+            normalize_signature(&sig.serialize_compact()).unwrap(),
+            expected
+        );
+
+        // a high-s signature is brought back to low s
+        let mut compact = sig.serialize_compact();
+        let order = secp256k1::constants::CURVE_ORDER;
+        let mut borrow = 0u16;
+        for i in (32..64).rev() {
+            let diff = order[i - 32] as i16 - compact[i] as i16 - borrow as i16;
+            compact[i] = diff.rem_euclid(256) as u8;
+            borrow = (diff < 0) as u16;
+        }
+        assert_ne!(compact.to_vec(), expected.0);
+        assert_eq!(normalize_signature(&compact).unwrap(), expected);
+
+        assert!(normalize_signature(&[0; 10]).is_err());
+    }
+}